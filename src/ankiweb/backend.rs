@@ -0,0 +1,641 @@
+use crate::ankiweb::models::{
+    DeckConfig, Note, NoteInfo, NoteModel, NoteRejection, SchedulingPreset,
+};
+use crate::ankiweb::search::{any_query, deck_query, field_query};
+use crate::error::{AnkiDeckBuilderError, Result};
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::Path;
+use tracing::{debug, info};
+
+/// Anything that speaks the AnkiConnect protocol. `AnkiClient` talks to a
+/// running Anki over HTTP; `MockAnki` keeps a collection in memory for tests.
+/// Every operation is built on `invoke_raw`, so implementations only need to
+/// deliver actions
+#[async_trait]
+pub trait AnkiBackend: Send + Sync {
+    /// Send one AnkiConnect action and return its `result` payload, turning
+    /// an `error` response into `AnkiConnectError`
+    async fn invoke_raw(&self, action: &str, params: Value) -> Result<Option<Value>>;
+
+    /// Verify that AnkiConnect is running and accessible
+    async fn verify_connection(&self) -> Result<()> {
+        let version: Option<u32> = invoke(self, "version", json!({})).await?;
+        info!(
+            "Successfully connected to AnkiConnect (version: {:?})",
+            version
+        );
+        Ok(())
+    }
+
+    /// AnkiConnect API version
+    async fn version(&self) -> Result<u32> {
+        invoke(self, "version", json!({})).await?.ok_or_else(|| {
+            AnkiDeckBuilderError::AnkiConnectError("No version returned".to_string())
+        })
+    }
+
+    /// Create a new deck
+    async fn create_deck(&self, name: &str) -> Result<i64> {
+        debug!("Creating deck: {}", name);
+
+        let deck_id: i64 = invoke(self, "createDeck", json!({ "deck": name }))
+            .await?
+            .ok_or_else(|| {
+                AnkiDeckBuilderError::AnkiConnectError("No deck ID returned".to_string())
+            })?;
+
+        info!("Created deck '{}' with ID: {}", name, deck_id);
+        Ok(deck_id)
+    }
+
+    /// Add a note to a deck
+    async fn add_note(&self, note: &Note) -> Result<i64> {
+        debug!("Adding note to deck: {}", note.deck_name);
+
+        let params = json!({ "note": note_params(note) });
+
+        let note_id: i64 = invoke(self, "addNote", params).await?.ok_or_else(|| {
+            AnkiDeckBuilderError::AnkiConnectError("No note ID returned".to_string())
+        })?;
+
+        debug!("Added note with ID: {}", note_id);
+        Ok(note_id)
+    }
+
+    /// Get list of all deck names
+    async fn get_decks(&self) -> Result<Vec<String>> {
+        debug!("Fetching deck names");
+
+        invoke(self, "deckNames", json!({})).await?.ok_or_else(|| {
+            AnkiDeckBuilderError::AnkiConnectError("No deck names returned".to_string())
+        })
+    }
+
+    /// Options group a deck uses
+    async fn get_deck_config(&self, deck: &str) -> Result<DeckConfig> {
+        debug!("Fetching options of deck: {}", deck);
+
+        let config: Option<Value> = invoke(self, "getDeckConfig", json!({ "deck": deck })).await?;
+        match config {
+            // Unknown decks are answered with `false`
+            Some(config @ Value::Object(_)) => Ok(serde_json::from_value(config)?),
+            _ => Err(AnkiDeckBuilderError::AnkiConnectError(format!(
+                "No options found for deck '{}'",
+                deck
+            ))),
+        }
+    }
+
+    /// Save changes to an options group
+    async fn save_deck_config(&self, config: &DeckConfig) -> Result<()> {
+        debug!("Saving options group: {}", config.name);
+
+        let saved: Option<bool> =
+            invoke(self, "saveDeckConfig", json!({ "config": config })).await?;
+        if saved != Some(true) {
+            return Err(AnkiDeckBuilderError::AnkiConnectError(format!(
+                "Could not save options group '{}'",
+                config.name
+            )));
+        }
+        Ok(())
+    }
+
+    /// Make decks use an options group
+    async fn set_deck_config_id(&self, decks: &[String], config_id: i64) -> Result<()> {
+        debug!("Setting options group {} on {:?}", config_id, decks);
+
+        let params = json!({ "decks": decks, "configId": config_id });
+        let set: Option<bool> = invoke(self, "setDeckConfigId", params).await?;
+        if set != Some(true) {
+            return Err(AnkiDeckBuilderError::AnkiConnectError(format!(
+                "Could not set options group {} on {}",
+                config_id,
+                decks.join(", ")
+            )));
+        }
+        Ok(())
+    }
+
+    /// Create an options group as a copy of another, returning its ID
+    async fn clone_deck_config_id(&self, name: &str, clone_from: i64) -> Result<i64> {
+        debug!("Creating options group '{}' from {}", name, clone_from);
+
+        let params = json!({ "name": name, "cloneFrom": clone_from });
+        let id: Option<Value> = invoke(self, "cloneDeckConfigId", params).await?;
+        id.and_then(|id| id.as_i64()).ok_or_else(|| {
+            AnkiDeckBuilderError::AnkiConnectError(format!(
+                "Could not create options group '{}'",
+                name
+            ))
+        })
+    }
+
+    /// ID of the options group with this name, if any deck uses it.
+    /// AnkiConnect has no way to list options groups, so decks are asked
+    async fn find_deck_config(&self, name: &str) -> Result<Option<i64>> {
+        for deck in self.get_decks().await? {
+            if let Ok(config) = self.get_deck_config(&deck).await {
+                if config.name == name {
+                    return Ok(Some(config.id));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Put a deck on the preset's own options group, shared by every deck
+    /// with that preset and created from the deck's current options the
+    /// first time, and set the preset's options
+    async fn apply_preset(&self, deck: &str, preset: SchedulingPreset) -> Result<DeckConfig> {
+        let mut config = self.get_deck_config(deck).await?;
+
+        let group = preset.group_name();
+        if config.name != group {
+            let id = match self.find_deck_config(&group).await? {
+                Some(id) => id,
+                None => self.clone_deck_config_id(&group, config.id).await?,
+            };
+            self.set_deck_config_id(&[deck.to_string()], id).await?;
+            config = self.get_deck_config(deck).await?;
+        }
+
+        preset.apply(&mut config);
+        self.save_deck_config(&config).await?;
+
+        info!("Applied the {} preset to '{}'", preset.name(), deck);
+        Ok(config)
+    }
+
+    /// Names of all Anki profiles
+    async fn get_profiles(&self) -> Result<Vec<String>> {
+        debug!("Fetching profile names");
+
+        invoke(self, "getProfiles", json!({}))
+            .await?
+            .ok_or_else(|| {
+                AnkiDeckBuilderError::AnkiConnectError("No profiles returned".to_string())
+            })
+    }
+
+    /// Name of the profile currently open in Anki
+    async fn get_active_profile(&self) -> Result<String> {
+        invoke(self, "getActiveProfile", json!({}))
+            .await?
+            .ok_or_else(|| {
+                AnkiDeckBuilderError::AnkiConnectError("No active profile returned".to_string())
+            })
+    }
+
+    /// Open a profile, closing the one currently open
+    async fn load_profile(&self, name: &str) -> Result<()> {
+        debug!("Loading profile: {}", name);
+
+        let loaded: Option<bool> = invoke(self, "loadProfile", json!({ "name": name })).await?;
+        if loaded != Some(true) {
+            return Err(AnkiDeckBuilderError::AnkiConnectError(format!(
+                "Could not load profile '{}'",
+                name
+            )));
+        }
+
+        info!("Loaded profile '{}'", name);
+        Ok(())
+    }
+
+    /// Open a profile unless it is already open. Returns the profile that was
+    /// open before when it changed, so the caller can switch back afterwards
+    async fn switch_profile(&self, name: &str) -> Result<Option<String>> {
+        let profiles = self.get_profiles().await?;
+        if !profiles.iter().any(|profile| profile == name) {
+            return Err(AnkiDeckBuilderError::ConfigurationError(format!(
+                "Anki profile '{}' not found (profiles: {})",
+                name,
+                profiles.join(", ")
+            )));
+        }
+
+        let active = self.get_active_profile().await?;
+        if active == name {
+            return Ok(None);
+        }
+
+        self.load_profile(name).await?;
+        Ok(Some(active))
+    }
+
+    /// Get list of all note type (model) names
+    async fn get_model_names(&self) -> Result<Vec<String>> {
+        debug!("Fetching model names");
+
+        invoke(self, "modelNames", json!({})).await?.ok_or_else(|| {
+            AnkiDeckBuilderError::AnkiConnectError("No model names returned".to_string())
+        })
+    }
+
+    /// Field names of a note type, in order
+    async fn get_model_field_names(&self, model: &str) -> Result<Vec<String>> {
+        debug!("Fetching fields of model: {}", model);
+
+        invoke(self, "modelFieldNames", json!({ "modelName": model }))
+            .await?
+            .ok_or_else(|| {
+                AnkiDeckBuilderError::AnkiConnectError(format!("No fields returned for {}", model))
+            })
+    }
+
+    /// Create a note type
+    async fn create_model(&self, model: &NoteModel) -> Result<()> {
+        debug!("Creating model: {}", model.name);
+
+        let templates: Vec<_> = model
+            .templates
+            .iter()
+            .map(|t| json!({ "Name": t.name, "Front": t.front, "Back": t.back }))
+            .collect();
+
+        let params = json!({
+            "modelName": model.name,
+            "inOrderFields": model.fields,
+            "css": model.css,
+            "cardTemplates": templates,
+        });
+
+        let _: Option<serde_json::Value> = invoke(self, "createModel", params).await?;
+
+        info!("Created model '{}'", model.name);
+        Ok(())
+    }
+
+    /// Create a note type unless one with the same name already exists
+    async fn ensure_model(&self, model: &NoteModel) -> Result<()> {
+        if self.get_model_names().await?.contains(&model.name) {
+            debug!("Model '{}' already exists", model.name);
+            return Ok(());
+        }
+        self.create_model(model).await
+    }
+
+    /// Check which notes Anki would accept, using its first-field duplicate check
+    async fn can_add_notes(&self, notes: &[Note]) -> Result<Vec<bool>> {
+        debug!("Checking {} notes with canAddNotes", notes.len());
+
+        let params = json!({ "notes": notes.iter().map(note_params).collect::<Vec<_>>() });
+        let results: Option<Vec<bool>> = invoke(self, "canAddNotes", params).await?;
+        Ok(results.unwrap_or_default())
+    }
+
+    /// Check which notes Anki would accept, with the reason for each one it
+    /// would refuse. AnkiConnect versions without error details fall back
+    /// to `canAddNotes`, where every refusal counts as a duplicate
+    async fn check_notes(&self, notes: &[Note]) -> Result<Vec<Option<NoteRejection>>> {
+        debug!(
+            "Checking {} notes with canAddNotesWithErrorDetail",
+            notes.len()
+        );
+
+        let params = json!({ "notes": notes.iter().map(note_params).collect::<Vec<_>>() });
+        let results: Option<Vec<Value>> =
+            match invoke(self, "canAddNotesWithErrorDetail", params).await {
+                Ok(results) => results,
+                Err(AnkiDeckBuilderError::AnkiConnectError(message))
+                    if message.contains("unsupported action") =>
+                {
+                    let accepted = self.can_add_notes(notes).await?;
+                    return Ok(accepted
+                        .into_iter()
+                        .map(|ok| (!ok).then_some(NoteRejection::Duplicate))
+                        .collect());
+                }
+                Err(e) => return Err(e),
+            };
+
+        Ok(results
+            .unwrap_or_default()
+            .iter()
+            .map(|result| {
+                if result["canAdd"].as_bool().unwrap_or(false) {
+                    None
+                } else {
+                    Some(NoteRejection::from_error(
+                        result["error"].as_str().unwrap_or_default(),
+                    ))
+                }
+            })
+            .collect())
+    }
+
+    /// Which of the notes would be refused, and why. Notes without a dedup
+    /// field are checked by Anki together in one request, so an empty first
+    /// field is told apart from a duplicate. The others are searched for
+    /// together, in one query per deck their scope limits them to, and count
+    /// as duplicates when a note in that deck, or anywhere for the
+    /// collection scope, has the same dedup field, unless they allow
+    /// duplicates
+    async fn check_duplicates(&self, notes: &[Note]) -> Result<Vec<Option<NoteRejection>>> {
+        let anki_checked: Vec<Note> = notes
+            .iter()
+            .filter(|note| note.dedup_field.is_none())
+            .cloned()
+            .collect();
+        let mut checks = match anki_checked.is_empty() {
+            true => Vec::new(),
+            false => self.check_notes(&anki_checked).await?,
+        }
+        .into_iter();
+
+        let mut queries: HashMap<Option<&str>, Vec<String>> = HashMap::new();
+        for note in notes.iter().filter(|note| !note.allow_duplicate) {
+            let (Some(field), Some(value)) = (&note.dedup_field, note.dedup_value()) else {
+                continue;
+            };
+            if !value.trim().is_empty() {
+                let deck = note.duplicate_deck();
+                queries
+                    .entry(deck)
+                    .or_default()
+                    .push(field_query(deck, field, value));
+            }
+        }
+        let mut found: HashMap<Option<&str>, Vec<NoteInfo>> = HashMap::new();
+        for (deck, queries) in queries {
+            let ids = self.find_notes(&any_query(&queries)).await?;
+            if !ids.is_empty() {
+                found.insert(deck, self.notes_info(&ids).await?);
+            }
+        }
+
+        Ok(notes
+            .iter()
+            .map(|note| {
+                let Some(field) = &note.dedup_field else {
+                    return checks.next().flatten();
+                };
+                let value = note
+                    .dedup_value()
+                    .map_or(String::new(), |v| v.to_lowercase());
+                if value.trim().is_empty() {
+                    return Some(NoteRejection::Empty);
+                }
+                // Anki matches field searches regardless of case
+                (!note.allow_duplicate
+                    && found
+                        .get(&note.duplicate_deck())
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|info| info.fields.get(field))
+                        .any(|found| found.value.to_lowercase() == value))
+                .then_some(NoteRejection::Duplicate)
+            })
+            .collect())
+    }
+
+    /// Find note IDs matching an Anki search query
+    async fn find_notes(&self, query: &str) -> Result<Vec<i64>> {
+        debug!("Finding notes: {}", query);
+
+        let notes: Option<Vec<i64>> = invoke(self, "findNotes", json!({ "query": query })).await?;
+        Ok(notes.unwrap_or_default())
+    }
+
+    /// Fields, tags, and note type of the given notes. Notes that no longer
+    /// exist are left out
+    async fn notes_info(&self, note_ids: &[i64]) -> Result<Vec<NoteInfo>> {
+        debug!("Fetching info for {} notes", note_ids.len());
+
+        let infos: Option<Vec<Value>> =
+            invoke(self, "notesInfo", json!({ "notes": note_ids })).await?;
+
+        // AnkiConnect answers `{}` for an unknown note ID
+        Ok(infos
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|info| serde_json::from_value(info).ok())
+            .collect())
+    }
+
+    /// Replace the content of some fields of an existing note; fields not
+    /// given keep theirs
+    async fn update_note_fields(
+        &self,
+        note_id: i64,
+        fields: &HashMap<String, String>,
+    ) -> Result<()> {
+        debug!("Updating {} fields of note {}", fields.len(), note_id);
+
+        let params = json!({ "note": { "id": note_id, "fields": fields } });
+        invoke::<_, Value>(self, "updateNoteFields", params).await?;
+        Ok(())
+    }
+
+    /// Replace the text shown on a deck's overview screen
+    async fn set_deck_description(&self, deck: &str, description: &str) -> Result<()> {
+        debug!("Setting the description of deck '{}'", deck);
+
+        let params = json!({ "deck": deck, "description": description });
+        invoke::<_, Value>(self, "setDeckDescription", params).await?;
+        Ok(())
+    }
+
+    /// Delete notes and their cards from the collection. IDs of notes that
+    /// no longer exist are ignored
+    async fn delete_notes(&self, note_ids: &[i64]) -> Result<()> {
+        debug!("Deleting {} notes", note_ids.len());
+
+        invoke::<_, Value>(self, "deleteNotes", json!({ "notes": note_ids })).await?;
+        Ok(())
+    }
+
+    /// Take tags off the given notes
+    async fn remove_tags(&self, note_ids: &[i64], tags: &[String]) -> Result<()> {
+        debug!("Removing tags {:?} from {} notes", tags, note_ids.len());
+
+        // AnkiConnect takes the tags space-separated
+        let params = json!({ "notes": note_ids, "tags": tags.join(" ") });
+        invoke::<_, Value>(self, "removeTags", params).await?;
+        Ok(())
+    }
+
+    /// Question-side text of every note in a deck (and its subdecks)
+    async fn deck_fronts(&self, deck: &str) -> Result<Vec<String>> {
+        let note_ids = self.find_notes(&deck_query(deck, &[])).await?;
+        if note_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let infos = self.notes_info(&note_ids).await?;
+        Ok(infos.iter().filter_map(NoteInfo::front).collect())
+    }
+
+    /// Find card IDs matching an Anki search query
+    async fn find_cards(&self, query: &str) -> Result<Vec<i64>> {
+        debug!("Finding cards: {}", query);
+
+        let cards: Option<Vec<i64>> = invoke(self, "findCards", json!({ "query": query })).await?;
+        Ok(cards.unwrap_or_default())
+    }
+
+    /// Suspend the given cards. Returns false if none of them changed state
+    async fn suspend(&self, card_ids: &[i64]) -> Result<bool> {
+        debug!("Suspending {} cards", card_ids.len());
+
+        let changed: Option<bool> = invoke(self, "suspend", json!({ "cards": card_ids })).await?;
+        Ok(changed.unwrap_or(false))
+    }
+
+    /// Unsuspend the given cards. Returns false if none of them changed state
+    async fn unsuspend(&self, card_ids: &[i64]) -> Result<bool> {
+        debug!("Unsuspending {} cards", card_ids.len());
+
+        let changed: Option<bool> = invoke(self, "unsuspend", json!({ "cards": card_ids })).await?;
+        Ok(changed.unwrap_or(false))
+    }
+
+    /// Store a file in Anki's media folder and return the name to reference
+    /// it by. The name carries a checksum of the content, so identical files
+    /// map to the same name and are only uploaded once
+    async fn store_media_file(&self, name: &str, data: &[u8]) -> Result<String> {
+        let filename = media_filename(name, data);
+
+        let existing = self.get_media_files_names(&filename).await?;
+        if existing.contains(&filename) {
+            debug!("Media file {} already stored", filename);
+            return Ok(filename);
+        }
+
+        debug!("Storing media file {} ({} bytes)", filename, data.len());
+
+        let params = json!({ "filename": filename, "data": BASE64.encode(data) });
+        let stored: Option<String> = invoke(self, "storeMediaFile", params).await?;
+
+        // Anki may rename the file if the name is already taken
+        Ok(stored.unwrap_or(filename))
+    }
+
+    /// Read a file from Anki's media folder, or None if it does not exist
+    async fn retrieve_media_file(&self, filename: &str) -> Result<Option<Vec<u8>>> {
+        debug!("Retrieving media file {}", filename);
+
+        let result: Option<serde_json::Value> =
+            invoke(self, "retrieveMediaFile", json!({ "filename": filename })).await?;
+
+        // AnkiConnect answers `false` for a missing file
+        match result {
+            Some(serde_json::Value::String(encoded)) => {
+                let data = BASE64.decode(encoded).map_err(|e| {
+                    AnkiDeckBuilderError::AnkiConnectError(format!(
+                        "Invalid media data for {}: {}",
+                        filename, e
+                    ))
+                })?;
+                Ok(Some(data))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Names of media files matching a glob pattern
+    async fn get_media_files_names(&self, pattern: &str) -> Result<Vec<String>> {
+        let names: Option<Vec<String>> =
+            invoke(self, "getMediaFilesNames", json!({ "pattern": pattern })).await?;
+        Ok(names.unwrap_or_default())
+    }
+
+    /// Open Anki's card browser on the cards matching a search, returning
+    /// the IDs of the cards it shows
+    async fn gui_browse(&self, query: &str) -> Result<Vec<i64>> {
+        debug!("Browsing cards: {}", query);
+
+        let cards: Option<Vec<i64>> = invoke(self, "guiBrowse", json!({ "query": query })).await?;
+        Ok(cards.unwrap_or_default())
+    }
+
+    /// Bring Anki's main window to its deck list
+    async fn gui_deck_browser(&self) -> Result<()> {
+        invoke::<_, Value>(self, "guiDeckBrowser", json!({})).await?;
+        Ok(())
+    }
+
+    /// Sync the collection with AnkiWeb, as the Sync button does. Fails when
+    /// Anki has no AnkiWeb login
+    async fn sync(&self) -> Result<()> {
+        debug!("Syncing with AnkiWeb");
+
+        invoke::<_, Value>(self, "sync", json!({})).await?;
+        info!("Synced with AnkiWeb");
+        Ok(())
+    }
+
+    /// Export a deck as an .apkg file. Anki writes the file itself, so
+    /// `path` is on the computer Anki runs on
+    async fn export_package(&self, deck: &str, path: &Path, with_scheduling: bool) -> Result<()> {
+        debug!("Exporting deck '{}' to {}", deck, path.display());
+
+        let params = json!({ "deck": deck, "path": path, "includeSched": with_scheduling });
+        let exported: Option<bool> = invoke(self, "exportPackage", params).await?;
+        if exported != Some(true) {
+            return Err(AnkiDeckBuilderError::AnkiConnectError(format!(
+                "could not export deck '{}'",
+                deck
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Send an action and deserialize its `result` payload
+async fn invoke<B, R>(backend: &B, action: &str, params: Value) -> Result<Option<R>>
+where
+    B: AnkiBackend + ?Sized,
+    R: DeserializeOwned,
+{
+    match backend.invoke_raw(action, params).await? {
+        None | Some(Value::Null) => Ok(None),
+        Some(result) => Ok(Some(serde_json::from_value(result)?)),
+    }
+}
+
+/// Content-addressed media name: "dan.mp3" becomes "dan_1a2b3c4d.mp3"
+pub(crate) fn media_filename(name: &str, data: &[u8]) -> String {
+    let checksum = crc32fast::hash(data);
+    match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => {
+            format!("{}_{:08x}.{}", stem, checksum, extension)
+        }
+        _ => format!("{}_{:08x}", name, checksum),
+    }
+}
+
+/// AnkiConnect representation of a note for addNote/canAddNotes. Anki's
+/// own duplicate check is left off for notes with a dedup field, which are
+/// searched for instead
+fn note_params(note: &Note) -> serde_json::Value {
+    json!({
+        "deckName": note.deck_name,
+        "modelName": note.model_name,
+        "fields": note.fields,
+        "tags": note.tags,
+        "options": {
+            "allowDuplicate": note.allow_duplicate || note.dedup_field.is_some(),
+            "duplicateScope": note.duplicate_scope.name(),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_media_filename() {
+        let name = media_filename("dan.mp3", b"audio");
+        assert!(name.starts_with("dan_") && name.ends_with(".mp3"));
+        assert_eq!(name, media_filename("dan.mp3", b"audio"));
+        assert_ne!(name, media_filename("dan.mp3", b"other audio"));
+        assert!(!media_filename("noextension", b"x").contains('.'));
+    }
+}
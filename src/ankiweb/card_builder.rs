@@ -0,0 +1,812 @@
+use crate::ankiweb::models::{
+    BidirectionalStrategy, DuplicateScope, Note, NoteMapping, NoteModel, SortField,
+};
+use crate::ankiweb::renderer::{letter_hint, CardFace, CardRenderer};
+use crate::language::{
+    conjugate, estimate_difficulty, find_confusables, is_cognate, normalize_text, transliterate,
+    LanguagePacks, PartOfSpeech, Section, Word, DEFAULT_COGNATE_THRESHOLD,
+};
+use std::collections::BTreeMap;
+
+/// Subdeck phrases go into, under the main deck
+pub const PHRASE_SUBDECK: &str = "Phrases";
+
+/// Builds the notes for a translated word, deriving tags from the language
+/// pair, deck name, part of speech, estimated difficulty, and whether the
+/// word is a cognate of its translation
+#[derive(Debug, Clone)]
+pub struct CardBuilder {
+    deck_name: String,
+    target_code: String,
+    base_code: String,
+    extra_base_codes: Vec<String>,
+    bidirectional: bool,
+    strategy: BidirectionalStrategy,
+    sort_field: SortField,
+    dedup_field: Option<String>,
+    allow_duplicates: bool,
+    duplicate_scope: DuplicateScope,
+    extra_tags: Vec<String>,
+    images: bool,
+    readings: bool,
+    conjugations: bool,
+    declensions: bool,
+    other_meanings: bool,
+    audio: bool,
+    mnemonics: bool,
+    etymology: bool,
+    examples: bool,
+    mapping: Option<NoteMapping>,
+    cognate_threshold: f64,
+    renderer: CardRenderer,
+    sections: BTreeMap<String, Section>,
+    confusables: BTreeMap<String, Vec<String>>,
+    confusable_hints: bool,
+    /// Gender rules for nouns of the packs' languages
+    packs: LanguagePacks,
+}
+
+impl CardBuilder {
+    pub fn new(deck_name: &str, target_code: &str, base_code: &str) -> Self {
+        Self {
+            deck_name: deck_name.to_string(),
+            target_code: target_code.to_string(),
+            base_code: base_code.to_string(),
+            extra_base_codes: Vec::new(),
+            bidirectional: true,
+            strategy: BidirectionalStrategy::Notes,
+            sort_field: SortField::Front,
+            dedup_field: None,
+            allow_duplicates: false,
+            duplicate_scope: DuplicateScope::Collection,
+            extra_tags: Vec::new(),
+            images: false,
+            readings: false,
+            conjugations: false,
+            declensions: false,
+            other_meanings: false,
+            audio: false,
+            mnemonics: false,
+            etymology: false,
+            examples: false,
+            mapping: None,
+            cognate_threshold: DEFAULT_COGNATE_THRESHOLD,
+            renderer: CardRenderer::default(),
+            sections: BTreeMap::new(),
+            confusables: BTreeMap::new(),
+            confusable_hints: false,
+            packs: LanguagePacks::default(),
+        }
+    }
+
+    /// Tag nouns with their gender by these packs' rules
+    pub fn with_language_packs(mut self, packs: LanguagePacks) -> Self {
+        self.packs = packs;
+        self
+    }
+
+    /// Further base languages whose translations share the card with the
+    /// main base language (see `combine_translations`)
+    pub fn with_extra_base_codes(mut self, codes: Vec<String>) -> Self {
+        self.extra_base_codes = codes;
+        self
+    }
+
+    pub fn with_bidirectional(mut self, bidirectional: bool) -> Self {
+        self.bidirectional = bidirectional;
+        self
+    }
+
+    pub fn with_bidirectional_strategy(mut self, strategy: BidirectionalStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    pub fn with_sort_field(mut self, sort_field: SortField) -> Self {
+        self.sort_field = sort_field;
+        self
+    }
+
+    /// Key duplicate detection on this field instead of the note type's default
+    pub fn with_dedup_field(mut self, field: Option<String>) -> Self {
+        self.dedup_field = field;
+        self
+    }
+
+    /// Let Anki add notes that duplicate existing ones
+    pub fn with_allow_duplicates(mut self, allow: bool) -> Self {
+        self.allow_duplicates = allow;
+        self
+    }
+
+    /// Which notes Anki checks new notes against for duplicates
+    pub fn with_duplicate_scope(mut self, scope: DuplicateScope) -> Self {
+        self.duplicate_scope = scope;
+        self
+    }
+
+    pub fn with_extra_tags(mut self, tags: Vec<String>) -> Self {
+        self.extra_tags = tags;
+        self
+    }
+
+    /// Use note types with an Image field
+    pub fn with_images(mut self, images: bool) -> Self {
+        self.images = images;
+        self
+    }
+
+    /// Use note types with a Reading field, filled with the romanized target word
+    pub fn with_readings(mut self, readings: bool) -> Self {
+        self.readings = readings;
+        self
+    }
+
+    /// Use note types with a Conjugation field, filled for verbs
+    pub fn with_conjugations(mut self, conjugations: bool) -> Self {
+        self.conjugations = conjugations;
+        self
+    }
+
+    /// Use note types with a Declension field, filled in later for nouns
+    /// (see `Note::set_declension`)
+    pub fn with_declensions(mut self, declensions: bool) -> Self {
+        self.declensions = declensions;
+        self
+    }
+
+    /// Use note types with an Other meanings field, filled in later from
+    /// the dictionary (see `Note::set_other_meanings`)
+    pub fn with_other_meanings(mut self, other_meanings: bool) -> Self {
+        self.other_meanings = other_meanings;
+        self
+    }
+
+    /// Use note types with Audio and Source fields, filled in later with a
+    /// recording (see `Note::set_audio`)
+    pub fn with_audio(mut self, audio: bool) -> Self {
+        self.audio = audio;
+        self
+    }
+
+    /// Use note types with a Hint field, filled in later with a memory hook
+    pub fn with_mnemonics(mut self, mnemonics: bool) -> Self {
+        self.mnemonics = mnemonics;
+        self
+    }
+
+    /// Use note types with an Etymology field, filled in later from Wiktionary
+    pub fn with_etymology(mut self, etymology: bool) -> Self {
+        self.etymology = etymology;
+        self
+    }
+
+    /// Use note types with an Example field, filled in later with a sentence
+    /// using the word
+    pub fn with_examples(mut self, examples: bool) -> Self {
+        self.examples = examples;
+        self
+    }
+
+    /// Put notes in one of the user's own note types instead of Basic
+    pub fn with_mapping(mut self, mapping: Option<NoteMapping>) -> Self {
+        self.mapping = mapping;
+        self
+    }
+
+    /// Similarity to the translation from which a word is tagged `cognate`
+    pub fn with_cognate_threshold(mut self, threshold: f64) -> Self {
+        self.cognate_threshold = threshold;
+        self
+    }
+
+    /// How Front and Back are formatted
+    pub fn with_renderer(mut self, renderer: CardRenderer) -> Self {
+        self.renderer = renderer;
+        self
+    }
+
+    /// Send these words to the subdecks of their generated sections
+    pub fn with_sections(mut self, sections: BTreeMap<String, Section>) -> Self {
+        self.sections = sections;
+        self
+    }
+
+    /// Tag the words of a deck that are easily mixed up with one another
+    /// `confusable` (see `find_confusables`)
+    pub fn with_confusables(mut self, words: &[Word]) -> Self {
+        self.confusables = find_confusables(words.iter().map(|word| word.text.as_str()));
+        self
+    }
+
+    /// Name the words a confusable word is easily mixed up with under the
+    /// answer
+    pub fn with_confusable_hints(mut self, hints: bool) -> Self {
+        self.confusable_hints = hints;
+        self
+    }
+
+    /// Deck that phrase notes are added to
+    pub fn phrase_deck(&self) -> String {
+        format!("{}::{}", self.deck_name, PHRASE_SUBDECK)
+    }
+
+    /// Deck that the notes of a generated section are added to
+    pub fn section_deck(&self, section: Section) -> String {
+        format!("{}::{}", self.deck_name, section.subdeck())
+    }
+
+    /// The subdecks of the sections words were given for
+    pub fn section_decks(&self) -> Vec<String> {
+        let mut sections: Vec<_> = self.sections.values().copied().collect();
+        sections.sort();
+        sections.dedup();
+        sections
+            .into_iter()
+            .map(|section| self.section_deck(section))
+            .collect()
+    }
+
+    fn reversed_template(&self) -> bool {
+        self.bidirectional && self.strategy == BidirectionalStrategy::Templates
+    }
+
+    /// Number of notes built per word
+    pub fn notes_per_word(&self) -> usize {
+        if self.bidirectional && !self.reversed_template() {
+            2
+        } else {
+            1
+        }
+    }
+
+    /// Number of Anki cards each built note produces
+    pub fn cards_per_note(&self) -> usize {
+        if self.reversed_template() {
+            2
+        } else {
+            1
+        }
+    }
+
+    /// Custom note type that must exist in Anki before adding notes, if any
+    pub fn required_model(&self) -> Option<NoteModel> {
+        // The user's own note type is checked, never created
+        if self.mapping.is_some() {
+            return None;
+        }
+
+        let model = match (self.sort_field, self.reversed_template()) {
+            (SortField::Rank, true) => NoteModel::ranked_reversed(),
+            (SortField::Rank, false) => NoteModel::ranked(),
+            (SortField::Front, true) => NoteModel::basic_reversed(),
+            (SortField::Front, false) => NoteModel::basic(),
+        };
+
+        if !self.images
+            && !self.readings
+            && !self.conjugations
+            && !self.declensions
+            && !self.other_meanings
+            && !self.audio
+            && !self.mnemonics
+            && !self.etymology
+            && !self.examples
+            && self.sort_field == SortField::Front
+        {
+            // Anki's built-in note types
+            return None;
+        }
+
+        let model = if self.images {
+            model.with_image()
+        } else {
+            model
+        };
+        let model = if self.readings {
+            model.with_reading()
+        } else {
+            model
+        };
+        let model = if self.conjugations {
+            model.with_conjugation()
+        } else {
+            model
+        };
+        let model = if self.declensions {
+            model.with_declension()
+        } else {
+            model
+        };
+        let model = if self.other_meanings {
+            model.with_other_meanings()
+        } else {
+            model
+        };
+        let model = if self.audio {
+            model.with_audio()
+        } else {
+            model
+        };
+        let model = if self.mnemonics {
+            model.with_hint()
+        } else {
+            model
+        };
+        let model = if self.etymology {
+            model.with_etymology()
+        } else {
+            model
+        };
+        Some(if self.examples {
+            model.with_example()
+        } else {
+            model
+        })
+    }
+
+    /// Build notes for one word: target→base, plus base→target if bidirectional.
+    /// With the templates strategy a single note covers both directions
+    pub fn build(
+        &self,
+        word: &str,
+        translation: &str,
+        pos: &PartOfSpeech,
+        rank: usize,
+    ) -> Vec<Note> {
+        // The same word in another encoding would slip past Anki's duplicate check
+        let word: &str = &normalize_text(word);
+        let translation: &str = &normalize_text(translation);
+        let confusable_with = self.confusables.get(word).map(|others| others.join(", "));
+        let see_also = confusable_with.as_deref().filter(|_| self.confusable_hints);
+
+        // You see the target word and recall the base-language meaning
+        let mut notes = vec![self.note(
+            CardFace {
+                prompt: word,
+                answer: translation,
+                pos,
+                example: None,
+                hint: None,
+                confusable: see_also,
+            },
+            &self.target_code,
+            &self.base_code,
+            rank,
+        )];
+
+        if self.reversed_template() {
+            notes[0] = notes[0].clone().with_reversed_card();
+        } else if self.bidirectional {
+            // You see the base-language word and recall the target word
+            let hint = letter_hint(word);
+            notes.push(self.note(
+                CardFace {
+                    prompt: translation,
+                    answer: word,
+                    pos,
+                    example: None,
+                    hint: Some(&hint),
+                    confusable: see_also,
+                },
+                &self.base_code,
+                &self.target_code,
+                rank,
+            ));
+        }
+
+        let difficulty = estimate_difficulty(&self.target_code, word, translation, rank);
+        let cognate = is_cognate(&self.target_code, word, translation, self.cognate_threshold);
+        let section = self.sections.get(word).copied();
+        let gender = match pos {
+            PartOfSpeech::Noun => self.packs.noun_gender(&self.target_code, word),
+            _ => None,
+        };
+        for note in &mut notes {
+            note.tags.push(format!("difficulty:{}", difficulty.name()));
+            if let Some(gender) = &gender {
+                note.tags.push(format!("gender:{}", tag_safe(gender)));
+            }
+            if cognate {
+                note.tags.push("cognate".to_string());
+            }
+            if confusable_with.is_some() {
+                note.tags.push("confusable".to_string());
+            }
+            if let Some(section) = section {
+                note.deck_name = self.section_deck(section);
+                note.tags.push(format!("section:{}", section.name()));
+            }
+        }
+
+        if self.images {
+            notes = notes.into_iter().map(Note::with_image_field).collect();
+        }
+        if self.readings {
+            // Empty for words already written in Latin letters
+            let reading = transliterate(&self.target_code, word).unwrap_or_default();
+            notes = notes
+                .into_iter()
+                .map(|note| note.with_reading_field(&reading))
+                .collect();
+        }
+        if self.conjugations {
+            // Empty for other parts of speech and verbs with no known conjugation
+            let conjugation = match pos {
+                PartOfSpeech::Verb => conjugate(&self.target_code, word)
+                    .map(|c| c.to_field())
+                    .unwrap_or_default(),
+                _ => String::new(),
+            };
+            notes = notes
+                .into_iter()
+                .map(|note| note.with_conjugation_field(&conjugation))
+                .collect();
+        }
+        if self.declensions {
+            notes = notes.into_iter().map(Note::with_declension_field).collect();
+        }
+        if self.other_meanings {
+            notes = notes
+                .into_iter()
+                .map(Note::with_other_meanings_field)
+                .collect();
+        }
+        if self.audio {
+            notes = notes.into_iter().map(Note::with_audio_field).collect();
+        }
+        if self.mnemonics {
+            notes = notes.into_iter().map(Note::with_hint_field).collect();
+        }
+        if self.etymology {
+            notes = notes.into_iter().map(Note::with_etymology_field).collect();
+        }
+        if self.examples {
+            notes = notes.into_iter().map(Note::with_example_field).collect();
+        }
+        if let Some(mapping) = &self.mapping {
+            notes = notes.into_iter().map(|note| mapping.apply(note)).collect();
+        }
+
+        notes
+    }
+
+    fn note(&self, face: CardFace, from: &str, to: &str, rank: usize) -> Note {
+        let fields = self.renderer.render(&face);
+        let deck_name = match face.pos {
+            PartOfSpeech::Phrase => self.phrase_deck(),
+            _ => self.deck_name.clone(),
+        };
+        let note = Note::new(deck_name, fields.front, fields.back)
+            .with_tags(self.tags(from, to, face.pos))
+            .with_allow_duplicate(self.allow_duplicates)
+            .with_duplicate_scope(self.duplicate_scope);
+
+        let note = match self.sort_field {
+            SortField::Rank => note.with_rank(rank),
+            SortField::Front => note,
+        };
+
+        match &self.dedup_field {
+            Some(field) => note.with_dedup_field(field),
+            None => note,
+        }
+    }
+
+    fn tags(&self, from: &str, to: &str, pos: &PartOfSpeech) -> Vec<String> {
+        let mut tags = vec![
+            "auto-generated".to_string(),
+            format!("{}-to-{}", from, to),
+            format!("pos:{}", pos.name()),
+            format!("deck:{}", tag_safe(&self.deck_name)),
+        ];
+        for code in &self.extra_base_codes {
+            if from == self.target_code {
+                tags.push(format!("{}-to-{}", from, code));
+            } else {
+                tags.push(format!("{}-to-{}", code, to));
+            }
+        }
+        tags.extend(self.extra_tags.iter().map(|t| tag_safe(t)));
+        tags
+    }
+}
+
+/// Card text for a word translated into several base languages, one line
+/// per language: "es: día<br>en: day". A single translation is left as is
+pub fn combine_translations(translations: &[(String, String)]) -> String {
+    match translations {
+        [(_, only)] => only.clone(),
+        _ => translations
+            .iter()
+            .map(|(code, text)| format!("{}: {}", code, text))
+            .collect::<Vec<_>>()
+            .join("<br>"),
+    }
+}
+
+/// Anki tags are space-separated, so replace whitespace with underscores
+fn tag_safe(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<_>>().join("_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ankiweb::renderer::headword;
+
+    #[test]
+    fn test_fields_are_normalized() {
+        let builder = CardBuilder::new("My Deck", "hr", "es");
+        let notes = builder.build("c\u{30c}aj\u{200b}", " té ", &PartOfSpeech::Noun, 1);
+        let expected = builder.build("čaj", "té", &PartOfSpeech::Noun, 1);
+        assert_eq!(notes[0].fields, expected[0].fields);
+        assert_eq!(headword(&notes[1].fields["Front"]), Some("té".to_string()));
+    }
+
+    #[test]
+    fn test_tags_use_language_codes() {
+        let builder = CardBuilder::new("My Deck", "hr", "es");
+        let notes = builder.build("dan", "día", &PartOfSpeech::Noun, 1);
+
+        assert_eq!(notes.len(), 2);
+        assert!(notes[0].tags.contains(&"hr-to-es".to_string()));
+        assert!(notes[1].tags.contains(&"es-to-hr".to_string()));
+        assert!(notes[0].tags.contains(&"pos:noun".to_string()));
+        assert!(notes[0].tags.contains(&"deck:My_Deck".to_string()));
+        assert!(notes[1].tags.contains(&"difficulty:easy".to_string()));
+        assert!(!notes[0].tags.contains(&"cognate".to_string()));
+        assert_eq!(headword(&notes[1].fields["Front"]), Some("día".to_string()));
+
+        let notes = builder.build("telefon", "teléfono", &PartOfSpeech::Noun, 900);
+        assert!(notes
+            .iter()
+            .all(|n| n.tags.contains(&"cognate".to_string())));
+    }
+
+    #[test]
+    fn test_phrases_go_to_subdeck() {
+        let builder = CardBuilder::new("Croatian", "hr", "es");
+        let notes = builder.build("hvala lijepa", "muchas gracias", &PartOfSpeech::Phrase, 1);
+
+        assert!(notes
+            .iter()
+            .all(|note| note.deck_name == "Croatian::Phrases"));
+        assert!(notes[0].tags.contains(&"pos:phrase".to_string()));
+        assert_eq!(
+            builder.build("dan", "día", &PartOfSpeech::Noun, 1)[0].deck_name,
+            "Croatian"
+        );
+    }
+
+    #[test]
+    fn test_sections_go_to_subdecks() {
+        let builder = CardBuilder::new("Croatian", "hr", "en").with_sections(BTreeMap::from([
+            ("dva".to_string(), Section::Numbers),
+            ("pola tri".to_string(), Section::Dates),
+        ]));
+
+        let notes = builder.build("pola tri", "half past two", &PartOfSpeech::Phrase, 1);
+        assert!(notes.iter().all(|note| note.deck_name == "Croatian::Dates"));
+        assert!(notes[0].tags.contains(&"section:dates".to_string()));
+        assert_eq!(
+            builder.build("dva", "two", &PartOfSpeech::Noun, 2)[0].deck_name,
+            "Croatian::Numbers"
+        );
+        assert_eq!(
+            builder.section_decks(),
+            ["Croatian::Numbers", "Croatian::Dates"]
+        );
+    }
+
+    #[test]
+    fn test_confusable_words() {
+        let words: Vec<Word> = ["kuća", "kuca", "voda"]
+            .iter()
+            .enumerate()
+            .map(|(rank, text)| Word::new(text.to_string(), PartOfSpeech::Noun, rank + 1))
+            .collect();
+        let builder = CardBuilder::new("Croatian", "hr", "es").with_confusables(&words);
+
+        let notes = builder.build("kuća", "casa", &PartOfSpeech::Noun, 1);
+        assert!(notes
+            .iter()
+            .all(|note| note.tags.contains(&"confusable".to_string())));
+        assert!(!notes[0].fields["Back"].contains("≠"));
+        let notes = builder.build("voda", "agua", &PartOfSpeech::Noun, 2);
+        assert!(!notes[0].tags.contains(&"confusable".to_string()));
+
+        let notes =
+            builder
+                .with_confusable_hints(true)
+                .build("kuća", "casa", &PartOfSpeech::Noun, 1);
+        assert!(notes
+            .iter()
+            .all(|note| note.fields["Back"].contains("≠ kuca")));
+    }
+
+    #[test]
+    fn test_extra_base_languages() {
+        let builder =
+            CardBuilder::new("Deck", "hr", "es").with_extra_base_codes(vec!["en".to_string()]);
+        let back = combine_translations(&[
+            ("es".to_string(), "día".to_string()),
+            ("en".to_string(), "day".to_string()),
+        ]);
+        let notes = builder.build("dan", &back, &PartOfSpeech::Noun, 1);
+
+        assert_eq!(back, "es: día<br>en: day");
+        assert!(notes[0].tags.contains(&"hr-to-es".to_string()));
+        assert!(notes[0].tags.contains(&"hr-to-en".to_string()));
+        assert!(notes[1].tags.contains(&"en-to-hr".to_string()));
+        assert_eq!(
+            combine_translations(&[("es".to_string(), "día".to_string())]),
+            "día"
+        );
+    }
+
+    #[test]
+    fn test_unidirectional_with_extra_tags() {
+        let builder = CardBuilder::new("Deck", "hr", "es")
+            .with_bidirectional(false)
+            .with_extra_tags(vec!["week 1".to_string()]);
+        let notes = builder.build("biti", "ser", &PartOfSpeech::Verb, 2);
+
+        assert_eq!(notes.len(), builder.notes_per_word());
+        assert_eq!(notes.len(), 1);
+        assert!(notes[0].tags.contains(&"week_1".to_string()));
+    }
+
+    #[test]
+    fn test_dedup_field_override() {
+        let builder =
+            CardBuilder::new("Deck", "hr", "es").with_dedup_field(Some("Back".to_string()));
+        let notes = builder.build("dan", "día", &PartOfSpeech::Noun, 1);
+
+        assert_eq!(
+            notes[0].dedup_value(),
+            Some(&"<div class=\"answer\">día</div>".to_string())
+        );
+        assert!(!notes[0].allow_duplicate);
+    }
+
+    #[test]
+    fn test_duplicate_options() {
+        let notes =
+            CardBuilder::new("Deck", "hr", "es").build("dan", "día", &PartOfSpeech::Noun, 1);
+        assert!(!notes[0].allow_duplicate);
+        assert_eq!(notes[0].duplicate_scope, DuplicateScope::Collection);
+
+        let builder = CardBuilder::new("Deck", "hr", "es")
+            .with_allow_duplicates(true)
+            .with_duplicate_scope(DuplicateScope::Deck)
+            .with_sort_field(SortField::Rank);
+        let notes = builder.build("dan", "día", &PartOfSpeech::Noun, 1);
+        assert!(notes.iter().all(|note| note.allow_duplicate));
+        assert!(notes
+            .iter()
+            .all(|note| note.duplicate_scope == DuplicateScope::Deck));
+    }
+
+    #[test]
+    fn test_templates_strategy() {
+        let builder = CardBuilder::new("Deck", "hr", "es")
+            .with_bidirectional_strategy(BidirectionalStrategy::Templates);
+        let notes = builder.build("dan", "día", &PartOfSpeech::Noun, 1);
+
+        assert_eq!(notes.len(), 1);
+        assert_eq!(builder.cards_per_note(), 2);
+        assert_eq!(notes[0].model_name, "Basic (and reversed card)");
+        assert!(builder.required_model().is_none());
+
+        let ranked = builder.with_sort_field(SortField::Rank);
+        assert_eq!(
+            ranked.required_model().unwrap().name,
+            ranked.build("dan", "día", &PartOfSpeech::Noun, 1)[0].model_name
+        );
+    }
+
+    #[test]
+    fn test_images_use_image_model() {
+        let builder = CardBuilder::new("Deck", "hr", "es").with_images(true);
+        let model = builder.required_model().unwrap();
+        let notes = builder.build("dan", "día", &PartOfSpeech::Noun, 1);
+
+        assert_eq!(notes.len(), 2);
+        assert!(notes.iter().all(|n| n.model_name == model.name));
+        assert!(model.fields.iter().all(|f| notes[0].fields.contains_key(f)));
+    }
+
+    #[test]
+    fn test_readings() {
+        let builder = CardBuilder::new("Deck", "ru", "es")
+            .with_sort_field(SortField::Rank)
+            .with_readings(true);
+        let model = builder.required_model().unwrap();
+        let notes = builder.build("дом", "casa", &PartOfSpeech::Noun, 1);
+
+        assert!(notes.iter().all(|n| n.model_name == model.name));
+        assert!(notes
+            .iter()
+            .all(|n| n.fields.get("Reading") == Some(&"dom".to_string())));
+        assert!(model.fields.iter().all(|f| notes[1].fields.contains_key(f)));
+    }
+
+    #[test]
+    fn test_conjugations() {
+        let builder = CardBuilder::new("Deck", "hr", "es").with_conjugations(true);
+        let model = builder.required_model().unwrap();
+        let verb = builder.build("raditi", "trabajar", &PartOfSpeech::Verb, 1);
+        let noun = builder.build("dan", "día", &PartOfSpeech::Noun, 2);
+
+        assert!(verb.iter().chain(&noun).all(|n| n.model_name == model.name));
+        assert!(model.fields.iter().all(|f| verb[0].fields.contains_key(f)));
+        assert!(verb[0].fields["Conjugation"].starts_with("ja radim<br>ti radiš"));
+        assert_eq!(noun[0].fields["Conjugation"], "");
+
+        let builder = builder.with_declensions(true);
+        let model = builder.required_model().unwrap();
+        let notes = builder.build("dan", "día", &PartOfSpeech::Noun, 2);
+        assert!(model.name.ends_with("+ Conjugation + Declension"));
+        assert!(notes.iter().all(|n| n.model_name == model.name));
+        assert!(model.fields.iter().all(|f| notes[0].fields.contains_key(f)));
+
+        // Any of these fields alone still needs a note type of its own
+        for builder in [
+            CardBuilder::new("Deck", "hr", "en").with_declensions(true),
+            CardBuilder::new("Deck", "hr", "en").with_other_meanings(true),
+            CardBuilder::new("Deck", "hr", "en").with_audio(true),
+            CardBuilder::new("Deck", "hr", "en").with_mnemonics(true),
+            CardBuilder::new("Deck", "hr", "en").with_etymology(true),
+            CardBuilder::new("Deck", "hr", "en").with_examples(true),
+        ] {
+            let model = builder.required_model().unwrap();
+            let notes = builder.build("oko", "eye", &PartOfSpeech::Noun, 3);
+            assert!(notes.iter().all(|n| n.model_name == model.name));
+            assert!(model.fields.iter().all(|f| notes[0].fields.contains_key(f)));
+        }
+    }
+
+    #[test]
+    fn test_mapping_to_user_model() {
+        let mapping = NoteMapping::new("Vocab")
+            .with_field_map(&["Front=Term".to_string(), "Back=Meaning".to_string()])
+            .unwrap();
+        let builder = CardBuilder::new("Deck", "hr", "es")
+            .with_images(true)
+            .with_mapping(Some(mapping));
+        let notes = builder.build("dan", "día", &PartOfSpeech::Noun, 1);
+
+        assert!(builder.required_model().is_none());
+        assert!(notes.iter().all(|n| n.model_name == "Vocab"));
+        assert_eq!(headword(&notes[1].fields["Term"]), Some("día".to_string()));
+        assert!(notes[0].fields.contains_key("Image"));
+    }
+
+    #[test]
+    fn test_rendered_fields() {
+        let builder = CardBuilder::new("Deck", "hr", "es");
+        let notes = builder.build("dan", "día & noche", &PartOfSpeech::Noun, 1);
+
+        assert!(notes[0].fields["Front"].contains("<b>dan</b>"));
+        assert!(notes[0].fields["Back"].contains("día &amp; noche"));
+        assert!(!notes[0].fields["Front"].contains("Hint"));
+        assert!(notes[1].fields["Front"].contains("<summary>Hint</summary>d _ _"));
+
+        let plain = builder.with_renderer(CardRenderer::plain());
+        let notes = plain.build("dan", "día", &PartOfSpeech::Noun, 1);
+        assert_eq!(notes[0].fields["Front"], "dan");
+        assert_eq!(notes[1].fields["Front"], "día");
+        assert_eq!(notes[1].fields["Back"], "dan");
+    }
+
+    #[test]
+    fn test_rank_sort_field() {
+        let builder = CardBuilder::new("Deck", "hr", "es").with_sort_field(SortField::Rank);
+        let notes = builder.build("dan", "día", &PartOfSpeech::Noun, 12);
+
+        assert!(notes
+            .iter()
+            .all(|n| n.fields.get("Rank") == Some(&"00012".to_string())));
+    }
+}
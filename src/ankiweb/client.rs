@@ -1,14 +1,101 @@
-use crate::ankiweb::models::Note;
+use crate::ankiweb::backend::AnkiBackend;
 use crate::error::{AnkiDeckBuilderError, Result};
+use crate::http::HttpSettings;
+use async_trait::async_trait;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
-use tracing::{debug, info};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
 
+/// Actions that can take a while in a large collection, given the long timeout
+const LONG_ACTIONS: &[&str] = &[
+    "canAddNotes",
+    "canAddNotesWithErrorDetail",
+    "createModel",
+    "exportPackage",
+    "findCards",
+    "findNotes",
+    "notesInfo",
+    "storeMediaFile",
+    "sync",
+];
+
+/// Actions that leave the collection and Anki's files as they are, the only
+/// ones a read-only client sends
+const READ_ONLY_ACTIONS: &[&str] = &[
+    "canAddNotes",
+    "canAddNotesWithErrorDetail",
+    "deckNames",
+    "findCards",
+    "findNotes",
+    "getActiveProfile",
+    "getDeckConfig",
+    "getMediaFilesNames",
+    "getProfiles",
+    "guiBrowse",
+    "modelFieldNames",
+    "modelNames",
+    "notesInfo",
+    "retrieveMediaFile",
+    "version",
+];
+
+/// Errors Anki gives while it is busy syncing or checking the database; the
+/// same request succeeds once it is done
+const TRANSIENT_ERRORS: &[&str] = &[
+    "collection is not available",
+    "collection not available",
+    "database is locked",
+];
+
+/// Longest wait between two retries
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(10);
+
+/// How long AnkiClient waits for AnkiConnect and how it retries while Anki is
+/// busy, from the `ankiconnect` section of the config file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AnkiConnectSettings {
+    /// Seconds to wait for an answer to most requests
+    pub timeout_secs: u64,
+    /// Seconds to wait for actions that can take long in a large collection
+    /// (searches, media, new note types)
+    pub long_timeout_secs: u64,
+    /// Times a request is retried while Anki is busy
+    pub retries: u32,
+    /// Milliseconds before the first retry; each next one waits twice as long
+    pub retry_delay_ms: u64,
+    /// Anki program `--launch-anki` starts, when not where its installer puts it
+    pub anki_path: Option<PathBuf>,
+    /// Seconds `--launch-anki` waits for AnkiConnect to come up
+    pub launch_timeout_secs: u64,
+}
+
+impl Default for AnkiConnectSettings {
+    fn default() -> Self {
+        Self {
+            timeout_secs: 30,
+            long_timeout_secs: 120,
+            retries: 5,
+            retry_delay_ms: 500,
+            anki_path: None,
+            launch_timeout_secs: 60,
+        }
+    }
+}
+
+/// AnkiConnect client talking to a running Anki over HTTP
 #[derive(Debug, Clone)]
 pub struct AnkiClient {
     base_url: String,
     client: Client,
+    timeout: Duration,
+    long_timeout: Duration,
+    retries: u32,
+    retry_delay: Duration,
+    read_only: bool,
 }
 
 #[derive(Serialize)]
@@ -26,111 +113,162 @@ struct AnkiResponse<T> {
 
 impl AnkiClient {
     pub fn new(base_url: String) -> Result<Self> {
-        let client = Client::builder()
-            .timeout(std::time::Duration::from_secs(30))
+        Self::with_settings(
+            base_url,
+            &AnkiConnectSettings::default(),
+            &HttpSettings::default(),
+        )
+    }
+
+    /// Client waiting and retrying as `settings` say, going through the
+    /// proxy and trusting the certificates of `http`
+    pub fn with_settings(
+        base_url: String,
+        settings: &AnkiConnectSettings,
+        http: &HttpSettings,
+    ) -> Result<Self> {
+        let client = crate::http::client_builder(http)?
             .build()
             .map_err(AnkiDeckBuilderError::HttpError)?;
 
-        Ok(Self { base_url, client })
+        Ok(Self {
+            base_url,
+            client,
+            timeout: Duration::from_secs(settings.timeout_secs),
+            long_timeout: Duration::from_secs(settings.long_timeout_secs),
+            retries: settings.retries,
+            retry_delay: Duration::from_millis(settings.retry_delay_ms),
+            read_only: false,
+        })
     }
 
-    /// Verify that AnkiConnect is running and accessible
-    pub async fn verify_connection(&self) -> Result<()> {
-        debug!("Verifying connection to AnkiConnect at {}", self.base_url);
+    /// Client that refuses actions changing anything in Anki, failing with
+    /// `ReadOnly` before they are sent
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
 
-        let request = AnkiRequest {
-            action: "version".to_string(),
-            version: 6,
-            params: json!({}),
-        };
+    /// Client giving up on requests that take longer than `timeout`
+    pub fn with_timeout(base_url: String, timeout: Duration) -> Result<Self> {
+        let mut client = Self::new(base_url)?;
+        client.timeout = timeout;
+        client.long_timeout = timeout;
+        Ok(client)
+    }
+
+    /// Time to wait for an answer to `action`
+    fn timeout_for(&self, action: &str) -> Duration {
+        if LONG_ACTIONS.contains(&action) {
+            self.long_timeout
+        } else {
+            self.timeout
+        }
+    }
+
+    /// Send a request once
+    async fn send(&self, request: &AnkiRequest<Value>) -> Result<Option<Value>> {
+        crate::logging::http_request("POST", &self.base_url, Some(&json!(request)));
 
+        let started = Instant::now();
         let response = self
             .client
             .post(&self.base_url)
-            .json(&request)
+            .timeout(self.timeout_for(&request.action))
+            .json(request)
             .send()
             .await
-            .map_err(|_| AnkiDeckBuilderError::AnkiConnectNotRunning {
-                url: self.base_url.clone(),
-            })?;
+            .map_err(AnkiDeckBuilderError::HttpError)?;
+        let status = response.status().as_u16();
 
-        let anki_response: AnkiResponse<u32> = response
+        let anki_response: AnkiResponse<Value> = response
             .json()
             .await
             .map_err(AnkiDeckBuilderError::HttpError)?;
+        crate::logging::http_response(
+            "POST",
+            &self.base_url,
+            status,
+            started,
+            Some(&json!({ "result": anki_response.result, "error": anki_response.error })),
+        );
 
         if let Some(error) = anki_response.error {
             return Err(AnkiDeckBuilderError::AnkiConnectError(error));
         }
 
-        info!(
-            "Successfully connected to AnkiConnect (version: {:?})",
-            anki_response.result
-        );
-        Ok(())
+        Ok(anki_response.result)
     }
+}
 
-    /// Create a new deck
-    pub async fn create_deck(&self, name: &str) -> Result<i64> {
-        debug!("Creating deck: {}", name);
+/// Whether an AnkiConnect error only means Anki is busy for now
+pub fn is_transient(error: &str) -> bool {
+    let error = error.to_lowercase();
+    TRANSIENT_ERRORS
+        .iter()
+        .any(|transient| error.contains(transient))
+}
 
+#[async_trait]
+impl AnkiBackend for AnkiClient {
+    /// Retried with growing delays while Anki is busy syncing or checking
+    /// its database
+    async fn invoke_raw(&self, action: &str, params: Value) -> Result<Option<Value>> {
+        if self.read_only && !READ_ONLY_ACTIONS.contains(&action) {
+            return Err(AnkiDeckBuilderError::ReadOnly(action.to_string()));
+        }
         let request = AnkiRequest {
-            action: "createDeck".to_string(),
+            action: action.to_string(),
             version: 6,
-            params: json!({ "deck": name }),
+            params,
         };
 
-        let response = self
-            .client
-            .post(&self.base_url)
-            .json(&request)
-            .send()
-            .await
-            .map_err(AnkiDeckBuilderError::HttpError)?;
-
-        let anki_response: AnkiResponse<i64> = response
-            .json()
-            .await
-            .map_err(AnkiDeckBuilderError::HttpError)?;
-
-        if let Some(error) = anki_response.error {
-            return Err(AnkiDeckBuilderError::AnkiConnectError(error));
+        let mut delay = self.retry_delay;
+        let mut attempt = 0;
+        loop {
+            match self.send(&request).await {
+                Err(AnkiDeckBuilderError::AnkiConnectError(error))
+                    if attempt < self.retries && is_transient(&error) =>
+                {
+                    attempt += 1;
+                    warn!(
+                        "Anki is busy ({}); retrying {} in {:.1}s ({}/{})",
+                        error,
+                        action,
+                        delay.as_secs_f64(),
+                        attempt,
+                        self.retries
+                    );
+                    tokio::time::sleep(delay).await;
+                    delay = (delay * 2).min(MAX_RETRY_DELAY);
+                }
+                result => return result,
+            }
         }
-
-        let deck_id = anki_response.result.ok_or_else(|| {
-            AnkiDeckBuilderError::AnkiConnectError("No deck ID returned".to_string())
-        })?;
-
-        info!("Created deck '{}' with ID: {}", name, deck_id);
-        Ok(deck_id)
     }
 
-    /// Add a note to a deck
-    pub async fn add_note(&self, note: &Note) -> Result<i64> {
-        debug!("Adding note to deck: {}", note.deck_name);
+    /// Verify that AnkiConnect is running and accessible
+    async fn verify_connection(&self) -> Result<()> {
+        debug!("Verifying connection to AnkiConnect at {}", self.base_url);
 
         let request = AnkiRequest {
-            action: "addNote".to_string(),
+            action: "version".to_string(),
             version: 6,
-            params: json!({
-                "note": {
-                    "deckName": note.deck_name,
-                    "modelName": note.model_name,
-                    "fields": note.fields,
-                    "tags": note.tags,
-                }
-            }),
+            params: json!({}),
         };
 
         let response = self
             .client
             .post(&self.base_url)
+            .timeout(self.timeout)
             .json(&request)
             .send()
             .await
-            .map_err(AnkiDeckBuilderError::HttpError)?;
+            .map_err(|_| AnkiDeckBuilderError::AnkiConnectNotRunning {
+                url: self.base_url.clone(),
+            })?;
 
-        let anki_response: AnkiResponse<i64> = response
+        let anki_response: AnkiResponse<u32> = response
             .json()
             .await
             .map_err(AnkiDeckBuilderError::HttpError)?;
@@ -139,43 +277,155 @@ impl AnkiClient {
             return Err(AnkiDeckBuilderError::AnkiConnectError(error));
         }
 
-        let note_id = anki_response.result.ok_or_else(|| {
-            AnkiDeckBuilderError::AnkiConnectError("No note ID returned".to_string())
-        })?;
+        info!(
+            "Successfully connected to AnkiConnect (version: {:?})",
+            anki_response.result
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ankiweb::backend::media_filename;
+    use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+    use mockito::Matcher;
+
+    #[tokio::test]
+    async fn test_read_only_refuses_changes() {
+        let mut server = mockito::Server::new_async().await;
+        let find = server
+            .mock("POST", "/")
+            .match_body(Matcher::PartialJson(json!({ "action": "findNotes" })))
+            .with_body(r#"{"result": [1], "error": null}"#)
+            .create_async()
+            .await;
+        let writes = server
+            .mock("POST", "/")
+            .match_body(Matcher::PartialJson(json!({ "action": "deleteNotes" })))
+            .expect(0)
+            .create_async()
+            .await;
 
-        debug!("Added note with ID: {}", note_id);
-        Ok(note_id)
+        let client = AnkiClient::new(server.url()).unwrap().with_read_only(true);
+        assert_eq!(client.find_notes("deck:Spanish").await.unwrap(), vec![1]);
+        assert!(matches!(
+            client.delete_notes(&[1]).await,
+            Err(AnkiDeckBuilderError::ReadOnly(action)) if action == "deleteNotes"
+        ));
+        find.assert_async().await;
+        writes.assert_async().await;
     }
 
-    /// Get list of all deck names
-    pub async fn get_decks(&self) -> Result<Vec<String>> {
-        debug!("Fetching deck names");
+    #[tokio::test]
+    async fn test_read_only_refuses_switching_profiles() {
+        let mut server = mockito::Server::new_async().await;
+        let load = server
+            .mock("POST", "/")
+            .match_body(Matcher::PartialJson(json!({ "action": "loadProfile" })))
+            .expect(0)
+            .create_async()
+            .await;
 
-        let request = AnkiRequest {
-            action: "deckNames".to_string(),
-            version: 6,
-            params: json!({}),
-        };
+        let client = AnkiClient::new(server.url()).unwrap().with_read_only(true);
+        assert!(matches!(
+            client.load_profile("Učenje").await,
+            Err(AnkiDeckBuilderError::ReadOnly(action)) if action == "loadProfile"
+        ));
+        load.assert_async().await;
+    }
 
-        let response = self
-            .client
-            .post(&self.base_url)
-            .json(&request)
-            .send()
-            .await
-            .map_err(AnkiDeckBuilderError::HttpError)?;
+    #[tokio::test]
+    async fn test_store_media_file() {
+        let mut server = mockito::Server::new_async().await;
+        let filename = media_filename("dan.mp3", b"audio");
 
-        let anki_response: AnkiResponse<Vec<String>> = response
-            .json()
-            .await
-            .map_err(AnkiDeckBuilderError::HttpError)?;
+        server
+            .mock("POST", "/")
+            .match_body(Matcher::PartialJson(
+                json!({ "action": "getMediaFilesNames" }),
+            ))
+            .with_body(r#"{"result": [], "error": null}"#)
+            .create_async()
+            .await;
+        let store = server
+            .mock("POST", "/")
+            .match_body(Matcher::PartialJson(json!({
+                "action": "storeMediaFile",
+                "params": { "filename": filename, "data": BASE64.encode(b"audio") },
+            })))
+            .with_body(format!(r#"{{"result": "{}", "error": null}}"#, filename))
+            .create_async()
+            .await;
 
-        if let Some(error) = anki_response.error {
-            return Err(AnkiDeckBuilderError::AnkiConnectError(error));
-        }
+        let client = AnkiClient::new(server.url()).unwrap();
+        assert_eq!(
+            client.store_media_file("dan.mp3", b"audio").await.unwrap(),
+            filename
+        );
+        store.assert_async().await;
+    }
 
-        anki_response.result.ok_or_else(|| {
-            AnkiDeckBuilderError::AnkiConnectError("No deck names returned".to_string())
-        })
+    #[tokio::test]
+    async fn test_store_media_file_skips_existing() {
+        let mut server = mockito::Server::new_async().await;
+        let filename = media_filename("dan.mp3", b"audio");
+
+        server
+            .mock("POST", "/")
+            .match_body(Matcher::PartialJson(
+                json!({ "action": "getMediaFilesNames" }),
+            ))
+            .with_body(format!(r#"{{"result": ["{}"], "error": null}}"#, filename))
+            .create_async()
+            .await;
+        let store = server
+            .mock("POST", "/")
+            .match_body(Matcher::PartialJson(json!({ "action": "storeMediaFile" })))
+            .expect(0)
+            .create_async()
+            .await;
+
+        let client = AnkiClient::new(server.url()).unwrap();
+        assert_eq!(
+            client.store_media_file("dan.mp3", b"audio").await.unwrap(),
+            filename
+        );
+        store.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_retrieve_media_file() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/")
+            .match_body(Matcher::PartialJson(
+                json!({ "params": { "filename": "dan.mp3" } }),
+            ))
+            .with_body(format!(
+                r#"{{"result": "{}", "error": null}}"#,
+                BASE64.encode(b"audio")
+            ))
+            .create_async()
+            .await;
+        server
+            .mock("POST", "/")
+            .match_body(Matcher::PartialJson(
+                json!({ "params": { "filename": "missing.mp3" } }),
+            ))
+            .with_body(r#"{"result": false, "error": null}"#)
+            .create_async()
+            .await;
+
+        let client = AnkiClient::new(server.url()).unwrap();
+        assert_eq!(
+            client.retrieve_media_file("dan.mp3").await.unwrap(),
+            Some(b"audio".to_vec())
+        );
+        assert_eq!(
+            client.retrieve_media_file("missing.mp3").await.unwrap(),
+            None
+        );
     }
 }
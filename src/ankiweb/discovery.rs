@@ -0,0 +1,144 @@
+use crate::ankiweb::backend::AnkiBackend;
+use crate::ankiweb::client::AnkiClient;
+use crate::error::{AnkiDeckBuilderError, Result};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+/// AnkiConnect's own port, then the ones it is most often moved to when
+/// something else holds 8765
+pub const COMMON_PORTS: &[u16] = &[8765, 8764, 8767, 8768];
+
+/// How long a probe waits for AnkiConnect to answer
+const PROBE_TIMEOUT: Duration = Duration::from_millis(800);
+
+/// Time between attempts while waiting for a launched Anki
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Where AnkiConnect might be: the configured URL first, then the common
+/// ports on this computer
+pub fn candidate_urls(configured: &str) -> Vec<String> {
+    let mut urls = vec![configured.to_string()];
+    for port in COMMON_PORTS {
+        let url = format!("http://localhost:{}", port);
+        if !urls.contains(&url) {
+            urls.push(url);
+        }
+    }
+    urls
+}
+
+/// The first URL AnkiConnect answers on, if any
+pub async fn find_ankiconnect(urls: &[String]) -> Option<String> {
+    for url in urls {
+        let Ok(client) = AnkiClient::with_timeout(url.clone(), PROBE_TIMEOUT) else {
+            continue;
+        };
+        if client.version().await.is_ok() {
+            debug!("AnkiConnect answers on {}", url);
+            return Some(url.clone());
+        }
+    }
+    None
+}
+
+/// Whether Anki itself is running, whatever AnkiConnect does. None when the
+/// platform's process listing (`tasklist`, `pgrep`) can't be used
+pub fn anki_process_running() -> Option<bool> {
+    if cfg!(target_os = "windows") {
+        let output = Command::new("tasklist")
+            .args(["/FI", "IMAGENAME eq anki.exe", "/NH"])
+            .output()
+            .ok()?;
+        let listing = String::from_utf8_lossy(&output.stdout).to_lowercase();
+        return Some(listing.contains("anki.exe"));
+    }
+
+    // The process is "anki" on Linux and "Anki" on macOS; -x keeps this
+    // tool, whose name also contains "anki", out of the matches
+    let status = Command::new("pgrep")
+        .args(["-x", "-i", "anki"])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .ok()?;
+    match status.code() {
+        Some(0) => Some(true),
+        Some(1) => Some(false),
+        _ => None,
+    }
+}
+
+/// Start Anki without waiting for it. Without a path, Anki is looked for
+/// where its installer puts it: the Applications folder on macOS, the user's
+/// programs folder on Windows, and the PATH elsewhere
+pub fn launch_anki(path: Option<&Path>) -> Result<()> {
+    let mut command = match path {
+        Some(path) if path.extension().is_some_and(|ext| ext == "app") => {
+            let mut command = Command::new("open");
+            command.arg("-a").arg(path);
+            command
+        }
+        Some(path) => Command::new(path),
+        None if cfg!(target_os = "macos") => {
+            let mut command = Command::new("open");
+            command.args(["-a", "Anki"]);
+            command
+        }
+        None if cfg!(target_os = "windows") => {
+            let local = std::env::var_os("LOCALAPPDATA").unwrap_or_default();
+            Command::new(Path::new(&local).join("Programs\\Anki\\anki.exe"))
+        }
+        None => Command::new("anki"),
+    };
+
+    debug!("Launching Anki: {:?}", command);
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| {
+            AnkiDeckBuilderError::ConfigurationError(format!(
+                "could not start Anki ({:?}): {}; give its path with --anki-path",
+                command.get_program(),
+                e
+            ))
+        })?;
+    Ok(())
+}
+
+/// Wait until AnkiConnect answers on one of `urls`, as it does once a
+/// starting Anki has loaded its add-ons, and return that URL
+pub async fn wait_for_ankiconnect(urls: &[String], timeout: Duration) -> Result<String> {
+    let started = Instant::now();
+    loop {
+        if let Some(url) = find_ankiconnect(urls).await {
+            return Ok(url);
+        }
+        if started.elapsed() >= timeout {
+            return Err(AnkiDeckBuilderError::AnkiConnectNotRunning {
+                url: urls.first().cloned().unwrap_or_default(),
+            });
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_candidate_urls() {
+        let urls = candidate_urls("http://localhost:8765");
+        assert_eq!(urls[0], "http://localhost:8765");
+        assert_eq!(urls.len(), COMMON_PORTS.len());
+        assert!(urls.contains(&"http://localhost:8764".to_string()));
+
+        let urls = candidate_urls("http://192.168.1.20:8765");
+        assert_eq!(urls[0], "http://192.168.1.20:8765");
+        assert_eq!(urls[1], "http://localhost:8765");
+    }
+}
@@ -0,0 +1,728 @@
+use crate::ankiweb::backend::AnkiBackend;
+use crate::error::{AnkiDeckBuilderError, Result};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+/// A note stored by the mock collection
+#[derive(Debug, Clone)]
+pub struct MockNote {
+    pub id: i64,
+    pub deck_name: String,
+    pub model_name: String,
+    pub fields: HashMap<String, String>,
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    decks: BTreeMap<String, i64>,
+    /// Deck name → description, for decks that have one
+    descriptions: HashMap<String, String>,
+    /// Options group ID → options group
+    deck_configs: BTreeMap<i64, Value>,
+    /// Deck name → options group ID, for decks not on the default group
+    deck_config_ids: HashMap<String, i64>,
+    /// Note type name → field names in order
+    models: BTreeMap<String, Vec<String>>,
+    notes: Vec<MockNote>,
+    suspended: HashSet<i64>,
+    /// Media file name → base64 content
+    media: BTreeMap<String, String>,
+    /// Action → error message returned instead of handling it, and how many
+    /// more times (forever if not given)
+    failures: HashMap<String, (String, Option<usize>)>,
+    delay: Option<Duration>,
+    /// Reported AnkiConnect API version
+    version: u32,
+    profiles: Vec<String>,
+    active_profile: String,
+    /// Number of times the collection was synced
+    syncs: usize,
+    /// Search the card browser was last opened on
+    browsed: Option<String>,
+    /// Action → number of requests for it
+    requests: HashMap<String, usize>,
+    next_id: i64,
+}
+
+/// In-memory stand-in for Anki with AnkiConnect, for tests that should not
+/// need a running Anki. Use it directly as an `AnkiBackend`, or serve it
+/// over HTTP with `MockAnkiServer` to exercise `AnkiClient` itself.
+///
+/// Each note has exactly one card, sharing the note's ID
+#[derive(Debug, Clone)]
+pub struct MockAnki {
+    state: Arc<Mutex<MockState>>,
+}
+
+impl Default for MockAnki {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockAnki {
+    /// Empty collection with the "Default" deck and options group, and
+    /// Anki's basic note types
+    pub fn new() -> Self {
+        let mut state = MockState {
+            version: 6,
+            profiles: vec!["User 1".to_string()],
+            active_profile: "User 1".to_string(),
+            next_id: 1,
+            ..Default::default()
+        };
+        state.decks.insert("Default".to_string(), 1);
+        state.deck_configs.insert(
+            1,
+            json!({
+                "id": 1,
+                "name": "Default",
+                "new": { "perDay": 20, "delays": [1.0, 10.0], "bury": false },
+                "rev": { "perDay": 200, "bury": false },
+                "maxTaken": 60,
+            }),
+        );
+        for name in ["Basic", "Basic (and reversed card)"] {
+            state
+                .models
+                .insert(name.to_string(), vec!["Front".into(), "Back".into()]);
+        }
+
+        Self {
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, MockState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Answer every request for `action` with an AnkiConnect error
+    pub fn fail_action(&self, action: &str, message: &str) {
+        self.state()
+            .failures
+            .insert(action.to_string(), (message.to_string(), None));
+    }
+
+    /// Answer the next `times` requests for `action` with an AnkiConnect
+    /// error, then handle it again
+    pub fn fail_action_times(&self, action: &str, message: &str, times: usize) {
+        self.state()
+            .failures
+            .insert(action.to_string(), (message.to_string(), Some(times)));
+    }
+
+    /// Wait this long before answering each request
+    pub fn set_delay(&self, delay: Duration) {
+        self.state().delay = Some(delay);
+    }
+
+    /// Report this AnkiConnect API version
+    pub fn set_version(&self, version: u32) {
+        self.state().version = version;
+    }
+
+    /// Add a profile that can be switched to. All profiles share one collection
+    pub fn add_profile(&self, name: &str) {
+        self.state().profiles.push(name.to_string());
+    }
+
+    /// Name of the open profile
+    pub fn active_profile(&self) -> String {
+        self.state().active_profile.clone()
+    }
+
+    /// All notes in the collection
+    pub fn notes(&self) -> Vec<MockNote> {
+        self.state().notes.clone()
+    }
+
+    /// Options group a deck uses
+    pub fn deck_config(&self, deck: &str) -> Value {
+        let state = self.state();
+        let id = state.deck_config_ids.get(deck).copied().unwrap_or(1);
+        state.deck_configs[&id].clone()
+    }
+
+    /// Description shown on a deck's overview screen, if it has one
+    pub fn deck_description(&self, deck: &str) -> Option<String> {
+        self.state().descriptions.get(deck).cloned()
+    }
+
+    /// Number of times the collection was synced with AnkiWeb
+    pub fn syncs(&self) -> usize {
+        self.state().syncs
+    }
+
+    /// Search the card browser was last opened on, if it was
+    pub fn browsed(&self) -> Option<String> {
+        self.state().browsed.clone()
+    }
+
+    /// Number of requests made for an action
+    pub fn requests(&self, action: &str) -> usize {
+        self.state().requests.get(action).copied().unwrap_or(0)
+    }
+
+    pub fn is_suspended(&self, card_id: i64) -> bool {
+        self.state().suspended.contains(&card_id)
+    }
+
+    fn delay(&self) -> Option<Duration> {
+        self.state().delay
+    }
+
+    /// Handle one AnkiConnect action, returning its result or error message
+    pub fn handle(&self, action: &str, params: &Value) -> std::result::Result<Value, String> {
+        let mut state = self.state();
+        *state.requests.entry(action.to_string()).or_default() += 1;
+
+        if let Some((message, times)) = state.failures.get_mut(action) {
+            let message = message.clone();
+            match times {
+                Some(0) => {}
+                Some(times) => {
+                    *times -= 1;
+                    return Err(message);
+                }
+                None => return Err(message),
+            }
+        }
+
+        match action {
+            "version" => Ok(json!(state.version)),
+            "getProfiles" => Ok(json!(state.profiles)),
+            "sync" => {
+                state.syncs += 1;
+                Ok(Value::Null)
+            }
+            "getActiveProfile" => Ok(json!(state.active_profile)),
+            "loadProfile" => {
+                let name = str_param(params, "name")?;
+                if !state.profiles.iter().any(|profile| profile == name) {
+                    return Ok(json!(false));
+                }
+                state.active_profile = name.to_string();
+                Ok(json!(true))
+            }
+            "deckNames" => Ok(json!(state.decks.keys().collect::<Vec<_>>())),
+            "createDeck" => {
+                let name = str_param(params, "deck")?;
+                let next_id = state.next_id;
+                let id = *state.decks.entry(name.to_string()).or_insert(next_id);
+                if id == next_id {
+                    state.next_id += 1;
+                }
+                Ok(json!(id))
+            }
+            "setDeckDescription" => {
+                let deck = str_param(params, "deck")?;
+                if !state.decks.contains_key(deck) {
+                    return Err(format!("deck was not found: {}", deck));
+                }
+                let description = str_param(params, "description")?.to_string();
+                state.descriptions.insert(deck.to_string(), description);
+                Ok(json!(true))
+            }
+            "getDeckConfig" => {
+                let deck = str_param(params, "deck")?;
+                if !state.decks.contains_key(deck) {
+                    return Ok(json!(false));
+                }
+                let id = state.deck_config_ids.get(deck).copied().unwrap_or(1);
+                Ok(state.deck_configs[&id].clone())
+            }
+            "saveDeckConfig" => {
+                let config = &params["config"];
+                let Some(id) = config["id"].as_i64() else {
+                    return Ok(json!(false));
+                };
+                match state.deck_configs.get_mut(&id) {
+                    Some(existing) => {
+                        *existing = config.clone();
+                        Ok(json!(true))
+                    }
+                    None => Ok(json!(false)),
+                }
+            }
+            "setDeckConfigId" => {
+                let decks: Vec<String> =
+                    serde_json::from_value(params["decks"].clone()).map_err(|e| e.to_string())?;
+                let id = params["configId"].as_i64().unwrap_or_default();
+                if !state.deck_configs.contains_key(&id)
+                    || decks.iter().any(|deck| !state.decks.contains_key(deck))
+                {
+                    return Ok(json!(false));
+                }
+                for deck in decks {
+                    state.deck_config_ids.insert(deck, id);
+                }
+                Ok(json!(true))
+            }
+            "cloneDeckConfigId" => {
+                let name = str_param(params, "name")?;
+                let from = params["cloneFrom"].as_i64().unwrap_or(1);
+                let Some(mut config) = state.deck_configs.get(&from).cloned() else {
+                    return Ok(json!(false));
+                };
+                let id = state.next_id;
+                state.next_id += 1;
+                config["id"] = json!(id);
+                config["name"] = json!(name);
+                state.deck_configs.insert(id, config);
+                Ok(json!(id))
+            }
+            "modelNames" => Ok(json!(state.models.keys().collect::<Vec<_>>())),
+            "modelFieldNames" => {
+                let name = str_param(params, "modelName")?;
+                let fields = state
+                    .models
+                    .get(name)
+                    .ok_or_else(|| format!("model was not found: {}", name))?;
+                Ok(json!(fields))
+            }
+            "createModel" => {
+                let name = str_param(params, "modelName")?;
+                if state.models.contains_key(name) {
+                    return Err(format!("Model name already exists: {}", name));
+                }
+                let fields = serde_json::from_value(params["inOrderFields"].clone())
+                    .map_err(|e| e.to_string())?;
+                state.models.insert(name.to_string(), fields);
+                Ok(json!({ "name": name }))
+            }
+            "addNote" => {
+                let note = parse_note(&params["note"])?;
+                state.check_can_add(&note, &params["note"])?;
+                let id = state.next_id;
+                state.next_id += 1;
+                state.notes.push(MockNote { id, ..note });
+                Ok(json!(id))
+            }
+            "canAddNotes" => {
+                let notes = params["notes"].as_array().cloned().unwrap_or_default();
+                let results: Vec<bool> = notes
+                    .iter()
+                    .map(|raw| {
+                        parse_note(raw)
+                            .and_then(|note| state.check_can_add(&note, raw))
+                            .is_ok()
+                    })
+                    .collect();
+                Ok(json!(results))
+            }
+            "canAddNotesWithErrorDetail" => {
+                let notes = params["notes"].as_array().cloned().unwrap_or_default();
+                let results: Vec<Value> = notes
+                    .iter()
+                    .map(|raw| {
+                        match parse_note(raw).and_then(|note| state.check_can_add(&note, raw)) {
+                            Ok(()) => json!({ "canAdd": true }),
+                            Err(error) => json!({ "canAdd": false, "error": error }),
+                        }
+                    })
+                    .collect();
+                Ok(json!(results))
+            }
+            "findNotes" | "findCards" => {
+                let query = str_param(params, "query")?;
+                let ids: Vec<i64> = state
+                    .notes
+                    .iter()
+                    .filter(|note| matches_query(note, query))
+                    .map(|note| note.id)
+                    .collect();
+                Ok(json!(ids))
+            }
+            "guiBrowse" => {
+                let query = str_param(params, "query")?;
+                let ids: Vec<i64> = state
+                    .notes
+                    .iter()
+                    .filter(|note| matches_query(note, query))
+                    .map(|note| note.id)
+                    .collect();
+                state.browsed = Some(query.to_string());
+                Ok(json!(ids))
+            }
+            "guiDeckBrowser" => Ok(Value::Null),
+            "notesInfo" => {
+                let ids: Vec<i64> =
+                    serde_json::from_value(params["notes"].clone()).map_err(|e| e.to_string())?;
+                let infos: Vec<Value> = ids
+                    .iter()
+                    .map(|id| match state.notes.iter().find(|note| note.id == *id) {
+                        Some(note) => state.note_info(note),
+                        None => json!({}),
+                    })
+                    .collect();
+                Ok(json!(infos))
+            }
+            "updateNoteFields" => {
+                let id = params["note"]["id"]
+                    .as_i64()
+                    .ok_or("missing parameter: note.id")?;
+                let fields: HashMap<String, String> =
+                    serde_json::from_value(params["note"]["fields"].clone())
+                        .map_err(|e| e.to_string())?;
+                let note = state
+                    .notes
+                    .iter_mut()
+                    .find(|note| note.id == id)
+                    .ok_or_else(|| format!("note was not found: {}", id))?;
+                for (name, value) in fields {
+                    if let Some(field) = note.fields.get_mut(&name) {
+                        *field = value;
+                    }
+                }
+                Ok(Value::Null)
+            }
+            "deleteNotes" => {
+                let ids: Vec<i64> =
+                    serde_json::from_value(params["notes"].clone()).map_err(|e| e.to_string())?;
+                state.notes.retain(|note| !ids.contains(&note.id));
+                state.suspended.retain(|card| !ids.contains(card));
+                Ok(Value::Null)
+            }
+            "removeTags" => {
+                let ids: Vec<i64> =
+                    serde_json::from_value(params["notes"].clone()).map_err(|e| e.to_string())?;
+                let tags: Vec<&str> = str_param(params, "tags")?.split_whitespace().collect();
+                for note in state.notes.iter_mut().filter(|note| ids.contains(&note.id)) {
+                    note.tags
+                        .retain(|tag| !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)));
+                }
+                Ok(Value::Null)
+            }
+            "suspend" | "unsuspend" => {
+                let cards: Vec<i64> =
+                    serde_json::from_value(params["cards"].clone()).map_err(|e| e.to_string())?;
+                let mut changed = false;
+                for card in cards {
+                    changed |= if action == "suspend" {
+                        state.suspended.insert(card)
+                    } else {
+                        state.suspended.remove(&card)
+                    };
+                }
+                Ok(json!(changed))
+            }
+            "storeMediaFile" => {
+                let filename = str_param(params, "filename")?;
+                let data = str_param(params, "data")?;
+                state.media.insert(filename.to_string(), data.to_string());
+                Ok(json!(filename))
+            }
+            // Not a real package: the deck's notes as JSON, enough to check
+            // that something was exported
+            "exportPackage" => {
+                let deck = str_param(params, "deck")?;
+                let path = str_param(params, "path")?;
+                if !state.decks.contains_key(deck) {
+                    return Ok(json!(false));
+                }
+                let notes: Vec<_> = state
+                    .notes
+                    .iter()
+                    .filter(|note| note.deck_name == deck)
+                    .map(|note| json!(note.fields))
+                    .collect();
+                std::fs::write(path, json!({ "deck": deck, "notes": notes }).to_string())
+                    .map_err(|e| e.to_string())?;
+                Ok(json!(true))
+            }
+            "retrieveMediaFile" => {
+                let filename = str_param(params, "filename")?;
+                Ok(match state.media.get(filename) {
+                    Some(data) => json!(data),
+                    None => json!(false),
+                })
+            }
+            "getMediaFilesNames" => {
+                let pattern = str_param(params, "pattern")?;
+                let names: Vec<&String> = state
+                    .media
+                    .keys()
+                    .filter(|name| glob_matches(pattern, name))
+                    .collect();
+                Ok(json!(names))
+            }
+            _ => Err(format!("unsupported action: {}", action)),
+        }
+    }
+}
+
+impl MockState {
+    /// A note in the shape `notesInfo` reports it, with fields ordered as
+    /// in its note type
+    fn note_info(&self, note: &MockNote) -> Value {
+        let order = self.models.get(&note.model_name);
+        let fields: serde_json::Map<String, Value> = note
+            .fields
+            .iter()
+            .map(|(name, value)| {
+                let position = order
+                    .and_then(|fields| fields.iter().position(|f| f == name))
+                    .unwrap_or(usize::MAX);
+                (name.clone(), json!({ "value": value, "order": position }))
+            })
+            .collect();
+
+        json!({
+            "noteId": note.id,
+            "modelName": note.model_name,
+            "tags": note.tags,
+            "fields": fields,
+        })
+    }
+
+    /// Anki rejects notes with an unknown deck or note type, an empty first
+    /// field, or a first field already used by a note of the same type
+    /// Anki's checks before adding a note, with the options of its `raw` form
+    fn check_can_add(&self, note: &MockNote, raw: &Value) -> std::result::Result<(), String> {
+        if !self.decks.contains_key(&note.deck_name) {
+            return Err(format!("deck was not found: {}", note.deck_name));
+        }
+        let fields = self
+            .models
+            .get(&note.model_name)
+            .ok_or_else(|| format!("model was not found: {}", note.model_name))?;
+
+        let first = note.fields.get(&fields[0]).cloned().unwrap_or_default();
+        if first.is_empty() {
+            return Err("cannot create note because it is empty".to_string());
+        }
+
+        let deck_scoped = raw["options"]["duplicateScope"].as_str() == Some("deck");
+        let duplicate = self.notes.iter().any(|other| {
+            other.model_name == note.model_name
+                && other.fields.get(&fields[0]) == Some(&first)
+                && (!deck_scoped || other.deck_name == note.deck_name)
+        });
+        if duplicate && !allow_duplicate(raw) {
+            return Err("cannot create note because it is a duplicate".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AnkiBackend for MockAnki {
+    async fn invoke_raw(&self, action: &str, params: Value) -> Result<Option<Value>> {
+        if let Some(delay) = self.delay() {
+            tokio::time::sleep(delay).await;
+        }
+        self.handle(action, &params)
+            .map(Some)
+            .map_err(AnkiDeckBuilderError::AnkiConnectError)
+    }
+}
+
+fn str_param<'a>(params: &'a Value, name: &str) -> std::result::Result<&'a str, String> {
+    params[name]
+        .as_str()
+        .ok_or_else(|| format!("missing parameter: {}", name))
+}
+
+fn parse_note(raw: &Value) -> std::result::Result<MockNote, String> {
+    Ok(MockNote {
+        id: 0,
+        deck_name: str_param(raw, "deckName")?.to_string(),
+        model_name: str_param(raw, "modelName")?.to_string(),
+        fields: serde_json::from_value(raw["fields"].clone()).map_err(|e| e.to_string())?,
+        tags: serde_json::from_value(raw["tags"].clone()).unwrap_or_default(),
+    })
+}
+
+fn allow_duplicate(raw: &Value) -> bool {
+    raw["options"]["allowDuplicate"].as_bool().unwrap_or(false)
+}
+
+/// Split a search into terms, keeping quoted parts together and removing
+/// quotes, backslash escapes and grouping parentheses
+fn search_terms(query: &str) -> Vec<String> {
+    let mut terms = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut chars = query.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => current.extend(chars.next()),
+            '"' => quoted = !quoted,
+            c if (c.is_whitespace() || c == '(' || c == ')') && !quoted => {
+                if !current.is_empty() {
+                    terms.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        terms.push(current);
+    }
+    terms
+}
+
+/// Supports the searches this crate builds: `deck:`, `tag:`, and `field:value`
+/// with `*` wildcards, and groups of them joined with `OR`
+fn matches_query(note: &MockNote, query: &str) -> bool {
+    let terms = search_terms(query);
+    terms
+        .split(|term| term == "OR")
+        .any(|group| matches_terms(note, group))
+}
+
+fn matches_terms(note: &MockNote, terms: &[String]) -> bool {
+    terms.iter().all(|term| {
+        let Some((key, value)) = term.split_once(':') else {
+            return note.fields.values().any(|field| field == term);
+        };
+
+        match key.to_lowercase().as_str() {
+            "deck" => {
+                note.deck_name == value || note.deck_name.starts_with(&format!("{}::", value))
+            }
+            "tag" => note.tags.iter().any(|tag| tag.eq_ignore_ascii_case(value)),
+            // Every note in the mock was added in this session
+            "added" => true,
+            _ => note
+                .fields
+                .iter()
+                .any(|(name, field)| name.eq_ignore_ascii_case(key) && glob_matches(value, field)),
+        }
+    })
+}
+
+/// Glob match supporting `*`
+fn glob_matches(pattern: &str, name: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == name,
+        Some((prefix, rest)) => {
+            let Some(remaining) = name.strip_prefix(prefix) else {
+                return false;
+            };
+            (0..=remaining.len())
+                .filter(|i| remaining.is_char_boundary(*i))
+                .any(|i| glob_matches(rest, &remaining[i..]))
+        }
+    }
+}
+
+/// Serves a `MockAnki` over HTTP on a local port, speaking just enough of
+/// the AnkiConnect protocol for `AnkiClient`. Stops when dropped
+pub struct MockAnkiServer {
+    url: String,
+    handle: JoinHandle<()>,
+}
+
+impl MockAnkiServer {
+    pub async fn start(anki: MockAnki) -> Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("http://{}", listener.local_addr()?);
+
+        let handle = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let anki = anki.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = serve_connection(stream, &anki).await {
+                        tracing::debug!("Mock AnkiConnect connection failed: {}", e);
+                    }
+                });
+            }
+        });
+
+        Ok(Self { url, handle })
+    }
+
+    /// URL to pass to `AnkiClient::new`
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+impl Drop for MockAnkiServer {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// Answer one HTTP request, then close the connection
+async fn serve_connection(mut stream: TcpStream, anki: &MockAnki) -> std::io::Result<()> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+
+    let header_end = loop {
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            return Ok(());
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+        if let Some(end) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+            break end + 4;
+        }
+    };
+
+    let headers = String::from_utf8_lossy(&buffer[..header_end]).to_lowercase();
+    let content_length = headers
+        .lines()
+        .find_map(|line| line.strip_prefix("content-length:"))
+        .and_then(|value| value.trim().parse::<usize>().ok())
+        .unwrap_or(0);
+
+    while buffer.len() < header_end + content_length {
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            break;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    }
+
+    let request: Value = serde_json::from_slice(&buffer[header_end..]).unwrap_or(Value::Null);
+    let action = request["action"].as_str().unwrap_or_default();
+
+    if let Some(delay) = anki.delay() {
+        tokio::time::sleep(delay).await;
+    }
+
+    let body = match anki.handle(action, &request["params"]) {
+        Ok(result) => json!({ "result": result, "error": null }),
+        Err(error) => json!({ "result": null, "error": error }),
+    }
+    .to_string();
+
+    let response = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_terms() {
+        assert_eq!(
+            search_terms(r#"deck:"My Deck" "Word:a\_b""#),
+            vec!["deck:My Deck", "Word:a_b"]
+        );
+    }
+
+    #[test]
+    fn test_glob_matches() {
+        assert!(glob_matches("dan_*.mp3", "dan_1234.mp3"));
+        assert!(glob_matches("dan.mp3", "dan.mp3"));
+        assert!(!glob_matches("dan_*.mp3", "dan_1234.jpg"));
+    }
+}
@@ -1,5 +1,24 @@
+pub mod backend;
+pub mod card_builder;
 pub mod client;
+pub mod discovery;
+pub mod mock;
 pub mod models;
+pub mod renderer;
+pub mod search;
 
-pub use client::AnkiClient;
-pub use models::{Note, NoteField};
+pub use backend::AnkiBackend;
+pub use card_builder::{combine_translations, CardBuilder};
+pub use client::{is_transient, AnkiClient, AnkiConnectSettings};
+pub use discovery::{
+    anki_process_running, candidate_urls, find_ankiconnect, launch_anki, wait_for_ankiconnect,
+};
+pub use mock::{MockAnki, MockAnkiServer};
+pub use models::{
+    BidirectionalStrategy, DeckConfig, DuplicatePolicy, DuplicateScope, Note, NoteField, NoteInfo,
+    NoteMapping, NoteModel, NoteRejection, SchedulingPreset, SortField,
+};
+pub use renderer::{CardFace, CardRenderer, RenderedFields};
+pub use search::{
+    added_today_query, any_query, deck_query, field_contains_query, field_query, tag_query,
+};
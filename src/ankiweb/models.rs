@@ -1,12 +1,196 @@
+use crate::ankiweb::renderer::headword;
+use crate::error::{AnkiDeckBuilderError, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+/// Name of the note type created when sorting by frequency rank
+pub const RANKED_MODEL_NAME: &str = "Anki Deck Builder (Ranked)";
+
+/// Ranked note type with an extra reversed card template
+pub const RANKED_REVERSED_MODEL_NAME: &str = "Anki Deck Builder (Ranked, and reversed card)";
+
+/// Anki's built-in note type that generates a card in each direction
+pub const BASIC_REVERSED_MODEL_NAME: &str = "Basic (and reversed card)";
+
+/// Field holding a picture when images are enabled
+pub const IMAGE_FIELD: &str = "Image";
+
+/// Name of the variant of a note type that carries an Image field
+pub fn image_model_name(base: &str) -> String {
+    format!("{} + Image", base)
+}
+
+/// Field holding the romanized target word for non-Latin scripts
+pub const READING_FIELD: &str = "Reading";
+
+/// Name of the variant of a note type that carries a Reading field
+pub fn reading_model_name(base: &str) -> String {
+    format!("{} + Reading", base)
+}
+
+/// Field holding a verb's present-tense conjugation
+pub const CONJUGATION_FIELD: &str = "Conjugation";
+
+/// Name of the variant of a note type that carries a Conjugation field
+pub fn conjugation_model_name(base: &str) -> String {
+    format!("{} + Conjugation", base)
+}
+
+/// Field holding a noun's genitive singular and nominative plural
+pub const DECLENSION_FIELD: &str = "Declension";
+
+/// Name of the variant of a note type that carries a Declension field
+pub fn declension_model_name(base: &str) -> String {
+    format!("{} + Declension", base)
+}
+
+/// Field listing a word's meanings besides the one on the card
+pub const OTHER_MEANINGS_FIELD: &str = "Other meanings";
+
+/// Name of the variant of a note type that carries an Other meanings field
+pub fn other_meanings_model_name(base: &str) -> String {
+    format!("{} + Other meanings", base)
+}
+
+/// Field holding a native speaker's recording of the target word
+pub const AUDIO_FIELD: &str = "Audio";
+
+/// Field crediting the speaker and license of the recording
+pub const SOURCE_FIELD: &str = "Source";
+
+/// Name of the variant of a note type that carries Audio and Source fields
+pub fn audio_model_name(base: &str) -> String {
+    format!("{} + Audio", base)
+}
+
+/// Field holding a memory hook for the target word
+pub const HINT_FIELD: &str = "Hint";
+
+/// Name of the variant of a note type that carries a Hint field
+pub fn hint_model_name(base: &str) -> String {
+    format!("{} + Hint", base)
+}
+
+/// Field telling where the target word comes from
+pub const ETYMOLOGY_FIELD: &str = "Etymology";
+
+/// Name of the variant of a note type that carries an Etymology field
+pub fn etymology_model_name(base: &str) -> String {
+    format!("{} + Etymology", base)
+}
+
+/// Field holding a sentence using the target word, with its translation
+pub const EXAMPLE_FIELD: &str = "Example";
+
+/// Name of the variant of a note type that carries an Example field
+pub fn example_model_name(base: &str) -> String {
+    format!("{} + Example", base)
+}
+
+/// How bidirectional cards are produced
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BidirectionalStrategy {
+    /// Two separate notes, one per direction
+    Notes,
+    /// One note whose note type has a reversed card template, so Anki
+    /// treats both cards as siblings
+    Templates,
+}
+
+/// What to do with words Anki already has notes for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicatePolicy {
+    /// Leave them out, before they are translated
+    Skip,
+    /// Add them again anyway
+    Allow,
+    /// Add them to a "Duplicates" subdeck, only checking for duplicates there
+    Deck,
+}
+
+/// Which notes Anki compares a new note against when checking for duplicates
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicateScope {
+    /// Every note of the same type, in any deck
+    #[default]
+    Collection,
+    /// Only notes in the same deck
+    Deck,
+}
+
+impl DuplicateScope {
+    /// Value of AnkiConnect's `duplicateScope` option
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Collection => "collection",
+            Self::Deck => "deck",
+        }
+    }
+}
+
+/// Why Anki would refuse a note
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NoteRejection {
+    /// Its first field matches a note of the same type
+    Duplicate,
+    /// Its first field, or the field duplicates are keyed on, is empty
+    Empty,
+    /// Anything else, such as a missing deck or note type
+    Other(String),
+}
+
+impl NoteRejection {
+    /// Classify an AnkiConnect error message
+    pub fn from_error(message: &str) -> Self {
+        if message.contains("duplicate") {
+            Self::Duplicate
+        } else if message.contains("empty") {
+            Self::Empty
+        } else {
+            Self::Other(message.to_string())
+        }
+    }
+}
+
+impl std::fmt::Display for NoteRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Duplicate => write!(f, "duplicate"),
+            Self::Empty => write!(f, "first field is empty"),
+            Self::Other(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+/// Field Anki uses to sort notes in the card browser
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortField {
+    /// Sort by the front of the card (Anki's "Basic" note type)
+    Front,
+    /// Sort by zero-padded frequency rank, i.e. learning order
+    Rank,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Note {
     pub deck_name: String,
     pub model_name: String,
     pub fields: HashMap<String, String>,
     pub tags: Vec<String>,
+    /// Add the note even when it duplicates an existing one
+    #[serde(default)]
+    pub allow_duplicate: bool,
+    /// Field identifying duplicates. None uses Anki's own check on the first
+    /// field; otherwise Anki's check is left off and the field is searched
+    #[serde(default)]
+    pub dedup_field: Option<String>,
+    /// Which notes count as duplicates of this one
+    #[serde(default)]
+    pub duplicate_scope: DuplicateScope,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -15,6 +199,230 @@ pub struct NoteField {
     pub back: String,
 }
 
+/// A card template within a note type
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CardTemplate {
+    pub name: String,
+    pub front: String,
+    pub back: String,
+}
+
+/// A note type (Anki "model") definition used with `createModel`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NoteModel {
+    pub name: String,
+    /// Field names in order. Anki sorts the browser by the first field
+    pub fields: Vec<String>,
+    pub templates: Vec<CardTemplate>,
+    pub css: String,
+    /// Field identifying duplicate notes
+    pub dedup_field: String,
+}
+
+impl NoteModel {
+    /// Definition matching Anki's built-in "Basic" note type
+    pub fn basic() -> Self {
+        Self {
+            name: "Basic".to_string(),
+            fields: vec!["Front".to_string(), "Back".to_string()],
+            templates: vec![CardTemplate {
+                name: "Card 1".to_string(),
+                front: "{{Front}}".to_string(),
+                back: "{{FrontSide}}<hr id=answer>{{Back}}".to_string(),
+            }],
+            css: ".card { font-family: arial; font-size: 20px; text-align: center; }".to_string(),
+            dedup_field: "Front".to_string(),
+        }
+    }
+
+    /// Definition matching Anki's built-in "Basic (and reversed card)" note type
+    pub fn basic_reversed() -> Self {
+        let mut model = Self::basic();
+        model.name = BASIC_REVERSED_MODEL_NAME.to_string();
+        model.templates.push(CardTemplate {
+            name: "Card 2".to_string(),
+            front: "{{Back}}".to_string(),
+            back: "{{FrontSide}}<hr id=answer>{{Front}}".to_string(),
+        });
+        model
+    }
+
+    /// Variant of this note type with an Image field shown on the answer side
+    pub fn with_image(mut self) -> Self {
+        self.name = image_model_name(&self.name);
+        self.fields.push(IMAGE_FIELD.to_string());
+        for template in &mut self.templates {
+            template.back.push_str("<br>{{Image}}");
+        }
+        self
+    }
+
+    /// Variant of this note type with a Reading field shown on the answer side
+    pub fn with_reading(mut self) -> Self {
+        self.name = reading_model_name(&self.name);
+        self.fields.push(READING_FIELD.to_string());
+        for template in &mut self.templates {
+            template.back.push_str("<br>{{Reading}}");
+        }
+        self
+    }
+
+    /// Variant of this note type with a Conjugation field shown on the
+    /// answer side when it is filled in
+    pub fn with_conjugation(mut self) -> Self {
+        self.name = conjugation_model_name(&self.name);
+        self.fields.push(CONJUGATION_FIELD.to_string());
+        for template in &mut self.templates {
+            template
+                .back
+                .push_str("{{#Conjugation}}<br>{{Conjugation}}{{/Conjugation}}");
+        }
+        self
+    }
+
+    /// Variant of this note type with a Declension field shown on the answer
+    /// side when it is filled in
+    pub fn with_declension(mut self) -> Self {
+        self.name = declension_model_name(&self.name);
+        self.fields.push(DECLENSION_FIELD.to_string());
+        for template in &mut self.templates {
+            template
+                .back
+                .push_str("{{#Declension}}<br>{{Declension}}{{/Declension}}");
+        }
+        self
+    }
+
+    /// Variant of this note type with an Other meanings field shown in
+    /// smaller print on the answer side when it is filled in
+    pub fn with_other_meanings(mut self) -> Self {
+        self.name = other_meanings_model_name(&self.name);
+        self.fields.push(OTHER_MEANINGS_FIELD.to_string());
+        for template in &mut self.templates {
+            template.back.push_str(
+                "{{#Other meanings}}<div class=\"other-meanings\" \
+                 style=\"font-size: 0.8em; color: #666\">{{Other meanings}}</div>\
+                 {{/Other meanings}}",
+            );
+        }
+        self
+    }
+
+    /// Variant of this note type with an Audio field played on the answer
+    /// side, and its Source credited below it, when they are filled in
+    pub fn with_audio(mut self) -> Self {
+        self.name = audio_model_name(&self.name);
+        self.fields.push(AUDIO_FIELD.to_string());
+        self.fields.push(SOURCE_FIELD.to_string());
+        for template in &mut self.templates {
+            template.back.push_str(
+                "{{#Audio}}<br>{{Audio}}{{/Audio}}\
+                 {{#Source}}<div class=\"source\" \
+                 style=\"font-size: 0.7em; color: #888\">{{Source}}</div>{{/Source}}",
+            );
+        }
+        self
+    }
+
+    /// Variant of this note type with a Hint field shown in italics on the
+    /// answer side when it is filled in
+    pub fn with_hint(mut self) -> Self {
+        self.name = hint_model_name(&self.name);
+        self.fields.push(HINT_FIELD.to_string());
+        for template in &mut self.templates {
+            template.back.push_str(
+                "{{#Hint}}<div class=\"hint\" style=\"font-style: italic\">{{Hint}}</div>{{/Hint}}",
+            );
+        }
+        self
+    }
+
+    /// Variant of this note type with an Etymology field shown in smaller
+    /// print on the answer side when it is filled in
+    pub fn with_etymology(mut self) -> Self {
+        self.name = etymology_model_name(&self.name);
+        self.fields.push(ETYMOLOGY_FIELD.to_string());
+        for template in &mut self.templates {
+            template.back.push_str(
+                "{{#Etymology}}<div class=\"etymology\" \
+                 style=\"font-size: 0.8em; color: #666\">{{Etymology}}</div>{{/Etymology}}",
+            );
+        }
+        self
+    }
+
+    /// Variant of this note type with an Example field shown in italics on
+    /// the answer side when it is filled in
+    pub fn with_example(mut self) -> Self {
+        self.name = example_model_name(&self.name);
+        self.fields.push(EXAMPLE_FIELD.to_string());
+        for template in &mut self.templates {
+            template.back.push_str(
+                "{{#Example}}<div class=\"example\" style=\"font-style: italic\">{{Example}}</div>{{/Example}}",
+            );
+        }
+        self
+    }
+
+    /// Front/Back note type with a leading "Rank" field used as the sort field
+    pub fn ranked() -> Self {
+        Self {
+            name: RANKED_MODEL_NAME.to_string(),
+            fields: vec![
+                "Rank".to_string(),
+                "Front".to_string(),
+                "Back".to_string(),
+                "Word".to_string(),
+            ],
+            templates: vec![CardTemplate {
+                name: "Card 1".to_string(),
+                front: "{{Front}}".to_string(),
+                back: "{{FrontSide}}<hr id=answer>{{Back}}".to_string(),
+            }],
+            css: ".card { font-family: arial; font-size: 20px; text-align: center; }".to_string(),
+            dedup_field: "Word".to_string(),
+        }
+    }
+
+    /// Ranked note type that also generates a Back→Front card
+    pub fn ranked_reversed() -> Self {
+        let mut model = Self::ranked();
+        model.name = RANKED_REVERSED_MODEL_NAME.to_string();
+        model.templates.push(CardTemplate {
+            name: "Card 2".to_string(),
+            front: "{{Back}}".to_string(),
+            back: "{{FrontSide}}<hr id=answer>{{Front}}".to_string(),
+        });
+        model
+    }
+
+    /// Every note type the deck builder may create, with and without images
+    /// and readings
+    pub fn builtin() -> Vec<Self> {
+        let models = [
+            Self::basic(),
+            Self::basic_reversed(),
+            Self::ranked(),
+            Self::ranked_reversed(),
+        ];
+        let with_images: Vec<Self> = models
+            .iter()
+            .cloned()
+            .chain(models.iter().cloned().map(Self::with_image))
+            .collect();
+        with_images
+            .iter()
+            .cloned()
+            .chain(with_images.iter().cloned().map(Self::with_reading))
+            .collect()
+    }
+}
+
+/// Zero-pad a frequency rank so it sorts correctly as text
+pub fn format_rank(rank: usize) -> String {
+    format!("{:05}", rank)
+}
+
 impl Note {
     pub fn new(deck_name: String, front: String, back: String) -> Self {
         let mut fields = HashMap::new();
@@ -29,6 +437,9 @@ impl Note {
                 "auto-generated".to_string(),
                 "language-learning".to_string(),
             ],
+            allow_duplicate: false,
+            dedup_field: None,
+            duplicate_scope: DuplicateScope::Collection,
         }
     }
 
@@ -36,4 +447,507 @@ impl Note {
         self.tags = tags;
         self
     }
+
+    /// Let Anki add the note even when it duplicates an existing one
+    pub fn with_allow_duplicate(mut self, allow: bool) -> Self {
+        self.allow_duplicate = allow;
+        self
+    }
+
+    pub fn with_duplicate_scope(mut self, scope: DuplicateScope) -> Self {
+        self.duplicate_scope = scope;
+        self
+    }
+
+    /// Use the ranked note type so the browser lists notes by frequency rank.
+    /// Anki checks duplicates against the first field, which is now the rank,
+    /// so duplicates are keyed on the plain Word field instead.
+    pub fn with_rank(mut self, rank: usize) -> Self {
+        let word = self.fields.get("Front").cloned().unwrap_or_default();
+        self.model_name = RANKED_MODEL_NAME.to_string();
+        self.fields.insert("Rank".to_string(), format_rank(rank));
+        self.fields.insert("Word".to_string(), word);
+        self.dedup_field = Some(NoteModel::ranked().dedup_field);
+        self
+    }
+
+    /// Switch to the matching note type with a reversed card template, so one
+    /// note produces cards in both directions
+    pub fn with_reversed_card(mut self) -> Self {
+        self.model_name = if self.model_name == RANKED_MODEL_NAME {
+            RANKED_REVERSED_MODEL_NAME.to_string()
+        } else {
+            BASIC_REVERSED_MODEL_NAME.to_string()
+        };
+        self
+    }
+
+    /// Key duplicate detection on the given field instead of Anki's first-field check
+    pub fn with_dedup_field(mut self, field: &str) -> Self {
+        self.dedup_field = Some(field.to_string());
+        self
+    }
+
+    /// Switch to the image variant of the note type, with the Image field
+    /// left empty until a picture is set
+    pub fn with_image_field(mut self) -> Self {
+        self.model_name = image_model_name(&self.model_name);
+        self.fields.insert(IMAGE_FIELD.to_string(), String::new());
+        self
+    }
+
+    /// Switch to the reading variant of the note type, with the given
+    /// romanization of the target word
+    pub fn with_reading_field(mut self, reading: &str) -> Self {
+        self.model_name = reading_model_name(&self.model_name);
+        self.fields
+            .insert(READING_FIELD.to_string(), reading.to_string());
+        self
+    }
+
+    /// Switch to the conjugation variant of the note type, with the given
+    /// conjugation (empty for words that aren't verbs)
+    pub fn with_conjugation_field(mut self, conjugation: &str) -> Self {
+        self.model_name = conjugation_model_name(&self.model_name);
+        self.fields
+            .insert(CONJUGATION_FIELD.to_string(), conjugation.to_string());
+        self
+    }
+
+    /// Switch to the declension variant of the note type, with the
+    /// Declension field left empty until forms are set
+    pub fn with_declension_field(mut self) -> Self {
+        self.model_name = declension_model_name(&self.model_name);
+        self.fields
+            .insert(DECLENSION_FIELD.to_string(), String::new());
+        self
+    }
+
+    /// Fill the Declension field
+    pub fn set_declension(&mut self, forms: String) {
+        self.fields.insert(DECLENSION_FIELD.to_string(), forms);
+    }
+
+    /// Switch to the other meanings variant of the note type, with the
+    /// Other meanings field left empty until they are set
+    pub fn with_other_meanings_field(mut self) -> Self {
+        self.model_name = other_meanings_model_name(&self.model_name);
+        self.fields
+            .insert(OTHER_MEANINGS_FIELD.to_string(), String::new());
+        self
+    }
+
+    /// Fill the Other meanings field
+    pub fn set_other_meanings(&mut self, meanings: String) {
+        self.fields
+            .insert(OTHER_MEANINGS_FIELD.to_string(), meanings);
+    }
+
+    /// Switch to the audio variant of the note type, with the Audio and
+    /// Source fields left empty until a recording is set
+    pub fn with_audio_field(mut self) -> Self {
+        self.model_name = audio_model_name(&self.model_name);
+        self.fields.insert(AUDIO_FIELD.to_string(), String::new());
+        self.fields.insert(SOURCE_FIELD.to_string(), String::new());
+        self
+    }
+
+    /// Fill the Audio field with a stored media file and credit its source
+    pub fn set_audio(&mut self, filename: &str, source: String) {
+        self.fields
+            .insert(AUDIO_FIELD.to_string(), format!("[sound:{}]", filename));
+        self.fields.insert(SOURCE_FIELD.to_string(), source);
+    }
+
+    /// Switch to the hint variant of the note type, with the Hint field left
+    /// empty until a memory hook is found
+    pub fn with_hint_field(mut self) -> Self {
+        self.model_name = hint_model_name(&self.model_name);
+        self.fields.insert(HINT_FIELD.to_string(), String::new());
+        self
+    }
+
+    /// Switch to the etymology variant of the note type, with the Etymology
+    /// field left empty until it is looked up
+    pub fn with_etymology_field(mut self) -> Self {
+        self.model_name = etymology_model_name(&self.model_name);
+        self.fields
+            .insert(ETYMOLOGY_FIELD.to_string(), String::new());
+        self
+    }
+
+    /// Switch to the example variant of the note type, with the Example
+    /// field left empty until a sentence is written for it
+    pub fn with_example_field(mut self) -> Self {
+        self.model_name = example_model_name(&self.model_name);
+        self.fields.insert(EXAMPLE_FIELD.to_string(), String::new());
+        self
+    }
+
+    /// Fill the Image field with HTML referencing a stored media file
+    pub fn set_image(&mut self, html: String) {
+        self.fields.insert(IMAGE_FIELD.to_string(), html);
+    }
+
+    /// Value of the field used for duplicate detection
+    pub fn dedup_value(&self) -> Option<&String> {
+        self.fields
+            .get(self.dedup_field.as_deref().unwrap_or("Front"))
+    }
+
+    /// The deck duplicates of this note are looked for in, None for the
+    /// whole collection
+    pub fn duplicate_deck(&self) -> Option<&str> {
+        match self.duplicate_scope {
+            DuplicateScope::Collection => None,
+            DuplicateScope::Deck => Some(&self.deck_name),
+        }
+    }
+}
+
+/// A note type already in the user's collection, and which of its fields
+/// receive the generated Front, Back, and Image content. Unmapped fields keep
+/// their own names
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NoteMapping {
+    pub model: String,
+    #[serde(default)]
+    pub fields: HashMap<String, String>,
+}
+
+impl NoteMapping {
+    pub fn new(model: &str) -> Self {
+        Self {
+            model: model.to_string(),
+            fields: HashMap::new(),
+        }
+    }
+
+    /// Add mappings written as "Front=Term", as given on the command line
+    pub fn with_field_map(mut self, entries: &[String]) -> Result<Self> {
+        for entry in entries {
+            let (from, to) = entry
+                .split_once('=')
+                .map(|(from, to)| (from.trim(), to.trim()))
+                .filter(|(from, to)| !from.is_empty() && !to.is_empty())
+                .ok_or_else(|| {
+                    AnkiDeckBuilderError::ConfigurationError(format!(
+                        "Invalid field mapping '{}', expected Field=ModelField",
+                        entry
+                    ))
+                })?;
+            self.fields.insert(from.to_string(), to.to_string());
+        }
+        Ok(self)
+    }
+
+    /// Name of the model field that receives a generated field
+    pub fn field<'a>(&'a self, name: &'a str) -> &'a str {
+        self.fields.get(name).map_or(name, String::as_str)
+    }
+
+    /// Move a generated note onto this note type
+    pub fn apply(&self, mut note: Note) -> Note {
+        note.model_name = self.model.clone();
+        note.fields = note
+            .fields
+            .into_iter()
+            .map(|(name, value)| (self.field(&name).to_string(), value))
+            .collect();
+        note.dedup_field = note.dedup_field.map(|f| self.field(&f).to_string());
+        note
+    }
+}
+
+/// Scheduling options bundled for new decks, each kept in its own options
+/// group so other decks are not affected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SchedulingPreset {
+    /// 10 new cards a day with extra learning steps
+    Gentle,
+    /// Anki's defaults: 20 new cards a day
+    Standard,
+    /// 40 new cards a day with short learning steps
+    Intense,
+}
+
+impl SchedulingPreset {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Gentle => "gentle",
+            Self::Standard => "standard",
+            Self::Intense => "intense",
+        }
+    }
+
+    /// Name of the options group holding this preset
+    pub fn group_name(&self) -> String {
+        format!("Anki Deck Builder ({})", self.name())
+    }
+
+    pub fn new_per_day(&self) -> u32 {
+        match self {
+            Self::Gentle => 10,
+            Self::Standard => 20,
+            Self::Intense => 40,
+        }
+    }
+
+    /// Learning steps in minutes
+    pub fn learning_steps(&self) -> Vec<f64> {
+        match self {
+            Self::Gentle => vec![1.0, 10.0, 60.0],
+            Self::Standard => vec![1.0, 10.0],
+            Self::Intense => vec![1.0, 5.0, 15.0],
+        }
+    }
+
+    pub fn reviews_per_day(&self) -> u32 {
+        match self {
+            Self::Gentle => 100,
+            Self::Standard => 200,
+            Self::Intense => 400,
+        }
+    }
+
+    /// Set the preset's options, leaving every other setting alone
+    pub fn apply(&self, config: &mut DeckConfig) {
+        config.new_cards.per_day = self.new_per_day();
+        config.new_cards.delays = self.learning_steps();
+        config.reviews.per_day = self.reviews_per_day();
+    }
+}
+
+/// An options group as reported by AnkiConnect's `getDeckConfig`. Only the
+/// settings presets change are typed; the rest is kept so that saving the
+/// group back leaves it as it was
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DeckConfig {
+    pub id: i64,
+    pub name: String,
+    #[serde(rename = "new", default)]
+    pub new_cards: NewCardConfig,
+    #[serde(rename = "rev", default)]
+    pub reviews: ReviewConfig,
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewCardConfig {
+    pub per_day: u32,
+    /// Learning steps in minutes
+    pub delays: Vec<f64>,
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReviewConfig {
+    pub per_day: u32,
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, serde_json::Value>,
+}
+
+/// A note as reported by AnkiConnect's `notesInfo`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NoteInfo {
+    pub note_id: i64,
+    pub model_name: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub fields: HashMap<String, NoteInfoField>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NoteInfoField {
+    pub value: String,
+    pub order: usize,
+}
+
+impl NoteInfo {
+    /// Plain text of the question side: the Front field, or the first field
+    /// of note types without one. Only the headword of a formatted field
+    /// counts, not its badge or hint
+    pub fn front(&self) -> Option<String> {
+        let field = self
+            .fields
+            .get("Front")
+            .or_else(|| self.fields.values().min_by_key(|f| f.order))?;
+        Some(headword(&field.value).unwrap_or_else(|| strip_html(&field.value)))
+    }
+}
+
+/// Field content without HTML tags and with common entities decoded
+pub fn strip_html(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&amp;", "&")
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_note_mapping() {
+        let mapping = NoteMapping::new("Vocab")
+            .with_field_map(&["Front=Term".to_string(), "Back = Meaning".to_string()])
+            .unwrap();
+        let note = mapping.apply(
+            Note::new("Deck".to_string(), "dan".to_string(), "día".to_string())
+                .with_dedup_field("Back"),
+        );
+
+        assert_eq!(note.model_name, "Vocab");
+        assert_eq!(note.fields.get("Term"), Some(&"dan".to_string()));
+        assert_eq!(note.fields.get("Meaning"), Some(&"día".to_string()));
+        assert_eq!(note.dedup_value(), Some(&"día".to_string()));
+        assert_eq!(mapping.field("Image"), "Image");
+
+        assert!(NoteMapping::new("Vocab")
+            .with_field_map(&["Front".to_string()])
+            .is_err());
+    }
+
+    #[test]
+    fn test_note_info_front() {
+        let info: NoteInfo = serde_json::from_value(serde_json::json!({
+            "noteId": 1,
+            "modelName": RANKED_MODEL_NAME,
+            "tags": [],
+            "fields": {
+                "Rank": { "value": "00001", "order": 0 },
+                "Front": { "value": "<b>dan</b>&nbsp;", "order": 1 },
+            },
+        }))
+        .unwrap();
+        assert_eq!(info.front(), Some("dan".to_string()));
+
+        let custom: NoteInfo = serde_json::from_value(serde_json::json!({
+            "noteId": 2,
+            "modelName": "Vocab",
+            "fields": {
+                "Meaning": { "value": "día", "order": 1 },
+                "Term": { "value": "dan", "order": 0 },
+            },
+        }))
+        .unwrap();
+        assert_eq!(custom.front(), Some("dan".to_string()));
+
+        let fields = crate::ankiweb::CardRenderer::default().render(&crate::ankiweb::CardFace {
+            prompt: "dan",
+            answer: "día",
+            pos: &crate::language::PartOfSpeech::Noun,
+            example: None,
+            hint: Some("d _ _"),
+            confusable: None,
+        });
+        let rendered: NoteInfo = serde_json::from_value(serde_json::json!({
+            "noteId": 3,
+            "modelName": "Basic",
+            "fields": {
+                "Front": { "value": fields.front, "order": 0 },
+                "Back": { "value": fields.back, "order": 1 },
+            },
+        }))
+        .unwrap();
+        assert_eq!(rendered.front(), Some("dan".to_string()));
+    }
+
+    #[test]
+    fn test_format_rank() {
+        assert_eq!(format_rank(7), "00007");
+        assert_eq!(format_rank(42000), "42000");
+        assert!(format_rank(9) < format_rank(10));
+    }
+
+    #[test]
+    fn test_with_rank() {
+        let note = Note::new("Deck".to_string(), "dan".to_string(), "día".to_string()).with_rank(3);
+
+        assert_eq!(note.model_name, RANKED_MODEL_NAME);
+        assert_eq!(note.fields.get("Rank"), Some(&"00003".to_string()));
+        assert_eq!(note.dedup_field.as_deref(), Some("Word"));
+        assert_eq!(note.dedup_value(), Some(&"dan".to_string()));
+        assert!(!note.allow_duplicate);
+    }
+
+    #[test]
+    fn test_with_reversed_card() {
+        let note = Note::new("Deck".to_string(), "dan".to_string(), "día".to_string());
+        assert_eq!(
+            note.clone().with_reversed_card().model_name,
+            BASIC_REVERSED_MODEL_NAME
+        );
+        assert_eq!(
+            note.with_rank(1).with_reversed_card().model_name,
+            RANKED_REVERSED_MODEL_NAME
+        );
+        assert_eq!(NoteModel::ranked_reversed().templates.len(), 2);
+    }
+
+    #[test]
+    fn test_image_variants() {
+        let model = NoteModel::ranked_reversed().with_image();
+        assert_eq!(model.name, image_model_name(RANKED_REVERSED_MODEL_NAME));
+        assert_eq!(model.fields.last().map(String::as_str), Some(IMAGE_FIELD));
+        assert!(model.templates.iter().all(|t| t.back.contains("{{Image}}")));
+
+        let note =
+            Note::new("Deck".to_string(), "dan".to_string(), "día".to_string()).with_image_field();
+        assert_eq!(note.model_name, NoteModel::basic().with_image().name);
+        assert_eq!(note.fields.get(IMAGE_FIELD), Some(&String::new()));
+    }
+
+    #[test]
+    fn test_audio_variants() {
+        let model = NoteModel::basic().with_audio();
+        assert_eq!(model.name, "Basic + Audio");
+        assert_eq!(model.fields[2..], [AUDIO_FIELD, SOURCE_FIELD]);
+
+        let mut note =
+            Note::new("Deck".to_string(), "dan".to_string(), "día".to_string()).with_audio_field();
+        assert_eq!(note.model_name, model.name);
+        note.set_audio("dan.mp3", "Forvo".to_string());
+        assert_eq!(note.fields[AUDIO_FIELD], "[sound:dan.mp3]");
+        assert_eq!(note.fields[SOURCE_FIELD], "Forvo");
+    }
+
+    #[test]
+    fn test_reading_variants() {
+        let model = NoteModel::basic().with_image().with_reading();
+        assert_eq!(model.name, "Basic + Image + Reading");
+        assert_eq!(model.fields.last().map(String::as_str), Some(READING_FIELD));
+
+        let note = Note::new("Deck".to_string(), "дом".to_string(), "casa".to_string())
+            .with_image_field()
+            .with_reading_field("dom");
+        assert_eq!(note.model_name, model.name);
+        assert_eq!(note.fields.get(READING_FIELD), Some(&"dom".to_string()));
+    }
+
+    #[test]
+    fn test_ranked_model_sorts_by_rank() {
+        let model = NoteModel::ranked();
+        assert_eq!(model.fields[0], "Rank");
+    }
 }
@@ -0,0 +1,248 @@
+use crate::ankiweb::models::strip_html;
+use crate::language::PartOfSpeech;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Line separator used within generated fields (see `combine_translations`)
+const LINE_BREAK: &str = "<br>";
+
+const HEADWORD_OPEN: &str = "<div class=\"headword\" style=\"font-size: 1.5em\"><b>";
+
+const BADGE_STYLE: &str = "display: inline-block; margin-top: 0.3em; padding: 0.1em 0.6em; \
+     border-radius: 0.8em; font-size: 0.7em; background: #e0e7ff; color: #3730a3";
+
+const EXAMPLE_STYLE: &str = "margin-top: 0.8em; font-style: italic; color: #555";
+
+const CONFUSABLE_STYLE: &str = "margin-top: 0.8em; font-size: 0.85em; color: #b45309";
+
+/// What goes on one card before it is formatted
+#[derive(Debug, Clone, Copy)]
+pub struct CardFace<'a> {
+    /// The side you see first; lines separated by `<br>`
+    pub prompt: &'a str,
+    /// The side you recall; lines separated by `<br>`
+    pub answer: &'a str,
+    pub pos: &'a PartOfSpeech,
+    /// Sentence using the word, shown with the answer
+    pub example: Option<&'a str>,
+    /// Nudge toward the answer, folded away on the question side
+    pub hint: Option<&'a str>,
+    /// Words easily mixed up with this one, shown under the answer
+    pub confusable: Option<&'a str>,
+}
+
+/// Front and Back field content of a card
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderedFields {
+    pub front: String,
+    pub back: String,
+}
+
+/// Turns card content into field HTML: a bold headword, a part-of-speech
+/// badge, a collapsible hint, and an example block under the answer. Text
+/// is escaped, so translations can't inject markup. With HTML turned off
+/// fields stay plain text, as they were before formatting existed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CardRenderer {
+    html: bool,
+    pos_badge: bool,
+}
+
+impl Default for CardRenderer {
+    fn default() -> Self {
+        Self {
+            html: true,
+            pos_badge: true,
+        }
+    }
+}
+
+impl CardRenderer {
+    /// Plain-text fields with no markup beyond the existing line breaks
+    pub fn plain() -> Self {
+        Self::default().with_html(false)
+    }
+
+    pub fn with_html(mut self, html: bool) -> Self {
+        self.html = html;
+        self
+    }
+
+    pub fn with_pos_badge(mut self, pos_badge: bool) -> Self {
+        self.pos_badge = pos_badge;
+        self
+    }
+
+    pub fn is_html(&self) -> bool {
+        self.html
+    }
+
+    pub fn render(&self, face: &CardFace) -> RenderedFields {
+        if !self.html {
+            // A hint on the question side would give it away in plain text
+            let mut back = face.answer.to_string();
+            if let Some(example) = face.example {
+                back.push_str(&format!("\n\n{}", example));
+            }
+            if let Some(confusable) = face.confusable {
+                back.push_str(&format!("\n\n{}", confusable_line(confusable)));
+            }
+            return RenderedFields {
+                front: face.prompt.to_string(),
+                back,
+            };
+        }
+
+        let mut front = format!("{}{}</b></div>", HEADWORD_OPEN, escape_lines(face.prompt));
+        if self.pos_badge {
+            front.push_str(&format!(
+                "<div><span class=\"pos\" style=\"{}\">{}</span></div>",
+                BADGE_STYLE,
+                face.pos.name()
+            ));
+        }
+        if let Some(hint) = face.hint {
+            front.push_str(&format!(
+                "<details class=\"hint\"><summary>Hint</summary>{}</details>",
+                escape(hint)
+            ));
+        }
+
+        let mut back = format!("<div class=\"answer\">{}</div>", escape_lines(face.answer));
+        if let Some(example) = face.example {
+            back.push_str(&format!(
+                "<div class=\"example\" style=\"{}\">{}</div>",
+                EXAMPLE_STYLE,
+                escape(example)
+            ));
+        }
+        if let Some(confusable) = face.confusable {
+            back.push_str(&format!(
+                "<div class=\"confusable\" style=\"{}\">{}</div>",
+                CONFUSABLE_STYLE,
+                escape(&confusable_line(confusable))
+            ));
+        }
+
+        RenderedFields { front, back }
+    }
+}
+
+/// Hint for recalling a word: its first letter and a blank per remaining
+/// letter ("dan" → "d _ _")
+pub fn letter_hint(word: &str) -> String {
+    let mut graphemes = word.graphemes(true);
+    let Some(first) = graphemes.next() else {
+        return String::new();
+    };
+    std::iter::once(first)
+        .chain(graphemes.map(|g| if g.trim().is_empty() { " " } else { "_" }))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// "≠ kuca, kuča": a reminder of the words not to mix an answer up with
+fn confusable_line(words: &str) -> String {
+    format!("≠ {}", words)
+}
+
+/// Plain text of the headword in a rendered Front field, or None when the
+/// field was not rendered as HTML
+pub fn headword(field: &str) -> Option<String> {
+    let start = field.find(HEADWORD_OPEN)? + HEADWORD_OPEN.len();
+    let end = field[start..].find("</b></div>")? + start;
+    Some(strip_html(&field[start..end].replace(LINE_BREAK, "\n")))
+}
+
+/// Escape each line of a `<br>`-separated field, keeping the breaks
+fn escape_lines(text: &str) -> String {
+    text.split(LINE_BREAK)
+        .map(escape)
+        .collect::<Vec<_>>()
+        .join(LINE_BREAK)
+}
+
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn face<'a>(prompt: &'a str, answer: &'a str) -> CardFace<'a> {
+        CardFace {
+            prompt,
+            answer,
+            pos: &PartOfSpeech::Noun,
+            example: None,
+            hint: None,
+            confusable: None,
+        }
+    }
+
+    #[test]
+    fn test_html_fields() {
+        let fields = CardRenderer::default().render(&CardFace {
+            example: Some("Dobar dan!"),
+            hint: Some("d _ _"),
+            ..face("dan", "es: día<br>en: <day> & night")
+        });
+
+        assert!(fields.front.contains("<b>dan</b>"));
+        assert!(fields.front.contains(">noun</span>"));
+        assert!(fields
+            .front
+            .contains("<details class=\"hint\"><summary>Hint</summary>d _ _</details>"));
+        assert!(fields
+            .back
+            .starts_with("<div class=\"answer\">es: día<br>en: &lt;day&gt; &amp; night</div>"));
+        assert!(fields.back.contains(">Dobar dan!</div>"));
+        assert!(!fields.back.contains("confusable"));
+        assert_eq!(headword(&fields.front), Some("dan".to_string()));
+
+        let no_badge = CardRenderer::default()
+            .with_pos_badge(false)
+            .render(&face("dan", "día"));
+        assert!(!no_badge.front.contains("class=\"pos\""));
+    }
+
+    #[test]
+    fn test_plain_fields() {
+        let fields = CardRenderer::plain().render(&CardFace {
+            example: Some("Dobar dan!"),
+            hint: Some("d _ _"),
+            ..face("dan", "día")
+        });
+
+        assert_eq!(fields.front, "dan");
+        assert_eq!(fields.back, "día\n\nDobar dan!");
+        assert_eq!(headword(&fields.front), None);
+    }
+
+    #[test]
+    fn test_confusable_line() {
+        let face = CardFace {
+            confusable: Some("kuca"),
+            ..face("kuća", "casa")
+        };
+
+        assert!(CardRenderer::default()
+            .render(&face)
+            .back
+            .ends_with(&format!(
+                "<div class=\"confusable\" style=\"{}\">≠ kuca</div>",
+                CONFUSABLE_STYLE
+            )));
+        assert_eq!(CardRenderer::plain().render(&face).back, "casa\n\n≠ kuca");
+    }
+
+    #[test]
+    fn test_letter_hint() {
+        assert_eq!(letter_hint("dan"), "d _ _");
+        assert_eq!(letter_hint("ćup"), "ć _ _");
+        assert_eq!(letter_hint(""), "");
+    }
+}
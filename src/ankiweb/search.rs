@@ -0,0 +1,118 @@
+/// Quote a value for use in an Anki search term (e.g. `deck:"My Deck"`)
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Build an Anki search query matching cards in a deck carrying all of the given tags
+pub fn deck_query(deck: &str, tags: &[String]) -> String {
+    let mut terms = vec![format!("deck:{}", quote(deck))];
+    terms.extend(tags.iter().map(|tag| format!("tag:{}", quote(tag))));
+    terms.join(" ")
+}
+
+/// Build an Anki search query matching notes with a tag, in any deck
+pub fn tag_query(tag: &str) -> String {
+    format!("tag:{}", quote(tag))
+}
+
+/// Build an Anki search query matching cards added to a deck (or its
+/// subdecks) today
+pub fn added_today_query(deck: &str) -> String {
+    format!("{} added:1", deck_query(deck, &[]))
+}
+
+/// Escape Anki search wildcards so a field value matches literally
+fn escape_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '\\' | '"' | '*' | '_' | ':') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Build an Anki search query matching notes whose field equals a value, in
+/// the deck if one is given and anywhere in the collection otherwise
+pub fn field_query(deck: Option<&str>, field: &str, value: &str) -> String {
+    let term = format!("\"{}:{}\"", escape_value(field), escape_value(value));
+    match deck {
+        Some(deck) => format!("deck:{} {}", quote(deck), term),
+        None => term,
+    }
+}
+
+/// Build an Anki search query matching what any of the given queries match
+pub fn any_query(queries: &[String]) -> String {
+    queries
+        .iter()
+        .map(|query| format!("({})", query))
+        .collect::<Vec<_>>()
+        .join(" OR ")
+}
+
+/// Build an Anki search query matching notes whose field contains a value
+/// anywhere, markup included
+pub fn field_contains_query(field: &str, value: &str) -> String {
+    format!("\"{}:*{}*\"", escape_value(field), escape_value(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deck_query_without_tags() {
+        assert_eq!(deck_query("Croatian", &[]), "deck:\"Croatian\"");
+    }
+
+    #[test]
+    fn test_deck_query_with_tags() {
+        let tags = vec!["pos:verb".to_string(), "auto-generated".to_string()];
+        assert_eq!(
+            deck_query("Croatian → Spanish", &tags),
+            "deck:\"Croatian → Spanish\" tag:\"pos:verb\" tag:\"auto-generated\""
+        );
+    }
+
+    #[test]
+    fn test_field_query() {
+        assert_eq!(
+            field_query(Some("Croatian"), "Word", "dan"),
+            "deck:\"Croatian\" \"Word:dan\""
+        );
+        assert_eq!(field_query(None, "Word", "dan"), "\"Word:dan\"");
+        assert_eq!(
+            field_query(Some("Croatian"), "Word", "a_b*"),
+            "deck:\"Croatian\" \"Word:a\\_b\\*\""
+        );
+    }
+
+    #[test]
+    fn test_any_query() {
+        let queries = [
+            field_query(Some("Croatian"), "Word", "dan"),
+            field_query(Some("Croatian"), "Word", "noć"),
+        ];
+        assert_eq!(
+            any_query(&queries),
+            "(deck:\"Croatian\" \"Word:dan\") OR (deck:\"Croatian\" \"Word:noć\")"
+        );
+    }
+
+    #[test]
+    fn test_field_contains_query() {
+        assert_eq!(field_contains_query("Front", "dan"), "\"Front:*dan*\"");
+    }
+
+    #[test]
+    fn test_added_today_query() {
+        assert_eq!(added_today_query("Croatian"), "deck:\"Croatian\" added:1");
+    }
+
+    #[test]
+    fn test_deck_query_escapes_quotes() {
+        assert_eq!(deck_query("My \"Deck\"", &[]), "deck:\"My \\\"Deck\\\"\"");
+    }
+}
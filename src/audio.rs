@@ -0,0 +1,783 @@
+//! Recordings of native speakers, from Forvo and from Wikimedia Commons
+//! (where Lingua Libre uploads its recordings), kept with their license
+
+use crate::cache_file;
+use crate::config::ServiceSettings;
+use crate::error::{AnkiDeckBuilderError, Result};
+use crate::http::HttpSettings;
+use async_trait::async_trait;
+use reqwest::{Client, Url};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const FORVO_API_URL: &str = "https://apifree.forvo.com";
+
+const COMMONS_API_URL: &str = "https://commons.wikimedia.org/w/api.php";
+
+/// Every pronunciation on Forvo is published under this license
+const FORVO_LICENSE: &str = "CC BY-NC-SA 3.0";
+
+/// Where a recording may come from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioProviderKind {
+    /// Forvo, when it has a key in FORVO_API_KEY or config.json
+    Forvo,
+    /// Wikimedia Commons, mostly Lingua Libre recordings
+    Commons,
+}
+
+/// Providers tried when none are given, in order
+pub const DEFAULT_AUDIO_PROVIDERS: &[AudioProviderKind] =
+    &[AudioProviderKind::Forvo, AudioProviderKind::Commons];
+
+/// Every audio provider there is, keyed or not, in the order they are
+/// tried, for telling which languages each one offers. Forvo without a key
+/// can't be asked anything
+pub fn known_audio_providers(services: &ServiceSettings) -> Result<Vec<Box<dyn AudioProvider>>> {
+    let forvo = match ForvoClient::from_env(services)? {
+        Some(forvo) => forvo,
+        None => ForvoClient::new("", &services.http)?,
+    };
+    Ok(vec![
+        Box::new(forvo),
+        Box::new(CommonsAudioClient::new(&services.http)?),
+    ])
+}
+
+/// A recording found for a word, and who to credit for it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AudioRecording {
+    /// Name of the provider it was found on
+    pub provider: String,
+    pub url: String,
+    pub speaker: Option<String>,
+    pub license: String,
+    /// Page describing the recording
+    pub source_url: Option<String>,
+}
+
+impl AudioRecording {
+    /// Credit line naming the speaker and license
+    pub fn attribution(&self) -> String {
+        match &self.speaker {
+            Some(speaker) => format!("{} on {} ({})", speaker, self.provider, self.license),
+            None => format!("{} ({})", self.provider, self.license),
+        }
+    }
+
+    /// Source field content: the attribution, linked to the recording's page
+    pub fn source_html(&self) -> String {
+        let credit = crate::images::html_escape(&self.attribution());
+        match &self.source_url {
+            Some(url) => format!(
+                "<a href=\"{}\">{}</a>",
+                crate::images::html_escape(url),
+                credit
+            ),
+            None => credit,
+        }
+    }
+
+    /// Extension of the audio file, mp3 when the URL doesn't tell
+    pub fn extension(&self) -> &str {
+        let path = self.url.split(['?', '#']).next().unwrap_or_default();
+        path.rsplit('/')
+            .next()
+            .and_then(|name| name.rsplit_once('.'))
+            .map(|(_, extension)| extension)
+            .filter(|extension| {
+                (1..=4).contains(&extension.len())
+                    && extension.chars().all(|c| c.is_ascii_alphanumeric())
+            })
+            .unwrap_or("mp3")
+    }
+}
+
+/// A downloaded recording
+#[derive(Debug, Clone)]
+pub struct Pronunciation {
+    pub recording: AudioRecording,
+    pub data: Vec<u8>,
+}
+
+impl Pronunciation {
+    /// Media file name for the word's recording
+    pub fn filename(&self, word: &str) -> String {
+        format!("{}.{}", word, self.recording.extension())
+    }
+}
+
+/// A service that finds recordings of words
+#[async_trait]
+pub trait AudioProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Whether the service may have recordings in the language
+    fn supports(&self, _language_code: &str) -> bool {
+        true
+    }
+
+    /// The best recording of a word, if the service has one
+    async fn find(&self, language_code: &str, word: &str) -> Result<Option<AudioRecording>>;
+}
+
+fn http_client(http: &HttpSettings) -> Result<Client> {
+    crate::http::client_builder(http)?
+        .timeout(Duration::from_secs(30))
+        .build()
+        .map_err(AnkiDeckBuilderError::HttpError)
+}
+
+/// Looks up the best-rated pronunciation on Forvo
+#[derive(Debug, Clone)]
+pub struct ForvoClient {
+    base_url: String,
+    api_key: String,
+    client: Client,
+}
+
+#[derive(Deserialize)]
+struct ForvoResponse {
+    #[serde(default)]
+    items: Vec<ForvoItem>,
+}
+
+#[derive(Deserialize)]
+struct ForvoItem {
+    username: Option<String>,
+    pathmp3: String,
+}
+
+impl ForvoClient {
+    pub fn new(api_key: &str, http: &HttpSettings) -> Result<Self> {
+        Self::with_base_url(FORVO_API_URL, api_key, http)
+    }
+
+    /// Use a different Forvo-compatible API server
+    pub fn with_base_url(base_url: &str, api_key: &str, http: &HttpSettings) -> Result<Self> {
+        Ok(Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key: api_key.to_string(),
+            client: http_client(http)?,
+        })
+    }
+
+    /// A client for the key in FORVO_API_KEY, or else the one from
+    /// config.json, if either is set
+    pub fn from_env(services: &ServiceSettings) -> Result<Option<Self>> {
+        let key = std::env::var("FORVO_API_KEY")
+            .ok()
+            .filter(|key| !key.trim().is_empty())
+            .or_else(|| services.forvo_api_key.clone());
+        match key {
+            Some(key) if !key.trim().is_empty() => Ok(Some(Self::new(key.trim(), &services.http)?)),
+            _ => Ok(None),
+        }
+    }
+
+    /// The key is part of the path, so requests are logged with it masked
+    fn url(&self, key: &str, language_code: &str, word: &str) -> Result<Url> {
+        let mut url = Url::parse(&self.base_url)
+            .map_err(|e| AnkiDeckBuilderError::AudioError(format!("Invalid Forvo URL: {}", e)))?;
+        url.path_segments_mut()
+            .map_err(|_| AnkiDeckBuilderError::AudioError("Invalid Forvo URL".to_string()))?
+            .pop_if_empty()
+            .extend([
+                "key",
+                key,
+                "format",
+                "json",
+                "action",
+                "word-pronunciations",
+                "word",
+                word,
+                "language",
+                language_code,
+                "order",
+                "rate-desc",
+                "limit",
+                "1",
+            ]);
+        Ok(url)
+    }
+}
+
+#[async_trait]
+impl AudioProvider for ForvoClient {
+    fn name(&self) -> &'static str {
+        "Forvo"
+    }
+
+    async fn find(&self, language_code: &str, word: &str) -> Result<Option<AudioRecording>> {
+        tracing::debug!("Looking up '{}' on Forvo", word);
+
+        let url = self.url(&self.api_key, language_code, word)?;
+        let logged_url = self.url("***", language_code, word)?.to_string();
+        crate::logging::http_request("GET", &logged_url, None);
+
+        let started = std::time::Instant::now();
+        let response = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .map_err(AnkiDeckBuilderError::HttpError)?;
+        crate::logging::http_response(
+            "GET",
+            &logged_url,
+            response.status().as_u16(),
+            started,
+            None,
+        );
+
+        if !response.status().is_success() {
+            return Err(AnkiDeckBuilderError::AudioError(format!(
+                "Forvo returned {}",
+                response.status()
+            )));
+        }
+
+        let found: ForvoResponse = response.json().await.map_err(|e| {
+            AnkiDeckBuilderError::AudioError(format!("Failed to parse Forvo response: {}", e))
+        })?;
+
+        let mut page = Url::parse("https://forvo.com/word/").expect("valid URL");
+        page.path_segments_mut()
+            .expect("base URL")
+            .pop_if_empty()
+            .push(word);
+        page.set_fragment(Some(language_code));
+
+        Ok(found.items.into_iter().next().map(|item| AudioRecording {
+            provider: self.name().to_string(),
+            url: item.pathmp3,
+            speaker: item.username,
+            license: FORVO_LICENSE.to_string(),
+            source_url: Some(page.to_string()),
+        }))
+    }
+}
+
+/// Searches Wikimedia Commons for Lingua Libre recordings, which are named
+/// "LL-Q<language item> (<ISO 639-3>)-<speaker>-<word>.wav"
+#[derive(Debug, Clone)]
+pub struct CommonsAudioClient {
+    base_url: String,
+    client: Client,
+}
+
+#[derive(Deserialize)]
+struct CommonsResponse {
+    #[serde(default)]
+    query: CommonsQuery,
+}
+
+#[derive(Deserialize, Default)]
+struct CommonsQuery {
+    #[serde(default)]
+    pages: Vec<CommonsPage>,
+}
+
+#[derive(Deserialize)]
+struct CommonsPage {
+    title: String,
+    /// Search rank, as pages come back unordered
+    #[serde(default = "last_index")]
+    index: u32,
+    #[serde(default)]
+    imageinfo: Vec<CommonsImageInfo>,
+}
+
+fn last_index() -> u32 {
+    u32::MAX
+}
+
+#[derive(Deserialize)]
+struct CommonsImageInfo {
+    url: String,
+    descriptionurl: Option<String>,
+    #[serde(default)]
+    extmetadata: CommonsMetadata,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase")]
+struct CommonsMetadata {
+    license_short_name: Option<CommonsValue>,
+    artist: Option<CommonsValue>,
+}
+
+#[derive(Deserialize)]
+struct CommonsValue {
+    value: String,
+}
+
+impl CommonsAudioClient {
+    pub fn new(http: &HttpSettings) -> Result<Self> {
+        Self::with_base_url(COMMONS_API_URL, http)
+    }
+
+    /// Use a different MediaWiki API endpoint
+    pub fn with_base_url(base_url: &str, http: &HttpSettings) -> Result<Self> {
+        Ok(Self {
+            base_url: base_url.to_string(),
+            client: http_client(http)?,
+        })
+    }
+}
+
+/// Whether a file title is a Lingua Libre recording of the word
+fn is_recording_of(title: &str, iso_code: &str, word: &str) -> bool {
+    let title = title.to_lowercase();
+    let name = title.strip_prefix("file:").unwrap_or(&title);
+    let stem = name.rsplit_once('.').map_or(name, |(stem, _)| stem);
+    name.starts_with("ll-")
+        && name.contains(&format!("({})-", iso_code))
+        && stem.ends_with(&format!("-{}", word.to_lowercase()))
+}
+
+/// Text of an HTML snippet, such as a linked user name
+fn strip_tags(html: &str) -> String {
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text.trim().to_string()
+}
+
+#[async_trait]
+impl AudioProvider for CommonsAudioClient {
+    fn name(&self) -> &'static str {
+        "Wikimedia Commons"
+    }
+
+    /// Lingua Libre names its recordings by ISO 639-3 code
+    fn supports(&self, language_code: &str) -> bool {
+        crate::language::leipzig::iso_639_3(language_code).is_some()
+    }
+
+    async fn find(&self, language_code: &str, word: &str) -> Result<Option<AudioRecording>> {
+        let Some(iso_code) = crate::language::leipzig::iso_639_3(language_code) else {
+            return Ok(None);
+        };
+        tracing::debug!("Looking up '{}' on Wikimedia Commons", word);
+
+        let search = format!("intitle:\"({})\" intitle:\"{}\"", iso_code, word);
+        let params = [
+            ("action", "query"),
+            ("format", "json"),
+            ("formatversion", "2"),
+            ("generator", "search"),
+            ("gsrnamespace", "6"),
+            ("gsrlimit", "20"),
+            ("gsrsearch", search.as_str()),
+            ("prop", "imageinfo"),
+            ("iiprop", "url|extmetadata"),
+            ("iiextmetadatafilter", "LicenseShortName|Artist"),
+        ];
+        let logged_url = crate::logging::url_with_query(&self.base_url, &params);
+        crate::logging::http_request("GET", &logged_url, None);
+
+        let started = std::time::Instant::now();
+        let response = self
+            .client
+            .get(&self.base_url)
+            .query(&params)
+            .send()
+            .await
+            .map_err(AnkiDeckBuilderError::HttpError)?;
+        crate::logging::http_response(
+            "GET",
+            &logged_url,
+            response.status().as_u16(),
+            started,
+            None,
+        );
+
+        if !response.status().is_success() {
+            return Err(AnkiDeckBuilderError::AudioError(format!(
+                "Wikimedia Commons returned {}",
+                response.status()
+            )));
+        }
+
+        let found: CommonsResponse = response.json().await.map_err(|e| {
+            AnkiDeckBuilderError::AudioError(format!(
+                "Failed to parse Wikimedia Commons response: {}",
+                e
+            ))
+        })?;
+
+        let mut pages = found.query.pages;
+        pages.sort_by_key(|page| page.index);
+        let recording = pages
+            .into_iter()
+            .filter(|page| is_recording_of(&page.title, iso_code, word))
+            .find_map(|page| {
+                let info = page.imageinfo.into_iter().next()?;
+                // A file without a stated license can't be credited properly
+                let license = info.extmetadata.license_short_name?.value;
+                Some(AudioRecording {
+                    provider: self.name().to_string(),
+                    url: info.url,
+                    speaker: info
+                        .extmetadata
+                        .artist
+                        .map(|artist| strip_tags(&artist.value))
+                        .filter(|artist| !artist.is_empty()),
+                    license,
+                    source_url: info.descriptionurl,
+                })
+            });
+        Ok(recording)
+    }
+}
+
+/// What the cache keeps next to a downloaded recording
+#[derive(Serialize, Deserialize)]
+struct AudioSidecar {
+    #[serde(flatten)]
+    recording: AudioRecording,
+    /// Name of the audio file beside it
+    file: String,
+    /// Seconds since the Unix epoch
+    fetched_at: u64,
+}
+
+/// Tries each provider in turn and caches what it downloads, with the
+/// license in a JSON file beside the audio
+pub struct AudioFetcher {
+    providers: Vec<Box<dyn AudioProvider>>,
+    client: Client,
+    cache_dir: Option<PathBuf>,
+}
+
+impl AudioFetcher {
+    /// Recordings are cached under `cache_dir/audio`
+    pub fn new(
+        providers: Vec<Box<dyn AudioProvider>>,
+        cache_dir: Option<PathBuf>,
+        http: &HttpSettings,
+    ) -> Result<Self> {
+        Ok(Self {
+            providers,
+            client: http_client(http)?,
+            cache_dir: cache_dir.map(|dir| dir.join("audio")),
+        })
+    }
+
+    /// The given providers, in order. Forvo is left out when it has no key
+    pub fn from_kinds(
+        kinds: &[AudioProviderKind],
+        cache_dir: Option<PathBuf>,
+        services: &ServiceSettings,
+    ) -> Result<Self> {
+        let mut providers: Vec<Box<dyn AudioProvider>> = Vec::new();
+        for kind in kinds {
+            match kind {
+                AudioProviderKind::Forvo => match ForvoClient::from_env(services)? {
+                    Some(forvo) => providers.push(Box::new(forvo)),
+                    None => tracing::debug!("No Forvo API key, skipping Forvo"),
+                },
+                AudioProviderKind::Commons => {
+                    providers.push(Box::new(CommonsAudioClient::new(&services.http)?))
+                }
+            }
+        }
+        Self::new(providers, cache_dir, &services.http)
+    }
+
+    /// Names of the providers, in the order they are tried
+    pub fn provider_names(&self) -> Vec<&'static str> {
+        self.providers.iter().map(|p| p.name()).collect()
+    }
+
+    /// A recording of the word from the first provider that has one
+    pub async fn fetch(&self, language_code: &str, word: &str) -> Result<Option<Pronunciation>> {
+        if let Some(cached) = self.cached(language_code, word) {
+            return Ok(Some(cached));
+        }
+
+        // A provider that fails is logged and the next one asked
+        for provider in &self.providers {
+            let recording = match provider.find(language_code, word).await {
+                Ok(Some(recording)) => recording,
+                Ok(None) => continue,
+                Err(e) => {
+                    tracing::warn!("{} has no recording of '{}': {}", provider.name(), word, e);
+                    continue;
+                }
+            };
+            match self.download(&recording).await {
+                Ok(data) => {
+                    let pronunciation = Pronunciation { recording, data };
+                    if let Err(e) = self.save(language_code, word, &pronunciation) {
+                        tracing::warn!("Could not cache the recording of '{}': {}", word, e);
+                    }
+                    return Ok(Some(pronunciation));
+                }
+                Err(e) => {
+                    tracing::warn!("Could not download {}: {}", recording.url, e);
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    async fn download(&self, recording: &AudioRecording) -> Result<Vec<u8>> {
+        crate::logging::http_request("GET", &recording.url, None);
+
+        let started = std::time::Instant::now();
+        let response = self
+            .client
+            .get(&recording.url)
+            .send()
+            .await
+            .map_err(AnkiDeckBuilderError::HttpError)?;
+        crate::logging::http_response(
+            "GET",
+            &recording.url,
+            response.status().as_u16(),
+            started,
+            None,
+        );
+        let response = response
+            .error_for_status()
+            .map_err(AnkiDeckBuilderError::HttpError)?;
+
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(AnkiDeckBuilderError::HttpError)?;
+        Ok(bytes.to_vec())
+    }
+
+    fn sidecar_path(&self, language_code: &str, word: &str) -> Option<PathBuf> {
+        let slug: String = word
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '_' })
+            .collect();
+        self.cache_dir
+            .as_ref()
+            .map(|dir| dir.join(language_code).join(format!("{}.json", slug)))
+    }
+
+    fn cached(&self, language_code: &str, word: &str) -> Option<Pronunciation> {
+        let path = self.sidecar_path(language_code, word)?;
+        let sidecar: AudioSidecar = cache_file::read_json(&path)?;
+        let data = std::fs::read(path.with_file_name(&sidecar.file)).ok()?;
+        Some(Pronunciation {
+            recording: sidecar.recording,
+            data,
+        })
+    }
+
+    fn save(&self, language_code: &str, word: &str, pronunciation: &Pronunciation) -> Result<()> {
+        let Some(path) = self.sidecar_path(language_code, word) else {
+            return Ok(());
+        };
+        // The sidecar goes last, so it never points at a half-written file
+        let file = audio_file_name(&path, pronunciation.recording.extension());
+        cache_file::write_atomic(&path.with_file_name(&file), &pronunciation.data)?;
+        let sidecar = AudioSidecar {
+            recording: pronunciation.recording.clone(),
+            file,
+            fetched_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+        };
+        cache_file::write_json(&path, &sidecar)
+    }
+}
+
+/// "dan.json" becomes "dan.mp3"
+fn audio_file_name(sidecar: &Path, extension: &str) -> String {
+    let stem = sidecar
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    format!("{}.{}", stem, extension)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::Matcher;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_attribution() {
+        let recording = AudioRecording {
+            provider: "Forvo".to_string(),
+            url: "https://example.com/audio/dan.mp3?x=1".to_string(),
+            speaker: Some("ana <hr>".to_string()),
+            license: FORVO_LICENSE.to_string(),
+            source_url: Some("https://forvo.com/word/dan/#hr".to_string()),
+        };
+        assert_eq!(recording.extension(), "mp3");
+        assert_eq!(
+            recording.source_html(),
+            "<a href=\"https://forvo.com/word/dan/#hr\">ana &lt;hr&gt; on Forvo (CC BY-NC-SA 3.0)</a>"
+        );
+
+        let recording = AudioRecording {
+            url: "https://example.com/audio".to_string(),
+            speaker: None,
+            source_url: None,
+            ..recording
+        };
+        assert_eq!(recording.extension(), "mp3");
+        assert_eq!(recording.source_html(), "Forvo (CC BY-NC-SA 3.0)");
+    }
+
+    #[tokio::test]
+    async fn test_providers() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock(
+                "GET",
+                "/key/secret/format/json/action/word-pronunciations/word/dan/language/hr/order/rate-desc/limit/1",
+            )
+            .with_body(r#"{"items": [{"username": "ana", "pathmp3": "https://example.com/dan.mp3"}]}"#)
+            .create_async()
+            .await;
+        let body = serde_json::json!({
+            "query": { "pages": [
+                {
+                    "title": "File:LL-Q6654 (hrv)-Ivo-danas.wav",
+                    "index": 1,
+                    "imageinfo": [{ "url": "https://example.com/danas.wav", "extmetadata": {
+                        "LicenseShortName": { "value": "CC BY-SA 4.0" },
+                    }}],
+                },
+                {
+                    "title": "File:LL-Q6654 (hrv)-Ana-dan.wav",
+                    "index": 2,
+                    "imageinfo": [{
+                        "url": "https://example.com/dan.wav",
+                        "descriptionurl": "https://commons.wikimedia.org/wiki/File:LL-Q6654_(hrv)-Ana-dan.wav",
+                        "extmetadata": {
+                            "LicenseShortName": { "value": "CC BY-SA 4.0" },
+                            "Artist": { "value": "<a href=\"//commons.wikimedia.org/wiki/User:Ana\">Ana</a>" },
+                        },
+                    }],
+                },
+            ]}
+        });
+        server
+            .mock("GET", "/w/api.php")
+            .match_query(Matcher::UrlEncoded("generator".into(), "search".into()))
+            .with_body(body.to_string())
+            .create_async()
+            .await;
+
+        let forvo =
+            ForvoClient::with_base_url(&server.url(), "secret", &HttpSettings::default()).unwrap();
+        let recording = forvo.find("hr", "dan").await.unwrap().unwrap();
+        assert_eq!(recording.speaker.as_deref(), Some("ana"));
+        assert_eq!(recording.license, FORVO_LICENSE);
+
+        let commons = CommonsAudioClient::with_base_url(
+            &format!("{}/w/api.php", server.url()),
+            &HttpSettings::default(),
+        )
+        .unwrap();
+        let recording = commons.find("hr", "dan").await.unwrap().unwrap();
+        assert_eq!(recording.url, "https://example.com/dan.wav");
+        assert_eq!(recording.extension(), "wav");
+        assert_eq!(
+            recording.attribution(),
+            "Ana on Wikimedia Commons (CC BY-SA 4.0)"
+        );
+        assert!(commons.find("xx", "dan").await.unwrap().is_none());
+    }
+
+    /// Finds the same recording of every word, counting lookups
+    struct StubProvider {
+        url: String,
+        lookups: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl AudioProvider for StubProvider {
+        fn name(&self) -> &'static str {
+            "Stub"
+        }
+
+        async fn find(&self, _language_code: &str, word: &str) -> Result<Option<AudioRecording>> {
+            self.lookups.fetch_add(1, Ordering::SeqCst);
+            if word == "missing" {
+                return Err(AnkiDeckBuilderError::AudioError("down".to_string()));
+            }
+            Ok(Some(AudioRecording {
+                provider: self.name().to_string(),
+                url: self.url.clone(),
+                speaker: None,
+                license: "CC0".to_string(),
+                source_url: None,
+            }))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetcher_falls_back_and_caches() {
+        let mut server = mockito::Server::new_async().await;
+        let download = server
+            .mock("GET", "/dan.ogg")
+            .with_body("ogg")
+            .expect(1)
+            .create_async()
+            .await;
+
+        let temp_dir = tempdir().unwrap();
+        let lookups = Arc::new(AtomicUsize::new(0));
+        let fetcher = AudioFetcher::new(
+            vec![
+                Box::new(
+                    CommonsAudioClient::with_base_url(
+                        "http://127.0.0.1:9",
+                        &HttpSettings::default(),
+                    )
+                    .unwrap(),
+                ),
+                Box::new(StubProvider {
+                    url: format!("{}/dan.ogg", server.url()),
+                    lookups: lookups.clone(),
+                }),
+            ],
+            Some(temp_dir.path().to_path_buf()),
+            &HttpSettings::default(),
+        )
+        .unwrap();
+
+        for _ in 0..2 {
+            let pronunciation = fetcher.fetch("hr", "dan").await.unwrap().unwrap();
+            assert_eq!(pronunciation.data, b"ogg");
+            assert_eq!(pronunciation.filename("dan"), "dan.ogg");
+            assert_eq!(pronunciation.recording.license, "CC0");
+        }
+        download.assert_async().await;
+        assert_eq!(lookups.load(Ordering::SeqCst), 1);
+
+        let sidecar: serde_json::Value = serde_json::from_str(
+            &std::fs::read_to_string(temp_dir.path().join("audio/hr/dan.json")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(sidecar["license"], "CC0");
+        assert_eq!(sidecar["file"], "dan.ogg");
+        assert!(temp_dir.path().join("audio/hr/dan.ogg").exists());
+
+        assert!(fetcher.fetch("hr", "missing").await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,1923 @@
+use crate::ankiweb::{
+    AnkiBackend, CardBuilder, DuplicatePolicy, DuplicateScope, Note, NoteRejection,
+    SchedulingPreset,
+};
+use crate::audio::{AudioFetcher, AudioProviderKind, DEFAULT_AUDIO_PROVIDERS};
+use crate::cancel::CancellationToken;
+use crate::config::ServiceSettings;
+use crate::deadline::Deadline;
+use crate::description::{render_description, suggested_new_per_day, DescriptionValues};
+use crate::enrich::{
+    validate_enricher_names, AudioEnricher, DeclensionEnricher, EnrichInput, EnrichmentPipeline,
+    EtymologyEnricher, ExampleEnricher, Fields, ImageEnricher, MnemonicEnricher,
+    OtherMeaningsEnricher,
+};
+use crate::error::{AnkiDeckBuilderError, Result};
+use crate::events::{BuildEvent, BuildOutcome, BuildPhase, BuildSummary};
+use crate::images::ImageClient;
+use crate::language::{
+    common_phrases, has_conjugation, has_declension, has_dictionary, has_starter_list,
+    has_transliteration, is_cognate, known_translators, load_frequency_data_from,
+    load_merged_frequency_data, resolve_weights, starter_words, Attribution, FrequencyData,
+    FrequencySourceKind, FrequencyThreshold, GeneratedWord, Glossary, GoogleTranslateClient,
+    LanguagePacks, LibreTranslateClient, ListSize, LlmClient, MnemonicFinder, MyMemoryClient,
+    PairSupport, PartOfSpeech, PostProcessor, Section, Selection, SourceWeight, TranslationChain,
+    Translator, TranslatorKind, WiktionaryClient, Word, WordFilter, DEFAULT_COGNATE_THRESHOLD,
+};
+use crate::manifest::WordStatus;
+use crate::pipeline::{translate_word, translate_words, TranslatedWord};
+use crate::report::FailurePhase;
+use crate::sink::{AnkiConnectSink, DeckSink};
+use futures::StreamExt;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// A frequency list to pick words from
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrequencyList {
+    pub source: FrequencySourceKind,
+    pub list_size: ListSize,
+    /// Weight of each source when `source` is merged
+    pub merge_weights: Vec<SourceWeight>,
+}
+
+impl Default for FrequencyList {
+    fn default() -> Self {
+        Self {
+            source: FrequencySourceKind::Opensubtitles,
+            list_size: ListSize::default(),
+            merge_weights: Vec::new(),
+        }
+    }
+}
+
+impl FrequencyList {
+    /// Credits of the sources a language's list comes from
+    pub fn attributions(&self, language_code: &str, packs: &LanguagePacks) -> Vec<Attribution> {
+        let sources: Vec<FrequencySourceKind> = match self.source {
+            FrequencySourceKind::Merged => {
+                let merged: Vec<_> = resolve_weights(&self.merge_weights)
+                    .into_iter()
+                    .map(|w| w.source)
+                    .filter(|source| source.build(packs).supports(language_code))
+                    .collect();
+                // Nothing to merge falls back on OpenSubtitles, as loading does
+                match merged.is_empty() {
+                    true => vec![FrequencySourceKind::Opensubtitles],
+                    false => merged,
+                }
+            }
+            kind => vec![kind],
+        };
+        sources
+            .iter()
+            .filter_map(|source| source.build(packs).attribution())
+            .collect()
+    }
+
+    /// Download the list for a language, or read it from the cache
+    pub async fn load(
+        &self,
+        language_code: &str,
+        cache_dir: &Path,
+        services: &ServiceSettings,
+        cancel: &CancellationToken,
+    ) -> Result<FrequencyData> {
+        match self.source {
+            FrequencySourceKind::Merged => {
+                load_merged_frequency_data(
+                    &self.merge_weights,
+                    language_code,
+                    self.list_size,
+                    cache_dir,
+                    services,
+                    cancel,
+                )
+                .await
+            }
+            kind => {
+                load_frequency_data_from(
+                    kind.build(&services.language_packs).as_ref(),
+                    language_code,
+                    self.list_size,
+                    cache_dir,
+                    services,
+                    cancel,
+                )
+                .await
+            }
+        }
+    }
+}
+
+/// Where the words of a deck come from
+#[derive(Debug, Clone)]
+pub enum WordSource {
+    /// The most frequent words of each part of speech
+    Top {
+        list: FrequencyList,
+        selection: Selection,
+        words_per_pos: usize,
+    },
+    /// Every word of the chosen parts of speech that clears a corpus
+    /// frequency threshold
+    Threshold {
+        list: FrequencyList,
+        threshold: FrequencyThreshold,
+    },
+    /// The `count` words ranked after `after_rank`, whatever their part of speech
+    After {
+        list: FrequencyList,
+        after_rank: usize,
+        count: usize,
+    },
+    /// These words, as they are
+    Words(Vec<Word>),
+    /// The hand-picked essentials of the target language, phrases included
+    Starter,
+}
+
+impl Default for WordSource {
+    fn default() -> Self {
+        Self::Top {
+            list: FrequencyList::default(),
+            selection: Selection::Equal,
+            words_per_pos: 100,
+        }
+    }
+}
+
+/// A word as a build would add it, for a dry run to show
+#[derive(Debug, Clone, Serialize)]
+pub struct PreviewCard {
+    pub word: String,
+    pub pos: PartOfSpeech,
+    pub rank: usize,
+    pub translation: String,
+    /// Why the word would be held back, or why it could not be translated
+    pub issue: Option<String>,
+    /// Enrichment fields are still empty; they are looked up during the build
+    pub notes: Vec<Note>,
+}
+
+/// Extra fields looked up for each word
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Enrichments {
+    /// A picture on noun cards
+    pub images: bool,
+    /// Romanized reading for non-Latin scripts
+    pub readings: bool,
+    pub conjugations: bool,
+    pub declensions: bool,
+    /// Other meanings from Wiktionary
+    pub senses: bool,
+    /// A native speaker's recording of the target word
+    pub audio: bool,
+    /// A sound-alike word of the base language as a memory hook
+    pub mnemonics: bool,
+    /// Where the word comes from, from Wiktionary
+    pub etymology: bool,
+    /// A sentence using the word, written by the LLM
+    pub examples: bool,
+}
+
+impl Enrichments {
+    /// The ones that have data for a language
+    pub fn available_for(self, language_code: &str, packs: &LanguagePacks) -> Self {
+        Self {
+            images: self.images,
+            readings: self.readings && has_transliteration(language_code),
+            conjugations: self.conjugations && has_conjugation(language_code),
+            declensions: self.declensions && has_declension(language_code),
+            senses: self.senses && has_dictionary(language_code, packs),
+            audio: self.audio,
+            mnemonics: self.mnemonics,
+            etymology: self.etymology && has_dictionary(language_code, packs),
+            examples: self.examples,
+        }
+    }
+}
+
+/// Everything that decides what deck gets built, without any of the
+/// command line around it
+#[derive(Debug, Clone)]
+pub struct DeckSpec {
+    pub deck_name: String,
+    pub target_language: String,
+    /// Languages shown on the cards; the first is the main one
+    pub base_languages: Vec<String>,
+    pub words: WordSource,
+    /// Common phrases added to a `Phrases` subdeck, on top of the words
+    pub phrases: usize,
+    /// Generated vocabulary added in subdecks of its own, on top of the words
+    pub sections: Vec<Section>,
+    /// Applied to words picked from a frequency list
+    pub filter: WordFilter,
+    /// Parts of speech words are picked from a frequency list for
+    pub parts_of_speech: Vec<PartOfSpeech>,
+    pub enrichments: Enrichments,
+    /// Where recordings are looked for, in order, when audio is enabled
+    pub audio_providers: Vec<AudioProviderKind>,
+    /// Enrichers run first, in this order (see [`crate::enrich::ENRICHER_NAMES`])
+    pub enrichment_order: Vec<String>,
+    pub bidirectional: bool,
+    pub preset: Option<SchedulingPreset>,
+    /// Skip words spelled like their translation, at this similarity
+    pub skip_cognates: Option<f64>,
+    pub back_translate: bool,
+    /// Translator asked first
+    pub translator: TranslatorKind,
+    /// LibreTranslate server to fall back on
+    pub libretranslate_url: Option<String>,
+    pub rules: PostProcessor,
+    /// Your own translations, taken before asking any provider
+    pub glossary: Glossary,
+    /// Words translated at the same time
+    pub concurrency: usize,
+    /// Credits of an earlier build of the deck, kept alongside those of its
+    /// frequency list
+    pub attributions: Vec<Attribution>,
+    /// Template of the deck description (see [`crate::description`]); the
+    /// built-in one in the base language when None, and an empty one leaves
+    /// the description as it is
+    pub description_template: Option<String>,
+    /// Name the words a confusable word is easily mixed up with on its cards
+    pub confusable_hints: bool,
+    /// Translations taken as they are, as a rebuild takes those of its
+    /// manifest
+    pub translations: BTreeMap<String, String>,
+}
+
+impl DeckSpec {
+    /// A deck of the 100 most frequent words of each part of speech, from
+    /// languages given by name or code, built in or added by `packs`
+    pub fn new(
+        deck_name: &str,
+        target_language: &str,
+        base_language: &str,
+        packs: &LanguagePacks,
+    ) -> Result<Self> {
+        Ok(Self {
+            deck_name: deck_name.to_string(),
+            target_language: language_code(target_language, packs)?,
+            base_languages: vec![language_code(base_language, packs)?],
+            words: WordSource::default(),
+            phrases: 0,
+            sections: Vec::new(),
+            filter: WordFilter::new(),
+            parts_of_speech: PartOfSpeech::all(),
+            enrichments: Enrichments::default(),
+            audio_providers: DEFAULT_AUDIO_PROVIDERS.to_vec(),
+            enrichment_order: Vec::new(),
+            bidirectional: true,
+            preset: None,
+            skip_cognates: None,
+            back_translate: false,
+            translator: TranslatorKind::default(),
+            libretranslate_url: None,
+            rules: PostProcessor::default(),
+            glossary: Glossary::default(),
+            concurrency: 4,
+            attributions: Vec::new(),
+            description_template: None,
+            confusable_hints: false,
+            translations: BTreeMap::new(),
+        })
+    }
+
+    /// More languages shown after the main one
+    pub fn with_extra_base_languages(
+        mut self,
+        languages: &[String],
+        packs: &LanguagePacks,
+    ) -> Result<Self> {
+        for language in languages {
+            self.base_languages.push(language_code(language, packs)?);
+        }
+        Ok(self)
+    }
+
+    pub fn with_words(mut self, words: WordSource) -> Self {
+        self.words = words;
+        self
+    }
+
+    /// Pick words for only these parts of speech, all of them when empty
+    pub fn with_parts_of_speech(mut self, parts_of_speech: Vec<PartOfSpeech>) -> Self {
+        self.parts_of_speech = parts_of_speech;
+        self
+    }
+
+    pub fn with_phrases(mut self, phrases: usize) -> Self {
+        self.phrases = phrases;
+        self
+    }
+
+    pub fn with_sections(mut self, sections: Vec<Section>) -> Self {
+        self.sections = sections;
+        self
+    }
+
+    pub fn with_filter(mut self, filter: WordFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    pub fn with_enrichments(mut self, enrichments: Enrichments) -> Self {
+        self.enrichments = enrichments;
+        self
+    }
+
+    pub fn with_audio_providers(mut self, providers: Vec<AudioProviderKind>) -> Self {
+        self.audio_providers = providers;
+        self
+    }
+
+    pub fn with_enrichment_order(mut self, names: &[String]) -> Result<Self> {
+        validate_enricher_names(names)?;
+        self.enrichment_order = names.to_vec();
+        Ok(self)
+    }
+
+    pub fn with_bidirectional(mut self, bidirectional: bool) -> Self {
+        self.bidirectional = bidirectional;
+        self
+    }
+
+    pub fn with_preset(mut self, preset: Option<SchedulingPreset>) -> Self {
+        self.preset = preset;
+        self
+    }
+
+    pub fn with_skip_cognates(mut self, threshold: Option<f64>) -> Self {
+        self.skip_cognates = threshold;
+        self
+    }
+
+    pub fn with_back_translation(mut self, back_translate: bool) -> Self {
+        self.back_translate = back_translate;
+        self
+    }
+
+    pub fn with_translator(mut self, translator: TranslatorKind) -> Self {
+        self.translator = translator;
+        self
+    }
+
+    pub fn with_libretranslate_url(mut self, url: Option<String>) -> Self {
+        self.libretranslate_url = url;
+        self
+    }
+
+    pub fn with_description_template(mut self, template: Option<String>) -> Self {
+        self.description_template = template;
+        self
+    }
+
+    pub fn with_confusable_hints(mut self, hints: bool) -> Self {
+        self.confusable_hints = hints;
+        self
+    }
+
+    pub fn with_rules(mut self, rules: PostProcessor) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    pub fn with_glossary(mut self, glossary: Glossary) -> Self {
+        self.glossary = glossary;
+        self
+    }
+
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency;
+        self
+    }
+
+    pub fn with_translations(mut self, translations: BTreeMap<String, String>) -> Self {
+        self.translations = translations;
+        self
+    }
+
+    pub fn with_attributions(mut self, attributions: Vec<Attribution>) -> Self {
+        self.attributions = attributions;
+        self
+    }
+
+    /// Subdeck the notes of words Anki already has go to with
+    /// [`DuplicatePolicy::Deck`]
+    pub fn duplicate_deck(&self) -> String {
+        format!("{}::Duplicates", self.deck_name)
+    }
+
+    pub fn base_language(&self) -> &str {
+        &self.base_languages[0]
+    }
+}
+
+fn language_code(input: &str, packs: &LanguagePacks) -> Result<String> {
+    packs
+        .language(input)
+        .map(|language| language.code)
+        .ok_or_else(|| AnkiDeckBuilderError::UnsupportedLanguage(input.to_string()))
+}
+
+/// The translators a build uses, caching in `cache_dir`: the chosen one
+/// first, then MyMemory, and LibreTranslate as the fallback when their
+/// answers look wrong
+pub fn translation_chain(
+    cache_dir: &Path,
+    postprocessor: PostProcessor,
+    translator: TranslatorKind,
+    libretranslate_url: Option<String>,
+    services: &ServiceSettings,
+) -> Result<TranslationChain> {
+    let http = &services.http;
+    let postprocessor = postprocessor.with_language_packs(services.language_packs.clone());
+    let mut providers: Vec<Box<dyn Translator>> = Vec::new();
+    match translator {
+        TranslatorKind::Mymemory => {}
+        TranslatorKind::Google => {
+            tracing::warn!(
+                "Google Translate is asked through its unofficial free endpoint, a second between requests; it may change or block requests without notice"
+            );
+            providers.push(Box::new(
+                GoogleTranslateClient::new(Some(cache_dir.to_path_buf()), http)?
+                    .with_postprocessor(postprocessor.clone()),
+            ));
+        }
+        TranslatorKind::Llm => providers.push(Box::new(
+            LlmClient::new(services, Some(cache_dir.to_path_buf()))?
+                .with_postprocessor(postprocessor.clone()),
+        )),
+    }
+    providers.push(Box::new(
+        MyMemoryClient::new(Some(cache_dir.to_path_buf()), http)?
+            .with_postprocessor(postprocessor.clone()),
+    ));
+    if let Some(url) = libretranslate_url.or_else(|| std::env::var("LIBRETRANSLATE_URL").ok()) {
+        providers.push(Box::new(
+            LibreTranslateClient::new(url, Some(cache_dir.to_path_buf()), http)?
+                .with_postprocessor(postprocessor),
+        ));
+    }
+    Ok(TranslationChain::new(providers).with_alternatives(known_translators(services)?))
+}
+
+type EventHandler = Box<dyn Fn(BuildEvent) + Send + Sync>;
+
+/// What a front end decides and keeps track of while [`DeckBuilder::build`]
+/// runs. Every step has a default, which is what an unattended build does
+pub trait BuildHooks: Send + Sync {
+    /// The deck was made ready; `deck_id` is None when it already existed
+    fn deck_prepared(&mut self, _deck_id: Option<i64>) {}
+
+    /// What to do with `duplicates`, words Anki already has notes for.
+    /// `refused` are words it would take no note for at all, which are left
+    /// out either way. Asked before anything is translated, when either is
+    /// not empty
+    fn duplicate_policy(
+        &mut self,
+        _duplicates: &[String],
+        _refused: &[String],
+    ) -> Result<DuplicatePolicy> {
+        Ok(DuplicatePolicy::Skip)
+    }
+
+    /// Last look at the words about to be added, of which `to_translate`
+    /// have no pinned translation and go to `translator`; false stops the
+    /// build before anything is translated
+    fn confirm(
+        &mut self,
+        _words: &[Word],
+        _to_translate: &[Word],
+        _translator: &TranslationChain,
+    ) -> Result<bool> {
+        Ok(true)
+    }
+
+    /// The translation to add the word at `position` (1-based) with, or None
+    /// to leave it out. Translations no provider vouched for are left out
+    fn review(&mut self, _position: usize, translated: &TranslatedWord) -> Result<Option<String>> {
+        Ok(translated
+            .issue
+            .is_none()
+            .then(|| translated.translation.clone()))
+    }
+
+    /// A note of `word` was added, with its ID when the sink gives one
+    fn note_added(&mut self, _word: &Word, _note_id: Option<i64>) {}
+
+    /// `word` wasn't added, or the build never got to it
+    fn failed(
+        &mut self,
+        _word: &Word,
+        _translation: Option<&str>,
+        _phase: FailurePhase,
+        _reason: &str,
+    ) {
+    }
+
+    /// Every note of `word` was handled
+    fn word_done(&mut self, _word: &Word, _translation: &str, _status: WordStatus) {}
+
+    /// The build ended; `output` is the file the sink wrote, if any
+    fn finished(&mut self, _summary: &BuildSummary, _output: Option<&Path>) {}
+}
+
+/// The hooks of a build that has none
+struct Unattended;
+
+impl BuildHooks for Unattended {}
+
+impl<H: BuildHooks + ?Sized> BuildHooks for &mut H {
+    fn deck_prepared(&mut self, deck_id: Option<i64>) {
+        (**self).deck_prepared(deck_id)
+    }
+
+    fn duplicate_policy(
+        &mut self,
+        duplicates: &[String],
+        refused: &[String],
+    ) -> Result<DuplicatePolicy> {
+        (**self).duplicate_policy(duplicates, refused)
+    }
+
+    fn confirm(
+        &mut self,
+        words: &[Word],
+        to_translate: &[Word],
+        translator: &TranslationChain,
+    ) -> Result<bool> {
+        (**self).confirm(words, to_translate, translator)
+    }
+
+    fn review(&mut self, position: usize, translated: &TranslatedWord) -> Result<Option<String>> {
+        (**self).review(position, translated)
+    }
+
+    fn note_added(&mut self, word: &Word, note_id: Option<i64>) {
+        (**self).note_added(word, note_id)
+    }
+
+    fn failed(
+        &mut self,
+        word: &Word,
+        translation: Option<&str>,
+        phase: FailurePhase,
+        reason: &str,
+    ) {
+        (**self).failed(word, translation, phase, reason)
+    }
+
+    fn word_done(&mut self, word: &Word, translation: &str, status: WordStatus) {
+        (**self).word_done(word, translation, status)
+    }
+
+    fn finished(&mut self, summary: &BuildSummary, output: Option<&Path>) {
+        (**self).finished(summary, output)
+    }
+}
+
+/// Builds the deck a [`DeckSpec`] describes into a [`DeckSink`], an Anki
+/// backend unless told otherwise. `build` runs every step unattended; front
+/// ends that ask the user along the way call the steps themselves
+pub struct DeckBuilder<'a> {
+    spec: DeckSpec,
+    sink: Box<dyn DeckSink + 'a>,
+    cache_dir: PathBuf,
+    services: ServiceSettings,
+    translator: Option<TranslationChain>,
+    card_builder: Option<CardBuilder>,
+    pipeline: Option<EnrichmentPipeline>,
+    words: Option<Vec<Word>>,
+    earlier_words: usize,
+    cancel: CancellationToken,
+    deadline: Deadline,
+    on_event: Option<EventHandler>,
+    hooks: Box<dyn BuildHooks + 'a>,
+}
+
+impl<'a> DeckBuilder<'a> {
+    pub fn new(spec: DeckSpec, backend: &'a dyn AnkiBackend, cache_dir: PathBuf) -> Self {
+        Self {
+            spec,
+            sink: Box::new(AnkiConnectSink::new(backend)),
+            cache_dir,
+            services: ServiceSettings::default(),
+            translator: None,
+            card_builder: None,
+            pipeline: None,
+            words: None,
+            earlier_words: 0,
+            cancel: CancellationToken::new(),
+            deadline: Deadline::none(),
+            on_event: None,
+            hooks: Box::new(Unattended),
+        }
+    }
+
+    /// Reach the translators and downloads with these settings, and know
+    /// the languages of their packs
+    pub fn with_services(mut self, services: ServiceSettings) -> Self {
+        self.services = services;
+        self
+    }
+
+    /// Write the notes to `sink` instead of the Anki backend
+    pub fn with_sink(mut self, sink: Box<dyn DeckSink + 'a>) -> Self {
+        self.sink = sink;
+        self
+    }
+
+    /// Where the notes go
+    pub fn sink(&self) -> &dyn DeckSink {
+        self.sink.as_ref()
+    }
+
+    /// Translate with these providers instead of the usual ones
+    pub fn with_translator(mut self, translator: TranslationChain) -> Self {
+        self.translator = Some(translator);
+        self
+    }
+
+    /// Make the notes with `card_builder`, one from [`Self::card_builder`]
+    /// with options of the front end's own
+    pub fn with_card_builder(mut self, card_builder: CardBuilder) -> Self {
+        self.card_builder = Some(card_builder);
+        self
+    }
+
+    /// Enrich the notes with `pipeline` instead of the spec's own
+    pub fn with_enrichment_pipeline(mut self, pipeline: EnrichmentPipeline) -> Self {
+        self.pipeline = Some(pipeline);
+        self
+    }
+
+    /// Build from `words`, loaded with [`Self::load_words`] beforehand so a
+    /// front end could show them, rather than loading them again
+    pub fn with_loaded_words(mut self, words: Vec<Word>) -> Self {
+        self.words = Some(words);
+        self
+    }
+
+    /// Count `words` added by earlier runs in the deck's description, as
+    /// for a deck grown a few words at a time
+    pub fn with_earlier_words(mut self, words: usize) -> Self {
+        self.earlier_words = words;
+        self
+    }
+
+    /// Stop at the next word once `cancel` is cancelled
+    pub fn with_cancel(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Stop at the next note once `deadline` passes
+    pub fn with_deadline(mut self, deadline: Deadline) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    /// Let `hooks` decide and keep track of what the build does along the way
+    pub fn with_hooks(mut self, hooks: impl BuildHooks + 'a) -> Self {
+        self.hooks = Box::new(hooks);
+        self
+    }
+
+    /// Hand the build's events to `handler`; without one they are dropped
+    pub fn with_events(mut self, handler: impl Fn(BuildEvent) + Send + Sync + 'static) -> Self {
+        self.on_event = Some(Box::new(handler));
+        self
+    }
+
+    fn emit(&self, event: BuildEvent) {
+        if let Some(handler) = &self.on_event {
+            handler(event);
+        }
+    }
+
+    pub fn spec(&self) -> &DeckSpec {
+        &self.spec
+    }
+
+    /// Credits of the data the deck's words come from. Starter lists and
+    /// given words need none of their own
+    pub fn attributions(&self) -> Vec<Attribution> {
+        let mut attributions = self.spec.attributions.clone();
+        let from_list = match &self.spec.words {
+            WordSource::Top { list, .. }
+            | WordSource::Threshold { list, .. }
+            | WordSource::After { list, .. } => {
+                list.attributions(&self.spec.target_language, &self.services.language_packs)
+            }
+            WordSource::Words(_) | WordSource::Starter => Vec::new(),
+        };
+        for attribution in from_list {
+            if !attributions.contains(&attribution) {
+                attributions.push(attribution);
+            }
+        }
+        attributions
+    }
+
+    /// The enrichments asked for that the target language has data for
+    pub fn enrichments(&self) -> Enrichments {
+        self.spec
+            .enrichments
+            .available_for(&self.spec.target_language, &self.services.language_packs)
+    }
+
+    /// The words of the spec's sections that the target language has rules
+    /// for, filtered like any other
+    pub fn section_words(&self) -> Vec<(Section, GeneratedWord)> {
+        let spec = &self.spec;
+        spec.sections
+            .iter()
+            .flat_map(|&section| {
+                section
+                    .words(&spec.target_language)
+                    .into_iter()
+                    .map(move |generated| (section, generated))
+            })
+            .filter(|(_, generated)| spec.filter.matches(&generated.word.text))
+            .collect()
+    }
+
+    /// Translations that need no translator: the spec's own, and the
+    /// English glosses of section words when English is the only base
+    /// language
+    pub fn pinned_translations(&self) -> BTreeMap<String, String> {
+        let mut pinned = BTreeMap::new();
+        if self.spec.base_languages == ["en"] {
+            pinned.extend(
+                self.section_words()
+                    .into_iter()
+                    .map(|(_, generated)| (generated.word.text, generated.gloss)),
+            );
+        }
+        pinned.extend(self.spec.translations.clone());
+        pinned
+    }
+
+    /// The words to build the deck from, followed by its phrases and the
+    /// words of its sections
+    pub async fn load_words(&self) -> Result<Vec<Word>> {
+        let spec = &self.spec;
+        let mut words = match &spec.words {
+            WordSource::Top {
+                list,
+                selection,
+                words_per_pos,
+            } => {
+                let data = list
+                    .load(
+                        &spec.target_language,
+                        &self.cache_dir,
+                        &self.services,
+                        &self.cancel,
+                    )
+                    .await?
+                    .with_filter(spec.filter.clone())
+                    .with_parts_of_speech(spec.parts_of_speech.clone());
+                let words = data.select(*selection, *words_per_pos);
+                // When the built-in list stands in for a failed download, a
+                // deck it can't fill is refused rather than left short
+                let wanted = words_per_pos * data.parts_of_speech().len();
+                if let Some(error) = data.download_error().filter(|_| words.len() < wanted) {
+                    return Err(AnkiDeckBuilderError::FrequencyDataNotFound(format!(
+                        "{} (the list could not be downloaded: {}; the built-in one has only {} of the {} words asked for, so try again once the download works, or lower --words-per-pos)",
+                        spec.target_language,
+                        error,
+                        words.len(),
+                        wanted
+                    )));
+                }
+                words
+            }
+            WordSource::Threshold { list, threshold } => {
+                let data = list
+                    .load(
+                        &spec.target_language,
+                        &self.cache_dir,
+                        &self.services,
+                        &self.cancel,
+                    )
+                    .await?;
+                if !data.has_counts() {
+                    return Err(AnkiDeckBuilderError::FrequencyDataNotFound(format!(
+                        "{} (the list has no word counts for a frequency threshold)",
+                        spec.target_language
+                    )));
+                }
+                data.with_filter(spec.filter.clone())
+                    .with_parts_of_speech(spec.parts_of_speech.clone())
+                    .select_threshold(*threshold)
+            }
+            WordSource::After {
+                list,
+                after_rank,
+                count,
+            } => list
+                .load(
+                    &spec.target_language,
+                    &self.cache_dir,
+                    &self.services,
+                    &self.cancel,
+                )
+                .await?
+                .with_filter(spec.filter.clone())
+                .with_parts_of_speech(spec.parts_of_speech.clone())
+                .next_words(*after_rank, *count),
+            WordSource::Words(words) => words.clone(),
+            WordSource::Starter => starter_words(&spec.target_language)
+                .into_iter()
+                .filter(|word| spec.filter.matches(&word.text))
+                .collect(),
+        };
+
+        // Words given as they are, and the starter list, already carry their
+        // phrases
+        if !matches!(spec.words, WordSource::Words(_) | WordSource::Starter) {
+            words.extend(
+                common_phrases(&spec.target_language, spec.phrases)
+                    .into_iter()
+                    .filter(|phrase| spec.filter.matches(&phrase.text)),
+            );
+        }
+        if !matches!(spec.words, WordSource::Words(_)) {
+            let mut seen: HashSet<String> = words.iter().map(|w| w.text.clone()).collect();
+            for (_, generated) in self.section_words() {
+                if seen.insert(generated.word.text.clone()) {
+                    words.push(generated.word);
+                }
+            }
+        }
+        Ok(words)
+    }
+
+    /// The enrichers the spec asks for, in its order. Front ends can swap in
+    /// their own (see [`EnrichmentPipeline::with_replaced`])
+    pub fn enrichment_pipeline(&self) -> Result<EnrichmentPipeline> {
+        let enrichments = self.enrichments();
+        let mut pipeline = EnrichmentPipeline::new();
+        if enrichments.images {
+            pipeline = pipeline.with(ImageEnricher::new(ImageClient::new(&self.services.http)?));
+        }
+        if enrichments.declensions {
+            pipeline = pipeline.with(DeclensionEnricher::new(self.wiktionary()?));
+        }
+        if enrichments.senses {
+            pipeline = pipeline.with(OtherMeaningsEnricher::new(self.wiktionary()?));
+        }
+        if enrichments.audio {
+            pipeline = pipeline.with(AudioEnricher::new(AudioFetcher::from_kinds(
+                &self.spec.audio_providers,
+                Some(self.cache_dir.clone()),
+                &self.services,
+            )?));
+        }
+        if enrichments.mnemonics {
+            pipeline = pipeline.with(MnemonicEnricher::new(MnemonicFinder::new(
+                self.spec.base_language(),
+            )));
+        }
+        if enrichments.etymology {
+            pipeline = pipeline.with(EtymologyEnricher::new(self.wiktionary()?));
+        }
+        if enrichments.examples {
+            pipeline = pipeline.with(ExampleEnricher::new(
+                LlmClient::new(&self.services, Some(self.cache_dir.clone()))?,
+                self.spec.base_language(),
+            ));
+        }
+        Ok(pipeline.with_order(&self.spec.enrichment_order))
+    }
+
+    fn wiktionary(&self) -> Result<WiktionaryClient> {
+        Ok(
+            WiktionaryClient::new(Some(self.cache_dir.clone()), &self.services.http)?
+                .with_language_packs(self.services.language_packs.clone()),
+        )
+    }
+
+    /// Turns words into notes; front ends can add their own options and
+    /// hand it back with [`Self::with_card_builder`]
+    pub fn card_builder(&self) -> CardBuilder {
+        if let Some(card_builder) = &self.card_builder {
+            return card_builder.clone();
+        }
+        let enrichments = self.enrichments();
+        CardBuilder::new(
+            &self.spec.deck_name,
+            &self.spec.target_language,
+            self.spec.base_language(),
+        )
+        .with_extra_base_codes(self.spec.base_languages[1..].to_vec())
+        .with_bidirectional(self.spec.bidirectional)
+        .with_images(enrichments.images)
+        .with_readings(enrichments.readings)
+        .with_conjugations(enrichments.conjugations)
+        .with_declensions(enrichments.declensions)
+        .with_other_meanings(enrichments.senses)
+        .with_audio(enrichments.audio)
+        .with_mnemonics(enrichments.mnemonics)
+        .with_etymology(enrichments.etymology)
+        .with_examples(enrichments.examples)
+        .with_cognate_threshold(self.spec.skip_cognates.unwrap_or(DEFAULT_COGNATE_THRESHOLD))
+        .with_confusable_hints(self.spec.confusable_hints)
+        .with_language_packs(self.services.language_packs.clone())
+        .with_sections(
+            self.section_words()
+                .into_iter()
+                .map(|(section, generated)| (generated.word.text, section))
+                .collect(),
+        )
+    }
+
+    /// What the deck's description tells about a deck of `words` words
+    pub fn description_values(&self, words: usize) -> DescriptionValues {
+        let spec = &self.spec;
+        let packs = &self.services.language_packs;
+        let name = |code: &str| {
+            packs
+                .language(code)
+                .map_or(code.to_string(), |lang| lang.name)
+        };
+        let cards = words * if spec.bidirectional { 2 } else { 1 };
+        DescriptionValues {
+            target: name(&spec.target_language),
+            target_code: spec.target_language.clone(),
+            base: spec
+                .base_languages
+                .iter()
+                .map(|code| name(code))
+                .collect::<Vec<_>>()
+                .join(", "),
+            base_code: spec.base_language().to_string(),
+            words,
+            cards,
+            new_per_day: match spec.preset {
+                Some(preset) => preset.new_per_day() as usize,
+                None => suggested_new_per_day(cards),
+            },
+            attributions: self.attributions(),
+            sections: !spec.sections.is_empty(),
+            genders: packs
+                .get(&spec.target_language)
+                .is_some_and(|pack| !pack.gender_rules.is_empty()),
+        }
+    }
+
+    /// Create the note type the cards need and the deck, apply the
+    /// scheduling preset, and describe a deck of `words` words. Returns the
+    /// new deck's ID, or None if it existed
+    pub async fn prepare_deck(
+        &self,
+        card_builder: &CardBuilder,
+        words: usize,
+    ) -> Result<Option<i64>> {
+        if let Some(model) = card_builder.required_model() {
+            self.sink.ensure_model(&model).await?;
+        }
+
+        let deck_id = match self.sink.create_deck(&self.spec.deck_name).await {
+            Ok(deck_id) => deck_id,
+            Err(e) => {
+                tracing::warn!("Deck creation returned: {}", e);
+                None
+            }
+        };
+
+        for subdeck in &self.deck_names(card_builder)[1..] {
+            self.sink.create_deck(subdeck).await?;
+        }
+
+        if let Some(preset) = self.spec.preset {
+            self.sink.apply_preset(&self.spec.deck_name, preset).await?;
+        }
+
+        // Older AnkiConnect versions lack the action; the manifest keeps the
+        // credits either way
+        let template = self.spec.description_template.as_deref();
+        if template != Some("") {
+            let description = render_description(template, &self.description_values(words))?;
+            if let Err(e) = self
+                .sink
+                .set_deck_description(&self.spec.deck_name, &description)
+                .await
+            {
+                tracing::warn!("Could not set the deck description: {}", e);
+            }
+        }
+        Ok(deck_id)
+    }
+
+    /// The deck, then the subdecks its phrases and sections go into
+    pub fn deck_names(&self, card_builder: &CardBuilder) -> Vec<String> {
+        let mut decks = vec![self.spec.deck_name.clone()];
+        if self.has_phrases() {
+            decks.push(card_builder.phrase_deck());
+        }
+        decks.extend(card_builder.section_decks());
+        decks
+    }
+
+    /// Translate `count` of the words, spread over the list, and build
+    /// their notes without adding anything to Anki. A word the translators
+    /// fail on keeps a placeholder, so the preview is shown offline too
+    pub async fn preview(
+        &mut self,
+        card_builder: &CardBuilder,
+        words: &[Word],
+        count: usize,
+    ) -> Result<Vec<PreviewCard>> {
+        let translator = self.translator()?;
+        let pinned = self.pinned_translations();
+        let spec = &self.spec;
+        let count = count.min(words.len());
+        let card_builder = card_builder.clone().with_confusables(words);
+
+        let mut cards = Vec::with_capacity(count);
+        for i in 0..count {
+            let word = words[i * words.len() / count].clone();
+            let (translation, issue) = match pinned.get(&word.text) {
+                Some(translation) => (translation.clone(), None),
+                None => match translate_word(
+                    &translator,
+                    word.clone(),
+                    &spec.target_language,
+                    &spec.base_languages,
+                )
+                .await
+                {
+                    Ok(translated) => (translated.translation, translated.issue),
+                    Err(e) => ("…".to_string(), Some(format!("not translated: {}", e))),
+                },
+            };
+            cards.push(PreviewCard {
+                notes: card_builder.build(&word.text, &translation, &word.pos, word.rank),
+                word: word.text,
+                pos: word.pos,
+                rank: word.rank,
+                translation,
+                issue,
+            });
+        }
+        Ok(cards)
+    }
+
+    /// Whether the deck gets phrases, so it needs the phrase subdeck
+    fn has_phrases(&self) -> bool {
+        match &self.spec.words {
+            WordSource::Words(words) => words.iter().any(|w| w.pos == PartOfSpeech::Phrase),
+            WordSource::Starter => has_starter_list(&self.spec.target_language),
+            _ => self.spec.phrases > 0,
+        }
+    }
+
+    /// The translators, unless given with `with_translator`, built from the spec
+    pub fn translator(&mut self) -> Result<TranslationChain> {
+        let translator = match self.translator.take() {
+            Some(translator) => translator,
+            None => translation_chain(
+                &self.cache_dir,
+                self.spec.rules.clone(),
+                self.spec.translator,
+                self.spec.libretranslate_url.clone(),
+                &self.services,
+            )?,
+        };
+        Ok(translator
+            .with_back_translation(self.spec.back_translate)
+            .with_glossary(self.spec.glossary.clone()))
+    }
+
+    /// Check that the translators can translate into every base language,
+    /// before anything is added to Anki
+    pub async fn check_translators(&mut self) -> Result<Vec<PairSupport>> {
+        let translator = self.translator()?;
+        let pairs = translator
+            .check_pairs(&self.spec.target_language, &self.spec.base_languages)
+            .await;
+        // Kept, along with what the providers said they offer
+        self.translator = Some(translator);
+        pairs
+    }
+
+    /// Build the whole deck, asking the hooks (see [`Self::with_hooks`])
+    /// along the way; without any, words Anki already has and translations
+    /// no provider could vouch for are left out. Words the build didn't get
+    /// to are handed to the hooks too, so a stopped run can be retried
+    pub async fn build(mut self) -> Result<BuildSummary> {
+        self.emit(BuildEvent::Phase(BuildPhase::LoadingWords));
+        let mut words = match self.words.take() {
+            Some(words) => words,
+            None => self.load_words().await?,
+        };
+
+        self.emit(BuildEvent::Phase(BuildPhase::PreparingDeck));
+        self.check_translators().await?;
+        self.sink.verify().await?;
+        let card_builder = self.card_builder().with_confusables(&words);
+        let deck_id = self
+            .prepare_deck(&card_builder, self.earlier_words + words.len())
+            .await?;
+        self.hooks.deck_prepared(deck_id);
+
+        // Words Anki would refuse are known before anything is translated.
+        // Only the recognition note can be checked this early, since the
+        // other direction needs the translation
+        let probes: Vec<_> = words
+            .iter()
+            .filter_map(|word| {
+                card_builder
+                    .build(&word.text, "…", &word.pos, word.rank)
+                    .into_iter()
+                    .next()
+            })
+            .collect();
+        let checks = if probes.is_empty() {
+            Vec::new()
+        } else {
+            self.sink.check_duplicates(&probes).await?
+        };
+        let mut duplicates = HashSet::new();
+        let mut refused = Vec::new();
+        for (word, check) in words.iter().zip(checks) {
+            match check {
+                None => {}
+                Some(NoteRejection::Duplicate) => {
+                    duplicates.insert(word.text.clone());
+                }
+                // A missing deck or note type would refuse every note
+                Some(NoteRejection::Other(message)) => {
+                    return Err(AnkiDeckBuilderError::AnkiConnectError(message));
+                }
+                Some(rejection) => refused.push((word.clone(), rejection)),
+            }
+        }
+        for (word, rejection) in &refused {
+            self.hooks
+                .failed(word, None, FailurePhase::AddNote, &rejection.to_string());
+        }
+        words.retain(|word| !refused.iter().any(|(r, _)| r.text == word.text));
+
+        let duplicate_policy = if duplicates.is_empty() && refused.is_empty() {
+            DuplicatePolicy::Skip
+        } else {
+            let mut names: Vec<String> = duplicates.iter().cloned().collect();
+            names.sort();
+            let refused: Vec<String> = refused.iter().map(|(w, _)| w.text.clone()).collect();
+            self.hooks.duplicate_policy(&names, &refused)?
+        };
+        let duplicate_deck = self.spec.duplicate_deck();
+        let mut duplicates_skipped = 0;
+        match duplicate_policy {
+            DuplicatePolicy::Skip => {
+                duplicates_skipped = duplicates.len();
+                words.retain(|word| !duplicates.contains(&word.text));
+            }
+            DuplicatePolicy::Allow => {}
+            DuplicatePolicy::Deck => {
+                self.sink.create_deck(&duplicate_deck).await?;
+            }
+        }
+
+        let enrichments = self.enrichments();
+        let translator = self.translator()?;
+        let pipeline = match self.pipeline.take() {
+            Some(pipeline) => pipeline,
+            None => self.enrichment_pipeline()?,
+        };
+        let wiktionary = self.wiktionary()?;
+        let english_only = self.spec.base_languages == ["en"];
+
+        let mut summary = BuildSummary {
+            outcome: BuildOutcome::Completed,
+            deck_name: self.spec.deck_name.clone(),
+            words: 0,
+            total_words: words.len(),
+            notes_added: 0,
+            cards_added: 0,
+            duplicates_skipped,
+            cognates_skipped: 0,
+            notes_failed: 0,
+            flagged: 0,
+            disambiguated: 0,
+            enriched: BTreeMap::new(),
+        };
+
+        let pinned = self.pinned_translations();
+        let unpinned: Vec<Word> = words
+            .iter()
+            .filter(|word| !pinned.contains_key(&word.text))
+            .cloned()
+            .collect();
+        if !self.hooks.confirm(&words, &unpinned, &translator)? {
+            summary.outcome = BuildOutcome::Cancelled;
+            self.emit(BuildEvent::Finished(summary.clone()));
+            return Ok(summary);
+        }
+
+        self.emit(BuildEvent::Phase(BuildPhase::Translating));
+        self.emit(BuildEvent::Progress {
+            done: 0,
+            total: summary.total_words,
+        });
+        let spec = &self.spec;
+        // Providers that answer many words per request get them all up front
+        translator
+            .prepare(&unpinned, &spec.target_language, &spec.base_languages)
+            .await;
+        pipeline.prepare(&words, &spec.target_language).await;
+        // Kept to hand on the words a stopped build never got to
+        let pending = words.clone();
+        let translated_words = translate_words(
+            &translator,
+            words,
+            &spec.target_language,
+            &spec.base_languages,
+            &pinned,
+            spec.concurrency,
+            &self.cancel,
+        );
+        futures::pin_mut!(translated_words);
+
+        let mut failure = None;
+        let mut deadline_hit = false;
+        'words: loop {
+            // Translations are cached and added notes are skipped as
+            // duplicates, so a rerun picks up where this one stopped
+            if self.deadline.expired() {
+                deadline_hit = true;
+                break;
+            }
+            let mut translated = match translated_words.next().await {
+                Some(Ok(translated)) => translated,
+                Some(Err(e)) => {
+                    failure = Some(e);
+                    break;
+                }
+                None => break,
+            };
+
+            // Looked up before anything else, so the review and the cards
+            // see the sense that fits the word's part of speech
+            if enrichments.senses && english_only {
+                let word = &translated.word;
+                let entry = wiktionary
+                    .senses(&spec.target_language, &word.text)
+                    .await
+                    .unwrap_or_else(|e| {
+                        tracing::warn!("No senses for '{}': {}", word.text, e);
+                        Default::default()
+                    });
+                if let Some(sense) = entry.disambiguate(&word.pos, &translated.translation) {
+                    tracing::info!(
+                        "'{}' as a {} means '{}' rather than '{}'",
+                        word.text,
+                        word.pos.name(),
+                        sense,
+                        translated.translation
+                    );
+                    translated.translation = sense;
+                    summary.disambiguated += 1;
+                }
+            }
+
+            summary.words += 1;
+            let word = &translated.word;
+            self.emit(BuildEvent::Progress {
+                done: summary.words,
+                total: summary.total_words,
+            });
+            self.emit(BuildEvent::Translated {
+                word: word.text.clone(),
+                pos: word.pos.clone(),
+                translation: translated.translation.clone(),
+                issue: translated.issue.clone(),
+            });
+            if translated.issue.is_some() {
+                summary.flagged += 1;
+            }
+
+            // An untrustworthy translation may just be the word echoed back,
+            // so only trusted ones are compared
+            if translated.issue.is_none()
+                && spec.skip_cognates.is_some_and(|threshold| {
+                    is_cognate(
+                        &spec.target_language,
+                        &word.text,
+                        &translated.translation,
+                        threshold,
+                    )
+                })
+            {
+                summary.cognates_skipped += 1;
+                continue;
+            }
+
+            let translation = match self.hooks.review(summary.words, &translated)? {
+                Some(translation) => translation,
+                // The review was interrupted rather than the word skipped
+                None if self.cancel.is_cancelled() => {
+                    self.hooks.failed(
+                        word,
+                        Some(&translated.translation),
+                        FailurePhase::NotReached,
+                        "cancelled",
+                    );
+                    break;
+                }
+                None => continue,
+            };
+
+            let mut notes = card_builder.build(&word.text, &translation, &word.pos, word.rank);
+            if duplicates.contains(&word.text) {
+                for note in &mut notes {
+                    match duplicate_policy {
+                        DuplicatePolicy::Allow => note.allow_duplicate = true,
+                        DuplicatePolicy::Deck => {
+                            note.deck_name = duplicate_deck.clone();
+                            note.duplicate_scope = DuplicateScope::Deck;
+                        }
+                        DuplicatePolicy::Skip => {}
+                    }
+                }
+            }
+            // All of a word's notes are checked together
+            let checks = match self.sink.check_duplicates(&notes).await {
+                Ok(checks) => checks,
+                Err(e) => {
+                    tracing::warn!("Failed to check the notes for '{}': {}", word.text, e);
+                    summary.notes_failed += notes.len();
+                    self.emit(BuildEvent::NoteFailed {
+                        word: word.text.clone(),
+                        error: e.to_string(),
+                    });
+                    self.hooks.failed(
+                        word,
+                        Some(&translation),
+                        FailurePhase::AddNote,
+                        &e.to_string(),
+                    );
+                    self.hooks.word_done(word, &translation, WordStatus::Failed);
+                    continue;
+                }
+            };
+
+            // Looked up once per word, and only when a note actually needs adding
+            let mut enrichment = None;
+            let mut status = WordStatus::Existing;
+            for (mut note, check) in notes.into_iter().zip(checks) {
+                if self.deadline.expired() || self.cancel.is_cancelled() {
+                    deadline_hit = !self.cancel.is_cancelled();
+                    let reason = match deadline_hit {
+                        true => "deadline reached",
+                        false => "cancelled",
+                    };
+                    self.hooks
+                        .failed(word, Some(&translation), FailurePhase::NotReached, reason);
+                    break 'words;
+                }
+
+                match check {
+                    None => {}
+                    Some(NoteRejection::Duplicate) => {
+                        summary.duplicates_skipped += 1;
+                        continue;
+                    }
+                    Some(rejection) => {
+                        tracing::warn!("Anki refused a note for '{}': {}", word.text, rejection);
+                        summary.notes_failed += 1;
+                        status = WordStatus::Failed;
+                        self.emit(BuildEvent::NoteFailed {
+                            word: word.text.clone(),
+                            error: rejection.to_string(),
+                        });
+                        self.hooks.failed(
+                            word,
+                            Some(&translation),
+                            FailurePhase::AddNote,
+                            &rejection.to_string(),
+                        );
+                        continue;
+                    }
+                }
+
+                if enrichment.is_none() {
+                    let input = EnrichInput {
+                        language: &spec.target_language,
+                        word,
+                        translation: &translation,
+                        fields: &Fields::new(),
+                    };
+                    enrichment = Some(pipeline.enrich(input, self.sink.as_ref()).await);
+                }
+                let filled = match &enrichment {
+                    Some(enrichment) => {
+                        enrichment.apply(&mut note);
+                        enrichment.filled.clone()
+                    }
+                    None => Vec::new(),
+                };
+
+                match self.sink.add_note(&note).await {
+                    Ok(note_id) => {
+                        summary.notes_added += 1;
+                        for name in filled {
+                            *summary.enriched.entry(name.to_string()).or_default() += 1;
+                        }
+                        if status == WordStatus::Existing {
+                            status = WordStatus::Added;
+                        }
+                        self.hooks.note_added(word, note_id);
+                        self.emit(BuildEvent::NoteAdded {
+                            word: word.text.clone(),
+                        });
+                    }
+                    Err(e) => {
+                        tracing::warn!(
+                            "Failed to add note for '{}→{}': {}",
+                            word.text,
+                            translation,
+                            e
+                        );
+                        summary.notes_failed += 1;
+                        status = WordStatus::Failed;
+                        self.emit(BuildEvent::NoteFailed {
+                            word: word.text.clone(),
+                            error: e.to_string(),
+                        });
+                        self.hooks.failed(
+                            word,
+                            Some(&translation),
+                            FailurePhase::AddNote,
+                            &e.to_string(),
+                        );
+                    }
+                }
+            }
+            self.hooks.word_done(word, &translation, status);
+        }
+
+        // Files are written with whatever was added, even when the build
+        // stopped early
+        let output = match self.sink.finish().await {
+            Ok(output) => output,
+            Err(e) => {
+                summary.outcome = BuildOutcome::Failed;
+                self.emit(BuildEvent::Finished(summary.clone()));
+                return Err(e);
+            }
+        };
+
+        summary.outcome = if failure.is_some() {
+            BuildOutcome::Failed
+        } else if deadline_hit {
+            BuildOutcome::DeadlineReached
+        } else if self.cancel.is_cancelled() {
+            BuildOutcome::Cancelled
+        } else {
+            BuildOutcome::Completed
+        };
+        // Whatever the build didn't get to, starting with the word that failed
+        let mut unreached = pending.iter().skip(summary.words);
+        if let Some(e) = &failure {
+            if let Some(word) = unreached.next() {
+                self.hooks
+                    .failed(word, None, FailurePhase::Translation, &e.to_string());
+            }
+        }
+        let reason = match summary.outcome {
+            BuildOutcome::Failed => "stopped after a translation failure",
+            BuildOutcome::Cancelled => "cancelled",
+            _ => "deadline reached",
+        };
+        for word in unreached {
+            self.hooks
+                .failed(word, None, FailurePhase::NotReached, reason);
+        }
+
+        summary.cards_added = summary.notes_added * card_builder.cards_per_note();
+        self.hooks.finished(&summary, output.as_deref());
+        self.emit(BuildEvent::Finished(summary.clone()));
+        match failure {
+            Some(e) => Err(e),
+            None => Ok(summary),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ankiweb::MockAnki;
+    use crate::sink::CsvSink;
+    use async_trait::async_trait;
+
+    /// Answers "<text>-<to>"
+    struct EchoTranslator;
+
+    #[async_trait]
+    impl Translator for EchoTranslator {
+        fn name(&self) -> &'static str {
+            "echo"
+        }
+
+        async fn translate(&self, text: &str, _from: &str, to: &str) -> Result<String> {
+            Ok(format!("{}-{}", text, to))
+        }
+    }
+
+    fn words(texts: &[&str]) -> Vec<Word> {
+        texts
+            .iter()
+            .enumerate()
+            .map(|(i, text)| Word::new(text.to_string(), PartOfSpeech::Noun, i + 1))
+            .collect()
+    }
+
+    #[test]
+    fn test_spec_resolves_languages() {
+        let spec = DeckSpec::new("Deck", "Croatian", "es", &LanguagePacks::default())
+            .unwrap()
+            .with_extra_base_languages(&["English".to_string()], &LanguagePacks::default())
+            .unwrap();
+        assert_eq!(spec.target_language, "hr");
+        assert_eq!(spec.base_languages, ["es", "en"]);
+
+        assert!(matches!(
+            DeckSpec::new("Deck", "Klingon", "en", &LanguagePacks::default()),
+            Err(AnkiDeckBuilderError::UnsupportedLanguage(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_build_into_mock_anki() {
+        let anki = MockAnki::new();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let spec = DeckSpec::new("Croatian", "hr", "es", &LanguagePacks::default())
+            .unwrap()
+            .with_words(WordSource::Words(words(&["dan", "noć", "kuća"])))
+            .with_bidirectional(false);
+
+        let summary = DeckBuilder::new(spec, &anki, temp_dir.path().to_path_buf())
+            .with_translator(TranslationChain::new(vec![Box::new(EchoTranslator)]))
+            .build()
+            .await
+            .unwrap();
+
+        assert_eq!(summary.outcome, BuildOutcome::Completed);
+        assert_eq!(summary.words, 3);
+        assert_eq!(summary.notes_added, 3);
+        let notes = anki.notes();
+        assert_eq!(notes.len(), 3);
+        assert!(notes.iter().all(|note| note.deck_name == "Croatian"));
+    }
+
+    #[tokio::test]
+    async fn test_build_into_csv_without_anki() {
+        let anki = MockAnki::new();
+        anki.fail_action("version", "not running");
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("croatian.csv");
+        let spec = DeckSpec::new("Croatian", "hr", "es", &LanguagePacks::default())
+            .unwrap()
+            .with_words(WordSource::Words(words(&["dan", "noć"])))
+            .with_bidirectional(false);
+
+        let summary = DeckBuilder::new(spec, &anki, temp_dir.path().to_path_buf())
+            .with_sink(Box::new(CsvSink::new(&path)))
+            .with_translator(TranslationChain::new(vec![Box::new(EchoTranslator)]))
+            .build()
+            .await
+            .unwrap();
+
+        assert_eq!(summary.notes_added, 2);
+        assert!(anki.notes().is_empty());
+        let csv = std::fs::read_to_string(&path).unwrap();
+        let rows: Vec<_> = csv.lines().filter(|line| !line.starts_with('#')).collect();
+        assert_eq!(rows.len(), 2);
+        assert!(rows[0].starts_with("Basic,Croatian,") && rows[0].contains("dan-es"));
+    }
+
+    #[tokio::test]
+    async fn test_deck_credits_its_word_lists() {
+        let anki = MockAnki::new();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let list = FrequencyList {
+            source: FrequencySourceKind::Merged,
+            ..FrequencyList::default()
+        };
+        let spec = DeckSpec::new("Croatian", "hr", "es", &LanguagePacks::default())
+            .unwrap()
+            .with_words(WordSource::After {
+                list,
+                after_rank: 0,
+                count: 10,
+            });
+        let builder = DeckBuilder::new(spec, &anki, temp_dir.path().to_path_buf());
+        let names: Vec<String> = builder.attributions().into_iter().map(|a| a.name).collect();
+        assert_eq!(names, ["FrequencyWords", "Leipzig Corpora Collection"]);
+
+        builder
+            .prepare_deck(&builder.card_builder(), 10)
+            .await
+            .unwrap();
+        let description = anki.deck_description("Croatian").unwrap();
+        assert!(description.contains("FrequencyWords by Hermit Dave (CC-BY-SA-4.0)"));
+        // Study guidance in the base language
+        assert!(description.contains("10 palabras en 20 tarjetas"));
+
+        // A deck of given words has nothing to credit
+        let spec = DeckSpec::new("Other", "hr", "es", &LanguagePacks::default())
+            .unwrap()
+            .with_words(WordSource::Words(words(&["dan"])))
+            .with_description_template(Some("{pair}{sources}".to_string()));
+        let builder = DeckBuilder::new(spec, &anki, temp_dir.path().to_path_buf());
+        builder
+            .prepare_deck(&builder.card_builder(), 1)
+            .await
+            .unwrap();
+        assert_eq!(
+            anki.deck_description("Other").as_deref(),
+            Some("Croatian → Spanish")
+        );
+
+        // An empty template keeps the description
+        anki.create_deck("Mine").await.unwrap();
+        let spec = DeckSpec::new("Mine", "hr", "es", &LanguagePacks::default())
+            .unwrap()
+            .with_description_template(Some(String::new()));
+        let builder = DeckBuilder::new(spec, &anki, temp_dir.path().to_path_buf());
+        builder
+            .prepare_deck(&builder.card_builder(), 1)
+            .await
+            .unwrap();
+        assert_eq!(anki.deck_description("Mine"), None);
+    }
+
+    #[tokio::test]
+    async fn test_phrases_go_to_their_subdeck() {
+        let anki = MockAnki::new();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut phrases = words(&["hvala lijepa"]);
+        phrases[0].pos = PartOfSpeech::Phrase;
+        let spec = DeckSpec::new("Croatian", "hr", "es", &LanguagePacks::default())
+            .unwrap()
+            .with_words(WordSource::Words(
+                words(&["dan"]).into_iter().chain(phrases).collect(),
+            ))
+            .with_bidirectional(false);
+
+        DeckBuilder::new(spec, &anki, temp_dir.path().to_path_buf())
+            .with_translator(TranslationChain::new(vec![Box::new(EchoTranslator)]))
+            .build()
+            .await
+            .unwrap();
+
+        let decks: Vec<_> = anki
+            .notes()
+            .into_iter()
+            .map(|note| note.deck_name)
+            .collect();
+        assert_eq!(decks, ["Croatian", "Croatian::Phrases"]);
+    }
+
+    #[tokio::test]
+    async fn test_starter_words() {
+        let anki = MockAnki::new();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let spec = DeckSpec::new("Croatian", "hr", "es", &LanguagePacks::default())
+            .unwrap()
+            .with_words(WordSource::Starter)
+            .with_phrases(5)
+            .with_filter(WordFilter::new().with_known_words(["bok".to_string()]));
+        let builder = DeckBuilder::new(spec, &anki, temp_dir.path().to_path_buf());
+
+        let words = builder.load_words().await.unwrap();
+        assert_eq!(words[0].text, "dobar dan");
+        // Phrases come from the starter list, not on top of it
+        assert_eq!(words.len(), starter_words("hr").len() - 1);
+        assert!(builder.has_phrases());
+    }
+
+    #[tokio::test]
+    async fn test_sections_in_their_subdecks() {
+        let anki = MockAnki::new();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let spec = DeckSpec::new("Croatian", "hr", "en", &LanguagePacks::default())
+            .unwrap()
+            .with_words(WordSource::Starter)
+            .with_sections(vec![Section::Numbers, Section::Dates])
+            .with_filter(
+                WordFilter::new()
+                    .with_include(&["^(bok|dva|tri|petak)$".to_string()])
+                    .unwrap(),
+            )
+            .with_bidirectional(false);
+
+        let summary = DeckBuilder::new(spec, &anki, temp_dir.path().to_path_buf())
+            .with_translator(TranslationChain::new(vec![Box::new(EchoTranslator)]))
+            .build()
+            .await
+            .unwrap();
+
+        // The starter list already has dva, tri and petak
+        assert_eq!(summary.words, 4);
+        let notes = anki.notes();
+        let deck_of = |back: &str| {
+            notes
+                .iter()
+                .find(|note| note.fields["Back"].contains(back))
+                .map(|note| note.deck_name.as_str())
+        };
+        assert_eq!(deck_of(">two<"), Some("Croatian::Numbers"));
+        assert_eq!(deck_of("Friday"), Some("Croatian::Dates"));
+        assert_eq!(deck_of("bok-en"), Some("Croatian"));
+    }
+
+    #[tokio::test]
+    async fn test_preview_adds_nothing() {
+        let anki = MockAnki::new();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let spec = DeckSpec::new("Croatian", "hr", "en", &LanguagePacks::default())
+            .unwrap()
+            .with_sections(vec![Section::Numbers])
+            .with_bidirectional(false);
+        let mut builder = DeckBuilder::new(spec, &anki, temp_dir.path().to_path_buf())
+            .with_translator(TranslationChain::new(vec![Box::new(EchoTranslator)]));
+        let card_builder = builder.card_builder();
+        let mut all = words(&["dan", "noć", "kuća", "sunce"]);
+        all.push(Word::new("dva".to_string(), PartOfSpeech::Noun, 2));
+
+        let preview = builder.preview(&card_builder, &all, 3).await.unwrap();
+
+        let previewed: Vec<_> = preview.iter().map(|card| card.word.as_str()).collect();
+        assert_eq!(previewed, ["dan", "noć", "sunce"]);
+        assert_eq!(preview[0].translation, "dan-en");
+        assert_eq!(preview[0].notes[0].deck_name, "Croatian");
+        assert_eq!(
+            builder.preview(&card_builder, &all[4..], 3).await.unwrap()[0].notes[0].deck_name,
+            "Croatian::Numbers"
+        );
+        assert_eq!(
+            builder.deck_names(&card_builder),
+            ["Croatian", "Croatian::Numbers"]
+        );
+        assert!(anki.notes().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_build_skips_words_already_in_anki() {
+        let anki = MockAnki::new();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let spec = DeckSpec::new("Croatian", "hr", "es", &LanguagePacks::default())
+            .unwrap()
+            .with_words(WordSource::Words(words(&["dan", "noć"])))
+            .with_bidirectional(false);
+        let build = |spec: DeckSpec| {
+            DeckBuilder::new(spec, &anki, temp_dir.path().to_path_buf())
+                .with_translator(TranslationChain::new(vec![Box::new(EchoTranslator)]))
+                .build()
+        };
+
+        build(spec.clone()).await.unwrap();
+        let summary = build(spec.with_words(WordSource::Words(words(&["dan", "sunce"]))))
+            .await
+            .unwrap();
+
+        assert_eq!(summary.duplicates_skipped, 1);
+        assert_eq!(summary.notes_added, 1);
+        assert_eq!(anki.notes().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_decks_for_two_targets_at_once() {
+        let anki = MockAnki::new();
+        let temp_dir = tempfile::tempdir().unwrap();
+        // One glossary, loaded once, with entries for both targets
+        let glossary_dir = temp_dir.path().join("glossaries");
+        std::fs::create_dir(&glossary_dir).unwrap();
+        std::fs::write(
+            glossary_dir.join("hr-es.csv"),
+            "word,translation\ndan,día\n",
+        )
+        .unwrap();
+        std::fs::write(
+            glossary_dir.join("it-es.csv"),
+            "word,translation\ncasa,hogar\n",
+        )
+        .unwrap();
+        let glossary = Glossary::load(&glossary_dir).unwrap();
+        let spec = |deck: &str, target: &str, texts: &[&str]| {
+            DeckSpec::new(deck, target, "es", &LanguagePacks::default())
+                .unwrap()
+                .with_words(WordSource::Words(words(texts)))
+                .with_glossary(glossary.clone())
+                .with_bidirectional(false)
+        };
+        let build = |spec: DeckSpec| {
+            DeckBuilder::new(spec, &anki, temp_dir.path().to_path_buf())
+                .with_translator(TranslationChain::new(vec![Box::new(EchoTranslator)]))
+                .build()
+        };
+
+        let (croatian, italian) = tokio::join!(
+            build(spec("Croatian", "hr", &["dan", "noć"])),
+            build(spec("Italian", "it", &["giorno", "notte", "casa"]))
+        );
+
+        assert_eq!(croatian.unwrap().notes_added, 2);
+        assert_eq!(italian.unwrap().notes_added, 3);
+        let notes = anki.notes();
+        let in_deck = |deck: &str| notes.iter().filter(|note| note.deck_name == deck).count();
+        assert_eq!((in_deck("Croatian"), in_deck("Italian")), (2, 3));
+        // Each deck used the glossary of its own pair
+        let has = |text: &str| notes.iter().any(|note| note.fields["Back"].contains(text));
+        assert!(has("día") && has("hogar") && has("notte-es"));
+        assert!(!has("dan-es") && !has("casa-es"));
+    }
+
+    /// Puts duplicates in their own deck, skips "noć" and keeps the rest
+    #[derive(Default)]
+    struct Recorder {
+        asked: Vec<String>,
+        added: Vec<String>,
+        failed: Vec<(String, FailurePhase)>,
+        finished: Option<BuildSummary>,
+    }
+
+    impl BuildHooks for Recorder {
+        fn duplicate_policy(
+            &mut self,
+            duplicates: &[String],
+            _refused: &[String],
+        ) -> Result<DuplicatePolicy> {
+            self.asked = duplicates.to_vec();
+            Ok(DuplicatePolicy::Deck)
+        }
+
+        fn review(
+            &mut self,
+            _position: usize,
+            translated: &TranslatedWord,
+        ) -> Result<Option<String>> {
+            Ok((translated.word.text != "noć").then(|| translated.translation.clone()))
+        }
+
+        fn note_added(&mut self, word: &Word, _note_id: Option<i64>) {
+            self.added.push(word.text.clone());
+        }
+
+        fn failed(
+            &mut self,
+            word: &Word,
+            _translation: Option<&str>,
+            phase: FailurePhase,
+            _reason: &str,
+        ) {
+            self.failed.push((word.text.clone(), phase));
+        }
+
+        fn finished(&mut self, summary: &BuildSummary, _output: Option<&Path>) {
+            self.finished = Some(summary.clone());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_build_asks_the_hooks() {
+        let anki = MockAnki::new();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let spec = DeckSpec::new("Croatian", "hr", "es", &LanguagePacks::default())
+            .unwrap()
+            .with_words(WordSource::Words(words(&["dan"])))
+            .with_bidirectional(false);
+        let builder = |spec: DeckSpec| {
+            DeckBuilder::new(spec, &anki, temp_dir.path().to_path_buf())
+                .with_translator(TranslationChain::new(vec![Box::new(EchoTranslator)]))
+        };
+        builder(spec.clone()).build().await.unwrap();
+
+        let mut hooks = Recorder::default();
+        let summary = builder(spec.with_words(WordSource::Words(words(&["dan", "noć", "sunce"]))))
+            .with_hooks(&mut hooks)
+            .build()
+            .await
+            .unwrap();
+
+        assert_eq!(hooks.asked, ["dan"]);
+        assert_eq!(hooks.added, ["dan", "sunce"]);
+        assert!(hooks.failed.is_empty());
+        assert_eq!(hooks.finished, Some(summary.clone()));
+        assert_eq!(summary.notes_added, 2);
+        let notes = anki.notes();
+        assert!(notes
+            .iter()
+            .any(|note| note.deck_name == "Croatian::Duplicates"));
+    }
+}
@@ -0,0 +1,388 @@
+//! Cache files that survive crashes and concurrent runs. A write goes to a
+//! temporary file that replaces the old one in a single rename, so a crash
+//! leaves the previous contents in place. Read-modify-write updates hold a
+//! lock file, so two runs adding to one cache don't lose each other's
+//! entries. A file that no longer parses is moved aside instead of failing
+//! the run or being read as empty and overwritten. Map caches that gain an
+//! entry at a time, like translations, collect them in a journal beside the
+//! file and merge it in chunks, so a long run doesn't rewrite a large cache
+//! for every word
+
+use crate::error::Result;
+use crate::language::normalize_text;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Size of a journal at which it is merged into its map cache
+const JOURNAL_LIMIT: u64 = 64 * 1024;
+
+/// Tells apart the temporary files of writes running at the same time
+static NEXT_TEMP: AtomicUsize = AtomicUsize::new(0);
+
+/// Replace `path` with `contents` all at once: readers see either the old
+/// file or the new one, never half of it
+pub fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+    create_parent(path)?;
+    let temp = sibling(
+        path,
+        &format!(
+            ".{}.{}.tmp",
+            std::process::id(),
+            NEXT_TEMP.fetch_add(1, Ordering::Relaxed)
+        ),
+    );
+
+    let written = File::create(&temp).and_then(|mut file| {
+        file.write_all(contents.as_ref())?;
+        file.sync_all()
+    });
+    if let Err(e) = written.and_then(|()| std::fs::rename(&temp, path)) {
+        let _ = std::fs::remove_file(&temp);
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+/// Write a value as pretty-printed JSON with [`write_atomic`]
+pub fn write_json<T: Serialize + ?Sized>(path: &Path, value: &T) -> Result<()> {
+    write_atomic(path, serde_json::to_string_pretty(value)?)
+}
+
+/// Read a JSON cache file. None when there is none or it can't be read;
+/// one that doesn't parse is quarantined so the next write starts afresh
+pub fn read_json<T: DeserializeOwned>(path: &Path) -> Option<T> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+        Err(e) => {
+            tracing::warn!("Could not read cache {}: {}", path.display(), e);
+            return None;
+        }
+    };
+    match serde_json::from_str(&content) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            quarantine(path, &e.to_string());
+            None
+        }
+    }
+}
+
+/// Move a corrupt cache file aside as `<name>.corrupt-<unix time>`, keeping
+/// it to look at while the cache is rebuilt. Returns where it went
+pub fn quarantine(path: &Path, reason: &str) -> Option<PathBuf> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let moved = sibling(path, &format!(".corrupt-{}", now));
+    match std::fs::rename(path, &moved) {
+        Ok(()) => {
+            tracing::warn!(
+                "Cache {} is corrupt ({}); moved it to {}",
+                path.display(),
+                reason,
+                moved.display()
+            );
+            Some(moved)
+        }
+        Err(e) => {
+            tracing::warn!(
+                "Cache {} is corrupt ({}) and could not be moved aside: {}",
+                path.display(),
+                reason,
+                e
+            );
+            None
+        }
+    }
+}
+
+/// Held while a cache file is updated; other runs wait for it to drop
+pub struct CacheLock {
+    _file: File,
+}
+
+/// Take the lock of a cache file, `<name>.lock` beside it, waiting while
+/// another run holds it
+pub fn lock(path: &Path) -> Result<CacheLock> {
+    create_parent(path)?;
+    let file = File::options()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(sibling(path, ".lock"))?;
+    file.lock()?;
+    Ok(CacheLock { _file: file })
+}
+
+/// Change a JSON cache file under its lock: read it (empty when missing or
+/// corrupt), let `change` edit it, and write it back atomically
+pub fn update_json<T, R>(path: &Path, change: impl FnOnce(&mut T) -> R) -> Result<R>
+where
+    T: Serialize + DeserializeOwned + Default,
+{
+    let _lock = lock(path)?;
+    let mut value = read_json(path).unwrap_or_default();
+    let result = change(&mut value);
+    write_json(path, &value)?;
+    Ok(result)
+}
+
+/// Add entries to a JSON map cache. They go on lines of their own at the end
+/// of `<name>.journal` beside it, which costs the same however large the
+/// cache has grown; once the journal passes 64 KiB it is merged into the
+/// cache in one atomic write. A crash loses nothing: the journal left behind
+/// is read along with the cache, and a line cut short is skipped
+pub fn append_entries<K: Serialize, V: Serialize>(
+    path: &Path,
+    entries: impl IntoIterator<Item = (K, V)>,
+) -> Result<()> {
+    let mut lines = String::new();
+    for entry in entries {
+        lines.push_str(&serde_json::to_string(&entry)?);
+        lines.push('\n');
+    }
+    if lines.is_empty() {
+        return Ok(());
+    }
+
+    let _lock = lock(path)?;
+    let mut journal = File::options()
+        .create(true)
+        .read(true)
+        .append(true)
+        .open(sibling(path, ".journal"))?;
+    // A line a crash cut short is ended first, so it doesn't swallow the next
+    let size = journal.metadata()?.len();
+    if size > 0 {
+        let mut last = [0u8];
+        journal.seek(SeekFrom::End(-1))?;
+        journal.read_exact(&mut last)?;
+        if last[0] != b'\n' {
+            lines.insert(0, '\n');
+        }
+    }
+    journal.write_all(lines.as_bytes())?;
+
+    if size + lines.len() as u64 >= JOURNAL_LIMIT {
+        merge_journal(path, |_: &mut HashMap<String, Value>| ())?;
+    }
+    Ok(())
+}
+
+/// Read a JSON map cache with the entries of its journal applied. None when
+/// there is neither
+pub fn read_map<V: DeserializeOwned>(path: &Path) -> Option<HashMap<String, V>> {
+    let map = read_json(path);
+    let journal = read_journal(path);
+    if map.is_none() && journal.is_empty() {
+        return None;
+    }
+    let mut map: HashMap<String, V> = map.unwrap_or_default();
+    map.extend(journal);
+    Some(map)
+}
+
+/// Look up the translation of `text` in a map cache keyed by
+/// [`normalize_text`]. Entries written before keys were normalized may hold
+/// another form, so those match once their keys are normalized too
+pub fn read_translation(path: &Path, text: &str) -> Option<String> {
+    let cache: HashMap<String, String> = read_map(path)?;
+    let text = normalize_text(text);
+    cache.get(&text).cloned().or_else(|| {
+        cache.into_iter().find_map(|(cached, translation)| {
+            (normalize_text(&cached) == text).then_some(translation)
+        })
+    })
+}
+
+/// Change a JSON map cache under its lock, with its journal merged in
+/// first, and write it back without the journal
+pub fn update_map<V, R>(path: &Path, change: impl FnOnce(&mut HashMap<String, V>) -> R) -> Result<R>
+where
+    V: Serialize + DeserializeOwned,
+{
+    let _lock = lock(path)?;
+    merge_journal(path, change)
+}
+
+/// Merge the journal of a map cache into it, leaving a single file
+pub fn flush_map(path: &Path) -> Result<()> {
+    update_map(path, |_: &mut HashMap<String, Value>| ())
+}
+
+/// `update_map` for a caller holding the lock. The journal is removed only
+/// once the cache is written, and replaying it again changes nothing, so a
+/// crash in between loses nothing either
+fn merge_journal<V, R>(path: &Path, change: impl FnOnce(&mut HashMap<String, V>) -> R) -> Result<R>
+where
+    V: Serialize + DeserializeOwned,
+{
+    let mut map = read_map(path).unwrap_or_default();
+    let result = change(&mut map);
+    write_json(path, &map)?;
+    match std::fs::remove_file(sibling(path, ".journal")) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+        _ => {}
+    }
+    Ok(result)
+}
+
+/// The entries of a map cache's journal, in the order they were added
+fn read_journal<V: DeserializeOwned>(path: &Path) -> Vec<(String, V)> {
+    let Ok(content) = std::fs::read_to_string(sibling(path, ".journal")) else {
+        return Vec::new();
+    };
+    content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+fn create_parent(path: &Path) -> Result<()> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    Ok(())
+}
+
+/// "cache/hr_es.json" with `suffix` ".lock" is "cache/hr_es.json.lock"
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_write_atomic_replaces_whole_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("translations").join("hr_es.json");
+
+        write_json(&path, &HashMap::from([("dan", "día")])).unwrap();
+        write_json(&path, &HashMap::from([("noć", "noche")])).unwrap();
+
+        let cache: HashMap<String, String> = read_json(&path).unwrap();
+        assert_eq!(cache, HashMap::from([("noć".into(), "noche".into())]));
+        // Nothing is left behind beside it
+        let names: Vec<_> = std::fs::read_dir(path.parent().unwrap())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(names, ["hr_es.json"]);
+    }
+
+    #[test]
+    fn test_read_translation_normalizes_stored_keys() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("hr_es.json");
+
+        // Written before keys were normalized: decomposed, with extra spaces
+        write_json(&path, &HashMap::from([("noc\u{301}  dobra", "noche")])).unwrap();
+        append_entries(&path, [("kuća", "casa")]).unwrap();
+
+        assert_eq!(
+            read_translation(&path, "kuc\u{301}a").as_deref(),
+            Some("casa")
+        );
+        assert_eq!(
+            read_translation(&path, "noć dobra").as_deref(),
+            Some("noche")
+        );
+        assert_eq!(read_translation(&path, "dan"), None);
+        assert_eq!(
+            read_translation(&temp_dir.path().join("none.json"), "dan"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_corrupt_file_is_quarantined() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("hr_es.json");
+        std::fs::write(&path, r#"{"dan": "dí"#).unwrap();
+
+        assert_eq!(read_json::<HashMap<String, String>>(&path), None);
+        assert!(!path.exists());
+        let moved: Vec<_> = std::fs::read_dir(temp_dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        assert!(moved[0].starts_with("hr_es.json.corrupt-"));
+
+        // The cache starts over rather than failing
+        update_json(&path, |cache: &mut HashMap<String, String>| {
+            cache.insert("dan".into(), "día".into())
+        })
+        .unwrap();
+        let cache: HashMap<String, String> = read_json(&path).unwrap();
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_journal_survives_a_crash_and_is_merged() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("hr_es.json");
+        write_json(&path, &HashMap::from([("dan", "día")])).unwrap();
+
+        append_entries(&path, [("noć", "noche")]).unwrap();
+        // A run killed halfway through a line, then another run carrying on
+        let journal = sibling(&path, ".journal");
+        let mut file = File::options().append(true).open(&journal).unwrap();
+        file.write_all(r#"["kuća", "ca"#.as_bytes()).unwrap();
+        append_entries(&path, [("pas", "perro")]).unwrap();
+
+        let cache: HashMap<String, String> = read_map(&path).unwrap();
+        assert_eq!(cache.len(), 3);
+        assert_eq!(cache["pas"], "perro");
+        // The cache file itself is only rewritten in chunks
+        let written: HashMap<String, String> = read_json(&path).unwrap();
+        assert_eq!(written.len(), 1);
+
+        flush_map(&path).unwrap();
+        assert!(!journal.exists());
+        let written: HashMap<String, String> = read_json(&path).unwrap();
+        assert_eq!(written, cache);
+
+        let big = "x".repeat(JOURNAL_LIMIT as usize);
+        append_entries(&path, [("big", big.as_str())]).unwrap();
+        assert!(!journal.exists());
+    }
+
+    #[test]
+    fn test_concurrent_updates_keep_every_entry() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("hr_es.json");
+
+        let threads: Vec<_> = (0..8)
+            .map(|i| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    update_json(&path, |cache: &mut HashMap<String, usize>| {
+                        cache.insert(format!("word{}", i), i)
+                    })
+                    .unwrap();
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        let cache: HashMap<String, usize> = read_json(&path).unwrap();
+        assert_eq!(cache.len(), 8);
+    }
+}
@@ -0,0 +1,30 @@
+use crate::output::Console;
+pub use tokio_util::sync::CancellationToken;
+
+/// Exit status of a process stopped by Ctrl-C (128 + SIGINT)
+pub const CANCELLED_EXIT_CODE: i32 = 130;
+
+/// A token cancelled by the first Ctrl-C, so the running command can stop
+/// at its next step and save its progress. A second Ctrl-C exits at once
+pub fn cancel_on_ctrl_c(console: &Console) -> CancellationToken {
+    let token = CancellationToken::new();
+    let cancel = token.clone();
+    let console = console.clone();
+
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_err() {
+            return;
+        }
+        crate::say_err!(
+            console,
+            "\n⏹️  Stopping after the current step (press Ctrl-C again to quit now)..."
+        );
+        cancel.cancel();
+
+        if tokio::signal::ctrl_c().await.is_ok() {
+            std::process::exit(CANCELLED_EXIT_CODE);
+        }
+    });
+
+    token
+}
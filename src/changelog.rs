@@ -0,0 +1,161 @@
+//! What each run contributed to a deck, kept per deck next to its manifest.
+//! Every run gets the next version of the deck and tags its notes with it,
+//! so the notes of a version can be found in Anki
+
+use crate::cache_file;
+use crate::error::Result;
+use crate::events::BuildOutcome;
+use crate::manifest::ManifestDiff;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Tag of the notes a deck version added, e.g. "adb:v3"
+pub fn version_tag(version: u32) -> String {
+    format!("adb:v{}", version)
+}
+
+/// One run of a deck
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChangelogEntry {
+    pub version: u32,
+    /// The run in `history`, when it was recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<u64>,
+    /// YYYY-MM-DD
+    pub date: String,
+    pub tag: String,
+    pub outcome: BuildOutcome,
+    pub notes_added: usize,
+    /// Words added to the deck for the first time
+    pub added: Vec<String>,
+    /// Words whose translation changed
+    pub updated: Vec<String>,
+    /// Words that failed before and are in the deck now
+    pub resolved: Vec<String>,
+    pub failed: Vec<String>,
+}
+
+impl ChangelogEntry {
+    pub fn new(version: u32, date: String, outcome: BuildOutcome, changes: &ManifestDiff) -> Self {
+        Self {
+            version,
+            run_id: None,
+            date,
+            tag: version_tag(version),
+            outcome,
+            notes_added: 0,
+            added: changes.added.clone(),
+            updated: changes
+                .updated
+                .iter()
+                .map(|(word, _, _)| word.clone())
+                .collect(),
+            resolved: changes.resolved.clone(),
+            failed: changes.failed.clone(),
+        }
+    }
+
+    pub fn with_run_id(mut self, run_id: Option<u64>) -> Self {
+        self.run_id = run_id;
+        self
+    }
+
+    pub fn with_notes_added(mut self, notes_added: usize) -> Self {
+        self.notes_added = notes_added;
+        self
+    }
+}
+
+/// Every run of one deck, oldest first
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DeckChangelog {
+    pub deck_name: String,
+    pub entries: Vec<ChangelogEntry>,
+}
+
+impl DeckChangelog {
+    /// Changelog file for a deck, named like its manifest
+    pub fn path(changelog_dir: &Path, deck_name: &str) -> PathBuf {
+        let slug: String = deck_name
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '_' })
+            .collect();
+        changelog_dir.join(format!("{}.json", slug))
+    }
+
+    /// The changelog, empty for a deck never built
+    pub fn load(path: &Path) -> Self {
+        cache_file::read_json(path).unwrap_or_default()
+    }
+
+    /// Add a run, replacing an entry of the same version
+    pub fn record(path: &Path, deck_name: &str, entry: ChangelogEntry) -> Result<()> {
+        cache_file::update_json(path, |changelog: &mut DeckChangelog| {
+            changelog.deck_name = deck_name.to_string();
+            changelog
+                .entries
+                .retain(|existing| existing.version != entry.version);
+            changelog.entries.push(entry);
+        })
+    }
+
+    pub fn latest_version(&self) -> u32 {
+        self.entries
+            .iter()
+            .map(|entry| entry.version)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// The run that added a word first, if the changelog saw it
+    pub fn added_in(&self, word: &str) -> Option<&ChangelogEntry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.added.iter().any(|added| added == word))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_record_runs() {
+        let temp_dir = tempdir().unwrap();
+        let path = DeckChangelog::path(temp_dir.path(), "Croatian::Spanish");
+        assert_eq!(DeckChangelog::load(&path).latest_version(), 0);
+
+        let first = ManifestDiff {
+            added: vec!["dan".to_string(), "kuća".to_string()],
+            failed: vec!["pas".to_string()],
+            ..ManifestDiff::default()
+        };
+        let entry =
+            ChangelogEntry::new(1, "2024-06-01".to_string(), BuildOutcome::Completed, &first)
+                .with_run_id(Some(7))
+                .with_notes_added(4);
+        DeckChangelog::record(&path, "Croatian::Spanish", entry).unwrap();
+
+        let second = ManifestDiff {
+            updated: vec![("dan".to_string(), "día".to_string(), "jornada".to_string())],
+            resolved: vec!["pas".to_string()],
+            ..ManifestDiff::default()
+        };
+        let entry = ChangelogEntry::new(
+            2,
+            "2024-06-02".to_string(),
+            BuildOutcome::Completed,
+            &second,
+        );
+        DeckChangelog::record(&path, "Croatian::Spanish", entry).unwrap();
+
+        let changelog = DeckChangelog::load(&path);
+        assert_eq!(changelog.deck_name, "Croatian::Spanish");
+        assert_eq!(changelog.latest_version(), 2);
+        assert_eq!(changelog.entries[1].tag, "adb:v2");
+        assert_eq!(changelog.entries[1].updated, vec!["dan"]);
+        assert_eq!(changelog.added_in("kuća").unwrap().run_id, Some(7));
+        assert!(changelog.added_in("pas").is_none());
+    }
+}
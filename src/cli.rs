@@ -1,45 +1,356 @@
+use crate::ankiweb::{
+    AnkiBackend, BidirectionalStrategy, DuplicatePolicy, DuplicateScope, SchedulingPreset,
+    SortField,
+};
+use crate::audio::AudioProviderKind;
+use crate::cancel::{cancel_on_ctrl_c, CancellationToken};
+use crate::config::{Config, Profile};
+use crate::deadline::{parse_duration, Deadline};
+use crate::error::AnkiDeckBuilderError;
+use crate::growth::GrowthState;
+use crate::http::HttpSettings;
+use crate::language::{
+    CacheMaxAge, FrequencySourceKind, FrequencyThreshold, ListSize, Selection, SourceWeight,
+    TranslatorKind,
+};
+use crate::manifest::RunManifest;
+use crate::output::Console;
+use crate::say;
+use crate::say_err;
+use crate::sink::{csv_media_dir, open_sink, DeckSink, OutputBackend};
 use anyhow::Result;
-use clap::{Parser, Subcommand};
+use clap::builder::{EnumValueParser, TypedValueParser};
+use clap::{Args, FromArgMatches, Parser, Subcommand, ValueEnum};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
 
 #[derive(Parser)]
 #[command(name = "anki-deck-builder")]
 #[command(about = "Build language learning Anki decks automatically", long_about = None)]
 #[command(version)]
 pub struct Cli {
+    /// Emit machine-readable JSON on stdout (human output goes to stderr)
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    /// Never prompt; fail on missing options instead (implied when stdin is not a terminal)
+    #[arg(long, global = true)]
+    pub non_interactive: bool,
+
+    /// Never change anything in Anki: actions that would add, edit or delete are refused
+    #[arg(long, global = true)]
+    pub read_only: bool,
+
+    /// Language of the messages: en, es or hr (defaults to the base language of the decks)
+    #[arg(long, global = true, value_name = "LOCALE")]
+    pub locale: Option<String>,
+
+    /// Write plain ASCII instead of emoji and symbols, e.g. for consoles that garble them
+    #[arg(long, global = true)]
+    pub no_emoji: bool,
+
+    /// Don't color the output (also when NO_COLOR is set)
+    #[arg(long, global = true)]
+    pub no_color: bool,
+
+    /// Stop cleanly once the run has taken this long (e.g. "90s", "30m", "1h30m")
+    #[arg(long, global = true, value_parser = parse_duration)]
+    pub max_duration: Option<std::time::Duration>,
+
+    /// Anki profile to work in; it is opened first and the previous one reopened afterwards
+    #[arg(long, global = true, conflicts_with = "read_only")]
+    pub profile: Option<String>,
+
+    /// Show more on the console: -v for debug messages, -vv for HTTP traces too
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+
+    /// Write the debug log to this file instead of a new one under the data directory
+    #[arg(long, global = true)]
+    pub log_file: Option<PathBuf>,
+
+    /// Keep downloaded and looked-up data (frequency lists, translations, media) here instead of the platform's cache directory
+    #[arg(long, global = true, value_name = "DIR")]
+    pub cache_dir: Option<PathBuf>,
+
+    /// Revalidate cached frequency lists older than this ("7d", "12h"), or "never" [default: 30d]
+    #[arg(long, global = true, value_name = "AGE")]
+    pub cache_max_age: Option<CacheMaxAge>,
+
+    /// Start Anki when it isn't running and wait for AnkiConnect to come up
+    #[arg(long, global = true)]
+    pub launch_anki: bool,
+
+    /// Anki program for --launch-anki, when not where its installer puts it
+    #[arg(long, global = true, value_name = "PATH")]
+    pub anki_path: Option<PathBuf>,
+
+    /// Send every HTTP request through this proxy (e.g. "http://proxy.corp:3128")
+    #[arg(long, global = true)]
+    pub proxy: Option<String>,
+
+    /// Also trust the root certificates in this PEM file
+    #[arg(long, global = true)]
+    pub ca_cert: Option<PathBuf>,
+
+    /// Accept invalid TLS certificates (for diagnosing proxy problems only)
+    #[arg(long, global = true)]
+    pub insecure: bool,
+
     #[command(subcommand)]
     pub command: Commands,
 }
 
 #[derive(Subcommand)]
 pub enum Commands {
-    /// Test connection to AnkiConnect
-    Test,
+    /// Check AnkiConnect, translators, and frequency sources, with latencies
+    Test {
+        /// Also check the dictionary, pronunciation, and image services
+        #[arg(long)]
+        full: bool,
+    },
+
+    /// Diagnose the whole setup and explain how to fix each problem found
+    Doctor {
+        /// Target language the translators and frequency lists are checked for
+        #[arg(short, long, default_value = "hr")]
+        target_language: String,
+
+        /// Base language the translators are checked for
+        #[arg(short, long, default_value = "en")]
+        base_language: String,
+
+        /// Frequency source to check
+        #[arg(long, value_parser = FrequencySourceArg::parser(), default_value = "opensubtitles")]
+        frequency_source: FrequencySourceKind,
+
+        /// Frequency list size to check
+        #[arg(long, value_parser = ListSizeArg::parser(), default_value = "50k")]
+        list_size: ListSize,
+    },
 
     /// Create a new language learning deck
-    Create {
-        /// Target language to learn (e.g., "Croatian", "hr")
+    Create(Box<CreateArgs>),
+
+    /// Create a deck with a full-screen dashboard showing each step, live
+    /// progress, and the translations as they arrive (reviewed inline with --review)
+    Tui(Box<CreateArgs>),
+
+    /// Build a deck again from the manifest a create run saved (e.g. on a new
+    /// computer), with the same words, translations, and options
+    Rebuild {
+        /// Manifest file, from the manifests folder of the data directory
+        manifest: PathBuf,
+
+        /// Name the rebuilt deck differently
+        #[arg(short, long)]
+        deck_name: Option<String>,
+    },
+
+    /// Add the next most frequent words to a deck; run it on a schedule (e.g. weekly from cron)
+    Grow {
+        /// Deck to grow; created on the first run
+        deck_name: String,
+
+        /// Words added by each run (remembered for the next runs)
+        #[arg(long)]
+        per_week: Option<usize>,
+
+        /// Language to learn; only needed the first time a deck is grown
         #[arg(short, long)]
         target_language: Option<String>,
 
-        /// Base language for translations (e.g., "Spanish", "es")
+        /// Language of the translations on the first run (defaults to English)
         #[arg(short, long)]
         base_language: Option<String>,
 
-        /// Number of words per part of speech
+        /// Sync the collection with AnkiWeb once the run completes
+        #[arg(long, default_value = "false")]
+        sync: bool,
+    },
+
+    /// Translate flagged notes again and update them in place. Flag a note by
+    /// giving it the "fix" tag in Anki's browser
+    Fix {
+        /// Language the deck teaches, telling the word from its translation
+        #[arg(short, long)]
+        target_language: String,
+
+        /// Tag marking the notes to fix; taken off each note once fixed
+        #[arg(long, default_value = crate::fix::DEFAULT_FIX_TAG)]
+        tag: String,
+
+        /// Anki search picking the notes instead of the tag (e.g. "added:7 pos:verb")
+        #[arg(long, conflicts_with = "tag")]
+        query: Option<String>,
+
+        /// Only fix notes in this deck (and its subdecks)
+        #[arg(short, long)]
+        deck: Option<String>,
+
+        /// Show the new translations without changing any note
+        #[arg(long, default_value = "false")]
+        dry_run: bool,
+
+        /// Translator to ask first
+        #[arg(long, value_parser = TranslatorArg::parser(), default_value = "mymemory")]
+        translator: TranslatorKind,
+
+        /// LibreTranslate server to fall back on (defaults to LIBRETRANSLATE_URL)
+        #[arg(long)]
+        libretranslate_url: Option<String>,
+
+        /// Also translate each answer back and require it to lead to the word
+        #[arg(long, default_value = "false")]
+        back_translate: bool,
+    },
+
+    /// Show what building a word list into an existing deck would add,
+    /// remove, and translate differently, without changing the deck
+    Diff {
+        /// Deck to compare with
+        deck_name: String,
+
+        /// Language the deck teaches
+        #[arg(short, long)]
+        target_language: String,
+
+        /// Languages of the translations (e.g. "es,en")
+        #[arg(short, long, default_value = "en", value_delimiter = ',')]
+        base_languages: Vec<String>,
+
+        /// Number of words per part of speech, as given to create
         #[arg(short, long, default_value = "100")]
         words_per_pos: usize,
 
-        /// Name of the deck to create
-        #[arg(short, long)]
-        deck_name: Option<String>,
+        /// Translator to ask first, as given to create
+        #[arg(long, value_parser = TranslatorArg::parser(), default_value = "mymemory")]
+        translator: TranslatorKind,
+
+        /// LibreTranslate server to fall back on (defaults to LIBRETRANSLATE_URL)
+        #[arg(long)]
+        libretranslate_url: Option<String>,
+    },
+
+    /// Download frequency lists and fill the translation cache ahead of time,
+    /// without Anki, so a later create with the same options is nearly instant
+    Prefetch {
+        /// Languages to learn (e.g. "hr,sr"); their frequency lists are downloaded in parallel
+        #[arg(short, long, required = true, value_delimiter = ',')]
+        target_languages: Vec<String>,
+
+        /// Languages the words are translated into (e.g. "es,en")
+        #[arg(short, long, default_value = "en", value_delimiter = ',')]
+        base_languages: Vec<String>,
+
+        /// Number of words per part of speech, as given to create
+        #[arg(short, long, default_value = "100")]
+        words_per_pos: usize,
+
+        /// How words are spread over parts of speech, as given to create
+        #[arg(long, value_parser = SelectionArg::parser(), default_value = "equal")]
+        selection: Selection,
+
+        /// Where to get word frequency lists from
+        #[arg(long, value_parser = FrequencySourceArg::parser(), default_value = "opensubtitles")]
+        frequency_source: FrequencySourceKind,
+
+        /// How much of the frequency list words are chosen from
+        #[arg(long, value_parser = ListSizeArg::parser(), default_value = "50k")]
+        list_size: ListSize,
+
+        /// Number of words translated at the same time
+        #[arg(long, default_value = "2")]
+        concurrency: usize,
+
+        /// Milliseconds between translation requests, to stay within the translators' rate limits
+        #[arg(long, default_value = "250")]
+        delay_ms: u64,
+
+        /// Translator to ask first, as given to create
+        #[arg(long, value_parser = TranslatorArg::parser(), default_value = "mymemory")]
+        translator: TranslatorKind,
+
+        /// LibreTranslate server to fall back on, as given to create (defaults to LIBRETRANSLATE_URL)
+        #[arg(long)]
+        libretranslate_url: Option<String>,
+    },
+
+    /// Check a frequency list before building decks from it: how its words
+    /// spread over parts of speech, numbers, stray letters and words in
+    /// another script, words listed twice, and how much of the corpus it covers
+    InspectFrequency {
+        /// Language whose list to inspect
+        language: String,
+
+        /// Where to get word frequency lists from
+        #[arg(long, value_parser = FrequencySourceArg::parser(), default_value = "opensubtitles")]
+        frequency_source: FrequencySourceKind,
+
+        /// How much of the frequency list to inspect
+        #[arg(long, value_parser = ListSizeArg::parser(), default_value = "50k")]
+        list_size: ListSize,
+
+        /// Suspicious words and duplicates shown of each kind
+        #[arg(long, default_value = "10")]
+        examples: usize,
+    },
 
-        /// Dry run - preview without creating the deck
+    /// Find which generated decks have a word, from the saved manifests and
+    /// optionally the Anki collection
+    WhereIs {
+        /// Word to look for, in the language learned or as a translation
+        word: String,
+
+        /// Also search the notes in Anki, reporting their tags and note IDs
+        #[arg(long, default_value = "false")]
+        anki: bool,
+    },
+
+    /// Serve an HTTP API for starting builds and following their progress,
+    /// for web front ends
+    Serve {
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:8766")]
+        address: std::net::SocketAddr,
+    },
+
+    /// Delete the notes a create run added to Anki
+    Undo {
+        /// Run number, as listed by `history` (defaults to the most recent
+        /// run that added notes)
+        run_id: Option<u64>,
+
+        /// List the notes that would be deleted, without deleting them
         #[arg(long, default_value = "false")]
         dry_run: bool,
 
-        /// Create bidirectional cards (both target→base and base→target)
-        #[arg(long, default_value = "true")]
-        bidirectional: bool,
+        /// Delete without asking first
+        #[arg(short, long, default_value = "false")]
+        yes: bool,
+    },
+
+    /// List supported languages and what data is available for each
+    Languages,
+
+    /// List past create runs, most recent first
+    History {
+        /// How many runs to list
+        #[arg(short = 'n', long, default_value = "20")]
+        limit: usize,
+
+        #[command(subcommand)]
+        action: Option<HistoryAction>,
+    },
+
+    /// Show what each run added to a deck, by the version its notes are
+    /// tagged with (adb:v1, adb:v2, …)
+    Changelog {
+        /// Deck name, as given to create
+        deck_name: String,
     },
 
     /// Configure AnkiConnect settings
@@ -51,478 +362,5496 @@ pub enum Commands {
         /// Show current configuration
         #[arg(long, default_value = "false")]
         show: bool,
+
+        #[command(subcommand)]
+        action: Option<ConfigAction>,
     },
-}
 
-pub async fn run() -> Result<()> {
-    let cli = Cli::parse();
+    /// Manage cards in an existing deck
+    Manage {
+        #[command(subcommand)]
+        action: ManageAction,
+    },
 
-    match cli.command {
-        Commands::Test => handle_test().await,
-        Commands::Create {
-            target_language,
-            base_language,
-            words_per_pos,
-            deck_name,
-            dry_run,
-            bidirectional,
-        } => {
-            handle_create(
-                target_language,
-                base_language,
-                words_per_pos,
-                deck_name,
-                dry_run,
-                bidirectional,
-            )
-            .await
-        }
-        Commands::Config {
-            ankiconnect_url,
-            show,
-        } => handle_config(ankiconnect_url, show).await,
-    }
+    /// Any other command runs `anki-deck-builder-<command>` from PATH
+    #[command(external_subcommand)]
+    External(Vec<OsString>),
 }
 
-async fn handle_test() -> Result<()> {
-    use crate::{AnkiClient, Config};
+#[derive(Args, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CreateArgs {
+    /// Target language to learn (e.g., "Croatian", "hr"); several (e.g. "hr,it") build one deck each, at the same time
+    #[arg(short, long)]
+    pub target_language: Option<String>,
 
-    println!("🔍 Testing AnkiConnect connection...\n");
+    /// Base language for translations (e.g., "Spanish", "es")
+    #[arg(short, long)]
+    pub base_language: Option<String>,
 
-    let config = Config::new()?;
-    println!("📍 AnkiConnect URL: {}", config.ankiconnect_url);
+    /// Several base languages shown together on each card (e.g. "es,en"); the first is the main one
+    #[arg(long, value_delimiter = ',', conflicts_with = "base_language")]
+    pub base_languages: Vec<String>,
 
-    let client = AnkiClient::new(config.ankiconnect_url.clone())?;
+    /// Number of words per part of speech
+    #[arg(short, long, default_value = "100")]
+    pub words_per_pos: usize,
 
-    match client.verify_connection().await {
-        Ok(()) => {
-            println!("✅ Successfully connected to AnkiConnect!\n");
+    /// Name of the deck to create
+    #[arg(short, long)]
+    pub deck_name: Option<String>,
 
-            // Try to get decks
-            match client.get_decks().await {
-                Ok(decks) => {
-                    println!("📚 Available decks ({}):", decks.len());
-                    for deck in decks.iter().take(10) {
-                        println!("  - {}", deck);
-                    }
-                    if decks.len() > 10 {
-                        println!("  ... and {} more", decks.len() - 10);
-                    }
-                }
-                Err(e) => {
-                    println!("⚠️  Could not retrieve decks: {}", e);
-                }
-            }
+    /// Default deck name from a template (e.g. "{target} for {base} speakers — {date}"); see the README for its placeholders
+    #[arg(long, visible_alias = "name-template", conflicts_with = "deck_name")]
+    pub deck_name_pattern: Option<String>,
 
-            Ok(())
-        }
-        Err(e) => {
-            println!("❌ Failed to connect to AnkiConnect");
-            println!("\nError: {}\n", e);
-            println!("💡 Troubleshooting:");
-            println!("  1. Make sure Anki is running");
-            println!("  2. Verify AnkiConnect add-on is installed (code: 2055492159)");
-            println!(
-                "  3. Check that AnkiConnect is accessible at {}",
-                config.ankiconnect_url
-            );
-            println!("  4. Try restarting Anki if the add-on was just installed");
+    /// Deck description from a template (e.g. "{pair}: {words} words{sources}") instead of the built-in study guide; "" leaves the description alone. See the README for its placeholders
+    #[arg(long)]
+    pub deck_description: Option<String>,
 
-            Err(e.into())
-        }
-    }
-}
+    /// Settings for a language pair from the "profiles" in config.json; options given here win
+    #[arg(long)]
+    pub config_profile: Option<String>,
 
-async fn handle_create(
-    target_language: Option<String>,
-    base_language: Option<String>,
-    words_per_pos: usize,
-    deck_name: Option<String>,
-    dry_run: bool,
-    bidirectional: bool,
-) -> Result<()> {
-    use crate::language::{get_language, get_prioritized_languages};
-    use dialoguer::{theme::ColorfulTheme, Confirm, Input, Select};
+    /// Dry run - preview without creating the deck
+    #[arg(long, default_value = "false")]
+    pub dry_run: bool,
 
-    println!("🚀 Anki Deck Builder - Language Learning Deck Creator\n");
+    /// Words a dry run translates and shows as the cards they would become
+    #[arg(long, default_value = "5", value_name = "N")]
+    pub preview: usize,
 
-    // Get target language (either from arg or interactive prompt)
-    let target_lang = match target_language {
-        Some(lang_input) => {
-            match get_language(&lang_input) {
-                Some(lang) => {
-                    println!("🎯 Target language: {} ({})", lang.name, lang.code);
-                    lang
-                }
-                None => {
-                    eprintln!("❌ Unsupported language: {}", lang_input);
-                    eprintln!("Use 'Croatian', 'hr', or run without --target-language for a selection menu");
-                    return Err(anyhow::anyhow!("Unsupported language: {}", lang_input));
-                }
-            }
-        }
-        None => {
-            let languages = get_prioritized_languages();
-            let lang_names: Vec<String> = languages
-                .iter()
-                .map(|l| format!("{} ({})", l.name, l.code))
-                .collect();
+    /// Create bidirectional cards (both target→base and base→target)
+    #[arg(long, default_value = "true")]
+    pub bidirectional: bool,
 
-            let selection = Select::with_theme(&ColorfulTheme::default())
-                .with_prompt("Select target language to learn")
-                .items(&lang_names)
-                .default(0) // Croatian by default
-                .interact()?;
+    /// How bidirectional cards are made: separate notes, or one note with a reversed template
+    #[arg(long, value_parser = StrategyArg::parser(), default_value = "notes")]
+    pub bidirectional_strategy: BidirectionalStrategy,
 
-            let selected = languages[selection].clone();
-            println!("🎯 Target language: {} ({})", selected.name, selected.code);
-            selected
-        }
-    };
+    /// Field Anki sorts the browser by ("rank" lists notes in learning order)
+    #[arg(long, value_parser = SortFieldArg::parser(), default_value = "front")]
+    pub sort_field: SortField,
 
-    // Get base language (either from arg or interactive prompt)
-    let base_lang = match base_language {
-        Some(lang_input) => match get_language(&lang_input) {
-            Some(lang) => {
-                println!("🏠 Base language: {} ({})", lang.name, lang.code);
-                lang
-            }
-            None => {
-                eprintln!("❌ Unsupported language: {}", lang_input);
-                return Err(anyhow::anyhow!("Unsupported language: {}", lang_input));
-            }
-        },
-        None => {
-            let languages = get_prioritized_languages();
-            let lang_names: Vec<String> = languages
-                .iter()
-                .map(|l| format!("{} ({})", l.name, l.code))
-                .collect();
+    /// Where to get word frequency lists from
+    #[arg(long, value_parser = FrequencySourceArg::parser(), default_value = "opensubtitles")]
+    pub frequency_source: FrequencySourceKind,
 
-            let selection = Select::with_theme(&ColorfulTheme::default())
-                .with_prompt("Select base language (for translations)")
-                .items(&lang_names)
-                .default(1) // Spanish by default
-                .interact()?;
+    /// How much of the frequency list words are chosen from
+    #[arg(long, value_parser = ListSizeArg::parser(), default_value = "50k")]
+    pub list_size: ListSize,
 
-            let selected = languages[selection].clone();
-            println!("🏠 Base language: {} ({})", selected.name, selected.code);
-            selected
-        }
-    };
+    /// Weight of each source in a merged list, e.g. opensubtitles=2,leipzig=1 (unlisted sources weigh 1, 0 leaves one out)
+    #[arg(long, value_delimiter = ',')]
+    pub merge_weights: Vec<SourceWeight>,
 
-    // Validate that target and base languages are different
-    if target_lang.code == base_lang.code {
-        eprintln!("❌ Target and base languages must be different!");
-        return Err(anyhow::anyhow!("Target and base languages are the same"));
-    }
+    /// Skip words spelled like their translation, optionally with the similarity (0-1) that counts as a cognate
+    #[arg(
+        long,
+        num_args = 0..=1,
+        default_missing_value = "0.7",
+        value_name = "THRESHOLD",
+        value_parser = parse_threshold
+    )]
+    pub skip_cognates: Option<f64>,
 
-    // Get deck name (either from arg or generate/prompt)
-    let final_deck_name = match deck_name {
-        Some(name) => {
-            println!("📚 Deck name: {}", name);
-            name
-        }
-        None => {
-            let default_name = format!(
-                "{} → {} (Top {} Words)",
-                target_lang.name,
-                base_lang.name,
-                words_per_pos * 8
-            ); // 8 parts of speech
+    /// Scheduling options for the deck: new cards per day, learning steps and review limit
+    #[arg(long, value_parser = PresetArg::parser())]
+    pub preset: Option<SchedulingPreset>,
 
-            let use_default = Confirm::with_theme(&ColorfulTheme::default())
-                .with_prompt(format!("Use default deck name: '{}'?", default_name))
-                .default(true)
-                .interact()?;
+    /// How words are spread over parts of speech; all pick words-per-pos × the number of parts of speech in total
+    #[arg(long, value_parser = SelectionArg::parser(), default_value = "equal")]
+    pub selection: Selection,
 
-            if use_default {
-                println!("📚 Deck name: {}", default_name);
-                default_name
-            } else {
-                let custom_name: String = Input::with_theme(&ColorfulTheme::default())
-                    .with_prompt("Enter custom deck name")
-                    .interact_text()?;
-                println!("📚 Deck name: {}", custom_name);
-                custom_name
-            }
-        }
-    };
+    /// Parts of speech to pick words for, comma-separated (e.g. nouns,verbs), or all
+    #[arg(
+        long = "pos",
+        value_delimiter = ',',
+        default_value = "all",
+        value_name = "PARTS"
+    )]
+    pub pos: Vec<String>,
 
-    println!("\n📋 Configuration Summary:");
-    println!(
-        "  Target language: {} ({})",
-        target_lang.name, target_lang.code
-    );
-    println!("  Base language: {} ({})", base_lang.name, base_lang.code);
-    println!("  Words per part of speech: {}", words_per_pos);
-    let estimated_cards = if bidirectional {
-        words_per_pos * 8 * 2 // Double for bidirectional
-    } else {
-        words_per_pos * 8
-    };
-    println!(
-        "  Total cards: ~{} (8 parts of speech{})",
-        estimated_cards,
-        if bidirectional { ", bidirectional" } else { "" }
-    );
-    println!("  Deck name: {}", final_deck_name);
-    println!(
-        "  Bidirectional: {}",
-        if bidirectional { "yes" } else { "no" }
-    );
-    println!("  Dry run: {}", dry_run);
+    /// Instead of a number per part of speech, pick every word the corpus counts at least this many times
+    #[arg(
+        long,
+        value_name = "COUNT",
+        conflicts_with_all = ["words_per_pos", "selection", "top_percent"]
+    )]
+    pub min_frequency: Option<usize>,
 
-    if dry_run {
-        println!("\n🔍 Dry run mode - no deck will be created");
-        println!("✅ Configuration validated successfully!");
-        return Ok(());
-    }
+    /// Instead of a number per part of speech, pick the most frequent words that together make up this percentage of the corpus
+    #[arg(
+        long,
+        value_name = "PERCENT",
+        value_parser = parse_percent,
+        conflicts_with_all = ["words_per_pos", "selection"]
+    )]
+    pub top_percent: Option<f64>,
 
-    // Phase 4: Load frequency data
-    println!("\n📊 Loading {} word frequency data...", target_lang.name);
+    /// Build a gentle first deck from a hand-picked list of essentials (greetings, numbers, days, question words, survival phrases) instead of the frequency list
+    #[arg(
+        long,
+        default_value = "false",
+        conflicts_with_all = ["words_per_pos", "selection", "pos", "min_frequency", "top_percent", "include_phrases", "from_report"]
+    )]
+    pub starter: bool,
 
-    use crate::{language::load_frequency_data, Config};
-    use indicatif::{ProgressBar, ProgressStyle};
+    /// Also add this many common phrases (e.g. "hvala lijepa", "por favor") to a Phrases subdeck
+    #[arg(long, default_value = "0", value_name = "N")]
+    pub include_phrases: usize,
 
-    let config = Config::new()?;
-    let cache_dir = config.cache_dir().clone();
+    /// Also add the numbers one to a hundred and the first ten ordinals, generated by rule, to a Numbers subdeck
+    #[arg(long, default_value = "false")]
+    pub include_numbers: bool,
 
-    let spinner = ProgressBar::new_spinner();
-    spinner.set_style(
-        ProgressStyle::default_spinner()
-            .template("{spinner:.green} {msg}")
-            .unwrap(),
-    );
-    spinner.set_message(format!("Fetching {} frequency data...", target_lang.name));
-    spinner.enable_steady_tick(std::time::Duration::from_millis(100));
+    /// Also add the days of the week, the months and clock times, generated by rule, to a Dates subdeck
+    #[arg(long, default_value = "false")]
+    pub include_dates: bool,
 
-    let freq_data = load_frequency_data(&target_lang.code, &cache_dir).await?;
-    spinner.finish_with_message(format!("✅ Loaded {} word data", target_lang.name));
+    /// Skip words shorter than this many letters
+    #[arg(long)]
+    pub min_word_length: Option<usize>,
 
-    // Get top words for each POS
-    use crate::language::PartOfSpeech;
-    let all_words = freq_data.get_all_top_words(words_per_pos);
-
-    println!("\n📝 Word selection:");
-    println!(
-        "  Nouns: {} words",
-        freq_data
-            .get_top_words(&PartOfSpeech::Noun, words_per_pos)
-            .len()
-    );
-    println!(
-        "  Verbs: {} words",
-        freq_data
-            .get_top_words(&PartOfSpeech::Verb, words_per_pos)
-            .len()
-    );
-    println!(
-        "  Adjectives: {} words",
-        freq_data
-            .get_top_words(&PartOfSpeech::Adjective, words_per_pos)
-            .len()
-    );
-    println!("  Total: {} words selected", all_words.len());
+    /// Skip words longer than this many letters
+    #[arg(long)]
+    pub max_word_length: Option<usize>,
 
-    if !all_words.is_empty() {
-        println!("\n🔤 Sample words:");
-        for word in all_words.iter().take(5) {
-            println!("  - {} ({:?})", word.text, word.pos);
-        }
-        if all_words.len() > 5 {
-            println!("  ... and {} more", all_words.len() - 5);
-        }
-    }
+    /// Only use words matching this regex, or glob with a "glob:" prefix (can be repeated)
+    #[arg(long)]
+    pub include: Vec<String>,
 
-    // Phase 5: Translate words
-    println!(
-        "\n🌐 Translating {} words from {} to {}...",
-        all_words.len(),
-        target_lang.name,
-        base_lang.name
-    );
+    /// Skip words matching this regex, or glob with a "glob:" prefix (can be repeated)
+    #[arg(long)]
+    pub exclude: Vec<String>,
 
-    use crate::language::{MyMemoryClient, Translator};
+    /// Skip the words listed in this file, one per line (e.g. words you already know)
+    #[arg(long)]
+    pub exclude_file: Option<PathBuf>,
 
-    let translator = MyMemoryClient::new(Some(cache_dir.clone()))?;
+    /// Skip words that already have cards in this Anki deck (can be repeated)
+    #[arg(long)]
+    pub exclude_deck: Vec<String>,
 
-    let progress = ProgressBar::new(all_words.len() as u64);
-    progress.set_style(
-        ProgressStyle::default_bar()
-            .template("{msg} [{bar:40}] {pos}/{len} ({percent}%)")
-            .unwrap()
-            .progress_chars("=>-"),
-    );
-    progress.set_message("Translating");
+    /// Add notes with this existing note type instead of Basic
+    #[arg(long)]
+    pub model: Option<String>,
 
-    let mut translations: Vec<(String, String, PartOfSpeech)> = Vec::new();
+    /// Note type fields that receive the card content (e.g. "Front=Term,Back=Meaning")
+    #[arg(long, value_delimiter = ',', requires = "model")]
+    pub field_map: Vec<String>,
 
-    for word in &all_words {
-        let translation = translator
-            .translate(&word.text, &target_lang.code, &base_lang.code)
-            .await?;
-        translations.push((word.text.clone(), translation, word.pos.clone()));
-        progress.inc(1);
-    }
+    /// Field used to detect duplicate notes (defaults to the note type's own check)
+    #[arg(long)]
+    pub dedup_field: Option<String>,
 
-    progress.finish_with_message("✅ Translation complete");
+    /// What to do with words Anki already has notes for (asked when not given; skipped without a terminal)
+    #[arg(long, value_parser = DuplicatesArg::parser())]
+    pub duplicates: Option<DuplicatePolicy>,
 
-    println!("\n📝 Sample translations:");
-    for (croatian, spanish, pos) in translations.iter().take(10) {
-        println!("  {} → {} ({:?})", croatian, spanish, pos);
-    }
-    if translations.len() > 10 {
-        println!("  ... and {} more", translations.len() - 10);
-    }
+    /// Add notes even when Anki already has a duplicate of them
+    #[arg(long, default_value = "false", conflicts_with = "duplicates")]
+    pub allow_duplicates: bool,
 
-    // Phase 6-7: Create Anki deck and add cards
-    println!("\n📚 Creating Anki deck: '{}'...", final_deck_name);
+    /// Which notes Anki checks for duplicates: this deck only, or every deck
+    #[arg(long, value_parser = DuplicateScopeArg::parser(), default_value = "collection")]
+    pub duplicate_scope: DuplicateScope,
 
-    use crate::AnkiClient;
+    /// Extra tag to add to every generated note (can be repeated)
+    #[arg(long = "tag")]
+    pub tags: Vec<String>,
 
-    let anki_client = AnkiClient::new(config.ankiconnect_url.clone())?;
+    /// Review each translation before adding it (progress is saved and can be resumed)
+    #[arg(long, default_value = "false")]
+    pub review: bool,
 
-    // Verify AnkiConnect is running
-    let verify_spinner = ProgressBar::new_spinner();
-    verify_spinner.set_style(
-        ProgressStyle::default_spinner()
-            .template("{spinner:.green} {msg}")
-            .unwrap(),
-    );
-    verify_spinner.set_message("Checking AnkiConnect connection...");
-    verify_spinner.enable_steady_tick(std::time::Duration::from_millis(100));
+    /// Add a picture to noun cards from Openverse (openly licensed images)
+    #[arg(long, default_value = "false")]
+    pub with_images: bool,
 
-    match anki_client.verify_connection().await {
-        Ok(()) => {
-            verify_spinner.finish_with_message("✅ Connected to AnkiConnect");
-        }
-        Err(e) => {
-            verify_spinner.finish_with_message("❌ Failed to connect");
-            eprintln!("\n❌ Could not connect to AnkiConnect: {}", e);
-            eprintln!("\n💡 Make sure:");
-            eprintln!("  1. Anki is running");
-            eprintln!("  2. AnkiConnect add-on is installed");
-            eprintln!("  3. Try running: make run ARGS=\"test\"");
-            return Err(e.into());
-        }
-    }
+    /// Add a present-tense conjugation table to verb cards (Croatian, Spanish, Italian)
+    #[arg(long, default_value = "false")]
+    pub with_conjugations: bool,
 
-    // Create deck
-    match anki_client.create_deck(&final_deck_name).await {
-        Ok(deck_id) => {
-            println!("✅ Created deck with ID: {}", deck_id);
-        }
-        Err(e) => {
-            // Deck might already exist, which is ok
-            tracing::warn!("Deck creation returned: {}", e);
-            println!("ℹ️  Using existing deck '{}'", final_deck_name);
-        }
-    }
+    /// Add the genitive singular and nominative plural to noun cards, from Wiktionary (languages with cases)
+    #[arg(long, default_value = "false")]
+    pub with_declensions: bool,
 
-    // Add cards
-    let total_cards = if bidirectional {
-        translations.len() * 2
-    } else {
-        translations.len()
-    };
+    /// Look up each word's senses on Wiktionary: fix English translations that belong to another part of speech and list the other meanings on the card
+    #[arg(long, default_value = "false")]
+    pub with_senses: bool,
 
-    println!(
-        "\n📝 Adding {} cards to deck{}",
-        total_cards,
-        if bidirectional {
-            " (bidirectional)"
-        } else {
-            ""
-        }
-    );
+    /// Add a native speaker's recording of each word, credited in a Source field
+    #[arg(long, default_value = "false")]
+    pub with_audio: bool,
 
-    use crate::Note;
+    /// Where recordings are looked for, in order (Forvo needs a key in FORVO_API_KEY or config.json)
+    #[arg(long, value_parser = AudioProviderArg::parser(), value_delimiter = ',', default_values = ["forvo", "commons"])]
+    pub audio_providers: Vec<AudioProviderKind>,
 
-    let card_progress = ProgressBar::new(total_cards as u64);
-    card_progress.set_style(
-        ProgressStyle::default_bar()
-            .template("{msg} [{bar:40}] {pos}/{len} ({percent}%)")
-            .unwrap()
-            .progress_chars("=>-"),
-    );
-    card_progress.set_message("Adding cards");
-
-    let mut success_count = 0;
-    let mut error_count = 0;
-
-    for (croatian, spanish, _pos) in &translations {
-        // Direction 1: Croatian (target) → Spanish (base)
-        // You see Croatian and recall the Spanish meaning
-        let front1 = croatian.clone();
-        let back1 = spanish.clone();
-        let note1 = Note::new(final_deck_name.clone(), front1, back1).with_tags(vec![
-            "auto-generated".to_string(),
-            "croatian-to-spanish".to_string(),
-        ]);
+    /// Add a memory hook to a Hint field: a word of the base language that sounds like the word learned
+    #[arg(long, default_value = "false")]
+    pub with_mnemonics: bool,
 
-        match anki_client.add_note(&note1).await {
-            Ok(_) => success_count += 1,
-            Err(e) => {
-                tracing::warn!("Failed to add note for '{}→{}': {}", croatian, spanish, e);
-                error_count += 1;
-            }
-        }
-        card_progress.inc(1);
+    /// Add where each word comes from, or which language it was borrowed from, to an Etymology field, from Wiktionary
+    #[arg(long, default_value = "false")]
+    pub with_etymology: bool,
 
-        // Direction 2 (if bidirectional): Spanish (base) → Croatian (target)
-        // You see Spanish and recall the Croatian word
-        if bidirectional {
-            let front2 = spanish.clone();
-            let back2 = croatian.clone();
-            let note2 = Note::new(final_deck_name.clone(), front2, back2).with_tags(vec![
-                "auto-generated".to_string(),
-                "spanish-to-croatian".to_string(),
-            ]);
+    /// Add a sentence using each word, with its translation, to an Example field, written by the LLM set up under `llm` in config.json
+    #[arg(long, default_value = "false")]
+    pub with_examples: bool,
 
-            match anki_client.add_note(&note2).await {
-                Ok(_) => success_count += 1,
-                Err(e) => {
-                    tracing::warn!("Failed to add note for '{}→{}': {}", spanish, croatian, e);
-                    error_count += 1;
-                }
-            }
-            card_progress.inc(1);
-        }
-    }
+    /// Enrichments to look up first, in this order; the rest follow
+    #[arg(long, value_delimiter = ',', value_parser = clap::builder::PossibleValuesParser::new(crate::enrich::ENRICHER_NAMES))]
+    pub enrichment_order: Vec<String>,
 
-    card_progress.finish_with_message("✅ Cards added");
+    /// Leave out the romanized Reading field added for non-Latin scripts (e.g. Russian, Japanese)
+    #[arg(long, default_value = "false")]
+    pub no_reading: bool,
 
-    println!("\n🎉 Deck creation complete!");
-    println!("  ✅ {} cards added successfully", success_count);
-    if error_count > 0 {
-        println!("  ⚠️  {} cards failed (may be duplicates)", error_count);
-    }
-    println!("  📚 Deck name: {}", final_deck_name);
-    println!(
-        "\n💡 Open Anki to start studying your {} words!",
-        success_count
-    );
+    /// Write fields as plain text, without the bold headword, part-of-speech badge, or hint
+    #[arg(long, default_value = "false")]
+    pub plain_fields: bool,
 
-    Ok(())
-}
+    /// Leave the part-of-speech badge off the question side
+    #[arg(long, default_value = "false", conflicts_with = "plain_fields")]
+    pub no_pos_badge: bool,
 
-async fn handle_config(ankiconnect_url: Option<String>, show: bool) -> Result<()> {
-    use crate::Config;
+    /// Under the answer of a word tagged `confusable`, name the words it is easily mixed up with
+    #[arg(long, default_value = "false")]
+    pub confusable_hints: bool,
 
-    if show {
-        let config = Config::new()?;
-        println!("Current configuration:");
-        println!("  AnkiConnect URL: {}", config.ankiconnect_url);
-        println!("  Translation Service: MyMemory (no API key required)");
-        println!("  Cache directory: {}", config.cache_dir.display());
-        return Ok(());
-    }
+    /// Translator to ask first: mymemory, or google through its unofficial free endpoint, a second between requests
+    #[arg(long, value_parser = TranslatorArg::parser())]
+    pub translator: Option<TranslatorKind>,
 
-    if let Some(url) = ankiconnect_url {
-        println!("Setting AnkiConnect URL to: {}", url);
-        println!("Note: Use environment variable ANKICONNECT_URL={}", url);
-    }
+    /// LibreTranslate server to fall back on when MyMemory's answer looks wrong (defaults to LIBRETRANSLATE_URL)
+    #[arg(long)]
+    pub libretranslate_url: Option<String>,
 
-    Ok(())
+    /// Check each translation by translating it back, flagging words that don't round-trip
+    #[arg(long, default_value = "false")]
+    pub back_translate: bool,
+
+    /// Number of words translated at the same time
+    #[arg(long, default_value = "4")]
+    pub concurrency: usize,
+
+    /// Start without asking when the estimate of the run goes over the `confirm_above` thresholds in config.json
+    #[arg(short = 'y', long, default_value = "false")]
+    #[serde(skip)]
+    pub yes: bool,
+
+    /// Translation post-processing rules file (defaults to rules.json in the data directory)
+    #[arg(long)]
+    pub rules: Option<PathBuf>,
+
+    /// Sync the collection with AnkiWeb once the run completes
+    #[arg(long, default_value = "false")]
+    pub sync: bool,
+
+    /// Open Anki's browser on the cards just added once the run completes
+    #[arg(long, default_value = "false")]
+    pub open: bool,
+
+    /// Where the notes go: a running Anki, an .apkg file Anki exports, a CSV file for Anki's importer, or printed
+    #[arg(long, value_parser = OutputBackendArg::parser(), default_value = "ankiconnect")]
+    pub output_backend: OutputBackend,
+
+    /// File the apkg and csv output backends write
+    #[arg(
+        long,
+        value_name = "PATH",
+        required_if_eq_any = [("output_backend", "apkg"), ("output_backend", "csv")]
+    )]
+    pub output: Option<PathBuf>,
+
+    /// Where to write the report of words that could not be added (.json or .csv)
+    #[arg(long)]
+    pub report: Option<PathBuf>,
+
+    /// Retry the words in a report from an earlier run instead of picking words by frequency
+    #[arg(long)]
+    pub from_report: Option<PathBuf>,
+
+    /// Manifest of the deck being rebuilt, whose words and translations are used as they are
+    #[arg(skip)]
+    #[serde(skip)]
+    pub rebuild_from: Option<RunManifest>,
+
+    /// Progress of the deck being grown; the next words after it are added
+    #[arg(skip)]
+    #[serde(skip)]
+    pub grow: Option<GrowthState>,
+}
+
+/// Options as `create` gets them when none are given, so a recipe saved by
+/// an older version still loads
+impl Default for CreateArgs {
+    fn default() -> Self {
+        let command = Self::augment_args(clap::Command::new("create"));
+        let matches = command
+            .try_get_matches_from(["create"])
+            .expect("create has defaults for every required option");
+        Self::from_arg_matches(&matches).expect("default create options are valid")
+    }
+}
+
+impl CreateArgs {
+    /// The target languages given, split at commas
+    pub fn target_languages(&self) -> Vec<String> {
+        self.target_language
+            .iter()
+            .flat_map(|languages| languages.split(','))
+            .map(str::trim)
+            .filter(|language| !language.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Whether the run talks to Anki: a dry run or another output backend
+    /// only does when asked to leave out the words of existing decks
+    pub fn uses_anki(&self) -> bool {
+        (!self.dry_run && self.output_backend.uses_anki()) || !self.exclude_deck.is_empty()
+    }
+
+    /// Fill in what the profile sets for options left at their defaults.
+    /// Enrichments can only be switched on here, never off
+    pub fn with_profile(mut self, profile: &Profile) -> Self {
+        if self.target_language.is_none() {
+            self.target_language = profile.target_language.clone();
+        }
+        if self.base_language.is_none() && self.base_languages.is_empty() {
+            self.base_language = profile.base_language.clone();
+        }
+        if self.translator.is_none() {
+            self.translator = profile.translator;
+        }
+        if self.libretranslate_url.is_none() {
+            self.libretranslate_url = profile.libretranslate_url.clone();
+        }
+        if self.deck_name.is_none() && self.deck_name_pattern.is_none() {
+            self.deck_name_pattern = profile.deck_name_pattern.clone();
+        }
+        if self.words_per_pos == Self::default().words_per_pos {
+            self.words_per_pos = profile.words_per_pos.unwrap_or(self.words_per_pos);
+        }
+        self.with_images |= profile.with_images.unwrap_or(false);
+        self.with_conjugations |= profile.with_conjugations.unwrap_or(false);
+        self.with_declensions |= profile.with_declensions.unwrap_or(false);
+        self.with_senses |= profile.with_senses.unwrap_or(false);
+        self.with_audio |= profile.with_audio.unwrap_or(false);
+        self.with_mnemonics |= profile.with_mnemonics.unwrap_or(false);
+        self.with_etymology |= profile.with_etymology.unwrap_or(false);
+        self.with_examples |= profile.with_examples.unwrap_or(false);
+        self.no_reading |= profile.readings == Some(false);
+        self.back_translate |= profile.back_translate.unwrap_or(false);
+        self
+    }
+}
+
+/// Declare a clap value enum standing in for a library enum with the same
+/// variants, so the library's types stay free of clap. Options take the
+/// library type with `value_parser = <Arg>::parser()`
+macro_rules! value_arg {
+    (
+        $(#[$meta:meta])*
+        $arg:ident => $target:ty {
+            $($(#[$variant_meta:meta])* $variant:ident,)+
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, ValueEnum)]
+        enum $arg {
+            $($(#[$variant_meta])* $variant,)+
+        }
+
+        impl $arg {
+            fn parser() -> impl TypedValueParser<Value = $target> {
+                EnumValueParser::<Self>::new().map(|arg| match arg {
+                    $(Self::$variant => <$target>::$variant,)+
+                })
+            }
+        }
+    };
+}
+
+value_arg! {
+    /// `--bidirectional-strategy` values
+    StrategyArg => BidirectionalStrategy {
+        /// Two separate notes, one per direction
+        Notes,
+        /// One note whose note type has a reversed card template, so Anki
+        /// treats both cards as siblings
+        Templates,
+    }
+}
+
+value_arg! {
+    /// `--selection` values
+    SelectionArg => Selection {
+        /// The same number of words for every part of speech
+        Equal,
+        /// Parts of speech in realistic proportions, mostly nouns and verbs
+        Weighted,
+        /// The most frequent words, whatever their part of speech
+        GlobalRank,
+    }
+}
+
+value_arg! {
+    /// `--sort-field` values
+    SortFieldArg => SortField {
+        /// Sort by the front of the card (Anki's "Basic" note type)
+        Front,
+        /// Sort by zero-padded frequency rank, i.e. learning order
+        Rank,
+    }
+}
+
+value_arg! {
+    /// `--preset` values
+    PresetArg => SchedulingPreset {
+        /// 10 new cards a day with extra learning steps
+        Gentle,
+        /// Anki's defaults: 20 new cards a day
+        Standard,
+        /// 40 new cards a day with short learning steps
+        Intense,
+    }
+}
+
+value_arg! {
+    /// `--duplicates` values
+    DuplicatesArg => DuplicatePolicy {
+        /// Leave them out, before they are translated
+        Skip,
+        /// Add them again anyway
+        Allow,
+        /// Add them to a "Duplicates" subdeck, only checking for duplicates there
+        Deck,
+    }
+}
+
+value_arg! {
+    /// `--audio-providers` values
+    AudioProviderArg => AudioProviderKind {
+        /// Forvo, when it has a key in FORVO_API_KEY or config.json
+        Forvo,
+        /// Wikimedia Commons, mostly Lingua Libre recordings
+        Commons,
+    }
+}
+
+value_arg! {
+    /// `--output-backend` values
+    OutputBackendArg => OutputBackend {
+        /// Add the notes to a running Anki through AnkiConnect
+        Ankiconnect,
+        /// Build the deck in Anki, then have Anki export it as an .apkg file
+        Apkg,
+        /// Write a CSV file for Anki's File → Import, with media in a folder beside it
+        Csv,
+        /// Print each note instead of saving it
+        Stdout,
+    }
+}
+
+value_arg! {
+    /// `--frequency-source` values
+    FrequencySourceArg => FrequencySourceKind {
+        /// Hermit Dave's FrequencyWords (subtitle-based, colloquial)
+        Opensubtitles,
+        /// Leipzig Corpora Collection word lists (news/web text)
+        Leipzig,
+        /// Every other source combined by rank fusion (see `--merge-weights`)
+        Merged,
+    }
+}
+
+value_arg! {
+    /// `--list-size` values
+    ListSizeArg => ListSize {
+        /// The 10,000 most frequent words
+        #[value(name = "10k")]
+        Top10k,
+        /// The 50,000 most frequent words
+        #[value(name = "50k")]
+        Top50k,
+        /// Every word the source has
+        Full,
+    }
+}
+
+value_arg! {
+    /// `--translator` values
+    TranslatorArg => TranslatorKind {
+        /// MyMemory, free and without a key
+        Mymemory,
+        /// Google Translate through its unofficial free endpoint, which may
+        /// change or block clients without notice; requests are spaced a second apart
+        Google,
+        /// A large language model behind an OpenAI-compatible API, set up under
+        /// `llm` in config.json; asked for many words per request, and paid for
+        Llm,
+    }
+}
+
+value_arg! {
+    /// `--duplicate-scope` values
+    DuplicateScopeArg => DuplicateScope {
+        /// Every note of the same type, in any deck
+        Collection,
+        /// Only notes in the same deck
+        Deck,
+    }
+}
+
+#[derive(Subcommand)]
+pub enum ConfigAction {
+    /// Check config.json for unknown keys and bad URLs, durations and templates
+    Validate,
+    /// Your own translations, used instead of asking any translator
+    Glossary {
+        #[command(subcommand)]
+        action: GlossaryAction,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum HistoryAction {
+    /// Everything recorded about one run
+    Show {
+        /// Run number, as listed by `history`
+        id: u64,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum GlossaryAction {
+    /// Add a translation, replacing the one the word already has
+    Add {
+        word: String,
+        translation: String,
+
+        /// Language of the word
+        #[arg(short, long)]
+        target_language: String,
+
+        /// Language of the translation
+        #[arg(short, long, default_value = "en")]
+        base_language: String,
+
+        /// Only use it for the word as this part of speech (e.g. "verb")
+        #[arg(long)]
+        pos: Option<String>,
+    },
+
+    /// Remove a word's translations
+    Remove {
+        word: String,
+
+        #[arg(short, long)]
+        target_language: String,
+
+        #[arg(short, long, default_value = "en")]
+        base_language: String,
+    },
+
+    /// List the translations of a language pair
+    List {
+        #[arg(short, long)]
+        target_language: String,
+
+        #[arg(short, long, default_value = "en")]
+        base_language: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ManageAction {
+    /// Suspend cards in a deck, optionally filtered by tag (e.g. "pos:verb")
+    Suspend {
+        /// Name of the deck
+        #[arg(short, long)]
+        deck: String,
+
+        /// Only affect cards with this tag (can be repeated)
+        #[arg(short, long)]
+        tag: Vec<String>,
+    },
+
+    /// Unsuspend cards in a deck, optionally filtered by tag (e.g. "pos:verb")
+    Unsuspend {
+        /// Name of the deck
+        #[arg(short, long)]
+        deck: String,
+
+        /// Only affect cards with this tag (can be repeated)
+        #[arg(short, long)]
+        tag: Vec<String>,
+    },
+}
+
+/// Run the command given on the command line, and report its error in the
+/// user's language. Err holds the exit code
+pub async fn run() -> std::result::Result<(), i32> {
+    let cli = Cli::parse();
+    // Before logging starts, so log lines follow --no-color too
+    let mut console = Console::new(cli.json, cli.no_emoji, cli.no_color);
+    execute(cli, &mut console).await.map_err(|e| {
+        let error = format!("{:#}", e);
+        say_err!(console, "Error: {}", console.localize(&error));
+        if let Some(log_file) = crate::logging::log_file() {
+            say_err!(console, "Full log: {}", log_file.display());
+        }
+        e.downcast_ref::<AnkiDeckBuilderError>()
+            .map_or(1, AnkiDeckBuilderError::exit_code)
+    })
+}
+
+/// Run `cli`'s command. `console` learns the locale to speak along the way,
+/// so errors are reported in it too
+async fn execute(cli: Cli, console: &mut Console) -> Result<()> {
+    let config = Config::new(cli.cache_dir.clone());
+    let log_dir = config.as_ref().ok().map(|config| config.log_dir());
+    crate::logging::init(cli.verbose, cli.log_file.as_deref(), log_dir.as_deref());
+    tracing::debug!(
+        "Running {} with {:?}",
+        env!("CARGO_PKG_VERSION"),
+        std::env::args()
+            .map(|arg| crate::logging::redact_url(&arg))
+            .collect::<Vec<_>>()
+    );
+    // Until a command knows its base language, the one used last
+    if let Some(base) = config.as_ref().ok().and_then(|config| {
+        crate::state::State::load(&config.state_file())
+            .recent_pairs
+            .first()
+            .and_then(|pair| pair.base.first().cloned())
+    }) {
+        *console = console.clone().with_implied_locale(&base);
+    }
+    if let Some(locale) = &cli.locale {
+        *console = console.clone().with_locale(locale)?;
+    }
+    let console = &*console;
+
+    let mut config = match config {
+        Ok(config) => config,
+        // Validating is how a config file that doesn't load gets fixed
+        Err(_)
+            if matches!(
+                cli.command,
+                Commands::Config {
+                    action: Some(ConfigAction::Validate),
+                    ..
+                }
+            ) =>
+        {
+            return handle_config_validate(console, &Config::directories(cli.cache_dir)?);
+        }
+        Err(e) => return Err(e),
+    };
+    if cli.cache_max_age.is_some() {
+        config.cache_max_age = cli.cache_max_age;
+    }
+    config.read_only = cli.read_only;
+    config.http = config.http.clone().merge(HttpSettings {
+        proxy: cli.proxy.clone(),
+        no_proxy: None,
+        ca_cert: cli.ca_cert.clone(),
+        insecure: cli.insecure,
+    });
+    if config.http.insecure {
+        tracing::warn!("Certificate checks are turned off (--insecure)");
+    }
+
+    use std::io::IsTerminal;
+    let interactive = !cli.non_interactive && std::io::stdin().is_terminal();
+    if interactive && !cli.json {
+        offer_env_import(console, &config)?;
+    }
+
+    let deadline = Deadline::from_limit(cli.max_duration);
+    let cancel = cancel_on_ctrl_c(console);
+    // Create stops by itself at its next step so it can save its progress;
+    // other commands are simply dropped where they are
+    let stops_itself = matches!(
+        cli.command,
+        Commands::Create(_)
+            | Commands::Tui(_)
+            | Commands::Rebuild { .. }
+            | Commands::Grow { .. }
+            | Commands::Serve { .. }
+    );
+
+    let uses_anki = !matches!(
+        cli.command,
+        Commands::Languages
+            | Commands::History { .. }
+            | Commands::Changelog { .. }
+            | Commands::Config { .. }
+            | Commands::Prefetch { .. }
+            | Commands::InspectFrequency { .. }
+            | Commands::WhereIs { anki: false, .. }
+    ) && !matches!(&cli.command, Commands::Create(args) if !args.uses_anki());
+    if uses_anki {
+        if let Some(url) =
+            locate_anki(console, &config, cli.launch_anki, cli.anki_path.as_deref()).await?
+        {
+            config.ankiconnect_url = url;
+        }
+    }
+    let previous_profile = match &cli.profile {
+        Some(profile) if uses_anki => switch_profile(console, &config, profile).await?,
+        _ => None,
+    };
+
+    let config = &config;
+    let command = async {
+        match cli.command {
+            Commands::Test { full } => handle_test(console, config, full).await,
+            Commands::Doctor {
+                target_language,
+                base_language,
+                frequency_source,
+                list_size,
+            } => {
+                handle_doctor(
+                    console,
+                    config,
+                    &target_language,
+                    &base_language,
+                    frequency_source,
+                    list_size,
+                )
+                .await
+            }
+            Commands::Create(args) if args.target_languages().len() > 1 => {
+                handle_create_many(console, config, *args, deadline, &cancel).await
+            }
+            Commands::Create(args) => {
+                handle_create(console, config, *args, interactive, deadline, &cancel, None).await
+            }
+            Commands::Tui(args) => handle_tui(console, config, *args, deadline, &cancel).await,
+            Commands::Rebuild {
+                manifest,
+                deck_name,
+            } => handle_rebuild(console, config, manifest, deck_name, deadline, &cancel).await,
+            Commands::Grow {
+                deck_name,
+                per_week,
+                target_language,
+                base_language,
+                sync,
+            } => {
+                handle_grow(
+                    console,
+                    config,
+                    GrowArgs {
+                        deck_name,
+                        per_week,
+                        target_language,
+                        base_language,
+                        sync,
+                    },
+                    deadline,
+                    &cancel,
+                )
+                .await
+            }
+            Commands::Fix {
+                target_language,
+                tag,
+                query,
+                deck,
+                dry_run,
+                translator,
+                libretranslate_url,
+                back_translate,
+            } => {
+                handle_fix(
+                    console,
+                    config,
+                    FixArgs {
+                        target_language,
+                        tag,
+                        query,
+                        deck,
+                        dry_run,
+                        translator,
+                        libretranslate_url,
+                        back_translate,
+                    },
+                )
+                .await
+            }
+            Commands::Diff {
+                deck_name,
+                target_language,
+                base_languages,
+                words_per_pos,
+                translator,
+                libretranslate_url,
+            } => {
+                handle_diff(
+                    console,
+                    config,
+                    DiffArgs {
+                        deck_name,
+                        target_language,
+                        base_languages,
+                        words_per_pos,
+                        translator,
+                        libretranslate_url,
+                    },
+                    &cancel,
+                )
+                .await
+            }
+            Commands::Prefetch {
+                target_languages,
+                base_languages,
+                words_per_pos,
+                selection,
+                frequency_source,
+                list_size,
+                concurrency,
+                delay_ms,
+                translator,
+                libretranslate_url,
+            } => {
+                handle_prefetch(
+                    console,
+                    config,
+                    PrefetchArgs {
+                        target_languages,
+                        base_languages,
+                        words_per_pos,
+                        selection,
+                        frequency_source,
+                        list_size,
+                        concurrency,
+                        delay: std::time::Duration::from_millis(delay_ms),
+                        translator,
+                        libretranslate_url,
+                    },
+                    &cancel,
+                )
+                .await
+            }
+            Commands::InspectFrequency {
+                language,
+                frequency_source,
+                list_size,
+                examples,
+            } => {
+                handle_inspect_frequency(
+                    console,
+                    config,
+                    &language,
+                    frequency_source,
+                    list_size,
+                    examples,
+                    &cancel,
+                )
+                .await
+            }
+            Commands::WhereIs { word, anki } => handle_where_is(console, config, &word, anki).await,
+            Commands::Serve { address } => handle_serve(console, config, address, &cancel).await,
+            Commands::Languages => handle_languages(console, config),
+            Commands::History { limit, action } => handle_history(console, config, limit, action),
+            Commands::Changelog { deck_name } => handle_changelog(console, config, &deck_name),
+            Commands::Undo {
+                run_id,
+                dry_run,
+                yes,
+            } => handle_undo(console, config, run_id, dry_run, yes, interactive).await,
+            Commands::Config {
+                action: Some(ConfigAction::Glossary { action }),
+                ..
+            } => handle_glossary(console, config, action),
+            Commands::Config {
+                action: Some(ConfigAction::Validate),
+                ..
+            } => handle_config_validate(console, config),
+            Commands::Config {
+                ankiconnect_url,
+                show,
+                action: None,
+            } => handle_config(console, config, ankiconnect_url, show).await,
+            Commands::Manage { action } => handle_manage(console, config, action).await,
+            Commands::External(args) => {
+                handle_external(console, config, args, cli.json, interactive).await
+            }
+        }
+    };
+    let command = async {
+        tokio::select! {
+            result = command => result,
+            _ = cancel.cancelled(), if !stops_itself => {
+                Err(AnkiDeckBuilderError::Cancelled("interrupted".to_string()).into())
+            }
+        }
+    };
+
+    // Commands check the deadline between steps; this hard stop only catches
+    // a request still in flight once the grace period is over
+    let result = match deadline.remaining() {
+        Some(remaining) => tokio::time::timeout(remaining + DEADLINE_GRACE, command)
+            .await
+            .unwrap_or_else(|_| {
+                Err(AnkiDeckBuilderError::DeadlineExceeded(
+                    "command did not stop in time".to_string(),
+                )
+                .into())
+            }),
+        None => command.await,
+    };
+
+    // Switched back even when the command failed
+    if let Some(previous) = previous_profile {
+        if let Err(e) = switch_profile(console, config, &previous).await {
+            say_err!(
+                console,
+                "⚠️  Could not switch back to Anki profile '{}': {}",
+                previous,
+                e
+            );
+        }
+    }
+
+    result
+}
+
+/// Find AnkiConnect before a command needs it: on another common port when
+/// it isn't at the configured URL, or in an Anki started for `--launch-anki`.
+/// Otherwise this only explains what is wrong, so commands that can do
+/// without Anki still run. Answers the URL to use instead of the configured one
+async fn locate_anki(
+    console: &Console,
+    config: &Config,
+    launch: bool,
+    anki_path: Option<&Path>,
+) -> Result<Option<String>> {
+    use crate::ankiweb::discovery::COMMON_PORTS;
+    use crate::ankiweb::{
+        anki_process_running, candidate_urls, find_ankiconnect, launch_anki, wait_for_ankiconnect,
+    };
+
+    let configured = config.ankiconnect_url.clone();
+    let candidates = candidate_urls(&configured);
+
+    let found = match find_ankiconnect(&candidates).await {
+        Some(url) => Some(url),
+        None => {
+            let running = anki_process_running();
+            match running {
+                Some(true) => say!(
+                    console,
+                    "⚠️  Anki is running, but AnkiConnect answers neither at {} nor on ports {}; is the add-on installed and enabled?",
+                    configured,
+                    COMMON_PORTS
+                        .iter()
+                        .map(|port| port.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                _ if launch => {}
+                Some(false) => {
+                    say!(console, "⚠️  Anki is not running; start it, or pass --launch-anki to have it started")
+                }
+                None => say!(console, "⚠️  AnkiConnect does not answer at {}; is Anki open?", configured),
+            }
+            if !launch || running == Some(true) {
+                return Ok(None);
+            }
+
+            say!(console, "🚀 Starting Anki and waiting for AnkiConnect...");
+            launch_anki(anki_path.or(config.ankiconnect.anki_path.as_deref()))?;
+            let timeout = std::time::Duration::from_secs(config.ankiconnect.launch_timeout_secs);
+            let url = wait_for_ankiconnect(&candidates, timeout).await?;
+            say!(console, "✅ AnkiConnect is up");
+            Some(url)
+        }
+    };
+
+    let moved = found.filter(|url| *url != configured);
+    if let Some(url) = &moved {
+        say!(
+            console,
+            "🔎 AnkiConnect is not at {} but answers at {}; using that",
+            configured,
+            url
+        );
+    }
+    Ok(moved)
+}
+
+/// Open an Anki profile, returning the one that was open before if it changed
+async fn switch_profile(
+    console: &Console,
+    config: &Config,
+    profile: &str,
+) -> Result<Option<String>> {
+    let client = config.anki_client()?;
+    client.verify_connection().await?;
+
+    let previous = client.switch_profile(profile).await?;
+    if previous.is_some() {
+        say!(console, "👤 Switched to Anki profile '{}'", profile);
+    }
+    Ok(previous)
+}
+
+/// Longest translation shown in the sample listing
+const SAMPLE_WIDTH: usize = 40;
+
+/// Extra time a request in flight at the deadline gets to finish
+const DEADLINE_GRACE: std::time::Duration = std::time::Duration::from_secs(10);
+
+async fn handle_test(console: &Console, config: &Config, full: bool) -> Result<()> {
+    use crate::health::{check_all, ANKICONNECT_SERVICE};
+
+    say!(console, "🔍 Checking external services...\n");
+
+    let checks = check_all(config, &config.services(), full).await;
+
+    for check in &checks {
+        let status = if check.ok { "✅" } else { "❌" };
+        say!(
+            console,
+            "{} {:<28} {:>6} ms  {}",
+            status,
+            check.service,
+            check.latency_ms,
+            check.target
+        );
+        if let Some(error) = &check.error {
+            say!(console, "     └─ {}", error);
+        }
+    }
+    say!(console);
+
+    let anki_ok = checks
+        .iter()
+        .any(|check| check.service == ANKICONNECT_SERVICE && check.ok);
+    let failed = checks.iter().filter(|check| !check.ok).count();
+
+    if anki_ok {
+        // Try to get decks
+        let client = config.anki_client()?;
+        let decks = match client.get_decks().await {
+            Ok(decks) => {
+                say!(console, "📚 Available decks ({}):", decks.len());
+                for deck in decks.iter().take(10) {
+                    say!(console, "  - {}", deck);
+                }
+                if decks.len() > 10 {
+                    say!(console, "  ... and {} more", decks.len() - 10);
+                }
+                Some(decks)
+            }
+            Err(e) => {
+                say!(console, "⚠️  Could not retrieve decks: {}", e);
+                None
+            }
+        };
+
+        console.emit_json(&json!({
+            "connected": true,
+            "ankiconnect_url": config.ankiconnect_url,
+            "decks": decks,
+            "services": checks,
+        }))?;
+    } else {
+        say!(console, "💡 AnkiConnect troubleshooting:");
+        say!(console, "  1. Make sure Anki is running");
+        say!(
+            console,
+            "  2. Verify AnkiConnect add-on is installed (code: 2055492159)"
+        );
+        say!(
+            console,
+            "  3. Check that AnkiConnect is accessible at {}",
+            config.ankiconnect_url
+        );
+        say!(
+            console,
+            "  4. Try restarting Anki if the add-on was just installed"
+        );
+
+        console.emit_json(&json!({
+            "connected": false,
+            "ankiconnect_url": config.ankiconnect_url,
+            "services": checks,
+        }))?;
+
+        return Err(AnkiDeckBuilderError::AnkiConnectNotRunning {
+            url: config.ankiconnect_url.clone(),
+        }
+        .into());
+    }
+
+    if failed > 0 {
+        anyhow::bail!("{} of {} service checks failed", failed, checks.len());
+    }
+
+    say!(console, "✅ All services reachable");
+    Ok(())
+}
+
+async fn handle_doctor(
+    console: &Console,
+    config: &Config,
+    target_language: &str,
+    base_language: &str,
+    frequency_source: FrequencySourceKind,
+    list_size: ListSize,
+) -> Result<()> {
+    use crate::doctor::{diagnose_anki, diagnose_directories, diagnose_services};
+
+    let packs = &config.language_packs;
+    let target = packs
+        .language(target_language)
+        .ok_or_else(|| AnkiDeckBuilderError::UnsupportedLanguage(target_language.to_string()))?;
+    let base = packs
+        .language(base_language)
+        .ok_or_else(|| AnkiDeckBuilderError::UnsupportedLanguage(base_language.to_string()))?;
+
+    say!(
+        console,
+        "🩺 Diagnosing setup for {} → {}...\n",
+        target.name,
+        base.name
+    );
+
+    let anki_client = config.anki_client()?;
+    let settings = config.services();
+
+    let (anki, services) = tokio::join!(
+        diagnose_anki(&anki_client, config),
+        diagnose_services(&target, &base, frequency_source, list_size, &settings)
+    );
+    let diagnoses: Vec<_> = anki
+        .into_iter()
+        .chain(diagnose_directories(config))
+        .chain(services)
+        .collect();
+
+    for diagnosis in &diagnoses {
+        let status = if diagnosis.ok { "✅" } else { "❌" };
+        say!(
+            console,
+            "{} {:<32} {}",
+            status,
+            diagnosis.check,
+            diagnosis.detail
+        );
+        if let Some(remediation) = &diagnosis.remediation {
+            say!(console, "     💡 {}", remediation);
+        }
+    }
+    say!(console);
+
+    let failed = diagnoses.iter().filter(|d| !d.ok).count();
+
+    console.emit_json(&json!({
+        "ok": failed == 0,
+        "target_language": target.code,
+        "base_language": base.code,
+        "checks": diagnoses,
+    }))?;
+
+    if failed > 0 {
+        anyhow::bail!("{} of {} checks failed", failed, diagnoses.len());
+    }
+
+    say!(console, "✅ Everything looks good");
+    Ok(())
+}
+
+fn handle_languages(console: &Console, config: &Config) -> Result<()> {
+    let entries = crate::language::language_support(&config.services())?;
+
+    let list = |names: &[&str]| match names.is_empty() {
+        true => "-".to_string(),
+        false => names.join(", "),
+    };
+    let flag = |available: bool| if available { "yes" } else { "-" }.to_string();
+    let header = [
+        "Code",
+        "Language",
+        "Frequency lists",
+        "Phrases",
+        "Starter",
+        "Dictionary",
+        "Audio",
+        "Translators",
+    ]
+    .map(|title| console.tr(title));
+    let mut rows = vec![header];
+    for language in &entries {
+        rows.push([
+            language.code.clone(),
+            language.name.clone(),
+            list(&language.frequency_sources),
+            flag(language.phrases),
+            flag(language.starter),
+            list(&language.dictionary),
+            list(&language.tts),
+            list(&language.translators),
+        ]);
+    }
+    // Each column as wide as its widest cell
+    let widths: Vec<usize> = (0..rows[0].len())
+        .map(|column| {
+            rows.iter()
+                .map(|row| row[column].chars().count())
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+
+    say!(console, "🌍 Supported languages\n");
+    for row in &rows {
+        let line: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        say!(console, "{}", line.join("  ").trim_end());
+    }
+
+    say!(
+        console,
+        "\nℹ️  Languages without a frequency list produce an empty deck."
+    );
+    say!(
+        console,
+        "ℹ️  Forvo recordings need a key in FORVO_API_KEY or config.json, and the llm translator one in llm.api_key or OPENAI_API_KEY."
+    );
+
+    console.emit_json(&entries)
+}
+
+/// What every deck of a `create` run loads the same way whatever its target
+/// language: the translation rules, the glossary, and the known words to
+/// leave out. Loaded once when several decks are made at once
+#[derive(Clone)]
+struct SharedResources {
+    rules_file: PathBuf,
+    rules: crate::language::PostProcessor,
+    glossary: crate::language::Glossary,
+    /// Known words from `--exclude-file`
+    file_words: Vec<String>,
+    /// Known words from `--exclude-deck`
+    deck_words: Vec<String>,
+}
+
+impl SharedResources {
+    /// The resources `args` ask for, with its profile applied
+    async fn load(config: &Config, args: &CreateArgs) -> Result<Self> {
+        use crate::language::{load_known_words, Glossary, PostProcessor};
+
+        let rules_file = args.rules.clone().unwrap_or_else(|| config.rules_file());
+        let file_words = match &args.exclude_file {
+            Some(path) => load_known_words(path)?,
+            None => Vec::new(),
+        };
+        let deck_words = if args.exclude_deck.is_empty() {
+            Vec::new()
+        } else {
+            words_in_decks(config, &args.exclude_deck).await?
+        };
+        Ok(Self {
+            rules: PostProcessor::load(&rules_file)?,
+            rules_file,
+            glossary: Glossary::load(&config.glossary_dir())?,
+            file_words,
+            deck_words,
+        })
+    }
+}
+
+/// Apply the profile `args` name, if any. The recipe keeps the profile's
+/// settings, not its name, so it can be rebuilt without the config file
+fn apply_config_profile(config: &Config, args: CreateArgs) -> Result<(CreateArgs, Option<String>)> {
+    Ok(match args.config_profile.clone() {
+        Some(name) => {
+            let profile = config.profile(&name)?;
+            (args.with_profile(profile), Some(name))
+        }
+        None => (args, None),
+    })
+}
+
+/// Create one deck. `shared` are the resources loaded for several decks at
+/// once; without them the deck loads its own
+async fn handle_create(
+    console: &Console,
+    config: &Config,
+    args: CreateArgs,
+    interactive: bool,
+    deadline: Deadline,
+    cancel: &CancellationToken,
+    shared: Option<&SharedResources>,
+) -> Result<()> {
+    let services = config.services();
+    let (args, profile_name) = apply_config_profile(config, args)?;
+    let SharedResources {
+        rules_file,
+        rules: postprocessor,
+        glossary,
+        file_words,
+        deck_words,
+    } = match shared {
+        Some(shared) => shared.clone(),
+        None => SharedResources::load(config, &args).await?,
+    };
+    let recipe_args = CreateArgs {
+        config_profile: None,
+        ..args.clone()
+    };
+    let CreateArgs {
+        target_language,
+        base_language,
+        base_languages,
+        words_per_pos,
+        deck_name,
+        deck_name_pattern,
+        deck_description,
+        config_profile: _,
+        dry_run,
+        preview,
+        bidirectional,
+        bidirectional_strategy,
+        sort_field,
+        frequency_source,
+        list_size,
+        merge_weights,
+        skip_cognates,
+        preset,
+        selection,
+        pos,
+        min_frequency,
+        top_percent,
+        starter,
+        include_phrases,
+        include_numbers,
+        include_dates,
+        min_word_length,
+        max_word_length,
+        include,
+        exclude,
+        exclude_file,
+        exclude_deck,
+        model,
+        field_map,
+        dedup_field,
+        duplicates,
+        allow_duplicates,
+        duplicate_scope,
+        tags,
+        review,
+        with_images,
+        with_conjugations,
+        with_declensions,
+        with_senses,
+        with_audio,
+        audio_providers,
+        with_mnemonics,
+        with_etymology,
+        with_examples,
+        enrichment_order,
+        no_reading,
+        plain_fields,
+        no_pos_badge,
+        confusable_hints,
+        translator,
+        libretranslate_url,
+        back_translate,
+        concurrency,
+        yes,
+        rules: _,
+        sync,
+        open,
+        output_backend,
+        output,
+        report,
+        from_report,
+        rebuild_from,
+        grow,
+    } = args;
+
+    use crate::ankiweb::card_builder::PHRASE_SUBDECK;
+    use crate::changelog::{ChangelogEntry, DeckChangelog};
+    use crate::history::{RunHistory, RunRecord};
+    use crate::language::{
+        common_phrases, get_prioritized_languages, has_phrases, has_starter_list, starter_words,
+        Section,
+    };
+    use crate::report::FailureReport;
+    use dialoguer::{Confirm, Input, Select};
+
+    let started = std::time::Instant::now();
+    // A retry defaults to the languages and deck of the run that failed
+    let retry = match &from_report {
+        Some(path) => Some(FailureReport::load(path)?),
+        None => None,
+    };
+    let target_language =
+        target_language.or_else(|| retry.as_ref().map(|r| r.target_language.clone()));
+    let base_languages = match &retry {
+        Some(retry) if base_language.is_none() && base_languages.is_empty() => {
+            retry.base_languages.clone()
+        }
+        _ => base_languages,
+    };
+    let deck_name = deck_name.or_else(|| retry.as_ref().map(|r| r.deck_name.clone()));
+
+    if !output_backend.uses_anki() && (sync || open) {
+        return Err(AnkiDeckBuilderError::ConfigurationError(format!(
+            "--sync and --open need Anki, which the {} output backend doesn't use",
+            output_backend.name()
+        ))
+        .into());
+    }
+
+    // The dashboard asks for review decisions itself
+    if review && !interactive && !console.has_dashboard() {
+        return Err(AnkiDeckBuilderError::MissingInput(
+            "--review needs an interactive terminal".to_string(),
+        )
+        .into());
+    }
+
+    say!(
+        console,
+        "🚀 Anki Deck Builder - Language Learning Deck Creator\n"
+    );
+    if let Some(name) = &profile_name {
+        say!(console, "🗂️  Profile: {}", name);
+    }
+
+    // Recent language pairs lead the prompts, the last one as the default
+    let state_file = config.state_file();
+    let state = crate::state::State::load(&state_file);
+    let recent_pairs: Vec<(crate::language::Language, Vec<crate::language::Language>)> = state
+        .recent_pairs
+        .iter()
+        .filter_map(|pair| {
+            let base = pair
+                .base
+                .iter()
+                .map(|code| config.language_packs.language(code))
+                .collect::<Option<Vec<_>>>()?;
+            Some((config.language_packs.language(&pair.target)?, base))
+        })
+        .filter(|(_, base)| !base.is_empty())
+        .collect();
+    let mut picked_base: Option<Vec<String>> = None;
+
+    // Get target language (either from arg or interactive prompt)
+    let target_lang = match target_language {
+        Some(lang_input) => match config.language_packs.language(&lang_input) {
+            Some(lang) => {
+                say!(console, "🎯 Target language: {} ({})", lang.name, lang.code);
+                lang
+            }
+            None => {
+                say_err!(console, "❌ Unsupported language: {}", lang_input);
+                say_err!(
+                    console,
+                    "Use 'Croatian', 'hr', or run without --target-language for a selection menu"
+                );
+                return Err(AnkiDeckBuilderError::UnsupportedLanguage(lang_input).into());
+            }
+        },
+        None if !interactive => {
+            return Err(AnkiDeckBuilderError::MissingInput("--target-language".to_string()).into());
+        }
+        None => {
+            let languages = get_prioritized_languages(&config.language_packs);
+            // Picking a recent pair answers the base language prompt too,
+            // unless base languages were given
+            let pairs = match base_language.is_none() && base_languages.is_empty() {
+                true => recent_pairs.as_slice(),
+                false => &[],
+            };
+            let lang_names: Vec<String> = pairs
+                .iter()
+                .map(|(target, base)| {
+                    let base: Vec<&str> = base.iter().map(|l| l.name.as_str()).collect();
+                    console
+                        .plain(&format!("↻ {} → {}", target.name, base.join(", ")))
+                        .into_owned()
+                })
+                .chain(languages.iter().map(|l| format!("{} ({})", l.name, l.code)))
+                .collect();
+
+            let selection = Select::with_theme(&*console.theme())
+                .with_prompt(console.tr("Select target language to learn"))
+                .items(&lang_names)
+                .default(0) // The last pair, or Croatian
+                .interact()?;
+
+            let selected = match pairs.get(selection) {
+                Some((target, base)) => {
+                    picked_base = Some(base.iter().map(|l| l.code.clone()).collect());
+                    target.clone()
+                }
+                None => languages[selection - pairs.len()].clone(),
+            };
+            say!(
+                console,
+                "🎯 Target language: {} ({})",
+                selected.name,
+                selected.code
+            );
+            selected
+        }
+    };
+    let base_languages = picked_base.unwrap_or(base_languages);
+
+    // With --base-languages the first one is the main base language
+    let (base_language, extra_base_inputs) = match base_languages.split_first() {
+        Some((first, rest)) => (Some(first.clone()), rest.to_vec()),
+        None => (base_language, Vec::new()),
+    };
+
+    // Get base language (either from arg or interactive prompt)
+    let base_lang = match base_language {
+        Some(lang_input) => match config.language_packs.language(&lang_input) {
+            Some(lang) => {
+                say!(console, "🏠 Base language: {} ({})", lang.name, lang.code);
+                lang
+            }
+            None => {
+                say_err!(console, "❌ Unsupported language: {}", lang_input);
+                return Err(AnkiDeckBuilderError::UnsupportedLanguage(lang_input).into());
+            }
+        },
+        None if !interactive => {
+            return Err(AnkiDeckBuilderError::MissingInput("--base-language".to_string()).into());
+        }
+        None => {
+            let languages = get_prioritized_languages(&config.language_packs);
+            let lang_names: Vec<String> = languages
+                .iter()
+                .map(|l| format!("{} ({})", l.name, l.code))
+                .collect();
+
+            // The one last used with this target, or Spanish
+            let default = state
+                .last_base_for(&target_lang.code)
+                .and_then(|base| base.first())
+                .and_then(|code| languages.iter().position(|l| l.code == *code))
+                .filter(|&i| languages[i].code != target_lang.code)
+                .unwrap_or(1);
+            let selection = Select::with_theme(&*console.theme())
+                .with_prompt(console.tr("Select base language (for translations)"))
+                .items(&lang_names)
+                .default(default)
+                .interact()?;
+
+            let selected = languages[selection].clone();
+            say!(
+                console,
+                "🏠 Base language: {} ({})",
+                selected.name,
+                selected.code
+            );
+            selected
+        }
+    };
+
+    // Messages from here on in the language the cards are translated into
+    let console = console.clone().with_implied_locale(&base_lang.code);
+    let console = &console;
+
+    let mut extra_base_langs: Vec<crate::language::Language> = Vec::new();
+    for lang_input in extra_base_inputs {
+        let Some(lang) = config.language_packs.language(&lang_input) else {
+            say_err!(console, "❌ Unsupported language: {}", lang_input);
+            return Err(AnkiDeckBuilderError::UnsupportedLanguage(lang_input).into());
+        };
+        if lang.code != base_lang.code && !extra_base_langs.iter().any(|l| l.code == lang.code) {
+            extra_base_langs.push(lang);
+        }
+    }
+    if !extra_base_langs.is_empty() {
+        say!(
+            console,
+            "🏠 Also translating into: {}",
+            extra_base_langs
+                .iter()
+                .map(|l| format!("{} ({})", l.name, l.code))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+
+    // Validate that target and base languages are different
+    if target_lang.code == base_lang.code
+        || extra_base_langs.iter().any(|l| l.code == target_lang.code)
+    {
+        say_err!(console, "❌ Target and base languages must be different!");
+        return Err(AnkiDeckBuilderError::ConfigurationError(
+            "Target and base languages are the same".to_string(),
+        )
+        .into());
+    }
+
+    let base_names = std::iter::once(&base_lang)
+        .chain(&extra_base_langs)
+        .map(|l| l.name.clone())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let base_codes = std::iter::once(&base_lang)
+        .chain(&extra_base_langs)
+        .map(|l| l.code.clone())
+        .collect::<Vec<_>>();
+
+    let pair = crate::state::LanguagePair {
+        target: target_lang.code.clone(),
+        base: base_codes.clone(),
+    };
+    if let Err(e) = crate::state::State::remember_pair(&state_file, pair) {
+        tracing::warn!("Failed to remember the language pair: {}", e);
+    }
+
+    // A review resumes with the parts of speech chosen for it, so the same
+    // words come up again
+    let session_path = crate::session::ReviewSession::path(
+        &config.session_dir(),
+        &target_lang.code,
+        &base_codes.join("+"),
+    );
+    let mut review_session = match review {
+        true => Some(resume_review(console, &session_path, deck_name.as_deref())?),
+        false => None,
+    };
+
+    let parts_of_speech = match review_session.as_mut() {
+        // Asked only when --pos leaves every part of speech in
+        Some(session) if pos == ["all"] && !console.has_dashboard() => {
+            choose_parts_of_speech(console, session, &session_path)?
+        }
+        _ => PartOfSpeech::parse_list(&pos).map_err(AnkiDeckBuilderError::ConfigurationError)?,
+    };
+
+    // A rebuild takes its words from the manifest instead
+    let starter_count = if starter && rebuild_from.is_none() {
+        if !has_starter_list(&target_lang.code) {
+            return Err(AnkiDeckBuilderError::ConfigurationError(format!(
+                "No starter list for {}; `languages` shows which languages have one",
+                target_lang.name
+            ))
+            .into());
+        }
+        Some(starter_words(&target_lang.code).len())
+    } else {
+        None
+    };
+
+    // A threshold picks however many words clear it, so they are counted up
+    // front for the deck name and the estimate. A retry, rebuild or grow
+    // takes its words from elsewhere
+    let threshold = match (min_frequency, top_percent) {
+        (Some(count), _) => Some(FrequencyThreshold::MinFrequency(count)),
+        (None, Some(percent)) => Some(FrequencyThreshold::TopPercent(percent)),
+        (None, None) => None,
+    }
+    .filter(|_| retry.is_none() && rebuild_from.is_none() && grow.is_none());
+    let threshold_count = match threshold {
+        Some(threshold) => {
+            let list = crate::builder::FrequencyList {
+                source: frequency_source,
+                list_size,
+                merge_weights: merge_weights.clone(),
+            };
+            let data = list
+                .load(&target_lang.code, config.cache_dir(), &services, cancel)
+                .await?;
+            if !data.has_counts() {
+                return Err(AnkiDeckBuilderError::ConfigurationError(format!(
+                    "The {} list for {} has no word counts; --min-frequency and --top-percent need a downloaded list",
+                    frequency_source.name(),
+                    target_lang.name
+                ))
+                .into());
+            }
+            Some(
+                data.with_parts_of_speech(parts_of_speech.clone())
+                    .select_threshold(threshold)
+                    .len(),
+            )
+        }
+        None => None,
+    };
+
+    // Get deck name (either from arg or generate/prompt)
+    let final_deck_name = match deck_name {
+        Some(name) => {
+            say!(console, "📚 Deck name: {}", name);
+            name
+        }
+        None => {
+            let word_count = starter_count
+                .or(threshold_count)
+                .unwrap_or(words_per_pos * parts_of_speech.len());
+            use crate::naming::{
+                render_deck_name, today, DeckNameValues, DEFAULT_TEMPLATE, STARTER_TEMPLATE,
+            };
+
+            // The option or profile wins over the config file's template
+            let template = match deck_name_pattern {
+                Some(pattern) => pattern,
+                None => match config.deck_name_pattern.clone() {
+                    Some(pattern) => pattern,
+                    None if starter_count.is_some() => STARTER_TEMPLATE.to_string(),
+                    None => DEFAULT_TEMPLATE.to_string(),
+                },
+            };
+            let values = DeckNameValues {
+                target: target_lang.name.clone(),
+                target_code: target_lang.code.clone(),
+                base: base_names.clone(),
+                base_code: base_codes.join("+"),
+                words: word_count,
+                source: match starter_count {
+                    Some(_) => "starter".to_string(),
+                    None => frequency_source.name().to_string(),
+                },
+                size: list_size.name().to_string(),
+                date: today(),
+            };
+            let default_name = render_deck_name(&template, &values)?;
+
+            // Without a terminal, accept the default name rather than prompting
+            let use_default = !interactive
+                || Confirm::with_theme(&*console.theme())
+                    .with_prompt(console.tr(&format!("Use default deck name: '{}'?", default_name)))
+                    .default(true)
+                    .interact()?;
+
+            if use_default {
+                say!(console, "📚 Deck name: {}", default_name);
+                default_name
+            } else {
+                let custom_name: String = Input::with_theme(&*console.theme())
+                    .with_prompt(console.tr("Enter custom deck name"))
+                    .interact_text()?;
+                say!(console, "📚 Deck name: {}", custom_name);
+                custom_name
+            }
+        }
+    };
+
+    // Decks made at once tell their lines apart by name
+    let console = match console.is_prefixed() {
+        true => console.clone().with_prefix(&final_deck_name),
+        false => console.clone(),
+    };
+    let console = &console;
+
+    // A saved review of another deck isn't applied to this one
+    if let Some(session) = review_session.as_mut() {
+        if !session.deck_name.is_empty() && session.deck_name != final_deck_name {
+            say!(
+                console,
+                "🔁 The saved review is of '{}', so '{}' is reviewed from the start",
+                session.deck_name,
+                final_deck_name
+            );
+            session.decisions.clear();
+        }
+    }
+
+    use crate::ankiweb::NoteMapping;
+
+    // The command line takes precedence over the config file
+    let note_mapping = match &model {
+        Some(name) => Some(NoteMapping::new(name).with_field_map(&field_map)?),
+        None => config.note_model.clone(),
+    };
+    if note_mapping.is_some()
+        && (sort_field == SortField::Rank
+            || (bidirectional && bidirectional_strategy == BidirectionalStrategy::Templates))
+    {
+        return Err(AnkiDeckBuilderError::ConfigurationError(
+            "A custom note type can't be combined with --sort-field rank or --bidirectional-strategy templates".to_string(),
+        )
+        .into());
+    }
+
+    use crate::language::{resolve_weights, WordFilter};
+
+    let (file_word_count, deck_word_count) = (file_words.len(), deck_words.len());
+    let word_filter = WordFilter::new()
+        .with_min_length(min_word_length)
+        .with_max_length(max_word_length)
+        .with_include(&include)?
+        .with_exclude(&exclude)?
+        .with_known_words(file_words.into_iter().chain(deck_words));
+
+    say!(console, "\n📋 Configuration Summary:");
+    say!(
+        console,
+        "  Target language: {} ({})",
+        target_lang.name,
+        target_lang.code
+    );
+    say!(
+        console,
+        "  Base language: {} ({})",
+        base_lang.name,
+        base_lang.code
+    );
+    for lang in &extra_base_langs {
+        say!(
+            console,
+            "  Extra base language: {} ({})",
+            lang.name,
+            lang.code
+        );
+    }
+    match (&retry, &from_report, &grow) {
+        (Some(retry), Some(path), _) => say!(
+            console,
+            "  Retrying: {} words from {}",
+            retry.words().len(),
+            path.display()
+        ),
+        (_, _, Some(state)) => say!(
+            console,
+            "  Growing: the next {} words after rank {}",
+            state.per_week,
+            state.reached_rank
+        ),
+        _ if starter_count.is_some() => say!(
+            console,
+            "  Starter: {} essentials (greetings, numbers, days, question words, survival phrases)",
+            starter_count.unwrap_or_default()
+        ),
+        _ => {
+            match threshold {
+                Some(FrequencyThreshold::MinFrequency(count)) => {
+                    say!(
+                        console,
+                        "  Words counted at least {} times in the corpus",
+                        count
+                    )
+                }
+                Some(FrequencyThreshold::TopPercent(percent)) => {
+                    say!(console, "  Words making up {}% of the corpus", percent)
+                }
+                None => {
+                    say!(console, "  Words per part of speech: {}", words_per_pos);
+                    say!(console, "  Selection: {:?}", selection);
+                }
+            }
+            if parts_of_speech.len() < PartOfSpeech::all().len() {
+                let names: Vec<_> = parts_of_speech.iter().map(PartOfSpeech::name).collect();
+                say!(console, "  Parts of speech: {}", names.join(", "));
+            }
+        }
+    }
+    // Phrases come with a fresh pick of words, not a retry, rebuild or grow
+    let phrase_count = match (&retry, &rebuild_from, &grow) {
+        (None, None, None) => common_phrases(&target_lang.code, include_phrases).len(),
+        _ => 0,
+    };
+    if include_phrases > 0 && !has_phrases(&target_lang.code) {
+        say!(
+            console,
+            "  ⚠️  No phrase list for {}; no phrases added",
+            target_lang.name
+        );
+    } else if phrase_count > 0 {
+        say!(
+            console,
+            "  Phrases: {} (in '{}::{}')",
+            phrase_count,
+            final_deck_name,
+            PHRASE_SUBDECK
+        );
+    }
+    // Sections, like phrases, come with a fresh pick of words
+    let asked_sections: Vec<Section> = [
+        (include_numbers, Section::Numbers),
+        (include_dates, Section::Dates),
+    ]
+    .into_iter()
+    .filter_map(|(asked, section)| asked.then_some(section))
+    .collect();
+    let mut sections = Vec::new();
+    let mut section_count = 0;
+    for section in asked_sections {
+        if !section.is_available(&target_lang.code) {
+            say!(
+                console,
+                "  ⚠️  No {} rules for {}; --include-{} is ignored",
+                section.name(),
+                target_lang.name,
+                section.name()
+            );
+        } else if retry.is_none() && rebuild_from.is_none() && grow.is_none() {
+            let count = section.words(&target_lang.code).len();
+            say!(
+                console,
+                "  {}: {} (in '{}::{}')",
+                section.subdeck(),
+                count,
+                final_deck_name,
+                section.subdeck()
+            );
+            section_count += count;
+            sections.push(section);
+        }
+    }
+    let estimated_words = match (&grow, starter_count) {
+        (Some(state), _) => state.per_week,
+        (None, Some(count)) => count,
+        (None, None) => threshold_count.unwrap_or(words_per_pos * parts_of_speech.len()),
+    } + phrase_count
+        + section_count;
+    let estimated_cards = if bidirectional {
+        estimated_words * 2 // Double for bidirectional
+    } else {
+        estimated_words
+    };
+    say!(
+        console,
+        "  Total cards: ~{} ({}{})",
+        estimated_cards,
+        if grow.is_some() {
+            "any part of speech".to_string()
+        } else if starter_count.is_some() {
+            "starter list".to_string()
+        } else if threshold.is_some() {
+            "frequency threshold".to_string()
+        } else {
+            format!("{} parts of speech", parts_of_speech.len())
+        },
+        if bidirectional { ", bidirectional" } else { "" }
+    );
+    say!(console, "  Deck name: {}", final_deck_name);
+    say!(
+        console,
+        "  Bidirectional: {}",
+        if bidirectional { "yes" } else { "no" }
+    );
+    if bidirectional {
+        say!(
+            console,
+            "  Bidirectional strategy: {:?}",
+            bidirectional_strategy
+        );
+    }
+    say!(console, "  Sort field: {:?}", sort_field);
+    if let Some(threshold) = skip_cognates {
+        say!(
+            console,
+            "  Skipping cognates: similarity {} or more",
+            threshold
+        );
+    }
+    if let Some(preset) = preset {
+        say!(console, "  Scheduling preset: {}", preset.name());
+    }
+    if starter_count.is_none() {
+        say!(
+            console,
+            "  Frequency source: {:?} ({} list)",
+            frequency_source,
+            list_size.name()
+        );
+    }
+    let merge_description = resolve_weights(&merge_weights)
+        .iter()
+        .map(SourceWeight::to_string)
+        .collect::<Vec<_>>()
+        .join(", ");
+    if starter_count.is_none() && frequency_source == FrequencySourceKind::Merged {
+        say!(console, "  Merge weights: {}", merge_description);
+    }
+    if min_word_length.is_some() || max_word_length.is_some() {
+        say!(
+            console,
+            "  Word length: {} to {} letters",
+            min_word_length.map_or("any".to_string(), |n| n.to_string()),
+            max_word_length.map_or("any".to_string(), |n| n.to_string())
+        );
+    }
+    if !include.is_empty() {
+        say!(console, "  Include words matching: {}", include.join(", "));
+    }
+    if !exclude.is_empty() {
+        say!(console, "  Exclude words matching: {}", exclude.join(", "));
+    }
+    if let Some(path) = &exclude_file {
+        say!(
+            console,
+            "  Known words: {} from {}",
+            file_word_count,
+            path.display()
+        );
+    }
+    if !exclude_deck.is_empty() {
+        say!(
+            console,
+            "  Known words: {} from decks {}",
+            deck_word_count,
+            exclude_deck.join(", ")
+        );
+    }
+    if let Some(mapping) = &note_mapping {
+        let mut fields: Vec<String> = mapping
+            .fields
+            .iter()
+            .map(|(from, to)| format!("{}→{}", from, to))
+            .collect();
+        fields.sort();
+        if fields.is_empty() {
+            say!(console, "  Note type: {}", mapping.model);
+        } else {
+            say!(
+                console,
+                "  Note type: {} ({})",
+                mapping.model,
+                fields.join(", ")
+            );
+        }
+    }
+    if with_images {
+        say!(console, "  Images: nouns, from Openverse");
+    }
+    use crate::builder::Enrichments;
+
+    let mut enrichments = Enrichments {
+        images: with_images,
+        readings: !no_reading,
+        conjugations: with_conjugations,
+        declensions: with_declensions,
+        senses: with_senses,
+        audio: with_audio,
+        mnemonics: with_mnemonics,
+        etymology: with_etymology,
+        examples: with_examples,
+    }
+    .available_for(&target_lang.code, &config.language_packs);
+    let readings = enrichments.readings;
+    if readings {
+        say!(console, "  Reading: romanized {} words", target_lang.name);
+    }
+    let conjugations = enrichments.conjugations;
+    if conjugations {
+        say!(console, "  Conjugations: present tense on verb cards");
+    } else if with_conjugations {
+        say!(
+            console,
+            "  ⚠️  No conjugations for {} verbs; --with-conjugations is ignored",
+            target_lang.name
+        );
+    }
+    let declensions = enrichments.declensions;
+    if declensions {
+        say!(console, "  Declensions: nouns, from Wiktionary");
+    } else if with_declensions {
+        say!(
+            console,
+            "  ⚠️  No declensions for {} nouns; --with-declensions is ignored",
+            target_lang.name
+        );
+    }
+    let senses = enrichments.senses;
+    // Wiktionary glosses its senses in English
+    let english_only = base_codes == ["en"];
+    if senses && english_only {
+        say!(
+            console,
+            "  Senses: from Wiktionary, translations checked against the part of speech"
+        );
+    } else if senses {
+        say!(
+            console,
+            "  Senses: other meanings from Wiktionary, in English"
+        );
+    } else if with_senses {
+        say!(
+            console,
+            "  ⚠️  No dictionary for {}; --with-senses is ignored",
+            target_lang.name
+        );
+    }
+    if enrichments.etymology {
+        say!(
+            console,
+            "  Etymology: word origins from Wiktionary, in English"
+        );
+    } else if with_etymology {
+        say!(
+            console,
+            "  ⚠️  No dictionary for {}; --with-etymology is ignored",
+            target_lang.name
+        );
+    }
+    use crate::audio::AudioFetcher;
+
+    let audio_fetcher = enrichments
+        .audio
+        .then(|| {
+            AudioFetcher::from_kinds(
+                &audio_providers,
+                Some(config.cache_dir().clone()),
+                &services,
+            )
+        })
+        .transpose()?
+        .filter(|fetcher| !fetcher.provider_names().is_empty());
+    enrichments.audio = audio_fetcher.is_some();
+    if let Some(fetcher) = &audio_fetcher {
+        say!(
+            console,
+            "  Audio: native speakers, from {}",
+            fetcher.provider_names().join(", then ")
+        );
+    } else if with_audio {
+        say!(console, "  ⚠️  No pronunciation provider to ask (Forvo needs a key in FORVO_API_KEY or config.json); --with-audio is ignored");
+    }
+    if enrichments.mnemonics {
+        use crate::language::MnemonicFinder;
+
+        if MnemonicFinder::new(&base_lang.code).is_empty() {
+            enrichments.mnemonics = false;
+            say!(
+                console,
+                "  ⚠️  No {} word list to find sound-alikes in; --with-mnemonics is ignored",
+                base_lang.name
+            );
+        } else {
+            say!(
+                console,
+                "  Mnemonics: {} sound-alikes in a Hint field",
+                base_lang.name
+            );
+        }
+    }
+    let llm = &services.llm;
+    if enrichments.examples && llm.key().is_none() {
+        enrichments.examples = false;
+        say!(console, "  ⚠️  No key for the LLM (llm.api_key in config.json or OPENAI_API_KEY); --with-examples is ignored");
+    } else if enrichments.examples {
+        say!(
+            console,
+            "  Examples: a sentence per word, written by {}",
+            llm.model
+        );
+    }
+    // The LLM is paid for by the token. A real run estimates its cost once
+    // the words are known; a dry run stops before, so it goes by the count
+    let llm_estimate =
+        (translator == Some(TranslatorKind::Llm) || enrichments.examples).then(|| {
+            let languages = match translator {
+                Some(TranslatorKind::Llm) => base_codes.len(),
+                _ => 0,
+            };
+            llm.estimate(estimated_words, languages, enrichments.examples)
+        });
+    if let Some(estimate) = llm_estimate.as_ref().filter(|_| dry_run) {
+        say!(
+            console,
+            "  LLM cost: at most ~${:.2} ({} requests, ~{} tokens to {}); cached words are free",
+            estimate.dollars,
+            estimate.requests,
+            estimate.input_tokens + estimate.output_tokens,
+            llm.model
+        );
+    }
+    if plain_fields {
+        say!(console, "  Fields: plain text");
+    }
+    say!(console, "  Dry run: {}", dry_run);
+
+    use crate::builder::{DeckBuilder, DeckSpec, FrequencyList, WordSource};
+    use crate::language::PartOfSpeech;
+    use indicatif::{ProgressBar, ProgressStyle};
+
+    let cache_dir = config.cache_dir().clone();
+
+    if !postprocessor.is_empty() {
+        say!(
+            console,
+            "  Using translation rules from {}",
+            rules_file.display()
+        );
+    }
+
+    // The words of the manifest being rebuilt or the report being retried
+    // are used as they are; all others come from the frequency list
+    let list = FrequencyList {
+        source: frequency_source,
+        list_size,
+        merge_weights: merge_weights.clone(),
+    };
+    let word_source = match (&rebuild_from, &retry, &grow) {
+        (Some(manifest), _, _) => WordSource::Words(manifest.rebuild_words()?),
+        (None, Some(retry), _) => WordSource::Words(
+            retry
+                .words()
+                .into_iter()
+                .filter(|word| word_filter.matches(&word.text))
+                .collect(),
+        ),
+        (None, None, Some(state)) => WordSource::After {
+            list,
+            after_rank: state.reached_rank,
+            count: state.per_week,
+        },
+        (None, None, None) if starter => WordSource::Starter,
+        (None, None, None) => match threshold {
+            Some(threshold) => WordSource::Threshold { list, threshold },
+            None => WordSource::Top {
+                list,
+                selection,
+                words_per_pos,
+            },
+        },
+    };
+
+    let translator_kind = translator.unwrap_or_default();
+    let spec = DeckSpec {
+        deck_name: final_deck_name.clone(),
+        target_language: target_lang.code.clone(),
+        base_languages: base_codes.clone(),
+        words: word_source,
+        phrases: phrase_count,
+        sections,
+        filter: word_filter,
+        parts_of_speech,
+        enrichments,
+        audio_providers,
+        enrichment_order,
+        bidirectional,
+        preset,
+        skip_cognates,
+        back_translate,
+        translator: translator_kind,
+        libretranslate_url,
+        rules: postprocessor,
+        glossary,
+        concurrency,
+        // A rebuild's words come from the manifest, and keep its credits
+        attributions: rebuild_from
+            .as_ref()
+            .map(|manifest| manifest.attributions.clone())
+            .unwrap_or_default(),
+        // A retry's few words would misdescribe the deck, so it keeps the
+        // description unless given one
+        description_template: deck_description
+            .or(config.deck_description.clone())
+            .or_else(|| retry.is_some().then(String::new)),
+        confusable_hints,
+        // A rebuild keeps the translations of its manifest
+        translations: rebuild_from
+            .as_ref()
+            .map(RunManifest::translations)
+            .unwrap_or_default(),
+    };
+    // A dry run only looks, whatever --read-only says
+    let anki_client = match dry_run {
+        true => config.anki_client()?.with_read_only(true),
+        false => config.anki_client()?,
+    };
+    let sink = open_sink(
+        output_backend,
+        &anki_client,
+        &final_deck_name,
+        output.as_deref(),
+        console,
+    )?;
+    let mut deck_builder = DeckBuilder::new(spec, &anki_client, cache_dir.clone())
+        .with_services(services.clone())
+        .with_sink(sink)
+        .with_cancel(cancel.clone());
+
+    use crate::ankiweb::CardRenderer;
+    use crate::changelog::version_tag;
+
+    // Every run is the next version of the deck, and tags its notes with it
+    let deck_version =
+        RunManifest::load(&RunManifest::path(&config.manifest_dir(), &final_deck_name))
+            .ok()
+            .flatten()
+            .map_or(1, |previous| previous.version + 1);
+    let tags: Vec<String> = tags
+        .into_iter()
+        .chain([version_tag(deck_version)])
+        .collect();
+
+    let card_builder = deck_builder
+        .card_builder()
+        .with_bidirectional_strategy(bidirectional_strategy)
+        .with_sort_field(sort_field)
+        .with_dedup_field(dedup_field)
+        .with_allow_duplicates(allow_duplicates)
+        .with_duplicate_scope(duplicate_scope)
+        .with_extra_tags(tags)
+        .with_mapping(note_mapping.clone())
+        .with_renderer(
+            CardRenderer::default()
+                .with_html(!plain_fields)
+                .with_pos_badge(!no_pos_badge),
+        );
+
+    // An unsupported pair would otherwise only fail once translating starts
+    for pair in deck_builder.check_translators().await? {
+        if !pair.skipped.is_empty() {
+            say!(
+                console,
+                "⚠️  {} can't translate {} → {}; only {} will be asked",
+                pair.skipped.join(", "),
+                pair.from,
+                pair.to,
+                pair.providers.join(", ")
+            );
+        }
+    }
+
+    if dry_run {
+        say!(console, "\n🔍 Dry run mode - no deck will be created");
+        let preview = preview_notes(console, &mut deck_builder, &card_builder, preview).await?;
+        say!(console, "\n✅ Configuration validated successfully!");
+        let spec = deck_builder.spec();
+        return console.emit_json(&json!({
+            "dry_run": true,
+            "target_language": { "code": target_lang.code, "name": target_lang.name },
+            "base_language": { "code": base_lang.code, "name": base_lang.name },
+            "extra_base_languages": extra_base_langs
+                .iter()
+                .map(|l| json!({ "code": l.code, "name": l.name }))
+                .collect::<Vec<_>>(),
+            "words_per_pos": words_per_pos,
+            "selection": selection,
+            "threshold": threshold,
+            "parts_of_speech": spec
+                .parts_of_speech
+                .iter()
+                .map(PartOfSpeech::name)
+                .collect::<Vec<_>>(),
+            "phrases": phrase_count,
+            "sections": spec.sections,
+            "starter": starter_count.is_some(),
+            "preset": preset,
+            "skip_cognates": skip_cognates,
+            "estimated_cards": estimated_cards,
+            "deck_name": final_deck_name,
+            "profile": profile_name,
+            "bidirectional": bidirectional,
+            "bidirectional_strategy": bidirectional_strategy,
+            "sort_field": sort_field,
+            "frequency_source": frequency_source,
+            "list_size": list_size,
+            "merge_weights": (frequency_source == FrequencySourceKind::Merged)
+                .then(|| resolve_weights(&merge_weights)),
+            "word_filter": {
+                "min_length": min_word_length,
+                "max_length": max_word_length,
+                "include": include,
+                "exclude": exclude,
+                "known_words": spec.filter.known_word_count(),
+            },
+            "note_model": note_mapping,
+            "with_images": with_images,
+            "readings": readings,
+            "conjugations": conjugations,
+            "declensions": declensions,
+            "senses": senses,
+            "etymology": enrichments.etymology,
+            "examples": enrichments.examples,
+            "llm_estimate": llm_estimate,
+            "audio_providers": audio_fetcher.as_ref().map(AudioFetcher::provider_names),
+            "enrichment_order": spec.enrichment_order,
+            "html_fields": !plain_fields,
+            "confusable_hints": confusable_hints,
+            "from_report": from_report,
+            "decks": deck_builder.deck_names(&card_builder),
+            "preview": preview,
+        }));
+    }
+
+    use crate::events::{BuildEvent, BuildOutcome};
+
+    // Phase 4: Load frequency data, or take the words of the report being
+    // retried or the manifest being rebuilt. Loaded up front, so they are
+    // shown before the deck is touched
+    let all_words = match &deck_builder.spec().words {
+        WordSource::Words(words) => {
+            if rebuild_from.is_some() {
+                say!(
+                    console,
+                    "\n🔁 Rebuilding {} words from the manifest",
+                    words.len()
+                );
+            } else {
+                say!(
+                    console,
+                    "\n🔁 Retrying {} words from the report",
+                    words.len()
+                );
+            }
+            words.clone()
+        }
+        WordSource::Starter => {
+            let words = deck_builder.load_words().await?;
+            say!(
+                console,
+                "\n📋 Using the {} starter list: {} words and phrases",
+                target_lang.name,
+                words.len()
+            );
+            words
+        }
+        _ => {
+            say!(
+                console,
+                "\n📊 Loading {} word frequency data...",
+                target_lang.name
+            );
+
+            let spinner = hidden_under_dashboard(console, ProgressBar::new_spinner());
+            spinner.set_style(
+                console.console_safe(
+                    ProgressStyle::default_spinner()
+                        .template("{prefix}{spinner:.green} {msg}")
+                        .unwrap(),
+                ),
+            );
+            spinner.set_message(format!("Fetching {} frequency data...", target_lang.name));
+            spinner.enable_steady_tick(std::time::Duration::from_millis(100));
+
+            let all_words = deck_builder.load_words().await?;
+            spinner.finish_with_message(
+                console
+                    .plain(&console.tr(&format!("✅ Loaded {} word data", target_lang.name)))
+                    .into_owned(),
+            );
+
+            let count = |pos: PartOfSpeech| all_words.iter().filter(|w| w.pos == pos).count();
+
+            say!(console, "\n📝 Word selection:");
+            say!(console, "  Nouns: {} words", count(PartOfSpeech::Noun));
+            say!(console, "  Verbs: {} words", count(PartOfSpeech::Verb));
+            say!(
+                console,
+                "  Adjectives: {} words",
+                count(PartOfSpeech::Adjective)
+            );
+            if count(PartOfSpeech::Phrase) > 0 {
+                say!(console, "  Phrases: {}", count(PartOfSpeech::Phrase));
+            }
+            say!(console, "  Total: {} words selected", all_words.len());
+
+            if !all_words.is_empty() {
+                say!(console, "\n🔤 Sample words:");
+                for word in all_words.iter().take(5) {
+                    say!(console, "  - {} ({:?})", word.text, word.pos);
+                }
+                if all_words.len() > 5 {
+                    say!(console, "  ... and {} more", all_words.len() - 5);
+                }
+            }
+
+            all_words
+        }
+    };
+
+    // Ranks a grown deck gets to once every word picked has been handled
+    let ranks = || {
+        all_words
+            .iter()
+            .filter(|word| word.pos != PartOfSpeech::Phrase)
+            .map(|word| word.rank)
+    };
+    let grown_ranks = ranks().min().zip(ranks().max());
+    if let (Some(state), None) = (&grow, grown_ranks) {
+        say!(
+            console,
+            "\nℹ️  No words left after rank {} in the frequency list; '{}' is fully grown",
+            state.reached_rank,
+            state.deck_name
+        );
+        return console.emit_json(&json!({
+            "deck_name": state.deck_name,
+            "words": 0,
+            "reached_rank": state.reached_rank,
+        }));
+    }
+
+    // Phase 5: Connect to Anki (or check the output file can be written)
+    // first, so nothing is translated for a deck that can't be written to
+    match (output_backend, &output) {
+        (OutputBackend::Csv, Some(path)) => say!(
+            console,
+            "\n📚 Writing deck '{}' to {}...",
+            final_deck_name,
+            path.display()
+        ),
+        (OutputBackend::Stdout, _) => say!(
+            console,
+            "\n📚 Printing the notes of '{}'...",
+            final_deck_name
+        ),
+        _ => say!(console, "\n📚 Creating Anki deck: '{}'...", final_deck_name),
+    }
+
+    // Files and printed notes need no Anki
+    if output_backend.uses_anki() {
+        // Verify AnkiConnect is running
+        let verify_spinner = hidden_under_dashboard(console, ProgressBar::new_spinner());
+        verify_spinner.set_style(
+            console.console_safe(
+                ProgressStyle::default_spinner()
+                    .template("{prefix}{spinner:.green} {msg}")
+                    .unwrap(),
+            ),
+        );
+        verify_spinner.set_message(console.tr("Checking AnkiConnect connection..."));
+        verify_spinner.enable_steady_tick(std::time::Duration::from_millis(100));
+
+        match anki_client.verify_connection().await {
+            Ok(()) => {
+                verify_spinner.finish_with_message(
+                    console
+                        .plain(&console.tr("✅ Connected to AnkiConnect"))
+                        .into_owned(),
+                );
+            }
+            Err(e) => {
+                verify_spinner.finish_with_message(
+                    console
+                        .plain(&console.tr("❌ Failed to connect"))
+                        .into_owned(),
+                );
+                say_err!(console, "\n❌ Could not connect to AnkiConnect: {}", e);
+                say_err!(console, "\n💡 Make sure:");
+                say_err!(console, "  1. Anki is running");
+                say_err!(console, "  2. AnkiConnect add-on is installed");
+                say_err!(console, "  3. Try running: make run ARGS=\"test\"");
+                return Err(e.into());
+            }
+        }
+
+        if let Some(mapping) = &note_mapping {
+            crate::doctor::check_note_mapping(&anki_client, mapping, &card_builder).await?;
+        }
+    }
+
+    // Phase 6: Translate each word and add its notes as soon as it is ready
+    use crate::images::ImageClient;
+    use crate::manifest::BuildRecipe;
+
+    let progress = hidden_under_dashboard(console, ProgressBar::new(0));
+    progress.set_style(
+        ProgressStyle::default_bar()
+            .template("{prefix}{msg} [{bar:40}] {pos}/{len} ({percent}%)")
+            .unwrap()
+            .progress_chars("=>-"),
+    );
+
+    let mut pipeline = deck_builder.enrichment_pipeline()?;
+    if enrichments.images && review && !console.has_dashboard() {
+        pipeline = pipeline.with_replaced(PickedImageEnricher {
+            images: ImageClient::new(&services.http)?,
+            progress: progress.clone(),
+            console: console.clone(),
+        });
+    }
+    let mut hooks = CreateHooks {
+        config,
+        console,
+        deck_name: final_deck_name.clone(),
+        output_backend,
+        preset,
+        interactive,
+        yes,
+        duplicates,
+        duplicate_deck: deck_builder.spec().duplicate_deck(),
+        words_checked: all_words.len(),
+        target: target_lang.clone(),
+        base_names: base_names.clone(),
+        base_codes: base_codes.clone(),
+        translator_kind,
+        concurrency,
+        bidirectional,
+        back_translate,
+        card_builder: card_builder.clone(),
+        estimate_pipeline: deck_builder.enrichment_pipeline()?,
+        session: review_session.map(|session| (session, session_path.clone())),
+        reviewer: None,
+        progress: progress.clone(),
+        failures: FailureReport::new(&final_deck_name, &target_lang.code, &base_codes),
+        manifest: RunManifest::new(&final_deck_name)
+            .with_version(deck_version)
+            .with_attributions(deck_builder.attributions()),
+        known_count: 0,
+        translators: Vec::new(),
+        estimate: None,
+        flagged: BTreeMap::new(),
+        note_ids: Vec::new(),
+        finished: None,
+    };
+
+    // A grown deck is described with the words of its earlier runs
+    let earlier_words = grow.as_ref().map_or(0, |state| {
+        state.history.iter().map(|run| run.words).sum::<usize>()
+    });
+    let bar = progress.clone();
+    let events = console.clone();
+    let result = deck_builder
+        .with_card_builder(card_builder.clone())
+        .with_enrichment_pipeline(pipeline)
+        .with_loaded_words(all_words)
+        .with_earlier_words(earlier_words)
+        .with_deadline(deadline)
+        .with_events(move |event| {
+            if let BuildEvent::Progress { done, .. } = &event {
+                bar.set_position(*done as u64);
+            }
+            events.emit(event);
+        })
+        .with_hooks(&mut hooks)
+        .build()
+        .await;
+    let failure = match result {
+        Ok(_) => None,
+        // Only a translation failure ends a build that can be wrapped up
+        Err(e) if hooks.finished.is_some() => Some(e),
+        Err(e) => return Err(e.into()),
+    };
+    // Or the run was called off at the estimate
+    let Some((summary, written)) = hooks.finished.take() else {
+        say!(console, "Nothing was translated or added");
+        return Ok(());
+    };
+    let CreateHooks {
+        failures,
+        manifest,
+        reviewer,
+        known_count,
+        translators,
+        estimate,
+        flagged,
+        note_ids,
+        ..
+    } = hooks;
+
+    // What `rebuild` needs to make this deck again. A retry keeps the recipe
+    // of the run it retries
+    let recipe = from_report.is_none().then(|| {
+        let options = CreateArgs {
+            target_language: Some(target_lang.code.clone()),
+            base_language: None,
+            base_languages: base_codes.clone(),
+            deck_name: Some(final_deck_name.clone()),
+            dry_run: false,
+            review: false,
+            // The words come from the manifest, so options that only pick
+            // words or shape translations don't matter any more
+            exclude_file: None,
+            exclude_deck: Vec::new(),
+            rules: None,
+            report: None,
+            from_report: None,
+            rebuild_from: None,
+            ..recipe_args
+        };
+        BuildRecipe {
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            target_language: target_lang.code.clone(),
+            base_languages: base_codes.clone(),
+            word_list_hash: String::new(),
+            frequency_source: match frequency_source {
+                FrequencySourceKind::Merged => {
+                    format!("merged {} ({})", list_size.name(), merge_description)
+                }
+                kind => format!("{} {}", kind.name(), list_size.name()),
+            },
+            translators,
+            options: serde_json::to_value(&options).unwrap_or_default(),
+        }
+    });
+    let manifest = manifest.with_recipe(recipe);
+
+    let words_done = summary.words;
+    let total_words = summary.total_words;
+    let success_count = summary.notes_added;
+    let error_count = summary.notes_failed;
+    let cognate_count = summary.cognates_skipped;
+    let disambiguated_count = summary.disambiguated;
+    // Words skipped before translating count among the duplicates
+    let duplicate_count = summary.duplicates_skipped - known_count;
+    let deadline_hit = summary.outcome == BuildOutcome::DeadlineReached;
+    let cancelled = summary.outcome == BuildOutcome::Cancelled;
+
+    let default_report = report.is_none();
+    let report_path =
+        report.unwrap_or_else(|| FailureReport::path(&config.report_dir(), &final_deck_name));
+    let report_path = if failures.is_empty() {
+        // A clean run leaves nothing to retry from an earlier one
+        if default_report && report_path.exists() {
+            let _ = std::fs::remove_file(&report_path);
+        }
+        None
+    } else {
+        match failures.save(&report_path) {
+            Ok(()) => Some(report_path),
+            Err(e) => {
+                tracing::warn!("Failed to save failure report: {}", e);
+                None
+            }
+        }
+    };
+
+    // Saved even when the run stops early, so the next one can be compared
+    let manifest_path = RunManifest::path(&config.manifest_dir(), &final_deck_name);
+    let previous_manifest = RunManifest::load(&manifest_path).unwrap_or_else(|e| {
+        tracing::warn!("Ignoring unreadable manifest: {}", e);
+        None
+    });
+    let changes = manifest.diff(previous_manifest.as_ref());
+    let manifest_attributions = manifest.attributions.clone();
+    if let Err(e) = manifest
+        .merged_with(previous_manifest)
+        .sealed()
+        .save(&manifest_path)
+    {
+        tracing::warn!("Failed to save run manifest: {}", e);
+    }
+
+    let mut synced = None;
+    let run = RunRecord::new(
+        summary.clone(),
+        &target_lang.code,
+        &base_codes,
+        started.elapsed(),
+    )
+    .with_failures(&failures)
+    .with_report(report_path.clone())
+    .with_note_ids(note_ids);
+    let run_id = RunHistory::record(&config.history_file(), run)
+        .map_err(|e| tracing::warn!("Failed to record the run in the history: {}", e))
+        .ok();
+    let entry = ChangelogEntry::new(
+        deck_version,
+        crate::naming::today(),
+        summary.outcome,
+        &changes,
+    )
+    .with_run_id(run_id)
+    .with_notes_added(success_count);
+    if let Err(e) = DeckChangelog::record(
+        &DeckChangelog::path(&config.changelog_dir(), &final_deck_name),
+        &final_deck_name,
+        entry,
+    ) {
+        tracing::warn!("Failed to update the deck's changelog: {}", e);
+    }
+
+    if let Some(e) = failure {
+        progress.abandon_with_message(
+            console
+                .plain(&console.tr("❌ Translation failed"))
+                .into_owned(),
+        );
+        say!(
+            console,
+            "\n❌ Stopped after {} of {} words; {} notes were added and are kept.",
+            words_done,
+            total_words,
+            success_count
+        );
+        print_report_hint(console, report_path.as_deref(), failures.failures.len());
+        return Err(e.into());
+    }
+
+    if deadline_hit {
+        progress.abandon_with_message(
+            console
+                .plain(&console.tr("⏱️  Deadline reached"))
+                .into_owned(),
+        );
+        say!(
+            console,
+            "\n⏱️  Deadline reached after {} of {} words. Rerun to add the rest.",
+            words_done,
+            total_words
+        );
+    } else if cancelled {
+        progress.abandon_with_message(console.plain(&console.tr("⏹️  Cancelled")).into_owned());
+        say!(
+            console,
+            "\n⏹️  Cancelled after {} of {} words; the notes added so far are kept.",
+            words_done,
+            total_words
+        );
+    } else {
+        progress.finish_with_message(console.plain(&console.tr("✅ Cards added")).into_owned());
+
+        if let Some(reviewer) = &reviewer {
+            reviewer.finish()?;
+        }
+
+        say!(console, "\n🎉 Deck creation complete!");
+
+        if let (Some(mut state), Some((from_rank, to_rank))) = (grow, grown_ranks) {
+            state.advance(from_rank, to_rank, words_done);
+            let path = GrowthState::path(&config.growth_dir(), &state.deck_name);
+            match state.save(&path) {
+                Ok(()) => say!(
+                    console,
+                    "  📈 Next run continues after rank {} ({} runs so far)",
+                    state.reached_rank,
+                    state.history.len()
+                ),
+                Err(e) => tracing::warn!("Failed to save growth progress: {}", e),
+            }
+        }
+
+        // The notes are in the collection either way, so a failed sync only warns
+        if sync {
+            match anki_client.sync().await {
+                Ok(()) => {
+                    say!(console, "  🔄 Synced with AnkiWeb");
+                    synced = Some(true);
+                }
+                Err(e) => {
+                    say!(console, "  ⚠️  Sync with AnkiWeb failed: {}", e);
+                    synced = Some(false);
+                }
+            }
+        }
+
+        if open {
+            open_in_anki(console, &anki_client, &final_deck_name, success_count).await;
+        }
+    }
+    say!(
+        console,
+        "  ✅ {} notes added successfully ({} cards)",
+        success_count,
+        success_count * card_builder.cards_per_note()
+    );
+    if known_count > 0 {
+        say!(
+            console,
+            "  ⏭️  {} words already in Anki skipped before translating",
+            known_count
+        );
+    }
+    if duplicate_count > 0 {
+        say!(console, "  ⏭️  {} duplicate notes skipped", duplicate_count);
+    }
+    let enriched_count = |name: &str| summary.enriched.get(name).copied().unwrap_or(0);
+    if with_images {
+        say!(
+            console,
+            "  🖼️  {} notes with images",
+            enriched_count("images")
+        );
+    }
+    if declensions {
+        say!(
+            console,
+            "  📚 {} notes with declensions",
+            enriched_count("declensions")
+        );
+    }
+    if enrichments.audio {
+        say!(
+            console,
+            "  🔊 {} notes with a recording",
+            enriched_count("audio")
+        );
+    }
+    if enrichments.mnemonics {
+        say!(
+            console,
+            "  💡 {} notes with a memory hook",
+            enriched_count("mnemonics")
+        );
+    }
+    if enrichments.etymology {
+        say!(
+            console,
+            "  🏛️  {} notes with an etymology",
+            enriched_count("etymology")
+        );
+    }
+    if enrichments.examples {
+        say!(
+            console,
+            "  💬 {} notes with an example sentence",
+            enriched_count("examples")
+        );
+    }
+    if senses {
+        say!(
+            console,
+            "  📖 {} notes with other meanings",
+            enriched_count("other_meanings")
+        );
+        if disambiguated_count > 0 {
+            say!(
+                console,
+                "  🔀 {} translations replaced by the sense of their part of speech",
+                disambiguated_count
+            );
+        }
+    }
+    if cognate_count > 0 {
+        say!(console, "  🔁 {} cognates skipped", cognate_count);
+    }
+    if error_count > 0 {
+        say!(console, "  ⚠️  {} notes failed", error_count);
+    }
+    say!(console, "  📚 Deck name: {}", final_deck_name);
+    for attribution in &manifest_attributions {
+        say!(console, "  📜 Word list: {}", attribution.credit());
+    }
+
+    if !review && !flagged.is_empty() {
+        say!(
+            console,
+            "\n⚠️  {} words held back for manual review (rerun with --review to check them):",
+            flagged.len()
+        );
+        for (word, issue) in flagged.iter().take(10) {
+            say!(console, "  - {} ({})", word, issue);
+        }
+        if flagged.len() > 10 {
+            say!(console, "  ... and {} more", flagged.len() - 10);
+        }
+    }
+
+    print_changes(console, &changes);
+    print_report_hint(console, report_path.as_deref(), failures.failures.len());
+
+    match (output_backend, &written) {
+        (OutputBackend::Csv, Some(path)) => {
+            say!(
+                console,
+                "\n💡 Import {} in Anki with File → Import to study your {} words",
+                path.display(),
+                success_count
+            );
+            let media_dir = csv_media_dir(path);
+            if media_dir.exists() {
+                say!(
+                    console,
+                    "   First copy the files in {} into Anki's collection.media folder",
+                    media_dir.display()
+                );
+            }
+        }
+        (OutputBackend::Apkg, Some(path)) => say!(
+            console,
+            "\n💡 Exported to {}; open it in Anki on any device to study your {} words",
+            path.display(),
+            success_count
+        ),
+        (OutputBackend::Stdout, _) => {}
+        _ => say!(
+            console,
+            "\n💡 Open Anki to start studying your {} words!",
+            success_count
+        ),
+    }
+
+    console.emit_json(&json!({
+        "dry_run": false,
+        "completed": !deadline_hit && !cancelled,
+        "cancelled": cancelled,
+        "deck_name": final_deck_name,
+        "output_backend": output_backend,
+        "output": written,
+        "words": words_done,
+        "notes_added": success_count,
+        "cards_added": success_count * card_builder.cards_per_note(),
+        "notes_skipped_duplicate": duplicate_count,
+        "words_skipped_duplicate": known_count,
+        "notes_failed": error_count,
+        "notes_with_images": enriched_count("images"),
+        "notes_with_declensions": enriched_count("declensions"),
+        "notes_with_other_meanings": enriched_count("other_meanings"),
+        "notes_with_audio": enriched_count("audio"),
+        "notes_with_mnemonics": enriched_count("mnemonics"),
+        "notes_with_etymology": enriched_count("etymology"),
+        "notes_with_examples": enriched_count("examples"),
+        "estimate": estimate,
+        "translations_disambiguated": disambiguated_count,
+        "cognates_skipped": cognate_count,
+        "flagged": flagged,
+        "changes": changes,
+        "report": report_path,
+        "synced": synced,
+        "attributions": manifest_attributions,
+    }))?;
+
+    if deadline_hit {
+        return Err(AnkiDeckBuilderError::DeadlineExceeded(format!(
+            "processed {} of {} words",
+            words_done, total_words
+        ))
+        .into());
+    }
+    if cancelled {
+        return Err(AnkiDeckBuilderError::Cancelled(format!(
+            "processed {} of {} words",
+            words_done, total_words
+        ))
+        .into());
+    }
+
+    Ok(())
+}
+
+/// Run `create` for several target languages at once, one deck each. The
+/// runs share the translation and frequency caches and don't prompt, so
+/// each deck gets its default (or `--deck-name-pattern`) name
+async fn handle_create_many(
+    console: &Console,
+    config: &Config,
+    args: CreateArgs,
+    deadline: Deadline,
+    cancel: &CancellationToken,
+) -> Result<()> {
+    use std::sync::{Arc, Mutex};
+
+    let targets = args.target_languages();
+    if args.deck_name.is_some() {
+        return Err(AnkiDeckBuilderError::ConfigurationError(
+            "--deck-name names a single deck; use --deck-name-pattern with {target} for several target languages".to_string(),
+        )
+        .into());
+    }
+    if args.review || args.from_report.is_some() || args.rebuild_from.is_some() {
+        return Err(AnkiDeckBuilderError::ConfigurationError(
+            "--review and --from-report work on one target language at a time".to_string(),
+        )
+        .into());
+    }
+
+    say!(
+        console,
+        "🌍 Creating {} decks at once: {}\n",
+        targets.len(),
+        targets.join(", ")
+    );
+    let (profile_args, _) = apply_config_profile(config, args.clone())?;
+    let shared = SharedResources::load(config, &profile_args).await?;
+    let runs = targets.iter().map(|target| {
+        let args = CreateArgs {
+            target_language: Some(target.clone()),
+            ..args.clone()
+        };
+        // Each run's lines are marked until it knows its deck name, and its
+        // result goes into the combined document
+        let results = Arc::new(Mutex::new(Vec::new()));
+        let console = console
+            .clone()
+            .with_prefix(target)
+            .with_results(results.clone());
+        let shared = &shared;
+        async move {
+            let result = handle_create(
+                &console,
+                config,
+                args,
+                false,
+                deadline,
+                cancel,
+                Some(shared),
+            )
+            .await;
+            let document = results.lock().unwrap_or_else(|e| e.into_inner()).pop();
+            (result, document)
+        }
+    });
+    let results = futures::future::join_all(runs).await;
+
+    say!(console, "\n📋 Summary:");
+    let mut decks = Vec::new();
+    for (target, (result, document)) in targets.iter().zip(&results) {
+        match result {
+            Ok(()) => say!(console, "  ✅ {}", target),
+            Err(e) => say!(console, "  ❌ {}: {:#}", target, e),
+        }
+        decks.push(json!({
+            "target_language": target,
+            "ok": result.is_ok(),
+            "error": result.as_ref().err().map(|e| format!("{:#}", e)),
+            "result": document,
+        }));
+    }
+    console.emit_json(&json!({ "decks": decks }))?;
+
+    let failed = results.iter().filter(|(result, _)| result.is_err()).count();
+    if failed > 0 {
+        anyhow::bail!("{} of {} decks failed", failed, targets.len());
+    }
+    Ok(())
+}
+
+/// Run `create` behind the full-screen dashboard. Its output goes to the
+/// dashboard as build events, and any error is printed once the terminal
+/// is given back
+async fn handle_tui(
+    console: &Console,
+    config: &Config,
+    args: CreateArgs,
+    deadline: Deadline,
+    cancel: &CancellationToken,
+) -> Result<()> {
+    use crate::events::BuildEvent;
+    use crate::tui::Dashboard;
+    use std::io::IsTerminal;
+
+    if console.is_json() {
+        return Err(AnkiDeckBuilderError::ConfigurationError(
+            "tui can't be combined with --json; use create instead".to_string(),
+        )
+        .into());
+    }
+    if !std::io::stdout().is_terminal() {
+        return Err(AnkiDeckBuilderError::MissingInput(
+            "tui needs an interactive terminal".to_string(),
+        )
+        .into());
+    }
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let dashboard = Dashboard::start(receiver, cancel.clone())?;
+    let console = console.clone().with_events(sender);
+
+    // Every setting comes from the arguments; prompts would draw over the dashboard
+    let result = handle_create(&console, config, args, false, deadline, cancel, None).await;
+    if let Err(e) = &result {
+        console.emit(BuildEvent::Message(format!("❌ {:#}", e)));
+    }
+    // Hanging up tells the dashboard the build is over
+    drop(console);
+
+    tokio::task::spawn_blocking(move || dashboard.wait()).await??;
+    result
+}
+
+/// Build a deck again from the recipe and words in its manifest. Translations
+/// come from the manifest and the frequency list isn't needed, so only
+/// what's missing here (note types, images) is fetched
+async fn handle_rebuild(
+    console: &Console,
+    config: &Config,
+    manifest_path: PathBuf,
+    deck_name: Option<String>,
+    deadline: Deadline,
+    cancel: &CancellationToken,
+) -> Result<()> {
+    let manifest = RunManifest::load(&manifest_path)?.ok_or_else(|| {
+        AnkiDeckBuilderError::MissingInput(format!(
+            "Manifest not found: {}",
+            manifest_path.display()
+        ))
+    })?;
+    let Some(recipe) = manifest.recipe.clone() else {
+        return Err(AnkiDeckBuilderError::ConfigurationError(format!(
+            "{} has no build recipe; run create once more to add one",
+            manifest_path.display()
+        ))
+        .into());
+    };
+    // Fail before anything is done when words lack what notes need
+    manifest.rebuild_words()?;
+
+    say!(
+        console,
+        "🔁 Rebuilding '{}' ({} words, {} → {})",
+        manifest.deck_name,
+        manifest.words.len(),
+        recipe.target_language,
+        recipe.base_languages.join("+")
+    );
+    if !manifest.is_intact() {
+        say!(console, "⚠️  The manifest was edited since it was written; rebuilding the words as they are now");
+    }
+    if recipe.tool_version != env!("CARGO_PKG_VERSION") {
+        say!(
+            console,
+            "ℹ️  Built with version {}, rebuilding with {}",
+            recipe.tool_version,
+            env!("CARGO_PKG_VERSION")
+        );
+    }
+
+    let mut args: CreateArgs = serde_json::from_value(recipe.options)?;
+    if deck_name.is_some() {
+        args.deck_name = deck_name;
+    }
+    args.rebuild_from = Some(manifest);
+
+    handle_create(console, config, args, false, deadline, cancel, None).await
+}
+
+/// Options of the grow command
+struct GrowArgs {
+    deck_name: String,
+    per_week: Option<usize>,
+    target_language: Option<String>,
+    base_language: Option<String>,
+    sync: bool,
+}
+
+/// Add the next words of a deck's frequency list. The first run sets the
+/// languages; later ones read them from the deck's growth state
+async fn handle_grow(
+    console: &Console,
+    config: &Config,
+    args: GrowArgs,
+    deadline: Deadline,
+    cancel: &CancellationToken,
+) -> Result<()> {
+    let GrowArgs {
+        deck_name,
+        per_week,
+        target_language,
+        base_language,
+        sync,
+    } = args;
+    use crate::growth::DEFAULT_PER_WEEK;
+
+    let path = GrowthState::path(&config.growth_dir(), &deck_name);
+    let mut state = match GrowthState::load(&path)? {
+        Some(state) => {
+            if target_language
+                .as_ref()
+                .is_some_and(|target| *target != state.target_language)
+            {
+                say!(
+                    console,
+                    "ℹ️  '{}' is grown in {}; --target-language is ignored",
+                    deck_name,
+                    state.target_language
+                );
+            }
+            state
+        }
+        None => {
+            let target_language = target_language.ok_or_else(|| {
+                AnkiDeckBuilderError::MissingInput(format!(
+                    "--target-language is needed the first time '{}' is grown",
+                    deck_name
+                ))
+            })?;
+            say!(console, "🌱 Starting to grow '{}'", deck_name);
+            GrowthState::new(
+                &deck_name,
+                &target_language,
+                base_language.as_deref().unwrap_or("en"),
+                per_week.unwrap_or(DEFAULT_PER_WEEK),
+            )
+        }
+    };
+    if let Some(per_week) = per_week {
+        state.per_week = per_week;
+    }
+
+    let args = CreateArgs {
+        target_language: Some(state.target_language.clone()),
+        base_language: Some(state.base_language.clone()),
+        deck_name: Some(state.deck_name.clone()),
+        sync,
+        grow: Some(state),
+        ..CreateArgs::default()
+    };
+    handle_create(console, config, args, false, deadline, cancel, None).await
+}
+
+async fn handle_serve(
+    console: &Console,
+    config: &Config,
+    address: std::net::SocketAddr,
+    cancel: &CancellationToken,
+) -> Result<()> {
+    use crate::ankiweb::AnkiBackend;
+    use crate::language::{Glossary, PostProcessor};
+    use crate::server::{serve, ServerState};
+    use std::sync::Arc;
+
+    let anki_client = config.anki_client()?;
+    anki_client.verify_connection().await?;
+    let rules = PostProcessor::load(&config.rules_file())?;
+    let state = ServerState::new(Arc::new(anki_client), config.cache_dir().clone(), rules)
+        .with_glossary(Glossary::load(&config.glossary_dir())?)
+        .with_services(config.services());
+
+    say!(
+        console,
+        "🌐 Serving builds on http://{} (Ctrl+C to stop)",
+        address
+    );
+    serve(state, address, cancel.clone()).await?;
+    say!(console, "👋 Server stopped");
+    Ok(())
+}
+
+/// Options of the prefetch command
+struct PrefetchArgs {
+    target_languages: Vec<String>,
+    base_languages: Vec<String>,
+    words_per_pos: usize,
+    selection: Selection,
+    frequency_source: FrequencySourceKind,
+    list_size: ListSize,
+    concurrency: usize,
+    delay: std::time::Duration,
+    translator: TranslatorKind,
+    libretranslate_url: Option<String>,
+}
+
+/// Options of the fix command
+struct FixArgs {
+    target_language: String,
+    tag: String,
+    query: Option<String>,
+    deck: Option<String>,
+    dry_run: bool,
+    translator: TranslatorKind,
+    libretranslate_url: Option<String>,
+    back_translate: bool,
+}
+
+/// Translate the notes picked by a tag or search again, replacing their
+/// translation where the providers now give a better one
+async fn handle_fix(console: &Console, config: &Config, args: FixArgs) -> Result<()> {
+    let FixArgs {
+        target_language,
+        tag,
+        query,
+        deck,
+        dry_run,
+        translator,
+        libretranslate_url,
+        back_translate,
+    } = args;
+    use crate::ankiweb::{deck_query, tag_query};
+    use crate::builder::translation_chain;
+    use crate::fix::{forget_translations, retranslate, AmendableNote};
+    use crate::language::{Glossary, PostProcessor};
+
+    let target = config
+        .language_packs
+        .language(&target_language)
+        .ok_or_else(|| AnkiDeckBuilderError::UnsupportedLanguage(target_language.to_string()))?;
+
+    let client = config.anki_client()?;
+    client.verify_connection().await?;
+
+    // Notes picked by their flag tag have it taken off once fixed
+    let by_tag = query.is_none();
+    let query = match (query.as_deref(), deck.as_deref()) {
+        (Some(query), Some(deck)) => format!("{} ({})", deck_query(deck, &[]), query),
+        (Some(query), None) => query.to_string(),
+        (None, Some(deck)) => deck_query(deck, &[tag.to_string()]),
+        (None, None) => tag_query(&tag),
+    };
+    let note_ids = client.find_notes(&query).await?;
+    if note_ids.is_empty() {
+        say!(console, "ℹ️  No notes matched: {}", query);
+        return console.emit_json(&json!({ "query": query, "notes": 0, "fixes": [] }));
+    }
+
+    let infos = client.notes_info(&note_ids).await?;
+    let notes: Vec<AmendableNote> = infos
+        .iter()
+        .filter_map(|info| AmendableNote::from_info(info, &target.code))
+        .collect();
+    let skipped = infos.len() - notes.len();
+    if skipped > 0 {
+        say!(
+            console,
+            "⚠️  Skipping {} notes that are not {} cards made by this tool",
+            skipped,
+            target.name
+        );
+    }
+    say!(console, "🔧 Translating {} notes again", notes.len());
+
+    // A cached answer would only repeat the translation being fixed
+    let services = config.services();
+    let cache_dir = config.cache_dir().clone();
+    for note in &notes {
+        forget_translations(
+            &cache_dir,
+            std::slice::from_ref(&note.word),
+            &note.target_code,
+            &note.base_codes,
+        )?;
+    }
+    let translator = translation_chain(
+        &cache_dir,
+        PostProcessor::load(&config.rules_file())?,
+        translator,
+        libretranslate_url,
+        &services,
+    )?
+    .with_back_translation(back_translate)
+    .with_glossary(Glossary::load(&config.glossary_dir())?);
+
+    let mut fixes = Vec::new();
+    let mut fixed = Vec::new();
+    for note in &notes {
+        let fix = match retranslate(&translator, note).await {
+            Ok(fix) => fix,
+            Err(e) => {
+                say!(console, "  ❌ {}: {}", note.word, e);
+                continue;
+            }
+        };
+        match &fix.issue {
+            Some(issue) => say!(
+                console,
+                "  ⚠️  {}: {} ({}; left as it is)",
+                fix.word,
+                fix.new_translation,
+                issue
+            ),
+            None if !fix.is_improvement() => {
+                say!(
+                    console,
+                    "  ➖ {}: {} (unchanged)",
+                    fix.word,
+                    fix.old_translation
+                )
+            }
+            None => say!(
+                console,
+                "  ✏️  {}: {} → {}",
+                fix.word,
+                fix.old_translation,
+                fix.new_translation
+            ),
+        }
+        if fix.issue.is_none() {
+            if fix.is_improvement() && !dry_run {
+                client.update_note_fields(fix.note_id, &fix.fields).await?;
+            }
+            fixed.push(fix.note_id);
+        }
+        fixes.push(fix);
+    }
+
+    let updated = fixes.iter().filter(|fix| fix.is_improvement()).count();
+    if dry_run {
+        say!(console, "\n🔍 Dry run: {} notes would be updated", updated);
+    } else {
+        // Notes the providers still can't translate keep their flag
+        if by_tag && !fixed.is_empty() {
+            client.remove_tags(&fixed, &[tag.to_string()]).await?;
+        }
+        say!(console, "\n✅ Updated {} of {} notes", updated, notes.len());
+    }
+
+    console.emit_json(&json!({
+        "query": query,
+        "notes": notes.len(),
+        "skipped": skipped,
+        "updated": updated,
+        "dry_run": dry_run,
+        "fixes": fixes,
+    }))
+}
+
+/// Options of the diff command
+struct DiffArgs {
+    deck_name: String,
+    target_language: String,
+    base_languages: Vec<String>,
+    words_per_pos: usize,
+    translator: TranslatorKind,
+    libretranslate_url: Option<String>,
+}
+
+async fn handle_diff(
+    console: &Console,
+    config: &Config,
+    args: DiffArgs,
+    cancel: &CancellationToken,
+) -> Result<()> {
+    let DiffArgs {
+        deck_name,
+        target_language,
+        base_languages,
+        words_per_pos,
+        translator,
+        libretranslate_url,
+    } = args;
+    use crate::ankiweb::deck_query;
+    use crate::builder::{translation_chain, FrequencyList};
+    use crate::diff::DeckDiff;
+    use crate::fix::AmendableNote;
+    use crate::language::PostProcessor;
+    use crate::pipeline::translate_words;
+    use dialoguer::console::style;
+    use futures::TryStreamExt;
+
+    let target = config
+        .language_packs
+        .language(&target_language)
+        .ok_or_else(|| AnkiDeckBuilderError::UnsupportedLanguage(target_language.to_string()))?;
+    let mut base_codes = Vec::new();
+    for input in &base_languages {
+        let base = config
+            .language_packs
+            .language(input)
+            .ok_or_else(|| AnkiDeckBuilderError::UnsupportedLanguage(input.clone()))?;
+        base_codes.push(base.code);
+    }
+
+    let client = config.anki_client()?;
+    client.verify_connection().await?;
+    if !client.get_decks().await?.contains(&deck_name) {
+        return Err(
+            AnkiDeckBuilderError::MissingInput(format!("Deck not found: {}", deck_name)).into(),
+        );
+    }
+
+    let note_ids = client.find_notes(&deck_query(&deck_name, &[])).await?;
+    let infos = client.notes_info(&note_ids).await?;
+    let notes: Vec<AmendableNote> = infos
+        .iter()
+        .filter_map(|info| AmendableNote::from_info(info, &target.code))
+        .collect();
+    say!(
+        console,
+        "🔍 Comparing '{}' ({} notes) with the top {} {} words per part of speech",
+        deck_name,
+        notes.len(),
+        words_per_pos,
+        target.name
+    );
+
+    let services = config.services();
+    let cache_dir = config.cache_dir().clone();
+    let freq_data = FrequencyList::default()
+        .load(&target.code, &cache_dir, &services, cancel)
+        .await?;
+    let words = freq_data.select(Selection::Equal, words_per_pos);
+    let translator = translation_chain(
+        &cache_dir,
+        PostProcessor::load(&config.rules_file())?,
+        translator,
+        libretranslate_url,
+        &services,
+    )?;
+    let pinned = BTreeMap::new();
+    let generated: Vec<_> = translate_words(
+        &translator,
+        words,
+        &target.code,
+        &base_codes,
+        &pinned,
+        2,
+        cancel,
+    )
+    .try_collect()
+    .await?;
+    if cancel.is_cancelled() {
+        anyhow::bail!("Stopped before every word was translated");
+    }
+
+    let diff = DeckDiff::between(&generated, &notes);
+    for entry in &diff.added {
+        say!(
+            console,
+            "{}",
+            style(format!(
+                "+ {} ({}): {}",
+                entry.word, entry.pos, entry.translation
+            ))
+            .green()
+        );
+    }
+    for entry in &diff.removed {
+        say!(
+            console,
+            "{}",
+            style(format!(
+                "- {} ({}): {}",
+                entry.word, entry.pos, entry.translation
+            ))
+            .red()
+        );
+    }
+    for entry in &diff.changed {
+        say!(
+            console,
+            "{}",
+            style(format!(
+                "~ {}: {} → {}",
+                entry.word, entry.old_translation, entry.new_translation
+            ))
+            .yellow()
+        );
+    }
+    if diff.is_empty() {
+        say!(
+            console,
+            "\n✅ '{}' already matches the word list",
+            deck_name
+        );
+    } else {
+        say!(
+            console,
+            "\n📋 {} to add, {} not in the list, {} translated differently, {} unchanged",
+            diff.added.len(),
+            diff.removed.len(),
+            diff.changed.len(),
+            diff.unchanged
+        );
+    }
+
+    console.emit_json(&json!({
+        "deck_name": deck_name,
+        "target_language": target.code,
+        "base_languages": base_codes,
+        "added": diff.added,
+        "removed": diff.removed,
+        "changed": diff.changed,
+        "unchanged": diff.unchanged,
+    }))
+}
+
+async fn handle_prefetch(
+    console: &Console,
+    config: &Config,
+    args: PrefetchArgs,
+    cancel: &CancellationToken,
+) -> Result<()> {
+    use crate::builder::{translation_chain, FrequencyList};
+    use crate::language::PostProcessor;
+    use crate::prefetch::{uncached_words, PrefetchStats, Prefetcher};
+    use indicatif::{ProgressBar, ProgressStyle};
+
+    let services = config.services();
+    let cache_dir = config.cache_dir().clone();
+
+    let mut targets = Vec::new();
+    for input in &args.target_languages {
+        targets.push(
+            config
+                .language_packs
+                .language(input)
+                .ok_or_else(|| AnkiDeckBuilderError::UnsupportedLanguage(input.clone()))?,
+        );
+    }
+    let mut base_codes = Vec::new();
+    for input in &args.base_languages {
+        let base = config
+            .language_packs
+            .language(input)
+            .ok_or_else(|| AnkiDeckBuilderError::UnsupportedLanguage(input.clone()))?;
+        base_codes.push(base.code);
+    }
+
+    say!(
+        console,
+        "📥 Prefetching {} for {}",
+        args.frequency_source.name(),
+        targets
+            .iter()
+            .map(|lang| lang.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+
+    // The lists come from different places, so they are fetched together
+    let list = FrequencyList {
+        source: args.frequency_source,
+        list_size: args.list_size,
+        merge_weights: Vec::new(),
+    };
+    let lists = futures::future::try_join_all(
+        targets
+            .iter()
+            .map(|lang| list.load(&lang.code, &cache_dir, &services, cancel)),
+    )
+    .await?;
+    say!(console, "✅ Loaded {} frequency lists", lists.len());
+
+    let rules_file = config.rules_file();
+    let translator = translation_chain(
+        &cache_dir,
+        PostProcessor::load(&rules_file)?,
+        args.translator,
+        args.libretranslate_url,
+        &services,
+    )?;
+    let prefetcher = Prefetcher::new(&translator, args.delay, args.concurrency);
+
+    let mut languages = Vec::new();
+    let mut total = PrefetchStats::default();
+    for (lang, freq_data) in targets.iter().zip(lists) {
+        let words = freq_data.select(args.selection, args.words_per_pos);
+        let selected = words.len();
+        let words = uncached_words(&cache_dir, words, &lang.code, &base_codes);
+        let cached = selected - words.len();
+        say!(
+            console,
+            "\n🌐 {}: {} words, {} already cached, {} to translate",
+            lang.name,
+            selected,
+            cached,
+            words.len()
+        );
+        translator.prepare(&words, &lang.code, &base_codes).await;
+
+        let bar = ProgressBar::new(words.len() as u64);
+        bar.set_style(
+            console.console_safe(
+                ProgressStyle::default_bar()
+                    .template("{bar:40.cyan/blue} {pos}/{len} {msg}")
+                    .unwrap(),
+            ),
+        );
+        if console.is_json() {
+            bar.set_draw_target(indicatif::ProgressDrawTarget::hidden());
+        }
+        let stats = prefetcher
+            .run(words, &lang.code, &base_codes, cancel, |word| {
+                bar.set_message(word.to_string());
+                bar.inc(1);
+            })
+            .await;
+        bar.finish_and_clear();
+
+        if stats.failed > 0 {
+            say!(
+                console,
+                "  ⚠️  {} words could not be translated",
+                stats.failed
+            );
+        }
+        languages.push(json!({
+            "language": lang.code,
+            "words": selected,
+            "already_cached": cached,
+            "translated": stats.translated,
+            "flagged": stats.flagged,
+            "failed": stats.failed,
+        }));
+        total.translated += stats.translated;
+        total.flagged += stats.flagged;
+        total.failed += stats.failed;
+    }
+
+    if cancel.is_cancelled() {
+        say!(
+            console,
+            "\n⏹️  Stopped; translations fetched so far stay cached"
+        );
+    } else {
+        say!(
+            console,
+            "\n✅ Cached {} new translations ({} flagged, {} failed) in {}",
+            total.translated + total.flagged,
+            total.flagged,
+            total.failed,
+            cache_dir.display()
+        );
+    }
+    console.emit_json(&json!({
+        "languages": languages,
+        "base_languages": base_codes,
+        "translated": total.translated,
+        "flagged": total.flagged,
+        "failed": total.failed,
+        "cancelled": cancel.is_cancelled(),
+    }))?;
+    Ok(())
+}
+
+/// Progress bars draw nothing while the dashboard owns the terminal, and
+/// start with the console's prefix like its lines
+fn hidden_under_dashboard(
+    console: &Console,
+    bar: indicatif::ProgressBar,
+) -> indicatif::ProgressBar {
+    bar.set_prefix(console.line_prefix());
+    if console.has_dashboard() {
+        bar.set_draw_target(indicatif::ProgressDrawTarget::hidden());
+    }
+    bar
+}
+
+/// Translate a few of the deck's words and show the notes they would become,
+/// with the decks and note type, without contacting AnkiConnect. Words that
+/// can't be loaded fail the dry run as they would fail the real one
+async fn preview_notes(
+    console: &Console,
+    deck_builder: &mut crate::builder::DeckBuilder<'_>,
+    card_builder: &crate::ankiweb::CardBuilder,
+    count: usize,
+) -> Result<Vec<crate::builder::PreviewCard>> {
+    use crate::ankiweb::models::strip_html;
+
+    let words = deck_builder.load_words().await?;
+    say!(
+        console,
+        "\n📚 Decks: {}",
+        deck_builder.deck_names(card_builder).join(", ")
+    );
+    say!(console, "📋 Words: {}", words.len());
+    if count == 0 || words.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    say!(
+        console,
+        "\n🃏 {} of the {} words as they would be added:",
+        count.min(words.len()),
+        words.len()
+    );
+    let cards = deck_builder.preview(card_builder, &words, count).await?;
+    for card in &cards {
+        say!(
+            console,
+            "\n  {} → {} ({}, rank {})",
+            card.word,
+            card.translation,
+            card.pos.name(),
+            card.rank
+        );
+        if let Some(issue) = &card.issue {
+            say!(console, "  ⚠️  {}", issue);
+        }
+        for note in &card.notes {
+            say!(console, "    Deck: {}", note.deck_name);
+            say!(console, "    Note type: {}", note.model_name);
+            let mut fields: Vec<_> = note.fields.iter().collect();
+            // Front and Back first, then the note type's extra fields
+            fields.sort_by_key(|(name, _)| match name.as_str() {
+                "Front" => (0, ""),
+                "Back" => (1, ""),
+                other => (2, other),
+            });
+            for (name, value) in fields {
+                // Blocks of the rendered field become spaces
+                let text = strip_html(&value.replace('<', " <"))
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                say!(
+                    console,
+                    "    {}: {}",
+                    name,
+                    if text.is_empty() {
+                        "(filled during the build)"
+                    } else {
+                        &text
+                    }
+                );
+            }
+            say!(console, "    Tags: {}", note.tags.join(" "));
+        }
+    }
+    Ok(cards)
+}
+
+/// Question-side words of the notes in existing decks, so a new deck can
+/// leave them out
+async fn words_in_decks(config: &Config, decks: &[String]) -> Result<Vec<String>> {
+    let anki_client = config.anki_client()?;
+    let existing = anki_client.get_decks().await?;
+
+    let mut words = Vec::new();
+    for deck in decks {
+        if !existing.contains(deck) {
+            return Err(AnkiDeckBuilderError::ConfigurationError(format!(
+                "Deck to exclude words from not found: {}",
+                deck
+            ))
+            .into());
+        }
+        words.extend(anki_client.deck_fronts(deck).await?);
+    }
+    Ok(words)
+}
+
+/// A percentage above 0 and at most 100, with or without a `%` sign
+fn parse_percent(input: &str) -> std::result::Result<f64, String> {
+    let percent: f64 = input
+        .trim_end_matches('%')
+        .parse()
+        .map_err(|_| format!("invalid percentage '{}'", input))?;
+    if !(percent > 0.0 && percent <= 100.0) {
+        return Err(format!(
+            "percentage '{}' must be above 0 and at most 100",
+            input
+        ));
+    }
+    Ok(percent)
+}
+
+/// A similarity between 0 and 1
+fn parse_threshold(input: &str) -> std::result::Result<f64, String> {
+    let threshold: f64 = input
+        .parse()
+        .map_err(|_| format!("invalid threshold '{}'", input))?;
+    if !(0.0..=1.0).contains(&threshold) {
+        return Err(format!("threshold '{}' must be between 0 and 1", input));
+    }
+    Ok(threshold)
+}
+
+/// Show the cards a run added in Anki's browser, or the deck list when it
+/// added none. Anki stays as it was if this fails
+async fn open_in_anki(
+    console: &Console,
+    anki_client: &dyn crate::ankiweb::AnkiBackend,
+    deck: &str,
+    added: usize,
+) {
+    use crate::ankiweb::added_today_query;
+
+    let opened = if added > 0 {
+        anki_client
+            .gui_browse(&added_today_query(deck))
+            .await
+            .map(|cards| {
+                say!(
+                    console,
+                    "  🔎 Opened {} new cards in Anki's browser",
+                    cards.len()
+                )
+            })
+    } else {
+        anki_client.gui_deck_browser().await
+    };
+    if let Err(e) = opened {
+        say!(console, "  ⚠️  Could not open Anki's browser: {}", e);
+    }
+}
+
+/// Point at the failure report and how to retry it
+fn print_report_hint(console: &Console, path: Option<&Path>, failures: usize) {
+    if let Some(path) = path {
+        say!(
+            console,
+            "\n📝 {} failures written to {}",
+            failures,
+            path.display()
+        );
+        say!(
+            console,
+            "   Retry them with: anki-deck-builder create --from-report {}",
+            path.display()
+        );
+    }
+}
+
+/// Print what this run changed compared with the previous run for the deck
+fn print_changes(console: &Console, changes: &crate::manifest::ManifestDiff) {
+    if changes.is_empty() {
+        say!(console, "\n📋 No changes since the last run");
+        return;
+    }
+
+    say!(console, "\n📋 Changes since the last run:");
+    if !changes.added.is_empty() {
+        say!(
+            console,
+            "  ➕ {} new words: {}",
+            changes.added.len(),
+            summarize_words(&changes.added)
+        );
+    }
+    for (word, old, new) in &changes.updated {
+        say!(console, "  ✏️  {}: {} → {}", word, old, new);
+    }
+    if !changes.resolved.is_empty() {
+        say!(
+            console,
+            "  🔧 {} earlier failures now added: {}",
+            changes.resolved.len(),
+            summarize_words(&changes.resolved)
+        );
+    }
+    if !changes.failed.is_empty() {
+        say!(
+            console,
+            "  ⚠️  {} words failed: {}",
+            changes.failed.len(),
+            summarize_words(&changes.failed)
+        );
+    }
+}
+
+/// First few words of a list, with a count of the rest
+fn summarize_words(words: &[String]) -> String {
+    const SHOWN: usize = 8;
+
+    let mut summary = words
+        .iter()
+        .take(SHOWN)
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(", ");
+    if words.len() > SHOWN {
+        summary.push_str(&format!(" (+{} more)", words.len() - SHOWN));
+    }
+    summary
+}
+
+/// Find a picture for a word, store it in Anki's media folder, and return the
+/// Images for `--review`, where the user picks one of several candidates
+/// (or none) for each noun
+struct PickedImageEnricher {
+    images: crate::images::ImageClient,
+    progress: indicatif::ProgressBar,
+    console: Console,
+}
+
+#[async_trait::async_trait]
+impl crate::enrich::Enricher for PickedImageEnricher {
+    fn name(&self) -> &'static str {
+        "images"
+    }
+
+    fn applies_to(&self, word: &crate::language::Word) -> bool {
+        word.pos == crate::language::PartOfSpeech::Noun
+    }
+
+    async fn enrich(
+        &self,
+        input: crate::enrich::EnrichInput<'_>,
+        sink: &dyn DeckSink,
+    ) -> crate::error::Result<crate::enrich::Fields> {
+        use crate::ankiweb::models::IMAGE_FIELD;
+        use dialoguer::Select;
+
+        let word = &input.word.text;
+        let candidates = self.images.search(input.translation, 5).await?;
+        if candidates.is_empty() {
+            return Ok(Default::default());
+        }
+
+        let mut items: Vec<String> = candidates
+            .iter()
+            .map(|image| {
+                format!(
+                    "{} — {}",
+                    image.attribution(),
+                    image.foreign_landing_url.as_deref().unwrap_or(&image.url)
+                )
+            })
+            .collect();
+        items.push(self.console.tr("No image"));
+
+        let choice = self
+            .progress
+            .suspend(|| {
+                Select::with_theme(&*self.console.theme())
+                    .with_prompt(
+                        self.console
+                            .tr(&format!("Image for '{}' ({})", word, input.translation)),
+                    )
+                    .items(&items)
+                    .default(0)
+                    .interact()
+            })
+            .map_err(|e| AnkiDeckBuilderError::ImageSearchError(e.to_string()))?;
+        let Some(image) = candidates.get(choice) else {
+            return Ok(Default::default());
+        };
+
+        let data = self.images.download(image).await?;
+        let filename = sink
+            .store_media_file(&format!("{}.jpg", word), &data)
+            .await?;
+        Ok([(IMAGE_FIELD.to_string(), image.field_html(&filename))].into())
+    }
+}
+
+/// The review saved for a language pair, when the user resumes it, or a
+/// new one. A review saved for a deck other than the one named with
+/// `--deck-name` isn't offered
+fn resume_review(
+    console: &Console,
+    path: &std::path::Path,
+    deck_name: Option<&str>,
+) -> Result<crate::session::ReviewSession> {
+    use crate::session::ReviewSession;
+    use dialoguer::Confirm;
+
+    let Some(saved) = ReviewSession::load(path)
+        .filter(ReviewSession::has_progress)
+        .filter(|saved| {
+            saved.deck_name.is_empty() || deck_name.is_none_or(|name| name == saved.deck_name)
+        })
+    else {
+        return Ok(ReviewSession::default());
+    };
+    // The dashboard can't show a prompt this early, so it always resumes
+    if console.has_dashboard() {
+        say!(
+            console,
+            "🔁 Resuming previous review of '{}' ({} words reviewed)",
+            saved.deck_name,
+            saved.decisions.len()
+        );
+        return Ok(saved);
+    }
+    let resume = Confirm::with_theme(&*console.theme())
+        .with_prompt(console.tr(&format!(
+            "Resume previous review of '{}' ({} words reviewed)?",
+            saved.deck_name,
+            saved.decisions.len()
+        )))
+        .default(true)
+        .interact()?;
+    Ok(match resume {
+        true => saved,
+        false => ReviewSession::default(),
+    })
+}
+
+/// Parts of speech to review words of: those a resumed review chose, or
+/// asked for and saved right away so an interrupted review comes back to them
+fn choose_parts_of_speech(
+    console: &Console,
+    session: &mut crate::session::ReviewSession,
+    path: &std::path::Path,
+) -> Result<Vec<crate::language::PartOfSpeech>> {
+    use crate::language::PartOfSpeech;
+    use dialoguer::MultiSelect;
+
+    if let Some(chosen) = &session.parts_of_speech {
+        let names: Vec<_> = chosen.iter().map(PartOfSpeech::name).collect();
+        say!(
+            console,
+            "🔁 Parts of speech from the previous review: {}",
+            names.join(", ")
+        );
+        return Ok(chosen.clone());
+    }
+
+    let all = PartOfSpeech::all();
+    let names: Vec<_> = all.iter().map(PartOfSpeech::name).collect();
+    let chosen = loop {
+        let picked = MultiSelect::with_theme(&*console.theme())
+            .with_prompt(console.tr("Parts of speech to review (space toggles, enter confirms)"))
+            .items(&names)
+            .defaults(&vec![true; all.len()])
+            .interact()?;
+        if !picked.is_empty() {
+            break picked
+                .into_iter()
+                .map(|i| all[i].clone())
+                .collect::<Vec<_>>();
+        }
+        say!(console, "Pick at least one part of speech");
+    };
+    session.parts_of_speech = Some(chosen.clone());
+    session.save(path)?;
+    Ok(chosen)
+}
+
+/// What `create` adds to a deck build: it asks what to do with duplicates
+/// and whether to start a costly run, lets the user review translations,
+/// and keeps the failure report, the run manifest and the IDs of the notes
+/// added
+struct CreateHooks<'a> {
+    config: &'a Config,
+    console: &'a Console,
+    deck_name: String,
+    output_backend: OutputBackend,
+    preset: Option<SchedulingPreset>,
+    interactive: bool,
+    /// Start a run whatever its estimate
+    yes: bool,
+    duplicates: Option<DuplicatePolicy>,
+    duplicate_deck: String,
+    /// Words checked with Anki before translating
+    words_checked: usize,
+    target: crate::language::Language,
+    base_names: String,
+    base_codes: Vec<String>,
+    translator_kind: TranslatorKind,
+    concurrency: usize,
+    bidirectional: bool,
+    back_translate: bool,
+    card_builder: crate::ankiweb::CardBuilder,
+    /// The enrichers, to count their lookups in the estimate
+    estimate_pipeline: crate::enrich::EnrichmentPipeline,
+    /// Review to start once the words are known, and where it is saved
+    session: Option<(crate::session::ReviewSession, PathBuf)>,
+    reviewer: Option<Reviewer>,
+    progress: indicatif::ProgressBar,
+    failures: crate::report::FailureReport,
+    manifest: RunManifest,
+    /// Words already in Anki, skipped before translating
+    known_count: usize,
+    translators: Vec<String>,
+    estimate: Option<crate::estimate::RunEstimate>,
+    /// Words no translator gave a trustworthy answer for, with the reason
+    flagged: BTreeMap<String, String>,
+    /// Notes that went into Anki, so `undo` can take them out again
+    note_ids: Vec<i64>,
+    /// How the build ended, and the file it wrote
+    finished: Option<(crate::events::BuildSummary, Option<PathBuf>)>,
+}
+
+impl crate::builder::BuildHooks for CreateHooks<'_> {
+    fn deck_prepared(&mut self, deck_id: Option<i64>) {
+        // An existing deck is simply added to
+        match deck_id {
+            Some(deck_id) => say!(self.console, "✅ Created deck with ID: {}", deck_id),
+            None if self.output_backend.uses_anki() => {
+                say!(self.console, "ℹ️  Using existing deck '{}'", self.deck_name)
+            }
+            None => {}
+        }
+        if let Some(preset) = self.preset.filter(|_| self.output_backend.uses_anki()) {
+            say!(
+                self.console,
+                "⚙️  Applied the {} preset: {} new cards/day, {} reviews/day",
+                preset.name(),
+                preset.new_per_day(),
+                preset.reviews_per_day()
+            );
+        }
+    }
+
+    fn duplicate_policy(
+        &mut self,
+        duplicates: &[String],
+        refused: &[String],
+    ) -> crate::error::Result<DuplicatePolicy> {
+        use dialoguer::Select;
+
+        say!(
+            self.console,
+            "\n🔎 Checked {} notes with Anki before translating:",
+            self.words_checked
+        );
+        if !refused.is_empty() {
+            say!(
+                self.console,
+                "  ⚠️  {} words Anki would refuse, left out: {}",
+                refused.len(),
+                summarize_words(refused)
+            );
+        }
+
+        let policy = match self.duplicates {
+            _ if duplicates.is_empty() => return Ok(DuplicatePolicy::Skip),
+            Some(policy) => policy,
+            None if self.interactive => {
+                say!(
+                    self.console,
+                    "  ⏭️  {} words already have notes: {}",
+                    duplicates.len(),
+                    summarize_words(duplicates)
+                );
+                let choice = Select::with_theme(&*self.console.theme())
+                    .with_prompt(self.console.tr("What should happen to them?"))
+                    .items(&[
+                        self.console.tr("Skip them"),
+                        self.console.tr("Add them again anyway"),
+                        self.console
+                            .tr(&format!("Add them to '{}'", self.duplicate_deck)),
+                    ])
+                    .default(0)
+                    .interact()?;
+                [
+                    DuplicatePolicy::Skip,
+                    DuplicatePolicy::Allow,
+                    DuplicatePolicy::Deck,
+                ][choice]
+            }
+            None => DuplicatePolicy::Skip,
+        };
+        match policy {
+            DuplicatePolicy::Skip => {
+                say!(
+                    self.console,
+                    "  ⏭️  {} words already have notes and are skipped",
+                    duplicates.len()
+                );
+                self.known_count = duplicates.len();
+            }
+            DuplicatePolicy::Allow => say!(
+                self.console,
+                "  ➕ {} words already have notes and are added again",
+                duplicates.len()
+            ),
+            DuplicatePolicy::Deck => say!(
+                self.console,
+                "  📂 {} words already have notes and go to '{}'",
+                duplicates.len(),
+                self.duplicate_deck
+            ),
+        }
+        Ok(policy)
+    }
+
+    fn confirm(
+        &mut self,
+        words: &[crate::language::Word],
+        to_translate: &[crate::language::Word],
+        translator: &crate::language::TranslationChain,
+    ) -> crate::error::Result<bool> {
+        use crate::estimate::{describe_duration, RunPlan};
+        use dialoguer::Confirm;
+
+        let total_notes = words.len() * self.card_builder.notes_per_word();
+        say!(
+            self.console,
+            "\n🌐 Translating {} words from {} to {} and adding {} notes ({} cards){}",
+            words.len(),
+            self.target.name,
+            self.base_names,
+            total_notes,
+            total_notes * self.card_builder.cards_per_note(),
+            if self.bidirectional {
+                " (bidirectional)"
+            } else {
+                ""
+            }
+        );
+        self.translators = translator
+            .provider_names()
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+        say!(
+            self.console,
+            "  Translators: {}{}",
+            self.translators.join(" → "),
+            if self.back_translate {
+                " (with back-translation check)"
+            } else {
+                ""
+            }
+        );
+
+        // What the run asks of the translators and lookup services, so a
+        // long or paid run can be called off before it starts
+        let config = self.config;
+        let estimate = RunPlan {
+            cache_dir: config.cache_dir(),
+            words: to_translate,
+            from: &self.target.code,
+            to: &self.base_codes,
+            translator: self.translator_kind,
+            concurrency: self.concurrency,
+            pipeline: &self.estimate_pipeline,
+            llm: &config.llm,
+        }
+        .estimate();
+        say!(self.console, "\n📋 Estimate:");
+        say!(
+            self.console,
+            "  Requests: ~{} ({} translations, {} lookups)",
+            estimate.requests(),
+            estimate.translation_requests,
+            estimate.lookup_requests
+        );
+        say!(
+            self.console,
+            "  Cache: {:.0}% of {} translations already cached",
+            estimate.cache_hit_ratio() * 100.0,
+            estimate.translations
+        );
+        say!(
+            self.console,
+            "  Time: {}{}",
+            describe_duration(estimate.seconds),
+            match self.translator_kind {
+                TranslatorKind::Google => " (Google Translate is asked once a second)",
+                _ => "",
+            }
+        );
+        if let Some(dollars) = estimate.dollars {
+            say!(
+                self.console,
+                "  Cost: ~${:.2} ({})",
+                dollars,
+                config.llm.model
+            );
+        }
+        let exceeded = estimate.exceeded(&config.confirm_above);
+        self.estimate = Some(estimate);
+        if !exceeded.is_empty() && !self.yes {
+            if !self.interactive {
+                return Err(AnkiDeckBuilderError::MissingInput(format!(
+                    "--yes to start a run estimated at {}",
+                    exceeded.join(", ")
+                )));
+            }
+            let confirmed = Confirm::with_theme(&*self.console.theme())
+                .with_prompt(self.console.tr(&format!(
+                    "This run is estimated at {}. Start it?",
+                    exceeded.join(", ")
+                )))
+                .default(false)
+                .interact()?;
+            if !confirmed {
+                return Ok(false);
+            }
+        }
+
+        self.reviewer = self.session.take().map(|(session, path)| {
+            Reviewer::start(session, &path, &self.deck_name, words.len(), self.console)
+        });
+        self.progress.set_length(words.len() as u64);
+        self.progress.set_message(self.console.tr("Translating"));
+        Ok(true)
+    }
+
+    fn review(
+        &mut self,
+        position: usize,
+        translated: &crate::pipeline::TranslatedWord,
+    ) -> crate::error::Result<Option<String>> {
+        let word = &translated.word;
+        if let Some(issue) = &translated.issue {
+            self.flagged.insert(word.text.clone(), issue.clone());
+        }
+        match (self.reviewer.as_mut(), &translated.issue) {
+            (Some(reviewer), _) => self
+                .progress
+                .suspend(|| reviewer.review(position, translated)),
+            // Rather than add cards that are probably wrong, leave them for a review run
+            (None, Some(issue)) => {
+                self.failures.record(
+                    word,
+                    Some(&translated.translation),
+                    crate::report::FailurePhase::Validation,
+                    issue,
+                );
+                Ok(None)
+            }
+            (None, None) => Ok(Some(translated.translation.clone())),
+        }
+    }
+
+    fn note_added(&mut self, _word: &crate::language::Word, note_id: Option<i64>) {
+        self.note_ids.extend(note_id);
+    }
+
+    fn failed(
+        &mut self,
+        word: &crate::language::Word,
+        translation: Option<&str>,
+        phase: crate::report::FailurePhase,
+        reason: &str,
+    ) {
+        self.failures.record(word, translation, phase, reason);
+    }
+
+    fn word_done(
+        &mut self,
+        word: &crate::language::Word,
+        translation: &str,
+        status: crate::manifest::WordStatus,
+    ) {
+        use crate::language::truncate_graphemes;
+
+        self.progress.set_message(
+            self.console
+                .plain(&format!(
+                    "{} → {}",
+                    word.text,
+                    truncate_graphemes(translation, SAMPLE_WIDTH)
+                ))
+                .into_owned(),
+        );
+        self.manifest.record(word, translation, status);
+    }
+
+    fn finished(&mut self, summary: &crate::events::BuildSummary, output: Option<&Path>) {
+        self.finished = Some((summary.clone(), output.map(Path::to_path_buf)));
+    }
+}
+
+/// Interactive review of each translation as it arrives, letting the user
+/// keep, edit, or skip it. Every decision is saved so an interrupted review
+/// can be resumed
+struct Reviewer {
+    session: crate::session::ReviewSession,
+    path: PathBuf,
+    console: Console,
+    total: usize,
+    /// Set once the user chooses to keep everything still to come
+    keep_rest: bool,
+}
+
+impl Reviewer {
+    /// Start reviewing the words of a deck, `session` holding what an
+    /// earlier review decided
+    fn start(
+        mut session: crate::session::ReviewSession,
+        path: &std::path::Path,
+        deck_name: &str,
+        total: usize,
+        console: &Console,
+    ) -> Self {
+        session.deck_name = deck_name.to_string();
+        say!(
+            console,
+            "\n🔎 Reviewing translations (progress is saved after each word)"
+        );
+
+        Self {
+            session,
+            path: path.to_path_buf(),
+            console: console.clone(),
+            total,
+            keep_rest: false,
+        }
+    }
+
+    /// Translation to use for the word at `position` (1-based), or None to skip it
+    fn review(
+        &mut self,
+        position: usize,
+        translated: &crate::pipeline::TranslatedWord,
+    ) -> crate::error::Result<Option<String>> {
+        use crate::session::ReviewDecision;
+        use dialoguer::{Input, Select};
+
+        let (word, pos) = (&translated.word.text, &translated.word.pos);
+        if self.keep_rest || self.session.is_reviewed(word, pos) {
+            return Ok(self.session.apply(word, pos, &translated.translation));
+        }
+
+        if self.console.has_dashboard() {
+            return self.review_in_dashboard(position, translated);
+        }
+
+        let theme = self.console.theme();
+        let options = [
+            "Keep",
+            "Edit translation",
+            "Skip word",
+            "Keep all remaining",
+        ]
+        .map(|option| self.console.tr(option));
+        let warning = translated
+            .issue
+            .as_ref()
+            .map(|issue| format!(" ⚠️  {}", issue))
+            .unwrap_or_default();
+
+        let choice = Select::with_theme(&*theme)
+            .with_prompt(self.console.plain(&format!(
+                "[{}/{}] {} → {} ({:?}){}",
+                position, self.total, word, translated.translation, translated.word.pos, warning
+            )))
+            .items(&options)
+            .default(0)
+            .interact()?;
+
+        let decision = match choice {
+            0 => ReviewDecision::Keep,
+            1 => {
+                let edited: String = Input::with_theme(&*theme)
+                    .with_prompt(self.console.tr(&format!("Translation for '{}'", word)))
+                    .with_initial_text(translated.translation.clone())
+                    .interact_text()?;
+                ReviewDecision::Edit(edited)
+            }
+            2 => ReviewDecision::Skip,
+            _ => {
+                self.keep_rest = true;
+                return Ok(Some(translated.translation.clone()));
+            }
+        };
+
+        self.session.record(word, pos, decision);
+        self.session.save(&self.path)?;
+        Ok(self.session.apply(word, pos, &translated.translation))
+    }
+
+    /// Ask the dashboard for the decision. None when it stopped waiting, as
+    /// when the run is cancelled mid-review; nothing is recorded then
+    fn review_in_dashboard(
+        &mut self,
+        position: usize,
+        translated: &crate::pipeline::TranslatedWord,
+    ) -> crate::error::Result<Option<String>> {
+        use crate::events::ReviewReply;
+
+        let (word, pos) = (&translated.word.text, &translated.word.pos);
+        let reply = self.console.request_review(
+            position,
+            self.total,
+            word,
+            &translated.word.pos,
+            &translated.translation,
+            translated.issue.as_deref(),
+        );
+        match reply {
+            Some(ReviewReply::Decide(decision)) => {
+                self.session.record(word, pos, decision);
+                self.session.save(&self.path)?;
+                Ok(self.session.apply(word, pos, &translated.translation))
+            }
+            Some(ReviewReply::KeepRest) => {
+                self.keep_rest = true;
+                Ok(Some(translated.translation.clone()))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Remove the saved session once every word has been handled
+    fn finish(&self) -> Result<()> {
+        crate::session::ReviewSession::clear(&self.path)?;
+        Ok(())
+    }
+}
+
+async fn handle_external(
+    console: &Console,
+    config: &Config,
+    args: Vec<OsString>,
+    json: bool,
+    interactive: bool,
+) -> Result<()> {
+    use crate::external::{list_external_commands, run_external_command, ExternalContext};
+
+    let Some((name, rest)) = args.split_first() else {
+        return Ok(());
+    };
+    let name = name.to_string_lossy();
+
+    let context = ExternalContext { json, interactive };
+    let result = run_external_command(&name, rest, config, context).await;
+
+    if let Err(AnkiDeckBuilderError::UnknownCommand(_)) = &result {
+        let available = list_external_commands();
+        if !available.is_empty() {
+            say_err!(
+                console,
+                "External commands on PATH: {}",
+                available.join(", ")
+            );
+        }
+        say_err!(
+            console,
+            "Run 'anki-deck-builder --help' for the built-in commands"
+        );
+    }
+    Ok(result?)
+}
+
+async fn handle_config(
+    console: &Console,
+    config: &Config,
+    ankiconnect_url: Option<String>,
+    show: bool,
+) -> Result<()> {
+    if show {
+        say!(console, "Current configuration:");
+        say!(console, "  AnkiConnect URL: {}", config.ankiconnect_url);
+        say!(
+            console,
+            "  Translation Service: MyMemory (no API key required)"
+        );
+        if config.forvo_api_key.is_some() {
+            say!(console, "  Forvo API key: set");
+        }
+        if config.llm.key().is_some() {
+            say!(
+                console,
+                "  LLM: {} at {}",
+                config.llm.model,
+                config.llm.api_url
+            );
+        }
+        say!(
+            console,
+            "  Config directory: {}",
+            config.config_dir.display()
+        );
+        say!(console, "  Data directory: {}", config.data_dir.display());
+        say!(console, "  Cache directory: {}", config.cache_dir.display());
+        if let Some(mapping) = &config.note_model {
+            say!(console, "  Note type: {}", mapping.model);
+        }
+        if !config.profiles.is_empty() {
+            let names: Vec<&str> = config.profiles.keys().map(String::as_str).collect();
+            say!(console, "  Profiles: {}", names.join(", "));
+        }
+        let http = &config.http;
+        if let Some(proxy) = &http.proxy {
+            say!(console, "  Proxy: {}", crate::logging::redact_url(proxy));
+        }
+        if let Some(ca_cert) = &http.ca_cert {
+            say!(console, "  CA certificate: {}", ca_cert.display());
+        }
+        if http.insecure {
+            say!(console, "  ⚠️  Certificate checks: off");
+        }
+        say!(
+            console,
+            "  AnkiConnect timeouts: {}s ({}s for long actions), {} retries while Anki is busy",
+            config.ankiconnect.timeout_secs,
+            config.ankiconnect.long_timeout_secs,
+            config.ankiconnect.retries
+        );
+        say!(console, "  Config file: {}", config.config_file().display());
+        return console.emit_json(&json!({
+            "ankiconnect_url": config.ankiconnect_url,
+            "translation_service": "MyMemory",
+            "config_dir": config.config_dir,
+            "data_dir": config.data_dir,
+            "cache_dir": config.cache_dir,
+            "note_model": config.note_model,
+            "profiles": config.profiles,
+            "http": {
+                "proxy": http.proxy.as_deref().map(crate::logging::redact_url),
+                "no_proxy": http.no_proxy,
+                "ca_cert": http.ca_cert,
+                "insecure": http.insecure,
+            },
+            "ankiconnect": config.ankiconnect,
+        }));
+    }
+
+    if let Some(url) = ankiconnect_url {
+        say!(console, "Setting AnkiConnect URL to: {}", url);
+        say!(
+            console,
+            "Note: Use environment variable ANKICONNECT_URL={}",
+            url
+        );
+    }
+
+    Ok(())
+}
+
+/// Offer to keep legacy environment settings in a new config file. Either
+/// way the file is created, so this is asked only once
+fn offer_env_import(console: &Console, config: &Config) -> Result<()> {
+    use dialoguer::Confirm;
+
+    let settings = config.env_settings_to_import();
+    if settings.is_empty() {
+        return Ok(());
+    }
+    say!(console, "Settings found in the environment:");
+    for (variable, _, value) in &settings {
+        if variable.ends_with("_KEY") {
+            say!(console, "  {} (set)", variable);
+        } else {
+            say!(
+                console,
+                "  {}={}",
+                variable,
+                crate::logging::redact_url(value)
+            );
+        }
+    }
+    let import = Confirm::with_theme(&*console.theme())
+        .with_prompt(console.tr("Save them to config.json?"))
+        .default(true)
+        .interact()?;
+    let path = config.create_config_file(if import { &settings } else { &[] })?;
+    if import {
+        say!(console, "✓ Saved to {}", path.display());
+    } else {
+        say!(
+            console,
+            "Left them in the environment; created an empty {}",
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+/// Check the config file of `config`, which may hold only the directories
+/// when the file doesn't load
+fn handle_config_validate(console: &Console, config: &Config) -> Result<()> {
+    let path = config.config_file();
+    if !path.exists() {
+        say!(
+            console,
+            "No config file at {}; defaults are in use",
+            path.display()
+        );
+        return console.emit_json(&json!({ "file": path, "valid": true, "issues": [] }));
+    }
+    let packs = crate::language::LanguagePacks::load(&config.language_pack_dir());
+    let issues = crate::config_schema::validate_config_file(&path, &packs)?;
+    console.emit_json(&json!({
+        "file": path,
+        "valid": issues.is_empty(),
+        "issues": issues
+            .iter()
+            .map(|issue| json!({ "path": issue.path, "message": issue.message }))
+            .collect::<Vec<_>>(),
+    }))?;
+    if issues.is_empty() {
+        say!(console, "✓ {} is valid", path.display());
+        return Ok(());
+    }
+    say!(console, "{}:", path.display());
+    for issue in &issues {
+        say!(console, "  ✗ {}", issue);
+    }
+    Err(AnkiDeckBuilderError::ConfigurationError(format!(
+        "{} problem{} in {}",
+        issues.len(),
+        if issues.len() == 1 { "" } else { "s" },
+        path.display()
+    ))
+    .into())
+}
+
+fn handle_glossary(console: &Console, config: &Config, action: GlossaryAction) -> Result<()> {
+    use crate::language::{GlossaryEntry, GlossaryFile, PartOfSpeech};
+
+    let (target_language, base_language) = match &action {
+        GlossaryAction::Add {
+            target_language,
+            base_language,
+            ..
+        }
+        | GlossaryAction::Remove {
+            target_language,
+            base_language,
+            ..
+        }
+        | GlossaryAction::List {
+            target_language,
+            base_language,
+        } => (target_language, base_language),
+    };
+    let mut codes = Vec::new();
+    for input in [target_language, base_language] {
+        let language = config
+            .language_packs
+            .language(input)
+            .ok_or_else(|| AnkiDeckBuilderError::UnsupportedLanguage(input.clone()))?;
+        codes.push(language.code);
+    }
+    let (from, to) = (&codes[0], &codes[1]);
+
+    let mut file = GlossaryFile::open(&config.glossary_dir(), from, to)?;
+
+    match action {
+        GlossaryAction::Add {
+            word,
+            translation,
+            pos,
+            ..
+        } => {
+            let pos = match pos {
+                Some(name) => Some(PartOfSpeech::from_name(&name.to_lowercase()).ok_or_else(
+                    || {
+                        AnkiDeckBuilderError::ConfigurationError(format!(
+                            "unknown part of speech '{}'",
+                            name
+                        ))
+                    },
+                )?),
+                None => None,
+            };
+            let entry = GlossaryEntry::new(&word, &translation).with_pos(pos);
+            let replaced = file.add(entry.clone());
+            file.save()?;
+            say!(
+                console,
+                "📖 {} {} → {} in {}",
+                if replaced { "Replaced" } else { "Added" },
+                entry.word,
+                entry.translation,
+                file.path.display()
+            );
+            console.emit_json(&json!({
+                "word": entry.word,
+                "translation": entry.translation,
+                "pos": entry.pos.as_ref().map(|pos| pos.name()),
+                "replaced": replaced,
+                "file": file.path,
+            }))
+        }
+        GlossaryAction::Remove { word, .. } => {
+            let removed = file.remove(&word);
+            if removed > 0 {
+                file.save()?;
+                say!(console, "🗑️  Removed {} from {}", word, file.path.display());
+            } else {
+                say!(
+                    console,
+                    "ℹ️  {} is not in the {}-{} glossary",
+                    word,
+                    from,
+                    to
+                );
+            }
+            console.emit_json(&json!({ "word": word, "removed": removed, "file": file.path }))
+        }
+        GlossaryAction::List { .. } => {
+            if file.entries.is_empty() {
+                say!(console, "ℹ️  No {}-{} glossary entries yet", from, to);
+            } else {
+                say!(
+                    console,
+                    "📖 {} ({} entries):",
+                    file.path.display(),
+                    file.entries.len()
+                );
+            }
+            for entry in &file.entries {
+                match &entry.pos {
+                    Some(pos) => say!(
+                        console,
+                        "  {} ({}) → {}",
+                        entry.word,
+                        pos.name(),
+                        entry.translation
+                    ),
+                    None => say!(console, "  {} → {}", entry.word, entry.translation),
+                }
+            }
+            let entries: Vec<_> = file
+                .entries
+                .iter()
+                .map(|entry| {
+                    json!({
+                        "word": entry.word,
+                        "translation": entry.translation,
+                        "pos": entry.pos.as_ref().map(|pos| pos.name()),
+                    })
+                })
+                .collect();
+            console.emit_json(&json!({ "file": file.path, "entries": entries }))
+        }
+    }
+}
+
+/// List the decks whose manifest has the word, then with `--anki` the
+/// generated notes showing it
+fn handle_history(
+    console: &Console,
+    config: &Config,
+    limit: usize,
+    action: Option<HistoryAction>,
+) -> Result<()> {
+    use crate::history::{RunHistory, RunRecord};
+    use crate::naming::format_timestamp;
+
+    let history = RunHistory::load(&config.history_file());
+    let languages = |run: &RunRecord| {
+        if run.base_languages.is_empty() {
+            run.target_language.clone()
+        } else {
+            format!(
+                "{} → {}",
+                run.target_language,
+                run.base_languages.join(", ")
+            )
+        }
+    };
+
+    let Some(HistoryAction::Show { id }) = action else {
+        if history.runs.is_empty() {
+            say!(
+                console,
+                "No runs recorded yet; they are added when create finishes"
+            );
+        }
+        let runs: Vec<&RunRecord> = history.runs.iter().rev().take(limit).collect();
+        for run in &runs {
+            say!(
+                console,
+                "{:>4}  {}  {:<24} {:<10} {} notes added, {} failed ({}, {}s)",
+                run.id,
+                format_timestamp(run.finished_at),
+                run.summary.deck_name,
+                languages(run),
+                run.summary.notes_added,
+                run.summary.notes_failed,
+                run.outcome(),
+                run.duration_secs
+            );
+        }
+        if history.runs.len() > runs.len() {
+            say!(
+                console,
+                "… {} older runs (use --limit to see more)",
+                history.runs.len() - runs.len()
+            );
+        }
+        return console.emit_json(&json!({ "runs": runs }));
+    };
+
+    let run = history.get(id).ok_or_else(|| {
+        AnkiDeckBuilderError::ConfigurationError(format!(
+            "no run {} in the history (see `history`)",
+            id
+        ))
+    })?;
+    let summary = &run.summary;
+    say!(console, "Run {}: {}", run.id, summary.deck_name);
+    say!(
+        console,
+        "  Finished: {} UTC",
+        format_timestamp(run.finished_at)
+    );
+    say!(console, "  Took: {}s", run.duration_secs);
+    say!(console, "  Outcome: {}", run.outcome());
+    say!(console, "  Languages: {}", languages(run));
+    say!(
+        console,
+        "  Words: {} of {}",
+        summary.words,
+        summary.total_words
+    );
+    say!(
+        console,
+        "  Notes added: {} ({} cards)",
+        summary.notes_added,
+        summary.cards_added
+    );
+    say!(
+        console,
+        "  Duplicates skipped: {}",
+        summary.duplicates_skipped
+    );
+    say!(console, "  Cognates skipped: {}", summary.cognates_skipped);
+    say!(console, "  Held back for review: {}", summary.flagged);
+    say!(console, "  Notes failed: {}", summary.notes_failed);
+    for failure in &run.failures {
+        say!(console, "    - {} ({})", failure.word, failure.error);
+    }
+    if let Some(report) = &run.report {
+        say!(console, "  Failure report: {}", report.display());
+    }
+    console.emit_json(run)
+}
+
+async fn handle_inspect_frequency(
+    console: &Console,
+    config: &Config,
+    input: &str,
+    frequency_source: FrequencySourceKind,
+    list_size: ListSize,
+    examples: usize,
+    cancel: &CancellationToken,
+) -> Result<()> {
+    use crate::builder::FrequencyList;
+    use crate::language::FrequencyReport;
+
+    let lang = config
+        .language_packs
+        .language(input)
+        .ok_or_else(|| AnkiDeckBuilderError::UnsupportedLanguage(input.to_string()))?;
+    let list = FrequencyList {
+        source: frequency_source,
+        list_size,
+        merge_weights: Vec::new(),
+    };
+    let freq_data = list
+        .load(&lang.code, config.cache_dir(), &config.services(), cancel)
+        .await?;
+    let report = FrequencyReport::new(&freq_data);
+
+    say!(
+        console,
+        "📊 {} list for {}: {} words",
+        frequency_source.name(),
+        lang.name,
+        report.words
+    );
+    for share in &report.parts_of_speech {
+        say!(
+            console,
+            "    {:<13} {:>6}  {:>5.1}%",
+            share.pos.name(),
+            share.words,
+            share.share * 100.0
+        );
+    }
+
+    match &report.coverage {
+        Some(coverage) => {
+            say!(
+                console,
+                "\n📈 {} words counted, {} listed words seen once",
+                coverage.corpus_words,
+                coverage.hapaxes
+            );
+            for step in &coverage.steps {
+                say!(
+                    console,
+                    "    Top {} words cover {:.1}% of the corpus",
+                    step.words,
+                    step.share * 100.0
+                );
+            }
+        }
+        None => say!(
+            console,
+            "\n📈 The list only ranks its words, so coverage is unknown"
+        ),
+    }
+
+    let script = report
+        .script
+        .map(|script| format!("{:?}", script))
+        .unwrap_or_else(|| "none".to_string());
+    for (label, words) in [
+        ("Numbers", &report.numbers),
+        ("Single letters", &report.single_letters),
+        (
+            &*format!("Not in {} script", script),
+            &report.foreign_script,
+        ),
+    ] {
+        if !words.is_empty() {
+            say!(
+                console,
+                "\n⚠️  {}: {} ({})",
+                label,
+                words.len(),
+                words
+                    .iter()
+                    .take(examples)
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+    }
+    if !report.duplicates.is_empty() {
+        say!(
+            console,
+            "\n⚠️  Listed more than once: {}",
+            report.duplicates.len()
+        );
+        for duplicate in report.duplicates.iter().take(examples) {
+            let entries: Vec<String> = duplicate
+                .parts_of_speech
+                .iter()
+                .zip(&duplicate.ranks)
+                .map(|(pos, rank)| format!("{} #{}", pos.name(), rank))
+                .collect();
+            say!(console, "    {} ({})", duplicate.text, entries.join(", "));
+        }
+    }
+    if report.suspicious() == 0 && report.duplicates.is_empty() {
+        say!(console, "\n✅ No suspicious words or duplicates");
+    }
+    console.emit_json(&report)
+}
+
+fn handle_changelog(console: &Console, config: &Config, deck_name: &str) -> Result<()> {
+    use crate::changelog::DeckChangelog;
+
+    let changelog = DeckChangelog::load(&DeckChangelog::path(&config.changelog_dir(), deck_name));
+    if changelog.entries.is_empty() {
+        say!(
+            console,
+            "No runs of '{}' recorded yet; they are added when create finishes",
+            deck_name
+        );
+    }
+    for entry in changelog.entries.iter().rev() {
+        let run = entry
+            .run_id
+            .map(|id| format!(", run {}", id))
+            .unwrap_or_default();
+        say!(
+            console,
+            "{}  {}  {} notes added ({}{})",
+            entry.tag,
+            entry.date,
+            entry.notes_added,
+            entry.outcome.label(),
+            run
+        );
+        for (label, words) in [
+            ("New", &entry.added),
+            ("Retranslated", &entry.updated),
+            ("Fixed", &entry.resolved),
+            ("Failed", &entry.failed),
+        ] {
+            if !words.is_empty() {
+                say!(console, "    {}: {}", label, words.join(", "));
+            }
+        }
+    }
+    console.emit_json(&changelog)
+}
+
+/// Delete exactly the notes a run recorded adding, after showing them
+async fn handle_undo(
+    console: &Console,
+    config: &Config,
+    run_id: Option<u64>,
+    dry_run: bool,
+    yes: bool,
+    interactive: bool,
+) -> Result<()> {
+    use crate::history::RunHistory;
+    use dialoguer::Confirm;
+
+    let history_file = config.history_file();
+    let history = RunHistory::load(&history_file);
+    let run = match run_id {
+        Some(id) => history.get(id).ok_or_else(|| {
+            AnkiDeckBuilderError::ConfigurationError(format!(
+                "no run {} in the history (see `history`)",
+                id
+            ))
+        })?,
+        None => history.last_undoable().ok_or_else(|| {
+            AnkiDeckBuilderError::ConfigurationError(
+                "no run in the history has notes to undo".to_string(),
+            )
+        })?,
+    };
+    if run.undone_at.is_some() {
+        return Err(AnkiDeckBuilderError::ConfigurationError(format!(
+            "run {} was already undone",
+            run.id
+        ))
+        .into());
+    }
+    if run.note_ids.is_empty() {
+        return Err(AnkiDeckBuilderError::ConfigurationError(format!(
+            "run {} added no notes to Anki",
+            run.id
+        ))
+        .into());
+    }
+
+    // Notes deleted by hand since are left out
+    let anki_client = config.anki_client()?;
+    let notes = anki_client.notes_info(&run.note_ids).await?;
+    say!(
+        console,
+        "Run {} ({}) added {} notes to '{}'; {} are still in Anki:",
+        run.id,
+        crate::naming::format_timestamp(run.finished_at),
+        run.note_ids.len(),
+        run.summary.deck_name,
+        notes.len()
+    );
+    for note in notes.iter().take(20) {
+        say!(console, "  - {}", note.front().unwrap_or_default());
+    }
+    if notes.len() > 20 {
+        say!(console, "  ... and {} more", notes.len() - 20);
+    }
+    let note_ids: Vec<i64> = notes.iter().map(|note| note.note_id).collect();
+
+    if dry_run || note_ids.is_empty() {
+        return console.emit_json(&json!({
+            "run": run.id,
+            "dry_run": dry_run,
+            "note_ids": note_ids,
+            "deleted": 0,
+        }));
+    }
+    if !yes {
+        if !interactive {
+            return Err(AnkiDeckBuilderError::MissingInput(
+                "--yes to delete the notes".to_string(),
+            )
+            .into());
+        }
+        let confirmed = Confirm::with_theme(&*console.theme())
+            .with_prompt(console.tr(&format!(
+                "Delete these {} notes and their cards?",
+                note_ids.len()
+            )))
+            .default(false)
+            .interact()?;
+        if !confirmed {
+            say!(console, "Nothing deleted");
+            return Ok(());
+        }
+    }
+
+    anki_client.delete_notes(&note_ids).await?;
+    RunHistory::mark_undone(&history_file, run.id)?;
+    say!(console, "🗑️  Deleted {} notes", note_ids.len());
+    console.emit_json(&json!({
+        "run": run.id,
+        "dry_run": false,
+        "note_ids": note_ids,
+        "deleted": note_ids.len(),
+    }))
+}
+
+async fn handle_where_is(console: &Console, config: &Config, word: &str, anki: bool) -> Result<()> {
+    use crate::ankiweb::models::strip_html;
+    use crate::ankiweb::{field_contains_query, tag_query};
+    use crate::manifest::RunManifest;
+
+    let found: Vec<_> = RunManifest::load_all(&config.manifest_dir())?
+        .iter()
+        .flat_map(|manifest| manifest.find(word))
+        .collect();
+
+    if found.is_empty() {
+        say!(console, "🔍 '{}' is in none of the saved manifests", word);
+    } else {
+        say!(console, "🔍 '{}' in saved manifests:", word);
+    }
+    for found in &found {
+        let translation = found.translation.replace("<br>", " / ");
+        if found.in_translation {
+            say!(
+                console,
+                "  📦 {}: {} → {} (as a translation)",
+                found.deck_name,
+                found.word,
+                translation
+            );
+        } else {
+            say!(
+                console,
+                "  📦 {}: {} → {} ({:?})",
+                found.deck_name,
+                found.word,
+                translation,
+                found.status
+            );
+        }
+    }
+
+    let mut notes = Vec::new();
+    if anki {
+        let client = config.anki_client()?;
+        client.verify_connection().await?;
+
+        // Fields hold markup, so search broadly and compare the text after
+        let mut note_ids = Vec::new();
+        for field in ["Front", "Back"] {
+            let query = format!(
+                "{} {}",
+                tag_query("auto-generated"),
+                field_contains_query(field, word)
+            );
+            note_ids.extend(client.find_notes(&query).await?);
+        }
+        note_ids.sort_unstable();
+        note_ids.dedup();
+
+        let wanted = word.trim().to_lowercase();
+        let shows_word = |value: &str| {
+            strip_html(&value.replace("<br>", "\n"))
+                .lines()
+                .map(|line| line.rsplit(": ").next().unwrap_or(line))
+                .any(|line| line.trim().to_lowercase() == wanted)
+        };
+        for info in client.notes_info(&note_ids).await? {
+            let matches = info.front().is_some_and(|front| shows_word(&front))
+                || info
+                    .fields
+                    .get("Back")
+                    .is_some_and(|back| shows_word(&back.value));
+            if matches {
+                notes.push(info);
+            }
+        }
+
+        if notes.is_empty() {
+            say!(console, "\n🗂️  No generated notes in Anki show '{}'", word);
+        } else {
+            say!(console, "\n🗂️  Generated notes in Anki showing '{}':", word);
+        }
+        for info in &notes {
+            let deck = info
+                .tags
+                .iter()
+                .find_map(|tag| tag.strip_prefix("deck:"))
+                .unwrap_or("?");
+            say!(
+                console,
+                "  📝 {} in {}: {}",
+                info.note_id,
+                deck,
+                info.tags.join(" ")
+            );
+        }
+    }
+
+    let notes: Vec<_> = notes
+        .iter()
+        .map(|info| {
+            json!({
+                "note_id": info.note_id,
+                "deck": info.tags.iter().find_map(|tag| tag.strip_prefix("deck:")),
+                "front": info.front(),
+                "tags": info.tags,
+            })
+        })
+        .collect();
+    console.emit_json(&json!({
+        "word": word,
+        "manifests": found,
+        "notes": if anki { Some(notes) } else { None },
+    }))
+}
+
+async fn handle_manage(console: &Console, config: &Config, action: ManageAction) -> Result<()> {
+    use crate::ankiweb::deck_query;
+
+    let (deck, tags, suspend) = match action {
+        ManageAction::Suspend { deck, tag } => (deck, tag, true),
+        ManageAction::Unsuspend { deck, tag } => (deck, tag, false),
+    };
+
+    let client = config.anki_client()?;
+    client.verify_connection().await?;
+
+    let query = deck_query(&deck, &tags);
+    let cards = client.find_cards(&query).await?;
+
+    if cards.is_empty() {
+        say!(console, "ℹ️  No cards matched: {}", query);
+    } else if suspend {
+        client.suspend(&cards).await?;
+        say!(
+            console,
+            "⏸️  Suspended {} cards matching: {}",
+            cards.len(),
+            query
+        );
+    } else {
+        client.unsuspend(&cards).await?;
+        say!(
+            console,
+            "▶️  Unsuspended {} cards matching: {}",
+            cards.len(),
+            query
+        );
+    }
+
+    console.emit_json(&json!({
+        "action": if suspend { "suspend" } else { "unsuspend" },
+        "query": query,
+        "cards": cards.len(),
+    }))
 }
@@ -1,35 +1,321 @@
+use crate::ankiweb::{AnkiClient, AnkiConnectSettings, NoteMapping};
+use crate::error::AnkiDeckBuilderError;
+use crate::estimate::ConfirmThresholds;
+use crate::http::HttpSettings;
+use crate::language::{CacheMaxAge, CachePolicy, LanguagePacks, LlmSettings, TranslatorKind};
 use anyhow::Result;
 use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Environment variable that moves the cache, like `--cache-dir`
+pub const CACHE_DIR_ENV: &str = "ANKI_DECK_BUILDER_CACHE";
+
+/// Settings older releases only took from the environment, and the
+/// config.json keys they can be kept under now
+pub const LEGACY_ENV_SETTINGS: &[(&str, &str)] = &[
+    ("ANKICONNECT_URL", "ankiconnect_url"),
+    ("FORVO_API_KEY", "forvo_api_key"),
+];
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Config {
     pub ankiconnect_url: String,
+    /// Settings: config.json and the translation rules
+    pub config_dir: PathBuf,
+    /// What can't be recreated: manifests, growth, review sessions, reports, logs
+    pub data_dir: PathBuf,
+    /// What can be downloaded or looked up again: frequency lists,
+    /// translations, declensions, recordings. Safe to delete
     pub cache_dir: PathBuf,
+    /// Note type to create notes with instead of Basic
+    #[serde(default)]
+    pub note_model: Option<NoteMapping>,
+    /// Named settings for each language pair, chosen with `--config-profile`
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+    /// Proxy and certificates for every HTTP request
+    #[serde(default)]
+    pub http: HttpSettings,
+    /// Timeouts and retries of AnkiConnect requests
+    #[serde(default)]
+    pub ankiconnect: AnkiConnectSettings,
+    /// Default deck name template, see `--deck-name-pattern`
+    #[serde(default)]
+    pub deck_name_pattern: Option<String>,
+    /// How long cached frequency lists are trusted, see `--cache-max-age`
+    #[serde(default)]
+    pub cache_max_age: Option<CacheMaxAge>,
+    /// Default deck description template, see `--deck-description`
+    #[serde(default)]
+    pub deck_description: Option<String>,
+    /// Key for Forvo recordings when FORVO_API_KEY isn't set
+    #[serde(default, skip_serializing)]
+    pub forvo_api_key: Option<String>,
+    /// Model asked with `--translator llm` and for example sentences
+    #[serde(default)]
+    pub llm: LlmSettings,
+    /// When `create` asks before starting a long or costly run
+    #[serde(default)]
+    pub confirm_above: ConfirmThresholds,
+    /// Languages added by the packs in the config directory
+    #[serde(skip)]
+    pub language_packs: LanguagePacks,
+    /// Clients refuse actions that change anything in Anki, as `--read-only`
+    /// asks
+    #[serde(skip)]
+    pub read_only: bool,
+}
+
+/// Settings for one language pair. Each one that is set stands in for the
+/// matching `create` option when that option isn't given
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    pub target_language: Option<String>,
+    pub base_language: Option<String>,
+    /// Translator asked first
+    pub translator: Option<TranslatorKind>,
+    /// LibreTranslate server to fall back on
+    pub libretranslate_url: Option<String>,
+    /// Default deck name, see `--deck-name-pattern`
+    pub deck_name_pattern: Option<String>,
+    pub words_per_pos: Option<usize>,
+    pub with_images: Option<bool>,
+    pub with_conjugations: Option<bool>,
+    pub with_declensions: Option<bool>,
+    pub with_senses: Option<bool>,
+    pub with_audio: Option<bool>,
+    pub with_mnemonics: Option<bool>,
+    pub with_etymology: Option<bool>,
+    pub with_examples: Option<bool>,
+    /// Romanized Reading field for non-Latin scripts
+    pub readings: Option<bool>,
+    pub back_translate: Option<bool>,
+}
+
+/// What the clients of a run are built with: network settings, the LLM,
+/// the Forvo key, how long cached frequency lists are trusted, and the
+/// language packs. Front ends fill it in from the config file, the
+/// environment and the command line
+#[derive(Debug, Clone, Default)]
+pub struct ServiceSettings {
+    pub http: HttpSettings,
+    pub llm: LlmSettings,
+    /// Key for Forvo recordings when FORVO_API_KEY isn't set
+    pub forvo_api_key: Option<String>,
+    pub cache_max_age: CacheMaxAge,
+    pub language_packs: LanguagePacks,
+}
+
+impl ServiceSettings {
+    /// When cached frequency lists are revalidated
+    pub fn cache_policy(&self) -> CachePolicy {
+        CachePolicy::new(self.cache_max_age)
+    }
+}
+
+const CONFIG_FILE: &str = "config.json";
+
+/// Settings read from config.json in the config directory (see
+/// [`crate::config_schema`] for what each may be)
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct ConfigFile {
+    #[serde(default)]
+    ankiconnect_url: Option<String>,
+    #[serde(default)]
+    forvo_api_key: Option<String>,
+    #[serde(default)]
+    note_model: Option<NoteMapping>,
+    #[serde(default)]
+    profiles: BTreeMap<String, Profile>,
+    #[serde(default)]
+    http: HttpSettings,
+    #[serde(default)]
+    ankiconnect: AnkiConnectSettings,
+    #[serde(default)]
+    deck_name_pattern: Option<String>,
+    #[serde(default)]
+    cache_max_age: Option<CacheMaxAge>,
+    #[serde(default)]
+    deck_description: Option<String>,
+    #[serde(default)]
+    llm: LlmSettings,
+    #[serde(default)]
+    confirm_above: ConfirmThresholds,
+}
+
+impl ConfigFile {
+    fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = std::fs::read_to_string(path)?;
+        Self::parse(&content).map_err(|e| {
+            anyhow::anyhow!(
+                "Invalid config file {}: {} (run `config validate` for details)",
+                path.display(),
+                e
+            )
+        })
+    }
+
+    pub(crate) fn parse(content: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(content)
+    }
 }
 
 impl Config {
-    pub fn new() -> Result<Self> {
+    /// The config file and the environment, keeping the cache in
+    /// `cache_dir` when given (as `--cache-dir` asks)
+    pub fn new(cache_dir: Option<PathBuf>) -> Result<Self> {
+        let mut config = Self::directories(cache_dir)?;
+        let file = ConfigFile::load(&config.config_file())?;
+        config.ankiconnect_url = std::env::var("ANKICONNECT_URL")
+            .ok()
+            .or(file.ankiconnect_url)
+            .unwrap_or_else(|| "http://localhost:8765".to_string());
+        config.forvo_api_key = file.forvo_api_key;
+        config.note_model = file.note_model;
+        config.profiles = file.profiles;
+        config.http = file.http.merge(HttpSettings::from_env());
+        config.ankiconnect = file.ankiconnect;
+        config.deck_name_pattern = file.deck_name_pattern;
+        config.cache_max_age = file.cache_max_age;
+        config.deck_description = file.deck_description;
+        config.llm = file.llm;
+        config.confirm_above = file.confirm_above;
+        config.language_packs = LanguagePacks::load(&config.language_pack_dir());
+        Ok(config)
+    }
+
+    /// The directories alone, without reading the config file, for looking
+    /// at a file that may not load. `cache_dir` takes precedence over
+    /// `ANKI_DECK_BUILDER_CACHE`
+    pub fn directories(cache_dir: Option<PathBuf>) -> Result<Self> {
         let project_dirs = ProjectDirs::from("com", "anki-deck-builder", "anki-deck-builder")
             .ok_or_else(|| anyhow::anyhow!("Could not determine project directories"))?;
 
-        let cache_dir = project_dirs.data_dir().to_path_buf();
-
-        // Create cache directory if it doesn't exist
-        std::fs::create_dir_all(&cache_dir)?;
+        let cache_dir = cache_dir
+            .or_else(|| std::env::var_os(CACHE_DIR_ENV).map(PathBuf::from))
+            .unwrap_or_else(|| project_dirs.cache_dir().to_path_buf());
+        let config_dir = project_dirs.config_dir().to_path_buf();
+        let data_dir = project_dirs.data_dir().to_path_buf();
+        for dir in [&config_dir, &data_dir, &cache_dir] {
+            std::fs::create_dir_all(dir)?;
+        }
 
         Ok(Config {
-            ankiconnect_url: std::env::var("ANKICONNECT_URL")
-                .unwrap_or_else(|_| "http://localhost:8765".to_string()),
+            ankiconnect_url: String::new(),
+            config_dir,
+            data_dir,
             cache_dir,
+            note_model: None,
+            profiles: BTreeMap::new(),
+            http: HttpSettings::default(),
+            ankiconnect: AnkiConnectSettings::default(),
+            deck_name_pattern: None,
+            cache_max_age: None,
+            deck_description: None,
+            forvo_api_key: None,
+            llm: LlmSettings::default(),
+            confirm_above: ConfirmThresholds::default(),
+            language_packs: LanguagePacks::default(),
+            read_only: false,
+        })
+    }
+
+    /// What the clients of a run are built with, as configured
+    pub fn services(&self) -> ServiceSettings {
+        ServiceSettings {
+            http: self.http.clone(),
+            llm: self.llm.clone(),
+            forvo_api_key: self.forvo_api_key.clone(),
+            cache_max_age: self.cache_max_age.unwrap_or_default(),
+            language_packs: self.language_packs.clone(),
+        }
+    }
+
+    /// A profile from the config file by name
+    pub fn profile(&self, name: &str) -> std::result::Result<&Profile, AnkiDeckBuilderError> {
+        self.profiles.get(name).ok_or_else(|| {
+            let known: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+            AnkiDeckBuilderError::ConfigurationError(if known.is_empty() {
+                format!(
+                    "no profile '{}'; add profiles to {}",
+                    name,
+                    self.config_file().display()
+                )
+            } else {
+                format!("no profile '{}' (known: {})", name, known.join(", "))
+            })
         })
     }
 
+    /// AnkiConnect client with the configured timeouts and retries, refusing
+    /// changes when `read_only` is set
+    pub fn anki_client(&self) -> Result<AnkiClient> {
+        Ok(
+            AnkiClient::with_settings(self.ankiconnect_url.clone(), &self.ankiconnect, &self.http)?
+                .with_read_only(self.read_only),
+        )
+    }
+
     pub fn cache_dir(&self) -> &PathBuf {
         &self.cache_dir
     }
 
+    pub fn data_dir(&self) -> &PathBuf {
+        &self.data_dir
+    }
+
+    /// A settings file in the config directory. Releases before the split
+    /// kept settings in the data directory, where they are still read from
+    /// until moved
+    fn settings_file(&self, name: &str) -> PathBuf {
+        let path = self.config_dir.join(name);
+        let legacy = self.data_dir.join(name);
+        if !path.exists() && legacy.exists() {
+            legacy
+        } else {
+            path
+        }
+    }
+
+    /// Optional settings file
+    pub fn config_file(&self) -> PathBuf {
+        self.settings_file(CONFIG_FILE)
+    }
+
+    /// Legacy settings set in the environment, as (variable, key, value),
+    /// while there is no config file to keep them in yet
+    pub fn env_settings_to_import(&self) -> Vec<(&'static str, &'static str, String)> {
+        if self.config_file().exists() {
+            return Vec::new();
+        }
+        LEGACY_ENV_SETTINGS
+            .iter()
+            .filter_map(|&(variable, key)| {
+                let value = std::env::var(variable).ok()?;
+                let value = value.trim();
+                (!value.is_empty()).then(|| (variable, key, value.to_string()))
+            })
+            .collect()
+    }
+
+    /// Start the config file with these settings, as (variable, key, value)
+    pub fn create_config_file(&self, settings: &[(&str, &str, String)]) -> Result<PathBuf> {
+        let path = self.config_dir.join(CONFIG_FILE);
+        let file: serde_json::Map<String, serde_json::Value> = settings
+            .iter()
+            .map(|(_, key, value)| (key.to_string(), value.clone().into()))
+            .collect();
+        crate::cache_file::write_json(&path, &file)?;
+        Ok(path)
+    }
+
     pub fn frequency_cache_dir(&self) -> PathBuf {
         self.cache_dir.join("frequency")
     }
@@ -37,10 +323,205 @@ impl Config {
     pub fn translation_cache_dir(&self) -> PathBuf {
         self.cache_dir.join("translations")
     }
+
+    /// Noun forms looked up on Wiktionary
+    pub fn declension_cache_dir(&self) -> PathBuf {
+        self.cache_dir.join("declensions")
+    }
+
+    pub fn session_dir(&self) -> PathBuf {
+        self.data_dir.join("sessions")
+    }
+
+    /// Per-deck record of the words added by previous runs
+    pub fn manifest_dir(&self) -> PathBuf {
+        self.data_dir.join("manifests")
+    }
+
+    /// How far `grow` has taken each deck
+    pub fn growth_dir(&self) -> PathBuf {
+        self.data_dir.join("growth")
+    }
+
+    /// What each run added to a deck, one file per deck
+    pub fn changelog_dir(&self) -> PathBuf {
+        self.data_dir.join("changelogs")
+    }
+
+    /// Reports of words a run could not add
+    pub fn report_dir(&self) -> PathBuf {
+        self.data_dir.join("reports")
+    }
+
+    /// Every finished `create` run, for the `history` command
+    pub fn history_file(&self) -> PathBuf {
+        self.data_dir.join("history.json")
+    }
+
+    /// What is remembered between runs, like recent language pairs
+    pub fn state_file(&self) -> PathBuf {
+        self.data_dir.join("state.json")
+    }
+
+    /// Debug logs of past runs
+    pub fn log_dir(&self) -> PathBuf {
+        self.data_dir.join("logs")
+    }
+
+    /// Your own translations, one file per language pair
+    pub fn glossary_dir(&self) -> PathBuf {
+        self.config_dir.join("glossaries")
+    }
+
+    /// Languages added without a new release, one `<code>.toml` each
+    pub fn language_pack_dir(&self) -> PathBuf {
+        self.config_dir.join("languages.d")
+    }
+
+    /// Default translation post-processing rules file
+    pub fn rules_file(&self) -> PathBuf {
+        self.settings_file("rules.json")
+    }
 }
 
 impl Default for Config {
     fn default() -> Self {
-        Self::new().expect("Failed to create default config")
+        Self::new(None).expect("Failed to create default config")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_profiles() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(CONFIG_FILE);
+        std::fs::write(
+            &path,
+            r#"{"profiles": {"croatian": {"target_language": "hr", "words_per_pos": 50, "with_declensions": true}}}"#,
+        )
+        .unwrap();
+
+        let config = Config {
+            ankiconnect_url: "http://localhost:9999".to_string(),
+            config_dir: temp_dir.path().to_path_buf(),
+            data_dir: temp_dir.path().to_path_buf(),
+            cache_dir: temp_dir.path().to_path_buf(),
+            note_model: None,
+            profiles: ConfigFile::load(&path).unwrap().profiles,
+            http: HttpSettings::default(),
+            ankiconnect: AnkiConnectSettings::default(),
+            deck_name_pattern: None,
+            cache_max_age: None,
+            deck_description: None,
+            forvo_api_key: None,
+            llm: LlmSettings::default(),
+            confirm_above: ConfirmThresholds::default(),
+            language_packs: LanguagePacks::default(),
+            read_only: false,
+        };
+        let profile = config.profile("croatian").unwrap();
+        assert_eq!(profile.target_language.as_deref(), Some("hr"));
+        assert_eq!(profile.words_per_pos, Some(50));
+        assert_eq!(profile.with_declensions, Some(true));
+        assert_eq!(profile.with_images, None);
+
+        let error = config.profile("japanese").unwrap_err().to_string();
+        assert!(error.contains("known: croatian"));
+
+        // A misspelled setting is an error rather than silently ignored
+        std::fs::write(&path, r#"{"profiles": {"x": {"word_per_pos": 5}}}"#).unwrap();
+        assert!(ConfigFile::load(&path).is_err());
+    }
+
+    #[test]
+    fn test_settings_read_from_the_data_directory_until_moved() {
+        let temp_dir = TempDir::new().unwrap();
+        let (config_dir, data_dir) = (temp_dir.path().join("config"), temp_dir.path().join("data"));
+        std::fs::create_dir_all(&data_dir).unwrap();
+        let config = Config {
+            ankiconnect_url: "http://localhost:9999".to_string(),
+            config_dir: config_dir.clone(),
+            data_dir: data_dir.clone(),
+            cache_dir: temp_dir.path().join("cache"),
+            note_model: None,
+            profiles: BTreeMap::new(),
+            http: HttpSettings::default(),
+            ankiconnect: AnkiConnectSettings::default(),
+            deck_name_pattern: None,
+            cache_max_age: None,
+            deck_description: None,
+            forvo_api_key: None,
+            llm: LlmSettings::default(),
+            confirm_above: ConfirmThresholds::default(),
+            language_packs: LanguagePacks::default(),
+            read_only: false,
+        };
+        assert_eq!(config.config_file(), config_dir.join(CONFIG_FILE));
+        assert_eq!(config.manifest_dir(), data_dir.join("manifests"));
+        assert_eq!(
+            config.frequency_cache_dir(),
+            temp_dir.path().join("cache/frequency")
+        );
+
+        std::fs::write(data_dir.join("rules.json"), "{}").unwrap();
+        assert_eq!(config.rules_file(), data_dir.join("rules.json"));
+        std::fs::create_dir_all(&config_dir).unwrap();
+        std::fs::write(config_dir.join("rules.json"), "{}").unwrap();
+        assert_eq!(config.rules_file(), config_dir.join("rules.json"));
+
+        // Legacy environment settings start a new config file
+        let path = config
+            .create_config_file(&[(
+                "ANKICONNECT_URL",
+                "ankiconnect_url",
+                "http://localhost:8777".to_string(),
+            )])
+            .unwrap();
+        assert_eq!(path, config_dir.join(CONFIG_FILE));
+        let file = ConfigFile::load(&path).unwrap();
+        assert_eq!(
+            file.ankiconnect_url.as_deref(),
+            Some("http://localhost:8777")
+        );
+        assert!(config.env_settings_to_import().is_empty());
+    }
+
+    #[test]
+    fn test_http_settings() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(CONFIG_FILE);
+        std::fs::write(
+            &path,
+            r#"{"http": {"proxy": "http://proxy.corp:3128", "ca_cert": "/etc/corp-ca.pem"}}"#,
+        )
+        .unwrap();
+
+        let http = ConfigFile::load(&path).unwrap().http;
+        assert_eq!(http.proxy.as_deref(), Some("http://proxy.corp:3128"));
+        assert_eq!(http.ca_cert, Some(PathBuf::from("/etc/corp-ca.pem")));
+        assert!(!http.insecure);
+    }
+
+    #[test]
+    fn test_ankiconnect_settings() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(CONFIG_FILE);
+        std::fs::write(
+            &path,
+            r#"{"ankiconnect": {"timeout_secs": 60, "retries": 0}}"#,
+        )
+        .unwrap();
+
+        let ankiconnect = ConfigFile::load(&path).unwrap().ankiconnect;
+        assert_eq!(ankiconnect.timeout_secs, 60);
+        assert_eq!(ankiconnect.retries, 0);
+        assert_eq!(
+            ankiconnect.long_timeout_secs,
+            AnkiConnectSettings::default().long_timeout_secs
+        );
     }
 }
@@ -0,0 +1,442 @@
+//! What config.json may contain, checked key by key so `config validate`
+//! can say exactly which setting is wrong: a misspelled key, a URL that
+//! doesn't parse, a duration that isn't one. Loading the file only reports
+//! the first problem serde runs into, and ignores unknown top-level keys
+
+use crate::config::ConfigFile;
+use crate::error::AnkiDeckBuilderError;
+use crate::language::cognate::edit_distance;
+use crate::language::{CacheMaxAge, LanguagePacks};
+use crate::naming::fill_template;
+use reqwest::Url;
+use serde::Serialize;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/// What a value in config.json must be. `null` always stands for unset
+#[derive(Debug)]
+enum Schema {
+    /// An object with these keys
+    Object(&'static [(&'static str, Schema)]),
+    /// An object keyed by names the user picks, each value alike
+    Map(&'static Schema),
+    String,
+    Bool,
+    /// A whole number, zero or more
+    Integer,
+    /// Any number, zero or more
+    Number,
+    /// An absolute URL such as "http://localhost:8765"
+    Url,
+    /// "never", a number of days such as "7d", or a duration such as "12h"
+    Duration,
+    /// A path to a file that exists
+    File,
+    /// A language name or code we support
+    Language,
+    /// A template using only these placeholders, named for messages
+    Template(&'static str, &'static [&'static str]),
+    /// One of these names
+    OneOf(&'static [&'static str]),
+}
+
+const PROFILE: Schema = Schema::Object(&[
+    ("target_language", Schema::Language),
+    ("base_language", Schema::Language),
+    ("translator", Schema::OneOf(&["mymemory", "google", "llm"])),
+    ("libretranslate_url", Schema::Url),
+    (
+        "deck_name_pattern",
+        Schema::Template("deck name", crate::naming::PLACEHOLDERS),
+    ),
+    ("words_per_pos", Schema::Integer),
+    ("with_images", Schema::Bool),
+    ("with_conjugations", Schema::Bool),
+    ("with_declensions", Schema::Bool),
+    ("with_senses", Schema::Bool),
+    ("with_audio", Schema::Bool),
+    ("with_mnemonics", Schema::Bool),
+    ("with_etymology", Schema::Bool),
+    ("with_examples", Schema::Bool),
+    ("readings", Schema::Bool),
+    ("back_translate", Schema::Bool),
+]);
+
+const CONFIG_FILE: Schema = Schema::Object(&[
+    ("ankiconnect_url", Schema::Url),
+    ("forvo_api_key", Schema::String),
+    (
+        "note_model",
+        Schema::Object(&[
+            ("model", Schema::String),
+            ("fields", Schema::Map(&Schema::String)),
+        ]),
+    ),
+    ("profiles", Schema::Map(&PROFILE)),
+    (
+        "http",
+        Schema::Object(&[
+            ("proxy", Schema::Url),
+            ("no_proxy", Schema::String),
+            ("ca_cert", Schema::File),
+            ("insecure", Schema::Bool),
+        ]),
+    ),
+    (
+        "ankiconnect",
+        Schema::Object(&[
+            ("timeout_secs", Schema::Integer),
+            ("long_timeout_secs", Schema::Integer),
+            ("retries", Schema::Integer),
+            ("retry_delay_ms", Schema::Integer),
+            ("anki_path", Schema::File),
+            ("launch_timeout_secs", Schema::Integer),
+        ]),
+    ),
+    (
+        "deck_name_pattern",
+        Schema::Template("deck name", crate::naming::PLACEHOLDERS),
+    ),
+    ("cache_max_age", Schema::Duration),
+    (
+        "deck_description",
+        Schema::Template("deck description", crate::description::PLACEHOLDERS),
+    ),
+    (
+        "llm",
+        Schema::Object(&[
+            ("api_url", Schema::Url),
+            ("api_key", Schema::String),
+            ("model", Schema::String),
+            ("input_price", Schema::Number),
+            ("output_price", Schema::Number),
+        ]),
+    ),
+    (
+        "confirm_above",
+        Schema::Object(&[
+            ("requests", Schema::Integer),
+            ("minutes", Schema::Integer),
+            ("dollars", Schema::Number),
+        ]),
+    ),
+]);
+
+/// One thing wrong with a config file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConfigIssue {
+    /// Where in the file, e.g. "profiles.croatian.words_per_pos"; empty for
+    /// the file as a whole
+    pub path: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+/// Everything wrong with the contents of a config file, by key; empty
+/// when it is fine. Languages are known from the built-in ones and `packs`
+pub fn validate_config(content: &str, packs: &LanguagePacks) -> Vec<ConfigIssue> {
+    let value: Value = match serde_json::from_str(content) {
+        Ok(value) => value,
+        Err(e) => {
+            return vec![ConfigIssue {
+                path: String::new(),
+                message: format!("not valid JSON: {}", e),
+            }]
+        }
+    };
+
+    let mut issues = Vec::new();
+    check(&CONFIG_FILE, &value, "", packs, &mut issues);
+    // Anything the schema lets through that loading still refuses
+    if issues.is_empty() {
+        if let Err(e) = ConfigFile::parse(content) {
+            issues.push(ConfigIssue {
+                path: String::new(),
+                message: e.to_string(),
+            });
+        }
+    }
+    issues
+}
+
+/// Check the config file at `path`; a missing one is fine
+pub fn validate_config_file(
+    path: &Path,
+    packs: &LanguagePacks,
+) -> std::io::Result<Vec<ConfigIssue>> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => Ok(validate_config(&content, packs)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+fn check(
+    schema: &Schema,
+    value: &Value,
+    path: &str,
+    packs: &LanguagePacks,
+    issues: &mut Vec<ConfigIssue>,
+) {
+    if let Some(message) = check_value(schema, value, path, packs, issues) {
+        issues.push(ConfigIssue {
+            path: path.to_string(),
+            message,
+        });
+    }
+}
+
+/// What is wrong with the value itself, after checking what it contains
+fn check_value(
+    schema: &Schema,
+    value: &Value,
+    path: &str,
+    packs: &LanguagePacks,
+    issues: &mut Vec<ConfigIssue>,
+) -> Option<String> {
+    if value.is_null() {
+        return None;
+    }
+    let text = value.as_str();
+
+    match schema {
+        Schema::Object(keys) => {
+            let Some(object) = value.as_object() else {
+                return Some(format!("expected an object, found {}", kind(value)));
+            };
+            for (key, value) in object {
+                let path = join(path, key);
+                match keys.iter().find(|(name, _)| name == key) {
+                    Some((_, schema)) => check(schema, value, &path, packs, issues),
+                    None => issues.push(ConfigIssue {
+                        path,
+                        message: unknown_key(key, keys),
+                    }),
+                }
+            }
+            None
+        }
+        Schema::Map(schema) => {
+            let Some(object) = value.as_object() else {
+                return Some(format!("expected an object, found {}", kind(value)));
+            };
+            for (key, value) in object {
+                check(schema, value, &join(path, key), packs, issues);
+            }
+            None
+        }
+        Schema::Bool => {
+            (!value.is_boolean()).then(|| format!("expected true or false, found {}", kind(value)))
+        }
+        Schema::Integer => value.as_u64().is_none().then(|| {
+            format!(
+                "expected a whole number of 0 or more, found {}",
+                kind(value)
+            )
+        }),
+        Schema::Number => value
+            .as_f64()
+            .filter(|n| *n >= 0.0)
+            .is_none()
+            .then(|| format!("expected a number of 0 or more, found {}", kind(value))),
+        _ if text.is_none() => Some(format!("expected a string, found {}", kind(value))),
+        Schema::String => None,
+        Schema::Url => {
+            let url = text.unwrap_or_default();
+            match Url::parse(url) {
+                Ok(parsed) if parsed.has_host() => None,
+                Ok(_) => Some(format!("'{}' is not a URL with a host", url)),
+                Err(e) => Some(format!(
+                    "'{}' is not a URL ({}); write it like http://localhost:8765",
+                    url, e
+                )),
+            }
+        }
+        Schema::Duration => text
+            .unwrap_or_default()
+            .parse::<CacheMaxAge>()
+            .err()
+            .map(|e| format!("{}; use e.g. \"7d\", \"12h\" or \"never\"", e)),
+        Schema::File => {
+            let file = PathBuf::from(text.unwrap_or_default());
+            (!file.is_file()).then(|| format!("no file at {}", file.display()))
+        }
+        Schema::Language => {
+            let language = text.unwrap_or_default();
+            packs.language(language).is_none().then(|| {
+                format!(
+                    "unknown language '{}'; see the `languages` command",
+                    language
+                )
+            })
+        }
+        Schema::OneOf(names) => {
+            let name = text.unwrap_or_default();
+            (!names.contains(&name))
+                .then(|| format!("'{}' is not one of {}", name, names.join(", ")))
+        }
+        Schema::Template(what, placeholders) => {
+            let known = |placeholder: &str| placeholders.contains(&placeholder).then(String::new);
+            match fill_template(text.unwrap_or_default(), what, placeholders, known) {
+                Err(AnkiDeckBuilderError::ConfigurationError(message)) => Some(message),
+                _ => None,
+            }
+        }
+    }
+}
+
+/// "unknown key 'word_per_pos' (did you mean 'words_per_pos'?)"
+fn unknown_key(key: &str, keys: &[(&str, Schema)]) -> String {
+    let closest = keys
+        .iter()
+        .map(|(name, _)| (edit_distance(key, name), *name))
+        .min()
+        .filter(|(distance, name)| *distance <= (name.len() / 3).max(1));
+    match closest {
+        Some((_, name)) => format!("unknown key '{}' (did you mean '{}'?)", key, name),
+        None => {
+            let names: Vec<&str> = keys.iter().map(|(name, _)| *name).collect();
+            format!("unknown key '{}' (known: {})", key, names.join(", "))
+        }
+    }
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+/// How a JSON value reads in a message
+fn kind(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => format!("\"{}\"", s),
+        Value::Array(_) => "a list".to_string(),
+        Value::Object(_) => "an object".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ankiweb::AnkiConnectSettings;
+    use crate::config::Profile;
+    use crate::estimate::ConfirmThresholds;
+    use crate::http::HttpSettings;
+    use crate::language::LlmSettings;
+
+    #[test]
+    fn test_valid_config() {
+        let config = r#"{
+            "ankiconnect_url": "http://localhost:8765",
+            "note_model": { "model": "Vocab", "fields": { "Front": "Term" } },
+            "profiles": { "croatian": { "target_language": "hr", "words_per_pos": 50 } },
+            "ankiconnect": { "timeout_secs": 60 },
+            "cache_max_age": "7d",
+            "deck_name_pattern": "{target} — {date}",
+            "deck_description": null
+        }"#;
+        assert_eq!(validate_config(config, &LanguagePacks::default()), []);
+    }
+
+    #[test]
+    fn test_issues_name_the_setting() {
+        let config = r#"{
+            "ankiconect_url": "http://localhost:8765",
+            "profiles": {
+                "croatian": { "word_per_pos": 50, "target_language": "Klingon" },
+                "spanish": { "libretranslate_url": "localhost:5000/translate", "with_audio": "yes" }
+            },
+            "http": { "proxy": "proxy.corp" },
+            "cache_max_age": "a fortnight",
+            "deck_description": "{pair} {level}"
+        }"#;
+        let issues: Vec<String> = validate_config(config, &LanguagePacks::default())
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(issues.len(), 8, "{:#?}", issues);
+        assert_eq!(
+            issues[0],
+            "ankiconect_url: unknown key 'ankiconect_url' (did you mean 'ankiconnect_url'?)"
+        );
+        assert!(issues[1].starts_with("cache_max_age: "));
+        assert!(issues[2].starts_with("deck_description: unknown placeholder {level}"));
+        assert!(issues[3].starts_with("http.proxy: 'proxy.corp' is not a URL"));
+        assert!(issues[4].starts_with("profiles.croatian.target_language: unknown language"));
+        assert_eq!(
+            issues[5],
+            "profiles.croatian.word_per_pos: unknown key 'word_per_pos' (did you mean 'words_per_pos'?)"
+        );
+        assert!(issues[6].starts_with("profiles.spanish.libretranslate_url: 'localhost:5000"));
+        assert_eq!(
+            issues[7],
+            "profiles.spanish.with_audio: expected true or false, found \"yes\""
+        );
+
+        let broken = validate_config("{\n  \"profiles\": {,\n}", &LanguagePacks::default());
+        assert!(
+            broken[0].message.contains("line 2 column 16"),
+            "{}",
+            broken[0]
+        );
+    }
+
+    #[test]
+    fn test_schema_covers_every_setting() {
+        let keys = |value: serde_json::Value| -> Vec<String> {
+            value.as_object().unwrap().keys().cloned().collect()
+        };
+        let schema_keys = |name: &str| -> Vec<String> {
+            let Schema::Object(top) = CONFIG_FILE else {
+                unreachable!()
+            };
+            let schema = match name {
+                "profiles" => &PROFILE,
+                _ => &top.iter().find(|(key, _)| *key == name).unwrap().1,
+            };
+            let Schema::Object(keys) = schema else {
+                unreachable!()
+            };
+            let mut keys: Vec<String> = keys.iter().map(|(key, _)| key.to_string()).collect();
+            keys.sort();
+            keys
+        };
+
+        assert_eq!(
+            keys(serde_json::to_value(Profile::default()).unwrap()),
+            schema_keys("profiles")
+        );
+        assert_eq!(
+            keys(serde_json::to_value(HttpSettings::default()).unwrap()),
+            schema_keys("http")
+        );
+        assert_eq!(
+            keys(serde_json::to_value(AnkiConnectSettings::default()).unwrap()),
+            schema_keys("ankiconnect")
+        );
+        // The key is never written back out
+        let mut llm = keys(serde_json::to_value(LlmSettings::default()).unwrap());
+        llm.push("api_key".to_string());
+        llm.sort();
+        assert_eq!(llm, schema_keys("llm"));
+        assert_eq!(
+            keys(serde_json::to_value(ConfirmThresholds::default()).unwrap()),
+            schema_keys("confirm_above")
+        );
+    }
+}
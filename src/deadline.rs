@@ -0,0 +1,102 @@
+use std::time::{Duration, Instant};
+
+/// Parse a duration like "90s", "30m", "2h", or "1h30m"
+pub fn parse_duration(input: &str) -> Result<Duration, String> {
+    let mut total = 0u64;
+    let mut digits = String::new();
+
+    for c in input.trim().chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+
+        let value: u64 = digits
+            .parse()
+            .map_err(|_| format!("invalid duration '{}'", input))?;
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            _ => return Err(format!("unknown unit '{}' in '{}' (use s, m, h)", c, input)),
+        };
+        total = value
+            .checked_mul(unit)
+            .and_then(|secs| total.checked_add(secs))
+            .ok_or_else(|| format!("duration '{}' is too long", input))?;
+        digits.clear();
+    }
+
+    if !digits.is_empty() {
+        return Err(format!("missing unit in '{}' (use s, m, h)", input));
+    }
+    if total == 0 {
+        return Err(format!("duration '{}' must be greater than zero", input));
+    }
+
+    Ok(Duration::from_secs(total))
+}
+
+/// Point in time a run must finish by. A deadline without a limit never expires
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Deadline {
+    at: Option<Instant>,
+}
+
+impl Deadline {
+    pub fn none() -> Self {
+        Self { at: None }
+    }
+
+    /// A deadline too far off for the clock to hold never expires
+    pub fn after(duration: Duration) -> Self {
+        Self {
+            at: Instant::now().checked_add(duration),
+        }
+    }
+
+    pub fn from_limit(limit: Option<Duration>) -> Self {
+        limit.map_or_else(Self::none, Self::after)
+    }
+
+    pub fn expired(&self) -> bool {
+        self.at.is_some_and(|at| Instant::now() >= at)
+    }
+
+    /// Time left, or None if there is no limit
+    pub fn remaining(&self) -> Option<Duration> {
+        self.at
+            .map(|at| at.saturating_duration_since(Instant::now()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("90s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("30m"), Ok(Duration::from_secs(1800)));
+        assert_eq!(parse_duration("1h30m"), Ok(Duration::from_secs(5400)));
+    }
+
+    #[test]
+    fn test_parse_duration_errors() {
+        assert!(parse_duration("30").is_err());
+        assert!(parse_duration("5d").is_err());
+        assert!(parse_duration("0m").is_err());
+        assert!(parse_duration("m").is_err());
+        assert!(parse_duration("9999999999999999h").is_err());
+        assert!(parse_duration(&format!("{}s1s", u64::MAX)).is_err());
+    }
+
+    #[test]
+    fn test_deadline() {
+        assert!(!Deadline::none().expired());
+        assert!(Deadline::none().remaining().is_none());
+        assert!(Deadline::after(Duration::ZERO).expired());
+        assert!(!Deadline::after(Duration::from_secs(60)).expired());
+        assert!(!Deadline::after(Duration::MAX).expired());
+    }
+}
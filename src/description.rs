@@ -0,0 +1,293 @@
+//! Deck descriptions with study guidance, written in the learner's own
+//! language from templates such as "{pair}: {words} words on {cards} cards"
+
+use crate::error::Result;
+use crate::language::Attribution;
+use crate::naming::fill_template;
+
+/// Placeholders a description template can use
+pub const PLACEHOLDERS: &[&str] = &[
+    "target",
+    "base",
+    "pair",
+    "words",
+    "cards",
+    "new_per_day",
+    "days",
+    "tags",
+    "sources",
+];
+
+/// What the placeholders of a description template stand for
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DescriptionValues {
+    /// Target language name, e.g. "Croatian"
+    pub target: String,
+    pub target_code: String,
+    /// Base language names, e.g. "Spanish, English"
+    pub base: String,
+    /// Main base language, which the description is written in
+    pub base_code: String,
+    /// Number of words the deck is built from
+    pub words: usize,
+    pub cards: usize,
+    /// New cards to study a day, from the scheduling preset or
+    /// [`suggested_new_per_day`]
+    pub new_per_day: usize,
+    /// Credits of the frequency lists the words come from
+    pub attributions: Vec<Attribution>,
+    /// Whether the deck has generated sections, tagged `section:<name>`
+    pub sections: bool,
+    /// Whether nouns are tagged `gender:<name>` by a language pack
+    pub genders: bool,
+}
+
+/// New cards a day that get through a deck in about a month, within what
+/// Anki's presets suggest
+pub fn suggested_new_per_day(cards: usize) -> usize {
+    (cards / 30).clamp(5, 20)
+}
+
+/// The text of a description in one language
+struct Strings {
+    template: &'static str,
+    tags: &'static str,
+    sources: &'static str,
+    direction: &'static str,
+    pos: &'static str,
+    difficulty: &'static str,
+    cognate: &'static str,
+    confusable: &'static str,
+    section: &'static str,
+    gender: &'static str,
+}
+
+const ENGLISH: Strings = Strings {
+    template: concat!(
+        "<b>{pair}</b><br>{words} words on {cards} cards.<br><br>",
+        "<b>How to study</b><br>Learn about {new_per_day} new cards a day and do your ",
+        "reviews every day; at that pace you will have seen every card in {days} days. ",
+        "Say each answer aloud before you turn the card over.<br><br>",
+        "{tags}{sources}Built with anki-deck-builder."
+    ),
+    tags: "Tags",
+    sources: "Word frequencies from",
+    direction: "which way the card asks",
+    pos: "part of speech",
+    difficulty: "how hard the word is likely to be",
+    cognate: "looks like its translation",
+    confusable: "easily mixed up with another word of the deck",
+    section: "generated vocabulary such as numbers or dates",
+    gender: "grammatical gender of the noun",
+};
+
+const SPANISH: Strings = Strings {
+    template: concat!(
+        "<b>{pair}</b><br>{words} palabras en {cards} tarjetas.<br><br>",
+        "<b>Cómo estudiar</b><br>Aprende unas {new_per_day} tarjetas nuevas al día y haz ",
+        "tus repasos todos los días; a ese ritmo habrás visto todas las tarjetas en {days} ",
+        "días. Di cada respuesta en voz alta antes de dar la vuelta a la tarjeta.<br><br>",
+        "{tags}{sources}Creado con anki-deck-builder."
+    ),
+    tags: "Etiquetas",
+    sources: "Frecuencias de palabras de",
+    direction: "dirección de la tarjeta",
+    pos: "categoría gramatical",
+    difficulty: "dificultad probable de la palabra",
+    cognate: "se parece a su traducción",
+    confusable: "se confunde fácilmente con otra palabra del mazo",
+    section: "vocabulario generado, como números o fechas",
+    gender: "género gramatical del sustantivo",
+};
+
+const CROATIAN: Strings = Strings {
+    template: concat!(
+        "<b>{pair}</b><br>Riječi: {words}, kartica: {cards}.<br><br>",
+        "<b>Kako učiti</b><br>Učite oko {new_per_day} novih kartica dnevno i svaki dan ",
+        "odradite ponavljanja; tim tempom vidjet ćete sve kartice za {days} dana. ",
+        "Svaki odgovor izgovorite naglas prije nego okrenete karticu.<br><br>",
+        "{tags}{sources}Izrađeno s anki-deck-builder."
+    ),
+    tags: "Oznake",
+    sources: "Učestalost riječi prema",
+    direction: "smjer kartice",
+    pos: "vrsta riječi",
+    difficulty: "očekivana težina riječi",
+    cognate: "slična je svom prijevodu",
+    confusable: "lako se zamijeni s drugom riječi iz špila",
+    section: "generirani rječnik, npr. brojevi ili datumi",
+    gender: "gramatički rod imenice",
+};
+
+const GERMAN: Strings = Strings {
+    template: concat!(
+        "<b>{pair}</b><br>{words} Wörter auf {cards} Karten.<br><br>",
+        "<b>So lernst du</b><br>Lerne etwa {new_per_day} neue Karten am Tag und mach jeden ",
+        "Tag deine Wiederholungen; in diesem Tempo hast du nach {days} Tagen jede Karte ",
+        "gesehen. Sprich jede Antwort laut aus, bevor du die Karte umdrehst.<br><br>",
+        "{tags}{sources}Erstellt mit anki-deck-builder."
+    ),
+    tags: "Schlagwörter",
+    sources: "Worthäufigkeiten aus",
+    direction: "Abfragerichtung der Karte",
+    pos: "Wortart",
+    difficulty: "voraussichtliche Schwierigkeit des Wortes",
+    cognate: "ähnelt seiner Übersetzung",
+    confusable: "leicht mit einem anderen Wort des Stapels zu verwechseln",
+    section: "erzeugter Wortschatz wie Zahlen oder Daten",
+    gender: "grammatisches Geschlecht des Nomens",
+};
+
+const FRENCH: Strings = Strings {
+    template: concat!(
+        "<b>{pair}</b><br>{words} mots sur {cards} cartes.<br><br>",
+        "<b>Comment étudier</b><br>Apprenez environ {new_per_day} nouvelles cartes par jour ",
+        "et faites vos révisions chaque jour ; à ce rythme, vous aurez vu toutes les cartes ",
+        "en {days} jours. Dites chaque réponse à voix haute avant de retourner la ",
+        "carte.<br><br>{tags}{sources}Créé avec anki-deck-builder."
+    ),
+    tags: "Étiquettes",
+    sources: "Fréquences des mots d'après",
+    direction: "sens de la carte",
+    pos: "catégorie grammaticale",
+    difficulty: "difficulté probable du mot",
+    cognate: "ressemble à sa traduction",
+    confusable: "facile à confondre avec un autre mot du paquet",
+    section: "vocabulaire généré, comme les nombres ou les dates",
+    gender: "genre grammatical du nom",
+};
+
+/// The description's text in a base language, English when it has none
+fn strings(language_code: &str) -> &'static Strings {
+    match language_code {
+        "es" => &SPANISH,
+        "hr" | "sr" | "bs" => &CROATIAN,
+        "de" => &GERMAN,
+        "fr" => &FRENCH,
+        _ => &ENGLISH,
+    }
+}
+
+/// What the tags on the deck's cards mean, as a block ending in a blank line
+fn tag_legend(strings: &Strings, values: &DescriptionValues) -> String {
+    let mut tags = vec![
+        (
+            format!("{}-to-{}", values.target_code, values.base_code),
+            strings.direction,
+        ),
+        ("pos:…".to_string(), strings.pos),
+        ("difficulty:…".to_string(), strings.difficulty),
+        ("cognate".to_string(), strings.cognate),
+        ("confusable".to_string(), strings.confusable),
+    ];
+    if values.sections {
+        tags.push(("section:…".to_string(), strings.section));
+    }
+    if values.genders {
+        tags.push(("gender:…".to_string(), strings.gender));
+    }
+    let lines: Vec<String> = tags
+        .into_iter()
+        .map(|(tag, meaning)| format!("<code>{}</code> – {}", tag, meaning))
+        .collect();
+    format!("<b>{}</b><br>{}<br><br>", strings.tags, lines.join("<br>"))
+}
+
+/// Credits of the word lists as a block ending in a blank line, or nothing
+fn source_credits(strings: &Strings, attributions: &[Attribution]) -> String {
+    if attributions.is_empty() {
+        return String::new();
+    }
+    let credits: Vec<String> = attributions.iter().map(Attribution::credit_html).collect();
+    format!(
+        "<b>{}</b><br>{}<br><br>",
+        strings.sources,
+        credits.join("<br>")
+    )
+}
+
+/// A deck's description from a template, or the built-in one in the base
+/// language. Unknown placeholders are an error
+pub fn render_description(template: Option<&str>, values: &DescriptionValues) -> Result<String> {
+    let strings = strings(&values.base_code);
+    let new_per_day = values.new_per_day.max(1);
+    fill_template(
+        template.unwrap_or(strings.template),
+        "deck description",
+        PLACEHOLDERS,
+        |placeholder| {
+            Some(match placeholder {
+                "target" => values.target.clone(),
+                "base" => values.base.clone(),
+                "pair" => format!("{} → {}", values.target, values.base),
+                "words" => values.words.to_string(),
+                "cards" => values.cards.to_string(),
+                "new_per_day" => new_per_day.to_string(),
+                "days" => values.cards.div_ceil(new_per_day).to_string(),
+                "tags" => tag_legend(strings, values),
+                "sources" => source_credits(strings, &values.attributions),
+                _ => return None,
+            })
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(base_code: &str) -> DescriptionValues {
+        DescriptionValues {
+            target: "Croatian".to_string(),
+            target_code: "hr".to_string(),
+            base: "Spanish".to_string(),
+            base_code: base_code.to_string(),
+            words: 300,
+            cards: 600,
+            new_per_day: suggested_new_per_day(600),
+            attributions: vec![Attribution::new(
+                "FrequencyWords",
+                "Hermit Dave",
+                "CC-BY-SA-4.0",
+                "https://github.com/hermitdave/FrequencyWords",
+            )],
+            sections: false,
+            genders: true,
+        }
+    }
+
+    #[test]
+    fn test_builtin_description() {
+        let description = render_description(None, &values("en")).unwrap();
+        assert!(description.starts_with("<b>Croatian → Spanish</b><br>300 words on 600 cards."));
+        assert!(description.contains("about 20 new cards a day"));
+        assert!(description.contains("every card in 30 days"));
+        assert!(description.contains("<code>hr-to-en</code> – which way the card asks"));
+        assert!(description.contains("<code>gender:…</code>"));
+        assert!(!description.contains("section:"));
+        assert!(description.contains("FrequencyWords by Hermit Dave (CC-BY-SA-4.0)"));
+
+        // Written in the learner's language
+        let description = render_description(None, &values("es")).unwrap();
+        assert!(description.contains("300 palabras en 600 tarjetas"));
+        assert!(description.contains("<b>Etiquetas</b>"));
+    }
+
+    #[test]
+    fn test_custom_template() {
+        let mut values = values("en");
+        values.attributions.clear();
+        assert_eq!(
+            render_description(Some("{pair}: {new_per_day}/day{sources}"), &values).unwrap(),
+            "Croatian → Spanish: 20/day"
+        );
+        assert!(render_description(Some("{target} {level}"), &values).is_err());
+    }
+
+    #[test]
+    fn test_suggested_new_per_day() {
+        assert_eq!(suggested_new_per_day(40), 5);
+        assert_eq!(suggested_new_per_day(300), 10);
+        assert_eq!(suggested_new_per_day(5000), 20);
+    }
+}
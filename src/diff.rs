@@ -0,0 +1,149 @@
+use crate::fix::AmendableNote;
+use crate::pipeline::TranslatedWord;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+
+/// A word only one side of a diff has
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DiffEntry {
+    pub word: String,
+    pub pos: String,
+    pub translation: String,
+    /// Note holding the word, for words already in the deck
+    pub note_id: Option<i64>,
+}
+
+/// A word both sides have, translated differently
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChangedEntry {
+    pub word: String,
+    pub note_id: i64,
+    pub old_translation: String,
+    pub new_translation: String,
+}
+
+/// What building a word list into a deck would change. Notes are matched by
+/// their word; reversed notes follow the note they were made with
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct DeckDiff {
+    /// Words of the list the deck doesn't have
+    pub added: Vec<DiffEntry>,
+    /// Words of the deck the list doesn't have
+    pub removed: Vec<DiffEntry>,
+    pub changed: Vec<ChangedEntry>,
+    pub unchanged: usize,
+}
+
+impl DeckDiff {
+    /// Compare a generated word list with the notes of a deck, read with
+    /// [`AmendableNote::from_info`]
+    pub fn between(generated: &[TranslatedWord], notes: &[AmendableNote]) -> Self {
+        // One note per word, the forward one when a deck has both directions
+        let mut existing: BTreeMap<&str, &AmendableNote> = BTreeMap::new();
+        for note in notes {
+            match existing.get(note.word.as_str()) {
+                Some(kept) if !kept.reversed || note.reversed => {}
+                _ => {
+                    existing.insert(&note.word, note);
+                }
+            }
+        }
+
+        let mut diff = Self::default();
+        let mut seen = HashSet::new();
+        for translated in generated {
+            let word = &translated.word.text;
+            if !seen.insert(word.as_str()) {
+                continue;
+            }
+            let translation = translated.translation.replace("<br>", " / ");
+            match existing.get(word.as_str()) {
+                None => diff.added.push(DiffEntry {
+                    word: word.clone(),
+                    pos: translated.word.pos.name().to_string(),
+                    translation,
+                    note_id: None,
+                }),
+                Some(note) if note.translation != translation => diff.changed.push(ChangedEntry {
+                    word: word.clone(),
+                    note_id: note.note_id,
+                    old_translation: note.translation.clone(),
+                    new_translation: translation,
+                }),
+                Some(_) => diff.unchanged += 1,
+            }
+        }
+        diff.removed = existing
+            .values()
+            .filter(|note| !seen.contains(note.word.as_str()))
+            .map(|note| DiffEntry {
+                word: note.word.clone(),
+                pos: note.pos.name().to_string(),
+                translation: note.translation.clone(),
+                note_id: Some(note.note_id),
+            })
+            .collect();
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ankiweb::{AnkiBackend, CardBuilder, MockAnki};
+    use crate::language::{PartOfSpeech, Word};
+
+    fn translated(word: &str, translation: &str) -> TranslatedWord {
+        TranslatedWord {
+            word: Word::new(word.to_string(), PartOfSpeech::Noun, 1),
+            translation: translation.to_string(),
+            issue: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_deck_diff() {
+        let anki = MockAnki::new();
+        anki.create_deck("Deck").await.unwrap();
+        let builder = CardBuilder::new("Deck", "hr", "es");
+        for (word, translation) in [("dan", "día"), ("kuća", "casa"), ("voda", "agua")] {
+            for note in builder.build(word, translation, &PartOfSpeech::Noun, 1) {
+                anki.add_note(&note).await.unwrap();
+            }
+        }
+        let ids = anki.find_notes("deck:Deck").await.unwrap();
+        let notes: Vec<_> = anki
+            .notes_info(&ids)
+            .await
+            .unwrap()
+            .iter()
+            .filter_map(|info| AmendableNote::from_info(info, "hr"))
+            .collect();
+        assert_eq!(notes.len(), 6);
+
+        let generated = vec![
+            translated("dan", "día"),
+            translated("kuća", "hogar"),
+            translated("noć", "noche"),
+        ];
+        let diff = DeckDiff::between(&generated, &notes);
+        assert_eq!(diff.unchanged, 1);
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].word, "noć");
+        assert_eq!(diff.added[0].note_id, None);
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].old_translation, "casa");
+        assert_eq!(diff.changed[0].new_translation, "hogar");
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].word, "voda");
+        assert!(!diff.is_empty());
+
+        assert!(DeckDiff::between(&generated[..1], &notes[..0])
+            .removed
+            .is_empty());
+    }
+}
@@ -0,0 +1,436 @@
+use crate::ankiweb::{AnkiBackend, CardBuilder, NoteMapping, NoteModel};
+use crate::config::{Config, ServiceSettings};
+use crate::error::{AnkiDeckBuilderError, Result};
+use crate::health::{check_http, check_translator, timed, ServiceCheck};
+use crate::language::{
+    FrequencySourceKind, Language, LibreTranslateClient, ListSize, MyMemoryClient, PartOfSpeech,
+};
+use serde::Serialize;
+use std::future::Future;
+use std::path::Path;
+use std::pin::Pin;
+
+/// Oldest AnkiConnect API version the client speaks
+pub const MIN_ANKICONNECT_VERSION: u32 = 6;
+
+/// File written and removed again to prove a directory is writable
+const WRITE_PROBE_FILE: &str = ".doctor-write-test";
+
+/// Outcome of one diagnostic check, with what to do about a failure
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnosis {
+    pub check: String,
+    pub ok: bool,
+    pub detail: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remediation: Option<String>,
+}
+
+impl Diagnosis {
+    fn pass(check: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self {
+            check: check.into(),
+            ok: true,
+            detail: detail.into(),
+            remediation: None,
+        }
+    }
+
+    fn fail(
+        check: impl Into<String>,
+        detail: impl Into<String>,
+        remediation: impl Into<String>,
+    ) -> Self {
+        Self {
+            check: check.into(),
+            ok: false,
+            detail: detail.into(),
+            remediation: Some(remediation.into()),
+        }
+    }
+}
+
+/// Check AnkiConnect's version, the note types the deck builder creates, and
+/// the configured note type, if any. Stops after the connection check when
+/// Anki can't be reached
+pub async fn diagnose_anki(anki: &dyn AnkiBackend, config: &Config) -> Vec<Diagnosis> {
+    let mut diagnoses = Vec::new();
+
+    let version = match anki.verify_connection().await {
+        Ok(()) => anki.version().await,
+        Err(e) => Err(e),
+    };
+    match version {
+        Ok(version) if version >= MIN_ANKICONNECT_VERSION => {
+            diagnoses.push(Diagnosis::pass(
+                "AnkiConnect",
+                format!("version {} at {}", version, config.ankiconnect_url),
+            ));
+        }
+        Ok(version) => {
+            diagnoses.push(Diagnosis::fail(
+                "AnkiConnect",
+                format!(
+                    "version {} is too old, version {} or newer is needed",
+                    version, MIN_ANKICONNECT_VERSION
+                ),
+                "Update the add-on from Anki's Tools → Add-ons → Check for Updates, then restart Anki",
+            ));
+            return diagnoses;
+        }
+        Err(e) => {
+            diagnoses.push(Diagnosis::fail(
+                "AnkiConnect",
+                e.to_string(),
+                format!(
+                    "Start Anki with the AnkiConnect add-on installed (code 2055492159), \
+                     or point ANKICONNECT_URL at it (currently {})",
+                    config.ankiconnect_url
+                ),
+            ));
+            return diagnoses;
+        }
+    }
+
+    let model_names = match anki.get_model_names().await {
+        Ok(names) => names,
+        Err(e) => {
+            diagnoses.push(Diagnosis::fail(
+                "Note types",
+                e.to_string(),
+                "Restart Anki and run doctor again",
+            ));
+            return diagnoses;
+        }
+    };
+
+    // Note types are only created when missing, so one with the same name
+    // but different fields would break adding notes
+    let mut missing = 0;
+    for model in NoteModel::builtin() {
+        if !model_names.contains(&model.name) {
+            missing += 1;
+            continue;
+        }
+        diagnoses.push(match check_model_fields(anki, &model).await {
+            Ok(()) => Diagnosis::pass(
+                format!("Note type '{}'", model.name),
+                "has the expected fields",
+            ),
+            Err(e) => Diagnosis::fail(
+                format!("Note type '{}'", model.name),
+                e.to_string(),
+                "Rename that note type in Anki (Tools → Manage Note Types) so a correct one can be created",
+            ),
+        });
+    }
+    if missing > 0 {
+        diagnoses.push(Diagnosis::pass(
+            "Note types",
+            format!("{} more will be created when first needed", missing),
+        ));
+    }
+
+    if let Some(mapping) = &config.note_model {
+        let card_builder = CardBuilder::new("Deck", "xx", "yy").with_mapping(Some(mapping.clone()));
+        diagnoses.push(
+            match check_note_mapping(anki, mapping, &card_builder).await {
+                Ok(()) => Diagnosis::pass(
+                    format!("Note type '{}'", mapping.model),
+                    "configured note type has every mapped field",
+                ),
+                Err(e) => Diagnosis::fail(
+                    format!("Note type '{}'", mapping.model),
+                    e.to_string(),
+                    format!(
+                        "Fix note_model in {}, or create the note type in Anki",
+                        config.config_file().display()
+                    ),
+                ),
+            },
+        );
+    }
+
+    diagnoses
+}
+
+/// Check that every data directory can be written to
+pub fn diagnose_directories(config: &Config) -> Vec<Diagnosis> {
+    [
+        ("Config directory", config.config_dir.clone()),
+        ("Data directory", config.data_dir().clone()),
+        ("Cache directory", config.cache_dir().clone()),
+        ("Frequency cache", config.frequency_cache_dir()),
+        ("Translation cache", config.translation_cache_dir()),
+        ("Declension cache", config.declension_cache_dir()),
+        ("Review sessions", config.session_dir()),
+        ("Run manifests", config.manifest_dir()),
+    ]
+    .into_iter()
+    .map(|(check, dir)| match check_writable(&dir) {
+        Ok(()) => Diagnosis::pass(check, dir.display().to_string()),
+        Err(e) => Diagnosis::fail(
+            check,
+            format!("{}: {}", dir.display(), e),
+            format!(
+                "Make the directory writable by your user (e.g. `chmod -R u+w {}`) or remove it so it is recreated",
+                dir.display()
+            ),
+        ),
+    })
+    .collect()
+}
+
+type DiagnosisFuture = Pin<Box<dyn Future<Output = Diagnosis> + Send>>;
+
+/// Check every configured translation provider with the chosen language
+/// pair, and the frequency source for the target language, in parallel
+pub async fn diagnose_services(
+    target: &Language,
+    base: &Language,
+    frequency_source: FrequencySourceKind,
+    list_size: ListSize,
+    services: &ServiceSettings,
+) -> Vec<Diagnosis> {
+    let mut checks: Vec<DiagnosisFuture> = Vec::new();
+    let pair = format!("{} → {}", target.code, base.code);
+
+    let (from, to) = (target.code.clone(), base.code.clone());
+    let http = services.http.clone();
+    checks.push(Box::pin(service_diagnosis(
+        timed("Translator (MyMemory)", pair.clone(), async move {
+            check_translator(&MyMemoryClient::new(None, &http)?, &from, &to).await
+        }),
+        "Check your internet connection; MyMemory also limits free requests per day, so retry tomorrow if the quota is used up".to_string(),
+    )));
+
+    if let Ok(url) = std::env::var("LIBRETRANSLATE_URL") {
+        let (from, to) = (target.code.clone(), base.code.clone());
+        let http = services.http.clone();
+        let remediation = format!(
+            "Check that LIBRETRANSLATE_URL ({}) points at a running LibreTranslate server with {} installed, or unset it",
+            url, pair
+        );
+        checks.push(Box::pin(service_diagnosis(
+            timed("Translator (LibreTranslate)", pair, async move {
+                check_translator(&LibreTranslateClient::new(url, None, &http)?, &from, &to).await
+            }),
+            remediation,
+        )));
+    }
+
+    // A merged list needs each of its sources that covers the language
+    let mut kinds = match frequency_source {
+        FrequencySourceKind::Merged => FrequencySourceKind::CORPORA
+            .into_iter()
+            .filter(|kind| kind.build(&services.language_packs).supports(&target.code))
+            .collect(),
+        kind => vec![kind],
+    };
+    if kinds.is_empty() {
+        kinds.push(frequency_source);
+    }
+    for kind in kinds {
+        checks.push(frequency_diagnosis(kind, target, list_size, services));
+    }
+
+    futures::future::join_all(checks).await
+}
+
+/// Check that a frequency source has a list for the language and can be reached
+fn frequency_diagnosis(
+    kind: FrequencySourceKind,
+    target: &Language,
+    list_size: ListSize,
+    services: &ServiceSettings,
+) -> DiagnosisFuture {
+    let packs = &services.language_packs;
+    let source = kind.build(packs);
+    let service = format!("Frequency ({})", source.id());
+    match source.download_url(&target.code, list_size) {
+        Some(url) => {
+            let http = services.http.clone();
+            Box::pin(service_diagnosis(
+                timed(service, url.clone(), async move {
+                    check_http(&url, &http).await
+                }),
+                "Check your internet connection; lists already downloaded are used from the cache"
+                    .to_string(),
+            ))
+        }
+        None => {
+            let alternatives: Vec<_> = FrequencySourceKind::ALL
+                .iter()
+                .filter(|kind| kind.build(packs).supports(&target.code))
+                .map(|kind| format!("--frequency-source {}", kind.name()))
+                .collect();
+            let remediation = if alternatives.is_empty() {
+                format!("No frequency source has a list for {}", target.name)
+            } else {
+                format!("Use {}", alternatives.join(" or "))
+            };
+            Box::pin(std::future::ready(Diagnosis::fail(
+                service,
+                format!("no list for {}", target.name),
+                remediation,
+            )))
+        }
+    }
+}
+
+async fn service_diagnosis(
+    check: impl Future<Output = ServiceCheck>,
+    remediation: String,
+) -> Diagnosis {
+    let check = check.await;
+    let detail = format!("{} ({} ms)", check.target, check.latency_ms);
+    match check.error {
+        None => Diagnosis::pass(check.service, detail),
+        Some(error) => {
+            Diagnosis::fail(check.service, format!("{}: {}", detail, error), remediation)
+        }
+    }
+}
+
+/// Make sure the user's own note type exists and has every field the
+/// generated notes fill
+pub async fn check_note_mapping(
+    anki: &dyn AnkiBackend,
+    mapping: &NoteMapping,
+    card_builder: &CardBuilder,
+) -> Result<()> {
+    if !anki.get_model_names().await?.contains(&mapping.model) {
+        return Err(AnkiDeckBuilderError::ConfigurationError(format!(
+            "Note type not found in Anki: {}",
+            mapping.model
+        )));
+    }
+
+    let model_fields = anki.get_model_field_names(&mapping.model).await?;
+    let sample = card_builder.build("word", "translation", &PartOfSpeech::Noun, 1);
+    let mut missing: Vec<&String> = sample
+        .iter()
+        .flat_map(|note| note.fields.keys())
+        .filter(|field| !model_fields.contains(field))
+        .collect();
+    missing.sort();
+    missing.dedup();
+
+    if !missing.is_empty() {
+        return Err(AnkiDeckBuilderError::ConfigurationError(format!(
+            "Note type '{}' has no field {} (its fields are {}); map them with --field-map",
+            mapping.model,
+            missing
+                .iter()
+                .map(|f| f.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+            model_fields.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+/// Make sure an existing note type has the fields the deck builder fills
+async fn check_model_fields(anki: &dyn AnkiBackend, model: &NoteModel) -> Result<()> {
+    let fields = anki.get_model_field_names(&model.name).await?;
+    let missing: Vec<&str> = model
+        .fields
+        .iter()
+        .filter(|field| !fields.contains(field))
+        .map(String::as_str)
+        .collect();
+
+    if missing.is_empty() {
+        return Ok(());
+    }
+    Err(AnkiDeckBuilderError::ConfigurationError(format!(
+        "missing field {} (its fields are {})",
+        missing.join(", "),
+        fields.join(", ")
+    )))
+}
+
+fn check_writable(dir: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let probe = dir.join(WRITE_PROBE_FILE);
+    std::fs::write(&probe, b"ok")?;
+    std::fs::remove_file(&probe)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ankiweb::MockAnki;
+    use tempfile::TempDir;
+
+    fn config(dir: &Path, note_model: Option<NoteMapping>) -> Config {
+        Config {
+            ankiconnect_url: "http://localhost:9999".to_string(),
+            config_dir: dir.to_path_buf(),
+            data_dir: dir.to_path_buf(),
+            cache_dir: dir.to_path_buf(),
+            note_model,
+            profiles: Default::default(),
+            http: Default::default(),
+            ankiconnect: Default::default(),
+            deck_name_pattern: None,
+            cache_max_age: None,
+            deck_description: None,
+            forvo_api_key: None,
+            llm: Default::default(),
+            confirm_above: Default::default(),
+            language_packs: Default::default(),
+            read_only: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_diagnose_anki() {
+        let temp_dir = TempDir::new().unwrap();
+        let anki = MockAnki::new();
+        let mut basic_reversed = NoteModel::basic_reversed().with_image();
+        basic_reversed.fields = vec!["Front".to_string(), "Back".to_string()];
+        anki.create_model(&basic_reversed).await.unwrap();
+
+        let mapping = NoteMapping::new("Vocab");
+        let diagnoses = diagnose_anki(&anki, &config(temp_dir.path(), Some(mapping))).await;
+        let failed: Vec<_> = diagnoses.iter().filter(|d| !d.ok).collect();
+
+        assert!(diagnoses[0].ok);
+        assert_eq!(failed.len(), 2);
+        assert_eq!(
+            failed[0].check,
+            format!("Note type '{}'", basic_reversed.name)
+        );
+        assert!(failed[0].detail.contains("missing field Image"));
+        assert_eq!(failed[1].check, "Note type 'Vocab'");
+        assert!(failed.iter().all(|d| d.remediation.is_some()));
+    }
+
+    #[tokio::test]
+    async fn test_diagnose_old_ankiconnect() {
+        let temp_dir = TempDir::new().unwrap();
+        let anki = MockAnki::new();
+        anki.set_version(5);
+
+        let diagnoses = diagnose_anki(&anki, &config(temp_dir.path(), None)).await;
+
+        assert_eq!(diagnoses.len(), 1);
+        assert!(!diagnoses[0].ok);
+        assert!(diagnoses[0].detail.contains("version 5"));
+    }
+
+    #[test]
+    fn test_diagnose_directories() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = config(temp_dir.path(), None);
+
+        let diagnoses = diagnose_directories(&config);
+
+        assert_eq!(diagnoses.len(), 8);
+        assert!(diagnoses.iter().all(|d| d.ok));
+        assert!(config.session_dir().is_dir());
+        assert!(!config.session_dir().join(WRITE_PROBE_FILE).exists());
+    }
+}
@@ -0,0 +1,662 @@
+//! Fields looked up for each word beyond its translation: pictures,
+//! recordings, dictionary forms. Each kind is an [`Enricher`], and a build
+//! runs them through an [`EnrichmentPipeline`], so a new kind plugs in
+//! without changing the front ends
+
+use crate::ankiweb::models::{
+    AUDIO_FIELD, DECLENSION_FIELD, ETYMOLOGY_FIELD, EXAMPLE_FIELD, HINT_FIELD, IMAGE_FIELD,
+    OTHER_MEANINGS_FIELD, SOURCE_FIELD,
+};
+use crate::ankiweb::renderer::escape;
+use crate::ankiweb::Note;
+use crate::audio::AudioFetcher;
+use crate::error::{AnkiDeckBuilderError, Result};
+use crate::images::ImageClient;
+use crate::language::{LlmClient, MnemonicFinder, PartOfSpeech, WiktionaryClient, Word};
+use crate::sink::DeckSink;
+use async_trait::async_trait;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use tokio::sync::Semaphore;
+
+/// Field values to set on a word's notes, by field name
+pub type Fields = BTreeMap<String, String>;
+
+/// Names of the built-in enrichers, in the order they run by default
+pub const ENRICHER_NAMES: &[&str] = &[
+    "images",
+    "declensions",
+    "other_meanings",
+    "audio",
+    "mnemonics",
+    "etymology",
+    "examples",
+];
+
+/// Lookups one enricher runs at the same time, unless told otherwise
+const DEFAULT_ENRICHER_CONCURRENCY: usize = 4;
+
+/// The word an enricher looks up
+#[derive(Debug, Clone, Copy)]
+pub struct EnrichInput<'a> {
+    pub language: &'a str,
+    pub word: &'a Word,
+    pub translation: &'a str,
+    /// What the enrichers before this one filled in
+    pub fields: &'a Fields,
+}
+
+/// One kind of extra content for cards
+#[async_trait]
+pub trait Enricher: Send + Sync {
+    /// Identifies the enricher in orderings and summaries ("images")
+    fn name(&self) -> &'static str;
+
+    /// Whether the enricher has anything for a word, checked before any lookup
+    fn applies_to(&self, _word: &Word) -> bool {
+        true
+    }
+
+    /// Look up `words` ahead of `enrich` asking for them one by one, for
+    /// enrichers whose source answers many words per request. The others
+    /// have nothing to do ahead
+    async fn prepare(&self, _words: &[Word], _language: &str) -> Result<()> {
+        Ok(())
+    }
+
+    /// Field values for a word, empty when nothing was found. Media files
+    /// are stored through `sink`
+    async fn enrich(&self, input: EnrichInput<'_>, sink: &dyn DeckSink) -> Result<Fields>;
+}
+
+/// What the pipeline found for a word
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Enrichment {
+    pub fields: Fields,
+    /// Enrichers that filled at least one field, in the order they ran
+    pub filled: Vec<&'static str>,
+}
+
+impl Enrichment {
+    /// Set the fields on a note
+    pub fn apply(&self, note: &mut Note) {
+        for (name, value) in &self.fields {
+            note.fields.insert(name.clone(), value.clone());
+        }
+    }
+}
+
+/// Word, part of speech, and translation an enrichment was made for
+type CacheKey = (String, String, String, String);
+
+struct Stage {
+    enricher: Box<dyn Enricher>,
+    permits: Semaphore,
+    cache: Mutex<HashMap<CacheKey, Fields>>,
+}
+
+impl Stage {
+    fn new(enricher: Box<dyn Enricher>) -> Self {
+        Self {
+            enricher,
+            permits: Semaphore::new(DEFAULT_ENRICHER_CONCURRENCY),
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+/// Enrichers run one after another for each word. Each one remembers what it
+/// found, so a word seen again isn't looked up twice, and limits how many
+/// lookups it runs at once when words are enriched concurrently
+#[derive(Default)]
+pub struct EnrichmentPipeline {
+    stages: Vec<Stage>,
+}
+
+impl EnrichmentPipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run an enricher after the ones already added
+    pub fn with(mut self, enricher: impl Enricher + 'static) -> Self {
+        self.stages.push(Stage::new(Box::new(enricher)));
+        self
+    }
+
+    /// Use this enricher in place of the one with the same name, keeping its
+    /// place. Added at the end when there is none
+    pub fn with_replaced(mut self, enricher: impl Enricher + 'static) -> Self {
+        let stage = Stage::new(Box::new(enricher));
+        match self
+            .stages
+            .iter()
+            .position(|s| s.enricher.name() == stage.enricher.name())
+        {
+            Some(i) => self.stages[i] = stage,
+            None => self.stages.push(stage),
+        }
+        self
+    }
+
+    /// Let an enricher run up to `concurrency` lookups at the same time
+    pub fn with_concurrency(mut self, name: &str, concurrency: usize) -> Self {
+        if let Some(stage) = self.stages.iter_mut().find(|s| s.enricher.name() == name) {
+            stage.permits = Semaphore::new(concurrency.max(1));
+        }
+        self
+    }
+
+    /// Run the named enrichers first, in this order; the others follow in
+    /// the order they were added
+    pub fn with_order(mut self, names: &[String]) -> Self {
+        self.stages.sort_by_key(|stage| {
+            names
+                .iter()
+                .position(|name| name == stage.enricher.name())
+                .unwrap_or(names.len())
+        });
+        self
+    }
+
+    /// Names of the enrichers, in the order they run
+    pub fn names(&self) -> Vec<&'static str> {
+        self.stages.iter().map(|s| s.enricher.name()).collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    /// The words of `words` each enricher would look up, by name
+    pub fn lookups<'w>(&self, words: &'w [Word]) -> Vec<(&'static str, Vec<&'w Word>)> {
+        self.stages
+            .iter()
+            .map(|stage| {
+                let enricher = &stage.enricher;
+                let words = words.iter().filter(|w| enricher.applies_to(w)).collect();
+                (enricher.name(), words)
+            })
+            .collect()
+    }
+
+    /// Let enrichers that look up many words at once do so for `words`
+    /// before they are enriched one by one. A failure only means the words
+    /// are looked up one by one
+    pub async fn prepare(&self, words: &[Word], language: &str) {
+        for stage in &self.stages {
+            let enricher = &stage.enricher;
+            let words: Vec<Word> = words
+                .iter()
+                .filter(|word| enricher.applies_to(word))
+                .cloned()
+                .collect();
+            if let Err(e) = enricher.prepare(&words, language).await {
+                tracing::warn!(
+                    "Could not look up {} ahead, asking word by word: {}",
+                    enricher.name(),
+                    e
+                );
+            }
+        }
+    }
+
+    /// Run every enricher that applies to the word. One that fails is
+    /// logged and the rest still run
+    pub async fn enrich(&self, input: EnrichInput<'_>, sink: &dyn DeckSink) -> Enrichment {
+        let key = (
+            input.language.to_string(),
+            input.word.text.clone(),
+            input.word.pos.name().to_string(),
+            input.translation.to_string(),
+        );
+        let mut enrichment = Enrichment::default();
+
+        for stage in &self.stages {
+            let enricher = &stage.enricher;
+            if !enricher.applies_to(input.word) {
+                continue;
+            }
+
+            let cached = stage.cache.lock().unwrap().get(&key).cloned();
+            let fields = match cached {
+                Some(fields) => fields,
+                None => {
+                    let _permit = stage
+                        .permits
+                        .acquire()
+                        .await
+                        .expect("semaphore is never closed");
+                    let stage_input = EnrichInput {
+                        fields: &enrichment.fields,
+                        ..input
+                    };
+                    match enricher.enrich(stage_input, sink).await {
+                        Ok(fields) => {
+                            stage
+                                .cache
+                                .lock()
+                                .unwrap()
+                                .insert(key.clone(), fields.clone());
+                            fields
+                        }
+                        Err(e) => {
+                            tracing::warn!(
+                                "No {} for '{}': {}",
+                                enricher.name(),
+                                input.word.text,
+                                e
+                            );
+                            continue;
+                        }
+                    }
+                }
+            };
+
+            if !fields.is_empty() {
+                enrichment.filled.push(enricher.name());
+                enrichment.fields.extend(fields);
+            }
+        }
+        enrichment
+    }
+}
+
+/// A picture for nouns from Openverse, searched by the translation
+pub struct ImageEnricher {
+    client: ImageClient,
+}
+
+impl ImageEnricher {
+    pub fn new(client: ImageClient) -> Self {
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl Enricher for ImageEnricher {
+    fn name(&self) -> &'static str {
+        "images"
+    }
+
+    fn applies_to(&self, word: &Word) -> bool {
+        word.pos == PartOfSpeech::Noun
+    }
+
+    async fn enrich(&self, input: EnrichInput<'_>, sink: &dyn DeckSink) -> Result<Fields> {
+        let Some(image) = self
+            .client
+            .search(input.translation, 1)
+            .await?
+            .into_iter()
+            .next()
+        else {
+            return Ok(Fields::new());
+        };
+        let data = self.client.download(&image).await?;
+        let filename = sink
+            .store_media_file(&format!("{}.jpg", input.word.text), &data)
+            .await?;
+        Ok(Fields::from([(
+            IMAGE_FIELD.to_string(),
+            image.field_html(&filename),
+        )]))
+    }
+}
+
+/// Genitive singular and nominative plural of nouns, from Wiktionary
+pub struct DeclensionEnricher {
+    wiktionary: WiktionaryClient,
+}
+
+impl DeclensionEnricher {
+    pub fn new(wiktionary: WiktionaryClient) -> Self {
+        Self { wiktionary }
+    }
+}
+
+#[async_trait]
+impl Enricher for DeclensionEnricher {
+    fn name(&self) -> &'static str {
+        "declensions"
+    }
+
+    fn applies_to(&self, word: &Word) -> bool {
+        word.pos == PartOfSpeech::Noun
+    }
+
+    async fn enrich(&self, input: EnrichInput<'_>, _sink: &dyn DeckSink) -> Result<Fields> {
+        let declension = self
+            .wiktionary
+            .decline(input.language, &input.word.text)
+            .await?;
+        Ok(declension
+            .map(|declension| (DECLENSION_FIELD.to_string(), declension.to_field()))
+            .into_iter()
+            .collect())
+    }
+}
+
+/// A word's meanings besides its translation, from Wiktionary
+pub struct OtherMeaningsEnricher {
+    wiktionary: WiktionaryClient,
+}
+
+impl OtherMeaningsEnricher {
+    pub fn new(wiktionary: WiktionaryClient) -> Self {
+        Self { wiktionary }
+    }
+}
+
+#[async_trait]
+impl Enricher for OtherMeaningsEnricher {
+    fn name(&self) -> &'static str {
+        "other_meanings"
+    }
+
+    async fn enrich(&self, input: EnrichInput<'_>, _sink: &dyn DeckSink) -> Result<Fields> {
+        let meanings = self
+            .wiktionary
+            .senses(input.language, &input.word.text)
+            .await?
+            .other_meanings(&input.word.pos, input.translation);
+        Ok(Some(meanings)
+            .filter(|meanings| !meanings.is_empty())
+            .map(|meanings| (OTHER_MEANINGS_FIELD.to_string(), meanings))
+            .into_iter()
+            .collect())
+    }
+}
+
+/// A native speaker's recording of the word, credited in the Source field
+pub struct AudioEnricher {
+    fetcher: AudioFetcher,
+}
+
+impl AudioEnricher {
+    pub fn new(fetcher: AudioFetcher) -> Self {
+        Self { fetcher }
+    }
+}
+
+#[async_trait]
+impl Enricher for AudioEnricher {
+    fn name(&self) -> &'static str {
+        "audio"
+    }
+
+    async fn enrich(&self, input: EnrichInput<'_>, sink: &dyn DeckSink) -> Result<Fields> {
+        let Some(pronunciation) = self.fetcher.fetch(input.language, &input.word.text).await?
+        else {
+            return Ok(Fields::new());
+        };
+        let filename = sink
+            .store_media_file(
+                &pronunciation.filename(&input.word.text),
+                &pronunciation.data,
+            )
+            .await?;
+        Ok(Fields::from([
+            (AUDIO_FIELD.to_string(), format!("[sound:{}]", filename)),
+            (
+                SOURCE_FIELD.to_string(),
+                pronunciation.recording.source_html(),
+            ),
+        ]))
+    }
+}
+
+/// A memory hook: a word of the learner's language that sounds like the
+/// word being learned, found without any lookup
+pub struct MnemonicEnricher {
+    finder: MnemonicFinder,
+}
+
+impl MnemonicEnricher {
+    pub fn new(finder: MnemonicFinder) -> Self {
+        Self { finder }
+    }
+}
+
+#[async_trait]
+impl Enricher for MnemonicEnricher {
+    fn name(&self) -> &'static str {
+        "mnemonics"
+    }
+
+    fn applies_to(&self, word: &Word) -> bool {
+        word.pos != PartOfSpeech::Phrase
+    }
+
+    async fn enrich(&self, input: EnrichInput<'_>, _sink: &dyn DeckSink) -> Result<Fields> {
+        Ok(self
+            .finder
+            .memory_hook(input.language, &input.word.text, input.translation)
+            .map(|hook| (HINT_FIELD.to_string(), hook))
+            .into_iter()
+            .collect())
+    }
+}
+
+/// Where the word comes from, or which language it was borrowed from, as
+/// Wiktionary's etymology of it begins
+pub struct EtymologyEnricher {
+    wiktionary: WiktionaryClient,
+}
+
+impl EtymologyEnricher {
+    pub fn new(wiktionary: WiktionaryClient) -> Self {
+        Self { wiktionary }
+    }
+}
+
+#[async_trait]
+impl Enricher for EtymologyEnricher {
+    fn name(&self) -> &'static str {
+        "etymology"
+    }
+
+    fn applies_to(&self, word: &Word) -> bool {
+        word.pos != PartOfSpeech::Phrase
+    }
+
+    async fn enrich(&self, input: EnrichInput<'_>, _sink: &dyn DeckSink) -> Result<Fields> {
+        let etymology = self
+            .wiktionary
+            .etymology(input.language, &input.word.text)
+            .await?;
+        Ok(etymology
+            .map(|etymology| (ETYMOLOGY_FIELD.to_string(), escape(&etymology)))
+            .into_iter()
+            .collect())
+    }
+}
+
+/// A short sentence using the word, with its translation, written by the
+/// LLM for many words per request
+pub struct ExampleEnricher {
+    llm: LlmClient,
+    base_language: String,
+}
+
+impl ExampleEnricher {
+    pub fn new(llm: LlmClient, base_language: &str) -> Self {
+        Self {
+            llm,
+            base_language: base_language.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl Enricher for ExampleEnricher {
+    fn name(&self) -> &'static str {
+        "examples"
+    }
+
+    fn applies_to(&self, word: &Word) -> bool {
+        word.pos != PartOfSpeech::Phrase
+    }
+
+    async fn prepare(&self, words: &[Word], language: &str) -> Result<()> {
+        self.llm
+            .examples(words, language, &self.base_language)
+            .await
+            .map(|_| ())
+    }
+
+    async fn enrich(&self, input: EnrichInput<'_>, _sink: &dyn DeckSink) -> Result<Fields> {
+        let mut examples = self
+            .llm
+            .examples(
+                std::slice::from_ref(input.word),
+                input.language,
+                &self.base_language,
+            )
+            .await?;
+        Ok(examples
+            .remove(&input.word.text)
+            .map(|example| {
+                (
+                    EXAMPLE_FIELD.to_string(),
+                    format!(
+                        "{}<br>{}",
+                        escape(&example.sentence),
+                        escape(&example.translation)
+                    ),
+                )
+            })
+            .into_iter()
+            .collect())
+    }
+}
+
+/// Check enricher names given by the user
+pub fn validate_enricher_names(names: &[String]) -> Result<()> {
+    match names
+        .iter()
+        .find(|name| !ENRICHER_NAMES.contains(&name.as_str()))
+    {
+        Some(name) => Err(AnkiDeckBuilderError::ConfigurationError(format!(
+            "unknown enrichment '{}' (expected one of {})",
+            name,
+            ENRICHER_NAMES.join(", ")
+        ))),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sink::StdoutSink;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// Fills one field, counting its lookups; fails for "error"
+    struct StubEnricher {
+        name: &'static str,
+        lookups: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl Enricher for StubEnricher {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn applies_to(&self, word: &Word) -> bool {
+            word.pos != PartOfSpeech::Phrase
+        }
+
+        async fn enrich(&self, input: EnrichInput<'_>, _sink: &dyn DeckSink) -> Result<Fields> {
+            self.lookups.fetch_add(1, Ordering::SeqCst);
+            if input.word.text == "error" {
+                return Err(AnkiDeckBuilderError::AudioError("down".to_string()));
+            }
+            // Sees what ran before it
+            let before: Vec<_> = input.fields.keys().cloned().collect();
+            Ok(Fields::from([(self.name.to_string(), before.join(","))]))
+        }
+    }
+
+    fn stub(name: &'static str) -> (StubEnricher, Arc<AtomicUsize>) {
+        let lookups = Arc::new(AtomicUsize::new(0));
+        let enricher = StubEnricher {
+            name,
+            lookups: lookups.clone(),
+        };
+        (enricher, lookups)
+    }
+
+    fn input<'a>(word: &'a Word, fields: &'a Fields) -> EnrichInput<'a> {
+        EnrichInput {
+            language: "hr",
+            word,
+            translation: "día",
+            fields,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_order_and_cache() {
+        let (first, first_lookups) = stub("first");
+        let (second, _) = stub("second");
+        let pipeline = EnrichmentPipeline::new()
+            .with(first)
+            .with(second)
+            .with_order(&["second".to_string()])
+            .with_concurrency("first", 1);
+        assert_eq!(pipeline.names(), ["second", "first"]);
+
+        let sink = StdoutSink::default();
+        let word = Word::new("dan".to_string(), PartOfSpeech::Noun, 1);
+        let none = Fields::new();
+        for _ in 0..2 {
+            let enrichment = pipeline.enrich(input(&word, &none), &sink).await;
+            assert_eq!(enrichment.filled, ["second", "first"]);
+            assert_eq!(enrichment.fields["second"], "");
+            assert_eq!(enrichment.fields["first"], "second");
+        }
+        assert_eq!(first_lookups.load(Ordering::SeqCst), 1);
+
+        let mut note = Note::new("Deck".to_string(), "dan".to_string(), "día".to_string());
+        pipeline
+            .enrich(input(&word, &none), &sink)
+            .await
+            .apply(&mut note);
+        assert_eq!(note.fields["first"], "second");
+
+        let phrase = Word::new("dobar dan".to_string(), PartOfSpeech::Phrase, 1);
+        assert!(pipeline
+            .enrich(input(&phrase, &none), &sink)
+            .await
+            .filled
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_failing_enricher_is_skipped() {
+        let (first, _) = stub("first");
+        let (replacement, replacement_lookups) = stub("first");
+        let (second, _) = stub("second");
+        let pipeline = EnrichmentPipeline::new()
+            .with(first)
+            .with(second)
+            .with_replaced(replacement);
+        assert_eq!(pipeline.names(), ["first", "second"]);
+
+        let sink = StdoutSink::default();
+        let word = Word::new("dan".to_string(), PartOfSpeech::Noun, 1);
+        let none = Fields::new();
+        pipeline.enrich(input(&word, &none), &sink).await;
+        assert_eq!(replacement_lookups.load(Ordering::SeqCst), 1);
+
+        let word = Word::new("error".to_string(), PartOfSpeech::Noun, 2);
+        let enrichment = pipeline.enrich(input(&word, &none), &sink).await;
+        assert!(enrichment.filled.is_empty());
+
+        assert!(validate_enricher_names(&["audio".to_string()]).is_ok());
+        assert!(validate_enricher_names(&["ipa".to_string()]).is_err());
+    }
+}
@@ -14,6 +14,20 @@ pub enum AnkiDeckBuilderError {
     #[error("Frequency data not available for language: {0}")]
     FrequencyDataNotFound(String),
 
+    #[error(
+        "Frequency list for '{expected}' looks like '{detected}'; the upstream source may have changed"
+    )]
+    FrequencyLanguageMismatch { expected: String, detected: String },
+
+    #[error("Image search failed: {0}")]
+    ImageSearchError(String),
+
+    #[error("Wiktionary lookup failed: {0}")]
+    WiktionaryError(String),
+
+    #[error("Pronunciation lookup failed: {0}")]
+    AudioError(String),
+
     #[error("Unsupported language: {0}")]
     UnsupportedLanguage(String),
 
@@ -23,6 +37,24 @@ pub enum AnkiDeckBuilderError {
     #[error("Invalid configuration: {0}")]
     ConfigurationError(String),
 
+    #[error("Missing required input in non-interactive mode: {0}")]
+    MissingInput(String),
+
+    #[error("Refused to send {0} to Anki in read-only mode")]
+    ReadOnly(String),
+
+    #[error("Run deadline exceeded: {0}")]
+    DeadlineExceeded(String),
+
+    #[error("Cancelled: {0}")]
+    Cancelled(String),
+
+    #[error("Unknown command '{0}' (no anki-deck-builder-{0} found on PATH)")]
+    UnknownCommand(String),
+
+    #[error("External command '{name}' exited with status {code}")]
+    ExternalCommandFailed { name: String, code: i32 },
+
     #[error("HTTP request failed: {0}")]
     HttpError(#[from] reqwest::Error),
 
@@ -31,6 +63,29 @@ pub enum AnkiDeckBuilderError {
 
     #[error("JSON parsing error: {0}")]
     JsonError(#[from] serde_json::Error),
+
+    #[error("Prompt failed: {0}")]
+    PromptError(#[from] dialoguer::Error),
+}
+
+impl AnkiDeckBuilderError {
+    /// Process exit code for this error: 2 for bad or missing input,
+    /// 3 when AnkiConnect is unreachable, 4 when the run deadline hit,
+    /// 130 when cancelled with Ctrl-C, an external command's own status when
+    /// it fails, 1 otherwise
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::MissingInput(_)
+            | Self::UnsupportedLanguage(_)
+            | Self::ConfigurationError(_)
+            | Self::UnknownCommand(_) => 2,
+            Self::AnkiConnectNotRunning { .. } => 3,
+            Self::DeadlineExceeded(_) => 4,
+            Self::Cancelled(_) => crate::cancel::CANCELLED_EXIT_CODE,
+            Self::ExternalCommandFailed { code, .. } => *code,
+            _ => 1,
+        }
+    }
 }
 
 pub type Result<T> = std::result::Result<T, AnkiDeckBuilderError>;
@@ -0,0 +1,286 @@
+//! What a build asks of the outside world, worked out before it starts: how
+//! many requests go out, how long they take at each provider's pace, how
+//! many translations the cache already has, and what a paid provider charges
+
+use crate::cache_file;
+use crate::enrich::EnrichmentPipeline;
+use crate::language::google_translate::MIN_REQUEST_INTERVAL;
+use crate::language::llm::LlmSettings;
+use crate::language::{normalize_text, TranslatorKind, Word};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Typical time of one request to a free translator or lookup service
+const REQUEST_SECONDS: f64 = 0.5;
+
+/// Typical time of one batch of words sent to the LLM
+const LLM_BATCH_SECONDS: f64 = 10.0;
+
+/// Estimates above which `create` asks before starting, the `confirm_above`
+/// section of config.json. One set to null never asks
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfirmThresholds {
+    pub requests: Option<usize>,
+    pub minutes: Option<u64>,
+    /// US dollars, for paid providers
+    pub dollars: Option<f64>,
+}
+
+impl Default for ConfirmThresholds {
+    fn default() -> Self {
+        Self {
+            requests: Some(5000),
+            minutes: Some(30),
+            dollars: Some(1.0),
+        }
+    }
+}
+
+/// What a build is about to do, for estimating it
+pub struct RunPlan<'a> {
+    pub cache_dir: &'a Path,
+    /// Words to translate, without those whose translation is given
+    pub words: &'a [Word],
+    pub from: &'a str,
+    /// Base languages; the first is the one example sentences are translated into
+    pub to: &'a [String],
+    pub translator: TranslatorKind,
+    pub concurrency: usize,
+    pub pipeline: &'a EnrichmentPipeline,
+    pub llm: &'a LlmSettings,
+}
+
+/// Requests, time, cache hits and cost of a build. Lookups beyond the
+/// translation are counted as if none were cached, so they are upper bounds
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RunEstimate {
+    pub translation_requests: usize,
+    /// Requests of the enrichments: pictures, recordings, dictionary lookups
+    pub lookup_requests: usize,
+    /// Translations the build needs, one per word and base language
+    pub translations: usize,
+    pub cached_translations: usize,
+    pub seconds: u64,
+    /// In US dollars, when a paid provider is asked
+    pub dollars: Option<f64>,
+}
+
+impl RunEstimate {
+    pub fn requests(&self) -> usize {
+        self.translation_requests + self.lookup_requests
+    }
+
+    pub fn minutes(&self) -> u64 {
+        self.seconds.div_ceil(60)
+    }
+
+    /// Share of the translations served from the cache, 1 when none are needed
+    pub fn cache_hit_ratio(&self) -> f64 {
+        match self.translations {
+            0 => 1.0,
+            total => self.cached_translations as f64 / total as f64,
+        }
+    }
+
+    /// The estimates that go over `thresholds`, described for a prompt
+    pub fn exceeded(&self, thresholds: &ConfirmThresholds) -> Vec<String> {
+        let mut exceeded = Vec::new();
+        if let Some(max) = thresholds.requests.filter(|max| self.requests() > *max) {
+            exceeded.push(format!("~{} requests (over {})", self.requests(), max));
+        }
+        if let Some(max) = thresholds.minutes.filter(|max| self.minutes() > *max) {
+            exceeded.push(format!("about {} minutes (over {})", self.minutes(), max));
+        }
+        if let (Some(dollars), Some(max)) = (self.dollars, thresholds.dollars) {
+            if dollars > max {
+                exceeded.push(format!("~${:.2} (over ${:.2})", dollars, max));
+            }
+        }
+        exceeded
+    }
+}
+
+impl RunPlan<'_> {
+    pub fn estimate(&self) -> RunEstimate {
+        let mut estimate = RunEstimate::default();
+        let mut seconds = 0.0;
+        let mut dollars = None;
+
+        for to in self.to {
+            let cached = self.cached_keys(&self.translation_cache(to));
+            let uncached = self
+                .words
+                .iter()
+                .filter(|word| !cached.contains_key(&normalize_text(&word.text)))
+                .count();
+            estimate.translations += self.words.len();
+            estimate.cached_translations += self.words.len() - uncached;
+
+            match self.translator {
+                TranslatorKind::Mymemory => {
+                    estimate.translation_requests += uncached;
+                    seconds += uncached as f64 * REQUEST_SECONDS / self.concurrency.max(1) as f64;
+                }
+                TranslatorKind::Google => {
+                    estimate.translation_requests += uncached;
+                    seconds += uncached as f64 * MIN_REQUEST_INTERVAL.as_secs_f64();
+                }
+                TranslatorKind::Llm => {
+                    let cost = self.llm.estimate(uncached, 1, false);
+                    estimate.translation_requests += cost.requests;
+                    seconds += cost.requests as f64 * LLM_BATCH_SECONDS;
+                    *dollars.get_or_insert(0.0) += cost.dollars;
+                }
+            }
+        }
+
+        for (name, words) in self.pipeline.lookups(self.words) {
+            match name {
+                "examples" => {
+                    let base = self.to.first().map_or("en", String::as_str);
+                    let cached = self.cached_keys(
+                        &self
+                            .cache_dir
+                            .join(format!("examples/llm/{}_{}.json", self.from, base)),
+                    );
+                    let uncached = words
+                        .iter()
+                        .filter(|word| !cached.contains_key(&word.text))
+                        .count();
+                    let cost = self.llm.estimate(uncached, 0, true);
+                    estimate.lookup_requests += cost.requests;
+                    seconds += cost.requests as f64 * LLM_BATCH_SECONDS;
+                    *dollars.get_or_insert(0.0) += cost.dollars;
+                }
+                // Found in a word list that comes with the program
+                "mnemonics" => {}
+                name => {
+                    // A search, then the file found
+                    let per_word = match name {
+                        "images" | "audio" => 2,
+                        _ => 1,
+                    };
+                    estimate.lookup_requests += words.len() * per_word;
+                    // Words are enriched one at a time
+                    seconds += (words.len() * per_word) as f64 * REQUEST_SECONDS;
+                }
+            }
+        }
+
+        estimate.seconds = seconds.ceil() as u64;
+        estimate.dollars = dollars;
+        estimate
+    }
+
+    /// Where the chosen translator caches translations into `to`
+    fn translation_cache(&self, to: &str) -> PathBuf {
+        let dir = self.cache_dir.join("translations");
+        let dir = match self.translator {
+            TranslatorKind::Mymemory => dir,
+            TranslatorKind::Google => dir.join("google"),
+            TranslatorKind::Llm => dir.join("llm"),
+        };
+        dir.join(format!("{}_{}.json", self.from, to))
+    }
+
+    fn cached_keys(&self, file: &Path) -> HashMap<String, serde_json::Value> {
+        cache_file::read_map(file).unwrap_or_default()
+    }
+}
+
+/// "under a minute", "about 12 min", "about 2 h 5 min"
+pub fn describe_duration(seconds: u64) -> String {
+    match seconds.div_ceil(60) {
+        _ if seconds < 60 => "under a minute".to_string(),
+        minutes if minutes < 60 => format!("about {} min", minutes),
+        minutes => format!("about {} h {} min", minutes / 60, minutes % 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::language::PartOfSpeech;
+
+    fn words(texts: &[&str]) -> Vec<Word> {
+        texts
+            .iter()
+            .enumerate()
+            .map(|(rank, text)| Word::new(text.to_string(), PartOfSpeech::Noun, rank + 1))
+            .collect()
+    }
+
+    #[test]
+    fn test_estimate_counts_cached_translations() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let translations = temp_dir.path().join("translations");
+        std::fs::create_dir_all(&translations).unwrap();
+        std::fs::write(translations.join("hr_en.json"), r#"{"kuća": "house"}"#).unwrap();
+
+        let words = words(&["kuća", "dan", "pas", "voda"]);
+        let to = vec!["en".to_string()];
+        let pipeline = EnrichmentPipeline::new();
+        let llm = LlmSettings::default();
+        let mut plan = RunPlan {
+            cache_dir: temp_dir.path(),
+            words: &words,
+            from: "hr",
+            to: &to,
+            translator: TranslatorKind::Mymemory,
+            concurrency: 3,
+            pipeline: &pipeline,
+            llm: &llm,
+        };
+
+        let estimate = plan.estimate();
+        assert_eq!(estimate.translation_requests, 3);
+        assert_eq!(estimate.cache_hit_ratio(), 0.25);
+        assert_eq!(estimate.dollars, None);
+
+        // Google is asked a second apart, whatever the concurrency
+        plan.translator = TranslatorKind::Google;
+        let estimate = plan.estimate();
+        assert_eq!(estimate.cached_translations, 0);
+        assert_eq!(estimate.seconds, 4);
+
+        // The LLM takes the words in one batch, and is paid for
+        plan.translator = TranslatorKind::Llm;
+        let estimate = plan.estimate();
+        assert_eq!(estimate.translation_requests, 1);
+        assert!(estimate.dollars.unwrap() > 0.0);
+    }
+
+    #[test]
+    fn test_exceeded() {
+        let estimate = RunEstimate {
+            translation_requests: 900,
+            lookup_requests: 200,
+            seconds: 45 * 60,
+            dollars: Some(0.4),
+            ..RunEstimate::default()
+        };
+        let thresholds = ConfirmThresholds {
+            requests: Some(1000),
+            ..ConfirmThresholds::default()
+        };
+        assert_eq!(
+            estimate.exceeded(&thresholds),
+            vec!["~1100 requests (over 1000)", "about 45 minutes (over 30)"]
+        );
+        let unlimited = ConfirmThresholds {
+            requests: None,
+            minutes: None,
+            dollars: None,
+        };
+        assert!(estimate.exceeded(&unlimited).is_empty());
+    }
+
+    #[test]
+    fn test_describe_duration() {
+        assert_eq!(describe_duration(20), "under a minute");
+        assert_eq!(describe_duration(90), "about 2 min");
+        assert_eq!(describe_duration(2 * 3600 + 300), "about 2 h 5 min");
+    }
+}
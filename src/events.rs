@@ -0,0 +1,152 @@
+use crate::language::PartOfSpeech;
+use crate::session::ReviewDecision;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+/// Step of a deck build
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum BuildPhase {
+    LoadingWords,
+    PreparingDeck,
+    Translating,
+    Finished,
+}
+
+impl BuildPhase {
+    pub const ALL: [Self; 4] = [
+        Self::LoadingWords,
+        Self::PreparingDeck,
+        Self::Translating,
+        Self::Finished,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::LoadingWords => "Load words",
+            Self::PreparingDeck => "Prepare deck",
+            Self::Translating => "Translate & add notes",
+            Self::Finished => "Done",
+        }
+    }
+}
+
+/// How a build ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BuildOutcome {
+    Completed,
+    DeadlineReached,
+    Cancelled,
+    Failed,
+}
+
+impl BuildOutcome {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Completed => "completed",
+            Self::DeadlineReached => "deadline reached",
+            Self::Cancelled => "cancelled",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+/// Totals of a finished build
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BuildSummary {
+    pub outcome: BuildOutcome,
+    pub deck_name: String,
+    pub words: usize,
+    pub total_words: usize,
+    pub notes_added: usize,
+    pub cards_added: usize,
+    pub duplicates_skipped: usize,
+    pub cognates_skipped: usize,
+    pub notes_failed: usize,
+    pub flagged: usize,
+    /// Translations replaced by the sense of their part of speech
+    #[serde(default)]
+    pub disambiguated: usize,
+    /// Notes added with fields filled by each enricher
+    #[serde(default)]
+    pub enriched: BTreeMap<String, usize>,
+}
+
+/// What a reviewer chose for one translation
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReviewReply {
+    Decide(ReviewDecision),
+    /// Keep this translation and every one still to come
+    KeepRest,
+}
+
+/// A translation waiting for the user to keep, edit, or skip it
+#[derive(Debug)]
+pub struct ReviewRequest {
+    /// 1-based position of the word in the build
+    pub position: usize,
+    pub total: usize,
+    pub word: String,
+    pub pos: PartOfSpeech,
+    pub translation: String,
+    pub issue: Option<String>,
+    reply: Sender<ReviewReply>,
+}
+
+impl ReviewRequest {
+    /// A request, and where its answer arrives
+    pub fn new(
+        position: usize,
+        total: usize,
+        word: &str,
+        pos: &PartOfSpeech,
+        translation: &str,
+        issue: Option<&str>,
+    ) -> (Self, Receiver<ReviewReply>) {
+        let (reply, answer) = channel();
+        let request = Self {
+            position,
+            total,
+            word: word.to_string(),
+            pos: pos.clone(),
+            translation: translation.to_string(),
+            issue: issue.map(str::to_string),
+            reply,
+        };
+        (request, answer)
+    }
+
+    pub fn answer(self, reply: ReviewReply) {
+        // The build may already have stopped waiting
+        let _ = self.reply.send(reply);
+    }
+}
+
+/// Progress of a deck build, for front ends other than the plain terminal
+/// output
+#[derive(Debug)]
+pub enum BuildEvent {
+    Phase(BuildPhase),
+    /// A line of the human-readable output
+    Message(String),
+    Progress {
+        done: usize,
+        total: usize,
+    },
+    Translated {
+        word: String,
+        pos: PartOfSpeech,
+        translation: String,
+        issue: Option<String>,
+    },
+    NoteAdded {
+        word: String,
+    },
+    NoteFailed {
+        word: String,
+        error: String,
+    },
+    Review(ReviewRequest),
+    Finished(BuildSummary),
+}
@@ -0,0 +1,212 @@
+use crate::config::Config;
+use crate::error::{AnkiDeckBuilderError, Result};
+use std::collections::BTreeSet;
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+
+/// External commands are executables on PATH named with this prefix, so
+/// `anki-deck-builder foo` runs `anki-deck-builder-foo` (like git and cargo)
+pub const EXTERNAL_PREFIX: &str = "anki-deck-builder-";
+
+/// How the main command was invoked, passed on to external commands
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExternalContext {
+    pub json: bool,
+    pub interactive: bool,
+}
+
+/// Find the executable for an external command on PATH
+pub fn find_external_command(name: &str) -> Option<PathBuf> {
+    find_in(&std::env::var_os("PATH")?, name)
+}
+
+/// Names of all external commands on PATH
+pub fn list_external_commands() -> Vec<String> {
+    let Some(path) = std::env::var_os("PATH") else {
+        return Vec::new();
+    };
+
+    let mut names = BTreeSet::new();
+    for dir in std::env::split_paths(&path) {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let file_name = entry.file_name();
+            let Some(name) = file_name
+                .to_str()
+                .and_then(|f| f.strip_prefix(EXTERNAL_PREFIX))
+                .map(|f| f.trim_end_matches(std::env::consts::EXE_SUFFIX))
+            else {
+                continue;
+            };
+            if !name.is_empty() && is_executable(&entry.path()) {
+                names.insert(name.to_string());
+            }
+        }
+    }
+    names.into_iter().collect()
+}
+
+/// Run an external command with the remaining arguments. The resolved
+/// configuration is handed over through the environment:
+///
+/// - `ANKI_DECK_BUILDER_CONFIG`: the configuration as JSON
+/// - `ANKICONNECT_URL`, `ANKI_DECK_BUILDER_DATA_DIR`, `ANKI_DECK_BUILDER_CACHE`: the most used
+///   settings on their own
+/// - `ANKI_DECK_BUILDER_JSON`, `ANKI_DECK_BUILDER_INTERACTIVE`: "1" or "0", from the global flags
+/// - `ANKI_DECK_BUILDER`: this executable, for calling back into built-in commands
+pub async fn run_external_command(
+    name: &str,
+    args: &[OsString],
+    config: &Config,
+    context: ExternalContext,
+) -> Result<()> {
+    let program = find_external_command(name)
+        .ok_or_else(|| AnkiDeckBuilderError::UnknownCommand(name.to_string()))?;
+    run_program(name, &program, args, config, context).await
+}
+
+async fn run_program(
+    name: &str,
+    program: &Path,
+    args: &[OsString],
+    config: &Config,
+    context: ExternalContext,
+) -> Result<()> {
+    tracing::debug!("Running external command {}", program.display());
+
+    let flag = |enabled: bool| if enabled { "1" } else { "0" };
+
+    let mut command = tokio::process::Command::new(program);
+    command
+        .args(args)
+        .env("ANKI_DECK_BUILDER_CONFIG", serde_json::to_string(config)?)
+        .env("ANKICONNECT_URL", &config.ankiconnect_url)
+        .env("ANKI_DECK_BUILDER_DATA_DIR", config.data_dir())
+        .env(crate::config::CACHE_DIR_ENV, config.cache_dir())
+        .env("ANKI_DECK_BUILDER_JSON", flag(context.json))
+        .env("ANKI_DECK_BUILDER_INTERACTIVE", flag(context.interactive))
+        // Stopped along with us if the run deadline forces an exit
+        .kill_on_drop(true);
+    if let Ok(exe) = std::env::current_exe() {
+        command.env("ANKI_DECK_BUILDER", exe);
+    }
+
+    let status = command.status().await?;
+    if status.success() {
+        return Ok(());
+    }
+
+    Err(AnkiDeckBuilderError::ExternalCommandFailed {
+        name: name.to_string(),
+        // Killed by a signal
+        code: status.code().unwrap_or(1),
+    })
+}
+
+fn find_in(path: &OsStr, name: &str) -> Option<PathBuf> {
+    let file_name = format!(
+        "{}{}{}",
+        EXTERNAL_PREFIX,
+        name,
+        std::env::consts::EXE_SUFFIX
+    );
+    std::env::split_paths(path)
+        .map(|dir| dir.join(&file_name))
+        .find(|candidate| is_executable(candidate))
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    path.metadata()
+        .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+    use tempfile::TempDir;
+
+    fn script(dir: &Path, name: &str, body: &str) -> PathBuf {
+        let path = dir.join(format!("{}{}", EXTERNAL_PREFIX, name));
+        std::fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    fn config(dir: &Path) -> Config {
+        Config {
+            ankiconnect_url: "http://localhost:9999".to_string(),
+            config_dir: dir.to_path_buf(),
+            data_dir: dir.to_path_buf(),
+            cache_dir: dir.to_path_buf(),
+            note_model: None,
+            profiles: Default::default(),
+            http: Default::default(),
+            ankiconnect: Default::default(),
+            deck_name_pattern: None,
+            cache_max_age: None,
+            deck_description: None,
+            forvo_api_key: None,
+            llm: Default::default(),
+            confirm_above: Default::default(),
+            language_packs: Default::default(),
+            read_only: false,
+        }
+    }
+
+    #[test]
+    fn test_find_in_path() {
+        let temp_dir = TempDir::new().unwrap();
+        let expected = script(temp_dir.path(), "stats", "exit 0");
+        std::fs::write(temp_dir.path().join("anki-deck-builder-plain"), "").unwrap();
+
+        let path = std::env::join_paths([Path::new("/nonexistent"), temp_dir.path()]).unwrap();
+        assert_eq!(find_in(&path, "stats"), Some(expected));
+        assert_eq!(find_in(&path, "plain"), None);
+        assert_eq!(find_in(&path, "missing"), None);
+    }
+
+    #[tokio::test]
+    async fn test_passes_config_and_exit_code() {
+        let temp_dir = TempDir::new().unwrap();
+        let out = temp_dir.path().join("out");
+        let program = script(
+            temp_dir.path(),
+            "echo",
+            &format!(
+                "echo \"$1 $ANKICONNECT_URL $ANKI_DECK_BUILDER_JSON\" > {}\nexit \"$2\"",
+                out.display()
+            ),
+        );
+        let context = ExternalContext {
+            json: true,
+            interactive: false,
+        };
+
+        let args = ["hello".into(), "0".into()];
+        run_program("echo", &program, &args, &config(temp_dir.path()), context)
+            .await
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&out).unwrap().trim(),
+            "hello http://localhost:9999 1"
+        );
+
+        let args = ["again".into(), "3".into()];
+        let err = run_program("echo", &program, &args, &config(temp_dir.path()), context)
+            .await
+            .unwrap_err();
+        assert_eq!(err.exit_code(), 3);
+    }
+}
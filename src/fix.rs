@@ -0,0 +1,288 @@
+use crate::ankiweb::models::strip_html;
+use crate::ankiweb::renderer::{headword, letter_hint};
+use crate::ankiweb::{CardFace, CardRenderer, NoteInfo};
+use crate::cache_file;
+use crate::error::Result;
+use crate::language::{normalize_text, PartOfSpeech, TranslationChain, Word};
+use crate::pipeline::translate_word;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Tag that marks notes for `fix` when no other tag or query is given
+pub const DEFAULT_FIX_TAG: &str = "fix";
+
+/// A note the deck builder made, read back from Anki so its translation can
+/// be asked for again. The direction and base languages come from its
+/// "hr-to-es" tags, the rest from its Front and Back fields
+#[derive(Debug, Clone, PartialEq)]
+pub struct AmendableNote {
+    pub note_id: i64,
+    /// The word being learned
+    pub word: String,
+    pub pos: PartOfSpeech,
+    pub target_code: String,
+    /// Languages of the translation, in the order they appear on the card
+    pub base_codes: Vec<String>,
+    /// Whether the translation is the prompt and the word the answer
+    pub reversed: bool,
+    /// Translation on the card now, as plain text
+    pub translation: String,
+    renderer: CardRenderer,
+}
+
+impl AmendableNote {
+    /// None for notes not made for learning `target_code`, and for note
+    /// types without Front and Back fields
+    pub fn from_info(info: &NoteInfo, target_code: &str) -> Option<Self> {
+        let front = &info.fields.get("Front")?.value;
+        let back = &info.fields.get("Back")?.value;
+
+        let directions: Vec<(&str, &str)> = info
+            .tags
+            .iter()
+            .filter_map(|tag| tag.split_once("-to-"))
+            .filter(|(from, to)| is_code(from) && is_code(to))
+            .collect();
+        let reversed = match directions.first()? {
+            (from, _) if *from == target_code => false,
+            (_, to) if *to == target_code => true,
+            _ => return None,
+        };
+        let base_codes = directions
+            .iter()
+            .filter_map(|&(from, to)| match reversed {
+                false if from == target_code => Some(to.to_string()),
+                true if to == target_code => Some(from.to_string()),
+                _ => None,
+            })
+            .collect();
+
+        let pos = info
+            .tags
+            .iter()
+            .find_map(|tag| tag.strip_prefix("pos:"))
+            .and_then(PartOfSpeech::from_name)
+            .unwrap_or(PartOfSpeech::Noun);
+
+        let renderer = CardRenderer::default()
+            .with_html(headword(front).is_some())
+            .with_pos_badge(front.contains("class=\"pos\""));
+        let prompt = headword(front).unwrap_or_else(|| strip_html(front));
+        let answer = strip_html(&back.replace("<br>", "\n"));
+        let (word, translation) = match reversed {
+            false => (prompt, answer),
+            true => (answer, prompt),
+        };
+        if word.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            note_id: info.note_id,
+            word,
+            pos,
+            target_code: target_code.to_string(),
+            base_codes,
+            reversed,
+            translation: translation.replace('\n', " / "),
+            renderer,
+        })
+    }
+
+    /// Front and Back fields with `translation` in place of the current one,
+    /// formatted as the note was
+    pub fn fields_with(&self, translation: &str) -> HashMap<String, String> {
+        let hint = letter_hint(&self.word);
+        let face = match self.reversed {
+            false => CardFace {
+                prompt: &self.word,
+                answer: translation,
+                pos: &self.pos,
+                example: None,
+                hint: None,
+                confusable: None,
+            },
+            true => CardFace {
+                prompt: translation,
+                answer: &self.word,
+                pos: &self.pos,
+                example: None,
+                hint: Some(&hint),
+                confusable: None,
+            },
+        };
+        let fields = self.renderer.render(&face);
+        HashMap::from([
+            ("Front".to_string(), fields.front),
+            ("Back".to_string(), fields.back),
+        ])
+    }
+}
+
+/// New translation for a note, or why there is none
+#[derive(Debug, Clone, Serialize)]
+pub struct NoteFix {
+    pub note_id: i64,
+    pub word: String,
+    pub old_translation: String,
+    pub new_translation: String,
+    /// Why the new translation is not trusted either
+    pub issue: Option<String>,
+    #[serde(skip)]
+    pub fields: HashMap<String, String>,
+}
+
+impl NoteFix {
+    /// Whether the note should be updated: the new translation is trusted
+    /// and differs from the old one
+    pub fn is_improvement(&self) -> bool {
+        self.issue.is_none() && self.new_translation != self.old_translation
+    }
+}
+
+/// Ask the translators again for a note's word
+pub async fn retranslate(translator: &TranslationChain, note: &AmendableNote) -> Result<NoteFix> {
+    let word = Word::new(note.word.clone(), note.pos.clone(), 0);
+    let translated = translate_word(translator, word, &note.target_code, &note.base_codes).await?;
+    Ok(NoteFix {
+        note_id: note.note_id,
+        word: note.word.clone(),
+        old_translation: note.translation.clone(),
+        new_translation: translated.translation.replace("<br>", " / "),
+        issue: translated.issue,
+        fields: note.fields_with(&translated.translation),
+    })
+}
+
+/// Drop the cached translations of `words` from `from` into each of `to`,
+/// so the providers are asked again instead of repeating the old answer.
+/// Returns how many were dropped
+pub fn forget_translations(
+    cache_dir: &Path,
+    words: &[String],
+    from: &str,
+    to: &[String],
+) -> Result<usize> {
+    let words: Vec<String> = words.iter().map(|word| normalize_text(word)).collect();
+    let mut forgotten = 0;
+    // Google's and the LLM's answers are cached apart from the other providers'
+    let dirs = [
+        cache_dir.join("translations"),
+        cache_dir.join("translations").join("google"),
+        cache_dir.join("translations").join("llm"),
+    ];
+    for cache_file in dirs.iter().flat_map(|dir| {
+        to.iter()
+            .map(move |code| dir.join(format!("{}_{}.json", from, code)))
+    }) {
+        if cache_file::read_map::<String>(&cache_file).is_none() {
+            continue;
+        }
+        // Entries still in the journal are forgotten too
+        forgotten += cache_file::update_map(&cache_file, |cache: &mut HashMap<String, String>| {
+            let before = cache.len();
+            cache.retain(|text, _| !words.contains(&normalize_text(text)));
+            before - cache.len()
+        })?;
+    }
+    Ok(forgotten)
+}
+
+fn is_code(text: &str) -> bool {
+    !text.is_empty() && text.chars().all(|c| c.is_ascii_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ankiweb::{AnkiBackend, CardBuilder, MockAnki};
+    use crate::language::Translator;
+    use async_trait::async_trait;
+
+    struct EchoTranslator;
+
+    #[async_trait]
+    impl Translator for EchoTranslator {
+        fn name(&self) -> &'static str {
+            "echo"
+        }
+
+        async fn translate(&self, text: &str, _from: &str, to: &str) -> Result<String> {
+            Ok(format!("{} ({})", text, to))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fix_notes_in_place() {
+        let anki = MockAnki::new();
+        anki.create_deck("Deck").await.unwrap();
+        let builder = CardBuilder::new("Deck", "hr", "es")
+            .with_extra_base_codes(vec!["en".to_string()])
+            .with_extra_tags(vec!["fix".to_string()]);
+        for note in builder.build("dan", "es: dan<br>en: dan", &PartOfSpeech::Noun, 1) {
+            anki.add_note(&note).await.unwrap();
+        }
+
+        let ids = anki.find_notes("tag:fix").await.unwrap();
+        let infos = anki.notes_info(&ids).await.unwrap();
+        let notes: Vec<_> = infos
+            .iter()
+            .filter_map(|info| AmendableNote::from_info(info, "hr"))
+            .collect();
+        assert_eq!(notes.len(), 2);
+        assert!(notes.iter().all(|note| note.word == "dan"));
+        assert_eq!(notes[0].base_codes, vec!["es", "en"]);
+        assert_eq!(notes[0].translation, "es: dan / en: dan");
+        assert!(AmendableNote::from_info(&infos[0], "sr").is_none());
+
+        let translator = TranslationChain::new(vec![Box::new(EchoTranslator)]);
+        for note in &notes {
+            let fix = retranslate(&translator, note).await.unwrap();
+            assert!(fix.is_improvement());
+            anki.update_note_fields(note.note_id, &fix.fields)
+                .await
+                .unwrap();
+        }
+        anki.remove_tags(&ids, &["fix".to_string()]).await.unwrap();
+
+        let stored = anki.notes();
+        let reversed = stored
+            .iter()
+            .find(|note| note.tags.contains(&"es-to-hr".to_string()))
+            .unwrap();
+        assert_eq!(
+            headword(&reversed.fields["Front"]).as_deref(),
+            Some("es: dan (es)\nen: dan (en)")
+        );
+        assert!(reversed.fields["Front"].contains("d _ _"));
+        assert!(stored
+            .iter()
+            .all(|note| !note.tags.contains(&"fix".to_string())));
+    }
+
+    #[test]
+    fn test_forget_translations() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let translations = temp_dir.path().join("translations");
+        std::fs::create_dir_all(&translations).unwrap();
+        std::fs::write(
+            translations.join("hr_en.json"),
+            r#"{"dan": "dan", "kuća": "house"}"#,
+        )
+        .unwrap();
+
+        let google = translations.join("google");
+        std::fs::create_dir_all(&google).unwrap();
+        std::fs::write(google.join("hr_es.json"), r#"{"dan": "día"}"#).unwrap();
+
+        let words = vec!["dan".to_string()];
+        let to = vec!["en".to_string(), "es".to_string()];
+        assert_eq!(
+            forget_translations(temp_dir.path(), &words, "hr", &to).unwrap(),
+            2
+        );
+        let cache = std::fs::read_to_string(translations.join("hr_en.json")).unwrap();
+        assert!(!cache.contains("\"dan\"") && cache.contains("kuća"));
+    }
+}
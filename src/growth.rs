@@ -0,0 +1,117 @@
+use crate::error::Result;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Words `grow` adds per run when not told otherwise
+pub const DEFAULT_PER_WEEK: usize = 25;
+
+/// How far `grow` has taken a deck through its frequency list. Saved after
+/// every completed run, so a scheduled job adds the next words each time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GrowthState {
+    pub deck_name: String,
+    pub target_language: String,
+    pub base_language: String,
+    /// Words added by each run
+    pub per_week: usize,
+    /// Rank of the last word reached; the next run starts after it
+    pub reached_rank: usize,
+    #[serde(default)]
+    pub history: Vec<GrowthRun>,
+}
+
+/// One completed `grow` run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GrowthRun {
+    /// Seconds since the Unix epoch
+    pub finished_at: u64,
+    /// Ranks of the first and last word picked
+    pub from_rank: usize,
+    pub to_rank: usize,
+    pub words: usize,
+}
+
+impl GrowthState {
+    pub fn new(
+        deck_name: &str,
+        target_language: &str,
+        base_language: &str,
+        per_week: usize,
+    ) -> Self {
+        Self {
+            deck_name: deck_name.to_string(),
+            target_language: target_language.to_string(),
+            base_language: base_language.to_string(),
+            per_week,
+            reached_rank: 0,
+            history: Vec::new(),
+        }
+    }
+
+    /// State file for a deck
+    pub fn path(growth_dir: &Path, deck_name: &str) -> PathBuf {
+        let slug: String = deck_name
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '_' })
+            .collect();
+        growth_dir.join(format!("{}.json", slug))
+    }
+
+    /// Load a deck's state, if it has been grown before
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content = std::fs::read_to_string(path)?;
+        Ok(Some(serde_json::from_str(&content)?))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Record a completed run over the words ranked `from_rank..=to_rank`
+    pub fn advance(&mut self, from_rank: usize, to_rank: usize, words: usize) {
+        self.reached_rank = self.reached_rank.max(to_rank);
+        self.history.push(GrowthRun {
+            finished_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            from_rank,
+            to_rank,
+            words,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_advance_and_reload() {
+        let temp_dir = tempdir().unwrap();
+        let path = GrowthState::path(temp_dir.path(), "Croatian::Growing");
+        assert!(path.ends_with("Croatian__Growing.json"));
+        assert_eq!(GrowthState::load(&path).unwrap(), None);
+
+        let mut state = GrowthState::new("Croatian::Growing", "hr", "en", 20);
+        state.advance(1, 24, 20);
+        state.advance(25, 47, 20);
+        state.save(&path).unwrap();
+
+        let loaded = GrowthState::load(&path).unwrap().unwrap();
+        assert_eq!(loaded.reached_rank, 47);
+        assert_eq!(loaded.history.len(), 2);
+        assert_eq!(loaded, state);
+    }
+}
@@ -0,0 +1,338 @@
+use crate::ankiweb::{AnkiBackend, AnkiClient, AnkiConnectSettings};
+use crate::audio::{AudioProvider, CommonsAudioClient, ForvoClient};
+use crate::config::{Config, ServiceSettings};
+use crate::error::{AnkiDeckBuilderError, Result};
+use crate::http::HttpSettings;
+use crate::images::ImageClient;
+use crate::language::google_translate::GOOGLE_TRANSLATE_URL;
+use crate::language::{
+    FrequencySourceKind, GoogleTranslateClient, LibreTranslateClient, ListSize, LlmClient,
+    MyMemoryClient, Translator, TranslatorKind, WiktionaryClient,
+};
+use serde::Serialize;
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tokio::task::JoinSet;
+
+/// Service name of the AnkiConnect check
+pub const ANKICONNECT_SERVICE: &str = "AnkiConnect";
+
+/// Language whose frequency list is probed on each source
+const PROBE_LANGUAGE: &str = "hr";
+
+/// Word looked up on the dictionary and pronunciation services
+const PROBE_WORD: &str = "dan";
+
+/// Timeout for each individual check
+const CHECK_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Result of checking one external service
+#[derive(Debug, Clone, Serialize)]
+pub struct ServiceCheck {
+    pub service: String,
+    pub target: String,
+    pub ok: bool,
+    pub latency_ms: u128,
+    pub error: Option<String>,
+}
+
+type CheckFuture = std::pin::Pin<Box<dyn Future<Output = ServiceCheck> + Send>>;
+
+/// Check every external service the pipeline depends on, in parallel.
+/// `full` adds the dictionary, pronunciation and image services the
+/// enrichments use. Results come back in a stable order regardless of
+/// which finished first
+pub async fn check_all(
+    config: &Config,
+    services: &ServiceSettings,
+    full: bool,
+) -> Vec<ServiceCheck> {
+    let mut checks = JoinSet::new();
+    let mut index = 0;
+    let mut spawn = |future: CheckFuture| {
+        checks.spawn(async move { (index, future.await) });
+        index += 1;
+    };
+
+    let anki_url = config.ankiconnect_url.clone();
+    let http = services.http.clone();
+    spawn(Box::pin(timed(
+        ANKICONNECT_SERVICE,
+        anki_url.clone(),
+        async move {
+            AnkiClient::with_settings(anki_url, &AnkiConnectSettings::default(), &http)?
+                .verify_connection()
+                .await
+        },
+    )));
+
+    let (kinds, libretranslate_urls) = configured_translators(config);
+    for kind in kinds {
+        let http = services.http.clone();
+        match kind {
+            TranslatorKind::Mymemory => spawn(Box::pin(timed(
+                "Translator (MyMemory)",
+                "https://api.mymemory.translated.net".to_string(),
+                async move { check_translator(&MyMemoryClient::new(None, &http)?, "en", "es").await },
+            ))),
+            TranslatorKind::Google => spawn(Box::pin(timed(
+                "Translator (Google)",
+                GOOGLE_TRANSLATE_URL.to_string(),
+                async move {
+                    check_translator(&GoogleTranslateClient::new(None, &http)?, "en", "es").await
+                },
+            ))),
+            TranslatorKind::Llm => {
+                let services = services.clone();
+                spawn(Box::pin(timed(
+                    "Translator (LLM)",
+                    services.llm.api_url.clone(),
+                    async move { check_translator(&LlmClient::new(&services, None)?, "en", "es").await },
+                )))
+            }
+        }
+    }
+
+    for url in libretranslate_urls {
+        let http = services.http.clone();
+        spawn(Box::pin(timed(
+            "Translator (LibreTranslate)",
+            url.clone(),
+            async move {
+                check_translator(&LibreTranslateClient::new(url, None, &http)?, "en", "es").await
+            },
+        )));
+    }
+
+    for kind in FrequencySourceKind::CORPORA {
+        let source = kind.build(&services.language_packs);
+        let Some(url) = source.download_url(PROBE_LANGUAGE, ListSize::default()) else {
+            continue;
+        };
+        let service = format!("Frequency ({})", source.id());
+        let http = services.http.clone();
+        spawn(Box::pin(timed(service, url.clone(), async move {
+            check_http(&url, &http).await
+        })));
+    }
+
+    if full {
+        let http = services.http.clone();
+        spawn(Box::pin(timed(
+            "Dictionary (Wiktionary)",
+            "https://en.wiktionary.org".to_string(),
+            async move {
+                WiktionaryClient::new(None, &http)?
+                    .senses(PROBE_LANGUAGE, PROBE_WORD)
+                    .await
+                    .map(|_| ())
+            },
+        )));
+        let http = services.http.clone();
+        spawn(Box::pin(timed(
+            "Pronunciation (Commons)",
+            "https://commons.wikimedia.org".to_string(),
+            async move { check_audio(&CommonsAudioClient::new(&http)?).await },
+        )));
+        if let Ok(Some(forvo)) = ForvoClient::from_env(services) {
+            spawn(Box::pin(timed(
+                "Pronunciation (Forvo)",
+                "https://apifree.forvo.com".to_string(),
+                async move { check_audio(&forvo).await },
+            )));
+        }
+        let http = services.http.clone();
+        spawn(Box::pin(timed(
+            "Images (Openverse)",
+            "https://api.openverse.org".to_string(),
+            async move {
+                ImageClient::new(&http)?
+                    .search("house", 1)
+                    .await
+                    .map(|_| ())
+            },
+        )));
+    }
+
+    let mut results = Vec::new();
+    while let Some(joined) = checks.join_next().await {
+        if let Ok(result) = joined {
+            results.push(result);
+        }
+    }
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, check)| check).collect()
+}
+
+/// The translators the chains a build would use ask: the one each profile
+/// picks, MyMemory, which every chain falls back on, and each LibreTranslate
+/// server set in a profile or `LIBRETRANSLATE_URL`
+fn configured_translators(config: &Config) -> (Vec<TranslatorKind>, Vec<String>) {
+    let mut kinds = Vec::new();
+    let mut urls = Vec::new();
+    let chosen = config
+        .profiles
+        .values()
+        .filter_map(|profile| profile.translator);
+    for kind in chosen.chain([TranslatorKind::Mymemory]) {
+        if !kinds.contains(&kind) {
+            kinds.push(kind);
+        }
+    }
+    let profile_urls = config
+        .profiles
+        .values()
+        .filter_map(|profile| profile.libretranslate_url.clone());
+    for url in std::env::var("LIBRETRANSLATE_URL")
+        .ok()
+        .into_iter()
+        .chain(profile_urls)
+    {
+        if !urls.contains(&url) {
+            urls.push(url);
+        }
+    }
+    (kinds, urls)
+}
+
+/// Run a check with a timeout, recording how long it took
+pub(crate) async fn timed(
+    service: impl Into<String>,
+    target: String,
+    check: impl Future<Output = Result<()>>,
+) -> ServiceCheck {
+    let start = Instant::now();
+    let error = match tokio::time::timeout(CHECK_TIMEOUT, check).await {
+        Ok(Ok(())) => None,
+        Ok(Err(e)) => Some(e.to_string()),
+        Err(_) => Some(format!("timed out after {}s", CHECK_TIMEOUT.as_secs())),
+    };
+
+    ServiceCheck {
+        service: service.into(),
+        target,
+        ok: error.is_none(),
+        latency_ms: start.elapsed().as_millis(),
+        error,
+    }
+}
+
+/// Translate a word between two languages, bypassing the cache
+pub(crate) async fn check_translator(
+    translator: &dyn Translator,
+    from: &str,
+    to: &str,
+) -> Result<()> {
+    translator.translate("hello", from, to).await.map(|_| ())
+}
+
+/// Look up a word's recording; finding none still means the service answered
+async fn check_audio(provider: &dyn AudioProvider) -> Result<()> {
+    provider.find(PROBE_LANGUAGE, PROBE_WORD).await.map(|_| ())
+}
+
+/// Check that a download URL answers a HEAD request successfully
+pub(crate) async fn check_http(url: &str, http: &HttpSettings) -> Result<()> {
+    crate::http::client_builder(http)?
+        .build()
+        .map_err(AnkiDeckBuilderError::HttpError)?
+        .head(url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(AnkiDeckBuilderError::HttpError)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_timed_records_failure() {
+        let check = timed("Failing", "nowhere".to_string(), async {
+            Err(AnkiDeckBuilderError::ConfigurationError(
+                "broken".to_string(),
+            ))
+        })
+        .await;
+
+        assert!(!check.ok);
+        assert_eq!(
+            check.error.as_deref(),
+            Some("Invalid configuration: broken")
+        );
+    }
+
+    #[test]
+    fn test_configured_translators() {
+        let mut config = Config::default();
+        config.profiles.clear();
+        assert_eq!(
+            configured_translators(&config).0,
+            vec![TranslatorKind::Mymemory]
+        );
+
+        for (name, translator, url) in [
+            (
+                "croatian",
+                TranslatorKind::Google,
+                Some("http://libre:5000"),
+            ),
+            ("spanish", TranslatorKind::Llm, None),
+            ("serbian", TranslatorKind::Google, Some("http://libre:5000")),
+        ] {
+            config.profiles.insert(
+                name.to_string(),
+                crate::config::Profile {
+                    translator: Some(translator),
+                    libretranslate_url: url.map(str::to_string),
+                    ..Default::default()
+                },
+            );
+        }
+        let (kinds, urls) = configured_translators(&config);
+        assert_eq!(
+            kinds,
+            vec![
+                TranslatorKind::Google,
+                TranslatorKind::Llm,
+                TranslatorKind::Mymemory
+            ]
+        );
+        assert_eq!(
+            urls.iter()
+                .filter(|url| *url == "http://libre:5000")
+                .count(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_check_http() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("HEAD", "/ok.txt")
+            .with_status(200)
+            .create_async()
+            .await;
+        server
+            .mock("HEAD", "/missing.txt")
+            .with_status(404)
+            .create_async()
+            .await;
+
+        assert!(check_http(
+            &format!("{}/ok.txt", server.url()),
+            &HttpSettings::default()
+        )
+        .await
+        .is_ok());
+        assert!(check_http(
+            &format!("{}/missing.txt", server.url()),
+            &HttpSettings::default()
+        )
+        .await
+        .is_err());
+    }
+}
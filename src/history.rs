@@ -0,0 +1,250 @@
+use crate::cache_file;
+use crate::error::Result;
+use crate::events::BuildSummary;
+use crate::report::{FailedWord, FailureReport};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Runs kept in the history; the oldest go first
+pub const MAX_RUNS: usize = 500;
+
+/// Failures kept with a run. The failure report has all of them
+pub const MAX_FAILURES: usize = 50;
+
+/// One finished `create` run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunRecord {
+    /// Numbered from 1 in the order runs finished
+    pub id: u64,
+    /// Seconds since the Unix epoch
+    pub finished_at: u64,
+    pub duration_secs: u64,
+    pub target_language: String,
+    pub base_languages: Vec<String>,
+    #[serde(flatten)]
+    pub summary: BuildSummary,
+    /// The first [`MAX_FAILURES`] words that could not be added
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failures: Vec<FailedWord>,
+    /// Failure report saved by the run, for all its failures
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub report: Option<PathBuf>,
+    /// Notes the run added to Anki, for `undo`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub note_ids: Vec<i64>,
+    /// When `undo` deleted the notes, in seconds since the Unix epoch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub undone_at: Option<u64>,
+}
+
+impl RunRecord {
+    pub fn new(
+        summary: BuildSummary,
+        target_language: &str,
+        base_languages: &[String],
+        duration: Duration,
+    ) -> Self {
+        Self {
+            id: 0,
+            finished_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            duration_secs: duration.as_secs(),
+            target_language: target_language.to_string(),
+            base_languages: base_languages.to_vec(),
+            summary,
+            failures: Vec::new(),
+            report: None,
+            note_ids: Vec::new(),
+            undone_at: None,
+        }
+    }
+
+    pub fn with_failures(mut self, failures: &FailureReport) -> Self {
+        self.failures = failures
+            .failures
+            .iter()
+            .take(MAX_FAILURES)
+            .cloned()
+            .collect();
+        self
+    }
+
+    pub fn with_report(mut self, report: Option<PathBuf>) -> Self {
+        self.report = report;
+        self
+    }
+
+    pub fn with_note_ids(mut self, note_ids: Vec<i64>) -> Self {
+        self.note_ids = note_ids;
+        self
+    }
+
+    /// How the run ended, as the history lists it
+    pub fn outcome(&self) -> &'static str {
+        if self.undone_at.is_some() {
+            return "undone";
+        }
+        self.summary.outcome.label()
+    }
+}
+
+/// Every recent run, oldest first, kept in one file shared by all decks
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunHistory {
+    pub runs: Vec<RunRecord>,
+}
+
+impl RunHistory {
+    /// The history, empty when nothing has run yet
+    pub fn load(path: &Path) -> Self {
+        cache_file::read_json(path).unwrap_or_default()
+    }
+
+    /// Add a run under the next id, dropping the oldest past [`MAX_RUNS`].
+    /// Returns the id
+    pub fn record(path: &Path, mut run: RunRecord) -> Result<u64> {
+        cache_file::update_json(path, |history: &mut RunHistory| {
+            run.id = history.runs.last().map_or(1, |last| last.id + 1);
+            let id = run.id;
+            history.runs.push(run);
+            let excess = history.runs.len().saturating_sub(MAX_RUNS);
+            history.runs.drain(..excess);
+            id
+        })
+    }
+
+    pub fn get(&self, id: u64) -> Option<&RunRecord> {
+        self.runs.iter().find(|run| run.id == id)
+    }
+
+    /// The most recent run that added notes and hasn't been undone
+    pub fn last_undoable(&self) -> Option<&RunRecord> {
+        self.runs
+            .iter()
+            .rev()
+            .find(|run| run.undone_at.is_none() && !run.note_ids.is_empty())
+    }
+
+    /// Note that a run's notes were deleted
+    pub fn mark_undone(path: &Path, id: u64) -> Result<()> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        cache_file::update_json(path, |history: &mut RunHistory| {
+            if let Some(run) = history.runs.iter_mut().find(|run| run.id == id) {
+                run.undone_at = Some(now);
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::BuildOutcome;
+    use crate::language::{PartOfSpeech, Word};
+    use crate::report::FailurePhase;
+    use tempfile::tempdir;
+
+    fn summary(deck_name: &str) -> BuildSummary {
+        BuildSummary {
+            outcome: BuildOutcome::Completed,
+            deck_name: deck_name.to_string(),
+            words: 20,
+            total_words: 20,
+            notes_added: 18,
+            cards_added: 36,
+            duplicates_skipped: 1,
+            cognates_skipped: 0,
+            notes_failed: 1,
+            flagged: 0,
+            disambiguated: 0,
+            enriched: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_record_and_reload() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("history.json");
+        assert!(RunHistory::load(&path).runs.is_empty());
+
+        let mut failures = FailureReport::new("Croatian", "hr", &["en".to_string()]);
+        failures.record(
+            &Word::new("kuća".to_string(), PartOfSpeech::Noun, 3),
+            None,
+            FailurePhase::Translation,
+            "timed out",
+        );
+        let run = RunRecord::new(
+            summary("Croatian"),
+            "hr",
+            &["en".to_string()],
+            Duration::from_secs(95),
+        )
+        .with_failures(&failures);
+        assert_eq!(RunHistory::record(&path, run.clone()).unwrap(), 1);
+        assert_eq!(
+            RunHistory::record(
+                &path,
+                RunRecord::new(summary("Spanish"), "es", &[], Duration::ZERO)
+            )
+            .unwrap(),
+            2
+        );
+
+        let history = RunHistory::load(&path);
+        assert_eq!(history.runs.len(), 2);
+        let first = history.get(1).unwrap();
+        assert_eq!(first, &RunRecord { id: 1, ..run });
+        assert_eq!(first.failures[0].word, "kuća");
+        assert_eq!(first.outcome(), "completed");
+        assert_eq!(history.get(2).unwrap().summary.deck_name, "Spanish");
+        assert_eq!(history.get(3), None);
+    }
+
+    #[test]
+    fn test_undo_marks_run() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("history.json");
+        let run = |note_ids: Vec<i64>| {
+            RunRecord::new(summary("Croatian"), "hr", &[], Duration::ZERO).with_note_ids(note_ids)
+        };
+        RunHistory::record(&path, run(vec![11, 12])).unwrap();
+        RunHistory::record(&path, run(Vec::new())).unwrap();
+        assert_eq!(RunHistory::load(&path).last_undoable().unwrap().id, 1);
+
+        RunHistory::mark_undone(&path, 1).unwrap();
+        let history = RunHistory::load(&path);
+        assert_eq!(history.get(1).unwrap().outcome(), "undone");
+        assert_eq!(history.get(1).unwrap().note_ids, [11, 12]);
+        assert_eq!(history.last_undoable(), None);
+    }
+
+    #[test]
+    fn test_oldest_runs_dropped() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("history.json");
+        let runs = (0..MAX_RUNS + 2)
+            .map(|i| RunRecord {
+                id: i as u64 + 1,
+                ..RunRecord::new(summary("Croatian"), "hr", &[], Duration::ZERO)
+            })
+            .collect();
+        cache_file::write_json(&path, &RunHistory { runs }).unwrap();
+
+        let id = RunHistory::record(
+            &path,
+            RunRecord::new(summary("Croatian"), "hr", &[], Duration::ZERO),
+        )
+        .unwrap();
+        assert_eq!(id, MAX_RUNS as u64 + 3);
+        let history = RunHistory::load(&path);
+        assert_eq!(history.runs.len(), MAX_RUNS);
+        assert_eq!(history.runs[0].id, 4);
+    }
+}
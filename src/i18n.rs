@@ -0,0 +1,294 @@
+//! Messages in the language of the person running the CLI. The code keeps
+//! writing English; `say!` looks each message up as printed in a catalog
+//! under `i18n/`, embedded at build time, and prints the translation in the
+//! console's [`Locale`] when there is one. Messages without one stay in
+//! English
+
+use crate::error::{AnkiDeckBuilderError, Result};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Locales with a catalog, and English
+pub const LOCALES: &[&str] = &["en", "es", "hr"];
+
+static CATALOGS: OnceLock<HashMap<&'static str, Vec<Entry>>> = OnceLock::new();
+
+/// An English message split at its placeholders, with its translation
+struct Entry {
+    english: Vec<&'static str>,
+    translation: Vec<&'static str>,
+}
+
+fn catalog_source(locale: &str) -> Option<&'static str> {
+    Some(match locale {
+        "es" => include_str!("i18n/es.txt"),
+        "hr" => include_str!("i18n/hr.txt"),
+        _ => return None,
+    })
+}
+
+fn catalog(locale: &str) -> Option<&'static [Entry]> {
+    let catalogs = CATALOGS.get_or_init(|| {
+        LOCALES
+            .iter()
+            .filter_map(|&locale| Some((locale, parse_catalog(catalog_source(locale)?))))
+            .collect()
+    });
+    catalogs.get(locale).map(Vec::as_slice)
+}
+
+fn parse_catalog(source: &'static str) -> Vec<Entry> {
+    let mut entries: Vec<Entry> = source
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('\t'))
+        .map(|(english, translation)| Entry {
+            english: english.split("{}").collect(),
+            translation: translation.split("{}").collect(),
+        })
+        .filter(|entry| entry.english.len() == entry.translation.len())
+        .collect();
+    // "Phrases: {} (in '{}::{}')" is tried before "Phrases: {}"
+    entries.sort_by_key(|entry| {
+        std::cmp::Reverse(entry.english.iter().map(|piece| piece.len()).sum::<usize>())
+    });
+    entries
+}
+
+/// The language messages are printed in: the one chosen with `--locale`,
+/// else the one implied by the base language of the decks being built,
+/// else English
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Locale {
+    chosen: Option<&'static str>,
+    implied: Option<&'static str>,
+}
+
+impl Locale {
+    /// Use `locale`, as asked with `--locale`
+    pub fn choose(&mut self, locale: &str) -> Result<()> {
+        let Some(locale) = LOCALES.iter().find(|known| **known == locale) else {
+            return Err(AnkiDeckBuilderError::ConfigurationError(format!(
+                "no messages in '{}'; --locale takes one of {}",
+                locale,
+                LOCALES.join(", ")
+            )));
+        };
+        self.chosen = Some(locale);
+        Ok(())
+    }
+
+    /// Speak the base language of the decks being built, unless `--locale`
+    /// chose otherwise. Base languages without a catalog mean English
+    pub fn imply(&mut self, base_language: &str) {
+        let locale = LOCALES
+            .iter()
+            .find(|known| **known == base_language)
+            .copied()
+            .unwrap_or("en");
+        self.implied = Some(locale);
+    }
+
+    /// The locale messages are printed in
+    pub fn name(&self) -> &'static str {
+        self.chosen.or(self.implied).unwrap_or("en")
+    }
+
+    /// `text` in this locale. Leading emoji, indentation and blank lines are
+    /// kept around the translated message
+    pub fn localize<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let Some(entries) = catalog(self.name()) else {
+            return Cow::Borrowed(text);
+        };
+        let body = text.trim_start_matches(is_lead);
+        let message = body.trim_end();
+        let (lead, trail) = (&text[..text.len() - body.len()], &body[message.len()..]);
+
+        entries
+            .iter()
+            .find_map(|entry| {
+                let values = fill_in(&entry.english, message)?;
+                let mut translated = lead.to_string();
+                for (i, piece) in entry.translation.iter().enumerate() {
+                    if i > 0 {
+                        translated.push_str(values[i - 1]);
+                    }
+                    translated.push_str(piece);
+                }
+                translated.push_str(trail);
+                Some(Cow::Owned(translated))
+            })
+            .unwrap_or(Cow::Borrowed(text))
+    }
+}
+
+/// Whether `c` is indentation or emoji in front of a message
+fn is_lead(c: char) -> bool {
+    c.is_whitespace() || crate::output::is_pictograph(c) || matches!(c, 'ℹ' | '▶' | '\u{fe0f}')
+}
+
+/// The values in `message` where `pieces`, a message split at its
+/// placeholders, leaves gaps. None when it is another message
+fn fill_in<'a>(pieces: &[&str], message: &'a str) -> Option<Vec<&'a str>> {
+    let (first, rest) = pieces.split_first()?;
+    let mut remaining = message.strip_prefix(first)?;
+    let Some((last, middle)) = rest.split_last() else {
+        return remaining.is_empty().then(Vec::new);
+    };
+    remaining = remaining.strip_suffix(last)?;
+    let mut values = Vec::with_capacity(rest.len());
+    for piece in middle {
+        let at = remaining.find(piece)?;
+        values.push(&remaining[..at]);
+        remaining = &remaining[at + piece.len()..];
+    }
+    values.push(remaining);
+    Some(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fill_in() {
+        let pieces: Vec<&str> = "Phrases: {} (in '{}::{}')".split("{}").collect();
+        assert_eq!(
+            fill_in(&pieces, "Phrases: 20 (in 'Croatian::Phrases')"),
+            Some(vec!["20", "Croatian", "Phrases"])
+        );
+        assert_eq!(fill_in(&pieces, "Phrases: 20"), None);
+        assert_eq!(fill_in(&["Cancelled"], "Cancelled"), Some(vec![]));
+        assert_eq!(fill_in(&["Cancelled"], "Cancelled early"), None);
+    }
+
+    /// The string literal that starts at `source` and the code after it,
+    /// placeholders as `{}` however they are formatted
+    fn literal(source: &str) -> (String, &str) {
+        let mut literal = String::new();
+        let mut chars = source.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => return (literal, &source[i + 1..]),
+                '\\' => match chars.next().map(|(_, c)| c) {
+                    Some('n') => literal.push('\n'),
+                    Some('\n') => while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {},
+                    Some(c) => literal.push(c),
+                    None => {}
+                },
+                '{' | '}' if chars.next_if(|(_, next)| *next == c).is_some() => literal.push(c),
+                '{' => {
+                    literal.push_str("{}");
+                    while chars.next_if(|(_, c)| *c != '}').is_some() {}
+                    chars.next();
+                }
+                c => literal.push(c),
+            }
+        }
+        (literal, "")
+    }
+
+    /// The first string literals passed to `say!`, `eprintln!` and the
+    /// dialogs in `source`, as the console gets them, so a raw print that
+    /// skips the catalogs is caught too
+    fn messages(mut source: &str) -> Vec<String> {
+        let markers = ["say!(", "say_err!(", "eprintln!(", ".with_prompt(", "tr("];
+        let mut messages = Vec::new();
+        while let Some((at, marker)) = markers
+            .iter()
+            .filter_map(|marker| Some((source.find(marker)?, marker)))
+            .min()
+        {
+            let called_on_name = source[..at]
+                .chars()
+                .next_back()
+                .is_some_and(|c| c.is_alphanumeric() || c == '_');
+            source = &source[at + marker.len()..];
+            let Some(quote) = source.find('"') else { break };
+            if called_on_name || source[..quote].contains([')', ';']) {
+                continue;
+            }
+            let (message, rest) = literal(&source[quote + 1..]);
+            messages.push(message);
+            source = rest;
+        }
+        messages
+    }
+
+    #[test]
+    fn test_catalogs_match_the_messages() {
+        let sources = [
+            include_str!("cli.rs"),
+            include_str!("cancel.rs"),
+            include_str!("sink.rs"),
+            include_str!("main.rs"),
+        ];
+        let messages: Vec<String> = sources
+            .iter()
+            .flat_map(|source| messages(source))
+            .map(|literal| literal.trim_start_matches(is_lead).trim_end().to_string())
+            .filter(|message| message.replace("{}", "").contains(char::is_alphabetic))
+            .collect();
+        // Errors are printed through the catalogs too
+        let mut literals = Vec::new();
+        for mut source in sources.into_iter().chain([include_str!("error.rs")]) {
+            while let Some(quote) = source.find('"') {
+                let (found, rest) = literal(&source[quote + 1..]);
+                literals.push(found);
+                source = rest;
+            }
+        }
+
+        for locale in ["es", "hr"] {
+            let source = catalog_source(locale).unwrap();
+            let lines: Vec<&str> = source
+                .lines()
+                .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+                .collect();
+            assert_eq!(catalog(locale).unwrap().len(), lines.len(), "{}", locale);
+            let englishes: Vec<&str> = lines
+                .iter()
+                .map(|line| line.split_once('\t').unwrap().0)
+                .collect();
+            for english in &englishes {
+                assert!(
+                    literals.iter().any(|literal| literal.contains(english)),
+                    "'{}' is no longer printed",
+                    english
+                );
+            }
+            for message in &messages {
+                assert!(
+                    englishes.contains(&message.as_str()),
+                    "'{}' is missing from the {} catalog",
+                    message,
+                    locale
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_localize() {
+        let mut locale = Locale::default();
+        locale.imply("hr");
+        assert_eq!(locale.name(), "hr");
+        assert_eq!(
+            locale.localize("  ✅ 12 notes added successfully (24 cards)"),
+            "  ✅ Dodano 12 bilješki (24 kartica)"
+        );
+        assert_eq!(
+            locale
+                .localize("\n⏹️  Cancelled after 3 of 10 words; the notes added so far are kept."),
+            "\n⏹️  Otkazano nakon 3 od 10 riječi; dosad dodane bilješke ostaju."
+        );
+        assert_eq!(locale.localize("kuća → house"), "kuća → house");
+        locale.imply("fr");
+        assert_eq!(locale.localize("Cancelled"), "Cancelled");
+        assert!(locale.choose("xx").is_err());
+        locale.choose("es").unwrap();
+        locale.imply("hr");
+        assert_eq!(locale.name(), "es");
+    }
+}
@@ -0,0 +1,202 @@
+use crate::error::{AnkiDeckBuilderError, Result};
+use crate::http::HttpSettings;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Openverse indexes openly licensed images, including Wikimedia Commons
+const OPENVERSE_API_URL: &str = "https://api.openverse.org/v1";
+
+/// An openly licensed image found for a word
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageResult {
+    pub id: String,
+    pub title: Option<String>,
+    pub creator: Option<String>,
+    pub license: String,
+    pub license_version: Option<String>,
+    pub url: String,
+    pub thumbnail: Option<String>,
+    pub foreign_landing_url: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SearchResponse {
+    results: Vec<ImageResult>,
+}
+
+impl ImageResult {
+    /// Credit line required by most Creative Commons licenses
+    pub fn attribution(&self) -> String {
+        let title = self.title.as_deref().unwrap_or("Untitled");
+        let name = match self.license.as_str() {
+            "cc0" => "CC0".to_string(),
+            "pdm" => "Public Domain Mark".to_string(),
+            other => format!("CC {}", other.to_uppercase()),
+        };
+        let license = match &self.license_version {
+            Some(version) => format!("{} {}", name, version),
+            None => name,
+        };
+        match &self.creator {
+            Some(creator) => format!("\"{}\" by {} ({})", title, creator, license),
+            None => format!("\"{}\" ({})", title, license),
+        }
+    }
+
+    /// Image field content for a stored media file, with its attribution
+    pub fn field_html(&self, filename: &str) -> String {
+        format!(
+            "<img src=\"{}\"><div class=\"attribution\">{}</div>",
+            filename,
+            html_escape(&self.attribution())
+        )
+    }
+}
+
+/// Searches Openverse and downloads thumbnails
+#[derive(Debug, Clone)]
+pub struct ImageClient {
+    base_url: String,
+    client: Client,
+}
+
+impl ImageClient {
+    pub fn new(http: &HttpSettings) -> Result<Self> {
+        Self::with_base_url(OPENVERSE_API_URL, http)
+    }
+
+    /// Use a different Openverse-compatible API server
+    pub fn with_base_url(base_url: &str, http: &HttpSettings) -> Result<Self> {
+        let client = crate::http::client_builder(http)?
+            .timeout(Duration::from_secs(30))
+            .build()
+            .map_err(AnkiDeckBuilderError::HttpError)?;
+
+        Ok(Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            client,
+        })
+    }
+
+    /// Find up to `limit` images for a word
+    pub async fn search(&self, query: &str, limit: usize) -> Result<Vec<ImageResult>> {
+        tracing::debug!("Searching images for '{}'", query);
+
+        let url = format!("{}/images/", self.base_url);
+        let page_size = limit.to_string();
+        let params = [("q", query), ("page_size", page_size.as_str())];
+        let logged_url = crate::logging::url_with_query(&url, &params);
+        crate::logging::http_request("GET", &logged_url, None);
+
+        let started = std::time::Instant::now();
+        let response = self
+            .client
+            .get(&url)
+            .query(&params)
+            .send()
+            .await
+            .map_err(AnkiDeckBuilderError::HttpError)?;
+        crate::logging::http_response(
+            "GET",
+            &logged_url,
+            response.status().as_u16(),
+            started,
+            None,
+        );
+
+        if !response.status().is_success() {
+            return Err(AnkiDeckBuilderError::ImageSearchError(format!(
+                "Openverse returned {}",
+                response.status()
+            )));
+        }
+
+        let search: SearchResponse = response.json().await.map_err(|e| {
+            AnkiDeckBuilderError::ImageSearchError(format!("Failed to parse response: {}", e))
+        })?;
+
+        Ok(search.results.into_iter().take(limit).collect())
+    }
+
+    /// Download the thumbnail, or the full image when there is none
+    pub async fn download(&self, image: &ImageResult) -> Result<Vec<u8>> {
+        let url = image.thumbnail.as_deref().unwrap_or(&image.url);
+        crate::logging::http_request("GET", url, None);
+
+        let started = std::time::Instant::now();
+        let response = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .map_err(AnkiDeckBuilderError::HttpError)?;
+        crate::logging::http_response("GET", url, response.status().as_u16(), started, None);
+        let response = response
+            .error_for_status()
+            .map_err(AnkiDeckBuilderError::HttpError)?;
+
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(AnkiDeckBuilderError::HttpError)?;
+        Ok(bytes.to_vec())
+    }
+}
+
+pub(crate) fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::HttpSettings;
+    use mockito::Matcher;
+
+    fn image(server_url: &str) -> ImageResult {
+        ImageResult {
+            id: "abc".to_string(),
+            title: Some("House <1>".to_string()),
+            creator: Some("Ana".to_string()),
+            license: "by".to_string(),
+            license_version: Some("2.0".to_string()),
+            url: format!("{}/full.jpg", server_url),
+            thumbnail: Some(format!("{}/thumb.jpg", server_url)),
+            foreign_landing_url: None,
+        }
+    }
+
+    #[test]
+    fn test_field_html() {
+        let html = image("http://example.com").field_html("kuca_1234abcd.jpg");
+
+        assert!(html.starts_with("<img src=\"kuca_1234abcd.jpg\">"));
+        assert!(html.contains("&quot;House &lt;1&gt;&quot; by Ana (CC BY 2.0)"));
+    }
+
+    #[tokio::test]
+    async fn test_search_and_download() {
+        let mut server = mockito::Server::new_async().await;
+        let body = serde_json::json!({ "results": [image(&server.url())] });
+        server
+            .mock("GET", "/images/")
+            .match_query(Matcher::UrlEncoded("q".into(), "casa".into()))
+            .with_body(body.to_string())
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/thumb.jpg")
+            .with_body("jpeg")
+            .create_async()
+            .await;
+
+        let client = ImageClient::with_base_url(&server.url(), &HttpSettings::default()).unwrap();
+        let results = client.search("casa", 5).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(client.download(&results[0]).await.unwrap(), b"jpeg");
+    }
+}
@@ -0,0 +1,169 @@
+use crate::deadline::parse_duration;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+/// Source of the current time, so staleness can be tested without waiting
+pub trait Clock: Send + Sync {
+    fn now(&self) -> SystemTime;
+}
+
+/// The computer's own clock
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// A clock stopped at one point in time
+#[derive(Debug, Clone, Copy)]
+pub struct FixedClock(pub SystemTime);
+
+impl Clock for FixedClock {
+    fn now(&self) -> SystemTime {
+        self.0
+    }
+}
+
+/// How long cached frequency lists are trusted before upstream is asked
+/// whether they changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum CacheMaxAge {
+    After(Duration),
+    /// Cached lists are used as they are, however old
+    Never,
+}
+
+impl CacheMaxAge {
+    /// 30 days
+    pub const DEFAULT: Self = Self::After(Duration::from_secs(30 * 24 * 60 * 60));
+}
+
+impl Default for CacheMaxAge {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl std::str::FromStr for CacheMaxAge {
+    type Err = String;
+
+    /// "never", a number of days such as "7d", or a duration such as "12h"
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let s = s.trim();
+        if s == "never" {
+            return Ok(Self::Never);
+        }
+        match s.strip_suffix('d').map(str::parse::<u64>) {
+            Some(Ok(0)) => Err(format!("max age '{}' must be greater than zero", s)),
+            Some(Ok(days)) => days
+                .checked_mul(24 * 60 * 60)
+                .map(|secs| Self::After(Duration::from_secs(secs)))
+                .ok_or_else(|| format!("max age '{}' is too long", s)),
+            _ => parse_duration(s).map(Self::After),
+        }
+    }
+}
+
+impl TryFrom<String> for CacheMaxAge {
+    type Error = String;
+
+    fn try_from(s: String) -> std::result::Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<CacheMaxAge> for String {
+    fn from(max_age: CacheMaxAge) -> Self {
+        match max_age {
+            CacheMaxAge::Never => "never".to_string(),
+            CacheMaxAge::After(age) => format!("{}s", age.as_secs()),
+        }
+    }
+}
+
+/// When a cached file is due for revalidation
+#[derive(Clone)]
+pub struct CachePolicy {
+    max_age: CacheMaxAge,
+    clock: Arc<dyn Clock>,
+}
+
+impl std::fmt::Debug for CachePolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CachePolicy")
+            .field("max_age", &self.max_age)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for CachePolicy {
+    fn default() -> Self {
+        Self::new(CacheMaxAge::default())
+    }
+}
+
+impl CachePolicy {
+    pub fn new(max_age: CacheMaxAge) -> Self {
+        Self {
+            max_age,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    pub fn now(&self) -> SystemTime {
+        self.clock.now()
+    }
+
+    /// Whether a file last modified at `modified` should be revalidated. A
+    /// time in the future, as after the clock was set back or the file was
+    /// copied from another computer, can't be trusted and counts as stale
+    pub fn is_stale(&self, modified: SystemTime) -> bool {
+        let CacheMaxAge::After(max_age) = self.max_age else {
+            return false;
+        };
+        match self.clock.now().duration_since(modified) {
+            Ok(age) => age > max_age,
+            Err(_) => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+    #[test]
+    fn test_is_stale() {
+        let now = SystemTime::UNIX_EPOCH + 1000 * DAY;
+        let policy = CachePolicy::new("7d".parse().unwrap()).with_clock(FixedClock(now));
+        assert!(!policy.is_stale(now - 6 * DAY));
+        assert!(!policy.is_stale(now));
+        assert!(policy.is_stale(now - 8 * DAY));
+        assert!(policy.is_stale(now + DAY));
+
+        let policy = CachePolicy::new("never".parse().unwrap()).with_clock(FixedClock(now));
+        assert!(!policy.is_stale(now - 5000 * DAY));
+        assert!(!policy.is_stale(now + DAY));
+
+        assert_eq!(CacheMaxAge::default(), CacheMaxAge::After(30 * DAY));
+        assert!("soon".parse::<CacheMaxAge>().is_err());
+        assert!(format!("{}d", u64::MAX).parse::<CacheMaxAge>().is_err());
+        assert!("9999999999999999h".parse::<CacheMaxAge>().is_err());
+        assert_eq!(
+            "12h".parse::<CacheMaxAge>(),
+            Ok(CacheMaxAge::After(DAY / 2))
+        );
+    }
+}
@@ -0,0 +1,127 @@
+use crate::language::transliteration::transliterate;
+
+/// Words at least this similar to their translation count as cognates
+pub const DEFAULT_COGNATE_THRESHOLD: f64 = 0.7;
+
+/// Whether a word is spelled so much like its translation ("telefon" and
+/// "teléfono") that a card for it teaches little
+pub fn is_cognate(language_code: &str, word: &str, translation: &str, threshold: f64) -> bool {
+    cognate_similarity(language_code, word, translation) >= threshold
+}
+
+/// Similarity from 0 to 1 between a word and the closest of its
+/// translations: one minus their edit distance over the longer length,
+/// compared without case or accents. Words in another script are compared
+/// through their romanization
+pub fn cognate_similarity(language_code: &str, word: &str, translation: &str) -> f64 {
+    let word = transliterate(language_code, word).unwrap_or_else(|| word.to_string());
+    let word = fold(&word);
+
+    translation_alternatives(translation)
+        .map(|alternative| {
+            let alternative = fold(alternative);
+            let longest = word.chars().count().max(alternative.chars().count());
+            if longest == 0 {
+                return 0.0;
+            }
+            1.0 - edit_distance(&word, &alternative) as f64 / longest as f64
+        })
+        .fold(0.0, f64::max)
+}
+
+/// Each meaning in card text such as "es: día, jornada<br>en: day"
+pub(crate) fn translation_alternatives(translation: &str) -> impl Iterator<Item = &str> {
+    translation
+        .split("<br>")
+        .map(|line| match line.split_once(": ") {
+            Some((code, rest))
+                if code.len() <= 3 && code.chars().all(|c| c.is_ascii_lowercase()) =>
+            {
+                rest
+            }
+            _ => line,
+        })
+        .flat_map(|line| line.split([',', ';', '/']))
+        .map(str::trim)
+        .filter(|alternative| !alternative.is_empty())
+}
+
+/// Lowercase with accents and other diacritics on Latin letters removed
+pub(crate) fn fold(text: &str) -> String {
+    text.to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => 'a',
+            'ç' | 'ć' | 'č' | 'ĉ' | 'ċ' => 'c',
+            'ď' | 'đ' => 'd',
+            'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ė' | 'ę' | 'ě' => 'e',
+            'ğ' | 'ģ' => 'g',
+            'ì' | 'í' | 'î' | 'ï' | 'ī' | 'į' | 'ı' => 'i',
+            'ķ' => 'k',
+            'ĺ' | 'ļ' | 'ľ' | 'ł' => 'l',
+            'ñ' | 'ń' | 'ņ' | 'ň' => 'n',
+            'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ő' => 'o',
+            'ŕ' | 'ř' => 'r',
+            'ś' | 'ş' | 'š' | 'ș' => 's',
+            'ţ' | 'ť' | 'ț' => 't',
+            'ù' | 'ú' | 'û' | 'ü' | 'ū' | 'ů' | 'ű' | 'ų' => 'u',
+            'ý' | 'ÿ' => 'y',
+            'ź' | 'ż' | 'ž' => 'z',
+            other => other,
+        })
+        .collect()
+}
+
+/// Levenshtein distance in characters
+pub(crate) fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "dan"), 3);
+        assert_eq!(edit_distance(&fold("Teléfono"), "telefono"), 0);
+    }
+
+    #[test]
+    fn test_is_cognate() {
+        assert!(is_cognate(
+            "hr",
+            "telefon",
+            "teléfono",
+            DEFAULT_COGNATE_THRESHOLD
+        ));
+        assert!(is_cognate(
+            "ru",
+            "телефон",
+            "teléfono",
+            DEFAULT_COGNATE_THRESHOLD
+        ));
+        assert!(!is_cognate("hr", "dan", "día", DEFAULT_COGNATE_THRESHOLD));
+        // A stricter threshold keeps near misses
+        assert!(!is_cognate("hr", "telefon", "teléfono", 0.9));
+    }
+
+    #[test]
+    fn test_translation_alternatives() {
+        let alternatives: Vec<_> =
+            translation_alternatives("es: día, jornada<br>en: day").collect();
+        assert_eq!(alternatives, vec!["día", "jornada", "day"]);
+    }
+}
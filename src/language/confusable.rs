@@ -0,0 +1,95 @@
+use crate::language::cognate::{edit_distance, fold};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Words shorter than this are one letter apart from too many others
+/// ("je", "ja", "da") for a single-letter difference to be worth a warning.
+/// Diacritics alone still count
+const MIN_CONFUSABLE_LENGTH: usize = 4;
+
+/// Whether a learner might mix up two different words: they are spelled
+/// alike but for diacritics ("kuća" and "kuca"), or one letter apart ("sestra"
+/// and "sestre")
+pub fn are_confusable(a: &str, b: &str) -> bool {
+    let (a, b) = (a.to_lowercase(), b.to_lowercase());
+    if a == b {
+        return false;
+    }
+    if fold(&a) == fold(&b) {
+        return true;
+    }
+    a.chars().count().min(b.chars().count()) >= MIN_CONFUSABLE_LENGTH && edit_distance(&a, &b) == 1
+}
+
+/// Each of `words` that can be mixed up with another of them, and those it
+/// can be mixed up with, in order
+pub fn find_confusables<'a>(
+    words: impl IntoIterator<Item = &'a str>,
+) -> BTreeMap<String, Vec<String>> {
+    let words: BTreeSet<&str> = words.into_iter().collect();
+
+    // Words one edit apart share the word with one letter left out, and words
+    // differing only in diacritics share their folded form, so only words
+    // sharing a key need comparing
+    let mut by_key: HashMap<String, Vec<&str>> = HashMap::new();
+    for &word in &words {
+        let lower: Vec<char> = word.to_lowercase().chars().collect();
+        let mut keys: BTreeSet<String> = (0..lower.len())
+            .map(|skip| {
+                lower
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| *i != skip)
+                    .map(|(_, c)| c)
+                    .collect()
+            })
+            .collect();
+        keys.insert(lower.iter().collect());
+        keys.insert(format!("\u{0}{}", fold(word)));
+        for key in keys {
+            by_key.entry(key).or_default().push(word);
+        }
+    }
+
+    let mut confusables: BTreeMap<String, BTreeSet<&str>> = BTreeMap::new();
+    for group in by_key.values().filter(|group| group.len() > 1) {
+        for (i, &a) in group.iter().enumerate() {
+            for &b in &group[i + 1..] {
+                if are_confusable(a, b) {
+                    confusables.entry(a.to_string()).or_default().insert(b);
+                    confusables.entry(b.to_string()).or_default().insert(a);
+                }
+            }
+        }
+    }
+    confusables
+        .into_iter()
+        .map(|(word, others)| (word, others.into_iter().map(str::to_string).collect()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_are_confusable() {
+        assert!(are_confusable("kuća", "kuca"));
+        assert!(are_confusable("sestra", "sestre"));
+        assert!(are_confusable("grad", "grah"));
+        assert!(are_confusable("kosa", "kosta"));
+        assert!(!are_confusable("dan", "dah"));
+        assert!(!are_confusable("Dan", "dan"));
+        assert!(!are_confusable("kuća", "kuhati"));
+    }
+
+    #[test]
+    fn test_find_confusables() {
+        let confusables = find_confusables(["kuća", "kuca", "kuča", "grad", "grah", "voda", "je"]);
+
+        assert_eq!(confusables["kuća"], ["kuca", "kuča"]);
+        assert_eq!(confusables["kuca"], ["kuća", "kuča"]);
+        assert_eq!(confusables["grad"], ["grah"]);
+        assert!(!confusables.contains_key("voda"));
+        assert!(!confusables.contains_key("je"));
+    }
+}
@@ -0,0 +1,415 @@
+//! Present-tense conjugation of verbs from their infinitive, using each
+//! language's regular endings plus a table of common irregular verbs.
+//! Croatian, Spanish, and Italian are covered. Verbs that follow no rule
+//! here (such as Spanish reflexives) get no conjugation rather than a wrong one
+
+/// Languages with a conjugator
+const CONJUGATED_LANGUAGES: &[&str] = &["hr", "es", "it"];
+
+/// Present-tense forms of a verb, one per person: first, second, and third
+/// person singular, then the same in plural
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conjugation {
+    pub forms: Vec<(&'static str, String)>,
+}
+
+impl Conjugation {
+    fn new(pronouns: &[&'static str; 6], forms: [String; 6]) -> Self {
+        Self {
+            forms: pronouns.iter().copied().zip(forms).collect(),
+        }
+    }
+
+    /// One "pronoun form" per line, separated by `<br>` like other fields
+    pub fn to_field(&self) -> String {
+        self.forms
+            .iter()
+            .map(|(pronoun, form)| format!("{} {}", pronoun, form))
+            .collect::<Vec<_>>()
+            .join("<br>")
+    }
+}
+
+/// Whether verbs in this language can be conjugated
+pub fn has_conjugation(language_code: &str) -> bool {
+    CONJUGATED_LANGUAGES.contains(&language_code)
+}
+
+/// Present tense of a verb given in its infinitive. None when the language
+/// has no conjugator or the word doesn't look like an infinitive it handles
+pub fn conjugate(language_code: &str, infinitive: &str) -> Option<Conjugation> {
+    let verb = infinitive.trim().to_lowercase();
+    let (pronouns, forms) = match language_code {
+        "hr" => (&CROATIAN_PRONOUNS, croatian(&verb)?),
+        "es" => (&SPANISH_PRONOUNS, spanish(&verb)?),
+        "it" => (&ITALIAN_PRONOUNS, italian(&verb)?),
+        _ => return None,
+    };
+    Some(Conjugation::new(pronouns, forms))
+}
+
+const CROATIAN_PRONOUNS: [&str; 6] = ["ja", "ti", "on/ona", "mi", "vi", "oni"];
+
+const CROATIAN_IRREGULAR: &[(&str, [&str; 6])] = &[
+    ("biti", ["sam", "si", "je", "smo", "ste", "su"]),
+    (
+        "htjeti",
+        ["hoću", "hoćeš", "hoće", "hoćemo", "hoćete", "hoće"],
+    ),
+    (
+        "moći",
+        ["mogu", "možeš", "može", "možemo", "možete", "mogu"],
+    ),
+    ("ići", ["idem", "ideš", "ide", "idemo", "idete", "idu"]),
+    (
+        "doći",
+        ["dođem", "dođeš", "dođe", "dođemo", "dođete", "dođu"],
+    ),
+    (
+        "reći",
+        ["kažem", "kažeš", "kaže", "kažemo", "kažete", "kažu"],
+    ),
+    (
+        "jesti",
+        ["jedem", "jedeš", "jede", "jedemo", "jedete", "jedu"],
+    ),
+    (
+        "piti",
+        ["pijem", "piješ", "pije", "pijemo", "pijete", "piju"],
+    ),
+    (
+        "pisati",
+        ["pišem", "pišeš", "piše", "pišemo", "pišete", "pišu"],
+    ),
+    (
+        "uzeti",
+        ["uzmem", "uzmeš", "uzme", "uzmemo", "uzmete", "uzmu"],
+    ),
+    (
+        "početi",
+        ["počnem", "počneš", "počne", "počnemo", "počnete", "počnu"],
+    ),
+    (
+        "razumjeti",
+        [
+            "razumijem",
+            "razumiješ",
+            "razumije",
+            "razumijemo",
+            "razumijete",
+            "razumiju",
+        ],
+    ),
+];
+
+fn croatian(verb: &str) -> Option<[String; 6]> {
+    if let Some(forms) = irregular(CROATIAN_IRREGULAR, verb) {
+        return Some(forms);
+    }
+
+    // Longest suffixes first: "-ovati" is also an "-ati" verb
+    const CLASSES: &[(&str, [&str; 6])] = &[
+        ("ovati", ["ujem", "uješ", "uje", "ujemo", "ujete", "uju"]),
+        ("evati", ["ujem", "uješ", "uje", "ujemo", "ujete", "uju"]),
+        ("nuti", ["nem", "neš", "ne", "nemo", "nete", "nu"]),
+        ("jeti", ["im", "iš", "i", "imo", "ite", "e"]),
+        ("iti", ["im", "iš", "i", "imo", "ite", "e"]),
+        ("ati", ["am", "aš", "a", "amo", "ate", "aju"]),
+    ];
+    regular(CLASSES, verb, 2)
+}
+
+const SPANISH_PRONOUNS: [&str; 6] = ["yo", "tú", "él/ella", "nosotros", "vosotros", "ellos"];
+
+/// Irregular and stem-changing verbs, which the endings alone get wrong
+const SPANISH_IRREGULAR: &[(&str, [&str; 6])] = &[
+    ("ser", ["soy", "eres", "es", "somos", "sois", "son"]),
+    (
+        "estar",
+        ["estoy", "estás", "está", "estamos", "estáis", "están"],
+    ),
+    ("ir", ["voy", "vas", "va", "vamos", "vais", "van"]),
+    ("haber", ["he", "has", "ha", "hemos", "habéis", "han"]),
+    (
+        "tener",
+        ["tengo", "tienes", "tiene", "tenemos", "tenéis", "tienen"],
+    ),
+    (
+        "hacer",
+        ["hago", "haces", "hace", "hacemos", "hacéis", "hacen"],
+    ),
+    (
+        "poder",
+        ["puedo", "puedes", "puede", "podemos", "podéis", "pueden"],
+    ),
+    (
+        "decir",
+        ["digo", "dices", "dice", "decimos", "decís", "dicen"],
+    ),
+    (
+        "querer",
+        [
+            "quiero", "quieres", "quiere", "queremos", "queréis", "quieren",
+        ],
+    ),
+    (
+        "saber",
+        ["sé", "sabes", "sabe", "sabemos", "sabéis", "saben"],
+    ),
+    (
+        "venir",
+        ["vengo", "vienes", "viene", "venimos", "venís", "vienen"],
+    ),
+    ("dar", ["doy", "das", "da", "damos", "dais", "dan"]),
+    ("ver", ["veo", "ves", "ve", "vemos", "veis", "ven"]),
+    (
+        "poner",
+        ["pongo", "pones", "pone", "ponemos", "ponéis", "ponen"],
+    ),
+    (
+        "salir",
+        ["salgo", "sales", "sale", "salimos", "salís", "salen"],
+    ),
+    (
+        "conocer",
+        [
+            "conozco",
+            "conoces",
+            "conoce",
+            "conocemos",
+            "conocéis",
+            "conocen",
+        ],
+    ),
+    (
+        "pensar",
+        [
+            "pienso", "piensas", "piensa", "pensamos", "pensáis", "piensan",
+        ],
+    ),
+    (
+        "empezar",
+        [
+            "empiezo",
+            "empiezas",
+            "empieza",
+            "empezamos",
+            "empezáis",
+            "empiezan",
+        ],
+    ),
+    (
+        "volver",
+        [
+            "vuelvo", "vuelves", "vuelve", "volvemos", "volvéis", "vuelven",
+        ],
+    ),
+    (
+        "encontrar",
+        [
+            "encuentro",
+            "encuentras",
+            "encuentra",
+            "encontramos",
+            "encontráis",
+            "encuentran",
+        ],
+    ),
+    (
+        "dormir",
+        [
+            "duermo", "duermes", "duerme", "dormimos", "dormís", "duermen",
+        ],
+    ),
+    (
+        "jugar",
+        ["juego", "juegas", "juega", "jugamos", "jugáis", "juegan"],
+    ),
+    (
+        "pedir",
+        ["pido", "pides", "pide", "pedimos", "pedís", "piden"],
+    ),
+    (
+        "sentir",
+        [
+            "siento", "sientes", "siente", "sentimos", "sentís", "sienten",
+        ],
+    ),
+];
+
+fn spanish(verb: &str) -> Option<[String; 6]> {
+    if let Some(forms) = irregular(SPANISH_IRREGULAR, verb) {
+        return Some(forms);
+    }
+
+    const CLASSES: &[(&str, [&str; 6])] = &[
+        ("ar", ["o", "as", "a", "amos", "áis", "an"]),
+        ("er", ["o", "es", "e", "emos", "éis", "en"]),
+        ("ir", ["o", "es", "e", "imos", "ís", "en"]),
+    ];
+    regular(CLASSES, verb, 1)
+}
+
+const ITALIAN_PRONOUNS: [&str; 6] = ["io", "tu", "lui/lei", "noi", "voi", "loro"];
+
+const ITALIAN_IRREGULAR: &[(&str, [&str; 6])] = &[
+    ("essere", ["sono", "sei", "è", "siamo", "siete", "sono"]),
+    ("avere", ["ho", "hai", "ha", "abbiamo", "avete", "hanno"]),
+    (
+        "andare",
+        ["vado", "vai", "va", "andiamo", "andate", "vanno"],
+    ),
+    ("fare", ["faccio", "fai", "fa", "facciamo", "fate", "fanno"]),
+    (
+        "dire",
+        ["dico", "dici", "dice", "diciamo", "dite", "dicono"],
+    ),
+    (
+        "potere",
+        ["posso", "puoi", "può", "possiamo", "potete", "possono"],
+    ),
+    (
+        "volere",
+        ["voglio", "vuoi", "vuole", "vogliamo", "volete", "vogliono"],
+    ),
+    (
+        "dovere",
+        ["devo", "devi", "deve", "dobbiamo", "dovete", "devono"],
+    ),
+    ("sapere", ["so", "sai", "sa", "sappiamo", "sapete", "sanno"]),
+    ("stare", ["sto", "stai", "sta", "stiamo", "state", "stanno"]),
+    ("dare", ["do", "dai", "dà", "diamo", "date", "danno"]),
+    (
+        "venire",
+        ["vengo", "vieni", "viene", "veniamo", "venite", "vengono"],
+    ),
+    (
+        "uscire",
+        ["esco", "esci", "esce", "usciamo", "uscite", "escono"],
+    ),
+];
+
+/// Common "-ire" verbs that insert "-isc-" in the singular and third plural
+const ITALIAN_ISC: &[&str] = &[
+    "capire",
+    "finire",
+    "preferire",
+    "pulire",
+    "spedire",
+    "costruire",
+    "suggerire",
+    "unire",
+];
+
+fn italian(verb: &str) -> Option<[String; 6]> {
+    if let Some(forms) = irregular(ITALIAN_IRREGULAR, verb) {
+        return Some(forms);
+    }
+    if ITALIAN_ISC.contains(&verb) {
+        let stem = &verb[..verb.len() - 3];
+        return Some(
+            ["isco", "isci", "isce", "iamo", "ite", "iscono"].map(|e| format!("{stem}{e}")),
+        );
+    }
+
+    const CLASSES: &[(&str, [&str; 6])] = &[
+        ("are", ["o", "i", "a", "iamo", "ate", "ano"]),
+        ("ere", ["o", "i", "e", "iamo", "ete", "ono"]),
+        ("ire", ["o", "i", "e", "iamo", "ite", "ono"]),
+    ];
+    let mut forms = regular(CLASSES, verb, 1)?;
+    for form in &mut forms {
+        // mangiare → mangi (not mangii); cercare → cerchi, cerchiamo
+        if let Some(stem) = form.strip_suffix("iamo").or_else(|| form.strip_suffix('i')) {
+            let ending = &form[stem.len()..];
+            if let Some(shorter) = stem.strip_suffix('i') {
+                *form = format!("{}{}", shorter, ending);
+            } else if verb.ends_with("care") || verb.ends_with("gare") {
+                *form = format!("{}h{}", stem, ending);
+            }
+        }
+    }
+    Some(forms)
+}
+
+fn irregular(table: &[(&str, [&str; 6])], verb: &str) -> Option<[String; 6]> {
+    table
+        .iter()
+        .find(|(infinitive, _)| *infinitive == verb)
+        .map(|(_, forms)| forms.map(str::to_string))
+}
+
+/// Replace the first matching infinitive ending with the class's person
+/// endings, keeping a stem at least `min_stem` letters long
+fn regular(classes: &[(&str, [&str; 6])], verb: &str, min_stem: usize) -> Option<[String; 6]> {
+    classes.iter().find_map(|(suffix, endings)| {
+        let stem = verb.strip_suffix(suffix)?;
+        (stem.chars().count() >= min_stem).then(|| endings.map(|e| format!("{stem}{e}")))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn forms(language_code: &str, verb: &str) -> Vec<String> {
+        conjugate(language_code, verb)
+            .unwrap()
+            .forms
+            .into_iter()
+            .map(|(_, form)| form)
+            .collect()
+    }
+
+    #[test]
+    fn test_croatian() {
+        assert_eq!(
+            forms("hr", "raditi"),
+            ["radim", "radiš", "radi", "radimo", "radite", "rade"]
+        );
+        assert_eq!(forms("hr", "gledati")[5], "gledaju");
+        assert_eq!(forms("hr", "kupovati")[0], "kupujem");
+        assert_eq!(forms("hr", "vidjeti")[1], "vidiš");
+        assert_eq!(forms("hr", "biti")[0], "sam");
+        assert_eq!(forms("hr", "moći")[1], "možeš");
+        // Not an infinitive
+        assert_eq!(conjugate("hr", "kuća"), None);
+    }
+
+    #[test]
+    fn test_spanish() {
+        assert_eq!(
+            forms("es", "hablar"),
+            ["hablo", "hablas", "habla", "hablamos", "habláis", "hablan"]
+        );
+        assert_eq!(forms("es", "comer")[4], "coméis");
+        assert_eq!(forms("es", "vivir")[3], "vivimos");
+        assert_eq!(forms("es", "tener")[0], "tengo");
+        assert_eq!(forms("es", "pensar")[0], "pienso");
+    }
+
+    #[test]
+    fn test_italian() {
+        assert_eq!(
+            forms("it", "parlare"),
+            ["parlo", "parli", "parla", "parliamo", "parlate", "parlano"]
+        );
+        assert_eq!(forms("it", "mangiare")[1], "mangi");
+        assert_eq!(forms("it", "mangiare")[3], "mangiamo");
+        assert_eq!(forms("it", "cercare")[1], "cerchi");
+        assert_eq!(forms("it", "pagare")[3], "paghiamo");
+        assert_eq!(forms("it", "finire")[0], "finisco");
+        assert_eq!(forms("it", "dormire")[5], "dormono");
+        assert_eq!(forms("it", "essere")[2], "è");
+    }
+
+    #[test]
+    fn test_field_and_languages() {
+        let conjugation = conjugate("es", "hablar").unwrap();
+        assert!(conjugation
+            .to_field()
+            .starts_with("yo hablo<br>tú hablas<br>"));
+        assert!(has_conjugation("hr"));
+        assert_eq!(conjugate("ja", "taberu"), None);
+    }
+}
@@ -0,0 +1,279 @@
+//! Calendar and clock words: the days of the week, the months, and the hours
+//! on the clock, both on the hour and half past, with their English glosses
+
+use crate::language::frequency::{PartOfSpeech, Word};
+use crate::language::numbers::{cardinal, has_number_words};
+use crate::language::sections::GeneratedWord;
+
+/// Whether dates and times can be generated for the language
+pub fn has_date_words(language_code: &str) -> bool {
+    has_number_words(language_code)
+}
+
+/// Monday to Sunday, January to December, then the hours one to twelve and
+/// the half hours after them. Empty for languages without rules
+pub fn date_words(language_code: &str) -> Vec<GeneratedWord> {
+    if !has_date_words(language_code) {
+        return Vec::new();
+    }
+    let names = weekdays(language_code)
+        .iter()
+        .zip(weekdays("en"))
+        .chain(months(language_code).iter().zip(months("en")))
+        .map(|(word, gloss)| (word.to_string(), gloss.to_string(), PartOfSpeech::Noun));
+    let times = (1..=12).flat_map(|hour| {
+        [
+            (
+                on_the_hour(language_code, hour),
+                on_the_hour("en", hour),
+                PartOfSpeech::Phrase,
+            ),
+            (
+                half_past(language_code, hour),
+                half_past("en", hour),
+                PartOfSpeech::Phrase,
+            ),
+        ]
+    });
+
+    names
+        .chain(times)
+        .enumerate()
+        .map(|(i, (text, gloss, pos))| GeneratedWord {
+            word: Word::new(text, pos, i + 1),
+            gloss,
+        })
+        .collect()
+}
+
+fn weekdays(language_code: &str) -> &'static [&'static str; 7] {
+    match language_code {
+        "hr" => &[
+            "ponedjeljak",
+            "utorak",
+            "srijeda",
+            "četvrtak",
+            "petak",
+            "subota",
+            "nedjelja",
+        ],
+        "es" => &[
+            "lunes",
+            "martes",
+            "miércoles",
+            "jueves",
+            "viernes",
+            "sábado",
+            "domingo",
+        ],
+        "fr" => &[
+            "lundi", "mardi", "mercredi", "jeudi", "vendredi", "samedi", "dimanche",
+        ],
+        "de" => &[
+            "Montag",
+            "Dienstag",
+            "Mittwoch",
+            "Donnerstag",
+            "Freitag",
+            "Samstag",
+            "Sonntag",
+        ],
+        "it" => &[
+            "lunedì",
+            "martedì",
+            "mercoledì",
+            "giovedì",
+            "venerdì",
+            "sabato",
+            "domenica",
+        ],
+        "pt" => &[
+            "segunda-feira",
+            "terça-feira",
+            "quarta-feira",
+            "quinta-feira",
+            "sexta-feira",
+            "sábado",
+            "domingo",
+        ],
+        _ => &[
+            "Monday",
+            "Tuesday",
+            "Wednesday",
+            "Thursday",
+            "Friday",
+            "Saturday",
+            "Sunday",
+        ],
+    }
+}
+
+fn months(language_code: &str) -> &'static [&'static str; 12] {
+    match language_code {
+        // Croatian months have Slavic names of their own
+        "hr" => &[
+            "siječanj",
+            "veljača",
+            "ožujak",
+            "travanj",
+            "svibanj",
+            "lipanj",
+            "srpanj",
+            "kolovoz",
+            "rujan",
+            "listopad",
+            "studeni",
+            "prosinac",
+        ],
+        "es" => &[
+            "enero",
+            "febrero",
+            "marzo",
+            "abril",
+            "mayo",
+            "junio",
+            "julio",
+            "agosto",
+            "septiembre",
+            "octubre",
+            "noviembre",
+            "diciembre",
+        ],
+        "fr" => &[
+            "janvier",
+            "février",
+            "mars",
+            "avril",
+            "mai",
+            "juin",
+            "juillet",
+            "août",
+            "septembre",
+            "octobre",
+            "novembre",
+            "décembre",
+        ],
+        "de" => &[
+            "Januar",
+            "Februar",
+            "März",
+            "April",
+            "Mai",
+            "Juni",
+            "Juli",
+            "August",
+            "September",
+            "Oktober",
+            "November",
+            "Dezember",
+        ],
+        "it" => &[
+            "gennaio",
+            "febbraio",
+            "marzo",
+            "aprile",
+            "maggio",
+            "giugno",
+            "luglio",
+            "agosto",
+            "settembre",
+            "ottobre",
+            "novembre",
+            "dicembre",
+        ],
+        "pt" => &[
+            "janeiro",
+            "fevereiro",
+            "março",
+            "abril",
+            "maio",
+            "junho",
+            "julho",
+            "agosto",
+            "setembro",
+            "outubro",
+            "novembro",
+            "dezembro",
+        ],
+        _ => &[
+            "January",
+            "February",
+            "March",
+            "April",
+            "May",
+            "June",
+            "July",
+            "August",
+            "September",
+            "October",
+            "November",
+            "December",
+        ],
+    }
+}
+
+/// The hour, 1 to 12, as said on the clock: "las dos", "zwei Uhr"
+fn on_the_hour(language_code: &str, hour: usize) -> String {
+    match (language_code, hour) {
+        ("hr", 1) => "jedan sat".to_string(),
+        ("hr", 2..=4) => format!("{} sata", cardinal("hr", hour)),
+        ("hr", _) => format!("{} sati", cardinal("hr", hour)),
+        ("es", 1) => "la una".to_string(),
+        ("es", _) => format!("las {}", cardinal("es", hour)),
+        ("fr", 1) => "une heure".to_string(),
+        ("fr", _) => format!("{} heures", cardinal("fr", hour)),
+        ("de", 1) => "ein Uhr".to_string(),
+        ("de", _) => format!("{} Uhr", cardinal("de", hour)),
+        ("it", 1) => "l'una".to_string(),
+        ("it", _) => format!("le {}", cardinal("it", hour)),
+        ("pt", 1) => "uma hora".to_string(),
+        ("pt", 2) => "duas horas".to_string(),
+        ("pt", _) => format!("{} horas", cardinal("pt", hour)),
+        _ => format!("{} o'clock", cardinal("en", hour)),
+    }
+}
+
+/// Thirty minutes after the hour. Croatian and German count towards the
+/// next hour instead: "pola tri" and "halb drei" are half past two
+fn half_past(language_code: &str, hour: usize) -> String {
+    let next = hour % 12 + 1;
+    match language_code {
+        "hr" => format!("pola {}", cardinal("hr", next)),
+        "de" => format!("halb {}", cardinal("de", next)),
+        "es" => format!("{} y media", on_the_hour("es", hour)),
+        "fr" => format!("{} et demie", on_the_hour("fr", hour)),
+        "it" => format!("{} e mezza", on_the_hour("it", hour)),
+        "pt" => match hour {
+            1 => "uma e meia".to_string(),
+            2 => "duas e meia".to_string(),
+            _ => format!("{} e meia", cardinal("pt", hour)),
+        },
+        _ => format!("half past {}", cardinal("en", hour)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_date_words() {
+        let words = date_words("de");
+        assert_eq!(words.len(), 7 + 12 + 24);
+        assert_eq!(words[0].word.text, "Montag");
+        assert_eq!(words[0].gloss, "Monday");
+        assert_eq!(words[7].word.text, "Januar");
+        assert_eq!(words[19].word.text, "ein Uhr");
+        assert_eq!(words[19].word.pos, PartOfSpeech::Phrase);
+        assert_eq!(words[20].word.text, "halb zwei");
+        assert_eq!(words[20].gloss, "half past one");
+
+        assert_eq!(on_the_hour("hr", 3), "tri sata");
+        assert_eq!(on_the_hour("hr", 5), "pet sati");
+        assert_eq!(half_past("hr", 12), "pola jedan");
+        assert_eq!(half_past("es", 1), "la una y media");
+        assert_eq!(half_past("fr", 2), "deux heures et demie");
+        assert_eq!(half_past("pt", 3), "três e meia");
+        assert!(date_words("ja").is_empty());
+    }
+}
@@ -0,0 +1,315 @@
+use crate::ankiweb::models::strip_html;
+use crate::cache_file;
+use crate::error::{AnkiDeckBuilderError, Result};
+use crate::http::HttpSettings;
+use crate::language::packs::LanguagePacks;
+use reqwest::Client;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// English Wiktionary's API, whose inflection tables mark every form with
+/// its case and number
+const WIKTIONARY_API_URL: &str = "https://en.wiktionary.org/w/api.php";
+
+/// Cache directory of declension lookups
+const DECLENSION_CACHE: &str = "declensions";
+
+/// Languages with noun cases, and the code Wiktionary files them under
+/// (Croatian and Serbian entries are Serbo-Croatian)
+const DECLINED_LANGUAGES: &[(&str, &str)] = &[
+    ("hr", "sh"),
+    ("sr", "sh"),
+    ("ru", "ru"),
+    ("uk", "uk"),
+    ("pl", "pl"),
+    ("de", "de"),
+    ("fi", "fi"),
+];
+
+/// Forms of a noun beyond its dictionary form
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Declension {
+    pub genitive_singular: Option<String>,
+    pub nominative_plural: Option<String>,
+}
+
+impl Declension {
+    /// One labelled form per line, separated by `<br>` like other fields
+    pub fn to_field(&self) -> String {
+        [
+            ("gen. sg.", &self.genitive_singular),
+            ("nom. pl.", &self.nominative_plural),
+        ]
+        .iter()
+        .filter_map(|(label, form)| form.as_ref().map(|form| format!("{} {}", label, form)))
+        .collect::<Vec<_>>()
+        .join("<br>")
+    }
+}
+
+/// Whether nouns in this language can be declined
+pub fn has_declension(language_code: &str) -> bool {
+    wiktionary_code(language_code).is_some()
+}
+
+fn wiktionary_code(language_code: &str) -> Option<&'static str> {
+    DECLINED_LANGUAGES
+        .iter()
+        .find(|(code, _)| *code == language_code)
+        .map(|(_, wiktionary)| *wiktionary)
+}
+
+#[derive(Deserialize)]
+struct ParseResponse {
+    parse: Option<ParsedPage>,
+}
+
+#[derive(Deserialize)]
+struct ParsedPage {
+    text: String,
+}
+
+/// Looks up noun declensions in Wiktionary's inflection tables. Lookups are
+/// cached per language, including words Wiktionary has no table for
+#[derive(Debug, Clone)]
+pub struct WiktionaryClient {
+    base_url: String,
+    client: Client,
+    cache_dir: Option<PathBuf>,
+    /// For the section names of the packs' languages
+    pub(crate) packs: LanguagePacks,
+}
+
+impl WiktionaryClient {
+    pub fn new(cache_dir: Option<PathBuf>, http: &HttpSettings) -> Result<Self> {
+        Self::with_base_url(WIKTIONARY_API_URL, cache_dir, http)
+    }
+
+    /// Use a different MediaWiki API endpoint
+    pub fn with_base_url(
+        base_url: &str,
+        cache_dir: Option<PathBuf>,
+        http: &HttpSettings,
+    ) -> Result<Self> {
+        let client = crate::http::client_builder(http)?
+            .timeout(Duration::from_secs(30))
+            .user_agent(concat!("anki-deck-builder/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .map_err(AnkiDeckBuilderError::HttpError)?;
+
+        Ok(Self {
+            base_url: base_url.to_string(),
+            client,
+            cache_dir,
+            packs: LanguagePacks::default(),
+        })
+    }
+
+    /// Find the entries of the packs' languages too, under their names
+    pub fn with_language_packs(mut self, packs: LanguagePacks) -> Self {
+        self.packs = packs;
+        self
+    }
+
+    /// Genitive singular and nominative plural of a noun. None when the
+    /// language has no cases here or Wiktionary has no table for the word
+    pub async fn decline(&self, language_code: &str, noun: &str) -> Result<Option<Declension>> {
+        let Some(wiktionary) = wiktionary_code(language_code) else {
+            return Ok(None);
+        };
+
+        let mut cache: HashMap<String, Option<Declension>> =
+            self.load_cache(DECLENSION_CACHE, language_code);
+        if let Some(cached) = cache.get(noun) {
+            return Ok(cached.clone());
+        }
+
+        tracing::debug!("Looking up declension of '{}' on Wiktionary", noun);
+        let declension = self
+            .fetch_page(noun)
+            .await?
+            .and_then(|html| parse_inflection_table(&html, wiktionary));
+        cache.insert(noun.to_string(), declension.clone());
+        if let Err(e) = self.save_cache(DECLENSION_CACHE, language_code, &cache) {
+            tracing::warn!("Failed to cache declension of '{}': {}", noun, e);
+        }
+        Ok(declension)
+    }
+
+    /// Rendered HTML of a page, or None when Wiktionary has no such page
+    pub(crate) async fn fetch_page(&self, title: &str) -> Result<Option<String>> {
+        let query = [
+            ("action", "parse"),
+            ("page", title),
+            ("prop", "text"),
+            ("format", "json"),
+            ("formatversion", "2"),
+        ];
+        let url = crate::logging::url_with_query(&self.base_url, &query);
+        crate::logging::http_request("GET", &url, None);
+
+        let started = std::time::Instant::now();
+        let response = self
+            .client
+            .get(&self.base_url)
+            .query(&query)
+            .send()
+            .await
+            .map_err(AnkiDeckBuilderError::HttpError)?;
+        crate::logging::http_response("GET", &url, response.status().as_u16(), started, None);
+        let response = response
+            .error_for_status()
+            .map_err(AnkiDeckBuilderError::HttpError)?;
+        let page: ParseResponse = response.json().await.map_err(|e| {
+            AnkiDeckBuilderError::WiktionaryError(format!("Failed to parse response: {}", e))
+        })?;
+
+        // A missing page comes back as an error object instead of a page
+        Ok(page.parse.map(|page| page.text))
+    }
+
+    fn cache_file(&self, kind: &str, language_code: &str) -> Option<PathBuf> {
+        self.cache_dir
+            .as_ref()
+            .map(|dir| dir.join(kind).join(format!("{}.json", language_code)))
+    }
+
+    /// Earlier lookups of one kind for a language, by word
+    pub(crate) fn load_cache<T: DeserializeOwned>(
+        &self,
+        kind: &str,
+        language_code: &str,
+    ) -> HashMap<String, T> {
+        self.cache_file(kind, language_code)
+            .and_then(|file| cache_file::read_json(&file))
+            .unwrap_or_default()
+    }
+
+    /// Add `cache` to the file, keeping words another run saved meanwhile
+    pub(crate) fn save_cache<T: Serialize + DeserializeOwned + Clone>(
+        &self,
+        kind: &str,
+        language_code: &str,
+        cache: &HashMap<String, T>,
+    ) -> Result<()> {
+        let Some(file) = self.cache_file(kind, language_code) else {
+            return Ok(());
+        };
+        cache_file::update_json(&file, |saved: &mut HashMap<String, T>| {
+            saved.extend(
+                cache
+                    .iter()
+                    .map(|(word, entry)| (word.clone(), entry.clone())),
+            );
+        })
+    }
+}
+
+/// Pick forms out of a rendered Wiktionary page. Table cells carry classes
+/// such as `form-of lang-sh gen|s-form-of`, naming the language and the
+/// grammatical tags of the form; the page's other languages are ignored
+fn parse_inflection_table(html: &str, wiktionary_code: &str) -> Option<Declension> {
+    let declension = Declension {
+        genitive_singular: find_form(html, wiktionary_code, &["gen", "s"]),
+        nominative_plural: find_form(html, wiktionary_code, &["nom", "p"]),
+    };
+    (declension.genitive_singular.is_some() || declension.nominative_plural.is_some())
+        .then_some(declension)
+}
+
+/// Text of the first form of the language carrying all the tags
+fn find_form(html: &str, wiktionary_code: &str, tags: &[&str]) -> Option<String> {
+    let language = format!("lang-{}", wiktionary_code);
+    let mut rest = html;
+    while let Some(start) = rest.find("class=\"") {
+        rest = &rest[start + 7..];
+        let end = rest.find('"')?;
+        let classes: Vec<&str> = rest[..end].split_whitespace().collect();
+        rest = &rest[end..];
+
+        let matches = classes.contains(&"form-of")
+            && classes.contains(&language.as_str())
+            && classes.iter().any(|class| {
+                class.strip_suffix("-form-of").is_some_and(|form_tags| {
+                    let form_tags: Vec<&str> = form_tags.split(['|', '/']).collect();
+                    tags.iter().all(|tag| form_tags.contains(tag))
+                })
+            });
+        if !matches {
+            continue;
+        }
+
+        let content = &rest[rest.find('>')? + 1..];
+        let content = &content[..content.find("</span>")?];
+        let form = strip_html(content).trim().to_string();
+        if !form.is_empty() {
+            return Some(form);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::HttpSettings;
+    use mockito::Matcher;
+    use tempfile::tempdir;
+
+    const PAGE: &str = r#"<h2 id="Czech">Czech</h2>
+        <span class="Latn form-of lang-cs gen|s-form-of" lang="cs"><a href="/wiki/x">x</a></span>
+        <h2 id="Serbo-Croatian">Serbo-Croatian</h2>
+        <table class="inflection-table">
+        <tr><th>nominative</th><td><span class="Latn" lang="sh">kuća</span></td>
+        <td><span class="Latn form-of lang-sh nom//voc|p-form-of" lang="sh"><a href="/wiki/ku%C4%87e">kuće</a></span></td></tr>
+        <tr><th>genitive</th><td><span class="Latn form-of lang-sh gen|s-form-of" lang="sh"><a href="/wiki/ku%C4%87e">kuće</a></span></td>
+        <td><span class="Latn form-of lang-sh gen|p-form-of" lang="sh">kuća</span></td></tr>
+        </table>"#;
+
+    #[test]
+    fn test_parse_inflection_table() {
+        let declension = parse_inflection_table(PAGE, "sh").unwrap();
+        assert_eq!(declension.genitive_singular.as_deref(), Some("kuće"));
+        assert_eq!(declension.nominative_plural.as_deref(), Some("kuće"));
+        assert_eq!(declension.to_field(), "gen. sg. kuće<br>nom. pl. kuće");
+
+        assert_eq!(parse_inflection_table(PAGE, "ru"), None);
+    }
+
+    #[tokio::test]
+    async fn test_decline_is_cached() {
+        let mut server = mockito::Server::new_async().await;
+        let lookup = server
+            .mock("GET", "/")
+            .match_query(Matcher::UrlEncoded("page".into(), "kuća".into()))
+            .with_body(serde_json::json!({ "parse": { "text": PAGE } }).to_string())
+            .expect(1)
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/")
+            .match_query(Matcher::UrlEncoded("page".into(), "xyz".into()))
+            .with_body(r#"{"error":{"code":"missingtitle"}}"#)
+            .create_async()
+            .await;
+
+        let temp_dir = tempdir().unwrap();
+        let client = WiktionaryClient::with_base_url(
+            &server.url(),
+            Some(temp_dir.path().to_path_buf()),
+            &HttpSettings::default(),
+        )
+        .unwrap();
+        for _ in 0..2 {
+            let declension = client.decline("hr", "kuća").await.unwrap().unwrap();
+            assert_eq!(declension.genitive_singular.as_deref(), Some("kuće"));
+        }
+        lookup.assert_async().await;
+
+        assert_eq!(client.decline("hr", "xyz").await.unwrap(), None);
+        assert_eq!(client.decline("es", "casa").await.unwrap(), None);
+    }
+}
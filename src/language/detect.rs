@@ -0,0 +1,241 @@
+use crate::error::{AnkiDeckBuilderError, Result};
+use crate::language::frequency::FrequencyData;
+use crate::language::packs::LanguagePacks;
+use std::collections::HashSet;
+
+/// Number of top-ranked words inspected
+const SAMPLE_SIZE: usize = 500;
+
+/// Lists shorter than this are too small to judge
+const MIN_SAMPLE: usize = 50;
+
+/// Share of a profile that must match for a language to be recognised
+const MIN_SCORE: f64 = 0.4;
+
+/// What identifies a language in the top of a frequency list
+enum Profile {
+    /// Most words are written in the language's own script
+    Script(fn(char) -> bool),
+    /// Its most common function words all rank highly
+    Stopwords(&'static [&'static str]),
+    /// The stopwords a language pack lists
+    PackStopwords(Vec<String>),
+}
+
+fn profile(language_code: &str, packs: &LanguagePacks) -> Option<Profile> {
+    use Profile::*;
+
+    if let Some(pack) = packs
+        .get(language_code)
+        .filter(|pack| !pack.stopwords.is_empty())
+    {
+        return Some(PackStopwords(pack.stopwords.clone()));
+    }
+    Some(match language_code {
+        "hr" => Stopwords(&[
+            "je", "da", "se", "na", "to", "ne", "što", "za", "su", "ja", "ti", "ali", "sam", "si",
+            "mi", "ovo", "kako", "ima", "bi", "od",
+        ]),
+        "es" => Stopwords(&[
+            "de", "la", "que", "el", "en", "los", "se", "no", "por", "un", "una", "es", "con",
+            "para", "lo", "las", "pero", "mi", "me", "esta",
+        ]),
+        "en" => Stopwords(&[
+            "the", "and", "you", "to", "of", "is", "it", "that", "in", "for", "what", "this", "on",
+            "have", "with", "be", "me", "not", "are", "do",
+        ]),
+        "fr" => Stopwords(&[
+            "de", "la", "le", "et", "les", "je", "est", "pas", "que", "un", "vous", "une", "il",
+            "en", "ne", "des", "pour", "qui", "ce", "dans",
+        ]),
+        "de" => Stopwords(&[
+            "der", "die", "und", "das", "ist", "ich", "nicht", "du", "zu", "den", "es", "sie",
+            "mit", "ein", "sich", "auf", "wir", "von", "eine", "dem",
+        ]),
+        "it" => Stopwords(&[
+            "di", "che", "il", "non", "la", "un", "per", "sono", "mi", "ho", "una", "in", "ma",
+            "lo", "si", "ti", "le", "con", "del", "della",
+        ]),
+        "pt" => Stopwords(&[
+            "de", "que", "não", "um", "para", "com", "uma", "os", "no", "se", "na", "por", "mais",
+            "as", "do", "da", "em", "você", "eu", "ao",
+        ]),
+        "nl" => Stopwords(&[
+            "de", "het", "een", "van", "en", "ik", "je", "is", "dat", "niet", "te", "die", "op",
+            "in", "met", "zijn", "voor", "wat", "maar", "er",
+        ]),
+        "pl" => Stopwords(&[
+            "nie", "się", "to", "że", "na", "jest", "jak", "co", "ale", "tak", "do", "mi", "za",
+            "ja", "czy", "po", "już", "mnie", "tylko", "ten",
+        ]),
+        "sv" => Stopwords(&[
+            "och", "det", "att", "är", "en", "som", "jag", "på", "inte", "för", "med", "du", "har",
+            "av", "den", "till", "om", "vi", "kan", "ett",
+        ]),
+        "no" => Stopwords(&[
+            "og", "det", "er", "jeg", "en", "til", "på", "ikke", "som", "du", "har", "med", "at",
+            "for", "av", "vi", "den", "kan", "meg", "han",
+        ]),
+        "da" => Stopwords(&[
+            "og", "det", "er", "jeg", "at", "en", "til", "på", "ikke", "du", "har", "med", "som",
+            "for", "den", "vi", "kan", "af", "mig", "han",
+        ]),
+        "fi" => Stopwords(&[
+            "ja", "on", "ei", "että", "se", "oli", "mitä", "en", "hän", "mutta", "ole", "niin",
+            "kun", "minä", "sinä", "tämä", "kuin", "jos", "vain", "nyt",
+        ]),
+        "tr" => Stopwords(&[
+            "bir", "ve", "bu", "da", "de", "ne", "için", "çok", "ben", "mi", "sen", "ama", "var",
+            "gibi", "daha", "değil", "olarak", "ile", "kadar", "sonra",
+        ]),
+        "ru" => Script(|c| matches!(c, '\u{0400}'..='\u{04FF}')),
+        "el" => Script(|c| matches!(c, '\u{0370}'..='\u{03FF}' | '\u{1F00}'..='\u{1FFF}')),
+        "ar" => Script(|c| matches!(c, '\u{0600}'..='\u{06FF}')),
+        "hi" => Script(|c| matches!(c, '\u{0900}'..='\u{097F}')),
+        "ko" => Script(|c| matches!(c, '\u{AC00}'..='\u{D7AF}' | '\u{1100}'..='\u{11FF}')),
+        "ja" => Script(|c| matches!(c, '\u{3040}'..='\u{30FF}')),
+        "zh" => Script(|c| matches!(c, '\u{4E00}'..='\u{9FFF}')),
+        _ => return None,
+    })
+}
+
+/// Languages with a detection profile
+const PROFILED: &[&str] = &[
+    "hr", "es", "en", "fr", "de", "it", "pt", "nl", "pl", "sv", "no", "da", "fi", "tr", "ru", "el",
+    "ar", "hi", "ko", "ja", "zh",
+];
+
+/// How well the sample matches a language, from 0.0 to 1.0
+fn score(profile: &Profile, sample: &[String]) -> f64 {
+    match profile {
+        Profile::Script(in_script) => {
+            let matching = sample
+                .iter()
+                .filter(|word| word.chars().any(in_script))
+                .count();
+            matching as f64 / sample.len() as f64
+        }
+        Profile::Stopwords(stopwords) => stopword_score(stopwords, sample),
+        Profile::PackStopwords(stopwords) => stopword_score(stopwords, sample),
+    }
+}
+
+fn stopword_score(stopwords: &[impl AsRef<str>], sample: &[String]) -> f64 {
+    let words: HashSet<&str> = sample.iter().map(String::as_str).collect();
+    let hits = stopwords
+        .iter()
+        .filter(|w| words.contains(&w.as_ref().to_lowercase().as_str()))
+        .count();
+    hits as f64 / stopwords.len() as f64
+}
+
+/// Best matching language for a sample of frequent words, if any matches well
+pub fn detect_language(sample: &[String], packs: &LanguagePacks) -> Option<String> {
+    if sample.is_empty() {
+        return None;
+    }
+
+    PROFILED
+        .iter()
+        .map(|code| code.to_string())
+        .chain(packs.iter().map(|pack| pack.code.clone()))
+        .filter_map(|code| {
+            let score = score(&profile(&code, packs)?, sample);
+            Some((code, score))
+        })
+        .filter(|(_, score)| *score >= MIN_SCORE)
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(code, _)| code)
+}
+
+/// Check that a downloaded frequency list is really in the requested
+/// language, so a moved or replaced upstream file fails loudly instead of
+/// producing a deck in the wrong language. The packs' stopwords tell their
+/// languages apart
+pub fn verify_language(
+    data: &FrequencyData,
+    language_code: &str,
+    packs: &LanguagePacks,
+) -> Result<()> {
+    let Some(expected) = profile(language_code, packs) else {
+        tracing::debug!("No detection profile for {}, skipping check", language_code);
+        return Ok(());
+    };
+
+    let mut words: Vec<_> = data.words.values().flatten().collect();
+    words.sort_by_key(|word| word.rank);
+    let sample: Vec<String> = words
+        .iter()
+        .take(SAMPLE_SIZE)
+        .map(|word| word.text.to_lowercase())
+        .collect();
+
+    if sample.len() < MIN_SAMPLE {
+        tracing::debug!("Only {} words, skipping language check", sample.len());
+        return Ok(());
+    }
+
+    if score(&expected, &sample) >= MIN_SCORE {
+        return Ok(());
+    }
+
+    Err(AnkiDeckBuilderError::FrequencyLanguageMismatch {
+        expected: language_code.to_string(),
+        detected: detect_language(&sample, packs).unwrap_or_else(|| "unknown".to_string()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::language::frequency::{PartOfSpeech, Word};
+
+    fn data_from(text: &str, language_code: &str) -> FrequencyData {
+        let mut data = FrequencyData::new(language_code.to_string());
+        // Repeat so the list is long enough to be judged
+        for (rank, word) in text.split_whitespace().cycle().take(100).enumerate() {
+            data.add_word(Word::new(word.to_string(), PartOfSpeech::Noun, rank + 1));
+        }
+        data
+    }
+
+    const CROATIAN: &str = "je da se na to ne što za su ja ti ali sam si mi ovo kako ima bi od dan";
+    const SPANISH: &str = "de la que el en los se no por un una es con para lo las pero mi me";
+
+    #[test]
+    fn test_matching_language_passes() {
+        assert!(
+            verify_language(&data_from(CROATIAN, "hr"), "hr", &LanguagePacks::default()).is_ok()
+        );
+        assert!(
+            verify_language(&data_from(SPANISH, "es"), "es", &LanguagePacks::default()).is_ok()
+        );
+    }
+
+    #[test]
+    fn test_wrong_language_fails() {
+        let err = verify_language(&data_from(SPANISH, "hr"), "hr", &LanguagePacks::default())
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            AnkiDeckBuilderError::FrequencyLanguageMismatch { ref detected, .. } if detected == "es"
+        ));
+    }
+
+    #[test]
+    fn test_script_detection() {
+        let russian = data_from("и в не на я что он с как это", "ru");
+        assert!(verify_language(&russian, "ru", &LanguagePacks::default()).is_ok());
+        assert!(verify_language(&russian, "hr", &LanguagePacks::default()).is_err());
+    }
+
+    #[test]
+    fn test_small_or_unprofiled_lists_are_skipped() {
+        let mut small = FrequencyData::new("hr".to_string());
+        small.add_word(Word::new("día".to_string(), PartOfSpeech::Noun, 1));
+        assert!(verify_language(&small, "hr", &LanguagePacks::default()).is_ok());
+        assert!(
+            verify_language(&data_from(SPANISH, "xx"), "xx", &LanguagePacks::default()).is_ok()
+        );
+    }
+}
@@ -0,0 +1,479 @@
+use crate::ankiweb::models::strip_html;
+use crate::error::Result;
+use crate::language::declension::WiktionaryClient;
+use crate::language::{truncate_graphemes, LanguagePacks, PartOfSpeech};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Cache directory of sense lookups
+const SENSE_CACHE: &str = "senses";
+
+/// Cache directory of etymology lookups
+const ETYMOLOGY_CACHE: &str = "etymology";
+
+/// Longest etymology put on a card, in characters; the first sentence
+/// usually says where the word comes from, the rest is for the web page
+pub const MAX_ETYMOLOGY_LENGTH: usize = 160;
+
+/// Most other meanings listed on a card
+pub const MAX_OTHER_MEANINGS: usize = 4;
+
+/// Languages Wiktionary files under another name than ours (section ids
+/// use underscores for spaces)
+const SECTION_NAMES: &[(&str, &str)] = &[
+    ("hr", "Serbo-Croatian"),
+    ("sr", "Serbo-Croatian"),
+    ("bs", "Serbo-Croatian"),
+    ("no", "Norwegian_Bokmål"),
+];
+
+/// One meaning of a word, glossed in English
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Sense {
+    pub pos: PartOfSpeech,
+    pub gloss: String,
+}
+
+/// Meanings of a word, in the order the dictionary lists them (most
+/// common first within each part of speech)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DictionaryEntry {
+    pub senses: Vec<Sense>,
+}
+
+impl DictionaryEntry {
+    /// Senses of one part of speech
+    pub fn senses_of<'a>(&'a self, pos: &'a PartOfSpeech) -> impl Iterator<Item = &'a Sense> {
+        self.senses.iter().filter(move |sense| &sense.pos == pos)
+    }
+
+    /// A better English translation for a word tagged `pos`, when the one
+    /// given only fits senses of another part of speech ("oko" tagged as a
+    /// noun but translated "around"). None when the translation fits the
+    /// part of speech, or the dictionary knows nothing to say otherwise
+    pub fn disambiguate(&self, pos: &PartOfSpeech, translation: &str) -> Option<String> {
+        let primary = self.senses_of(pos).next()?;
+        let translated = terms(translation);
+        let fits = |sense: &Sense| terms(&sense.gloss).iter().any(|t| translated.contains(t));
+
+        if self.senses_of(pos).any(fits) {
+            return None;
+        }
+        self.senses
+            .iter()
+            .filter(|sense| &sense.pos != pos)
+            .any(fits)
+            .then(|| short_gloss(&primary.gloss))
+    }
+
+    /// Meanings besides the translation on the card, one per line: further
+    /// senses of the same part of speech first, then other parts of speech
+    /// labelled with theirs. Empty when there are none
+    pub fn other_meanings(&self, pos: &PartOfSpeech, translation: &str) -> String {
+        let translated = terms(translation);
+        let ordered = self
+            .senses_of(pos)
+            .chain(self.senses.iter().filter(|sense| &sense.pos != pos));
+
+        let mut lines: Vec<String> = Vec::new();
+        for sense in ordered {
+            let gloss = short_gloss(&sense.gloss);
+            if terms(&gloss).iter().any(|t| translated.contains(t)) {
+                continue;
+            }
+            let line = if &sense.pos == pos {
+                gloss
+            } else {
+                format!("{}: {}", sense.pos.name(), gloss)
+            };
+            if !lines.contains(&line) {
+                lines.push(line);
+            }
+        }
+        lines.truncate(MAX_OTHER_MEANINGS);
+        lines.join("<br>")
+    }
+}
+
+/// Whether Wiktionary has entries for words of this language
+pub fn has_dictionary(language_code: &str, packs: &LanguagePacks) -> bool {
+    section_id(language_code, packs).is_some()
+}
+
+/// Id of the language's section on a Wiktionary page
+fn section_id(language_code: &str, packs: &LanguagePacks) -> Option<String> {
+    SECTION_NAMES
+        .iter()
+        .find(|(code, _)| *code == language_code)
+        .map(|(_, name)| name.to_string())
+        .or_else(|| {
+            packs
+                .language(language_code)
+                .map(|language| language.name.replace(' ', "_"))
+        })
+}
+
+impl WiktionaryClient {
+    /// Senses Wiktionary lists for a word, of the parts of speech cards are
+    /// made for. Empty when the language or the word has no entry
+    pub async fn senses(&self, language_code: &str, word: &str) -> Result<DictionaryEntry> {
+        let Some(section) = section_id(language_code, &self.packs) else {
+            return Ok(DictionaryEntry::default());
+        };
+
+        let mut cache: HashMap<String, Vec<Sense>> = self.load_cache(SENSE_CACHE, language_code);
+        if let Some(senses) = cache.get(word) {
+            return Ok(DictionaryEntry {
+                senses: senses.clone(),
+            });
+        }
+
+        tracing::debug!("Looking up senses of '{}' on Wiktionary", word);
+        let senses = self
+            .fetch_page(word)
+            .await?
+            .map(|html| parse_senses(&html, &section))
+            .unwrap_or_default();
+        cache.insert(word.to_string(), senses.clone());
+        if let Err(e) = self.save_cache(SENSE_CACHE, language_code, &cache) {
+            tracing::warn!("Failed to cache senses of '{}': {}", word, e);
+        }
+        Ok(DictionaryEntry { senses })
+    }
+
+    /// Where a word comes from, as Wiktionary's first etymology of it
+    /// begins ("Borrowed from German Kuchen"). None when the language or
+    /// the word has no entry, or the entry says nothing of its origin
+    pub async fn etymology(&self, language_code: &str, word: &str) -> Result<Option<String>> {
+        let Some(section) = section_id(language_code, &self.packs) else {
+            return Ok(None);
+        };
+
+        let mut cache: HashMap<String, Option<String>> =
+            self.load_cache(ETYMOLOGY_CACHE, language_code);
+        if let Some(etymology) = cache.get(word) {
+            return Ok(etymology.clone());
+        }
+
+        tracing::debug!("Looking up the etymology of '{}' on Wiktionary", word);
+        let etymology = self
+            .fetch_page(word)
+            .await?
+            .and_then(|html| parse_etymology(&html, &section));
+        cache.insert(word.to_string(), etymology.clone());
+        if let Err(e) = self.save_cache(ETYMOLOGY_CACHE, language_code, &cache) {
+            tracing::warn!("Failed to cache the etymology of '{}': {}", word, e);
+        }
+        Ok(etymology)
+    }
+}
+
+/// A language's section of a rendered Wiktionary page, up to the next
+/// language's
+fn language_section<'a>(html: &'a str, section_id: &str) -> Option<&'a str> {
+    let start = html.find(&format!("<h2 id=\"{}\"", section_id))?;
+    let section = &html[start + 1..];
+    Some(&section[..section.find("<h2").unwrap_or(section.len())])
+}
+
+/// First sentence of the first paragraph under an Etymology heading in a
+/// language's section, as plain text
+fn parse_etymology(html: &str, section_id: &str) -> Option<String> {
+    let mut rest = language_section(html, section_id)?;
+    while let Some((_, id, body_start)) = next_heading(rest) {
+        let body = &rest[body_start..];
+        let end = next_heading(body).map_or(body.len(), |(start, _, _)| start);
+        if id.starts_with("Etymology") {
+            let body = &body[..end];
+            let start = body.find("<p")?;
+            let paragraph = &body[start..];
+            let paragraph = &paragraph[..paragraph.find("</p>").unwrap_or(paragraph.len())];
+            let text = strip_html(paragraph)
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ");
+            let sentence = first_sentence(&text).trim_end_matches('.');
+            return Some(truncate_graphemes(sentence, MAX_ETYMOLOGY_LENGTH))
+                .filter(|sentence| !sentence.is_empty());
+        }
+        rest = &body[end..];
+    }
+    None
+}
+
+/// Abbreviations etymologies use before a capital letter ("cf. Latin")
+const ABBREVIATIONS: &[&str] = &["c", "ca", "cf", "dim", "e.g", "esp", "i.e", "lit", "pl"];
+
+/// Text up to the end of its first sentence: a full stop after anything
+/// but an abbreviation, followed by a capital letter
+fn first_sentence(text: &str) -> &str {
+    let mut offset = 0;
+    while let Some(found) = text[offset..].find(". ") {
+        let end = offset + found + 1;
+        let last_word = text[..end - 1].rsplit(' ').next().unwrap_or_default();
+        if text[end + 1..].starts_with(char::is_uppercase)
+            && !ABBREVIATIONS.contains(&last_word.to_lowercase().as_str())
+        {
+            return &text[..end];
+        }
+        offset = end;
+    }
+    text
+}
+
+/// Senses in a language's section of a rendered Wiktionary page: each
+/// part-of-speech heading is followed by a numbered list of definitions,
+/// whose nested lists (examples, quotations, subsenses) are left out
+fn parse_senses(html: &str, section_id: &str) -> Vec<Sense> {
+    let Some(section) = language_section(html, section_id) else {
+        return Vec::new();
+    };
+
+    let mut senses = Vec::new();
+    let mut rest = section;
+    while let Some((_, id, body_start)) = next_heading(rest) {
+        let body = &rest[body_start..];
+        let end = next_heading(body).map_or(body.len(), |(start, _, _)| start);
+        if let Some(pos) = heading_pos(id) {
+            senses.extend(definitions(&body[..end]).into_iter().map(|gloss| Sense {
+                pos: pos.clone(),
+                gloss,
+            }));
+        }
+        rest = &body[end..];
+    }
+    senses
+}
+
+/// Where the next subsection heading starts, its id, and where the content
+/// after it starts
+fn next_heading(html: &str) -> Option<(usize, &str, usize)> {
+    let mut offset = 0;
+    while let Some(found) = html[offset..].find("<h") {
+        let start = offset + found;
+        let tag = &html[start..];
+        let level = tag.as_bytes().get(2).copied().unwrap_or_default();
+        if (b'3'..=b'6').contains(&level) && tag[3..].starts_with(" id=\"") {
+            let id_start = start + 8;
+            let id_end = id_start + html[id_start..].find('"')?;
+            let after = id_end + html[id_end..].find('>')? + 1;
+            return Some((start, &html[id_start..id_end], after));
+        }
+        offset = start + 2;
+    }
+    None
+}
+
+/// Part of speech of a heading id such as `Noun` or `Verb_2`
+fn heading_pos(id: &str) -> Option<PartOfSpeech> {
+    let name = id
+        .rsplit_once('_')
+        .filter(|(_, number)| number.chars().all(|c| c.is_ascii_digit()))
+        .map_or(id, |(name, _)| name);
+    PartOfSpeech::all()
+        .into_iter()
+        .find(|pos| pos.name().eq_ignore_ascii_case(name))
+}
+
+/// Top-level items of the first numbered list, as plain text
+fn definitions(html: &str) -> Vec<String> {
+    let Some(start) = html.find("<ol") else {
+        return Vec::new();
+    };
+    let mut rest = &html[start..];
+    rest = &rest[rest.find('>').map_or(rest.len(), |end| end + 1)..];
+
+    let mut items = Vec::new();
+    let mut item: Option<String> = None;
+    let mut depth = 1;
+    while depth > 0 {
+        let Some(tag_start) = rest.find('<') else {
+            break;
+        };
+        if depth == 1 {
+            if let Some(item) = item.as_mut() {
+                item.push_str(&rest[..tag_start]);
+            }
+        }
+        let tag_end = rest[tag_start..]
+            .find('>')
+            .map_or(rest.len(), |end| tag_start + end + 1);
+        let tag = &rest[tag_start..tag_end];
+        rest = &rest[tag_end..];
+
+        let name: String = tag
+            .trim_start_matches(['<', '/'])
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect();
+        let closing = tag.starts_with("</");
+        match (name.as_str(), closing) {
+            ("ol" | "ul" | "dl", false) => depth += 1,
+            ("ol" | "ul" | "dl", true) => depth -= 1,
+            ("li", false) if depth == 1 => item = Some(String::new()),
+            ("li", true) if depth == 1 => items.extend(item.take()),
+            _ if depth == 1 => {
+                if let Some(item) = item.as_mut() {
+                    item.push_str(tag);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    items
+        .iter()
+        .map(|item| {
+            strip_html(item)
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+                .trim_end_matches('.')
+                .to_string()
+        })
+        .filter(|gloss| !gloss.is_empty())
+        .collect()
+}
+
+/// A gloss without its labels and qualifiers, up to its first semicolon
+/// ("(anatomy) eye; sight" → "eye")
+fn short_gloss(gloss: &str) -> String {
+    let mut text = String::new();
+    let mut depth = 0;
+    for c in gloss.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth > 0 => depth -= 1,
+            _ if depth == 0 => text.push(c),
+            _ => {}
+        }
+    }
+    let first = text.split(';').next().unwrap_or_default();
+    first.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Lowercase alternatives in a gloss or translation, for comparing them
+/// ("to look, to see" → ["look", "see"])
+fn terms(text: &str) -> Vec<String> {
+    short_gloss(&text.replace("<br>", ";").replace(';', ","))
+        .split(',')
+        .map(|term| {
+            let term = term.trim().to_lowercase();
+            term.strip_prefix("to ")
+                .map_or(term.clone(), str::to_string)
+        })
+        .filter(|term| !term.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::HttpSettings;
+    use mockito::Matcher;
+    use tempfile::tempdir;
+
+    const PAGE: &str = r#"<div class="mw-heading mw-heading2"><h2 id="Slovene">Slovene</h2></div>
+        <div class="mw-heading mw-heading3"><h3 id="Noun">Noun</h3></div>
+        <ol><li>eye (Slovene)</li></ol>
+        <div class="mw-heading mw-heading2"><h2 id="Serbo-Croatian">Serbo-Croatian</h2></div>
+        <div class="mw-heading mw-heading3"><h3 id="Etymology_1">Etymology 1</h3></div>
+        <p>Inherited from <a href="/wiki/Proto-Slavic">Proto-Slavic</a> <i>*oko</i>, cf.
+            Latin <i>oculus</i>. More at <a href="/wiki/eye">eye</a>.</p>
+        <div class="mw-heading mw-heading4"><h4 id="Noun_2">Noun</h4></div>
+        <p><span class="headword-line"><strong>ȍko</strong></span></p>
+        <ol><li>(<span class="ib-content">anatomy</span>) <a href="/wiki/eye">eye</a>
+            <dl><dd><i>Boli me oko.</i> My eye hurts.</dd></dl></li>
+        <li><a href="/wiki/loop">loop</a>, <a href="/wiki/stitch">stitch</a>
+            <ol><li>a subsense</li></ol></li></ol>
+        <div class="mw-heading mw-heading4"><h4 id="Declension">Declension</h4></div>
+        <ol><li>not a sense</li></ol>
+        <div class="mw-heading mw-heading3"><h3 id="Preposition">Preposition</h3></div>
+        <ol><li><a href="/wiki/around">around</a>, <a href="/wiki/about">about</a></li></ol>"#;
+
+    fn oko() -> DictionaryEntry {
+        DictionaryEntry {
+            senses: parse_senses(PAGE, "Serbo-Croatian"),
+        }
+    }
+
+    #[test]
+    fn test_parse_senses() {
+        let entry = oko();
+        let senses: Vec<(&PartOfSpeech, &str)> = entry
+            .senses
+            .iter()
+            .map(|sense| (&sense.pos, sense.gloss.as_str()))
+            .collect();
+        assert_eq!(
+            senses,
+            [
+                (&PartOfSpeech::Noun, "(anatomy) eye"),
+                (&PartOfSpeech::Noun, "loop, stitch"),
+                (&PartOfSpeech::Preposition, "around, about"),
+            ]
+        );
+        assert!(parse_senses(PAGE, "Russian").is_empty());
+    }
+
+    #[test]
+    fn test_parse_etymology() {
+        assert_eq!(
+            parse_etymology(PAGE, "Serbo-Croatian").as_deref(),
+            Some("Inherited from Proto-Slavic *oko, cf. Latin oculus")
+        );
+        assert_eq!(parse_etymology(PAGE, "Slovene"), None);
+        assert_eq!(first_sentence("From Latin. Compare German"), "From Latin.");
+    }
+
+    #[test]
+    fn test_disambiguate() {
+        let entry = oko();
+        let noun = PartOfSpeech::Noun;
+        // The preposition's sense on a noun card is replaced
+        assert_eq!(entry.disambiguate(&noun, "around"), Some("eye".to_string()));
+        // A fitting or unknown translation is kept
+        assert_eq!(entry.disambiguate(&noun, "Eye"), None);
+        assert_eq!(entry.disambiguate(&noun, "eyeball"), None);
+        assert_eq!(
+            entry.disambiguate(&PartOfSpeech::Preposition, "about"),
+            None
+        );
+
+        assert_eq!(
+            entry.other_meanings(&noun, "eye"),
+            "loop, stitch<br>preposition: around, about"
+        );
+        assert_eq!(
+            entry.other_meanings(&PartOfSpeech::Preposition, "around"),
+            "noun: eye<br>noun: loop, stitch"
+        );
+        assert_eq!(DictionaryEntry::default().other_meanings(&noun, "eye"), "");
+    }
+
+    #[tokio::test]
+    async fn test_senses_are_cached() {
+        let mut server = mockito::Server::new_async().await;
+        let lookup = server
+            .mock("GET", "/")
+            .match_query(Matcher::UrlEncoded("page".into(), "oko".into()))
+            .with_body(serde_json::json!({ "parse": { "text": PAGE } }).to_string())
+            .expect(1)
+            .create_async()
+            .await;
+
+        let temp_dir = tempdir().unwrap();
+        let client = WiktionaryClient::with_base_url(
+            &server.url(),
+            Some(temp_dir.path().to_path_buf()),
+            &HttpSettings::default(),
+        )
+        .unwrap();
+        for _ in 0..2 {
+            let entry = client.senses("hr", "oko").await.unwrap();
+            assert_eq!(entry.senses.len(), 3);
+        }
+        lookup.assert_async().await;
+        assert!(temp_dir.path().join("senses").join("hr.json").exists());
+    }
+}
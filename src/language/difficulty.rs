@@ -0,0 +1,97 @@
+use crate::language::cognate::{cognate_similarity, DEFAULT_COGNATE_THRESHOLD};
+use crate::language::text::grapheme_len;
+use serde::{Deserialize, Serialize};
+
+/// Ranks up to this are the words every beginner meets
+const COMMON_RANK: f64 = 500.0;
+
+/// Ranks from this on are rare enough to count as fully hard
+const RARE_RANK: f64 = 20_000.0;
+
+/// How hard a word is likely to be for a learner, used to tag notes
+/// `difficulty:easy|medium|hard` so Anki's filtered decks can pick them out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    /// Lowercase name used in tags (e.g. `difficulty:easy`)
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Easy => "easy",
+            Self::Medium => "medium",
+            Self::Hard => "hard",
+        }
+    }
+
+    pub fn from_score(score: f64) -> Self {
+        if score < 0.35 {
+            Self::Easy
+        } else if score < 0.65 {
+            Self::Medium
+        } else {
+            Self::Hard
+        }
+    }
+}
+
+/// Estimate how hard a word is from its frequency rank, its length, and how
+/// far its spelling is from the translation. Cognates ("telefon" for
+/// "teléfono") count as much easier
+pub fn estimate_difficulty(
+    language_code: &str,
+    word: &str,
+    translation: &str,
+    rank: usize,
+) -> Difficulty {
+    Difficulty::from_score(difficulty_score(language_code, word, translation, rank))
+}
+
+/// Difficulty from 0 (easiest) to 1 (hardest)
+pub fn difficulty_score(language_code: &str, word: &str, translation: &str, rank: usize) -> f64 {
+    let rarity =
+        ((rank.max(1) as f64).ln() - COMMON_RANK.ln()) / (RARE_RANK.ln() - COMMON_RANK.ln());
+    let length = (grapheme_len(word) as f64 - 4.0) / 8.0;
+    let similarity = cognate_similarity(language_code, word, translation);
+
+    let score =
+        0.45 * rarity.clamp(0.0, 1.0) + 0.2 * length.clamp(0.0, 1.0) + 0.35 * (1.0 - similarity);
+    if similarity >= DEFAULT_COGNATE_THRESHOLD {
+        score * 0.5
+    } else {
+        score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_difficulty() {
+        // Frequent and short
+        assert_eq!(
+            estimate_difficulty("hr", "dan", "día", 12),
+            Difficulty::Easy
+        );
+        // A cognate is easy even when it is not that frequent
+        assert_eq!(
+            estimate_difficulty("hr", "telefon", "es: teléfono<br>en: telephone", 4_000),
+            Difficulty::Easy
+        );
+        // Rare, long, and nothing like the translation
+        assert_eq!(
+            estimate_difficulty("hr", "čovjekoljublje", "filantropía", 30_000),
+            Difficulty::Hard
+        );
+        // Compared through the romanization
+        assert!(
+            difficulty_score("ru", "телефон", "teléfono", 4_000)
+                < difficulty_score("ru", "подоконник", "alféizar", 4_000)
+        );
+    }
+}
@@ -0,0 +1,501 @@
+use crate::cache_file;
+use crate::cancel::CancellationToken;
+use crate::error::{AnkiDeckBuilderError, Result};
+use crate::http::HttpSettings;
+use crate::language::frequency_fetcher::FetchMetadata;
+use reqwest::header::{
+    CONTENT_RANGE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, IF_RANGE, LAST_MODIFIED, RANGE,
+};
+use reqwest::{Client, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// A download is abandoned when the server sends nothing for this long
+const STALL_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// What is recorded next to each downloaded file
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct ArtifactMetadata {
+    url: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    size: u64,
+    crc32: u32,
+}
+
+/// A downloaded file, kept as it came from the server
+#[derive(Debug, Clone)]
+pub struct Artifact {
+    pub path: PathBuf,
+    pub metadata: FetchMetadata,
+}
+
+/// Downloads source files into a directory of raw artifacts, kept apart
+/// from the data parsed out of them. Downloads are conditional on the
+/// ETag/Last-Modified validators, checked against the advertised size, and
+/// resumed from where an interrupted one stopped
+#[derive(Debug, Clone)]
+pub struct Downloader {
+    dir: PathBuf,
+    client: Client,
+    cancel: CancellationToken,
+}
+
+impl Downloader {
+    pub fn new(dir: impl Into<PathBuf>, http: &HttpSettings) -> Result<Self> {
+        let client = crate::http::client_builder(http)?
+            .connect_timeout(Duration::from_secs(30))
+            .build()
+            .map_err(AnkiDeckBuilderError::HttpError)?;
+        Ok(Self {
+            dir: dir.into(),
+            client,
+            cancel: CancellationToken::new(),
+        })
+    }
+
+    /// Stop downloading when the token is cancelled. What was downloaded
+    /// so far is kept and resumed next time
+    pub fn with_cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Where the file downloaded from `url` is stored
+    pub fn artifact_path(&self, url: &str) -> PathBuf {
+        let name = url
+            .split(['?', '#'])
+            .next()
+            .and_then(|path| path.rsplit('/').find(|segment| !segment.is_empty()))
+            .unwrap_or("download");
+        let name: String = name
+            .chars()
+            .map(|c| {
+                if c.is_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        self.dir.join(name)
+    }
+
+    /// Download `url` unless it is unchanged since `validators`, in which
+    /// case None is returned. Without validators, a stored copy that the
+    /// server confirms is current is returned instead of downloading again
+    pub async fn download(
+        &self,
+        url: &str,
+        validators: &FetchMetadata,
+    ) -> Result<Option<Artifact>> {
+        std::fs::create_dir_all(&self.dir)?;
+        let path = self.artifact_path(url);
+        let stored = load_verified(&path, url);
+        let caller_has_copy = validators.etag.is_some() || validators.last_modified.is_some();
+
+        // Cleared when the server refuses to resume, so the retry starts over
+        let mut resume = true;
+        loop {
+            let partial = partial_download(&path, url).filter(|_| resume);
+
+            let mut request = self.client.get(url);
+            let (etag, last_modified) = if caller_has_copy {
+                (validators.etag.as_ref(), validators.last_modified.as_ref())
+            } else {
+                match &stored {
+                    Some(stored) => (stored.etag.as_ref(), stored.last_modified.as_ref()),
+                    None => (None, None),
+                }
+            };
+            if let Some(etag) = etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+            if let Some((offset, validator)) = &partial {
+                tracing::info!("Resuming download of {} at byte {}", url, offset);
+                request = request
+                    .header(RANGE, format!("bytes={}-", offset))
+                    .header(IF_RANGE, validator);
+            }
+
+            crate::logging::http_request("GET", url, None);
+            let started = std::time::Instant::now();
+            let response = request.send().await.map_err(|e| {
+                AnkiDeckBuilderError::FrequencyDataNotFound(format!(
+                    "Failed to download {}: {}",
+                    url, e
+                ))
+            })?;
+            crate::logging::http_response("GET", url, response.status().as_u16(), started, None);
+
+            match response.status() {
+                StatusCode::NOT_MODIFIED => {
+                    tracing::info!("{} is unchanged upstream", url);
+                    if caller_has_copy {
+                        return Ok(None);
+                    }
+                    if let Some(stored) = stored {
+                        return Ok(Some(Artifact {
+                            metadata: fetch_metadata(&stored),
+                            path,
+                        }));
+                    }
+                    return Err(AnkiDeckBuilderError::FrequencyDataNotFound(format!(
+                        "Server returned 304 for an unconditional request to {}",
+                        url
+                    )));
+                }
+                // The partial copy no longer lines up with the file; start over
+                StatusCode::RANGE_NOT_SATISFIABLE if resume => {
+                    discard_partial(&path);
+                    resume = false;
+                    continue;
+                }
+                status if !status.is_success() => {
+                    return Err(AnkiDeckBuilderError::FrequencyDataNotFound(format!(
+                        "HTTP {}: Could not download {}",
+                        status, url
+                    )));
+                }
+                _ => {}
+            }
+
+            let offset = match (&partial, response.status()) {
+                (Some((offset, _)), StatusCode::PARTIAL_CONTENT) => *offset,
+                _ => 0,
+            };
+            let artifact = self.save(url, &path, offset, response).await?;
+            return Ok(Some(artifact));
+        }
+    }
+
+    /// Stream a response body into the partial file, then move it into
+    /// place once it has the size the server announced
+    async fn save(
+        &self,
+        url: &str,
+        path: &Path,
+        offset: u64,
+        mut response: Response,
+    ) -> Result<Artifact> {
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string)
+        };
+        let etag = header(ETAG);
+        let last_modified = header(LAST_MODIFIED);
+        let expected_size = if offset > 0 {
+            header(CONTENT_RANGE).and_then(|range| total_from_content_range(&range))
+        } else {
+            response.content_length()
+        };
+
+        // Recorded first, so an interrupted download can be resumed
+        let partial = ArtifactMetadata {
+            url: url.to_string(),
+            etag,
+            last_modified,
+            ..Default::default()
+        };
+        write_metadata(&partial_metadata_path(path), &partial)?;
+
+        let mut file = std::fs::File::options()
+            .create(true)
+            .append(offset > 0)
+            .write(true)
+            .truncate(offset == 0)
+            .open(partial_path(path))?;
+        let mut written = offset;
+        loop {
+            let chunk = tokio::select! {
+                chunk = tokio::time::timeout(STALL_TIMEOUT, response.chunk()) => chunk,
+                _ = self.cancel.cancelled() => {
+                    file.flush()?;
+                    return Err(AnkiDeckBuilderError::Cancelled(format!(
+                        "download of {} stopped after {} bytes; it resumes on the next run",
+                        url, written
+                    )));
+                }
+            };
+            let chunk = chunk
+                .map_err(|_| {
+                    AnkiDeckBuilderError::FrequencyDataNotFound(format!(
+                        "Download of {} stalled after {} bytes",
+                        url, written
+                    ))
+                })?
+                .map_err(|e| {
+                    AnkiDeckBuilderError::FrequencyDataNotFound(format!(
+                        "Download of {} broke off after {} bytes: {}",
+                        url, written, e
+                    ))
+                })?;
+            let Some(chunk) = chunk else {
+                break;
+            };
+            file.write_all(&chunk)?;
+            written += chunk.len() as u64;
+        }
+        file.flush()?;
+        drop(file);
+
+        if let Some(expected) = expected_size {
+            if written != expected {
+                return Err(AnkiDeckBuilderError::FrequencyDataNotFound(format!(
+                    "Download of {} is incomplete ({} of {} bytes); it resumes on the next run",
+                    url, written, expected
+                )));
+            }
+        }
+
+        std::fs::rename(partial_path(path), path)?;
+        let _ = std::fs::remove_file(partial_metadata_path(path));
+        let metadata = ArtifactMetadata {
+            size: written,
+            crc32: checksum(path)?,
+            ..partial
+        };
+        write_metadata(&metadata_path(path), &metadata)?;
+        tracing::info!("Downloaded {} ({} bytes)", url, written);
+
+        Ok(Artifact {
+            path: path.to_path_buf(),
+            metadata: fetch_metadata(&metadata),
+        })
+    }
+}
+
+fn fetch_metadata(artifact: &ArtifactMetadata) -> FetchMetadata {
+    FetchMetadata {
+        etag: artifact.etag.clone(),
+        last_modified: artifact.last_modified.clone(),
+        ..Default::default()
+    }
+}
+
+/// The stored artifact's metadata, if it came from `url` and still has the
+/// size and checksum it was saved with
+fn load_verified(path: &Path, url: &str) -> Option<ArtifactMetadata> {
+    let metadata = read_metadata(&metadata_path(path)).filter(|m| m.url == url)?;
+    let intact = std::fs::metadata(path).is_ok_and(|file| file.len() == metadata.size)
+        && checksum(path).is_ok_and(|crc| crc == metadata.crc32);
+    if !intact {
+        tracing::warn!("Stored copy of {} is damaged, downloading again", url);
+        return None;
+    }
+    Some(metadata)
+}
+
+/// Bytes already downloaded and the validator to resume them with
+fn partial_download(path: &Path, url: &str) -> Option<(u64, String)> {
+    let metadata = read_metadata(&partial_metadata_path(path)).filter(|m| m.url == url)?;
+    let validator = metadata.etag.or(metadata.last_modified)?;
+    let offset = std::fs::metadata(partial_path(path)).ok()?.len();
+    (offset > 0).then_some((offset, validator))
+}
+
+fn discard_partial(path: &Path) {
+    let _ = std::fs::remove_file(partial_path(path));
+    let _ = std::fs::remove_file(partial_metadata_path(path));
+}
+
+/// Total length from a `Content-Range: bytes 100-199/200` header
+fn total_from_content_range(value: &str) -> Option<u64> {
+    value.rsplit('/').next()?.trim().parse().ok()
+}
+
+fn checksum(path: &Path) -> Result<u32> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = crc32fast::Hasher::new();
+    let mut buffer = [0; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            return Ok(hasher.finalize());
+        }
+        hasher.update(&buffer[..read]);
+    }
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+fn partial_path(path: &Path) -> PathBuf {
+    with_suffix(path, ".part")
+}
+
+fn metadata_path(path: &Path) -> PathBuf {
+    with_suffix(path, ".meta.json")
+}
+
+fn partial_metadata_path(path: &Path) -> PathBuf {
+    with_suffix(path, ".part.meta.json")
+}
+
+fn read_metadata(path: &Path) -> Option<ArtifactMetadata> {
+    cache_file::read_json(path)
+}
+
+fn write_metadata(path: &Path, metadata: &ArtifactMetadata) -> Result<()> {
+    cache_file::write_json(path, metadata)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::HttpSettings;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_download_records_validators() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/hr.txt")
+            .with_header("etag", "\"abc\"")
+            .with_body("biti 100\ndan 90")
+            .create_async()
+            .await;
+
+        let dir = tempdir().unwrap();
+        let downloader = Downloader::new(dir.path(), &HttpSettings::default()).unwrap();
+        let url = format!("{}/hr.txt", server.url());
+        let artifact = downloader
+            .download(&url, &FetchMetadata::default())
+            .await
+            .unwrap()
+            .unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(artifact.path, dir.path().join("hr.txt"));
+        assert_eq!(artifact.metadata.etag.as_deref(), Some("\"abc\""));
+        assert_eq!(
+            std::fs::read_to_string(&artifact.path).unwrap(),
+            "biti 100\ndan 90"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_download_not_modified() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/hr.txt")
+            .match_header("if-none-match", "\"abc\"")
+            .with_status(304)
+            .create_async()
+            .await;
+
+        let dir = tempdir().unwrap();
+        let downloader = Downloader::new(dir.path(), &HttpSettings::default()).unwrap();
+        let validators = FetchMetadata {
+            etag: Some("\"abc\"".to_string()),
+            ..Default::default()
+        };
+        let url = format!("{}/hr.txt", server.url());
+        let outcome = downloader.download(&url, &validators).await.unwrap();
+
+        mock.assert_async().await;
+        assert!(outcome.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_stored_copy_is_revalidated() {
+        let mut server = mockito::Server::new_async().await;
+        let first = server
+            .mock("GET", "/hr.txt")
+            .with_header("etag", "\"abc\"")
+            .with_body("biti 100")
+            .create_async()
+            .await;
+
+        let dir = tempdir().unwrap();
+        let downloader = Downloader::new(dir.path(), &HttpSettings::default()).unwrap();
+        let url = format!("{}/hr.txt", server.url());
+        downloader
+            .download(&url, &FetchMetadata::default())
+            .await
+            .unwrap();
+        first.assert_async().await;
+        first.remove_async().await;
+
+        // The stored copy is reused once the server confirms it
+        let unchanged = server
+            .mock("GET", "/hr.txt")
+            .match_header("if-none-match", "\"abc\"")
+            .with_status(304)
+            .create_async()
+            .await;
+        let artifact = downloader
+            .download(&url, &FetchMetadata::default())
+            .await
+            .unwrap()
+            .unwrap();
+        unchanged.assert_async().await;
+        assert_eq!(std::fs::read_to_string(artifact.path).unwrap(), "biti 100");
+
+        // A damaged copy is not trusted
+        std::fs::write(dir.path().join("hr.txt"), "bitx 100").unwrap();
+        assert!(load_verified(&dir.path().join("hr.txt"), &url).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_resumes_partial_download() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/hr.txt")
+            .match_header("range", "bytes=5-")
+            .match_header("if-range", "\"abc\"")
+            .with_status(206)
+            .with_header("etag", "\"abc\"")
+            .with_header("content-range", "bytes 5-13/14")
+            .with_body("00\ndan 90")
+            .create_async()
+            .await;
+
+        let dir = tempdir().unwrap();
+        let downloader = Downloader::new(dir.path(), &HttpSettings::default()).unwrap();
+        let url = format!("{}/hr.txt", server.url());
+        let path = downloader.artifact_path(&url);
+        std::fs::write(partial_path(&path), "biti ").unwrap();
+        let partial = ArtifactMetadata {
+            url: url.clone(),
+            etag: Some("\"abc\"".to_string()),
+            ..Default::default()
+        };
+        write_metadata(&partial_metadata_path(&path), &partial).unwrap();
+
+        let artifact = downloader
+            .download(&url, &FetchMetadata::default())
+            .await
+            .unwrap()
+            .unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(
+            std::fs::read_to_string(&artifact.path).unwrap(),
+            "biti 00\ndan 90"
+        );
+        assert!(!partial_path(&path).exists());
+    }
+
+    #[test]
+    fn test_artifact_path() {
+        let downloader = Downloader::new("/cache/raw", &HttpSettings::default()).unwrap();
+        assert_eq!(
+            downloader.artifact_path("https://example.org/corpora/hrv_10K.tar.gz?x=1"),
+            PathBuf::from("/cache/raw/hrv_10K.tar.gz")
+        );
+        assert_eq!(total_from_content_range("bytes 5-14/15"), Some(15));
+        assert_eq!(total_from_content_range("bytes 5-14/*"), None);
+    }
+}
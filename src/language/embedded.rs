@@ -0,0 +1,93 @@
+//! Curated frequency lists for Croatian and Spanish, gzip-compressed into
+//! the binary so decks can be built without a network. Each list is a data
+//! file under `embedded/`, decoded the first time it is asked for
+
+use crate::language::frequency::{FrequencyData, PartOfSpeech, Word};
+use flate2::read::GzDecoder;
+use std::io::Read;
+use std::sync::OnceLock;
+
+static CROATIAN: OnceLock<FrequencyData> = OnceLock::new();
+static SPANISH: OnceLock<FrequencyData> = OnceLock::new();
+
+fn embedded_list(language_code: &str) -> Option<(&'static [u8], &'static OnceLock<FrequencyData>)> {
+    Some(match language_code {
+        "hr" => (include_bytes!("embedded/hr.txt.gz"), &CROATIAN),
+        "es" => (include_bytes!("embedded/es.txt.gz"), &SPANISH),
+        _ => return None,
+    })
+}
+
+/// Whether a curated word list ships with the binary for the language
+pub fn has_embedded_data(language_code: &str) -> bool {
+    embedded_list(language_code).is_some()
+}
+
+/// The curated word list of a language, None for languages without one
+pub fn embedded_frequency_data(language_code: &str) -> Option<FrequencyData> {
+    let (compressed, decoded) = embedded_list(language_code)?;
+    Some(
+        decoded
+            .get_or_init(|| {
+                tracing::info!("Decoding embedded frequency list for {}", language_code);
+                parse_embedded_list(decompress(compressed), language_code)
+            })
+            .clone(),
+    )
+}
+
+fn decompress(compressed: &[u8]) -> String {
+    let mut text = String::new();
+    GzDecoder::new(compressed)
+        .read_to_string(&mut text)
+        .expect("embedded frequency lists are gzip-compressed UTF-8");
+    text
+}
+
+/// Lines of a part of speech and a word, most frequent first
+fn parse_embedded_list(text: String, language_code: &str) -> FrequencyData {
+    let mut data = FrequencyData::new(language_code.to_string());
+    let entries = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let (pos, text) = line.split_once(' ')?;
+            Some((PartOfSpeech::from_name(pos)?, text.trim()))
+        });
+    for (i, (pos, text)) in entries.enumerate() {
+        data.add_word(Word::new(text.to_string(), pos, i + 1));
+    }
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embedded_frequency_data() {
+        for code in ["hr", "es"] {
+            assert!(has_embedded_data(code));
+            let data = embedded_frequency_data(code).unwrap();
+            assert_eq!(data.language, code);
+            for pos in PartOfSpeech::all() {
+                assert!(
+                    data.get_top_words(&pos, 10).len() >= 10,
+                    "{} {:?}",
+                    code,
+                    pos
+                );
+            }
+        }
+
+        let croatian = embedded_frequency_data("hr").unwrap();
+        let verbs = croatian.get_top_words(&PartOfSpeech::Verb, 2);
+        assert_eq!(verbs[0].text, "biti");
+        assert_eq!(verbs[1].text, "imati");
+        assert!(verbs[0].rank < verbs[1].rank);
+
+        assert!(!has_embedded_data("ja"));
+        assert!(embedded_frequency_data("ja").is_none());
+    }
+}
@@ -0,0 +1,185 @@
+use crate::error::{AnkiDeckBuilderError, Result};
+use crate::language::text::{grapheme_len, normalize_text};
+use regex::{Regex, RegexBuilder};
+use std::collections::HashSet;
+use std::path::Path;
+
+/// Patterns starting with this are globs (`*` and `?` wildcards) matched
+/// against the whole word; all others are regular expressions
+const GLOB_PREFIX: &str = "glob:";
+
+/// Decides which words from a frequency list may go into a deck
+#[derive(Debug, Clone, Default)]
+pub struct WordFilter {
+    min_length: Option<usize>,
+    max_length: Option<usize>,
+    include: Vec<Regex>,
+    exclude: Vec<Regex>,
+    known_words: HashSet<String>,
+}
+
+impl WordFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Shortest word kept, in graphemes
+    pub fn with_min_length(mut self, min_length: Option<usize>) -> Self {
+        self.min_length = min_length;
+        self
+    }
+
+    /// Longest word kept, in graphemes
+    pub fn with_max_length(mut self, max_length: Option<usize>) -> Self {
+        self.max_length = max_length;
+        self
+    }
+
+    /// Keep only words matching at least one of these patterns
+    pub fn with_include(mut self, patterns: &[String]) -> Result<Self> {
+        self.include = compile_patterns(patterns)?;
+        Ok(self)
+    }
+
+    /// Drop words matching any of these patterns
+    pub fn with_exclude(mut self, patterns: &[String]) -> Result<Self> {
+        self.exclude = compile_patterns(patterns)?;
+        Ok(self)
+    }
+
+    /// Drop these words, compared case-insensitively and however their
+    /// accents are encoded
+    pub fn with_known_words<I: IntoIterator<Item = String>>(mut self, words: I) -> Self {
+        self.known_words
+            .extend(words.into_iter().map(|w| normalize_text(&w).to_lowercase()));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min_length.is_none()
+            && self.max_length.is_none()
+            && self.include.is_empty()
+            && self.exclude.is_empty()
+            && self.known_words.is_empty()
+    }
+
+    /// Number of words excluded as already known
+    pub fn known_word_count(&self) -> usize {
+        self.known_words.len()
+    }
+
+    pub fn matches(&self, word: &str) -> bool {
+        let length = grapheme_len(word);
+        if self.min_length.is_some_and(|min| length < min)
+            || self.max_length.is_some_and(|max| length > max)
+        {
+            return false;
+        }
+
+        if !self.include.is_empty() && !self.include.iter().any(|re| re.is_match(word)) {
+            return false;
+        }
+
+        !self.exclude.iter().any(|re| re.is_match(word))
+            && !self
+                .known_words
+                .contains(&normalize_text(word).to_lowercase())
+    }
+}
+
+/// Read a known-words file: one word per line, blank lines and `#` comments
+/// ignored. Only the first tab-separated column is used, so an Anki "Notes in
+/// Plain Text" export works as is
+pub fn load_known_words(path: &Path) -> Result<Vec<String>> {
+    let content = std::fs::read_to_string(path)?;
+    Ok(content
+        .lines()
+        .filter_map(|line| line.split('\t').next())
+        .map(normalize_text)
+        .filter(|word| !word.is_empty() && !word.starts_with('#'))
+        .collect())
+}
+
+fn compile_patterns(patterns: &[String]) -> Result<Vec<Regex>> {
+    patterns
+        .iter()
+        .map(|pattern| {
+            let compiled = match pattern.strip_prefix(GLOB_PREFIX) {
+                Some(glob) => RegexBuilder::new(&glob_to_regex(glob))
+                    .case_insensitive(true)
+                    .build(),
+                None => Regex::new(pattern),
+            };
+            compiled.map_err(|e| {
+                AnkiDeckBuilderError::ConfigurationError(format!(
+                    "Invalid word pattern '{}': {}",
+                    pattern, e
+                ))
+            })
+        })
+        .collect()
+}
+
+fn glob_to_regex(glob: &str) -> String {
+    let mut regex = String::from("^");
+    for c in glob.chars() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            _ => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    regex
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_length_bounds() {
+        let filter = WordFilter::new()
+            .with_min_length(Some(3))
+            .with_max_length(Some(4));
+
+        assert!(!filter.matches("je"));
+        assert!(filter.matches("dan"));
+        assert!(filter.matches("kuća"));
+        assert!(!filter.matches("vrijeme"));
+    }
+
+    #[test]
+    fn test_patterns() {
+        let filter = WordFilter::new()
+            .with_include(&["^[a-zčćđšž]+$".to_string()])
+            .unwrap()
+            .with_exclude(&["glob:ne*".to_string(), "ti$".to_string()])
+            .unwrap();
+
+        assert!(filter.matches("dan"));
+        assert!(!filter.matches("Zagreb"));
+        assert!(!filter.matches("nešto"));
+        assert!(!filter.matches("biti"));
+        assert!(filter.matches("ime"));
+
+        assert!(WordFilter::new().with_exclude(&["(".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_known_words_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("known.txt");
+        std::fs::write(&path, "\u{feff}# learned\nDan\tdía\n\n  kuc\u{301}a \n").unwrap();
+
+        let filter = WordFilter::new().with_known_words(load_known_words(&path).unwrap());
+
+        assert_eq!(filter.known_word_count(), 2);
+        assert!(!filter.matches("dan"));
+        assert!(!filter.matches("kuća"));
+        assert!(!filter.matches("kuc\u{301}a"));
+        assert!(filter.matches("noć"));
+        assert!(WordFilter::new().is_empty());
+    }
+}
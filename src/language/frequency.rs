@@ -1,5 +1,8 @@
+use crate::language::filter::WordFilter;
+use crate::language::text::normalize_text;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::Arc;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Hash, Eq, PartialEq)]
 pub enum PartOfSpeech {
@@ -11,9 +14,12 @@ pub enum PartOfSpeech {
     Pronoun,
     Conjunction,
     Interjection,
+    /// A multi-word expression, from the phrase lists rather than a frequency list
+    Phrase,
 }
 
 impl PartOfSpeech {
+    /// Parts of speech frequency lists sort words into
     pub fn all() -> Vec<Self> {
         vec![
             Self::Noun,
@@ -26,12 +32,110 @@ impl PartOfSpeech {
             Self::Interjection,
         ]
     }
+
+    /// Lowercase name used in tags (e.g. `pos:noun`)
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Noun => "noun",
+            Self::Verb => "verb",
+            Self::Adjective => "adjective",
+            Self::Adverb => "adverb",
+            Self::Preposition => "preposition",
+            Self::Pronoun => "pronoun",
+            Self::Conjunction => "conjunction",
+            Self::Interjection => "interjection",
+            Self::Phrase => "phrase",
+        }
+    }
+
+    /// Part of speech with this tag name
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::all()
+            .into_iter()
+            .chain([Self::Phrase])
+            .find(|pos| pos.name() == name)
+    }
+
+    /// Parts of speech named on the command line, singular or plural
+    /// ("noun" or "nouns"), in the order of [`PartOfSpeech::all`]. "all",
+    /// like no names at all, stands for every part of speech
+    pub fn parse_list(names: &[String]) -> Result<Vec<Self>, String> {
+        let all = Self::all();
+        let mut parts = Vec::new();
+        for name in names.iter().map(|name| name.trim().to_lowercase()) {
+            if name == "all" {
+                return Ok(all);
+            }
+            let pos = all
+                .iter()
+                .find(|pos| name == pos.name() || name.strip_suffix('s') == Some(pos.name()))
+                .ok_or_else(|| {
+                    let known: Vec<_> = all.iter().map(Self::name).collect();
+                    format!(
+                        "unknown part of speech '{}' (expected all, {})",
+                        name,
+                        known.join(", ")
+                    )
+                })?;
+            if !parts.contains(pos) {
+                parts.push(pos.clone());
+            }
+        }
+        if parts.is_empty() {
+            return Ok(all);
+        }
+        parts.sort_by_key(|pos| all.iter().position(|p| p == pos));
+        Ok(parts)
+    }
+
+    /// Rough share of this part of speech among the words a learner meets
+    pub fn weight(&self) -> f64 {
+        match self {
+            Self::Noun => 0.40,
+            Self::Verb => 0.25,
+            Self::Adjective => 0.15,
+            Self::Adverb => 0.08,
+            Self::Preposition => 0.04,
+            Self::Pronoun => 0.04,
+            Self::Conjunction => 0.03,
+            Self::Interjection => 0.01,
+            // Phrases are added on top of the selection, never picked by it
+            Self::Phrase => 0.0,
+        }
+    }
+}
+
+/// How words are picked from the frequency list. Every strategy picks
+/// `words_per_pos` times the number of chosen parts of speech in total
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Selection {
+    /// The same number of words for every part of speech
+    #[default]
+    Equal,
+    /// Parts of speech in realistic proportions, mostly nouns and verbs
+    Weighted,
+    /// The most frequent words, whatever their part of speech
+    GlobalRank,
+}
+
+/// A cut-off on how often words occur in the corpus, picking however many
+/// words clear it instead of a number per part of speech
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FrequencyThreshold {
+    /// Words counted at least this many times
+    MinFrequency(usize),
+    /// The most frequent words that together make up this percentage of
+    /// the words counted in the list
+    TopPercent(f64),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Word {
     pub text: String,
     pub pos: PartOfSpeech,
+    /// Times the word occurs in the corpus, 0 for lists that only rank words
     pub frequency: usize,
     pub rank: usize,
 }
@@ -39,7 +143,7 @@ pub struct Word {
 impl Word {
     pub fn new(text: String, pos: PartOfSpeech, rank: usize) -> Self {
         Self {
-            text,
+            text: normalize_text(&text),
             pos,
             frequency: 0,
             rank,
@@ -51,6 +155,19 @@ impl Word {
 pub struct FrequencyData {
     pub language: String,
     pub words: HashMap<PartOfSpeech, Vec<Word>>,
+    /// Applied when words are selected, so the cached list stays complete
+    #[serde(skip)]
+    filter: Arc<WordFilter>,
+    /// Words ranked below this are not selected, slicing a larger cached list
+    #[serde(skip)]
+    max_rank: Option<usize>,
+    /// Parts of speech words are selected from
+    #[serde(skip, default = "PartOfSpeech::all")]
+    parts_of_speech: Vec<PartOfSpeech>,
+    /// Why the list could not be downloaded, when the one built into the
+    /// binary stands in for it
+    #[serde(skip)]
+    download_error: Option<String>,
 }
 
 impl FrequencyData {
@@ -58,25 +175,356 @@ impl FrequencyData {
         Self {
             language,
             words: HashMap::new(),
+            filter: Arc::default(),
+            max_rank: None,
+            parts_of_speech: PartOfSpeech::all(),
+            download_error: None,
         }
     }
 
-    pub fn add_word(&mut self, word: Word) {
+    /// Mark the list as standing in for one that failed to download
+    pub fn with_download_error(mut self, error: String) -> Self {
+        self.download_error = Some(error);
+        self
+    }
+
+    pub fn download_error(&self) -> Option<&str> {
+        self.download_error.as_deref()
+    }
+
+    /// Select only words the filter accepts
+    pub fn with_filter(mut self, filter: WordFilter) -> Self {
+        self.filter = Arc::new(filter);
+        self
+    }
+
+    /// Select only words ranked at most `max_rank`
+    pub fn with_max_rank(mut self, max_rank: Option<usize>) -> Self {
+        self.max_rank = max_rank;
+        self
+    }
+
+    /// Select only words of these parts of speech, all of them when empty
+    pub fn with_parts_of_speech(mut self, parts_of_speech: Vec<PartOfSpeech>) -> Self {
+        if !parts_of_speech.is_empty() {
+            self.parts_of_speech = parts_of_speech;
+        }
+        self
+    }
+
+    /// Parts of speech words are selected from
+    pub fn parts_of_speech(&self) -> &[PartOfSpeech] {
+        &self.parts_of_speech
+    }
+
+    /// Add a word, normalizing its spelling so lists that store accents
+    /// differently agree
+    pub fn add_word(&mut self, mut word: Word) {
+        word.text = normalize_text(&word.text);
         self.words.entry(word.pos.clone()).or_default().push(word);
     }
 
     pub fn get_top_words(&self, pos: &PartOfSpeech, count: usize) -> Vec<Word> {
+        if !self.parts_of_speech.contains(pos) {
+            return Vec::new();
+        }
         self.words
             .get(pos)
-            .map(|words| words.iter().take(count).cloned().collect())
+            .map(|words| {
+                words
+                    .iter()
+                    .filter(|word| self.max_rank.is_none_or(|max| word.rank <= max))
+                    .filter(|word| self.filter.matches(&word.text))
+                    .take(count)
+                    .cloned()
+                    .collect()
+            })
             .unwrap_or_default()
     }
 
     pub fn get_all_top_words(&self, count_per_pos: usize) -> Vec<Word> {
         let mut all_words = Vec::new();
-        for pos in PartOfSpeech::all() {
-            all_words.extend(self.get_top_words(&pos, count_per_pos));
+        for pos in &self.parts_of_speech {
+            all_words.extend(self.get_top_words(pos, count_per_pos));
         }
         all_words
     }
+
+    /// The `count` most frequent words ranked after `after_rank`, whatever
+    /// their part of speech, to continue where an earlier pick stopped
+    pub fn next_words(&self, after_rank: usize, count: usize) -> Vec<Word> {
+        let mut words: Vec<Word> = self
+            .words
+            .iter()
+            .filter(|(pos, _)| self.parts_of_speech.contains(pos))
+            .flat_map(|(_, words)| words)
+            .filter(|word| word.rank > after_rank)
+            .filter(|word| self.max_rank.is_none_or(|max| word.rank <= max))
+            .filter(|word| self.filter.matches(&word.text))
+            .cloned()
+            .collect();
+        words.sort_by_key(|word| word.rank);
+        words.truncate(count);
+        words
+    }
+
+    /// Whether the list carries corpus counts; some, like the embedded
+    /// lists, only rank their words
+    pub fn has_counts(&self) -> bool {
+        self.words.values().flatten().any(|word| word.frequency > 0)
+    }
+
+    /// Words of the chosen parts of speech counted at least `min_frequency`
+    /// times, in frequency order
+    pub fn select_min_frequency(&self, min_frequency: usize) -> Vec<Word> {
+        let mut words: Vec<Word> = self
+            .parts_of_speech
+            .iter()
+            .filter_map(|pos| self.words.get(pos))
+            .flatten()
+            .filter(|word| word.frequency >= min_frequency.max(1))
+            .filter(|word| self.max_rank.is_none_or(|max| word.rank <= max))
+            .filter(|word| self.filter.matches(&word.text))
+            .cloned()
+            .collect();
+        words.sort_by_key(|word| word.rank);
+        words
+    }
+
+    /// The most frequent words that together make up `percent` of the words
+    /// counted in the list, less those of other parts of speech or filtered
+    /// out, in frequency order
+    pub fn select_top_percent(&self, percent: f64) -> Vec<Word> {
+        let mut counts: Vec<usize> = self
+            .words
+            .values()
+            .flatten()
+            .filter(|word| self.max_rank.is_none_or(|max| word.rank <= max))
+            .map(|word| word.frequency)
+            .collect();
+        counts.sort_unstable_by(|a, b| b.cmp(a));
+        let target = counts.iter().sum::<usize>() as f64 * percent / 100.0;
+
+        // The count of the word that brings the coverage up to the target
+        let mut covered = 0;
+        let cutoff = counts
+            .into_iter()
+            .find(|count| {
+                covered += count;
+                covered as f64 >= target
+            })
+            .unwrap_or_default();
+        self.select_min_frequency(cutoff)
+    }
+
+    /// Every word that clears the threshold, in frequency order
+    pub fn select_threshold(&self, threshold: FrequencyThreshold) -> Vec<Word> {
+        match threshold {
+            FrequencyThreshold::MinFrequency(min_frequency) => {
+                self.select_min_frequency(min_frequency)
+            }
+            FrequencyThreshold::TopPercent(percent) => self.select_top_percent(percent),
+        }
+    }
+
+    /// Pick words with a selection strategy. Equal keeps the words grouped
+    /// by part of speech; the others return them in frequency order
+    pub fn select(&self, selection: Selection, count_per_pos: usize) -> Vec<Word> {
+        let total = count_per_pos * self.parts_of_speech.len();
+        // Shares of the chosen parts of speech, scaled to add up to one
+        let weights: f64 = self.parts_of_speech.iter().map(PartOfSpeech::weight).sum();
+        let mut selected = match selection {
+            Selection::Equal => return self.get_all_top_words(count_per_pos),
+            Selection::GlobalRank => Vec::new(),
+            Selection::Weighted => self
+                .parts_of_speech
+                .iter()
+                .flat_map(|pos| {
+                    let quota = (total as f64 * pos.weight() / weights).round() as usize;
+                    self.get_top_words(pos, quota)
+                })
+                .collect(),
+        };
+
+        // Fill up with the most frequent words left, also making up for parts
+        // of speech that have fewer words than their share
+        let mut rest: Vec<Word> = self
+            .get_all_top_words(total)
+            .into_iter()
+            .filter(|word| {
+                !selected
+                    .iter()
+                    .any(|s| s.text == word.text && s.pos == word.pos)
+            })
+            .collect();
+        rest.sort_by_key(|word| word.rank);
+        let missing = total.saturating_sub(selected.len());
+        selected.extend(rest.into_iter().take(missing));
+
+        selected.truncate(total);
+        selected.sort_by_key(|word| word.rank);
+        selected
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_top_words_skip_filtered() {
+        let mut data = FrequencyData::new("hr".to_string());
+        for (rank, text) in ["je", "dan", "biti", "kuća"].iter().enumerate() {
+            data.add_word(Word::new(text.to_string(), PartOfSpeech::Noun, rank + 1));
+        }
+
+        let filtered = data
+            .with_filter(WordFilter::new().with_known_words(vec!["dan".to_string()]))
+            .get_top_words(&PartOfSpeech::Noun, 2);
+
+        let texts: Vec<_> = filtered.iter().map(|w| w.text.as_str()).collect();
+        assert_eq!(texts, vec!["je", "biti"]);
+    }
+
+    #[test]
+    fn test_selection_strategies() {
+        let mut data = FrequencyData::new("hr".to_string());
+        for rank in 1..=20 {
+            data.add_word(Word::new(
+                format!("i{}", rank),
+                PartOfSpeech::Interjection,
+                rank,
+            ));
+        }
+        for rank in 21..=60 {
+            data.add_word(Word::new(format!("n{}", rank), PartOfSpeech::Noun, rank));
+        }
+        let count =
+            |words: &[Word], pos: PartOfSpeech| words.iter().filter(|w| w.pos == pos).count();
+
+        // 2 per part of speech is 16 words in total
+        let equal = data.select(Selection::Equal, 2);
+        assert_eq!(equal.len(), 4);
+
+        let global = data.select(Selection::GlobalRank, 2);
+        assert_eq!(global.len(), 16);
+        assert_eq!(count(&global, PartOfSpeech::Interjection), 16);
+
+        // Nouns get their share and the parts of speech with no words are
+        // made up for by the most frequent words left
+        let weighted = data.select(Selection::Weighted, 2);
+        assert_eq!(weighted.len(), 16);
+        assert_eq!(count(&weighted, PartOfSpeech::Noun), 6);
+        assert!(weighted.windows(2).all(|pair| pair[0].rank < pair[1].rank));
+    }
+
+    #[test]
+    fn test_next_words() {
+        let mut data = FrequencyData::new("hr".to_string());
+        for (rank, text) in ["je", "dan", "biti", "kuća", "oko"].iter().enumerate() {
+            let pos = if rank % 2 == 0 {
+                PartOfSpeech::Verb
+            } else {
+                PartOfSpeech::Noun
+            };
+            data.add_word(Word::new(text.to_string(), pos, rank + 1));
+        }
+
+        let texts = |words: Vec<Word>| words.into_iter().map(|w| w.text).collect::<Vec<_>>();
+        assert_eq!(texts(data.next_words(0, 2)), ["je", "dan"]);
+        assert_eq!(texts(data.next_words(2, 2)), ["biti", "kuća"]);
+        assert_eq!(texts(data.next_words(4, 2)), ["oko"]);
+        assert!(data.next_words(5, 2).is_empty());
+    }
+
+    #[test]
+    fn test_parts_of_speech_subset() {
+        let names = |names: &[&str]| {
+            PartOfSpeech::parse_list(&names.iter().map(|n| n.to_string()).collect::<Vec<_>>())
+        };
+        assert_eq!(
+            names(&["verbs", "Noun", "noun"]).unwrap(),
+            [PartOfSpeech::Noun, PartOfSpeech::Verb]
+        );
+        assert_eq!(names(&["all"]).unwrap(), PartOfSpeech::all());
+        assert_eq!(names(&[]).unwrap(), PartOfSpeech::all());
+        assert!(names(&["phrases"]).is_err());
+
+        let mut data = FrequencyData::new("hr".to_string());
+        for (rank, (text, pos)) in [
+            ("je", PartOfSpeech::Verb),
+            ("dan", PartOfSpeech::Noun),
+            ("dobar", PartOfSpeech::Adjective),
+            ("kuća", PartOfSpeech::Noun),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            data.add_word(Word::new(text.to_string(), pos, rank + 1));
+        }
+        let data = data.with_parts_of_speech(vec![PartOfSpeech::Noun, PartOfSpeech::Adjective]);
+
+        let texts = |words: Vec<Word>| words.into_iter().map(|w| w.text).collect::<Vec<_>>();
+        assert!(data.get_top_words(&PartOfSpeech::Verb, 5).is_empty());
+        assert_eq!(texts(data.next_words(0, 5)), ["dan", "dobar", "kuća"]);
+        assert_eq!(
+            texts(data.select(Selection::GlobalRank, 1)),
+            ["dan", "dobar"]
+        );
+        // Two chosen parts of speech share the words between them alone
+        assert_eq!(data.select(Selection::Weighted, 2).len(), 3);
+    }
+
+    #[test]
+    fn test_frequency_thresholds() {
+        let mut data = FrequencyData::new("hr".to_string());
+        for (rank, (text, pos, frequency)) in [
+            ("je", PartOfSpeech::Verb, 500),
+            ("dan", PartOfSpeech::Noun, 300),
+            ("kuća", PartOfSpeech::Noun, 100),
+            ("oko", PartOfSpeech::Noun, 60),
+            ("more", PartOfSpeech::Noun, 40),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            data.add_word(Word {
+                frequency,
+                ..Word::new(text.to_string(), pos, rank + 1)
+            });
+        }
+        assert!(data.has_counts());
+
+        let texts = |words: Vec<Word>| words.into_iter().map(|w| w.text).collect::<Vec<_>>();
+        assert_eq!(texts(data.select_min_frequency(100)), ["je", "dan", "kuća"]);
+        // "je" and "dan" make up 80% of the 1,000 words counted
+        assert_eq!(texts(data.select_top_percent(80.0)), ["je", "dan"]);
+        assert_eq!(texts(data.select_top_percent(81.0)), ["je", "dan", "kuća"]);
+        assert_eq!(data.select_top_percent(100.0).len(), 5);
+
+        // Coverage counts every word, but only chosen ones are picked
+        let nouns = data.with_parts_of_speech(vec![PartOfSpeech::Noun]);
+        assert_eq!(
+            texts(nouns.select_threshold(FrequencyThreshold::TopPercent(80.0))),
+            ["dan"]
+        );
+
+        let mut ranked_only = FrequencyData::new("hr".to_string());
+        ranked_only.add_word(Word::new("dan".to_string(), PartOfSpeech::Noun, 1));
+        assert!(!ranked_only.has_counts());
+        assert!(ranked_only.select_top_percent(50.0).is_empty());
+        assert!(ranked_only.select_min_frequency(0).is_empty());
+    }
+
+    #[test]
+    fn test_max_rank_slices_list() {
+        let mut data = FrequencyData::new("hr".to_string());
+        data.add_word(Word::new("dan".to_string(), PartOfSpeech::Noun, 10));
+        data.add_word(Word::new("kuća".to_string(), PartOfSpeech::Noun, 20_000));
+
+        assert_eq!(data.get_top_words(&PartOfSpeech::Noun, 5).len(), 2);
+
+        let sliced = data.with_max_rank(Some(10_000));
+        assert_eq!(sliced.get_top_words(&PartOfSpeech::Noun, 5).len(), 1);
+    }
 }
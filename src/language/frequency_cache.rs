@@ -0,0 +1,185 @@
+use crate::cache_file;
+use crate::error::Result;
+use crate::language::frequency::FrequencyData;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Layout version of cached frequency lists. Bump it along with a new entry
+/// in [`MIGRATIONS`] whenever the serialized form of `FrequencyData` changes
+pub(crate) const FREQUENCY_CACHE_VERSION: u32 = 2;
+
+/// Turns the `data` of a cache file at version `i` into version `i + 1`
+type Migration = fn(Value) -> Option<Value>;
+
+/// One migration per version below the current one, in order
+const MIGRATIONS: [Migration; FREQUENCY_CACHE_VERSION as usize] = [
+    // 0: a bare `FrequencyData` without an envelope, which is already the
+    // shape of version 1's data
+    Some,
+    // 1: words without their corpus counts, which only a fresh fetch has
+    |_| None,
+];
+
+/// A cache file: the list and the layout it was written in
+#[derive(Serialize, Deserialize)]
+struct CacheEnvelope<T> {
+    version: u32,
+    /// Seconds since the Unix epoch
+    generated_at: u64,
+    data: T,
+}
+
+/// Write a frequency list to a cache file in the current layout
+pub(crate) fn write_frequency_cache(path: &Path, data: &FrequencyData) -> Result<()> {
+    let envelope = CacheEnvelope {
+        version: FREQUENCY_CACHE_VERSION,
+        generated_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+        data,
+    };
+    cache_file::write_json(path, &envelope)
+}
+
+/// Read a cached frequency list, migrating one written by an older version
+/// and saving it back in the current layout. None when nothing is cached or
+/// the file can't be read as any known layout, so the list is fetched again
+pub(crate) fn read_frequency_cache(path: &Path) -> Result<Option<FrequencyData>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let content = std::fs::read_to_string(path)?;
+    let value = match serde_json::from_str::<Value>(&content) {
+        Ok(value) => value,
+        // A write cut short; keep the file aside rather than overwrite it
+        Err(e) => {
+            cache_file::quarantine(path, &e.to_string());
+            return Ok(None);
+        }
+    };
+    let (version, mut data) = match value {
+        Value::Object(mut fields) if fields.contains_key("version") => {
+            let version = fields.get("version").and_then(Value::as_u64);
+            match (version, fields.remove("data")) {
+                (Some(version), Some(data)) => (version as u32, data),
+                _ => return Ok(incompatible(path, "its envelope is malformed")),
+            }
+        }
+        bare => (0, bare),
+    };
+
+    if version > FREQUENCY_CACHE_VERSION {
+        return Ok(incompatible(
+            path,
+            &format!("it was written by a newer version (layout {})", version),
+        ));
+    }
+    for (from, migrate) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        data = match migrate(data) {
+            Some(data) => data,
+            None => {
+                return Ok(incompatible(
+                    path,
+                    &format!("layout {} can't be migrated", from),
+                ))
+            }
+        };
+    }
+
+    let data: FrequencyData = match serde_json::from_value(data) {
+        Ok(data) => data,
+        Err(e) => return Ok(incompatible(path, &e.to_string())),
+    };
+    if version < FREQUENCY_CACHE_VERSION {
+        tracing::info!(
+            "Migrated frequency cache {} from layout {} to {}",
+            path.display(),
+            version,
+            FREQUENCY_CACHE_VERSION
+        );
+        resave(path, &data)?;
+    }
+    Ok(Some(data))
+}
+
+/// Rewrite a migrated file without making it look freshly fetched, since its
+/// age decides when it is revalidated
+fn resave(path: &Path, data: &FrequencyData) -> Result<()> {
+    let modified = std::fs::metadata(path)?.modified()?;
+    write_frequency_cache(path, data)?;
+    std::fs::File::options()
+        .write(true)
+        .open(path)?
+        .set_modified(modified)?;
+    Ok(())
+}
+
+fn incompatible(path: &Path, reason: &str) -> Option<FrequencyData> {
+    tracing::warn!(
+        "Frequency cache {} is incompatible ({}), regenerating",
+        path.display(),
+        reason
+    );
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::language::frequency::{PartOfSpeech, Word};
+    use tempfile::tempdir;
+
+    fn sample() -> FrequencyData {
+        let mut data = FrequencyData::new("hr".to_string());
+        data.add_word(Word::new("dan".to_string(), PartOfSpeech::Noun, 1));
+        data
+    }
+
+    #[test]
+    fn test_round_trip_and_migration() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("hr_frequency.json");
+        assert!(read_frequency_cache(&path).unwrap().is_none());
+
+        write_frequency_cache(&path, &sample()).unwrap();
+        let data = read_frequency_cache(&path).unwrap().unwrap();
+        assert_eq!(data.get_top_words(&PartOfSpeech::Noun, 5).len(), 1);
+        let saved: Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved["version"], FREQUENCY_CACHE_VERSION);
+        assert_eq!(saved["data"]["language"], "hr");
+
+        // Files from before the envelope, and those of layout 1, have no
+        // word counts and are fetched again
+        std::fs::write(&path, serde_json::to_string(&sample()).unwrap()).unwrap();
+        assert!(read_frequency_cache(&path).unwrap().is_none());
+        let layout_1 = serde_json::json!({ "version": 1, "generated_at": 0, "data": sample() });
+        std::fs::write(&path, layout_1.to_string()).unwrap();
+        assert!(read_frequency_cache(&path).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_incompatible_cache_is_regenerated() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("hr_frequency.json");
+
+        let newer = serde_json::json!({
+            "version": FREQUENCY_CACHE_VERSION + 1,
+            "generated_at": 0,
+            "data": {},
+        });
+        std::fs::write(&path, newer.to_string()).unwrap();
+        assert!(read_frequency_cache(&path).unwrap().is_none());
+
+        std::fs::write(&path, r#"{"language": 5}"#).unwrap();
+        assert!(read_frequency_cache(&path).unwrap().is_none());
+
+        // A file cut short by a crash is moved aside
+        std::fs::write(&path, r#"{"version": 2, "da"#).unwrap();
+        assert!(read_frequency_cache(&path).unwrap().is_none());
+        assert!(!path.exists());
+    }
+}
@@ -1,68 +1,148 @@
 use crate::error::{AnkiDeckBuilderError, Result};
 use crate::language::frequency::{FrequencyData, PartOfSpeech, Word};
-use reqwest::Client;
-use std::time::Duration;
+use crate::language::frequency_source::{Attribution, FrequencySource, ListSize};
+use crate::language::packs::{LanguagePack, LanguagePacks};
+use crate::language::text::{grapheme_len, min_word_length};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
 
-/// Fetch Croatian frequency data from external sources
-pub async fn fetch_croatian_frequency() -> Result<FrequencyData> {
-    let client = Client::builder()
-        .timeout(Duration::from_secs(60))
-        .build()
-        .map_err(AnkiDeckBuilderError::HttpError)?;
+/// Hermit Dave's FrequencyWords list for Croatian (50k words)
+pub const CROATIAN_FREQUENCY_URL: &str =
+    "https://raw.githubusercontent.com/hermitdave/FrequencyWords/master/content/2018/hr/hr_50k.txt";
 
-    // Try Hermit Dave's FrequencyWords repository first
-    tracing::info!("Fetching Croatian frequency data from GitHub...");
+/// Hermit Dave's complete FrequencyWords list for Croatian
+pub const CROATIAN_FULL_FREQUENCY_URL: &str =
+    "https://raw.githubusercontent.com/hermitdave/FrequencyWords/master/content/2018/hr/hr_full.txt";
 
-    let url = "https://raw.githubusercontent.com/hermitdave/FrequencyWords/master/content/2018/hr/hr_50k.txt";
+/// HTTP validators from the last download, used for conditional requests
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FetchMetadata {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    /// Size of the list that was downloaded. Caches from before sizes could
+    /// be chosen hold the default list
+    #[serde(default)]
+    pub list_size: ListSize,
+}
+
+/// Result of a conditional fetch
+#[derive(Debug)]
+pub enum FetchOutcome {
+    /// Upstream has not changed since the validators were recorded
+    NotModified,
+    /// Upstream returned new data
+    Modified {
+        data: FrequencyData,
+        metadata: FetchMetadata,
+    },
+}
 
-    let response = client.get(url).send().await.map_err(|e| {
-        AnkiDeckBuilderError::FrequencyDataNotFound(format!(
-            "Failed to fetch Croatian frequency data: {}",
-            e
+/// Hermit Dave's FrequencyWords lists, built from OpenSubtitles
+#[derive(Debug, Clone, Default)]
+pub struct OpenSubtitlesSource {
+    /// Packs give lists for the languages it has none of
+    packs: LanguagePacks,
+}
+
+impl OpenSubtitlesSource {
+    pub fn new(packs: LanguagePacks) -> Self {
+        Self { packs }
+    }
+}
+
+#[async_trait]
+impl FrequencySource for OpenSubtitlesSource {
+    fn id(&self) -> &'static str {
+        "opensubtitles"
+    }
+
+    fn attribution(&self) -> Option<Attribution> {
+        Some(Attribution::new(
+            "FrequencyWords",
+            "Hermit Dave",
+            "CC-BY-SA-4.0",
+            "https://github.com/hermitdave/FrequencyWords",
         ))
-    })?;
+    }
 
-    if !response.status().is_success() {
-        return Err(AnkiDeckBuilderError::FrequencyDataNotFound(format!(
-            "HTTP {}: Could not download Croatian frequency list",
-            response.status()
-        )));
+    fn supports(&self, language_code: &str) -> bool {
+        self.download_url(language_code, ListSize::default())
+            .is_some()
     }
 
-    let text = response.text().await.map_err(|e| {
-        AnkiDeckBuilderError::FrequencyDataNotFound(format!("Failed to read frequency data: {}", e))
-    })?;
+    fn download_url(&self, language_code: &str, size: ListSize) -> Option<String> {
+        if language_code != "hr" {
+            // A language pack's list comes in one size, sliced to the others
+            return self
+                .packs
+                .get(language_code)
+                .and_then(|pack| pack.frequency_url.clone());
+        }
+        // There is no 10k file; the 50k one is sliced instead
+        let url = match size {
+            ListSize::Top10k | ListSize::Top50k => CROATIAN_FREQUENCY_URL,
+            ListSize::Full => CROATIAN_FULL_FREQUENCY_URL,
+        };
+        Some(url.to_string())
+    }
 
-    parse_frequency_file(&text, "hr")
+    fn parse(&self, language_code: &str, raw: &[u8]) -> Result<FrequencyData> {
+        let text = std::str::from_utf8(raw).map_err(|e| {
+            AnkiDeckBuilderError::FrequencyDataNotFound(format!(
+                "Failed to read frequency data: {}",
+                e
+            ))
+        })?;
+        parse_frequency_file(text, language_code, self.packs.get(language_code))
+    }
 }
 
 /// Parse frequency file in format: "word frequency"
-fn parse_frequency_file(content: &str, language_code: &str) -> Result<FrequencyData> {
-    let mut data = FrequencyData::new(language_code.to_string());
-
-    for (rank, line) in content.lines().enumerate() {
+fn parse_frequency_file(
+    content: &str,
+    language_code: &str,
+    pack: Option<&LanguagePack>,
+) -> Result<FrequencyData> {
+    let ranked = content.lines().enumerate().filter_map(|(rank, line)| {
         let parts: Vec<&str> = line.split_whitespace().collect();
 
         if parts.len() < 2 {
-            continue;
+            return None;
         }
 
-        let word_text = parts[0];
+        Some((rank + 1, parts[0], parts[1].parse().unwrap_or(0)))
+    });
+
+    Ok(frequency_data_from_ranked(ranked, language_code, pack))
+}
+
+/// Build frequency data from (rank, word, count) triples in frequency order,
+/// guessing parts of speech with the language's pack if it has one
+pub(crate) fn frequency_data_from_ranked<'a>(
+    ranked: impl IntoIterator<Item = (usize, &'a str, usize)>,
+    language_code: &str,
+    pack: Option<&LanguagePack>,
+) -> FrequencyData {
+    let mut data = FrequencyData::new(language_code.to_string());
 
+    for (rank, word_text, frequency) in ranked {
         // Skip very short words (likely articles/prepositions)
-        if word_text.len() < 2 {
+        if grapheme_len(word_text) < min_word_length(language_code) {
             continue;
         }
 
-        // Categorize by POS using simple heuristics for Croatian
+        // Categorize by POS using the language pack's rules, or simple
+        // heuristics for Croatian
         // TODO: Use actual POS tagging in future versions
-        let pos = guess_croatian_pos(word_text);
+        let pos = pack
+            .and_then(|pack| pack.guess_pos(word_text))
+            .unwrap_or_else(|| guess_croatian_pos(word_text));
 
         data.add_word(Word {
             text: word_text.to_string(),
             pos,
-            frequency: 0,
-            rank: rank + 1,
+            frequency,
+            rank,
         });
     }
 
@@ -71,7 +151,7 @@ fn parse_frequency_file(content: &str, language_code: &str) -> Result<FrequencyD
         data.words.values().map(|v| v.len()).sum::<usize>()
     );
 
-    Ok(data)
+    data
 }
 
 /// Simple POS guessing for Croatian based on word endings
@@ -102,7 +182,7 @@ fn guess_croatian_pos(word: &str) -> PartOfSpeech {
     // Common adverb markers
     if word_lower.ends_with("no")
         || word_lower.ends_with("ko")
-        || word_lower.ends_with("je") && word_lower.len() > 4
+        || word_lower.ends_with("je") && grapheme_len(&word_lower) > 4
     {
         return PartOfSpeech::Adverb;
     }
@@ -138,6 +218,8 @@ fn guess_croatian_pos(word: &str) -> PartOfSpeech {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::http::HttpSettings;
+    use crate::language::downloader::Downloader;
 
     #[test]
     fn test_guess_croatian_pos() {
@@ -154,20 +236,51 @@ mod tests {
     #[test]
     fn test_parse_frequency_file() {
         let sample = "biti 12345\ndan 11000\nhrvatski 9000\nu 8000";
-        let data = parse_frequency_file(sample, "hr").unwrap();
+        let data = parse_frequency_file(sample, "hr", None).unwrap();
 
         assert_eq!(data.language, "hr");
-        assert!(data.words.get(&PartOfSpeech::Verb).is_some());
-        assert!(data.words.get(&PartOfSpeech::Noun).is_some());
+        assert!(data.words.contains_key(&PartOfSpeech::Verb));
+        assert!(data.words.contains_key(&PartOfSpeech::Noun));
+        let verbs = data.get_top_words(&PartOfSpeech::Verb, 1);
+        assert_eq!(
+            (verbs[0].text.as_str(), verbs[0].frequency),
+            ("biti", 12345)
+        );
+    }
+
+    #[test]
+    fn test_single_letters_are_skipped() {
+        let data =
+            frequency_data_from_ranked([(1, "ć", 90), (2, "u", 80), (3, "dan", 70)], "hr", None);
+        let texts: Vec<_> = data
+            .get_all_top_words(10)
+            .iter()
+            .map(|w| w.text.clone())
+            .collect();
+        assert_eq!(texts, vec!["dan".to_string()]);
+
+        let data = frequency_data_from_ranked([(1, "日", 90)], "ja", None);
+        assert_eq!(data.get_all_top_words(10).len(), 1);
     }
 
     #[tokio::test]
     #[ignore] // Requires internet connection
     async fn test_fetch_croatian_frequency() {
-        let result = fetch_croatian_frequency().await;
-        assert!(result.is_ok());
+        let temp_dir = tempfile::tempdir().unwrap();
+        let downloader = Downloader::new(temp_dir.path(), &HttpSettings::default()).unwrap();
+        let outcome = OpenSubtitlesSource::default()
+            .fetch(
+                "hr",
+                ListSize::default(),
+                &downloader,
+                &FetchMetadata::default(),
+            )
+            .await
+            .unwrap();
 
-        let data = result.unwrap();
+        let FetchOutcome::Modified { data, .. } = outcome else {
+            panic!("expected data for an unconditional fetch");
+        };
         assert_eq!(data.language, "hr");
         assert!(!data.words.is_empty());
     }
@@ -1,5 +1,16 @@
-use crate::error::Result;
-use crate::language::frequency::{FrequencyData, PartOfSpeech, Word};
+use crate::cache_file;
+use crate::cancel::CancellationToken;
+use crate::config::ServiceSettings;
+use crate::error::{AnkiDeckBuilderError, Result};
+use crate::language::cache_policy::CachePolicy;
+use crate::language::detect::verify_language;
+use crate::language::downloader::Downloader;
+use crate::language::embedded::embedded_frequency_data;
+use crate::language::frequency::FrequencyData;
+use crate::language::frequency_cache::{read_frequency_cache, write_frequency_cache};
+use crate::language::frequency_fetcher::{FetchMetadata, FetchOutcome, OpenSubtitlesSource};
+use crate::language::frequency_source::{FrequencySource, ListSize};
+use crate::language::packs::LanguagePacks;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
@@ -11,69 +22,236 @@ pub struct FrequencyEntry {
     pub rank: usize,
 }
 
-/// Load frequency data for a given language
+/// Load frequency data for a given language from the default source
 pub async fn load_frequency_data(
     language_code: &str,
     cache_dir: &std::path::Path,
 ) -> Result<FrequencyData> {
-    // Try cache first
-    if let Some(cached_data) = try_load_from_cache(language_code, cache_dir)? {
-        tracing::info!("Loaded frequency data from cache for {}", language_code);
-        return Ok(cached_data);
+    load_frequency_data_from(
+        &OpenSubtitlesSource::default(),
+        language_code,
+        ListSize::default(),
+        cache_dir,
+        &ServiceSettings::default(),
+        &CancellationToken::new(),
+    )
+    .await
+}
+
+/// Load frequency data for a given language from a specific source.
+/// Each source is cached separately, holding the complete parsed list of the
+/// largest size fetched so far; smaller sizes are sliced from it. The files
+/// it was parsed from are kept under `raw/` in the source's cache directory.
+/// A cancelled download keeps what it has and resumes on the next call
+pub async fn load_frequency_data_from(
+    source: &dyn FrequencySource,
+    language_code: &str,
+    size: ListSize,
+    cache_dir: &std::path::Path,
+    services: &ServiceSettings,
+    cancel: &CancellationToken,
+) -> Result<FrequencyData> {
+    let policy = services.cache_policy();
+    load_frequency_data_with(
+        source,
+        language_code,
+        size,
+        cache_dir,
+        services,
+        cancel,
+        &policy,
+    )
+    .await
+}
+
+/// Like [`load_frequency_data_from`], revalidating the cache as `policy` says
+pub async fn load_frequency_data_with(
+    source: &dyn FrequencySource,
+    language_code: &str,
+    size: ListSize,
+    cache_dir: &std::path::Path,
+    services: &ServiceSettings,
+    cancel: &CancellationToken,
+    policy: &CachePolicy,
+) -> Result<FrequencyData> {
+    // The built-in list is the whole list of a language the source has none
+    // of. It is not cached, as it ships with every version
+    if !source.supports(language_code) {
+        if let Some(data) = embedded_frequency_data(language_code) {
+            tracing::info!("Using the embedded word list for {}", language_code);
+            return Ok(data.with_max_rank(size.max_rank()));
+        }
     }
 
-    // Fetch from sources
-    tracing::info!("Fetching frequency data for {}", language_code);
-    let data = fetch_frequency_data(language_code).await?;
+    let source_id = source.id();
+    let cached_metadata = load_fetch_metadata(source_id, language_code, cache_dir);
 
-    // Save to cache
-    save_to_cache(language_code, &data, cache_dir)?;
+    // Try cache first, unless it holds a smaller list than asked for
+    let cached = try_load_from_cache(source_id, language_code, cache_dir, policy)?
+        .filter(|_| cached_metadata.list_size >= size);
+    let stale = match cached {
+        Some((cached_data, false)) => {
+            tracing::info!("Loaded frequency data from cache for {}", language_code);
+            return Ok(cached_data.with_max_rank(size.max_rank()));
+        }
+        Some((cached_data, true)) => Some(cached_data),
+        None => None,
+    };
+
+    // Fetch from sources, conditionally if we already hold a stale copy.
+    // A stale copy is revalidated at its own size so it never shrinks
+    let (fetch_size, validators) = match stale {
+        Some(_) => (cached_metadata.list_size, cached_metadata),
+        None => (size, FetchMetadata::default()),
+    };
+
+    tracing::info!("Fetching frequency data for {}", language_code);
+    let downloader = Downloader::new(get_raw_dir(source_id, cache_dir), &services.http)?
+        .with_cancellation(cancel.clone());
+    let outcome = match fetch_frequency_data(
+        source,
+        language_code,
+        fetch_size,
+        &downloader,
+        &validators,
+        &services.language_packs,
+    )
+    .await
+    {
+        Ok(outcome) => outcome,
+        Err(e @ AnkiDeckBuilderError::Cancelled(_)) => return Err(e),
+        Err(e) => match stale {
+            // A stale list is better than none when the refresh fails
+            Some(data) => {
+                tracing::warn!("Refresh failed, using stale cache: {}", e);
+                return Ok(data.with_max_rank(size.max_rank()));
+            }
+            // Offline with nothing cached, the built-in list will do. It
+            // is not cached, so the next run online fetches the real one
+            None => match embedded_frequency_data(language_code) {
+                Some(data) => {
+                    tracing::warn!("Fetch failed, using the embedded word list: {}", e);
+                    return Ok(data
+                        .with_max_rank(size.max_rank())
+                        .with_download_error(e.to_string()));
+                }
+                None => return Err(e),
+            },
+        },
+    };
+
+    let data = match (outcome, stale) {
+        (FetchOutcome::NotModified, Some(data)) => {
+            touch_cache(source_id, language_code, cache_dir, policy)?;
+            data
+        }
+        (FetchOutcome::Modified { data, metadata }, _) => {
+            // Save to cache
+            save_to_cache(source_id, language_code, &data, cache_dir)?;
+            let metadata = FetchMetadata {
+                list_size: fetch_size,
+                ..metadata
+            };
+            save_fetch_metadata(source_id, language_code, &metadata, cache_dir)?;
+            data
+        }
+        (FetchOutcome::NotModified, None) => {
+            return Err(AnkiDeckBuilderError::FrequencyDataNotFound(format!(
+                "Server reported no change but nothing is cached for {}",
+                language_code
+            )))
+        }
+    };
 
-    Ok(data)
+    Ok(data.with_max_rank(size.max_rank()))
 }
 
-/// Try to load frequency data from cache
+/// Try to load frequency data from cache. The flag is true when the policy
+/// says the cache should be revalidated
 fn try_load_from_cache(
+    source_id: &str,
     language_code: &str,
     cache_dir: &std::path::Path,
-) -> Result<Option<FrequencyData>> {
-    let cache_file = get_cache_file_path(language_code, cache_dir);
+    policy: &CachePolicy,
+) -> Result<Option<(FrequencyData, bool)>> {
+    let cache_file = get_cache_file_path(source_id, language_code, cache_dir);
 
-    if !cache_file.exists() {
+    let Some(data) = read_frequency_cache(&cache_file)? else {
         return Ok(None);
-    }
+    };
 
-    // Check if cache is stale (older than 30 days)
     let metadata = std::fs::metadata(&cache_file)?;
-    if let Ok(modified) = metadata.modified() {
-        let age = modified.elapsed().unwrap_or_default();
-        if age.as_secs() > 30 * 24 * 60 * 60 {
-            tracing::warn!("Cache is stale, will refetch");
-            return Ok(None);
-        }
+    let stale = match metadata.modified() {
+        Ok(modified) => policy.is_stale(modified),
+        Err(_) => false,
+    };
+    if stale {
+        tracing::warn!("Cache is stale, will check for upstream changes");
     }
 
-    let content = std::fs::read_to_string(&cache_file)?;
-    let data: FrequencyData = serde_json::from_str(&content)?;
+    Ok(Some((data, stale)))
+}
+
+/// When a source's cached list for the language was last written or
+/// confirmed current, None when nothing is cached
+pub(crate) fn cache_modified(
+    source_id: &str,
+    language_code: &str,
+    cache_dir: &std::path::Path,
+) -> Option<std::time::SystemTime> {
+    std::fs::metadata(get_cache_file_path(source_id, language_code, cache_dir))
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// Mark the cache as fresh again after upstream confirmed it is unchanged
+fn touch_cache(
+    source_id: &str,
+    language_code: &str,
+    cache_dir: &std::path::Path,
+    policy: &CachePolicy,
+) -> Result<()> {
+    let cache_file = get_cache_file_path(source_id, language_code, cache_dir);
+    std::fs::File::options()
+        .write(true)
+        .open(&cache_file)?
+        .set_modified(policy.now())?;
+    tracing::info!("Frequency cache for {} is still current", language_code);
+    Ok(())
+}
+
+/// Load the HTTP validators recorded with the cached data
+fn load_fetch_metadata(
+    source_id: &str,
+    language_code: &str,
+    cache_dir: &std::path::Path,
+) -> FetchMetadata {
+    cache_file::read_json(&get_metadata_file_path(source_id, language_code, cache_dir))
+        .unwrap_or_default()
+}
 
-    Ok(Some(data))
+/// Save the HTTP validators for the cached data
+fn save_fetch_metadata(
+    source_id: &str,
+    language_code: &str,
+    metadata: &FetchMetadata,
+    cache_dir: &std::path::Path,
+) -> Result<()> {
+    cache_file::write_json(
+        &get_metadata_file_path(source_id, language_code, cache_dir),
+        metadata,
+    )
 }
 
 /// Save frequency data to cache
 fn save_to_cache(
+    source_id: &str,
     language_code: &str,
     data: &FrequencyData,
     cache_dir: &std::path::Path,
 ) -> Result<()> {
-    let cache_file = get_cache_file_path(language_code, cache_dir);
-
-    // Create cache directory if it doesn't exist
-    if let Some(parent) = cache_file.parent() {
-        std::fs::create_dir_all(parent)?;
-    }
-
-    let json = serde_json::to_string_pretty(data)?;
-    std::fs::write(&cache_file, json)?;
+    let cache_file = get_cache_file_path(source_id, language_code, cache_dir);
+    write_frequency_cache(&cache_file, data)?;
 
     tracing::info!("Saved frequency data to cache: {}", cache_file.display());
 
@@ -81,84 +259,138 @@ fn save_to_cache(
 }
 
 /// Get cache file path for a language
-fn get_cache_file_path(language_code: &str, cache_dir: &std::path::Path) -> PathBuf {
+fn get_cache_file_path(
+    source_id: &str,
+    language_code: &str,
+    cache_dir: &std::path::Path,
+) -> PathBuf {
     cache_dir
         .join("frequency")
+        .join(source_id)
         .join(format!("{}_frequency.json", language_code))
 }
 
-/// Fetch frequency data from sources
-async fn fetch_frequency_data(language_code: &str) -> Result<FrequencyData> {
-    match language_code {
-        "hr" => load_croatian_data().await,
-        "es" => load_spanish_data().await,
-        _ => {
-            // For now, use embedded sample data for other languages
-            load_sample_data(language_code).await
-        }
-    }
+/// Get the directory a source's downloaded files are kept in
+fn get_raw_dir(source_id: &str, cache_dir: &std::path::Path) -> PathBuf {
+    cache_dir.join("frequency").join(source_id).join("raw")
 }
 
-/// Load Croatian frequency data
-async fn load_croatian_data() -> Result<FrequencyData> {
-    use crate::language::frequency_fetcher::fetch_croatian_frequency;
-
-    tracing::info!("Fetching Croatian frequency data from online sources...");
-
-    // Fetch from Hermit Dave's FrequencyWords repository (50k words)
-    fetch_croatian_frequency().await
+/// Get metadata file path for a language's cached frequency data
+fn get_metadata_file_path(
+    source_id: &str,
+    language_code: &str,
+    cache_dir: &std::path::Path,
+) -> PathBuf {
+    cache_dir
+        .join("frequency")
+        .join(source_id)
+        .join(format!("{}_frequency.meta.json", language_code))
 }
 
-/// Load Spanish frequency data
-async fn load_spanish_data() -> Result<FrequencyData> {
-    tracing::info!("Loading Spanish frequency data (embedded sample)");
-
-    let mut data = FrequencyData::new("es".to_string());
-
-    // Sample Spanish words (for testing)
-    let nouns = vec![
-        ("día", 1),
-        ("tiempo", 2),
-        ("año", 3),
-        ("cosa", 4),
-        ("hombre", 5),
-        ("mundo", 6),
-        ("vida", 7),
-        ("mano", 8),
-        ("parte", 9),
-        ("niño", 10),
-    ];
-
-    for (word, rank) in nouns {
-        data.add_word(Word {
-            text: word.to_string(),
-            pos: PartOfSpeech::Noun,
-            frequency: 0,
-            rank,
-        });
-    }
-
-    Ok(data)
-}
+/// Fetch frequency data from the source, or an empty list for languages it
+/// does not cover
+async fn fetch_frequency_data(
+    source: &dyn FrequencySource,
+    language_code: &str,
+    size: ListSize,
+    downloader: &Downloader,
+    validators: &FetchMetadata,
+    packs: &LanguagePacks,
+) -> Result<FetchOutcome> {
+    if source.supports(language_code) {
+        let outcome = source
+            .fetch(language_code, size, downloader, validators)
+            .await?;
+        if let FetchOutcome::Modified { data, .. } = &outcome {
+            verify_language(data, language_code, packs)?;
+        }
+        return Ok(outcome);
+    }
 
-/// Load sample data for other languages
-async fn load_sample_data(language_code: &str) -> Result<FrequencyData> {
     tracing::warn!(
         "No specific data for {}, returning empty dataset",
         language_code
     );
-    Ok(FrequencyData::new(language_code.to_string()))
+    Ok(FetchOutcome::Modified {
+        data: FrequencyData::new(language_code.to_string()),
+        metadata: FetchMetadata::default(),
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::language::frequency::{PartOfSpeech, Selection, Word};
+    use async_trait::async_trait;
+    use std::sync::atomic::{AtomicUsize, Ordering};
     use tempfile::tempdir;
 
+    /// Serves a word at every rank up to the list size, counting downloads
+    #[derive(Default)]
+    struct CountingSource {
+        fetches: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl FrequencySource for CountingSource {
+        fn id(&self) -> &'static str {
+            "counting"
+        }
+
+        fn supports(&self, _language_code: &str) -> bool {
+            true
+        }
+
+        fn download_url(&self, _language_code: &str, _size: ListSize) -> Option<String> {
+            None
+        }
+
+        fn parse(&self, language_code: &str, _raw: &[u8]) -> Result<FrequencyData> {
+            Ok(FrequencyData::new(language_code.to_string()))
+        }
+
+        async fn fetch(
+            &self,
+            language_code: &str,
+            size: ListSize,
+            _downloader: &Downloader,
+            _validators: &FetchMetadata,
+        ) -> Result<FetchOutcome> {
+            self.fetches.fetch_add(1, Ordering::SeqCst);
+            let mut data = FrequencyData::new(language_code.to_string());
+            let ranks: &[usize] = match size {
+                ListSize::Full => &[5, 20_000, 80_000],
+                _ => &[5, 20_000],
+            };
+            for rank in ranks {
+                data.add_word(Word::new(format!("w{}", rank), PartOfSpeech::Noun, *rank));
+            }
+            Ok(FetchOutcome::Modified {
+                data,
+                metadata: FetchMetadata::default(),
+            })
+        }
+    }
+
     #[tokio::test]
     #[ignore] // Requires internet connection to fetch real data
     async fn test_load_croatian_data() {
-        let data = load_croatian_data().await.unwrap();
+        let temp_dir = tempdir().unwrap();
+        let downloader =
+            Downloader::new(temp_dir.path(), &ServiceSettings::default().http).unwrap();
+        let outcome = OpenSubtitlesSource::default()
+            .fetch(
+                "hr",
+                ListSize::default(),
+                &downloader,
+                &FetchMetadata::default(),
+            )
+            .await
+            .unwrap();
+        let data = match outcome {
+            FetchOutcome::Modified { data, .. } => data,
+            FetchOutcome::NotModified => panic!("expected data for unconditional fetch"),
+        };
         assert_eq!(data.language, "hr");
 
         // Should have words in multiple categories
@@ -167,25 +399,287 @@ mod tests {
         assert!(all_words.len() >= 10, "Should have at least 10 words");
     }
 
+    /// The OpenSubtitles parser fed from a local server, so caching is
+    /// tested without the network
+    struct StubbedOpenSubtitles {
+        url: String,
+    }
+
+    #[async_trait]
+    impl FrequencySource for StubbedOpenSubtitles {
+        fn id(&self) -> &'static str {
+            OpenSubtitlesSource::default().id()
+        }
+
+        fn supports(&self, language_code: &str) -> bool {
+            language_code == "hr"
+        }
+
+        fn download_url(&self, _language_code: &str, _size: ListSize) -> Option<String> {
+            Some(self.url.clone())
+        }
+
+        fn parse(&self, language_code: &str, raw: &[u8]) -> Result<FrequencyData> {
+            OpenSubtitlesSource::default().parse(language_code, raw)
+        }
+    }
+
     #[tokio::test]
     async fn test_caching() {
         let temp_dir = tempdir().unwrap();
         let cache_dir = temp_dir.path().to_path_buf();
+        let cancel = CancellationToken::new();
+        let services = ServiceSettings::default();
+
+        let mut server = mockito::Server::new_async().await;
+        let list = server
+            .mock("GET", "/hr_50k.txt")
+            .with_body("biti 900\nimati 800\nkuća 700\ndobar 600\nvrijeme 500\n")
+            .expect(1)
+            .create_async()
+            .await;
+        let source = StubbedOpenSubtitles {
+            url: format!("{}/hr_50k.txt", server.url()),
+        };
+        let load = || {
+            load_frequency_data_from(
+                &source,
+                "hr",
+                ListSize::default(),
+                &cache_dir,
+                &services,
+                &cancel,
+            )
+        };
 
         // First load (will create cache)
-        let data1 = load_frequency_data("hr", &cache_dir).await.unwrap();
+        let data1 = load().await.unwrap();
 
         // Second load (should use cache)
-        let data2 = load_frequency_data("hr", &cache_dir).await.unwrap();
+        let data2 = load().await.unwrap();
 
         assert_eq!(data1.language, data2.language);
+        assert_eq!(data1.get_all_top_words(5).len(), 5);
         assert_eq!(
             data1.get_all_top_words(5).len(),
             data2.get_all_top_words(5).len()
         );
+        list.assert_async().await;
 
         // Verify cache file exists
-        let cache_file = get_cache_file_path("hr", &cache_dir);
+        let cache_file = get_cache_file_path("opensubtitles", "hr", &cache_dir);
         assert!(cache_file.exists());
     }
+
+    #[tokio::test]
+    async fn test_embedded_list_needs_no_network() {
+        let temp_dir = tempdir().unwrap();
+
+        // OpenSubtitles has no Spanish list of its own
+        let data = load_frequency_data("es", temp_dir.path()).await.unwrap();
+        assert_eq!(data.language, "es");
+        assert_eq!(data.select(Selection::Weighted, 100).len(), 800);
+        assert!(data.download_error().is_none());
+        // It ships with the binary, so it is never cached
+        assert!(!get_cache_file_path("opensubtitles", "es", temp_dir.path()).exists());
+    }
+
+    async fn load_counting(
+        source: &CountingSource,
+        cache_dir: &std::path::Path,
+        policy: &CachePolicy,
+    ) {
+        load_frequency_data_with(
+            source,
+            "xx",
+            ListSize::default(),
+            cache_dir,
+            &ServiceSettings::default(),
+            &CancellationToken::new(),
+            policy,
+        )
+        .await
+        .unwrap();
+    }
+
+    fn set_cache_modified(cache_dir: &std::path::Path, modified: std::time::SystemTime) {
+        let cache_file = get_cache_file_path("counting", "xx", cache_dir);
+        std::fs::File::options()
+            .write(true)
+            .open(&cache_file)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+    }
+
+    fn is_stale(cache_dir: &std::path::Path, policy: &CachePolicy) -> bool {
+        try_load_from_cache("counting", "xx", cache_dir, policy)
+            .unwrap()
+            .unwrap()
+            .1
+    }
+
+    #[tokio::test]
+    async fn test_stale_cache_is_refreshed() {
+        let temp_dir = tempdir().unwrap();
+        let cache_dir = temp_dir.path().to_path_buf();
+        let policy = CachePolicy::default();
+        let source = CountingSource::default();
+
+        load_counting(&source, &cache_dir, &policy).await;
+
+        // Age the cache past the max age
+        let day = std::time::Duration::from_secs(24 * 60 * 60);
+        set_cache_modified(&cache_dir, std::time::SystemTime::now() - 31 * day);
+        assert!(is_stale(&cache_dir, &policy));
+
+        load_counting(&source, &cache_dir, &policy).await;
+        assert!(!is_stale(&cache_dir, &policy));
+        assert_eq!(source.fetches.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_cache_policy_decides_staleness() {
+        use crate::language::cache_policy::{CacheMaxAge, FixedClock};
+
+        let temp_dir = tempdir().unwrap();
+        let cache_dir = temp_dir.path().to_path_buf();
+        let source = CountingSource::default();
+        let day = std::time::Duration::from_secs(24 * 60 * 60);
+        let now = std::time::SystemTime::UNIX_EPOCH + 20_000 * day;
+        let week = CachePolicy::new(CacheMaxAge::After(7 * day)).with_clock(FixedClock(now));
+        let never = CachePolicy::new(CacheMaxAge::Never).with_clock(FixedClock(now));
+
+        load_counting(&source, &cache_dir, &never).await;
+        set_cache_modified(&cache_dir, now - 6 * day);
+        assert!(!is_stale(&cache_dir, &week));
+        set_cache_modified(&cache_dir, now - 8 * day);
+        assert!(is_stale(&cache_dir, &week));
+        assert!(!is_stale(&cache_dir, &never));
+
+        // A modification time in the future is not trusted, and is
+        // replaced once the list is revalidated
+        set_cache_modified(&cache_dir, now + day);
+        assert!(is_stale(&cache_dir, &week));
+        let future = std::time::SystemTime::now() + 10 * day;
+        set_cache_modified(&cache_dir, future);
+        load_counting(&source, &cache_dir, &CachePolicy::default()).await;
+        assert!(!is_stale(&cache_dir, &CachePolicy::default()));
+    }
+
+    #[tokio::test]
+    async fn test_list_size_slices_cached_list() {
+        let temp_dir = tempdir().unwrap();
+        let source = CountingSource::default();
+        let cancel = CancellationToken::new();
+        let nouns = |data: FrequencyData| data.get_top_words(&PartOfSpeech::Noun, 10).len();
+
+        let data = load_frequency_data_from(
+            &source,
+            "xx",
+            ListSize::Top50k,
+            temp_dir.path(),
+            &ServiceSettings::default(),
+            &cancel,
+        )
+        .await
+        .unwrap();
+        assert_eq!(nouns(data), 2);
+
+        // A smaller list comes from the cache
+        let data = load_frequency_data_from(
+            &source,
+            "xx",
+            ListSize::Top10k,
+            temp_dir.path(),
+            &ServiceSettings::default(),
+            &cancel,
+        )
+        .await
+        .unwrap();
+        assert_eq!(nouns(data), 1);
+        assert_eq!(source.fetches.load(Ordering::SeqCst), 1);
+
+        // A larger one is fetched once, then covers every smaller size
+        for (size, expected) in [(ListSize::Full, 3), (ListSize::Top50k, 2)] {
+            let data = load_frequency_data_from(
+                &source,
+                "xx",
+                size,
+                temp_dir.path(),
+                &ServiceSettings::default(),
+                &cancel,
+            )
+            .await
+            .unwrap();
+            assert_eq!(nouns(data), expected);
+        }
+        assert_eq!(source.fetches.load(Ordering::SeqCst), 2);
+    }
+
+    /// A source whose server can't be reached
+    struct OfflineSource;
+
+    #[async_trait]
+    impl FrequencySource for OfflineSource {
+        fn id(&self) -> &'static str {
+            "offline"
+        }
+
+        fn supports(&self, _language_code: &str) -> bool {
+            true
+        }
+
+        fn download_url(&self, _language_code: &str, _size: ListSize) -> Option<String> {
+            None
+        }
+
+        fn parse(&self, language_code: &str, _raw: &[u8]) -> Result<FrequencyData> {
+            Ok(FrequencyData::new(language_code.to_string()))
+        }
+
+        async fn fetch(
+            &self,
+            _language_code: &str,
+            _size: ListSize,
+            _downloader: &Downloader,
+            _validators: &FetchMetadata,
+        ) -> Result<FetchOutcome> {
+            Err(AnkiDeckBuilderError::FrequencyDataNotFound(
+                "no network".to_string(),
+            ))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_offline_falls_back_on_embedded_list() {
+        let temp_dir = tempdir().unwrap();
+        let cancel = CancellationToken::new();
+
+        let data = load_frequency_data_from(
+            &OfflineSource,
+            "hr",
+            ListSize::default(),
+            temp_dir.path(),
+            &ServiceSettings::default(),
+            &cancel,
+        )
+        .await
+        .unwrap();
+        assert_eq!(data.get_top_words(&PartOfSpeech::Verb, 1)[0].text, "biti");
+        assert!(data.download_error().unwrap().contains("no network"));
+        // The next run online still fetches the real list
+        assert!(!get_cache_file_path("offline", "hr", temp_dir.path()).exists());
+
+        let result = load_frequency_data_from(
+            &OfflineSource,
+            "ja",
+            ListSize::default(),
+            temp_dir.path(),
+            &ServiceSettings::default(),
+            &cancel,
+        )
+        .await;
+        assert!(result.is_err());
+    }
 }
@@ -0,0 +1,344 @@
+use crate::cancel::CancellationToken;
+use crate::config::ServiceSettings;
+use crate::error::{AnkiDeckBuilderError, Result};
+use crate::language::frequency::{FrequencyData, Word};
+use crate::language::frequency_cache::{read_frequency_cache, write_frequency_cache};
+use crate::language::frequency_loader::{cache_modified, load_frequency_data_from};
+use crate::language::frequency_source::{FrequencySource, FrequencySourceKind, ListSize};
+use crate::language::packs::LanguagePacks;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Damping constant of reciprocal rank fusion. Larger values flatten the
+/// difference between the top ranks of each list
+pub const RRF_K: f64 = 60.0;
+
+/// How much one frequency source counts when lists are merged
+/// (`opensubtitles=2` on the command line)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SourceWeight {
+    pub source: FrequencySourceKind,
+    pub weight: f64,
+}
+
+impl std::str::FromStr for SourceWeight {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let (name, weight) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected SOURCE=WEIGHT, got '{}'", s))?;
+        let source = name.trim().parse::<FrequencySourceKind>()?;
+        if source == FrequencySourceKind::Merged {
+            return Err("a merged list can't be merged again".to_string());
+        }
+        let weight: f64 = weight
+            .trim()
+            .parse()
+            .map_err(|_| format!("invalid weight '{}'", weight))?;
+        if !weight.is_finite() || weight < 0.0 {
+            return Err(format!("weight must be zero or more, got {}", weight));
+        }
+        Ok(Self { source, weight })
+    }
+}
+
+impl std::fmt::Display for SourceWeight {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}={}", self.source.name(), self.weight)
+    }
+}
+
+/// Weights of the sources in a merged list. Sources not mentioned count
+/// with weight 1, so an empty list weighs every corpus the same
+pub fn resolve_weights(weights: &[SourceWeight]) -> Vec<SourceWeight> {
+    FrequencySourceKind::CORPORA
+        .iter()
+        .map(|&source| {
+            let weight = weights
+                .iter()
+                .rev()
+                .find(|w| w.source == source)
+                .map_or(1.0, |w| w.weight);
+            SourceWeight { source, weight }
+        })
+        .filter(|w| w.weight > 0.0)
+        .collect()
+}
+
+/// Stand-in for the merged list where a single source is expected. It has
+/// nothing to download itself; see [`load_merged_frequency_data`]
+#[derive(Debug, Clone, Default)]
+pub struct MergedSource {
+    packs: LanguagePacks,
+}
+
+impl MergedSource {
+    pub fn new(packs: LanguagePacks) -> Self {
+        Self { packs }
+    }
+}
+
+#[async_trait]
+impl FrequencySource for MergedSource {
+    fn id(&self) -> &'static str {
+        "merged"
+    }
+
+    fn supports(&self, language_code: &str) -> bool {
+        FrequencySourceKind::CORPORA
+            .iter()
+            .any(|kind| kind.build(&self.packs).supports(language_code))
+    }
+
+    fn download_url(&self, _language_code: &str, _size: ListSize) -> Option<String> {
+        None
+    }
+
+    fn parse(&self, _language_code: &str, _raw: &[u8]) -> Result<FrequencyData> {
+        Err(AnkiDeckBuilderError::FrequencyDataNotFound(
+            "merged lists are built from other sources".to_string(),
+        ))
+    }
+}
+
+/// Combine frequency lists with weighted reciprocal rank fusion: a word
+/// scores `weight / (RRF_K + rank)` in every list it appears in, and the
+/// merged list is ordered by total score. A word ranked well by several
+/// corpora beats one that only a single corpus favors. Words are matched by
+/// spelling; the part of speech and count come from the most heavily
+/// weighted list
+pub fn fuse_frequency_lists(language_code: &str, lists: &[(&FrequencyData, f64)]) -> FrequencyData {
+    struct Fused {
+        word: Word,
+        score: f64,
+        pos_weight: f64,
+        best_rank: usize,
+    }
+
+    let mut fused: HashMap<String, Fused> = HashMap::new();
+    for (data, weight) in lists {
+        for word in data.words.values().flatten() {
+            let score = weight / (RRF_K + word.rank as f64);
+            let entry = fused.entry(word.text.clone()).or_insert_with(|| Fused {
+                word: word.clone(),
+                score: 0.0,
+                pos_weight: *weight,
+                best_rank: word.rank,
+            });
+            entry.score += score;
+            entry.best_rank = entry.best_rank.min(word.rank);
+            if *weight > entry.pos_weight {
+                entry.word.pos = word.pos.clone();
+                entry.word.frequency = word.frequency;
+                entry.pos_weight = *weight;
+            }
+        }
+    }
+
+    let mut ranked: Vec<Fused> = fused.into_values().collect();
+    ranked.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then(a.best_rank.cmp(&b.best_rank))
+            .then_with(|| a.word.text.cmp(&b.word.text))
+    });
+
+    let mut data = FrequencyData::new(language_code.to_string());
+    for (index, fused) in ranked.into_iter().enumerate() {
+        data.add_word(Word {
+            rank: index + 1,
+            ..fused.word
+        });
+    }
+    data
+}
+
+/// Load each weighted source's list (from its own cache when possible) and
+/// merge them. The merged list is cached too, per list size and weights, and
+/// rebuilt once any of its sources' caches is newer or it passes the max
+/// age. Sources without a list for the language are left out
+pub async fn load_merged_frequency_data(
+    weights: &[SourceWeight],
+    language_code: &str,
+    size: ListSize,
+    cache_dir: &Path,
+    services: &ServiceSettings,
+    cancel: &CancellationToken,
+) -> Result<FrequencyData> {
+    let packs = &services.language_packs;
+    let mut weights = resolve_weights(weights);
+    weights.retain(|w| w.source.build(packs).supports(language_code));
+    if weights.is_empty() {
+        // Nothing to merge; fall back to whatever a single source provides
+        return load_frequency_data_from(
+            FrequencySourceKind::Opensubtitles.build(packs).as_ref(),
+            language_code,
+            size,
+            cache_dir,
+            services,
+            cancel,
+        )
+        .await;
+    }
+
+    let cache_file = merged_cache_path(&weights, language_code, size, cache_dir);
+    if let Some(data) = load_fresh_merge(&cache_file, &weights, language_code, cache_dir, services)
+    {
+        tracing::info!(
+            "Loaded merged frequency data from cache for {}",
+            language_code
+        );
+        return Ok(data.with_max_rank(size.max_rank()));
+    }
+
+    let mut lists = Vec::new();
+    for weight in &weights {
+        let source = weight.source.build(packs);
+        match load_frequency_data_from(
+            source.as_ref(),
+            language_code,
+            size,
+            cache_dir,
+            services,
+            cancel,
+        )
+        .await
+        {
+            Ok(data) => lists.push((data, weight.weight)),
+            Err(e @ AnkiDeckBuilderError::Cancelled(_)) => return Err(e),
+            Err(e) => tracing::warn!("Leaving {} out of the merge: {}", source.id(), e),
+        }
+    }
+    if lists.is_empty() {
+        return Err(AnkiDeckBuilderError::FrequencyDataNotFound(format!(
+            "no source could be loaded to merge for {}",
+            language_code
+        )));
+    }
+
+    // Each list only takes part up to the size asked for
+    let sliced: Vec<(FrequencyData, f64)> = lists
+        .into_iter()
+        .map(|(data, weight)| (slice_to_size(data, size), weight))
+        .collect();
+    let refs: Vec<(&FrequencyData, f64)> = sliced.iter().map(|(data, w)| (data, *w)).collect();
+    let merged = fuse_frequency_lists(language_code, &refs);
+
+    write_frequency_cache(&cache_file, &merged)?;
+    tracing::info!(
+        "Saved merged frequency data to cache: {}",
+        cache_file.display()
+    );
+
+    Ok(merged.with_max_rank(size.max_rank()))
+}
+
+/// The cached merge, unless it is older than one of its sources or the max age
+fn load_fresh_merge(
+    cache_file: &Path,
+    weights: &[SourceWeight],
+    language_code: &str,
+    cache_dir: &Path,
+    services: &ServiceSettings,
+) -> Option<FrequencyData> {
+    let merged_at = std::fs::metadata(cache_file).ok()?.modified().ok()?;
+    if services.cache_policy().is_stale(merged_at) {
+        return None;
+    }
+    for weight in weights {
+        let source_at = cache_modified(weight.source.name(), language_code, cache_dir)?;
+        if source_at > merged_at {
+            return None;
+        }
+    }
+    read_frequency_cache(cache_file).ok().flatten()
+}
+
+fn slice_to_size(mut data: FrequencyData, size: ListSize) -> FrequencyData {
+    if let Some(max) = size.max_rank() {
+        for words in data.words.values_mut() {
+            words.retain(|word| word.rank <= max);
+        }
+    }
+    data
+}
+
+/// Cache file of a merge, named after the list size and a checksum of the
+/// weights so differently weighted merges don't overwrite each other
+fn merged_cache_path(
+    weights: &[SourceWeight],
+    language_code: &str,
+    size: ListSize,
+    cache_dir: &Path,
+) -> PathBuf {
+    let key: Vec<String> = weights.iter().map(SourceWeight::to_string).collect();
+    let checksum = crc32fast::hash(key.join(",").as_bytes());
+    cache_dir.join("frequency").join("merged").join(format!(
+        "{}_{}_{:08x}_frequency.json",
+        language_code,
+        size.name(),
+        checksum
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::language::frequency::PartOfSpeech;
+
+    fn list(words: &[(&str, PartOfSpeech)]) -> FrequencyData {
+        let mut data = FrequencyData::new("hr".to_string());
+        for (index, (text, pos)) in words.iter().enumerate() {
+            data.add_word(Word::new(text.to_string(), pos.clone(), index + 1));
+        }
+        data
+    }
+
+    fn order(data: &FrequencyData) -> Vec<String> {
+        let mut words: Vec<&Word> = data.words.values().flatten().collect();
+        words.sort_by_key(|word| word.rank);
+        words.iter().map(|word| word.text.clone()).collect()
+    }
+
+    #[test]
+    fn test_fuse_frequency_lists() {
+        use PartOfSpeech::*;
+        let subtitles = list(&[("hej", Interjection), ("dan", Noun), ("kuća", Noun)]);
+        let news = list(&[("vlada", Noun), ("dan", Noun), ("kuća", Adjective)]);
+
+        // Found in both lists, "dan" and "kuća" beat words only one list has
+        let merged = fuse_frequency_lists("hr", &[(&subtitles, 1.0), (&news, 1.0)]);
+        assert_eq!(order(&merged), ["dan", "kuća", "hej", "vlada"]);
+
+        // A heavier list decides ties and the part of speech
+        let merged = fuse_frequency_lists("hr", &[(&subtitles, 1.0), (&news, 2.0)]);
+        assert_eq!(order(&merged)[..3], ["dan", "kuća", "vlada"]);
+        assert_eq!(merged.get_top_words(&Adjective, 5)[0].text, "kuća");
+    }
+
+    #[test]
+    fn test_source_weights() {
+        let weight: SourceWeight = "leipzig=0.5".parse().unwrap();
+        assert_eq!(weight.source, FrequencySourceKind::Leipzig);
+        assert_eq!(weight.weight, 0.5);
+        let weight: SourceWeight = "OpenSubtitles=2".parse().unwrap();
+        assert_eq!(weight.source, FrequencySourceKind::Opensubtitles);
+        assert!("unknown=1".parse::<SourceWeight>().is_err());
+        assert!("leipzig".parse::<SourceWeight>().is_err());
+        assert!("merged=1".parse::<SourceWeight>().is_err());
+        assert!("leipzig=-1".parse::<SourceWeight>().is_err());
+
+        // Unlisted sources weigh 1; a weight of 0 drops a source
+        let resolved = resolve_weights(&["opensubtitles=0".parse().unwrap()]);
+        assert_eq!(
+            resolved,
+            vec![SourceWeight {
+                source: FrequencySourceKind::Leipzig,
+                weight: 1.0
+            }]
+        );
+    }
+}
@@ -0,0 +1,189 @@
+use crate::error::{AnkiDeckBuilderError, Result};
+use crate::language::downloader::Downloader;
+use crate::language::frequency::FrequencyData;
+use crate::language::frequency_fetcher::{FetchMetadata, FetchOutcome, OpenSubtitlesSource};
+use crate::language::frequency_merge::MergedSource;
+use crate::language::leipzig::LeipzigSource;
+use crate::language::packs::LanguagePacks;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+/// A provider of word frequency lists. A source that publishes each list as
+/// one file only says where to download it and how to parse it; the shared
+/// [`Downloader`] takes care of caching and resuming the download
+#[async_trait]
+pub trait FrequencySource: Send + Sync {
+    /// Short identifier, also used to keep each source's cache separate
+    fn id(&self) -> &'static str;
+
+    /// Whether this source can provide data for the language
+    fn supports(&self, language_code: &str) -> bool;
+
+    /// URL the list of this size for this language is downloaded from, if supported
+    fn download_url(&self, language_code: &str, size: ListSize) -> Option<String>;
+
+    /// Parse a downloaded file into a frequency list
+    fn parse(&self, language_code: &str, raw: &[u8]) -> Result<FrequencyData>;
+
+    /// Credit the list's makers ask for when it is shared, if any
+    fn attribution(&self) -> Option<Attribution> {
+        None
+    }
+
+    /// Fetch the frequency list unless it is unchanged since `validators`
+    async fn fetch(
+        &self,
+        language_code: &str,
+        size: ListSize,
+        downloader: &Downloader,
+        validators: &FetchMetadata,
+    ) -> Result<FetchOutcome> {
+        let url = self.download_url(language_code, size).ok_or_else(|| {
+            AnkiDeckBuilderError::FrequencyDataNotFound(language_code.to_string())
+        })?;
+        tracing::info!("Fetching {} frequency list from {}...", self.id(), url);
+
+        let Some(artifact) = downloader.download(&url, validators).await? else {
+            return Ok(FetchOutcome::NotModified);
+        };
+        let raw = std::fs::read(&artifact.path)?;
+        let data = self.parse(language_code, &raw)?;
+        Ok(FetchOutcome::Modified {
+            data,
+            metadata: artifact.metadata,
+        })
+    }
+}
+
+/// Who made a frequency list and the license decks built from it are
+/// shared under
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Attribution {
+    /// Name of the data set
+    pub name: String,
+    pub author: String,
+    /// SPDX identifier, e.g. "CC-BY-4.0"
+    pub license: String,
+    pub url: String,
+}
+
+impl Attribution {
+    pub fn new(name: &str, author: &str, license: &str, url: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            author: author.to_string(),
+            license: license.to_string(),
+            url: url.to_string(),
+        }
+    }
+
+    /// One line of credit, e.g. "FrequencyWords by Hermit Dave (CC-BY-SA-4.0)"
+    pub fn credit(&self) -> String {
+        format!("{} by {} ({})", self.name, self.author, self.license)
+    }
+
+    /// The credit with a link to the data set, for deck descriptions
+    pub fn credit_html(&self) -> String {
+        format!(
+            "{}, <a href=\"{}\">{}</a>",
+            self.credit(),
+            self.url,
+            self.url
+        )
+    }
+}
+
+/// Deck description crediting the frequency lists a deck's words came from
+pub fn deck_description(attributions: &[Attribution]) -> String {
+    let credits: Vec<String> = attributions.iter().map(Attribution::credit_html).collect();
+    format!(
+        "Built with anki-deck-builder. Word frequencies from:<br>{}",
+        credits.join("<br>")
+    )
+}
+
+/// How much of a language's frequency list to download and use. Larger lists
+/// cover every smaller one, so a cached list is sliced instead of refetched
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum ListSize {
+    /// The 10,000 most frequent words
+    #[serde(rename = "10k")]
+    Top10k,
+    /// The 50,000 most frequent words
+    #[default]
+    #[serde(rename = "50k")]
+    Top50k,
+    /// Every word the source has
+    #[serde(rename = "full")]
+    Full,
+}
+
+impl ListSize {
+    /// Name used on the command line (e.g. `--list-size 10k`)
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Top10k => "10k",
+            Self::Top50k => "50k",
+            Self::Full => "full",
+        }
+    }
+
+    /// Highest frequency rank included, None for the whole list
+    pub fn max_rank(&self) -> Option<usize> {
+        match self {
+            Self::Top10k => Some(10_000),
+            Self::Top50k => Some(50_000),
+            Self::Full => None,
+        }
+    }
+}
+
+/// Frequency sources selectable from the command line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FrequencySourceKind {
+    /// Hermit Dave's FrequencyWords (subtitle-based, colloquial)
+    Opensubtitles,
+    /// Leipzig Corpora Collection word lists (news/web text)
+    Leipzig,
+    /// Every other source combined by rank fusion (see `--merge-weights`)
+    Merged,
+}
+
+impl FrequencySourceKind {
+    /// Sources built from a single corpus, the ones a merged list combines
+    pub const CORPORA: [Self; 2] = [Self::Opensubtitles, Self::Leipzig];
+
+    /// Every source, in the order the command line lists them
+    pub const ALL: [Self; 3] = [Self::Opensubtitles, Self::Leipzig, Self::Merged];
+
+    /// Name used on the command line (e.g. `--frequency-source leipzig`)
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Opensubtitles => "opensubtitles",
+            Self::Leipzig => "leipzig",
+            Self::Merged => "merged",
+        }
+    }
+
+    /// The source, knowing the languages of `packs` too
+    pub fn build(&self, packs: &LanguagePacks) -> Box<dyn FrequencySource> {
+        match self {
+            Self::Opensubtitles => Box::new(OpenSubtitlesSource::new(packs.clone())),
+            Self::Leipzig => Box::new(LeipzigSource::new().with_language_packs(packs.clone())),
+            Self::Merged => Box::new(MergedSource::new(packs.clone())),
+        }
+    }
+}
+
+impl std::str::FromStr for FrequencySourceKind {
+    type Err = String;
+
+    /// A source by its command line name, in any case
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown frequency source '{}'", s))
+    }
+}
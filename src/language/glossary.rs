@@ -0,0 +1,371 @@
+use crate::error::{AnkiDeckBuilderError, Result};
+use crate::language::frequency::PartOfSpeech;
+use crate::language::text::normalize_text;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// A translation of your own, used instead of asking any provider
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GlossaryEntry {
+    pub word: String,
+    pub translation: String,
+    /// Only for the word as this part of speech; any when not given
+    pub pos: Option<PartOfSpeech>,
+}
+
+impl GlossaryEntry {
+    pub fn new(word: &str, translation: &str) -> Self {
+        Self {
+            word: normalize_text(word),
+            translation: normalize_text(translation),
+            pos: None,
+        }
+    }
+
+    pub fn with_pos(mut self, pos: Option<PartOfSpeech>) -> Self {
+        self.pos = pos;
+        self
+    }
+}
+
+/// An entry as written in a glossary file
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawEntry {
+    word: String,
+    translation: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pos: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TomlGlossary {
+    #[serde(default)]
+    entry: Vec<RawEntry>,
+}
+
+const CSV_HEADER: &str = "word,translation,pos";
+
+/// The glossary of one language pair: `<from>-<to>.toml` (or `.csv`) in
+/// the glossaries directory. TOML files list `[[entry]]` tables with `word`,
+/// `translation` and an optional `pos`; CSV files have the same columns
+#[derive(Debug, Clone)]
+pub struct GlossaryFile {
+    pub path: PathBuf,
+    pub entries: Vec<GlossaryEntry>,
+}
+
+impl GlossaryFile {
+    /// The pair's file, the TOML one unless only a CSV one exists. Missing
+    /// files have no entries
+    pub fn open(dir: &Path, from: &str, to: &str) -> Result<Self> {
+        let toml = dir.join(format!("{}-{}.toml", from, to));
+        let csv = toml.with_extension("csv");
+        let path = if !toml.exists() && csv.exists() {
+            csv
+        } else {
+            toml
+        };
+        Self::load(&path)
+    }
+
+    fn load(path: &Path) -> Result<Self> {
+        let entries = if path.exists() {
+            let content = std::fs::read_to_string(path)?;
+            let raw = if is_csv(path) {
+                parse_csv(&content)
+            } else {
+                toml::from_str::<TomlGlossary>(&content)
+                    .map(|glossary| glossary.entry)
+                    .map_err(|e| invalid(path, &e.to_string()))?
+            };
+            raw.into_iter()
+                .map(|entry| {
+                    let pos = match entry.pos.as_deref().map(str::trim) {
+                        None | Some("") => None,
+                        Some(name) => Some(PartOfSpeech::from_name(name).ok_or_else(|| {
+                            invalid(path, &format!("unknown part of speech '{}'", name))
+                        })?),
+                    };
+                    Ok(GlossaryEntry::new(&entry.word, &entry.translation).with_pos(pos))
+                })
+                .collect::<Result<Vec<_>>>()?
+        } else {
+            Vec::new()
+        };
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            entries,
+        })
+    }
+
+    /// Add an entry, replacing the one for the same word and part of speech.
+    /// Returns whether one was replaced
+    pub fn add(&mut self, entry: GlossaryEntry) -> bool {
+        match self
+            .entries
+            .iter_mut()
+            .find(|e| e.word.to_lowercase() == entry.word.to_lowercase() && e.pos == entry.pos)
+        {
+            Some(existing) => {
+                *existing = entry;
+                true
+            }
+            None => {
+                self.entries.push(entry);
+                false
+            }
+        }
+    }
+
+    /// Remove every entry for a word, returning how many there were
+    pub fn remove(&mut self, word: &str) -> usize {
+        let before = self.entries.len();
+        self.entries
+            .retain(|e| e.word.to_lowercase() != normalize_text(word).to_lowercase());
+        before - self.entries.len()
+    }
+
+    /// Write the entries back in the file's format. Comments in a TOML file
+    /// are not kept
+    pub fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let raw: Vec<RawEntry> = self
+            .entries
+            .iter()
+            .map(|entry| RawEntry {
+                word: entry.word.clone(),
+                translation: entry.translation.clone(),
+                pos: entry.pos.as_ref().map(|pos| pos.name().to_string()),
+            })
+            .collect();
+
+        let content = if is_csv(&self.path) {
+            let mut lines = vec![CSV_HEADER.to_string()];
+            lines.extend(raw.iter().map(|entry| {
+                [
+                    entry.word.as_str(),
+                    entry.translation.as_str(),
+                    entry.pos.as_deref().unwrap_or_default(),
+                ]
+                .map(csv_field)
+                .join(",")
+            }));
+            lines.join("\n") + "\n"
+        } else {
+            toml::to_string(&TomlGlossary { entry: raw })
+                .map_err(|e| invalid(&self.path, &e.to_string()))?
+        };
+        std::fs::write(&self.path, content)?;
+        Ok(())
+    }
+}
+
+/// Every glossary in a directory, keyed by language pair. Consulted before
+/// any translation provider
+#[derive(Debug, Clone, Default)]
+pub struct Glossary {
+    pairs: HashMap<String, Vec<GlossaryEntry>>,
+}
+
+impl Glossary {
+    /// Load the `<from>-<to>` glossaries of a directory. A missing directory
+    /// means no glossaries
+    pub fn load(dir: &Path) -> Result<Self> {
+        let mut pairs = HashMap::new();
+        let Ok(files) = std::fs::read_dir(dir) else {
+            return Ok(Self::default());
+        };
+        for file in files {
+            let path = file?.path();
+            let is_glossary = path
+                .extension()
+                .is_some_and(|ext| ext == "toml" || ext == "csv");
+            let pair = path.file_stem().and_then(|stem| stem.to_str());
+            if let (true, Some(pair)) = (is_glossary, pair) {
+                let entries: &mut Vec<GlossaryEntry> = pairs.entry(pair.to_string()).or_default();
+                entries.extend(GlossaryFile::load(&path)?.entries);
+            }
+        }
+        Ok(Self { pairs })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pairs.values().all(Vec::is_empty)
+    }
+
+    /// Your translation of a word from `from` into `to`. An entry for the
+    /// word's part of speech wins over one for any
+    pub fn lookup(
+        &self,
+        word: &str,
+        pos: Option<&PartOfSpeech>,
+        from: &str,
+        to: &str,
+    ) -> Option<&str> {
+        let entries = self.pairs.get(&format!("{}-{}", from, to))?;
+        let word = normalize_text(word).to_lowercase();
+        let matching: Vec<&GlossaryEntry> = entries
+            .iter()
+            .filter(|entry| entry.word.to_lowercase() == word)
+            .collect();
+
+        matching
+            .iter()
+            .find(|entry| pos.is_some() && entry.pos.as_ref() == pos)
+            .or_else(|| matching.iter().find(|entry| entry.pos.is_none()))
+            .or_else(|| matching.first().filter(|_| pos.is_none()))
+            .map(|entry| entry.translation.as_str())
+    }
+}
+
+fn is_csv(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "csv")
+}
+
+fn invalid(path: &Path, message: &str) -> AnkiDeckBuilderError {
+    AnkiDeckBuilderError::ConfigurationError(format!(
+        "invalid glossary {}: {}",
+        path.display(),
+        message
+    ))
+}
+
+/// Rows of a CSV glossary, skipping the header, blank lines and `#` comments
+fn parse_csv(content: &str) -> Vec<RawEntry> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter(|line| !line.eq_ignore_ascii_case(CSV_HEADER) && *line != "word,translation")
+        .filter_map(|line| {
+            let mut fields = split_csv_line(line).into_iter();
+            Some(RawEntry {
+                word: fields.next()?,
+                translation: fields.next()?,
+                pos: fields.next(),
+            })
+        })
+        .collect()
+}
+
+/// Fields of a CSV line, where quoted fields may hold commas and `""`
+/// stands for a quote
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field).trim().to_string()),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field.trim().to_string());
+    fields
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_lookup_prefers_the_part_of_speech() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join("hr-en.toml"),
+            r#"
+[[entry]]
+word = "dan"
+translation = "day"
+
+[[entry]]
+word = "jesti"
+translation = "to eat"
+pos = "verb"
+"#,
+        )
+        .unwrap();
+        std::fs::write(
+            temp_dir.path().join("hr-es.csv"),
+            "word,translation,pos\ndan,día\n\"kuća\",\"casa, hogar\",noun\n",
+        )
+        .unwrap();
+
+        let glossary = Glossary::load(temp_dir.path()).unwrap();
+        assert_eq!(
+            glossary.lookup("Dan", Some(&PartOfSpeech::Noun), "hr", "en"),
+            Some("day")
+        );
+        assert_eq!(
+            glossary.lookup("jesti", Some(&PartOfSpeech::Verb), "hr", "en"),
+            Some("to eat")
+        );
+        assert_eq!(
+            glossary.lookup("jesti", Some(&PartOfSpeech::Noun), "hr", "en"),
+            None
+        );
+        assert_eq!(
+            glossary.lookup("kuća", None, "hr", "es"),
+            Some("casa, hogar")
+        );
+        assert_eq!(glossary.lookup("dan", None, "hr", "de"), None);
+
+        std::fs::write(
+            temp_dir.path().join("hr-de.toml"),
+            "[[entry]]\nword = \"dan\"\ntranslation = \"Tag\"\npos = \"nown\"\n",
+        )
+        .unwrap();
+        assert!(Glossary::load(temp_dir.path()).is_err());
+    }
+
+    #[test]
+    fn test_add_and_remove_entries() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("hr-es.csv"), "dan,dia\n").unwrap();
+
+        let mut file = GlossaryFile::open(temp_dir.path(), "hr", "es").unwrap();
+        assert!(file.path.ends_with("hr-es.csv"));
+        assert!(file.add(GlossaryEntry::new("dan", "día")));
+        assert!(!file.add(GlossaryEntry::new("kuća", "casa, hogar")));
+        file.save().unwrap();
+
+        let mut file = GlossaryFile::open(temp_dir.path(), "hr", "es").unwrap();
+        assert_eq!(file.entries.len(), 2);
+        assert_eq!(file.entries[1].translation, "casa, hogar");
+        assert_eq!(file.remove("DAN"), 1);
+        file.save().unwrap();
+
+        let mut file = GlossaryFile::open(temp_dir.path(), "hr", "en").unwrap();
+        assert!(file.path.ends_with("hr-en.toml"));
+        file.add(GlossaryEntry::new("jesti", "to eat").with_pos(Some(PartOfSpeech::Verb)));
+        file.save().unwrap();
+
+        let glossary = Glossary::load(temp_dir.path()).unwrap();
+        assert_eq!(glossary.lookup("dan", None, "hr", "es"), None);
+        assert_eq!(
+            glossary.lookup("jesti", Some(&PartOfSpeech::Verb), "hr", "en"),
+            Some("to eat")
+        );
+    }
+}
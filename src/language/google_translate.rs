@@ -0,0 +1,278 @@
+use crate::cache_file;
+use crate::error::{AnkiDeckBuilderError, Result};
+use crate::http::HttpSettings;
+use crate::language::postprocess::PostProcessor;
+use crate::language::text::normalize_text;
+use crate::language::translator::Translator;
+use async_trait::async_trait;
+use reqwest::{Client, StatusCode};
+use serde_json::Value;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// The free endpoint Google's own web widgets use. It is unofficial: it has
+/// no key and no terms for this use, and may change or block clients
+pub const GOOGLE_TRANSLATE_URL: &str = "https://translate.googleapis.com/translate_a/single";
+
+/// Time between two requests, however many words are translated at once.
+/// The endpoint blocks clients that ask too fast for a while
+pub const MIN_REQUEST_INTERVAL: Duration = Duration::from_millis(1000);
+
+/// Google Translate through its unofficial free endpoint. Answers are cached
+/// apart from the other providers', so switching to it doesn't return their
+/// translations
+#[derive(Debug)]
+pub struct GoogleTranslateClient {
+    base_url: String,
+    client: Client,
+    cache_dir: Option<PathBuf>,
+    postprocessor: PostProcessor,
+    interval: Duration,
+    /// When the last request went out, held while waiting for the next
+    last_request: Mutex<Option<Instant>>,
+}
+
+impl GoogleTranslateClient {
+    pub fn new(cache_dir: Option<PathBuf>, http: &HttpSettings) -> Result<Self> {
+        let client = crate::http::client_builder(http)?
+            .timeout(Duration::from_secs(30))
+            .build()
+            .map_err(AnkiDeckBuilderError::HttpError)?;
+
+        Ok(Self {
+            base_url: GOOGLE_TRANSLATE_URL.to_string(),
+            client,
+            cache_dir,
+            postprocessor: PostProcessor::new(),
+            interval: MIN_REQUEST_INTERVAL,
+            last_request: Mutex::new(None),
+        })
+    }
+
+    /// Clean up provider output with these rules before it is cached
+    pub fn with_postprocessor(mut self, postprocessor: PostProcessor) -> Self {
+        self.postprocessor = postprocessor;
+        self
+    }
+
+    /// Ask another server, e.g. a test double, with no wait between requests
+    pub fn with_base_url(mut self, base_url: String) -> Self {
+        self.base_url = base_url;
+        self.interval = Duration::ZERO;
+        self
+    }
+
+    fn cache_file(&self, from: &str, to: &str) -> Option<PathBuf> {
+        Some(
+            self.cache_dir
+                .as_ref()?
+                .join("translations")
+                .join("google")
+                .join(format!("{}_{}.json", from, to)),
+        )
+    }
+
+    /// Try to load translation from cache
+    fn try_load_from_cache(&self, text: &str, from: &str, to: &str) -> Option<String> {
+        cache_file::read_translation(&self.cache_file(from, to)?, text)
+    }
+
+    /// Save translation to cache
+    fn save_to_cache(&self, text: &str, translation: &str, from: &str, to: &str) -> Result<()> {
+        let Some(cache_file) = self.cache_file(from, to) else {
+            return Ok(());
+        };
+
+        // Appended to the journal, which another run may be adding to as well
+        cache_file::append_entries(&cache_file, [(normalize_text(text), translation)])
+    }
+
+    /// Wait until the last request is far enough behind
+    async fn wait_turn(&self) {
+        let mut last_request = self.last_request.lock().await;
+        if let Some(last) = *last_request {
+            let due = last + self.interval;
+            if due > Instant::now() {
+                tokio::time::sleep_until(due.into()).await;
+            }
+        }
+        *last_request = Some(Instant::now());
+    }
+}
+
+/// Google's code for a language where it differs from ours
+fn google_code(code: &str) -> &str {
+    match code {
+        "zh" => "zh-CN",
+        "he" => "iw",
+        code => code,
+    }
+}
+
+/// The translated text of an answer: the first element lists the
+/// translated sentences, each starting with its text
+fn parse_translation(answer: &Value) -> Option<String> {
+    let sentences = answer.get(0)?.as_array()?;
+    let text: String = sentences
+        .iter()
+        .filter_map(|sentence| sentence.get(0)?.as_str())
+        .collect();
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+#[async_trait]
+impl Translator for GoogleTranslateClient {
+    fn name(&self) -> &'static str {
+        "google"
+    }
+
+    async fn translate(&self, text: &str, from: &str, to: &str) -> Result<String> {
+        // Try cache first
+        if let Some(cached) = self.try_load_from_cache(text, from, to) {
+            tracing::debug!("Cache hit for: {}", text);
+            return Ok(cached);
+        }
+
+        tracing::debug!("Translating '{}' from {} to {}", text, from, to);
+        self.wait_turn().await;
+
+        let query = [
+            ("client", "gtx"),
+            ("sl", google_code(from)),
+            ("tl", google_code(to)),
+            ("dt", "t"),
+            ("q", text),
+        ];
+        let logged_url = crate::logging::url_with_query(&self.base_url, &query);
+        crate::logging::http_request("GET", &logged_url, None);
+
+        let started = std::time::Instant::now();
+        let response = self
+            .client
+            .get(&self.base_url)
+            .query(&query)
+            .send()
+            .await
+            .map_err(|e| {
+                AnkiDeckBuilderError::TranslationError(format!("HTTP request failed: {}", e))
+            })?;
+        crate::logging::http_response(
+            "GET",
+            &logged_url,
+            response.status().as_u16(),
+            started,
+            None,
+        );
+
+        if response.status() == StatusCode::TOO_MANY_REQUESTS {
+            return Err(AnkiDeckBuilderError::TranslationError(
+                "Google Translate is refusing requests for now (429); wait a while or use another translator"
+                    .to_string(),
+            ));
+        }
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(AnkiDeckBuilderError::TranslationError(format!(
+                "Translation API returned {}: {}",
+                status, error_text
+            )));
+        }
+
+        let answer: Value = response.json().await.map_err(|e| {
+            AnkiDeckBuilderError::TranslationError(format!("Failed to parse response: {}", e))
+        })?;
+        let translated = parse_translation(&answer).ok_or_else(|| {
+            AnkiDeckBuilderError::TranslationError(
+                "Google Translate answered without a translation".to_string(),
+            )
+        })?;
+
+        let translation = self.postprocessor.apply(from, to, &translated);
+
+        // Save to cache
+        if let Err(e) = self.save_to_cache(text, &translation, from, to) {
+            tracing::warn!("Failed to cache translation: {}", e);
+        }
+
+        Ok(translation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::HttpSettings;
+    use mockito::Matcher;
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_parse_translation() {
+        let answer = json!([
+            [
+                ["La casa ", "Kuća ", null, null, 10],
+                ["es grande.", "je velika.", null, null, 10]
+            ],
+            null,
+            "hr"
+        ]);
+        assert_eq!(
+            parse_translation(&answer),
+            Some("La casa es grande.".to_string())
+        );
+        assert_eq!(parse_translation(&json!([null, null, "hr"])), None);
+    }
+
+    #[tokio::test]
+    async fn test_translate_and_cache() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("sl".into(), "hr".into()),
+                Matcher::UrlEncoded("tl".into(), "zh-CN".into()),
+                Matcher::UrlEncoded("q".into(), "kuća".into()),
+            ]))
+            .with_body(r#"[[["房子","kuća",null,null,10]],null,"hr"]"#)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let temp_dir = tempdir().unwrap();
+        let client = GoogleTranslateClient::new(
+            Some(temp_dir.path().to_path_buf()),
+            &HttpSettings::default(),
+        )
+        .unwrap()
+        .with_base_url(server.url());
+        assert_eq!(client.translate("kuća", "hr", "zh").await.unwrap(), "房子");
+        assert_eq!(client.translate("kuća", "hr", "zh").await.unwrap(), "房子");
+        mock.assert_async().await;
+        let cache = temp_dir.path().join("translations/google/hr_zh.json");
+        let cached: std::collections::HashMap<String, String> =
+            cache_file::read_map(&cache).unwrap();
+        assert_eq!(cached["kuća"], "房子");
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", Matcher::Any)
+            .with_status(429)
+            .create_async()
+            .await;
+
+        let client = GoogleTranslateClient::new(None, &HttpSettings::default())
+            .unwrap()
+            .with_base_url(server.url());
+        let err = client.translate("kuća", "hr", "es").await.unwrap_err();
+        assert!(err.to_string().contains("wait a while"));
+    }
+}
@@ -1,3 +1,11 @@
+use crate::config::ServiceSettings;
+use crate::error::Result;
+use crate::language::{
+    has_dictionary, has_embedded_data, has_phrases, has_starter_list, FrequencySourceKind,
+    GoogleTranslateClient, LanguagePacks, LibreTranslateClient, LlmClient, MyMemoryClient,
+    Translator,
+};
+use serde::Serialize;
 use std::collections::HashMap;
 
 /// Language information with ISO 639-1 code and full name
@@ -16,42 +24,59 @@ impl Language {
     }
 }
 
-/// Get a language by code or name (case-insensitive)
+/// Get a built-in language by code or name (case-insensitive). Languages
+/// from language packs are found with [`LanguagePacks::language`]
 pub fn get_language(input: &str) -> Option<Language> {
+    find_language(input, &LanguagePacks::default())
+}
+
+/// A language by code or name among the built-in ones and those of `packs`
+pub(crate) fn find_language(input: &str, packs: &LanguagePacks) -> Option<Language> {
     let input_lower = input.to_lowercase();
 
     // Try as code first
-    if let Some(name) = get_language_name(&input_lower) {
-        return Some(Language::new(&input_lower, name));
+    if let Some(name) = get_language_name(&input_lower, packs) {
+        return Some(Language::new(&input_lower, &name));
     }
 
     // Try as name
-    if let Some(code) = get_language_code(&input_lower) {
-        return Some(Language::new(code, input));
+    if let Some(code) = get_language_code(&input_lower, packs) {
+        return Some(Language::new(&code, input));
     }
 
     None
 }
 
 /// Get language name from code
-fn get_language_name(code: &str) -> Option<&'static str> {
-    let languages = get_supported_languages_map();
-    languages.get(code).copied()
+fn get_language_name(code: &str, packs: &LanguagePacks) -> Option<String> {
+    get_supported_languages_map(packs).remove(code)
 }
 
 /// Get language code from name (case-insensitive)
-fn get_language_code(name: &str) -> Option<&'static str> {
-    let languages = get_supported_languages_map();
+fn get_language_code(name: &str, packs: &LanguagePacks) -> Option<String> {
+    let languages = get_supported_languages_map(packs);
     let name_lower = name.to_lowercase();
 
     languages
-        .iter()
-        .find(|(_, &lang_name)| lang_name.to_lowercase() == name_lower)
-        .map(|(code, _)| *code)
+        .into_iter()
+        .find(|(_, lang_name)| lang_name.to_lowercase() == name_lower)
+        .map(|(code, _)| code)
+}
+
+/// Supported languages: the built-in ones and those from language packs
+fn get_supported_languages_map(packs: &LanguagePacks) -> HashMap<String, String> {
+    let mut map: HashMap<String, String> = builtin_languages()
+        .into_iter()
+        .map(|(code, name)| (code.to_string(), name.to_string()))
+        .collect();
+    for pack in packs.iter() {
+        map.insert(pack.code.clone(), pack.name.clone());
+    }
+    map
 }
 
 /// Supported languages for MVP (focused on Croatian and Spanish)
-fn get_supported_languages_map() -> HashMap<&'static str, &'static str> {
+fn builtin_languages() -> HashMap<&'static str, &'static str> {
     let mut map = HashMap::new();
 
     // MVP languages
@@ -83,10 +108,10 @@ fn get_supported_languages_map() -> HashMap<&'static str, &'static str> {
 }
 
 /// Get list of supported languages for selection
-pub fn get_supported_languages() -> Vec<Language> {
-    let mut languages: Vec<Language> = get_supported_languages_map()
+pub fn get_supported_languages(packs: &LanguagePacks) -> Vec<Language> {
+    let mut languages: Vec<Language> = get_supported_languages_map(packs)
         .iter()
-        .map(|(&code, &name)| Language::new(code, name))
+        .map(|(code, name)| Language::new(code, name))
         .collect();
 
     // Sort by name for better UX
@@ -96,22 +121,15 @@ pub fn get_supported_languages() -> Vec<Language> {
 }
 
 /// Get prioritized languages for selection (MVP languages first)
-pub fn get_prioritized_languages() -> Vec<Language> {
-    let mut languages = vec![
-        // MVP languages first
-        Language::new("hr", "Croatian"),
-        Language::new("es", "Spanish"),
-        // Then common languages
-        Language::new("en", "English"),
-        Language::new("fr", "French"),
-        Language::new("de", "German"),
-        Language::new("it", "Italian"),
-        Language::new("pt", "Portuguese"),
-    ];
+pub fn get_prioritized_languages(packs: &LanguagePacks) -> Vec<Language> {
+    const FIRST: [&str; 7] = ["hr", "es", "en", "fr", "de", "it", "pt"];
 
-    // Then rest alphabetically
-    let mut others = get_supported_languages();
-    others.retain(|lang| !["hr", "es", "en", "fr", "de", "it", "pt"].contains(&lang.code.as_str()));
+    // MVP languages first, then common languages, then the rest
+    // alphabetically, each under the name a language pack may have given it
+    let (mut languages, others): (Vec<Language>, Vec<Language>) = get_supported_languages(packs)
+        .into_iter()
+        .partition(|lang| FIRST.contains(&lang.code.as_str()));
+    languages.sort_by_key(|lang| FIRST.iter().position(|code| *code == lang.code));
     languages.extend(others);
 
     languages
@@ -122,6 +140,92 @@ pub fn is_supported(code_or_name: &str) -> bool {
     get_language(code_or_name).is_some()
 }
 
+/// What data and services a language has
+#[derive(Debug, Clone, Serialize)]
+pub struct LanguageSupport {
+    pub code: String,
+    pub name: String,
+    pub frequency_sources: Vec<&'static str>,
+    pub translators: Vec<&'static str>,
+    pub tts: Vec<&'static str>,
+    pub dictionary: Vec<&'static str>,
+    /// Whether `--include-phrases` has a phrase list to draw from
+    pub phrases: bool,
+    /// Whether `create --starter` has a list to build from
+    pub starter: bool,
+}
+
+/// Every translation provider there is, configured or not, for telling
+/// which languages each one offers. The LLM is left out until it has a key
+pub fn known_translators(services: &ServiceSettings) -> Result<Vec<Box<dyn Translator>>> {
+    let libre_url = std::env::var("LIBRETRANSLATE_URL")
+        .unwrap_or_else(|_| "https://libretranslate.com".to_string());
+    let http = &services.http;
+    let mut translators: Vec<Box<dyn Translator>> = vec![
+        Box::new(MyMemoryClient::new(None, http)?),
+        Box::new(LibreTranslateClient::new(libre_url, None, http)?),
+        Box::new(GoogleTranslateClient::new(None, http)?),
+    ];
+    if let Ok(llm) = LlmClient::new(services, None) {
+        translators.push(Box::new(llm));
+    }
+    Ok(translators)
+}
+
+/// Support for every language, MVP languages first. Providers that need a
+/// key are listed whether it is set or not
+pub fn language_support(services: &ServiceSettings) -> Result<Vec<LanguageSupport>> {
+    let packs = &services.language_packs;
+    let sources: Vec<_> = FrequencySourceKind::CORPORA
+        .iter()
+        .map(|kind| kind.build(packs))
+        .collect();
+    let mut translators = known_translators(services)?;
+    if !translators
+        .iter()
+        .any(|translator| translator.name() == "llm")
+    {
+        translators.push(Box::new(LlmClient::unconfigured(services)?));
+    }
+    let audio = crate::audio::known_audio_providers(services)?;
+
+    Ok(get_prioritized_languages(packs)
+        .into_iter()
+        .map(|language| {
+            let mut frequency_sources: Vec<&str> = sources
+                .iter()
+                .filter(|source| source.supports(&language.code))
+                .map(|source| source.id())
+                .collect();
+            if has_embedded_data(&language.code) {
+                frequency_sources.push("embedded");
+            }
+            LanguageSupport {
+                frequency_sources,
+                translators: translators
+                    .iter()
+                    .filter(|translator| translator.supports(&language.code))
+                    .map(|translator| translator.name())
+                    .collect(),
+                tts: audio
+                    .iter()
+                    .filter(|provider| provider.supports(&language.code))
+                    .map(|provider| provider.name())
+                    .collect(),
+                dictionary: if has_dictionary(&language.code, packs) {
+                    vec!["wiktionary"]
+                } else {
+                    Vec::new()
+                },
+                phrases: has_phrases(&language.code),
+                starter: has_starter_list(&language.code),
+                code: language.code,
+                name: language.name,
+            }
+        })
+        .collect())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -161,4 +265,33 @@ mod tests {
         assert!(is_supported("Spanish"));
         assert!(!is_supported("xyz"));
     }
+
+    #[test]
+    fn test_language_support_lists_every_provider() {
+        let support = language_support(&ServiceSettings::default()).unwrap();
+        let croatian = &support[0];
+        assert_eq!(croatian.code, "hr");
+        assert!(croatian.frequency_sources.contains(&"embedded"));
+        // Providers that need a key are listed without one
+        assert!(croatian.translators.contains(&"llm"));
+        assert_eq!(croatian.tts, ["Forvo", "Wikimedia Commons"]);
+        assert_eq!(croatian.dictionary, ["wiktionary"]);
+    }
+
+    #[test]
+    fn test_language_packs_join_registry() {
+        let packs = LanguagePacks::new(vec![crate::language::LanguagePack {
+            code: "sl".to_string(),
+            name: "Slovenian".to_string(),
+            ..Default::default()
+        }]);
+
+        let lang = packs.language("slovenian").unwrap();
+        assert_eq!(lang.code, "sl");
+        assert_eq!(packs.language("sl").unwrap().name, "Slovenian");
+        assert!(get_language("sl").is_none());
+        let prioritized = get_prioritized_languages(&packs);
+        assert_eq!(prioritized[0].code, "hr");
+        assert!(prioritized.iter().any(|lang| lang.code == "sl"));
+    }
 }
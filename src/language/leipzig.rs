@@ -0,0 +1,269 @@
+use crate::error::{AnkiDeckBuilderError, Result};
+use crate::language::frequency::FrequencyData;
+use crate::language::frequency_fetcher::frequency_data_from_ranked;
+use crate::language::frequency_source::{Attribution, FrequencySource, ListSize};
+use crate::language::packs::{LanguagePack, LanguagePacks};
+use async_trait::async_trait;
+use std::io::Read;
+
+const LEIPZIG_DOWNLOAD_URL: &str = "https://downloads.wortschatz-leipzig.de/corpora";
+
+/// Default corpus, `{lang}` is replaced with the ISO 639-3 code and `{size}`
+/// with the corpus size closest to the list size asked for.
+/// Override with the LEIPZIG_CORPUS environment variable
+const DEFAULT_CORPUS: &str = "{lang}_wikipedia_2021_{size}";
+
+/// Leipzig Corpora Collection word lists
+#[derive(Debug, Clone)]
+pub struct LeipzigSource {
+    base_url: String,
+    corpus: String,
+    /// For the parts of speech of the packs' languages
+    packs: LanguagePacks,
+}
+
+impl LeipzigSource {
+    pub fn new() -> Self {
+        Self {
+            base_url: LEIPZIG_DOWNLOAD_URL.to_string(),
+            corpus: std::env::var("LEIPZIG_CORPUS").unwrap_or_else(|_| DEFAULT_CORPUS.to_string()),
+            packs: LanguagePacks::default(),
+        }
+    }
+
+    /// Use a different download server and corpus name template
+    pub fn with_corpus(base_url: &str, corpus: &str) -> Self {
+        Self {
+            base_url: base_url.to_string(),
+            corpus: corpus.to_string(),
+            packs: LanguagePacks::default(),
+        }
+    }
+
+    /// Guess parts of speech with these packs' rules
+    pub fn with_language_packs(mut self, packs: LanguagePacks) -> Self {
+        self.packs = packs;
+        self
+    }
+
+    fn corpus_name(&self, language_code: &str, size: ListSize) -> Option<String> {
+        let corpus_size = match size {
+            ListSize::Top10k => "10K",
+            ListSize::Top50k => "30K",
+            ListSize::Full => "1M",
+        };
+        iso_639_3(language_code).map(|lang| {
+            self.corpus
+                .replace("{lang}", lang)
+                .replace("{size}", corpus_size)
+        })
+    }
+}
+
+impl Default for LeipzigSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl FrequencySource for LeipzigSource {
+    fn id(&self) -> &'static str {
+        "leipzig"
+    }
+
+    fn attribution(&self) -> Option<Attribution> {
+        Some(Attribution::new(
+            "Leipzig Corpora Collection",
+            "Leipzig University",
+            "CC-BY-4.0",
+            "https://wortschatz.uni-leipzig.de/en/download",
+        ))
+    }
+
+    fn supports(&self, language_code: &str) -> bool {
+        iso_639_3(language_code).is_some()
+    }
+
+    fn download_url(&self, language_code: &str, size: ListSize) -> Option<String> {
+        self.corpus_name(language_code, size)
+            .map(|corpus| format!("{}/{}.tar.gz", self.base_url, corpus))
+    }
+
+    fn parse(&self, language_code: &str, raw: &[u8]) -> Result<FrequencyData> {
+        let words = extract_words_file(raw)?;
+        Ok(parse_words_file(
+            &words,
+            language_code,
+            self.packs.get(language_code),
+        ))
+    }
+}
+
+/// Pull the `*-words.txt` file out of a Leipzig `.tar.gz` download
+fn extract_words_file(archive: &[u8]) -> Result<String> {
+    let mut tar = tar::Archive::new(flate2::read::GzDecoder::new(archive));
+
+    for entry in tar.entries()? {
+        let mut entry = entry?;
+        let is_words = entry.path()?.to_string_lossy().ends_with("-words.txt");
+
+        if is_words {
+            let mut content = String::new();
+            entry.read_to_string(&mut content)?;
+            return Ok(content);
+        }
+    }
+
+    Err(AnkiDeckBuilderError::FrequencyDataNotFound(
+        "Leipzig archive has no words file".to_string(),
+    ))
+}
+
+/// Parse a Leipzig words file (`id<TAB>word<TAB>frequency`), skipping
+/// punctuation, numbers, and markup tokens
+fn parse_words_file(
+    content: &str,
+    language_code: &str,
+    pack: Option<&LanguagePack>,
+) -> FrequencyData {
+    let mut entries: Vec<(&str, usize)> = content
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split('\t');
+            let _id = parts.next()?;
+            let word = parts.next()?;
+            let frequency = parts.next()?.trim().parse().ok()?;
+            Some((word, frequency))
+        })
+        .filter(|(word, _)| {
+            word.chars()
+                .all(|c| c.is_alphabetic() || c == '-' || c == '\'')
+        })
+        .collect();
+
+    // Stable sort keeps file order for equal counts
+    entries.sort_by_key(|(_, frequency)| std::cmp::Reverse(*frequency));
+
+    let ranked = entries
+        .into_iter()
+        .enumerate()
+        .map(|(i, (word, frequency))| (i + 1, word, frequency));
+
+    frequency_data_from_ranked(ranked, language_code, pack)
+}
+
+/// Leipzig corpora are named by ISO 639-3 code
+pub(crate) fn iso_639_3(language_code: &str) -> Option<&'static str> {
+    let code = match language_code {
+        "hr" => "hrv",
+        "es" => "spa",
+        "en" => "eng",
+        "fr" => "fra",
+        "de" => "deu",
+        "it" => "ita",
+        "pt" => "por",
+        "ru" => "rus",
+        "ja" => "jpn",
+        "ko" => "kor",
+        "zh" => "zho",
+        "ar" => "ara",
+        "hi" => "hin",
+        "nl" => "nld",
+        "pl" => "pol",
+        "sv" => "swe",
+        "no" => "nob",
+        "da" => "dan",
+        "fi" => "fin",
+        "el" => "ell",
+        "tr" => "tur",
+        _ => return None,
+    };
+    Some(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::HttpSettings;
+    use crate::language::downloader::Downloader;
+    use crate::language::frequency::PartOfSpeech;
+    use crate::language::frequency_fetcher::{FetchMetadata, FetchOutcome};
+
+    fn build_archive(name: &str, content: &str) -> Vec<u8> {
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+            Vec::new(),
+            flate2::Compression::default(),
+        ));
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, name, content.as_bytes())
+            .unwrap();
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    #[test]
+    fn test_parse_words_file() {
+        let content = "1\t.\t90000\n2\tdan\t500\n3\tbiti\t800\n4\t2020\t400\n";
+        let data = parse_words_file(content, "hr", None);
+
+        let verbs = data.get_top_words(&PartOfSpeech::Verb, 10);
+        assert_eq!(verbs[0].text, "biti");
+        assert_eq!(verbs[0].rank, 1);
+        assert_eq!(verbs[0].frequency, 800);
+
+        let nouns = data.get_top_words(&PartOfSpeech::Noun, 10);
+        assert_eq!(nouns.len(), 1);
+        assert_eq!(nouns[0].rank, 2);
+    }
+
+    #[test]
+    fn test_extract_words_file() {
+        let archive = build_archive("hrv_test/hrv_test-words.txt", "1\tdan\t5\n");
+        assert_eq!(extract_words_file(&archive).unwrap(), "1\tdan\t5\n");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_from_server() {
+        let mut server = mockito::Server::new_async().await;
+        let archive = build_archive("hrv_test-words.txt", "1\tdan\t5\n2\tkuća\t3\n");
+        let mock = server
+            .mock("GET", "/hrv_10K.tar.gz")
+            .with_body(archive)
+            .create_async()
+            .await;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let downloader = Downloader::new(temp_dir.path(), &HttpSettings::default()).unwrap();
+        let source = LeipzigSource::with_corpus(&server.url(), "{lang}_{size}");
+        let outcome = source
+            .fetch(
+                "hr",
+                ListSize::Top10k,
+                &downloader,
+                &FetchMetadata::default(),
+            )
+            .await
+            .unwrap();
+
+        mock.assert_async().await;
+        // The archive is kept as downloaded, apart from the parsed list
+        assert!(temp_dir.path().join("hrv_10K.tar.gz").exists());
+        match outcome {
+            FetchOutcome::Modified { data, .. } => {
+                assert_eq!(data.get_top_words(&PartOfSpeech::Noun, 10).len(), 2);
+            }
+            FetchOutcome::NotModified => panic!("expected new data"),
+        }
+    }
+
+    #[test]
+    fn test_supports() {
+        let source = LeipzigSource::new();
+        assert!(source.supports("hr"));
+        assert!(!source.supports("xx"));
+    }
+}
@@ -1,17 +1,31 @@
+use crate::cache_file;
 use crate::error::{AnkiDeckBuilderError, Result};
+use crate::http::HttpSettings;
+use crate::language::postprocess::PostProcessor;
+use crate::language::text::normalize_text;
 use crate::language::translator::Translator;
 use async_trait::async_trait;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::OnceLock;
 use std::time::Duration;
 
+/// Languages offered by the public LibreTranslate models
+const SUPPORTED_LANGUAGES: &[&str] = &[
+    "ar", "cs", "da", "de", "el", "en", "es", "fi", "fr", "hi", "hu", "id", "it", "ja", "ko", "nl",
+    "pl", "pt", "ru", "sk", "sv", "tr", "uk", "zh",
+];
+
 #[derive(Debug, Clone)]
 pub struct LibreTranslateClient {
     base_url: String,
     client: Client,
     cache_dir: Option<PathBuf>,
+    postprocessor: PostProcessor,
+    /// Language code → codes it translates into, as the server reported them
+    languages: OnceLock<HashMap<String, Vec<String>>>,
 }
 
 #[derive(Serialize)]
@@ -22,6 +36,15 @@ struct TranslateRequest {
     format: String,
 }
 
+/// A language in the answer to `GET /languages`. Servers before 1.3 don't
+/// list targets; every language can then be translated into every other
+#[derive(Deserialize)]
+struct ServerLanguage {
+    code: String,
+    #[serde(default)]
+    targets: Option<Vec<String>>,
+}
+
 #[derive(Deserialize)]
 struct TranslateResponse {
     #[serde(rename = "translatedText")]
@@ -29,8 +52,8 @@ struct TranslateResponse {
 }
 
 impl LibreTranslateClient {
-    pub fn new(base_url: String, cache_dir: Option<PathBuf>) -> Result<Self> {
-        let client = Client::builder()
+    pub fn new(base_url: String, cache_dir: Option<PathBuf>, http: &HttpSettings) -> Result<Self> {
+        let client = crate::http::client_builder(http)?
             .timeout(Duration::from_secs(30))
             .build()
             .map_err(AnkiDeckBuilderError::HttpError)?;
@@ -39,9 +62,17 @@ impl LibreTranslateClient {
             base_url,
             client,
             cache_dir,
+            postprocessor: PostProcessor::new(),
+            languages: OnceLock::new(),
         })
     }
 
+    /// Clean up provider output with these rules before it is cached
+    pub fn with_postprocessor(mut self, postprocessor: PostProcessor) -> Self {
+        self.postprocessor = postprocessor;
+        self
+    }
+
     /// Try to load translation from cache
     fn try_load_from_cache(&self, text: &str, from: &str, to: &str) -> Option<String> {
         let cache_dir = self.cache_dir.as_ref()?;
@@ -49,15 +80,7 @@ impl LibreTranslateClient {
             .join("translations")
             .join(format!("{}_{}.json", from, to));
 
-        if !cache_file.exists() {
-            return None;
-        }
-
-        // Load cache file
-        let content = std::fs::read_to_string(&cache_file).ok()?;
-        let cache: HashMap<String, String> = serde_json::from_str(&content).ok()?;
-
-        cache.get(text).cloned()
+        cache_file::read_translation(&cache_file, text)
     }
 
     /// Save translation to cache
@@ -67,32 +90,73 @@ impl LibreTranslateClient {
             None => return Ok(()), // No caching if no cache dir
         };
 
-        let translations_dir = cache_dir.join("translations");
-        std::fs::create_dir_all(&translations_dir)?;
+        let cache_file = cache_dir
+            .join("translations")
+            .join(format!("{}_{}.json", from, to));
 
-        let cache_file = translations_dir.join(format!("{}_{}.json", from, to));
+        // Appended to the journal, which another run may be adding to as well
+        cache_file::append_entries(&cache_file, [(normalize_text(text), translation)])
+    }
+}
 
-        // Load existing cache or create new
-        let mut cache: HashMap<String, String> = if cache_file.exists() {
-            let content = std::fs::read_to_string(&cache_file)?;
-            serde_json::from_str(&content).unwrap_or_default()
-        } else {
-            HashMap::new()
-        };
+#[async_trait]
+impl Translator for LibreTranslateClient {
+    fn name(&self) -> &'static str {
+        "libretranslate"
+    }
 
-        // Add new translation
-        cache.insert(text.to_string(), translation.to_string());
+    fn supports(&self, language_code: &str) -> bool {
+        match self.languages.get() {
+            Some(languages) => languages.contains_key(language_code),
+            None => SUPPORTED_LANGUAGES.contains(&language_code),
+        }
+    }
 
-        // Save back to file
-        let json = serde_json::to_string_pretty(&cache)?;
-        std::fs::write(&cache_file, json)?;
+    fn supports_pair(&self, from: &str, to: &str) -> bool {
+        match self.languages.get() {
+            Some(languages) => languages
+                .get(from)
+                .is_some_and(|targets| targets.iter().any(|target| target == to)),
+            None => self.supports(from) && self.supports(to),
+        }
+    }
 
+    async fn probe_languages(&self) -> Result<()> {
+        if self.languages.get().is_some() {
+            return Ok(());
+        }
+        let url = format!("{}/languages", self.base_url);
+        crate::logging::http_request("GET", &url, None);
+        let started = std::time::Instant::now();
+        let response = self.client.get(&url).send().await?;
+        crate::logging::http_response("GET", &url, response.status().as_u16(), started, None);
+        if !response.status().is_success() {
+            return Err(AnkiDeckBuilderError::TranslationError(format!(
+                "{} returned {}",
+                url,
+                response.status()
+            )));
+        }
+
+        let listed: Vec<ServerLanguage> = response.json().await?;
+        let codes: Vec<String> = listed.iter().map(|l| l.code.clone()).collect();
+        let languages = listed
+            .into_iter()
+            .map(|language| {
+                let targets = language.targets.unwrap_or_else(|| {
+                    codes
+                        .iter()
+                        .filter(|code| **code != language.code)
+                        .cloned()
+                        .collect()
+                });
+                (language.code, targets)
+            })
+            .collect();
+        let _ = self.languages.set(languages);
         Ok(())
     }
-}
 
-#[async_trait]
-impl Translator for LibreTranslateClient {
     async fn translate(&self, text: &str, from: &str, to: &str) -> Result<String> {
         // Try cache first
         if let Some(cached) = self.try_load_from_cache(text, from, to) {
@@ -110,7 +174,9 @@ impl Translator for LibreTranslateClient {
         };
 
         let url = format!("{}/translate", self.base_url);
+        crate::logging::http_request("POST", &url, serde_json::to_value(&request).ok().as_ref());
 
+        let started = std::time::Instant::now();
         let response = self
             .client
             .post(&url)
@@ -120,6 +186,7 @@ impl Translator for LibreTranslateClient {
             .map_err(|e| {
                 AnkiDeckBuilderError::TranslationError(format!("HTTP request failed: {}", e))
             })?;
+        crate::logging::http_response("POST", &url, response.status().as_u16(), started, None);
 
         if !response.status().is_success() {
             let status = response.status();
@@ -137,7 +204,9 @@ impl Translator for LibreTranslateClient {
             AnkiDeckBuilderError::TranslationError(format!("Failed to parse response: {}", e))
         })?;
 
-        let translation = translate_response.translated_text;
+        let translation = self
+            .postprocessor
+            .apply(from, to, &translate_response.translated_text);
 
         // Save to cache
         if let Err(e) = self.save_to_cache(text, &translation, from, to) {
@@ -168,13 +237,18 @@ impl Translator for LibreTranslateClient {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::http::HttpSettings;
     use tempfile::tempdir;
 
     #[tokio::test]
     #[ignore] // Requires internet and LibreTranslate service
     async fn test_translate() {
-        let client =
-            LibreTranslateClient::new("https://libretranslate.com".to_string(), None).unwrap();
+        let client = LibreTranslateClient::new(
+            "https://libretranslate.com".to_string(),
+            None,
+            &HttpSettings::default(),
+        )
+        .unwrap();
 
         let result = client.translate("hello", "en", "es").await;
         assert!(result.is_ok());
@@ -192,6 +266,7 @@ mod tests {
         let client = LibreTranslateClient::new(
             "https://libretranslate.com".to_string(),
             Some(cache_dir.clone()),
+            &HttpSettings::default(),
         )
         .unwrap();
 
@@ -201,5 +276,53 @@ mod tests {
         // Load from cache
         let cached = client.try_load_from_cache("test", "en", "es");
         assert_eq!(cached, Some("prueba".to_string()));
+
+        // A word cached in one encoding is found in the other
+        client
+            .save_to_cache("c\u{30c}aj", "té", "hr", "es")
+            .unwrap();
+        let cached = client.try_load_from_cache("čaj", "hr", "es");
+        assert_eq!(cached, Some("té".to_string()));
+    }
+
+    #[test]
+    fn test_supports() {
+        let client = LibreTranslateClient::new(
+            "https://libretranslate.com".to_string(),
+            None,
+            &HttpSettings::default(),
+        )
+        .unwrap();
+
+        assert!(client.supports("es"));
+        assert!(!client.supports("hr"));
+    }
+
+    #[tokio::test]
+    async fn test_probe_languages() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/languages")
+            .with_body(
+                r#"[
+                    {"code": "en", "name": "English", "targets": ["es", "hr"]},
+                    {"code": "hr", "name": "Croatian", "targets": ["en"]},
+                    {"code": "es", "name": "Spanish", "targets": ["en"]}
+                ]"#,
+            )
+            .expect(1)
+            .create_async()
+            .await;
+
+        let client =
+            LibreTranslateClient::new(server.url(), None, &HttpSettings::default()).unwrap();
+        assert!(!client.supports_pair("hr", "en"));
+        client.probe_languages().await.unwrap();
+        client.probe_languages().await.unwrap();
+        mock.assert_async().await;
+
+        assert!(client.supports_pair("hr", "en"));
+        assert!(!client.supports_pair("hr", "es"));
+        assert!(!client.supports("it"));
     }
 }
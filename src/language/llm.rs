@@ -0,0 +1,586 @@
+//! Translations and example sentences from a large language model behind an
+//! OpenAI-compatible chat completions API. Many words go into one request
+//! and the model answers with a JSON object, so a deck takes a few requests
+//! rather than one per word
+
+use crate::cache_file;
+use crate::config::ServiceSettings;
+use crate::error::{AnkiDeckBuilderError, Result};
+use crate::http::HttpSettings;
+use crate::language::frequency::Word;
+use crate::language::packs::LanguagePacks;
+use crate::language::postprocess::PostProcessor;
+use crate::language::text::normalize_text;
+use crate::language::translator::Translator;
+use async_trait::async_trait;
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+pub const DEFAULT_LLM_API_URL: &str = "https://api.openai.com/v1";
+
+pub const DEFAULT_LLM_MODEL: &str = "gpt-4o-mini";
+
+/// Words sent in one request. Larger batches make fewer requests but a
+/// longer wait for each, and a bad answer loses more
+pub const BATCH_SIZE: usize = 40;
+
+/// Rough token counts for estimates: the instructions of each request, a
+/// word as sent and as the key of the answer, and what is written for it
+const PROMPT_TOKENS: usize = 120;
+const WORD_TOKENS: usize = 8;
+const TRANSLATION_TOKENS: usize = 6;
+const EXAMPLE_TOKENS: usize = 45;
+
+/// Which model to ask and what it costs, the `llm` section of config.json
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LlmSettings {
+    /// Base URL of the API, OpenAI's or any server that speaks it
+    pub api_url: String,
+    /// Sent as a bearer token; OPENAI_API_KEY when not set here
+    #[serde(skip_serializing)]
+    pub api_key: Option<String>,
+    pub model: String,
+    /// US dollars per million tokens sent, for estimates
+    pub input_price: f64,
+    /// US dollars per million tokens written by the model, for estimates
+    pub output_price: f64,
+}
+
+impl Default for LlmSettings {
+    fn default() -> Self {
+        Self {
+            api_url: DEFAULT_LLM_API_URL.to_string(),
+            api_key: None,
+            model: DEFAULT_LLM_MODEL.to_string(),
+            input_price: 0.15,
+            output_price: 0.6,
+        }
+    }
+}
+
+impl LlmSettings {
+    /// The key from config.json, or else the one in OPENAI_API_KEY
+    pub fn key(&self) -> Option<String> {
+        self.api_key
+            .clone()
+            .or_else(|| std::env::var("OPENAI_API_KEY").ok())
+            .map(|key| key.trim().to_string())
+            .filter(|key| !key.is_empty())
+    }
+
+    /// What translating `words` words into `languages` languages costs at
+    /// most, with an example sentence for each when `examples` is set.
+    /// Cached words cost nothing, so a rerun costs less
+    pub fn estimate(&self, words: usize, languages: usize, examples: bool) -> CostEstimate {
+        let batches = words.div_ceil(BATCH_SIZE);
+        let mut estimate = CostEstimate {
+            requests: batches * languages,
+            input_tokens: (batches * PROMPT_TOKENS + words * WORD_TOKENS) * languages,
+            output_tokens: words * (WORD_TOKENS + TRANSLATION_TOKENS) * languages,
+            dollars: 0.0,
+        };
+        if examples {
+            estimate.requests += batches;
+            estimate.input_tokens += batches * PROMPT_TOKENS + words * WORD_TOKENS;
+            estimate.output_tokens += words * (WORD_TOKENS + EXAMPLE_TOKENS);
+        }
+        estimate.dollars = (estimate.input_tokens as f64 * self.input_price
+            + estimate.output_tokens as f64 * self.output_price)
+            / 1_000_000.0;
+        estimate
+    }
+}
+
+/// Requests, tokens and price of a run, worked out before anything is sent
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct CostEstimate {
+    pub requests: usize,
+    pub input_tokens: usize,
+    pub output_tokens: usize,
+    /// In US dollars, at the configured prices
+    pub dollars: f64,
+}
+
+/// A sentence using a word, with its translation into the base language
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Example {
+    pub sentence: String,
+    pub translation: String,
+}
+
+/// A model asked through an OpenAI-compatible chat completions API. Answers
+/// are cached apart from the other providers', so switching to it doesn't
+/// return their translations
+#[derive(Debug)]
+pub struct LlmClient {
+    settings: LlmSettings,
+    api_key: String,
+    client: Client,
+    cache_dir: Option<PathBuf>,
+    postprocessor: PostProcessor,
+    /// For the names of the packs' languages in the prompts
+    packs: LanguagePacks,
+}
+
+impl LlmClient {
+    /// A client for the configured model; an error when no key is set
+    pub fn new(services: &ServiceSettings, cache_dir: Option<PathBuf>) -> Result<Self> {
+        Ok(
+            Self::with_settings(services.llm.clone(), cache_dir, &services.http)?
+                .with_language_packs(services.language_packs.clone()),
+        )
+    }
+
+    pub fn with_settings(
+        settings: LlmSettings,
+        cache_dir: Option<PathBuf>,
+        http: &HttpSettings,
+    ) -> Result<Self> {
+        let api_key = settings.key().ok_or_else(|| {
+            AnkiDeckBuilderError::ConfigurationError(
+                "no API key for the LLM; set llm.api_key in config.json or OPENAI_API_KEY"
+                    .to_string(),
+            )
+        })?;
+        Self::build(settings, api_key, cache_dir, http)
+    }
+
+    /// A client for telling which languages the model can be asked in,
+    /// whether a key is set or not. Without one, asking it anything fails
+    pub fn unconfigured(services: &ServiceSettings) -> Result<Self> {
+        let api_key = services.llm.key().unwrap_or_default();
+        Ok(
+            Self::build(services.llm.clone(), api_key, None, &services.http)?
+                .with_language_packs(services.language_packs.clone()),
+        )
+    }
+
+    fn build(
+        settings: LlmSettings,
+        api_key: String,
+        cache_dir: Option<PathBuf>,
+        http: &HttpSettings,
+    ) -> Result<Self> {
+        let client = crate::http::client_builder(http)?
+            .timeout(Duration::from_secs(120))
+            .build()
+            .map_err(AnkiDeckBuilderError::HttpError)?;
+
+        Ok(Self {
+            settings,
+            api_key,
+            client,
+            cache_dir,
+            postprocessor: PostProcessor::new(),
+            packs: LanguagePacks::default(),
+        })
+    }
+
+    /// Clean up provider output with these rules before it is cached
+    pub fn with_postprocessor(mut self, postprocessor: PostProcessor) -> Self {
+        self.postprocessor = postprocessor;
+        self
+    }
+
+    /// Know the languages of these packs by name as well
+    pub fn with_language_packs(mut self, packs: LanguagePacks) -> Self {
+        self.packs = packs;
+        self
+    }
+
+    /// The English name the model is told, or the code for a language we
+    /// don't know
+    fn language_name(&self, code: &str) -> String {
+        self.packs
+            .language(code)
+            .map_or_else(|| code.to_string(), |language| language.name)
+    }
+
+    /// `kind` is "translations" or "examples"
+    fn cache_file(&self, kind: &str, from: &str, to: &str) -> Option<PathBuf> {
+        Some(
+            self.cache_dir
+                .as_ref()?
+                .join(kind)
+                .join("llm")
+                .join(format!("{}_{}.json", from, to)),
+        )
+    }
+
+    fn load_cache<T: serde::de::DeserializeOwned>(
+        &self,
+        kind: &str,
+        from: &str,
+        to: &str,
+    ) -> HashMap<String, T> {
+        self.cache_file(kind, from, to)
+            .and_then(|file| cache_file::read_map(&file))
+            .unwrap_or_default()
+    }
+
+    /// Add a batch's answers to the cache's journal, which another run may
+    /// be adding to as well
+    fn save_to_cache<T: Serialize>(
+        &self,
+        kind: &str,
+        from: &str,
+        to: &str,
+        found: &HashMap<String, T>,
+    ) {
+        let Some(file) = self.cache_file(kind, from, to) else {
+            return;
+        };
+        if let Err(e) = cache_file::append_entries(&file, found) {
+            tracing::warn!("Failed to cache {}: {}", kind, e);
+        }
+    }
+
+    /// Send the instructions and the request, and return the JSON object
+    /// the model answers with
+    async fn ask(&self, instructions: &str, request: &Value) -> Result<Value> {
+        let url = format!(
+            "{}/chat/completions",
+            self.settings.api_url.trim_end_matches('/')
+        );
+        let body = json!({
+            "model": self.settings.model,
+            "temperature": 0,
+            "response_format": { "type": "json_object" },
+            "messages": [
+                { "role": "system", "content": instructions },
+                { "role": "user", "content": request.to_string() },
+            ],
+        });
+        crate::logging::http_request("POST", &url, None);
+
+        let started = std::time::Instant::now();
+        let response = self
+            .client
+            .post(&url)
+            .bearer_auth(&self.api_key)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| {
+                AnkiDeckBuilderError::TranslationError(format!("HTTP request failed: {}", e))
+            })?;
+        crate::logging::http_response("POST", &url, response.status().as_u16(), started, None);
+
+        match response.status() {
+            StatusCode::UNAUTHORIZED => {
+                return Err(AnkiDeckBuilderError::ConfigurationError(
+                    "the LLM API refused the key (401); check llm.api_key in config.json or OPENAI_API_KEY"
+                        .to_string(),
+                ))
+            }
+            StatusCode::TOO_MANY_REQUESTS => {
+                return Err(AnkiDeckBuilderError::TranslationError(
+                    "the LLM API is refusing requests (429): the rate limit or quota is used up"
+                        .to_string(),
+                ))
+            }
+            status if !status.is_success() => {
+                let error_text = response
+                    .text()
+                    .await
+                    .unwrap_or_else(|_| "Unknown error".to_string());
+                return Err(AnkiDeckBuilderError::TranslationError(format!(
+                    "LLM API returned {}: {}",
+                    status, error_text
+                )));
+            }
+            _ => {}
+        }
+
+        let answer: Value = response.json().await.map_err(|e| {
+            AnkiDeckBuilderError::TranslationError(format!("Failed to parse response: {}", e))
+        })?;
+        if let Some(tokens) = answer["usage"]["total_tokens"].as_u64() {
+            tracing::debug!("{} used {} tokens", self.settings.model, tokens);
+        }
+        let content = answer["choices"][0]["message"]["content"]
+            .as_str()
+            .ok_or_else(|| {
+                AnkiDeckBuilderError::TranslationError("the model gave no answer".to_string())
+            })?;
+        serde_json::from_str(content).map_err(|e| {
+            AnkiDeckBuilderError::TranslationError(format!("the model's answer is not JSON: {}", e))
+        })
+    }
+
+    /// Translations of `texts` from `from` into `to`, by normalized text,
+    /// asking for the ones not cached `BATCH_SIZE` at a time. Texts the
+    /// model leaves out are missing
+    pub async fn translate_many(
+        &self,
+        texts: &[String],
+        from: &str,
+        to: &str,
+    ) -> Result<HashMap<String, String>> {
+        let cache: HashMap<String, String> = self.load_cache("translations", from, to);
+        let mut uncached: Vec<String> = Vec::new();
+        for text in texts.iter().map(|text| normalize_text(text)) {
+            if !cache.contains_key(&text) && !uncached.contains(&text) {
+                uncached.push(text);
+            }
+        }
+
+        let instructions = format!(
+            "You translate words and short phrases from {} into {} for vocabulary flashcards. \
+             Give each its most common meaning, the way a dictionary would, without explanations. \
+             Answer with a JSON object {{\"translations\": {{...}}}} mapping each given word to its translation.",
+            self.language_name(from),
+            self.language_name(to)
+        );
+        let mut found = HashMap::new();
+        for batch in uncached.chunks(BATCH_SIZE) {
+            tracing::debug!(
+                "Asking {} for {} translations from {} to {}",
+                self.settings.model,
+                batch.len(),
+                from,
+                to
+            );
+            let answer = self.ask(&instructions, &json!({ "words": batch })).await?;
+            let mut translated = HashMap::new();
+            for text in batch {
+                if let Some(translation) = answer["translations"][text.as_str()]
+                    .as_str()
+                    .map(str::trim)
+                    .filter(|t| !t.is_empty())
+                {
+                    let translation = self.postprocessor.apply(from, to, translation);
+                    translated.insert(text.clone(), translation);
+                }
+            }
+            self.save_to_cache("translations", from, to, &translated);
+            found.extend(translated);
+        }
+
+        Ok(texts
+            .iter()
+            .map(|text| normalize_text(text))
+            .filter_map(|text| {
+                let translation = cache.get(&text).or_else(|| found.get(&text))?.clone();
+                Some((text, translation))
+            })
+            .collect())
+    }
+
+    /// An example sentence in `language` for each of `words`, translated
+    /// into `base`, by word. Words not cached are asked for `BATCH_SIZE` at
+    /// a time; those the model leaves out are missing
+    pub async fn examples(
+        &self,
+        words: &[Word],
+        language: &str,
+        base: &str,
+    ) -> Result<HashMap<String, Example>> {
+        let cache: HashMap<String, Example> = self.load_cache("examples", language, base);
+        let mut uncached: Vec<&Word> = Vec::new();
+        for word in words {
+            if !cache.contains_key(&word.text) && !uncached.iter().any(|w| w.text == word.text) {
+                uncached.push(word);
+            }
+        }
+
+        let instructions = format!(
+            "You write example sentences for {} vocabulary flashcards. For each word, write one \
+             short, natural, everyday sentence using it as the given part of speech, and translate \
+             the sentence into {}. Answer with a JSON object {{\"examples\": {{...}}}} mapping each \
+             word to {{\"sentence\": ..., \"translation\": ...}}.",
+            self.language_name(language),
+            self.language_name(base)
+        );
+        let mut found = HashMap::new();
+        for batch in uncached.chunks(BATCH_SIZE) {
+            tracing::debug!(
+                "Asking {} for {} example sentences in {}",
+                self.settings.model,
+                batch.len(),
+                language
+            );
+            let request: Vec<Value> = batch
+                .iter()
+                .map(|word| json!({ "word": word.text, "pos": word.pos }))
+                .collect();
+            let answer = self
+                .ask(&instructions, &json!({ "words": request }))
+                .await?;
+            let mut written = HashMap::new();
+            for word in batch {
+                let example = serde_json::from_value::<Example>(
+                    answer["examples"][word.text.as_str()].clone(),
+                );
+                match example {
+                    Ok(example) if !example.sentence.trim().is_empty() => {
+                        written.insert(word.text.clone(), example);
+                    }
+                    _ => tracing::debug!("No example sentence for '{}'", word.text),
+                }
+            }
+            self.save_to_cache("examples", language, base, &written);
+            found.extend(written);
+        }
+
+        Ok(words
+            .iter()
+            .filter_map(|word| {
+                let example = cache.get(&word.text).or_else(|| found.get(&word.text))?;
+                Some((word.text.clone(), example.clone()))
+            })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl Translator for LlmClient {
+    fn name(&self) -> &'static str {
+        "llm"
+    }
+
+    async fn prepare(&self, texts: &[String], from: &str, to: &str) -> Result<()> {
+        self.translate_many(texts, from, to).await.map(|_| ())
+    }
+
+    async fn translate(&self, text: &str, from: &str, to: &str) -> Result<String> {
+        let mut found = self.translate_many(&[text.to_string()], from, to).await?;
+        found.remove(&normalize_text(text)).ok_or_else(|| {
+            AnkiDeckBuilderError::TranslationError(format!(
+                "the model gave no translation for '{}'",
+                text
+            ))
+        })
+    }
+
+    async fn translate_batch(&self, texts: &[String], from: &str, to: &str) -> Result<Vec<String>> {
+        let found = self.translate_many(texts, from, to).await?;
+        texts
+            .iter()
+            .map(|text| {
+                found.get(&normalize_text(text)).cloned().ok_or_else(|| {
+                    AnkiDeckBuilderError::TranslationError(format!(
+                        "the model gave no translation for '{}'",
+                        text
+                    ))
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::language::PartOfSpeech;
+    use mockito::Matcher;
+    use tempfile::tempdir;
+
+    fn settings(url: String) -> LlmSettings {
+        LlmSettings {
+            api_url: url,
+            api_key: Some("sk-test".to_string()),
+            ..LlmSettings::default()
+        }
+    }
+
+    fn completion(content: Value) -> String {
+        json!({
+            "choices": [{ "message": { "role": "assistant", "content": content.to_string() } }],
+            "usage": { "total_tokens": 42 }
+        })
+        .to_string()
+    }
+
+    #[tokio::test]
+    async fn test_translates_a_batch_in_one_request_and_caches() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/chat/completions")
+            .match_header("authorization", "Bearer sk-test")
+            .match_body(Matcher::PartialJson(
+                json!({ "response_format": { "type": "json_object" } }),
+            ))
+            .with_body(completion(json!({
+                "translations": { "kuća": "house", "dan": "day" }
+            })))
+            .expect(1)
+            .create_async()
+            .await;
+
+        let temp_dir = tempdir().unwrap();
+        let client = LlmClient::with_settings(
+            settings(server.url()),
+            Some(temp_dir.path().into()),
+            &HttpSettings::default(),
+        )
+        .unwrap();
+        let texts = vec!["kuća".to_string(), "dan".to_string(), "pas".to_string()];
+        let found = client.translate_many(&texts, "hr", "en").await.unwrap();
+        assert_eq!(found.len(), 2);
+        assert_eq!(found["dan"], "day");
+
+        // Served from the cache without another request
+        assert_eq!(client.translate("kuća", "hr", "en").await.unwrap(), "house");
+        mock.assert_async().await;
+        let cache = temp_dir.path().join("translations/llm/hr_en.json");
+        let cached: HashMap<String, String> = cache_file::read_map(&cache).unwrap();
+        assert_eq!(cached.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_examples() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/chat/completions")
+            .with_body(completion(json!({
+                "examples": {
+                    "kuća": { "sentence": "Kuća je velika.", "translation": "The house is big." }
+                }
+            })))
+            .create_async()
+            .await;
+
+        let client =
+            LlmClient::with_settings(settings(server.url()), None, &HttpSettings::default())
+                .unwrap();
+        let words = vec![
+            Word::new("kuća".to_string(), PartOfSpeech::Noun, 1),
+            Word::new("pas".to_string(), PartOfSpeech::Noun, 2),
+        ];
+        let examples = client.examples(&words, "hr", "en").await.unwrap();
+        assert_eq!(examples.len(), 1);
+        assert_eq!(examples["kuća"].translation, "The house is big.");
+    }
+
+    #[tokio::test]
+    async fn test_refused_key() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/chat/completions")
+            .with_status(401)
+            .create_async()
+            .await;
+
+        let client =
+            LlmClient::with_settings(settings(server.url()), None, &HttpSettings::default())
+                .unwrap();
+        let err = client.translate("kuća", "hr", "en").await.unwrap_err();
+        assert!(err.to_string().contains("llm.api_key"));
+    }
+
+    #[test]
+    fn test_estimate() {
+        let settings = LlmSettings::default();
+        let estimate = settings.estimate(100, 2, true);
+        // Three batches into each of two languages, and three for examples
+        assert_eq!(estimate.requests, 9);
+        assert!(estimate.dollars > 0.0 && estimate.dollars < 0.1);
+        assert_eq!(settings.estimate(0, 1, false).requests, 0);
+    }
+}
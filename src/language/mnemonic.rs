@@ -0,0 +1,253 @@
+//! Memory hooks: a word of the learner's own language that sounds like the
+//! word being learned, such as "dan" and "done". Words are compared by a
+//! rough phonetic key rather than by spelling, so "čaj" and "chai" match
+
+use crate::language::cognate::{fold, translation_alternatives};
+use crate::language::embedded::embedded_frequency_data;
+use crate::language::starter::starter_words;
+use crate::language::transliteration::transliterate;
+use crate::language::PartOfSpeech;
+use std::collections::HashSet;
+
+/// Sound-alikes at least this similar to the word make a hook
+pub const DEFAULT_MNEMONIC_SIMILARITY: f64 = 0.7;
+
+/// Spellings of one sound, replaced before letters are compared. The
+/// uppercase letters stand for sounds Latin letters have no single letter for
+const SOUNDS: &[(&str, &str)] = &[
+    ("tch", "C"),
+    ("ch", "C"),
+    ("č", "C"),
+    ("ć", "C"),
+    ("sch", "S"),
+    ("sh", "S"),
+    ("š", "S"),
+    ("dž", "J"),
+    ("đ", "J"),
+    ("nj", "N"),
+    ("ñ", "N"),
+    ("gn", "N"),
+    ("lj", "L"),
+    ("ž", "Z"),
+    ("ph", "f"),
+    ("th", "t"),
+    ("qu", "k"),
+    ("ck", "k"),
+];
+
+/// Spellings only some languages read differently
+fn language_sounds(language_code: &str) -> &'static [(&'static str, &'static str)] {
+    match language_code {
+        "es" => &[("ll", "i"), ("j", "h")],
+        "en" => &[("j", "J"), ("ee", "i"), ("oo", "u")],
+        "fr" => &[("j", "Z"), ("ou", "u")],
+        "de" => &[("ei", "ai"), ("w", "v"), ("j", "i")],
+        _ => &[],
+    }
+}
+
+/// How a word roughly sounds: letters for the same sound made equal,
+/// silent ones dropped, and doubled ones merged
+pub fn phonetic_key(language_code: &str, word: &str) -> String {
+    let mut word = transliterate(language_code, word)
+        .unwrap_or_else(|| word.to_string())
+        .to_lowercase();
+    for (spelling, sound) in language_sounds(language_code).iter().chain(SOUNDS) {
+        word = word.replace(spelling, sound);
+    }
+    if matches!(language_code, "en" | "fr") && word.len() > 3 {
+        if let Some(stem) = word.strip_suffix('e') {
+            word = stem.to_string();
+        }
+    }
+
+    let mut key = String::new();
+    for c in fold(&word).chars() {
+        let c = match c {
+            'c' | 'q' | 'k' => 'k',
+            'w' => 'v',
+            'y' | 'j' => 'i',
+            'z' => 's',
+            'x' => {
+                key.push('k');
+                's'
+            }
+            'h' => continue,
+            c if c.is_alphabetic() => c,
+            _ => continue,
+        };
+        if !key.ends_with(c) {
+            key.push(c);
+        }
+    }
+    key
+}
+
+/// Similarity from 0 to 1 of how two words sound, each read the way its
+/// language reads it
+pub fn phonetic_similarity(a_language: &str, a: &str, b_language: &str, b: &str) -> f64 {
+    key_similarity(&phonetic_key(a_language, a), &phonetic_key(b_language, b))
+}
+
+/// One minus the sound distance of two keys over the longer length
+fn key_similarity(a: &str, b: &str) -> f64 {
+    let longest = a.chars().count().max(b.chars().count());
+    if longest == 0 {
+        return 0.0;
+    }
+    1.0 - sound_distance(a, b) / longest as f64
+}
+
+/// Levenshtein distance where swapping one vowel for another costs half,
+/// as consonants carry most of how a word is heard
+fn sound_distance(a: &str, b: &str) -> f64 {
+    let is_vowel = |c: char| "aeiou".contains(c);
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<f64> = (0..=b.len()).map(|j| j as f64).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![(i + 1) as f64; b.len() + 1];
+        for (j, &cb) in b.iter().enumerate() {
+            let cost = match (ca == cb, is_vowel(ca) && is_vowel(cb)) {
+                (true, _) => 0.0,
+                (false, true) => 0.5,
+                (false, false) => 1.0,
+            };
+            current[j + 1] = (previous[j] + cost)
+                .min(previous[j + 1] + 1.0)
+                .min(current[j] + 1.0);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Looks for sound-alikes among the words of the learner's language
+#[derive(Debug, Clone)]
+pub struct MnemonicFinder {
+    /// Candidate words with their phonetic keys, most frequent first
+    words: Vec<(String, String)>,
+    threshold: f64,
+}
+
+impl MnemonicFinder {
+    /// Sound-alikes from the word lists built into the binary for the
+    /// language (see [`embedded_frequency_data`] and [`starter_words`])
+    pub fn new(language_code: &str) -> Self {
+        let mut words: Vec<_> = embedded_frequency_data(language_code)
+            .map(|data| data.words.into_values().flatten().collect())
+            .unwrap_or_default();
+        words.sort_by_key(|word| word.rank);
+        words.extend(
+            starter_words(language_code)
+                .into_iter()
+                .filter(|word| word.pos != PartOfSpeech::Phrase),
+        );
+        Self::with_words(language_code, words.into_iter().map(|word| word.text))
+    }
+
+    /// Sound-alikes from a list of words, most frequent first
+    pub fn with_words(language_code: &str, words: impl IntoIterator<Item = String>) -> Self {
+        let mut seen = HashSet::new();
+        let words = words
+            .into_iter()
+            .filter(|word| !word.contains(' ') && seen.insert(word.clone()))
+            .map(|word| {
+                let key = phonetic_key(language_code, &word);
+                (word, key)
+            })
+            .collect();
+        Self {
+            words,
+            threshold: DEFAULT_MNEMONIC_SIMILARITY,
+        }
+    }
+
+    /// Similarity a sound-alike needs to be used
+    pub fn with_threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// The word that sounds most like `word`, starting with the same sound.
+    /// The word itself and its translations don't count, as they teach
+    /// nothing new; of equally close words the more frequent one wins
+    pub fn sound_alike(&self, language_code: &str, word: &str, translation: &str) -> Option<&str> {
+        let key = phonetic_key(language_code, word);
+        let first = key.chars().next()?;
+        if key.chars().count() < 2 {
+            return None;
+        }
+        let excluded: HashSet<String> = translation_alternatives(translation)
+            .map(fold)
+            .chain([fold(word)])
+            .collect();
+
+        let mut best: Option<(&str, f64)> = None;
+        for (candidate, candidate_key) in &self.words {
+            if !candidate_key.starts_with(first) || excluded.contains(&fold(candidate)) {
+                continue;
+            }
+            let similarity = key_similarity(&key, candidate_key);
+            if best.is_none_or(|(_, best)| similarity > best) {
+                best = Some((candidate, similarity));
+            }
+        }
+        best.filter(|(_, similarity)| *similarity >= self.threshold)
+            .map(|(candidate, _)| candidate)
+    }
+
+    /// A hook for remembering `word`: its sound-alike, tied to its meaning
+    pub fn memory_hook(
+        &self,
+        language_code: &str,
+        word: &str,
+        translation: &str,
+    ) -> Option<String> {
+        let sound_alike = self.sound_alike(language_code, word, translation)?;
+        let meaning = translation_alternatives(translation).next()?;
+        Some(format!(
+            "“{}” sounds like “{}”: picture “{}” with “{}”",
+            word, sound_alike, sound_alike, meaning
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phonetic_key() {
+        assert_eq!(phonetic_key("hr", "šuma"), phonetic_key("en", "shuma"));
+        assert_eq!(phonetic_key("hr", "čaj"), phonetic_key("en", "chai"));
+        assert_eq!(phonetic_key("es", "llamar"), "iamar");
+        assert_eq!(phonetic_key("en", "done"), "don");
+        assert_eq!(phonetic_key("hr", "kava"), phonetic_key("es", "cava"));
+        assert!(phonetic_similarity("hr", "dan", "en", "done") > DEFAULT_MNEMONIC_SIMILARITY);
+        assert!(phonetic_similarity("hr", "iz", "en", "yes") < DEFAULT_MNEMONIC_SIMILARITY);
+    }
+
+    #[test]
+    fn test_sound_alike() {
+        let finder = MnemonicFinder::with_words(
+            "en",
+            ["the", "day", "done", "down", "money"].map(String::from),
+        );
+        assert_eq!(finder.sound_alike("hr", "dan", "day"), Some("done"));
+        // The translation itself is no hook
+        assert_eq!(finder.sound_alike("hr", "dej", "day"), None);
+        assert_eq!(finder.sound_alike("hr", "pas", "dog"), None);
+        assert_eq!(
+            finder.memory_hook("hr", "dan", "day, daytime").as_deref(),
+            Some("“dan” sounds like “done”: picture “done” with “day”")
+        );
+
+        assert!(!MnemonicFinder::new("es").is_empty());
+        assert!(MnemonicFinder::new("ja").is_empty());
+    }
+}
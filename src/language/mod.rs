@@ -1,14 +1,82 @@
+pub mod cache_policy;
+pub mod cognate;
+pub mod confusable;
+pub mod conjugation;
+pub mod dates;
+pub mod declension;
+pub mod detect;
+pub mod dictionary;
+pub mod difficulty;
+pub mod downloader;
+pub mod embedded;
+pub mod filter;
 pub mod frequency;
+pub mod frequency_cache;
 pub mod frequency_fetcher;
 pub mod frequency_loader;
+pub mod frequency_merge;
+pub mod frequency_source;
+pub mod glossary;
+pub mod google_translate;
 pub mod languages;
+pub mod leipzig;
 pub mod libre_translate;
+pub mod llm;
+pub mod mnemonic;
 pub mod mymemory_translate;
+pub mod numbers;
+pub mod packs;
+pub mod phrases;
+pub mod postprocess;
+pub mod quality;
+pub mod sections;
+pub mod starter;
+pub mod text;
 pub mod translator;
+pub mod transliteration;
+pub mod validation;
 
-pub use frequency::{FrequencyData, PartOfSpeech, Word};
-pub use frequency_loader::load_frequency_data;
-pub use languages::{get_language, get_prioritized_languages, is_supported, Language};
+pub use cache_policy::{CacheMaxAge, CachePolicy, Clock, FixedClock, SystemClock};
+pub use cognate::{cognate_similarity, is_cognate, DEFAULT_COGNATE_THRESHOLD};
+pub use confusable::{are_confusable, find_confusables};
+pub use conjugation::{conjugate, has_conjugation, Conjugation};
+pub use dates::{date_words, has_date_words};
+pub use declension::{has_declension, Declension, WiktionaryClient};
+pub use dictionary::{has_dictionary, DictionaryEntry, Sense};
+pub use difficulty::{estimate_difficulty, Difficulty};
+pub use downloader::Downloader;
+pub use embedded::{embedded_frequency_data, has_embedded_data};
+pub use filter::{load_known_words, WordFilter};
+pub use frequency::{FrequencyData, FrequencyThreshold, PartOfSpeech, Selection, Word};
+pub use frequency_fetcher::OpenSubtitlesSource;
+pub use frequency_loader::{
+    load_frequency_data, load_frequency_data_from, load_frequency_data_with,
+};
+pub use frequency_merge::{
+    fuse_frequency_lists, load_merged_frequency_data, resolve_weights, MergedSource, SourceWeight,
+};
+pub use frequency_source::{
+    deck_description, Attribution, FrequencySource, FrequencySourceKind, ListSize,
+};
+pub use glossary::{Glossary, GlossaryEntry, GlossaryFile};
+pub use google_translate::GoogleTranslateClient;
+pub use languages::{
+    get_language, get_prioritized_languages, is_supported, known_translators, language_support,
+    Language, LanguageSupport,
+};
+pub use leipzig::LeipzigSource;
 pub use libre_translate::LibreTranslateClient;
+pub use llm::{CostEstimate, Example, LlmClient, LlmSettings};
+pub use mnemonic::{phonetic_similarity, MnemonicFinder, DEFAULT_MNEMONIC_SIMILARITY};
 pub use mymemory_translate::MyMemoryClient;
-pub use translator::Translator;
+pub use numbers::{has_number_words, number_words};
+pub use packs::{load_language_packs, LanguagePack, LanguagePacks};
+pub use phrases::{common_phrases, has_phrases};
+pub use postprocess::{PostProcessor, Rule};
+pub use quality::FrequencyReport;
+pub use sections::{GeneratedWord, Section};
+pub use starter::{has_starter_list, starter_words};
+pub use text::{grapheme_len, min_word_length, normalize_text, truncate_graphemes};
+pub use translator::{Translator, TranslatorKind};
+pub use transliteration::{has_transliteration, transliterate};
+pub use validation::{CheckedTranslation, PairSupport, TranslationChain, TranslationIssue};
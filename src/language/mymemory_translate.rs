@@ -1,9 +1,12 @@
+use crate::cache_file;
 use crate::error::{AnkiDeckBuilderError, Result};
+use crate::http::HttpSettings;
+use crate::language::postprocess::PostProcessor;
+use crate::language::text::normalize_text;
 use crate::language::translator::Translator;
 use async_trait::async_trait;
 use reqwest::Client;
 use serde::Deserialize;
-use std::collections::HashMap;
 use std::path::PathBuf;
 use std::time::Duration;
 
@@ -11,6 +14,7 @@ use std::time::Duration;
 pub struct MyMemoryClient {
     client: Client,
     cache_dir: Option<PathBuf>,
+    postprocessor: PostProcessor,
 }
 
 #[derive(Deserialize)]
@@ -26,13 +30,23 @@ struct ResponseData {
 }
 
 impl MyMemoryClient {
-    pub fn new(cache_dir: Option<PathBuf>) -> Result<Self> {
-        let client = Client::builder()
+    pub fn new(cache_dir: Option<PathBuf>, http: &HttpSettings) -> Result<Self> {
+        let client = crate::http::client_builder(http)?
             .timeout(Duration::from_secs(30))
             .build()
             .map_err(AnkiDeckBuilderError::HttpError)?;
 
-        Ok(Self { client, cache_dir })
+        Ok(Self {
+            client,
+            cache_dir,
+            postprocessor: PostProcessor::new(),
+        })
+    }
+
+    /// Clean up provider output with these rules before it is cached
+    pub fn with_postprocessor(mut self, postprocessor: PostProcessor) -> Self {
+        self.postprocessor = postprocessor;
+        self
     }
 
     /// Try to load translation from cache
@@ -42,15 +56,7 @@ impl MyMemoryClient {
             .join("translations")
             .join(format!("{}_{}.json", from, to));
 
-        if !cache_file.exists() {
-            return None;
-        }
-
-        // Load cache file
-        let content = std::fs::read_to_string(&cache_file).ok()?;
-        let cache: HashMap<String, String> = serde_json::from_str(&content).ok()?;
-
-        cache.get(text).cloned()
+        cache_file::read_translation(&cache_file, text)
     }
 
     /// Save translation to cache
@@ -60,32 +66,21 @@ impl MyMemoryClient {
             None => return Ok(()), // No caching if no cache dir
         };
 
-        let translations_dir = cache_dir.join("translations");
-        std::fs::create_dir_all(&translations_dir)?;
-
-        let cache_file = translations_dir.join(format!("{}_{}.json", from, to));
-
-        // Load existing cache or create new
-        let mut cache: HashMap<String, String> = if cache_file.exists() {
-            let content = std::fs::read_to_string(&cache_file)?;
-            serde_json::from_str(&content).unwrap_or_default()
-        } else {
-            HashMap::new()
-        };
-
-        // Add new translation
-        cache.insert(text.to_string(), translation.to_string());
-
-        // Save back to file
-        let json = serde_json::to_string_pretty(&cache)?;
-        std::fs::write(&cache_file, json)?;
+        let cache_file = cache_dir
+            .join("translations")
+            .join(format!("{}_{}.json", from, to));
 
-        Ok(())
+        // Appended to the journal, which another run may be adding to as well
+        cache_file::append_entries(&cache_file, [(normalize_text(text), translation)])
     }
 }
 
 #[async_trait]
 impl Translator for MyMemoryClient {
+    fn name(&self) -> &'static str {
+        "mymemory"
+    }
+
     async fn translate(&self, text: &str, from: &str, to: &str) -> Result<String> {
         // Try cache first
         if let Some(cached) = self.try_load_from_cache(text, from, to) {
@@ -97,19 +92,31 @@ impl Translator for MyMemoryClient {
 
         // MyMemory API uses language pairs like "en|es" for English to Spanish
         let lang_pair = format!("{}|{}", from, to);
-        
+
         // MyMemory API endpoint
         let url = "https://api.mymemory.translated.net/get";
-        
+
+        let query = [("q", text), ("langpair", &lang_pair)];
+        let logged_url = crate::logging::url_with_query(url, &query);
+        crate::logging::http_request("GET", &logged_url, None);
+
+        let started = std::time::Instant::now();
         let response = self
             .client
             .get(url)
-            .query(&[("q", text), ("langpair", &lang_pair)])
+            .query(&query)
             .send()
             .await
             .map_err(|e| {
                 AnkiDeckBuilderError::TranslationError(format!("HTTP request failed: {}", e))
             })?;
+        crate::logging::http_response(
+            "GET",
+            &logged_url,
+            response.status().as_u16(),
+            started,
+            None,
+        );
 
         if !response.status().is_success() {
             let status = response.status();
@@ -127,7 +134,9 @@ impl Translator for MyMemoryClient {
             AnkiDeckBuilderError::TranslationError(format!("Failed to parse response: {}", e))
         })?;
 
-        let translation = mymemory_response.response_data.translated_text;
+        let translation =
+            self.postprocessor
+                .apply(from, to, &mymemory_response.response_data.translated_text);
 
         // Save to cache
         if let Err(e) = self.save_to_cache(text, &translation, from, to) {
@@ -159,12 +168,13 @@ impl Translator for MyMemoryClient {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::http::HttpSettings;
     use tempfile::tempdir;
 
     #[tokio::test]
     #[ignore] // Requires internet connection
     async fn test_translate() {
-        let client = MyMemoryClient::new(None).unwrap();
+        let client = MyMemoryClient::new(None, &HttpSettings::default()).unwrap();
 
         let result = client.translate("hello", "en", "es").await;
         assert!(result.is_ok());
@@ -179,7 +189,8 @@ mod tests {
         let temp_dir = tempdir().unwrap();
         let cache_dir = temp_dir.path().to_path_buf();
 
-        let client = MyMemoryClient::new(Some(cache_dir.clone())).unwrap();
+        let client =
+            MyMemoryClient::new(Some(cache_dir.clone()), &HttpSettings::default()).unwrap();
 
         // Save to cache
         client.save_to_cache("test", "prueba", "en", "es").unwrap();
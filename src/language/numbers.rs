@@ -0,0 +1,432 @@
+//! Number words built by rule: the cardinals from one to a hundred and the
+//! first ten ordinals, with their English glosses
+
+use crate::language::frequency::{PartOfSpeech, Word};
+use crate::language::sections::GeneratedWord;
+
+/// Languages numbers are generated for
+pub const NUMBER_LANGUAGES: &[&str] = &["hr", "es", "fr", "de", "it", "pt", "en"];
+
+/// Whether number words can be generated for the language
+pub fn has_number_words(language_code: &str) -> bool {
+    NUMBER_LANGUAGES.contains(&language_code)
+}
+
+/// One to a hundred, then first to tenth. Empty for languages without rules
+pub fn number_words(language_code: &str) -> Vec<GeneratedWord> {
+    if !has_number_words(language_code) {
+        return Vec::new();
+    }
+    let cardinals = (1..=100).map(|n| {
+        (
+            cardinal(language_code, n),
+            english_cardinal(n),
+            PartOfSpeech::Noun,
+        )
+    });
+    let ordinals = ordinals(language_code)
+        .iter()
+        .zip(ordinals("en"))
+        .map(|(word, gloss)| (word.to_string(), gloss.to_string(), PartOfSpeech::Adjective));
+
+    cardinals
+        .chain(ordinals)
+        .enumerate()
+        .map(|(i, (text, gloss, pos))| GeneratedWord {
+            word: Word::new(text, pos, i + 1),
+            gloss,
+        })
+        .collect()
+}
+
+/// The number `n`, from 1 to 100, spelled out
+pub fn cardinal(language_code: &str, n: usize) -> String {
+    match language_code {
+        "hr" => croatian(n),
+        "es" => spanish(n),
+        "fr" => french(n),
+        "de" => german(n),
+        "it" => italian(n),
+        "pt" => portuguese(n),
+        _ => english_cardinal(n),
+    }
+}
+
+fn split(n: usize) -> (usize, usize) {
+    (n / 10, n % 10)
+}
+
+fn english_cardinal(n: usize) -> String {
+    const SMALL: [&str; 20] = [
+        "zero",
+        "one",
+        "two",
+        "three",
+        "four",
+        "five",
+        "six",
+        "seven",
+        "eight",
+        "nine",
+        "ten",
+        "eleven",
+        "twelve",
+        "thirteen",
+        "fourteen",
+        "fifteen",
+        "sixteen",
+        "seventeen",
+        "eighteen",
+        "nineteen",
+    ];
+    const TENS: [&str; 10] = [
+        "", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+    ];
+    match split(n) {
+        _ if n < 20 => SMALL[n].to_string(),
+        (10, 0) => "one hundred".to_string(),
+        (tens, 0) => TENS[tens].to_string(),
+        (tens, unit) => format!("{}-{}", TENS[tens], SMALL[unit]),
+    }
+}
+
+fn croatian(n: usize) -> String {
+    const SMALL: [&str; 20] = [
+        "nula",
+        "jedan",
+        "dva",
+        "tri",
+        "četiri",
+        "pet",
+        "šest",
+        "sedam",
+        "osam",
+        "devet",
+        "deset",
+        "jedanaest",
+        "dvanaest",
+        "trinaest",
+        "četrnaest",
+        "petnaest",
+        "šesnaest",
+        "sedamnaest",
+        "osamnaest",
+        "devetnaest",
+    ];
+    const TENS: [&str; 10] = [
+        "",
+        "",
+        "dvadeset",
+        "trideset",
+        "četrdeset",
+        "pedeset",
+        "šezdeset",
+        "sedamdeset",
+        "osamdeset",
+        "devedeset",
+    ];
+    match split(n) {
+        _ if n < 20 => SMALL[n].to_string(),
+        (10, 0) => "sto".to_string(),
+        (tens, 0) => TENS[tens].to_string(),
+        (tens, unit) => format!("{} {}", TENS[tens], SMALL[unit]),
+    }
+}
+
+fn spanish(n: usize) -> String {
+    const SMALL: [&str; 16] = [
+        "cero", "uno", "dos", "tres", "cuatro", "cinco", "seis", "siete", "ocho", "nueve", "diez",
+        "once", "doce", "trece", "catorce", "quince",
+    ];
+    const TENS: [&str; 10] = [
+        "",
+        "",
+        "veinte",
+        "treinta",
+        "cuarenta",
+        "cincuenta",
+        "sesenta",
+        "setenta",
+        "ochenta",
+        "noventa",
+    ];
+    // The teens and twenties fuse into one word, which takes an accent when
+    // it ends in a stressed syllable
+    let fused = |prefix: &str, unit: usize| {
+        let unit = match unit {
+            2 => "dós",
+            3 => "trés",
+            6 => "séis",
+            _ => SMALL[unit],
+        };
+        format!("{}{}", prefix, unit)
+    };
+    match split(n) {
+        _ if n < 16 => SMALL[n].to_string(),
+        (1, unit) => fused("dieci", unit),
+        (2, 0) => TENS[2].to_string(),
+        (2, unit) => fused("veinti", unit),
+        (10, 0) => "cien".to_string(),
+        (tens, 0) => TENS[tens].to_string(),
+        (tens, unit) => format!("{} y {}", TENS[tens], SMALL[unit]),
+    }
+}
+
+fn french(n: usize) -> String {
+    const SMALL: [&str; 17] = [
+        "zéro", "un", "deux", "trois", "quatre", "cinq", "six", "sept", "huit", "neuf", "dix",
+        "onze", "douze", "treize", "quatorze", "quinze", "seize",
+    ];
+    const TENS: [&str; 7] = [
+        "",
+        "",
+        "vingt",
+        "trente",
+        "quarante",
+        "cinquante",
+        "soixante",
+    ];
+    match split(n) {
+        _ if n <= 16 => SMALL[n].to_string(),
+        (1, unit) => format!("dix-{}", SMALL[unit]),
+        (10, 0) => "cent".to_string(),
+        // Seventy and ninety count on from sixty and eighty
+        (7, 1) => "soixante et onze".to_string(),
+        (7, _) => format!("soixante-{}", french(n - 60)),
+        (8, 0) => "quatre-vingts".to_string(),
+        (8, _) | (9, _) => format!("quatre-vingt-{}", french(n - 80)),
+        (tens, 0) => TENS[tens].to_string(),
+        (tens, 1) => format!("{} et un", TENS[tens]),
+        (tens, unit) => format!("{}-{}", TENS[tens], SMALL[unit]),
+    }
+}
+
+fn german(n: usize) -> String {
+    const SMALL: [&str; 20] = [
+        "null",
+        "eins",
+        "zwei",
+        "drei",
+        "vier",
+        "fünf",
+        "sechs",
+        "sieben",
+        "acht",
+        "neun",
+        "zehn",
+        "elf",
+        "zwölf",
+        "dreizehn",
+        "vierzehn",
+        "fünfzehn",
+        "sechzehn",
+        "siebzehn",
+        "achtzehn",
+        "neunzehn",
+    ];
+    const TENS: [&str; 10] = [
+        "", "", "zwanzig", "dreißig", "vierzig", "fünfzig", "sechzig", "siebzig", "achtzig",
+        "neunzig",
+    ];
+    match split(n) {
+        _ if n < 20 => SMALL[n].to_string(),
+        (10, 0) => "hundert".to_string(),
+        (tens, 0) => TENS[tens].to_string(),
+        // The unit comes first: einundzwanzig, "one and twenty"
+        (tens, 1) => format!("einund{}", TENS[tens]),
+        (tens, unit) => format!("{}und{}", SMALL[unit], TENS[tens]),
+    }
+}
+
+fn italian(n: usize) -> String {
+    const SMALL: [&str; 20] = [
+        "zero",
+        "uno",
+        "due",
+        "tre",
+        "quattro",
+        "cinque",
+        "sei",
+        "sette",
+        "otto",
+        "nove",
+        "dieci",
+        "undici",
+        "dodici",
+        "tredici",
+        "quattordici",
+        "quindici",
+        "sedici",
+        "diciassette",
+        "diciotto",
+        "diciannove",
+    ];
+    const TENS: [&str; 10] = [
+        "",
+        "",
+        "venti",
+        "trenta",
+        "quaranta",
+        "cinquanta",
+        "sessanta",
+        "settanta",
+        "ottanta",
+        "novanta",
+    ];
+    match split(n) {
+        _ if n < 20 => SMALL[n].to_string(),
+        (10, 0) => "cento".to_string(),
+        (tens, 0) => TENS[tens].to_string(),
+        // The tens drop their vowel before uno and otto, and tre is stressed
+        (tens, unit @ (1 | 8)) => format!("{}{}", &TENS[tens][..TENS[tens].len() - 1], SMALL[unit]),
+        (tens, 3) => format!("{}tré", TENS[tens]),
+        (tens, unit) => format!("{}{}", TENS[tens], SMALL[unit]),
+    }
+}
+
+fn portuguese(n: usize) -> String {
+    const SMALL: [&str; 20] = [
+        "zero",
+        "um",
+        "dois",
+        "três",
+        "quatro",
+        "cinco",
+        "seis",
+        "sete",
+        "oito",
+        "nove",
+        "dez",
+        "onze",
+        "doze",
+        "treze",
+        "catorze",
+        "quinze",
+        "dezesseis",
+        "dezessete",
+        "dezoito",
+        "dezenove",
+    ];
+    const TENS: [&str; 10] = [
+        "",
+        "",
+        "vinte",
+        "trinta",
+        "quarenta",
+        "cinquenta",
+        "sessenta",
+        "setenta",
+        "oitenta",
+        "noventa",
+    ];
+    match split(n) {
+        _ if n < 20 => SMALL[n].to_string(),
+        (10, 0) => "cem".to_string(),
+        (tens, 0) => TENS[tens].to_string(),
+        (tens, unit) => format!("{} e {}", TENS[tens], SMALL[unit]),
+    }
+}
+
+fn ordinals(language_code: &str) -> &'static [&'static str; 10] {
+    match language_code {
+        "hr" => &[
+            "prvi", "drugi", "treći", "četvrti", "peti", "šesti", "sedmi", "osmi", "deveti",
+            "deseti",
+        ],
+        "es" => &[
+            "primero", "segundo", "tercero", "cuarto", "quinto", "sexto", "séptimo", "octavo",
+            "noveno", "décimo",
+        ],
+        "fr" => &[
+            "premier",
+            "deuxième",
+            "troisième",
+            "quatrième",
+            "cinquième",
+            "sixième",
+            "septième",
+            "huitième",
+            "neuvième",
+            "dixième",
+        ],
+        "de" => &[
+            "erste", "zweite", "dritte", "vierte", "fünfte", "sechste", "siebte", "achte",
+            "neunte", "zehnte",
+        ],
+        "it" => &[
+            "primo", "secondo", "terzo", "quarto", "quinto", "sesto", "settimo", "ottavo", "nono",
+            "decimo",
+        ],
+        "pt" => &[
+            "primeiro", "segundo", "terceiro", "quarto", "quinto", "sexto", "sétimo", "oitavo",
+            "nono", "décimo",
+        ],
+        _ => &[
+            "first", "second", "third", "fourth", "fifth", "sixth", "seventh", "eighth", "ninth",
+            "tenth",
+        ],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cardinals() {
+        let cases = [
+            (
+                "en",
+                [21, 70, 100],
+                ["twenty-one", "seventy", "one hundred"],
+            ),
+            ("hr", [21, 40, 100], ["dvadeset jedan", "četrdeset", "sto"]),
+            (
+                "es",
+                [16, 22, 31],
+                ["dieciséis", "veintidós", "treinta y uno"],
+            ),
+            (
+                "fr",
+                [21, 71, 91],
+                ["vingt et un", "soixante et onze", "quatre-vingt-onze"],
+            ),
+            (
+                "fr",
+                [77, 80, 81],
+                ["soixante-dix-sept", "quatre-vingts", "quatre-vingt-un"],
+            ),
+            (
+                "de",
+                [21, 35, 100],
+                ["einundzwanzig", "fünfunddreißig", "hundert"],
+            ),
+            ("it", [21, 23, 38], ["ventuno", "ventitré", "trentotto"]),
+            ("pt", [16, 21, 100], ["dezesseis", "vinte e um", "cem"]),
+        ];
+        for (code, numbers, expected) in cases {
+            for (n, word) in numbers.iter().zip(expected) {
+                assert_eq!(cardinal(code, *n), word, "{} {}", code, n);
+            }
+        }
+        assert_eq!(cardinal("es", 18), "dieciocho");
+        assert_eq!(cardinal("es", 12), "doce");
+    }
+
+    #[test]
+    fn test_number_words() {
+        for code in NUMBER_LANGUAGES {
+            let words = number_words(code);
+            assert_eq!(words.len(), 110, "{}", code);
+            let unique: std::collections::HashSet<_> = words.iter().map(|w| &w.word.text).collect();
+            assert_eq!(unique.len(), words.len(), "{}", code);
+        }
+        let words = number_words("fr");
+        assert_eq!(words[0].word.text, "un");
+        assert_eq!(words[0].gloss, "one");
+        assert_eq!(words[100].word.text, "premier");
+        assert_eq!(words[100].word.pos, PartOfSpeech::Adjective);
+        assert_eq!(words[100].gloss, "first");
+        assert!(number_words("ja").is_empty());
+    }
+}
@@ -0,0 +1,298 @@
+use crate::error::{AnkiDeckBuilderError, Result};
+use crate::language::frequency::PartOfSpeech;
+use crate::language::languages::{find_language, Language};
+use serde::{Deserialize, Deserializer};
+use std::path::Path;
+use std::sync::Arc;
+
+/// A language described in `languages.d/<code>.toml` in the config
+/// directory, so adding one needs no new release. Packs are merged with the
+/// built-in languages at startup; a pack for a built-in language can rename
+/// it or fill in what it lacks
+///
+/// ```toml
+/// name = "Slovenian"
+/// frequency_url = "https://raw.githubusercontent.com/hermitdave/FrequencyWords/master/content/2018/sl/sl_50k.txt"
+/// stopwords = ["je", "in", "da", "se", "na", "ne", "za", "so", "to", "pa"]
+///
+/// [[pos_rules]]
+/// pos = "verb"
+/// suffixes = ["ti", "či"]
+///
+/// [[gender_rules]]
+/// gender = "feminine"
+/// suffixes = ["a"]
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LanguagePack {
+    /// ISO 639-1 code; the file name without `.toml` when not given
+    #[serde(default)]
+    pub code: String,
+    pub name: String,
+    /// A list of "word count" lines in frequency order, as FrequencyWords
+    /// publishes them. Used as the `opensubtitles` source for the language
+    #[serde(default)]
+    pub frequency_url: Option<String>,
+    /// Tried in order to tell a listed word's part of speech; words none of
+    /// them match are nouns
+    #[serde(default)]
+    pub pos_rules: Vec<PosRule>,
+    /// The language's most common words, to check a downloaded list really
+    /// is in it
+    #[serde(default)]
+    pub stopwords: Vec<String>,
+    /// Removed from the front of translations by the `strip_articles` rule
+    #[serde(default)]
+    pub articles: Vec<String>,
+    /// Tried in order to tell a noun's gender, tagged `gender:<name>`
+    #[serde(default)]
+    pub gender_rules: Vec<GenderRule>,
+}
+
+/// Words that are one part of speech: these exact words, and words ending
+/// in one of the suffixes
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PosRule {
+    #[serde(deserialize_with = "part_of_speech")]
+    pub pos: PartOfSpeech,
+    #[serde(default)]
+    pub words: Vec<String>,
+    #[serde(default)]
+    pub suffixes: Vec<String>,
+}
+
+/// Nouns of one gender, matched like a [`PosRule`]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GenderRule {
+    pub gender: String,
+    #[serde(default)]
+    pub words: Vec<String>,
+    #[serde(default)]
+    pub suffixes: Vec<String>,
+}
+
+fn part_of_speech<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<PartOfSpeech, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    PartOfSpeech::from_name(&name.to_lowercase())
+        .ok_or_else(|| serde::de::Error::custom(format!("unknown part of speech '{}'", name)))
+}
+
+/// Whether a lowercase word is one of `words` or longer than, and ending
+/// in, one of `suffixes`
+fn matches(word: &str, words: &[String], suffixes: &[String]) -> bool {
+    words.iter().any(|w| w.to_lowercase() == word)
+        || suffixes
+            .iter()
+            .any(|suffix| word.len() > suffix.len() && word.ends_with(&suffix.to_lowercase()))
+}
+
+impl LanguagePack {
+    /// Read a pack file, named after the language it describes
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let invalid = |reason: &str| {
+            AnkiDeckBuilderError::ConfigurationError(format!(
+                "Invalid language pack {}: {}",
+                path.display(),
+                reason
+            ))
+        };
+
+        let content = std::fs::read_to_string(path)?;
+        let mut pack: Self = toml::from_str(&content).map_err(|e| invalid(e.message()))?;
+        if pack.code.is_empty() {
+            pack.code = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or_default()
+                .to_string();
+        }
+        pack.code = pack.code.to_lowercase();
+        if !(2..=3).contains(&pack.code.len()) || !pack.code.chars().all(|c| c.is_ascii_lowercase())
+        {
+            return Err(invalid(&format!(
+                "'{}' is not a language code; name the file e.g. sl.toml",
+                pack.code
+            )));
+        }
+        if pack.name.trim().is_empty() {
+            return Err(invalid("the name is empty"));
+        }
+        Ok(pack)
+    }
+
+    /// Part of speech of a listed word by the pack's rules, or None when it
+    /// has no rules and the built-in guess applies
+    pub fn guess_pos(&self, word: &str) -> Option<PartOfSpeech> {
+        if self.pos_rules.is_empty() {
+            return None;
+        }
+        let word = word.to_lowercase();
+        Some(
+            self.pos_rules
+                .iter()
+                .find(|rule| matches(&word, &rule.words, &rule.suffixes))
+                .map_or(PartOfSpeech::Noun, |rule| rule.pos.clone()),
+        )
+    }
+
+    /// Gender of a noun by the pack's rules, if one matches
+    pub fn gender(&self, word: &str) -> Option<&str> {
+        let word = word.to_lowercase();
+        self.gender_rules
+            .iter()
+            .find(|rule| matches(&word, &rule.words, &rule.suffixes))
+            .map(|rule| rule.gender.as_str())
+    }
+}
+
+/// The language packs a run uses, as loaded at startup. Cheap to clone, so
+/// each client that needs them can keep its own
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LanguagePacks(Arc<Vec<LanguagePack>>);
+
+impl LanguagePacks {
+    pub fn new(packs: Vec<LanguagePack>) -> Self {
+        Self(Arc::new(packs))
+    }
+
+    /// Every pack in `dir` (see [`load_language_packs`])
+    pub fn load(dir: &Path) -> Self {
+        Self::new(load_language_packs(dir))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &LanguagePack> {
+        self.0.iter()
+    }
+
+    /// The pack for a language code, if there is one
+    pub fn get(&self, code: &str) -> Option<&LanguagePack> {
+        self.0.iter().find(|pack| pack.code == code)
+    }
+
+    /// A language by code or name (case-insensitive), the built-in ones and
+    /// those the packs add
+    pub fn language(&self, input: &str) -> Option<Language> {
+        find_language(input, self)
+    }
+
+    /// Gender of a noun by the pack for its language, if any rule matches
+    pub fn noun_gender(&self, code: &str, word: &str) -> Option<String> {
+        self.get(code)
+            .and_then(|pack| pack.gender(word).map(str::to_string))
+    }
+}
+
+/// Read every `*.toml` pack in `dir`, in file name order. A pack that can't
+/// be read is left out with a warning, so one typo doesn't stop every run
+pub fn load_language_packs(dir: &Path) -> Vec<LanguagePack> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+        .collect();
+    paths.sort();
+
+    let mut packs: Vec<LanguagePack> = Vec::new();
+    for path in paths {
+        match LanguagePack::from_file(&path) {
+            Ok(pack) if packs.iter().any(|p| p.code == pack.code) => {
+                tracing::warn!(
+                    "Ignoring {}: another pack already describes '{}'",
+                    path.display(),
+                    pack.code
+                );
+            }
+            Ok(pack) => {
+                tracing::debug!("Loaded language pack {} ({})", pack.code, pack.name);
+                packs.push(pack);
+            }
+            Err(e) => tracing::warn!("{}", e),
+        }
+    }
+    packs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const SLOVENIAN: &str = r#"
+name = "Slovenian"
+frequency_url = "https://example.com/sl_50k.txt"
+stopwords = ["je", "in"]
+articles = []
+
+[[pos_rules]]
+pos = "preposition"
+words = ["v", "na"]
+
+[[pos_rules]]
+pos = "Verb"
+suffixes = ["ti"]
+
+[[gender_rules]]
+gender = "feminine"
+suffixes = ["a"]
+"#;
+
+    #[test]
+    fn test_load_packs() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("sl.toml"), SLOVENIAN).unwrap();
+        std::fs::write(dir.path().join("xx.toml"), "name = ").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "not a pack").unwrap();
+
+        let packs = load_language_packs(dir.path());
+        assert_eq!(packs.len(), 1);
+        let pack = &packs[0];
+        assert_eq!(pack.code, "sl");
+        assert_eq!(pack.name, "Slovenian");
+        assert_eq!(pack.stopwords, ["je", "in"]);
+
+        assert!(load_language_packs(&dir.path().join("missing")).is_empty());
+    }
+
+    #[test]
+    fn test_invalid_packs() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("sl.toml");
+
+        std::fs::write(
+            &path,
+            "name = \"Slovenian\"\n[[pos_rules]]\npos = \"gerund\"",
+        )
+        .unwrap();
+        let err = LanguagePack::from_file(&path).unwrap_err().to_string();
+        assert!(err.contains("unknown part of speech 'gerund'"), "{}", err);
+
+        std::fs::write(&path, "name = \"Slovenian\"\ncolour = \"red\"").unwrap();
+        assert!(LanguagePack::from_file(&path).is_err());
+
+        let path = dir.path().join("slovenian.toml");
+        std::fs::write(&path, "name = \"Slovenian\"").unwrap();
+        assert!(LanguagePack::from_file(&path).is_err());
+    }
+
+    #[test]
+    fn test_rules() {
+        let pack: LanguagePack = toml::from_str(SLOVENIAN).unwrap();
+
+        assert_eq!(pack.guess_pos("V"), Some(PartOfSpeech::Preposition));
+        assert_eq!(pack.guess_pos("delati"), Some(PartOfSpeech::Verb));
+        assert_eq!(pack.guess_pos("dan"), Some(PartOfSpeech::Noun));
+        // A suffix alone is not a word ending in it
+        assert_eq!(pack.guess_pos("ti"), Some(PartOfSpeech::Noun));
+        assert_eq!(LanguagePack::default().guess_pos("delati"), None);
+
+        assert_eq!(pack.gender("hiša"), Some("feminine"));
+        assert_eq!(pack.gender("dan"), None);
+    }
+}
@@ -0,0 +1,251 @@
+//! Common multi-word expressions that single-word frequency lists miss,
+//! such as greetings and courtesies. The lists are curated per language and
+//! ordered from most to least useful for a beginner
+
+use crate::language::frequency::{PartOfSpeech, Word};
+
+const CROATIAN: &[&str] = &[
+    "hvala lijepa",
+    "dobro jutro",
+    "dobar dan",
+    "dobra večer",
+    "laku noć",
+    "kako si",
+    "kako ste",
+    "nema problema",
+    "nema na čemu",
+    "oprostite",
+    "izvolite",
+    "molim vas",
+    "vidimo se",
+    "do viđenja",
+    "sretan put",
+    "dobar tek",
+    "ne znam",
+    "ne razumijem",
+    "koliko košta",
+    "gdje je",
+    "u redu",
+    "naravno",
+    "svaka čast",
+    "sve najbolje",
+    "sretan rođendan",
+];
+
+const SPANISH: &[&str] = &[
+    "por favor",
+    "muchas gracias",
+    "de nada",
+    "buenos días",
+    "buenas tardes",
+    "buenas noches",
+    "hasta luego",
+    "hasta mañana",
+    "¿qué tal?",
+    "¿cómo estás?",
+    "lo siento",
+    "con permiso",
+    "no pasa nada",
+    "no sé",
+    "no entiendo",
+    "¿cuánto cuesta?",
+    "¿dónde está?",
+    "de acuerdo",
+    "por supuesto",
+    "a veces",
+    "sin embargo",
+    "buen provecho",
+    "mucho gusto",
+    "feliz cumpleaños",
+    "que te vaya bien",
+];
+
+const FRENCH: &[&str] = &[
+    "s'il vous plaît",
+    "merci beaucoup",
+    "de rien",
+    "bonne nuit",
+    "bonne journée",
+    "à bientôt",
+    "à demain",
+    "ça va",
+    "comment ça va",
+    "excusez-moi",
+    "je suis désolé",
+    "je ne sais pas",
+    "je ne comprends pas",
+    "combien ça coûte",
+    "où est",
+    "d'accord",
+    "bien sûr",
+    "pas de problème",
+    "bon appétit",
+    "enchanté",
+    "joyeux anniversaire",
+    "tout à fait",
+    "il y a",
+    "en fait",
+    "tout de suite",
+];
+
+const GERMAN: &[&str] = &[
+    "vielen Dank",
+    "bitte schön",
+    "guten Morgen",
+    "guten Tag",
+    "guten Abend",
+    "gute Nacht",
+    "auf Wiedersehen",
+    "bis später",
+    "bis morgen",
+    "wie geht's",
+    "es tut mir leid",
+    "keine Ahnung",
+    "kein Problem",
+    "ich weiß nicht",
+    "ich verstehe nicht",
+    "wie viel kostet das",
+    "wo ist",
+    "alles klar",
+    "na klar",
+    "guten Appetit",
+    "herzlichen Glückwunsch",
+    "alles Gute",
+    "zum Beispiel",
+    "auf jeden Fall",
+    "gar nicht",
+];
+
+const ITALIAN: &[&str] = &[
+    "per favore",
+    "grazie mille",
+    "prego",
+    "buongiorno",
+    "buona sera",
+    "buona notte",
+    "a presto",
+    "a domani",
+    "come stai",
+    "come va",
+    "mi dispiace",
+    "non lo so",
+    "non capisco",
+    "quanto costa",
+    "dov'è",
+    "va bene",
+    "d'accordo",
+    "certo",
+    "non c'è problema",
+    "buon appetito",
+    "piacere",
+    "buon compleanno",
+    "in bocca al lupo",
+    "per esempio",
+    "da solo",
+];
+
+const PORTUGUESE: &[&str] = &[
+    "por favor",
+    "muito obrigado",
+    "de nada",
+    "bom dia",
+    "boa tarde",
+    "boa noite",
+    "até logo",
+    "até amanhã",
+    "tudo bem",
+    "como está",
+    "com licença",
+    "me desculpe",
+    "não sei",
+    "não entendo",
+    "quanto custa",
+    "onde fica",
+    "está bem",
+    "claro que sim",
+    "sem problema",
+    "bom apetite",
+    "muito prazer",
+    "feliz aniversário",
+    "por exemplo",
+    "às vezes",
+    "de repente",
+];
+
+const ENGLISH: &[&str] = &[
+    "thank you",
+    "you're welcome",
+    "excuse me",
+    "good morning",
+    "good afternoon",
+    "good evening",
+    "good night",
+    "see you later",
+    "how are you",
+    "nice to meet you",
+    "I'm sorry",
+    "I don't know",
+    "I don't understand",
+    "how much is it",
+    "where is",
+    "of course",
+    "no problem",
+    "never mind",
+    "all right",
+    "by the way",
+    "happy birthday",
+    "take care",
+    "for example",
+    "at least",
+    "as well",
+];
+
+fn phrase_list(language_code: &str) -> Option<&'static [&'static str]> {
+    Some(match language_code {
+        "hr" => CROATIAN,
+        "es" => SPANISH,
+        "fr" => FRENCH,
+        "de" => GERMAN,
+        "it" => ITALIAN,
+        "pt" => PORTUGUESE,
+        "en" => ENGLISH,
+        _ => return None,
+    })
+}
+
+/// Whether a phrase list ships for the language
+pub fn has_phrases(language_code: &str) -> bool {
+    phrase_list(language_code).is_some()
+}
+
+/// The `count` most useful phrases of a language, ranked in list order.
+/// Empty for languages without a list
+pub fn common_phrases(language_code: &str, count: usize) -> Vec<Word> {
+    phrase_list(language_code)
+        .unwrap_or_default()
+        .iter()
+        .take(count)
+        .enumerate()
+        .map(|(i, phrase)| Word::new(phrase.to_string(), PartOfSpeech::Phrase, i + 1))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_common_phrases() {
+        let phrases = common_phrases("hr", 3);
+        let texts: Vec<_> = phrases.iter().map(|p| p.text.as_str()).collect();
+        assert_eq!(texts, ["hvala lijepa", "dobro jutro", "dobar dan"]);
+        assert!(phrases.iter().all(|p| p.pos == PartOfSpeech::Phrase));
+        assert_eq!(phrases[2].rank, 3);
+
+        assert!(common_phrases("ja", 10).is_empty());
+        assert!(!has_phrases("ja"));
+        for code in ["hr", "es", "fr", "de", "it", "pt", "en"] {
+            assert!(common_phrases(code, 100).len() >= 20, "{}", code);
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use crate::error::{AnkiDeckBuilderError, Result};
+use crate::language::packs::LanguagePacks;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// A post-processing rule as written in the rules file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "rule", rename_all = "snake_case")]
+pub enum Rule {
+    /// Remove a leading article of the output language ("el día" → "día")
+    StripArticles,
+    /// Lowercase the whole translation
+    Lowercase,
+    /// Remove trailing punctuation ("día." → "día")
+    TrimPunctuation,
+    /// Regex replacement, `$1` style references allowed in the replacement
+    Replace {
+        pattern: String,
+        replacement: String,
+    },
+}
+
+#[derive(Debug, Clone)]
+enum CompiledRule {
+    StripArticles,
+    Lowercase,
+    TrimPunctuation,
+    Replace(Regex, String),
+}
+
+/// Rules applied to translator output before it is cached. Rules under the
+/// "*" key apply to every pair, then rules under "<from>-<to>" (e.g. "hr-es")
+#[derive(Debug, Clone, Default)]
+pub struct PostProcessor {
+    rules: HashMap<String, Vec<CompiledRule>>,
+    /// For the articles of the packs' languages
+    packs: LanguagePacks,
+}
+
+impl PostProcessor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build from rules keyed by language pair
+    pub fn from_rules(rules: HashMap<String, Vec<Rule>>) -> Result<Self> {
+        let mut compiled = HashMap::new();
+
+        for (pair, pair_rules) in rules {
+            let pair_compiled = pair_rules
+                .into_iter()
+                .map(compile)
+                .collect::<Result<Vec<_>>>()?;
+            compiled.insert(pair, pair_compiled);
+        }
+
+        Ok(Self {
+            rules: compiled,
+            packs: LanguagePacks::default(),
+        })
+    }
+
+    /// Strip the articles these packs give as well
+    pub fn with_language_packs(mut self, packs: LanguagePacks) -> Self {
+        self.packs = packs;
+        self
+    }
+
+    /// Load rules from a JSON file. A missing file means no rules
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::new());
+        }
+
+        let content = std::fs::read_to_string(path)?;
+        let rules: HashMap<String, Vec<Rule>> = serde_json::from_str(&content)?;
+        Self::from_rules(rules)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.values().all(Vec::is_empty)
+    }
+
+    /// Apply the rules for a translation from `from` into `to`
+    pub fn apply(&self, from: &str, to: &str, text: &str) -> String {
+        let pair = format!("{}-{}", from, to);
+        let rules = self
+            .rules
+            .get("*")
+            .into_iter()
+            .chain(self.rules.get(&pair))
+            .flatten();
+
+        let mut result = text.trim().to_string();
+        for rule in rules {
+            result = apply_rule(rule, to, &result, &self.packs);
+        }
+        result
+    }
+}
+
+fn compile(rule: Rule) -> Result<CompiledRule> {
+    Ok(match rule {
+        Rule::StripArticles => CompiledRule::StripArticles,
+        Rule::Lowercase => CompiledRule::Lowercase,
+        Rule::TrimPunctuation => CompiledRule::TrimPunctuation,
+        Rule::Replace {
+            pattern,
+            replacement,
+        } => {
+            let regex = Regex::new(&pattern).map_err(|e| {
+                AnkiDeckBuilderError::ConfigurationError(format!(
+                    "Invalid rule pattern '{}': {}",
+                    pattern, e
+                ))
+            })?;
+            CompiledRule::Replace(regex, replacement)
+        }
+    })
+}
+
+fn apply_rule(
+    rule: &CompiledRule,
+    language_code: &str,
+    text: &str,
+    packs: &LanguagePacks,
+) -> String {
+    match rule {
+        CompiledRule::StripArticles => strip_article(language_code, text, packs).to_string(),
+        CompiledRule::Lowercase => text.to_lowercase(),
+        CompiledRule::TrimPunctuation => text
+            .trim_end_matches(|c: char| c.is_ascii_punctuation() || c == '…')
+            .trim_end()
+            .to_string(),
+        CompiledRule::Replace(regex, replacement) => {
+            regex.replace_all(text, replacement.as_str()).into_owned()
+        }
+    }
+}
+
+/// Strip one leading article, keeping the text if nothing would be left
+fn strip_article<'a>(language_code: &str, text: &'a str, packs: &LanguagePacks) -> &'a str {
+    for article in articles(language_code, packs) {
+        let Some(prefix) = text.get(..article.len()) else {
+            continue;
+        };
+        if !prefix.eq_ignore_ascii_case(&article) {
+            continue;
+        }
+
+        let rest = &text[article.len()..];
+        // Elided articles ("l'") attach directly; others need a space
+        let rest = if article.ends_with('\'') {
+            rest
+        } else if rest.starts_with(' ') {
+            rest.trim_start()
+        } else {
+            continue;
+        };
+
+        if !rest.is_empty() {
+            return rest;
+        }
+    }
+    text
+}
+
+/// A language pack's articles, longest first so "l'" is tried before "l",
+/// or else the built-in ones
+fn articles(language_code: &str, packs: &LanguagePacks) -> Vec<String> {
+    if let Some(pack) = packs
+        .get(language_code)
+        .filter(|pack| !pack.articles.is_empty())
+    {
+        let mut articles = pack.articles.clone();
+        articles.sort_by_key(|article| std::cmp::Reverse(article.len()));
+        return articles;
+    }
+    let builtin: &[&str] = match language_code {
+        "es" => &["el", "la", "los", "las", "un", "una", "unos", "unas"],
+        "en" => &["the", "an", "a"],
+        "fr" => &["les", "le", "la", "l'", "une", "un", "des"],
+        "de" => &["der", "die", "das", "den", "dem", "des", "eine", "ein"],
+        "it" => &["gli", "il", "lo", "la", "le", "l'", "una", "uno", "un", "i"],
+        "pt" => &["os", "as", "o", "a", "uma", "um"],
+        "nl" => &["de", "het", "een"],
+        _ => &[],
+    };
+    builtin.iter().map(|article| article.to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn processor(json: &str) -> PostProcessor {
+        PostProcessor::from_rules(serde_json::from_str(json).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_builtin_rules() {
+        let p = processor(
+            r#"{"*": [{"rule": "strip_articles"}, {"rule": "trim_punctuation"}, {"rule": "lowercase"}]}"#,
+        );
+
+        assert_eq!(p.apply("hr", "es", "El Día."), "día");
+        assert_eq!(p.apply("hr", "fr", "l'homme"), "homme");
+        assert_eq!(p.apply("hr", "es", "la"), "la");
+        assert_eq!(p.apply("hr", "es", "lago"), "lago");
+    }
+
+    #[test]
+    fn test_pair_specific_replace() {
+        let p = processor(
+            r#"{"hr-es": [{"rule": "replace", "pattern": "^([^,]+),.*$", "replacement": "$1"}]}"#,
+        );
+
+        assert_eq!(p.apply("hr", "es", "casa, hogar"), "casa");
+        assert_eq!(p.apply("hr", "en", "house, home"), "house, home");
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        let rules = serde_json::from_str(
+            r#"{"*": [{"rule": "replace", "pattern": "(", "replacement": ""}]}"#,
+        )
+        .unwrap();
+        assert!(PostProcessor::from_rules(rules).is_err());
+    }
+
+    #[test]
+    fn test_missing_file_is_empty() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let p = PostProcessor::load(&temp_dir.path().join("rules.json")).unwrap();
+        assert!(p.is_empty());
+        assert_eq!(p.apply("hr", "es", " día "), "día");
+    }
+}
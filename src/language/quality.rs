@@ -0,0 +1,265 @@
+//! How trustworthy a parsed frequency list looks: how its words spread over
+//! parts of speech, which tokens are unlikely to be words of the language,
+//! which words it lists twice, and how much of the corpus its top covers
+
+use crate::language::frequency::{FrequencyData, PartOfSpeech, Word};
+use crate::language::text::grapheme_len;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Top-of-list sizes coverage is reported for
+const COVERAGE_STEPS: [usize; 4] = [100, 1000, 5000, 10000];
+
+/// Writing systems told apart when looking for words in a foreign script
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Script {
+    Latin,
+    Cyrillic,
+    Greek,
+    Arabic,
+    Hebrew,
+    Devanagari,
+    Hangul,
+    /// Chinese characters and Japanese kana, which Japanese mixes freely
+    Cjk,
+    Other,
+}
+
+impl Script {
+    /// Script of a letter, None for digits, punctuation and the like
+    pub fn of(c: char) -> Option<Self> {
+        if !c.is_alphabetic() {
+            return None;
+        }
+        Some(match c {
+            'a'..='z' | 'A'..='Z' | '\u{00C0}'..='\u{024F}' | '\u{1E00}'..='\u{1EFF}' => {
+                Self::Latin
+            }
+            '\u{0400}'..='\u{052F}' => Self::Cyrillic,
+            '\u{0370}'..='\u{03FF}' | '\u{1F00}'..='\u{1FFF}' => Self::Greek,
+            '\u{0600}'..='\u{06FF}' | '\u{0750}'..='\u{077F}' => Self::Arabic,
+            '\u{0590}'..='\u{05FF}' => Self::Hebrew,
+            '\u{0900}'..='\u{097F}' => Self::Devanagari,
+            '\u{AC00}'..='\u{D7AF}' | '\u{1100}'..='\u{11FF}' => Self::Hangul,
+            '\u{3040}'..='\u{30FF}' | '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}' => {
+                Self::Cjk
+            }
+            _ => Self::Other,
+        })
+    }
+
+    /// Script of the first letter of a word
+    pub fn of_word(word: &str) -> Option<Self> {
+        word.chars().find_map(Self::of)
+    }
+}
+
+/// Words listed under one part of speech
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PosShare {
+    pub pos: PartOfSpeech,
+    pub words: usize,
+    /// Share of the list, from 0.0 to 1.0
+    pub share: f64,
+}
+
+/// A word the list has more than once, ignoring case
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Duplicate {
+    pub text: String,
+    /// Part of speech of each entry, in rank order
+    pub parts_of_speech: Vec<PartOfSpeech>,
+    pub ranks: Vec<usize>,
+}
+
+/// Share of the corpus the top of the list accounts for
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CoverageStep {
+    pub words: usize,
+    /// From 0.0 to 1.0
+    pub share: f64,
+}
+
+/// Word counts of a list that carries them
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Coverage {
+    /// Occurrences of every listed word together
+    pub corpus_words: usize,
+    /// Words seen only once, mostly typos and names
+    pub hapaxes: usize,
+    pub steps: Vec<CoverageStep>,
+}
+
+/// What `inspect-frequency` found in a list. Suspicious words are in rank order
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FrequencyReport {
+    pub language: String,
+    pub words: usize,
+    pub parts_of_speech: Vec<PosShare>,
+    /// Script most words are written in
+    pub script: Option<Script>,
+    /// Tokens with digits in them
+    pub numbers: Vec<String>,
+    /// Lone letters, which are rarely words outside Chinese, Japanese and Korean
+    pub single_letters: Vec<String>,
+    /// Words in another script than most of the list
+    pub foreign_script: Vec<String>,
+    pub duplicates: Vec<Duplicate>,
+    /// None when the list only ranks its words
+    pub coverage: Option<Coverage>,
+}
+
+impl FrequencyReport {
+    pub fn new(data: &FrequencyData) -> Self {
+        let mut words: Vec<&Word> = data.words.values().flatten().collect();
+        words.sort_by_key(|word| word.rank);
+
+        let parts_of_speech = PartOfSpeech::all()
+            .into_iter()
+            .chain([PartOfSpeech::Phrase])
+            .filter_map(|pos| {
+                let count = data.words.get(&pos).map_or(0, Vec::len);
+                (count > 0).then(|| PosShare {
+                    pos,
+                    words: count,
+                    share: count as f64 / words.len() as f64,
+                })
+            })
+            .collect();
+
+        let mut scripts: HashMap<Script, usize> = HashMap::new();
+        for script in words.iter().filter_map(|word| Script::of_word(&word.text)) {
+            *scripts.entry(script).or_default() += 1;
+        }
+        let script = scripts
+            .into_iter()
+            .max_by_key(|(_, count)| *count)
+            .map(|(script, _)| script);
+
+        let texts = |keep: &dyn Fn(&str) -> bool| -> Vec<String> {
+            words
+                .iter()
+                .filter(|word| keep(&word.text))
+                .map(|word| word.text.clone())
+                .collect()
+        };
+        let numbers = texts(&|text| text.chars().any(|c| c.is_numeric()));
+        let single_letters = texts(&|text| {
+            grapheme_len(text) == 1
+                && !matches!(
+                    Script::of_word(text),
+                    None | Some(Script::Cjk | Script::Hangul)
+                )
+        });
+        let foreign_script =
+            texts(&|text| Script::of_word(text).is_some_and(|found| Some(found) != script));
+
+        Self {
+            language: data.language.clone(),
+            words: words.len(),
+            parts_of_speech,
+            script,
+            numbers,
+            single_letters,
+            foreign_script,
+            duplicates: duplicates(&words),
+            coverage: data.has_counts().then(|| coverage(&words)),
+        }
+    }
+
+    /// Words flagged as numbers, single letters or in a foreign script
+    pub fn suspicious(&self) -> usize {
+        self.numbers.len() + self.single_letters.len() + self.foreign_script.len()
+    }
+}
+
+/// Entries sharing a spelling, `words` being in rank order
+fn duplicates(words: &[&Word]) -> Vec<Duplicate> {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut duplicates: Vec<Duplicate> = Vec::new();
+    for word in words {
+        let key = word.text.to_lowercase();
+        let at = *seen.entry(key).or_insert_with(|| {
+            duplicates.push(Duplicate {
+                text: word.text.clone(),
+                parts_of_speech: Vec::new(),
+                ranks: Vec::new(),
+            });
+            duplicates.len() - 1
+        });
+        duplicates[at].parts_of_speech.push(word.pos.clone());
+        duplicates[at].ranks.push(word.rank);
+    }
+    duplicates.retain(|duplicate| duplicate.ranks.len() > 1);
+    duplicates
+}
+
+fn coverage(words: &[&Word]) -> Coverage {
+    let mut counts: Vec<usize> = words.iter().map(|word| word.frequency).collect();
+    counts.sort_unstable_by(|a, b| b.cmp(a));
+    let corpus_words: usize = counts.iter().sum();
+    let steps = COVERAGE_STEPS
+        .iter()
+        .filter(|&&step| step < counts.len())
+        .map(|&step| CoverageStep {
+            words: step,
+            share: counts[..step].iter().sum::<usize>() as f64 / corpus_words.max(1) as f64,
+        })
+        .collect();
+    Coverage {
+        corpus_words,
+        hapaxes: counts.iter().filter(|&&count| count == 1).count(),
+        steps,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report() {
+        let mut data = FrequencyData::new("hr".to_string());
+        let entries = [
+            ("je", PartOfSpeech::Verb, 900),
+            ("kuća", PartOfSpeech::Noun, 400),
+            ("2024", PartOfSpeech::Noun, 300),
+            ("dobar", PartOfSpeech::Adjective, 200),
+            ("Kuća", PartOfSpeech::Noun, 100),
+            ("x", PartOfSpeech::Noun, 50),
+            ("дом", PartOfSpeech::Noun, 49),
+            ("pas", PartOfSpeech::Noun, 1),
+        ];
+        for (rank, (text, pos, count)) in entries.into_iter().enumerate() {
+            let mut word = Word::new(text.to_string(), pos, rank + 1);
+            word.frequency = count;
+            data.add_word(word);
+        }
+
+        let report = FrequencyReport::new(&data);
+        assert_eq!(report.words, 8);
+        assert_eq!(report.script, Some(Script::Latin));
+        assert_eq!(report.parts_of_speech[0].pos, PartOfSpeech::Noun);
+        assert_eq!(report.parts_of_speech[0].words, 6);
+        assert_eq!(report.numbers, vec!["2024"]);
+        assert_eq!(report.single_letters, vec!["x"]);
+        assert_eq!(report.foreign_script, vec!["дом"]);
+        assert_eq!(report.suspicious(), 3);
+        assert_eq!(report.duplicates.len(), 1);
+        assert_eq!(report.duplicates[0].text, "kuća");
+        assert_eq!(report.duplicates[0].ranks, vec![2, 5]);
+
+        let coverage = report.coverage.unwrap();
+        assert_eq!(coverage.corpus_words, 2000);
+        assert_eq!(coverage.hapaxes, 1);
+        assert!(coverage.steps.is_empty());
+
+        // Ideographs are whole words, not stray letters
+        let mut data = FrequencyData::new("zh".to_string());
+        data.add_word(Word::new("人".to_string(), PartOfSpeech::Noun, 1));
+        let report = FrequencyReport::new(&data);
+        assert!(report.single_letters.is_empty());
+        assert!(report.coverage.is_none());
+    }
+}
@@ -0,0 +1,56 @@
+//! Optional sections of a deck whose vocabulary is generated by rule rather
+//! than picked from a frequency list, each in a subdeck of its own
+
+use crate::language::dates::{date_words, has_date_words};
+use crate::language::frequency::Word;
+use crate::language::numbers::{has_number_words, number_words};
+use serde::{Deserialize, Serialize};
+
+/// A generated word and what it means in English
+#[derive(Debug, Clone)]
+pub struct GeneratedWord {
+    pub word: Word,
+    pub gloss: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Section {
+    /// One to a hundred and the first ten ordinals
+    Numbers,
+    /// Weekdays, months and clock times
+    Dates,
+}
+
+impl Section {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Numbers => "numbers",
+            Self::Dates => "dates",
+        }
+    }
+
+    /// Subdeck the section's notes go into, under the main deck
+    pub fn subdeck(self) -> &'static str {
+        match self {
+            Self::Numbers => "Numbers",
+            Self::Dates => "Dates",
+        }
+    }
+
+    /// Whether the section can be generated for the language
+    pub fn is_available(self, language_code: &str) -> bool {
+        match self {
+            Self::Numbers => has_number_words(language_code),
+            Self::Dates => has_date_words(language_code),
+        }
+    }
+
+    /// The section's words in the language, ranked in order
+    pub fn words(self, language_code: &str) -> Vec<GeneratedWord> {
+        match self {
+            Self::Numbers => number_words(language_code),
+            Self::Dates => date_words(language_code),
+        }
+    }
+}
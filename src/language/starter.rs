@@ -0,0 +1,77 @@
+//! Hand-picked essentials for a first deck: greetings, numbers, days,
+//! question words and what it takes to get by. The lists are data files
+//! under `starter/`, embedded at build time
+
+use crate::language::frequency::{PartOfSpeech, Word};
+
+fn starter_list(language_code: &str) -> Option<&'static str> {
+    Some(match language_code {
+        "hr" => include_str!("starter/hr.txt"),
+        "es" => include_str!("starter/es.txt"),
+        "fr" => include_str!("starter/fr.txt"),
+        "de" => include_str!("starter/de.txt"),
+        "it" => include_str!("starter/it.txt"),
+        "pt" => include_str!("starter/pt.txt"),
+        "en" => include_str!("starter/en.txt"),
+        _ => return None,
+    })
+}
+
+/// Whether a starter list ships for the language
+pub fn has_starter_list(language_code: &str) -> bool {
+    starter_list(language_code).is_some()
+}
+
+/// The starter words of a language, ranked in list order. Empty for
+/// languages without a list
+pub fn starter_words(language_code: &str) -> Vec<Word> {
+    starter_list(language_code)
+        .unwrap_or_default()
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let (pos, text) = line.split_once(' ')?;
+            let pos = PartOfSpeech::from_name(pos);
+            if pos.is_none() {
+                tracing::warn!(
+                    "Skipping starter word with unknown part of speech: {}",
+                    line
+                );
+            }
+            Some((pos?, text.trim()))
+        })
+        .enumerate()
+        .map(|(i, (pos, text))| Word::new(text.to_string(), pos, i + 1))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_starter_words() {
+        let words = starter_words("hr");
+        assert_eq!(words[0].text, "bok");
+        assert_eq!(words[0].pos, PartOfSpeech::Interjection);
+        assert_eq!(words[1].text, "dobar dan");
+        assert_eq!(words[1].pos, PartOfSpeech::Phrase);
+        assert_eq!(words[9].rank, 10);
+
+        assert!(starter_words("ja").is_empty());
+        assert!(!has_starter_list("ja"));
+        for code in ["hr", "es", "fr", "de", "it", "pt", "en"] {
+            let words = starter_words(code);
+            let lines = starter_list(code)
+                .unwrap()
+                .lines()
+                .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+                .count();
+            assert_eq!(words.len(), lines, "{}", code);
+            let unique: HashSet<_> = words.iter().map(|w| &w.text).collect();
+            assert_eq!(unique.len(), words.len(), "{}", code);
+        }
+    }
+}
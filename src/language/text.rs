@@ -0,0 +1,85 @@
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Characters that take no space and only get in the way of matching words:
+/// soft hyphens, zero-width spaces, direction marks, word joiners and byte
+/// order marks. Zero-width (non-)joiners are kept, as some scripts need them
+const INVISIBLE: [char; 6] = [
+    '\u{ad}', '\u{200b}', '\u{200e}', '\u{200f}', '\u{2060}', '\u{feff}',
+];
+
+/// Put text from any source into one form, so "č" typed precomposed and "c"
+/// plus a combining caron compare, cache and dedupe as the same word. Strips
+/// invisible characters, composes to NFC and collapses runs of whitespace
+pub fn normalize_text(text: &str) -> String {
+    let composed: String = text
+        .chars()
+        .filter(|c| !INVISIBLE.contains(c))
+        .nfc()
+        .collect();
+    composed.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Number of user-perceived characters, so "ć" counts as one whether it is
+/// stored precomposed or as "c" plus a combining accent
+pub fn grapheme_len(text: &str) -> usize {
+    text.graphemes(true).count()
+}
+
+/// Shortest word kept from a frequency list. Shorter tokens are mostly
+/// articles, prepositions, and stray letters
+pub fn min_word_length(language_code: &str) -> usize {
+    match language_code {
+        // A single ideograph or syllable block is often a whole word
+        "zh" | "ja" | "ko" => 1,
+        _ => 2,
+    }
+}
+
+/// Shorten text to at most `max` graphemes, ending with "…" when cut
+pub fn truncate_graphemes(text: &str, max: usize) -> String {
+    if grapheme_len(text) <= max {
+        return text.to_string();
+    }
+
+    let kept: String = text.graphemes(true).take(max.saturating_sub(1)).collect();
+    format!("{}…", kept)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_text() {
+        assert_eq!(normalize_text("c\u{30c}ovjek"), "čovjek");
+        assert_eq!(normalize_text("\u{feff} kuća\u{200b} "), "kuća");
+        assert_eq!(normalize_text("dobar\u{ad}  dan\n"), "dobar dan");
+        assert_eq!(normalize_text("a\u{200d}b"), "a\u{200d}b");
+        assert_eq!(normalize_text("šećer"), "šećer");
+    }
+
+    #[test]
+    fn test_grapheme_len() {
+        assert_eq!(grapheme_len("ć"), 1);
+        assert_eq!(grapheme_len("c\u{301}"), 1);
+        assert_eq!(grapheme_len("dan"), 3);
+        assert_eq!(grapheme_len("日本"), 2);
+    }
+
+    #[test]
+    fn test_min_word_length() {
+        assert!(grapheme_len("ć") < min_word_length("hr"));
+        assert!(grapheme_len("日") >= min_word_length("ja"));
+    }
+
+    #[test]
+    fn test_truncate_graphemes() {
+        assert_eq!(truncate_graphemes("día", 3), "día");
+        assert_eq!(truncate_graphemes("čovjekoljublje", 6), "čovje…");
+        assert_eq!(
+            truncate_graphemes("c\u{301}c\u{301}c\u{301}", 2),
+            "c\u{301}…"
+        );
+    }
+}
@@ -1,8 +1,50 @@
 use crate::error::Result;
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+/// Translator asked first; the others are kept to fall back on
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TranslatorKind {
+    /// MyMemory, free and without a key
+    #[default]
+    Mymemory,
+    /// Google Translate through its unofficial free endpoint, which may
+    /// change or block clients without notice; requests are spaced a second apart
+    Google,
+    /// A large language model behind an OpenAI-compatible API, set up under
+    /// `llm` in config.json; asked for many words per request, and paid for
+    Llm,
+}
 
 #[async_trait]
 pub trait Translator: Send + Sync {
+    /// Short provider identifier
+    fn name(&self) -> &'static str;
+
+    /// Whether the provider can translate to and from the language
+    fn supports(&self, _language_code: &str) -> bool {
+        true
+    }
+
+    /// Whether the provider can translate from `from` into `to`
+    fn supports_pair(&self, from: &str, to: &str) -> bool {
+        self.supports(from) && self.supports(to)
+    }
+
+    /// Ask the service which pairs it translates, for `supports_pair` to
+    /// use instead of the built-in list. Most providers can't be asked
+    async fn probe_languages(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Translate `texts` ahead of `translate` asking for them one by one,
+    /// for providers that answer many in one request and cache them. The
+    /// others have nothing to do ahead
+    async fn prepare(&self, _texts: &[String], _from: &str, _to: &str) -> Result<()> {
+        Ok(())
+    }
+
     /// Translate a single text from source language to target language
     async fn translate(&self, text: &str, from: &str, to: &str) -> Result<String>;
 
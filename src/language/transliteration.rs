@@ -0,0 +1,565 @@
+//! Romanization of words written in non-Latin scripts, using per-script
+//! rules rather than a dictionary. Cyrillic, Greek, Arabic, Devanagari,
+//! Hangul, and Japanese kana are covered; Chinese characters and Japanese
+//! kanji need a dictionary for their readings and are left as they are
+
+/// Languages written in a script these rules can romanize
+const NON_LATIN_LANGUAGES: &[&str] = &["ru", "uk", "sr", "el", "ar", "hi", "ko", "ja"];
+
+/// Whether words in this language get a romanized reading
+pub fn has_transliteration(language_code: &str) -> bool {
+    NON_LATIN_LANGUAGES.contains(&language_code)
+}
+
+/// Romanize a word. Returns None when nothing in it needed romanizing, or
+/// when its script has no rules (such as Chinese)
+pub fn transliterate(language_code: &str, text: &str) -> Option<String> {
+    let romanized = match language_code {
+        "ja" => romanize_kana(text),
+        "ko" => romanize_hangul(text),
+        "hi" => romanize_devanagari(text),
+        _ => text
+            .chars()
+            .map(|c| romanize_char(language_code, c))
+            .collect(),
+    };
+
+    (romanized != text).then_some(romanized)
+}
+
+/// Romanize a single alphabetic character, keeping its case
+fn romanize_char(language_code: &str, c: char) -> String {
+    let lower = c.to_lowercase().next().unwrap_or(c);
+    let Some(latin) = cyrillic(language_code, lower)
+        .or_else(|| greek(lower))
+        .or_else(|| arabic(c))
+    else {
+        return c.to_string();
+    };
+
+    if c == lower {
+        return latin.to_string();
+    }
+    let mut chars = latin.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn cyrillic(language_code: &str, c: char) -> Option<&'static str> {
+    // Serbian uses its own Latin alphabet (Gaj's), one letter per letter
+    if language_code == "sr" {
+        let latin = match c {
+            'ђ' => "đ",
+            'ј' => "j",
+            'љ' => "lj",
+            'њ' => "nj",
+            'ћ' => "ć",
+            'џ' => "dž",
+            'ж' => "ž",
+            'х' => "h",
+            'ц' => "c",
+            'ч' => "č",
+            'ш' => "š",
+            _ => return common_cyrillic(c),
+        };
+        return Some(latin);
+    }
+
+    if language_code == "uk" {
+        let latin = match c {
+            'г' => "h",
+            'ґ' => "g",
+            'и' => "y",
+            'і' => "i",
+            'ї' => "i",
+            'є' => "ye",
+            'щ' => "shch",
+            _ => return common_cyrillic(c).or_else(|| russian(c)),
+        };
+        return Some(latin);
+    }
+
+    common_cyrillic(c).or_else(|| russian(c))
+}
+
+/// Letters romanized the same way in Russian, Ukrainian, and Serbian
+fn common_cyrillic(c: char) -> Option<&'static str> {
+    let latin = match c {
+        'а' => "a",
+        'б' => "b",
+        'в' => "v",
+        'г' => "g",
+        'д' => "d",
+        'е' => "e",
+        'з' => "z",
+        'и' => "i",
+        'к' => "k",
+        'л' => "l",
+        'м' => "m",
+        'н' => "n",
+        'о' => "o",
+        'п' => "p",
+        'р' => "r",
+        'с' => "s",
+        'т' => "t",
+        'у' => "u",
+        'ф' => "f",
+        _ => return None,
+    };
+    Some(latin)
+}
+
+fn russian(c: char) -> Option<&'static str> {
+    let latin = match c {
+        'ё' => "yo",
+        'ж' => "zh",
+        'й' => "y",
+        'х' => "kh",
+        'ц' => "ts",
+        'ч' => "ch",
+        'ш' => "sh",
+        'щ' => "shch",
+        'ъ' | 'ь' => "",
+        'ы' => "y",
+        'э' => "e",
+        'ю' => "yu",
+        'я' => "ya",
+        _ => return None,
+    };
+    Some(latin)
+}
+
+fn greek(c: char) -> Option<&'static str> {
+    let latin = match c {
+        'α' | 'ά' => "a",
+        'β' => "v",
+        'γ' => "g",
+        'δ' => "d",
+        'ε' | 'έ' => "e",
+        'ζ' => "z",
+        'η' | 'ή' => "i",
+        'θ' => "th",
+        'ι' | 'ί' | 'ϊ' | 'ΐ' => "i",
+        'κ' => "k",
+        'λ' => "l",
+        'μ' => "m",
+        'ν' => "n",
+        'ξ' => "x",
+        'ο' | 'ό' => "o",
+        'π' => "p",
+        'ρ' => "r",
+        'σ' | 'ς' => "s",
+        'τ' => "t",
+        'υ' | 'ύ' | 'ϋ' | 'ΰ' => "y",
+        'φ' => "f",
+        'χ' => "ch",
+        'ψ' => "ps",
+        'ω' | 'ώ' => "o",
+        _ => return None,
+    };
+    Some(latin)
+}
+
+/// Consonants and long vowels only: short vowels are rarely written
+fn arabic(c: char) -> Option<&'static str> {
+    let latin = match c {
+        'ا' | 'ى' | 'ة' | 'أ' => "a",
+        'إ' => "i",
+        'آ' => "aa",
+        'ب' => "b",
+        'ت' => "t",
+        'ث' => "th",
+        'ج' => "j",
+        'ح' | 'ه' => "h",
+        'خ' => "kh",
+        'د' => "d",
+        'ذ' => "dh",
+        'ر' => "r",
+        'ز' => "z",
+        'س' | 'ص' => "s",
+        'ش' => "sh",
+        'ض' => "d",
+        'ط' => "t",
+        'ظ' => "z",
+        'ع' | 'ء' | 'ؤ' | 'ئ' => "'",
+        'غ' => "gh",
+        'ف' => "f",
+        'ق' => "q",
+        'ك' => "k",
+        'ل' => "l",
+        'م' => "m",
+        'ن' => "n",
+        'و' => "w",
+        'ي' => "y",
+        // Vowel marks, when present
+        '\u{064E}' => "a",
+        '\u{0650}' => "i",
+        '\u{064F}' => "u",
+        '\u{0652}' | '\u{0651}' => "",
+        _ => return None,
+    };
+    Some(latin)
+}
+
+/// Devanagari consonants carry an inherent "a", replaced by a following
+/// vowel sign and dropped before a virama or at the end of a word
+fn romanize_devanagari(text: &str) -> String {
+    let mut out = String::new();
+    let mut pending_a = false;
+
+    for c in text.chars() {
+        if let Some(consonant) = devanagari_consonant(c) {
+            if pending_a {
+                out.push('a');
+            }
+            out.push_str(consonant);
+            pending_a = true;
+        } else if let Some(sign) = devanagari_vowel_sign(c) {
+            out.push_str(sign);
+            pending_a = false;
+        } else if c == '\u{094D}' {
+            // Virama
+            pending_a = false;
+        } else if let Some(vowel) = devanagari_vowel(c) {
+            if pending_a {
+                out.push('a');
+            }
+            out.push_str(vowel);
+            pending_a = false;
+        } else {
+            if pending_a {
+                out.push('a');
+            }
+            pending_a = false;
+            match c {
+                // Anusvara and chandrabindu nasalize the vowel before them
+                '\u{0902}' | '\u{0901}' => out.push('n'),
+                '\u{0903}' => out.push('h'),
+                '\u{093C}' => {}
+                _ => out.push(c),
+            }
+        }
+    }
+    out
+}
+
+fn devanagari_consonant(c: char) -> Option<&'static str> {
+    let latin = match c {
+        'क' => "k",
+        'ख' => "kh",
+        'ग' => "g",
+        'घ' => "gh",
+        'ङ' => "n",
+        'च' => "ch",
+        'छ' => "chh",
+        'ज' => "j",
+        'झ' => "jh",
+        'ञ' => "n",
+        'ट' => "t",
+        'ठ' => "th",
+        'ड' => "d",
+        'ढ' => "dh",
+        'ण' => "n",
+        'त' => "t",
+        'थ' => "th",
+        'द' => "d",
+        'ध' => "dh",
+        'न' => "n",
+        'प' => "p",
+        'फ' => "ph",
+        'ब' => "b",
+        'भ' => "bh",
+        'म' => "m",
+        'य' => "y",
+        'र' => "r",
+        'ल' => "l",
+        'व' => "v",
+        'श' | 'ष' => "sh",
+        'स' => "s",
+        'ह' => "h",
+        _ => return None,
+    };
+    Some(latin)
+}
+
+fn devanagari_vowel(c: char) -> Option<&'static str> {
+    let latin = match c {
+        'अ' => "a",
+        'आ' => "aa",
+        'इ' => "i",
+        'ई' => "ee",
+        'उ' => "u",
+        'ऊ' => "oo",
+        'ऋ' => "ri",
+        'ए' => "e",
+        'ऐ' => "ai",
+        'ओ' => "o",
+        'औ' => "au",
+        _ => return None,
+    };
+    Some(latin)
+}
+
+fn devanagari_vowel_sign(c: char) -> Option<&'static str> {
+    let latin = match c {
+        'ा' => "aa",
+        'ि' => "i",
+        'ी' => "ee",
+        'ु' => "u",
+        'ू' => "oo",
+        'ृ' => "ri",
+        'े' => "e",
+        'ै' => "ai",
+        'ो' => "o",
+        'ौ' => "au",
+        _ => return None,
+    };
+    Some(latin)
+}
+
+const HANGUL_INITIALS: [&str; 19] = [
+    "g", "kk", "n", "d", "tt", "r", "m", "b", "pp", "s", "ss", "", "j", "jj", "ch", "k", "t", "p",
+    "h",
+];
+
+const HANGUL_MEDIALS: [&str; 21] = [
+    "a", "ae", "ya", "yae", "eo", "e", "yeo", "ye", "o", "wa", "wae", "oe", "yo", "u", "wo", "we",
+    "wi", "yu", "eu", "ui", "i",
+];
+
+const HANGUL_FINALS: [&str; 28] = [
+    "", "k", "k", "k", "n", "n", "n", "t", "l", "k", "m", "l", "l", "l", "p", "l", "m", "p", "p",
+    "t", "t", "ng", "t", "t", "k", "t", "p", "t",
+];
+
+/// Revised Romanization, syllable by syllable, without the sound changes
+/// between syllables
+fn romanize_hangul(text: &str) -> String {
+    text.chars()
+        .map(|c| {
+            let code = c as u32;
+            if !(0xAC00..=0xD7A3).contains(&code) {
+                return c.to_string();
+            }
+            let index = (code - 0xAC00) as usize;
+            format!(
+                "{}{}{}",
+                HANGUL_INITIALS[index / (21 * 28)],
+                HANGUL_MEDIALS[(index % (21 * 28)) / 28],
+                HANGUL_FINALS[index % 28]
+            )
+        })
+        .collect()
+}
+
+/// Hepburn romanization of hiragana and katakana
+fn romanize_kana(text: &str) -> String {
+    // Katakana is hiragana shifted by 0x60
+    let hiragana: Vec<char> = text
+        .chars()
+        .map(|c| match c {
+            'ァ'..='ヶ' => char::from_u32(c as u32 - 0x60).unwrap_or(c),
+            _ => c,
+        })
+        .collect();
+
+    let mut out = String::new();
+    let mut double_next = false;
+    let mut i = 0;
+    while i < hiragana.len() {
+        let c = hiragana[i];
+
+        let syllable = match (kana(c), hiragana.get(i + 1).and_then(|&n| small_kana(n))) {
+            // "きゃ" → "kya", "しゃ" → "sha"
+            (Some(base), Some(small)) if base.ends_with('i') && base.len() > 1 => {
+                i += 1;
+                let stem = &base[..base.len() - 1];
+                match stem {
+                    "sh" | "ch" | "j" => format!("{}{}", stem, &small[1..]),
+                    _ => format!("{}{}", stem, small),
+                }
+            }
+            (Some(base), _) => base.to_string(),
+            (None, _) => match c {
+                'っ' => {
+                    double_next = true;
+                    i += 1;
+                    continue;
+                }
+                // Long vowel mark repeats the vowel before it
+                'ー' => out
+                    .chars()
+                    .last()
+                    .filter(|v| "aeiou".contains(*v))
+                    .map(String::from)
+                    .unwrap_or_default(),
+                _ => small_kana(c)
+                    .map(str::to_string)
+                    .unwrap_or_else(|| c.to_string()),
+            },
+        };
+
+        if double_next {
+            if let Some(first) = syllable.chars().next().filter(|c| !"aeioun".contains(*c)) {
+                out.push(if syllable.starts_with("ch") {
+                    't'
+                } else {
+                    first
+                });
+            }
+            double_next = false;
+        }
+        out.push_str(&syllable);
+        i += 1;
+    }
+    out
+}
+
+fn small_kana(c: char) -> Option<&'static str> {
+    let latin = match c {
+        'ゃ' => "ya",
+        'ゅ' => "yu",
+        'ょ' => "yo",
+        'ぁ' => "a",
+        'ぃ' => "i",
+        'ぅ' => "u",
+        'ぇ' => "e",
+        'ぉ' => "o",
+        _ => return None,
+    };
+    Some(latin)
+}
+
+fn kana(c: char) -> Option<&'static str> {
+    let latin = match c {
+        'あ' => "a",
+        'い' => "i",
+        'う' => "u",
+        'え' => "e",
+        'お' => "o",
+        'か' => "ka",
+        'き' => "ki",
+        'く' => "ku",
+        'け' => "ke",
+        'こ' => "ko",
+        'が' => "ga",
+        'ぎ' => "gi",
+        'ぐ' => "gu",
+        'げ' => "ge",
+        'ご' => "go",
+        'さ' => "sa",
+        'し' => "shi",
+        'す' => "su",
+        'せ' => "se",
+        'そ' => "so",
+        'ざ' => "za",
+        'じ' => "ji",
+        'ず' => "zu",
+        'ぜ' => "ze",
+        'ぞ' => "zo",
+        'た' => "ta",
+        'ち' => "chi",
+        'つ' => "tsu",
+        'て' => "te",
+        'と' => "to",
+        'だ' => "da",
+        'ぢ' => "ji",
+        'づ' => "zu",
+        'で' => "de",
+        'ど' => "do",
+        'な' => "na",
+        'に' => "ni",
+        'ぬ' => "nu",
+        'ね' => "ne",
+        'の' => "no",
+        'は' => "ha",
+        'ひ' => "hi",
+        'ふ' => "fu",
+        'へ' => "he",
+        'ほ' => "ho",
+        'ば' => "ba",
+        'び' => "bi",
+        'ぶ' => "bu",
+        'べ' => "be",
+        'ぼ' => "bo",
+        'ぱ' => "pa",
+        'ぴ' => "pi",
+        'ぷ' => "pu",
+        'ぺ' => "pe",
+        'ぽ' => "po",
+        'ま' => "ma",
+        'み' => "mi",
+        'む' => "mu",
+        'め' => "me",
+        'も' => "mo",
+        'や' => "ya",
+        'ゆ' => "yu",
+        'よ' => "yo",
+        'ら' => "ra",
+        'り' => "ri",
+        'る' => "ru",
+        'れ' => "re",
+        'ろ' => "ro",
+        'わ' => "wa",
+        'を' => "o",
+        'ん' => "n",
+        'ゔ' => "vu",
+        _ => return None,
+    };
+    Some(latin)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cyrillic() {
+        assert_eq!(transliterate("ru", "Москва").as_deref(), Some("Moskva"));
+        assert_eq!(transliterate("ru", "щука").as_deref(), Some("shchuka"));
+        assert_eq!(transliterate("uk", "Київ").as_deref(), Some("Kyiv"));
+        assert_eq!(transliterate("sr", "љубав").as_deref(), Some("ljubav"));
+        assert_eq!(transliterate("sr", "ljubav"), None);
+    }
+
+    #[test]
+    fn test_greek_and_arabic() {
+        assert_eq!(transliterate("el", "θάλασσα").as_deref(), Some("thalassa"));
+        assert_eq!(transliterate("ar", "كتاب").as_deref(), Some("ktab"));
+    }
+
+    #[test]
+    fn test_devanagari() {
+        assert_eq!(transliterate("hi", "नमस्ते").as_deref(), Some("namaste"));
+        assert_eq!(transliterate("hi", "किताब").as_deref(), Some("kitaab"));
+    }
+
+    #[test]
+    fn test_hangul() {
+        assert_eq!(transliterate("ko", "사랑").as_deref(), Some("sarang"));
+        assert_eq!(transliterate("ko", "한국").as_deref(), Some("hanguk"));
+    }
+
+    #[test]
+    fn test_kana() {
+        assert_eq!(
+            transliterate("ja", "ありがとう").as_deref(),
+            Some("arigatou")
+        );
+        assert_eq!(transliterate("ja", "きょう").as_deref(), Some("kyou"));
+        assert_eq!(transliterate("ja", "しゃしん").as_deref(), Some("shashin"));
+        assert_eq!(transliterate("ja", "がっこう").as_deref(), Some("gakkou"));
+        assert_eq!(transliterate("ja", "コーヒー").as_deref(), Some("koohii"));
+        // Kanji need a dictionary
+        assert_eq!(transliterate("ja", "日本"), None);
+    }
+
+    #[test]
+    fn test_latin_languages() {
+        assert!(!has_transliteration("hr"));
+        assert_eq!(transliterate("hr", "kuća"), None);
+        assert_eq!(transliterate("zh", "你好"), None);
+    }
+}
@@ -0,0 +1,505 @@
+use crate::error::{AnkiDeckBuilderError, Result};
+use crate::language::frequency::{PartOfSpeech, Word};
+use crate::language::glossary::Glossary;
+use crate::language::text::normalize_text;
+use crate::language::translator::Translator;
+use serde::Serialize;
+use std::fmt;
+
+/// Fragments of the error text MyMemory sometimes returns as a "translation"
+const PLACEHOLDER_MARKERS: &[&str] = &[
+    "MYMEMORY WARNING",
+    "QUERY LENGTH LIMIT",
+    "PLEASE SELECT TWO DISTINCT LANGUAGES",
+    "INVALID LANGUAGE PAIR",
+    "NO QUERY SPECIFIED",
+];
+
+/// Round trips less similar than this to the original word are flagged
+const MIN_ROUND_TRIP_SIMILARITY: f64 = 0.5;
+
+/// Why a translation was not trusted
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "issue", rename_all = "snake_case")]
+pub enum TranslationIssue {
+    /// The provider returned nothing
+    Empty,
+    /// The provider returned the source word unchanged
+    Unchanged,
+    /// The provider returned an error message or symbols instead of a word
+    Placeholder,
+    /// Translating back does not lead to the original word
+    RoundTripMismatch { back_translation: String },
+}
+
+impl fmt::Display for TranslationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "empty translation"),
+            Self::Unchanged => write!(f, "returned unchanged"),
+            Self::Placeholder => write!(f, "placeholder text"),
+            Self::RoundTripMismatch { back_translation } => {
+                write!(f, "translates back as '{}'", back_translation)
+            }
+        }
+    }
+}
+
+/// Spot translations that are obviously unusable
+pub fn check_translation(source: &str, translation: &str) -> Option<TranslationIssue> {
+    let trimmed = translation.trim();
+    if trimmed.is_empty() {
+        return Some(TranslationIssue::Empty);
+    }
+
+    let upper = trimmed.to_uppercase();
+    if PLACEHOLDER_MARKERS.iter().any(|m| upper.contains(m))
+        || !trimmed.chars().any(char::is_alphabetic)
+    {
+        return Some(TranslationIssue::Placeholder);
+    }
+
+    if trimmed.to_lowercase() == source.trim().to_lowercase() {
+        return Some(TranslationIssue::Unchanged);
+    }
+
+    None
+}
+
+/// How close a back-translation comes to the original word, from 0 to 1.
+/// Providers often answer with several alternatives ("día, jornada"), so the
+/// best-matching alternative counts
+pub fn round_trip_similarity(original: &str, back_translation: &str) -> f64 {
+    let original = original.trim().to_lowercase();
+    back_translation
+        .split([',', ';', '/'])
+        .map(|alternative| similarity(&original, &alternative.trim().to_lowercase()))
+        .fold(0.0, f64::max)
+}
+
+/// 1 minus the edit distance relative to the longer word
+fn similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    1.0 - previous[b.len()] as f64 / longest as f64
+}
+
+/// A translation together with the provider that produced it and, if no
+/// provider gave a trustworthy answer, what is wrong with it
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckedTranslation {
+    pub text: String,
+    pub provider: &'static str,
+    pub issue: Option<TranslationIssue>,
+}
+
+/// Which providers of a chain translate one language pair
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PairSupport {
+    pub from: String,
+    pub to: String,
+    /// Providers that will be asked, in order
+    pub providers: Vec<&'static str>,
+    /// Providers of the chain that can't translate the pair and are skipped
+    pub skipped: Vec<&'static str>,
+}
+
+/// Asks each provider in turn until one gives a translation that passes the
+/// checks. When none does, the first answer is returned with its issue so
+/// the word can be reviewed by hand. Words in the glossary never reach the
+/// providers
+pub struct TranslationChain {
+    providers: Vec<Box<dyn Translator>>,
+    back_translate: bool,
+    glossary: Glossary,
+    /// Translators outside the chain, named when a pair has no provider
+    alternatives: Vec<Box<dyn Translator>>,
+}
+
+impl TranslationChain {
+    pub fn new(providers: Vec<Box<dyn Translator>>) -> Self {
+        Self {
+            providers,
+            back_translate: false,
+            glossary: Glossary::default(),
+            alternatives: Vec::new(),
+        }
+    }
+
+    /// Suggest these when none of the providers translate a pair
+    pub fn with_alternatives(mut self, alternatives: Vec<Box<dyn Translator>>) -> Self {
+        self.alternatives = alternatives;
+        self
+    }
+
+    /// Take your own translations from `glossary` before asking any provider
+    pub fn with_glossary(mut self, glossary: Glossary) -> Self {
+        self.glossary = glossary;
+        self
+    }
+
+    /// Also translate each answer back and require it to lead to the original word
+    pub fn with_back_translation(mut self, back_translate: bool) -> Self {
+        self.back_translate = back_translate;
+        self
+    }
+
+    /// Names of the providers, in the order they are asked
+    pub fn provider_names(&self) -> Vec<&'static str> {
+        self.providers.iter().map(|p| p.name()).collect()
+    }
+
+    pub async fn translate(&self, text: &str, from: &str, to: &str) -> Result<CheckedTranslation> {
+        self.translate_as(text, None, from, to).await
+    }
+
+    /// Let providers that answer many words per request translate `words`
+    /// into each of `to` ahead of time, so asking for them one by one is
+    /// served from their caches. Words in the glossary are left out. A
+    /// failure only means the words are asked for one by one
+    pub async fn prepare(&self, words: &[Word], from: &str, to: &[String]) {
+        for to in to {
+            let texts: Vec<String> = words
+                .iter()
+                .filter(|word| {
+                    self.glossary
+                        .lookup(&word.text, Some(&word.pos), from, to)
+                        .is_none()
+                })
+                .map(|word| normalize_text(&word.text))
+                .collect();
+            for provider in self.providers.iter().filter(|p| p.supports_pair(from, to)) {
+                if let Err(e) = provider.prepare(&texts, from, to).await {
+                    tracing::warn!(
+                        "{} failed to translate ahead into {}, asking word by word: {}",
+                        provider.name(),
+                        to,
+                        e
+                    );
+                }
+            }
+        }
+    }
+
+    /// Check before translating anything that some provider translates
+    /// from `from` into each of `to`. Providers that can say which pairs
+    /// they offer are asked first; the others go by their built-in lists.
+    /// A pair no provider supports is an error naming those that would
+    pub async fn check_pairs(&self, from: &str, to: &[String]) -> Result<Vec<PairSupport>> {
+        for provider in &self.providers {
+            if let Err(e) = provider.probe_languages().await {
+                tracing::warn!(
+                    "Could not ask {} which languages it offers, going by its built-in list: {}",
+                    provider.name(),
+                    e
+                );
+            }
+        }
+
+        let mut pairs = Vec::new();
+        for to in to {
+            let (providers, skipped): (Vec<_>, Vec<_>) = self
+                .providers
+                .iter()
+                .partition(|p| p.supports_pair(from, to));
+            if providers.is_empty() {
+                return Err(AnkiDeckBuilderError::UnsupportedLanguage(format!(
+                    "{} → {}: {}",
+                    from,
+                    to,
+                    self.unsupported_pair_hint(from, to)
+                )));
+            }
+            pairs.push(PairSupport {
+                from: from.to_string(),
+                to: to.clone(),
+                providers: providers.iter().map(|p| p.name()).collect(),
+                skipped: skipped.iter().map(|p| p.name()).collect(),
+            });
+        }
+        Ok(pairs)
+    }
+
+    /// What could translate a pair none of the chain's providers does
+    fn unsupported_pair_hint(&self, from: &str, to: &str) -> String {
+        let names = self.provider_names();
+        let others: Vec<&str> = self
+            .alternatives
+            .iter()
+            .filter(|t| !names.contains(&t.name()) && t.supports_pair(from, to))
+            .map(|t| t.name())
+            .collect();
+        if others.is_empty() {
+            format!(
+                "none of the translators ({}) offer this pair; add its words to a glossary with `config glossary add`",
+                names.join(", ")
+            )
+        } else {
+            format!(
+                "{} can't translate it, but {} can (see --translator and --libretranslate-url)",
+                names.join(", "),
+                others.join(", ")
+            )
+        }
+    }
+
+    /// Translate a word used as `pos`, which picks among its glossary entries
+    pub async fn translate_as(
+        &self,
+        text: &str,
+        pos: Option<&PartOfSpeech>,
+        from: &str,
+        to: &str,
+    ) -> Result<CheckedTranslation> {
+        let text: &str = &normalize_text(text);
+        if let Some(translation) = self.glossary.lookup(text, pos, from, to) {
+            return Ok(CheckedTranslation {
+                text: translation.to_string(),
+                provider: "glossary",
+                issue: None,
+            });
+        }
+
+        let mut flagged: Option<CheckedTranslation> = None;
+        let mut last_error = None;
+
+        for provider in self.providers.iter().filter(|p| p.supports_pair(from, to)) {
+            let translation = match provider.translate(text, from, to).await {
+                Ok(translation) => normalize_text(&translation),
+                Err(e) => {
+                    tracing::warn!("{} failed to translate '{}': {}", provider.name(), text, e);
+                    last_error = Some(e);
+                    continue;
+                }
+            };
+
+            let issue = match check_translation(text, &translation) {
+                None if self.back_translate => {
+                    self.check_round_trip(provider.as_ref(), text, &translation, from, to)
+                        .await
+                }
+                issue => issue,
+            };
+
+            let checked = CheckedTranslation {
+                text: translation,
+                provider: provider.name(),
+                issue,
+            };
+            match &checked.issue {
+                None => return Ok(checked),
+                Some(issue) => {
+                    tracing::debug!(
+                        "{} translation of '{}' rejected: {}",
+                        provider.name(),
+                        text,
+                        issue
+                    );
+                    flagged.get_or_insert(checked);
+                }
+            }
+        }
+
+        flagged.ok_or_else(|| {
+            last_error.unwrap_or_else(|| {
+                AnkiDeckBuilderError::TranslationError(format!(
+                    "No translator supports {} → {}",
+                    from, to
+                ))
+            })
+        })
+    }
+
+    async fn check_round_trip(
+        &self,
+        provider: &dyn Translator,
+        text: &str,
+        translation: &str,
+        from: &str,
+        to: &str,
+    ) -> Option<TranslationIssue> {
+        // A failed back-translation says nothing about the forward one
+        let back_translation = match provider.translate(translation, to, from).await {
+            Ok(back) => back,
+            Err(e) => {
+                tracing::debug!("Back-translation of '{}' failed: {}", translation, e);
+                return None;
+            }
+        };
+
+        (round_trip_similarity(text, &back_translation) < MIN_ROUND_TRIP_SIMILARITY)
+            .then_some(TranslationIssue::RoundTripMismatch { back_translation })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::HttpSettings;
+    use async_trait::async_trait;
+    use std::collections::HashMap;
+
+    struct FixedTranslator {
+        name: &'static str,
+        answers: HashMap<&'static str, &'static str>,
+    }
+
+    #[async_trait]
+    impl Translator for FixedTranslator {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        async fn translate(&self, text: &str, _from: &str, _to: &str) -> Result<String> {
+            self.answers
+                .get(text)
+                .map(|t| t.to_string())
+                .ok_or_else(|| AnkiDeckBuilderError::TranslationError("unknown".to_string()))
+        }
+    }
+
+    fn fixed(name: &'static str, answers: &[(&'static str, &'static str)]) -> Box<dyn Translator> {
+        Box::new(FixedTranslator {
+            name,
+            answers: answers.iter().copied().collect(),
+        })
+    }
+
+    #[test]
+    fn test_check_translation() {
+        assert_eq!(check_translation("dan", "día"), None);
+        assert_eq!(
+            check_translation("dan", "  "),
+            Some(TranslationIssue::Empty)
+        );
+        assert_eq!(
+            check_translation("dan", "Dan"),
+            Some(TranslationIssue::Unchanged)
+        );
+        assert_eq!(
+            check_translation(
+                "dan",
+                "MYMEMORY WARNING: YOU USED ALL AVAILABLE FREE TRANSLATIONS"
+            ),
+            Some(TranslationIssue::Placeholder)
+        );
+        assert_eq!(
+            check_translation("dan", "???"),
+            Some(TranslationIssue::Placeholder)
+        );
+    }
+
+    #[test]
+    fn test_round_trip_similarity() {
+        assert_eq!(round_trip_similarity("dan", "Dan"), 1.0);
+        assert_eq!(round_trip_similarity("dan", "vrijeme, dan"), 1.0);
+        assert!(round_trip_similarity("kuća", "kuca") >= 0.75);
+        assert!(round_trip_similarity("dan", "noć") < MIN_ROUND_TRIP_SIMILARITY);
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_to_next_provider() {
+        let chain = TranslationChain::new(vec![
+            fixed("first", &[("dan", "dan")]),
+            fixed("second", &[("dan", "día")]),
+        ]);
+
+        let checked = chain.translate("dan", "hr", "es").await.unwrap();
+        assert_eq!(checked.text, "día");
+        assert_eq!(checked.provider, "second");
+        assert_eq!(checked.issue, None);
+    }
+
+    #[tokio::test]
+    async fn test_flags_unresolved_word() {
+        let chain = TranslationChain::new(vec![
+            fixed("first", &[("dan", "dan")]),
+            fixed("second", &[]),
+        ]);
+
+        let checked = chain.translate("dan", "hr", "es").await.unwrap();
+        assert_eq!(checked.provider, "first");
+        assert_eq!(checked.issue, Some(TranslationIssue::Unchanged));
+
+        let failing = TranslationChain::new(vec![fixed("only", &[])]);
+        assert!(failing.translate("dan", "hr", "es").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_back_translation() {
+        let chain = TranslationChain::new(vec![
+            fixed("first", &[("dan", "noche"), ("noche", "noć")]),
+            fixed("second", &[("dan", "día"), ("día", "dan")]),
+        ])
+        .with_back_translation(true);
+
+        let checked = chain.translate("dan", "hr", "es").await.unwrap();
+        assert_eq!(checked.text, "día");
+        assert_eq!(checked.provider, "second");
+    }
+
+    #[tokio::test]
+    async fn test_glossary_comes_first() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        std::fs::write(temp_dir.path().join("hr-es.csv"), "dan,jornada\n").unwrap();
+        let chain = TranslationChain::new(vec![fixed("only", &[("dan", "día")])])
+            .with_glossary(Glossary::load(temp_dir.path()).unwrap());
+
+        let checked = chain.translate("dan", "hr", "es").await.unwrap();
+        assert_eq!(checked.text, "jornada");
+        assert_eq!(checked.provider, "glossary");
+        let checked = chain.translate("dan", "hr", "en").await.unwrap();
+        assert_eq!(checked.provider, "only");
+    }
+
+    #[tokio::test]
+    async fn test_check_pairs() {
+        use crate::config::ServiceSettings;
+        use crate::language::{known_translators, LibreTranslateClient};
+
+        // Unreachable, so the built-in list is used
+        let libre = || {
+            Box::new(
+                LibreTranslateClient::new(
+                    "http://127.0.0.1:1".to_string(),
+                    None,
+                    &HttpSettings::default(),
+                )
+                .unwrap(),
+            ) as Box<dyn Translator>
+        };
+        let chain = TranslationChain::new(vec![fixed("first", &[]), libre()]);
+        let pairs = chain
+            .check_pairs("hr", &["es".to_string(), "en".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(pairs.len(), 2);
+        assert_eq!(pairs[0].providers, ["first"]);
+        assert_eq!(pairs[0].skipped, ["libretranslate"]);
+
+        let known = known_translators(&ServiceSettings::default()).unwrap();
+        let error = TranslationChain::new(vec![libre()])
+            .with_alternatives(known)
+            .check_pairs("hr", &["es".to_string()])
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("hr → es"));
+        assert!(error.contains("mymemory, google can"));
+    }
+}
@@ -1,10 +1,44 @@
 pub mod ankiweb;
+pub mod audio;
+pub mod builder;
+pub mod cache_file;
+pub mod cancel;
+pub mod changelog;
 pub mod cli;
 pub mod config;
+pub mod config_schema;
+pub mod deadline;
+pub mod description;
+pub mod diff;
+pub mod doctor;
+pub mod enrich;
 pub mod error;
+pub mod estimate;
+pub mod events;
+pub mod external;
+pub mod fix;
+pub mod growth;
+pub mod health;
+pub mod history;
+pub mod http;
+pub mod i18n;
+pub mod images;
 pub mod language;
+pub mod logging;
+pub mod manifest;
+pub mod naming;
+pub mod output;
+pub mod pipeline;
+pub mod prefetch;
+pub mod report;
+pub mod server;
+pub mod session;
+pub mod sink;
+pub mod state;
+pub mod tui;
 
 // Re-export commonly used types
-pub use ankiweb::{AnkiClient, Note};
+pub use ankiweb::{AnkiBackend, AnkiClient, Note};
+pub use builder::{DeckBuilder, DeckSpec};
 pub use config::Config;
 pub use error::{AnkiDeckBuilderError, Result};
@@ -1,18 +1,10 @@
 use anki_deck_builder::cli;
-use anyhow::Result;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 #[tokio::main]
-async fn main() -> Result<()> {
-    // Initialize logging
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "anki_deck_builder=info".into()),
-        )
-        .with(tracing_subscriber::fmt::layer())
-        .init();
-
-    // Parse CLI arguments and execute the command
-    cli::run().await
+async fn main() {
+    // Parse CLI arguments, set up logging and execute the command. A failed
+    // command has reported its error and gives the exit code
+    if let Err(code) = cli::run().await {
+        std::process::exit(code);
+    }
 }
@@ -0,0 +1,463 @@
+use crate::error::{AnkiDeckBuilderError, Result};
+use crate::language::{Attribution, PartOfSpeech, Word};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// What happened to a word's notes during a run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WordStatus {
+    /// At least one note was added
+    Added,
+    /// Every note was already in the deck
+    Existing,
+    /// At least one note could not be added
+    Failed,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub translation: String,
+    pub status: WordStatus,
+    /// Missing from manifests written before decks could be rebuilt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pos: Option<PartOfSpeech>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rank: Option<usize>,
+}
+
+/// How a deck was built, so `rebuild` can make the same deck again
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BuildRecipe {
+    /// Version of anki-deck-builder that built the deck
+    pub tool_version: String,
+    pub target_language: String,
+    pub base_languages: Vec<String>,
+    /// Hash of the words and translations, to tell whether they changed
+    /// since the manifest was written
+    pub word_list_hash: String,
+    /// Frequency list the words were picked from, e.g. "opensubtitles 50k"
+    pub frequency_source: String,
+    /// Translation providers, in the order they were asked
+    pub translators: Vec<String>,
+    /// `create` options, with the languages and deck name filled in
+    pub options: serde_json::Value,
+}
+
+/// Record of the words a deck was built from, kept between runs so the next
+/// run can report what changed, and with the recipe of the latest run so the
+/// deck can be rebuilt elsewhere
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RunManifest {
+    pub deck_name: String,
+    /// Counts the runs of the deck from 1; 0 in manifests written before
+    /// decks had versions
+    #[serde(default)]
+    pub version: u32,
+    pub words: BTreeMap<String, ManifestEntry>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recipe: Option<BuildRecipe>,
+    /// Credits of the frequency lists the words came from, to go with the
+    /// deck wherever it is shared
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attributions: Vec<Attribution>,
+}
+
+/// Changes between a run and the previous manifest for the same deck
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ManifestDiff {
+    /// Words added to the deck for the first time
+    pub added: Vec<String>,
+    /// Words whose translation differs from last time, as (word, old, new)
+    pub updated: Vec<(String, String, String)>,
+    /// Words that failed last time and are in the deck now
+    pub resolved: Vec<String>,
+    /// Words that failed this run
+    pub failed: Vec<String>,
+}
+
+/// A manifest word found by `where-is`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WordMatch {
+    pub deck_name: String,
+    pub word: String,
+    pub translation: String,
+    pub status: WordStatus,
+    /// Whether the search matched the translation rather than the word
+    pub in_translation: bool,
+}
+
+impl ManifestDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.updated.is_empty()
+            && self.resolved.is_empty()
+            && self.failed.is_empty()
+    }
+}
+
+impl RunManifest {
+    pub fn new(deck_name: &str) -> Self {
+        Self {
+            deck_name: deck_name.to_string(),
+            version: 0,
+            words: BTreeMap::new(),
+            recipe: None,
+            attributions: Vec::new(),
+        }
+    }
+
+    pub fn with_version(mut self, version: u32) -> Self {
+        self.version = version;
+        self
+    }
+
+    pub fn with_recipe(mut self, recipe: Option<BuildRecipe>) -> Self {
+        self.recipe = recipe;
+        self
+    }
+
+    pub fn with_attributions(mut self, attributions: Vec<Attribution>) -> Self {
+        self.attributions = attributions;
+        self
+    }
+
+    /// Manifest file for a deck
+    pub fn path(manifest_dir: &Path, deck_name: &str) -> PathBuf {
+        let slug: String = deck_name
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '_' })
+            .collect();
+        manifest_dir.join(format!("{}.json", slug))
+    }
+
+    /// Load the manifest of the previous run, if any
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content = std::fs::read_to_string(path)?;
+        Ok(Some(serde_json::from_str(&content)?))
+    }
+
+    /// Save the manifest to disk
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+
+    pub fn record(&mut self, word: &Word, translation: &str, status: WordStatus) {
+        self.words.insert(
+            word.text.clone(),
+            ManifestEntry {
+                translation: translation.to_string(),
+                status,
+                pos: Some(word.pos.clone()),
+                rank: Some(word.rank),
+            },
+        );
+    }
+
+    /// CRC32 of every word with its part of speech, rank, and translation
+    pub fn word_list_hash(&self) -> String {
+        let mut hasher = crc32fast::Hasher::new();
+        for (word, entry) in &self.words {
+            let pos = entry.pos.as_ref().map_or("", PartOfSpeech::name);
+            let rank = entry.rank.unwrap_or_default();
+            hasher
+                .update(format!("{}\t{}\t{}\t{}\n", word, pos, rank, entry.translation).as_bytes());
+        }
+        format!("{:08x}", hasher.finalize())
+    }
+
+    /// Stamp the recipe with the hash of the words as they are now
+    pub fn sealed(mut self) -> Self {
+        let hash = self.word_list_hash();
+        if let Some(recipe) = &mut self.recipe {
+            recipe.word_list_hash = hash;
+        }
+        self
+    }
+
+    /// Whether the words still match the hash in the recipe
+    pub fn is_intact(&self) -> bool {
+        self.recipe
+            .as_ref()
+            .is_some_and(|recipe| recipe.word_list_hash == self.word_list_hash())
+    }
+
+    /// Words to add again when rebuilding the deck, most frequent first
+    pub fn rebuild_words(&self) -> Result<Vec<Word>> {
+        let mut words = Vec::with_capacity(self.words.len());
+        for (text, entry) in &self.words {
+            let (Some(pos), Some(rank)) = (&entry.pos, entry.rank) else {
+                return Err(AnkiDeckBuilderError::ConfigurationError(format!(
+                    "The manifest for '{}' predates rebuilding; run create once more to update it",
+                    self.deck_name
+                )));
+            };
+            words.push(Word::new(text.clone(), pos.clone(), rank));
+        }
+        words.sort_by_key(|word| word.rank);
+        Ok(words)
+    }
+
+    /// Recorded translation of each word
+    pub fn translations(&self) -> BTreeMap<String, String> {
+        self.words
+            .iter()
+            .map(|(word, entry)| (word.clone(), entry.translation.clone()))
+            .collect()
+    }
+
+    /// Every manifest in a directory. Files that can't be read are skipped
+    /// with a warning
+    pub fn load_all(manifest_dir: &Path) -> Result<Vec<Self>> {
+        let Ok(files) = std::fs::read_dir(manifest_dir) else {
+            return Ok(Vec::new());
+        };
+        let mut manifests = Vec::new();
+        for file in files {
+            let path = file?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                match Self::load(&path) {
+                    Ok(Some(manifest)) => manifests.push(manifest),
+                    Ok(None) => {}
+                    Err(e) => tracing::warn!("Skipping manifest {}: {}", path.display(), e),
+                }
+            }
+        }
+        manifests.sort_by(|a, b| a.deck_name.cmp(&b.deck_name));
+        Ok(manifests)
+    }
+
+    /// Words that are `text`, or whose translation is, ignoring case. Each
+    /// line and comma-separated alternative of a translation counts
+    pub fn find(&self, text: &str) -> Vec<WordMatch> {
+        let text = text.trim().to_lowercase();
+        self.words
+            .iter()
+            .filter_map(|(word, entry)| {
+                let in_translation = if word.to_lowercase() == text {
+                    false
+                } else if translation_alternatives(&entry.translation).any(|t| t == text) {
+                    true
+                } else {
+                    return None;
+                };
+                Some(WordMatch {
+                    deck_name: self.deck_name.clone(),
+                    word: word.clone(),
+                    translation: entry.translation.clone(),
+                    status: entry.status,
+                    in_translation,
+                })
+            })
+            .collect()
+    }
+
+    /// Compare this run against the previous manifest
+    pub fn diff(&self, previous: Option<&RunManifest>) -> ManifestDiff {
+        let mut diff = ManifestDiff::default();
+
+        for (word, entry) in &self.words {
+            let before = previous.and_then(|p| p.words.get(word));
+
+            if entry.status == WordStatus::Failed {
+                diff.failed.push(word.clone());
+                continue;
+            }
+
+            match before {
+                Some(before) if before.status == WordStatus::Failed => {
+                    diff.resolved.push(word.clone());
+                }
+                Some(before) if before.translation != entry.translation => {
+                    diff.updated.push((
+                        word.clone(),
+                        before.translation.clone(),
+                        entry.translation.clone(),
+                    ));
+                }
+                None if entry.status == WordStatus::Added => diff.added.push(word.clone()),
+                _ => {}
+            }
+        }
+
+        diff
+    }
+
+    /// Combine with the previous manifest, so words a partial run never
+    /// reached are still remembered. A run without a recipe (a retry) keeps
+    /// the previous one
+    pub fn merged_with(mut self, previous: Option<RunManifest>) -> Self {
+        if let Some(previous) = previous {
+            for (word, entry) in previous.words {
+                self.words.entry(word).or_insert(entry);
+            }
+            self.recipe = self.recipe.or(previous.recipe);
+            for attribution in previous.attributions {
+                if !self.attributions.contains(&attribution) {
+                    self.attributions.push(attribution);
+                }
+            }
+        }
+        self
+    }
+}
+
+/// Lowercase alternatives of a card translation: "es: día, jornada<br>en: day"
+/// gives "día", "jornada" and "day"
+fn translation_alternatives(translation: &str) -> impl Iterator<Item = String> + '_ {
+    translation
+        .split("<br>")
+        .map(|line| match line.split_once(": ") {
+            Some((code, rest))
+                if code.len() <= 3 && code.chars().all(|c| c.is_ascii_lowercase()) =>
+            {
+                rest
+            }
+            _ => line,
+        })
+        .flat_map(|line| line.split([',', ';']))
+        .map(|alternative| alternative.trim().to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn word(text: &str, rank: usize) -> Word {
+        Word::new(text.to_string(), PartOfSpeech::Noun, rank)
+    }
+
+    fn previous() -> RunManifest {
+        let mut manifest = RunManifest::new("Deck");
+        manifest.record(&word("dan", 3), "día", WordStatus::Added);
+        manifest.record(&word("kuća", 7), "casa", WordStatus::Added);
+        manifest.record(&word("biti", 1), "ser", WordStatus::Failed);
+        manifest
+    }
+
+    #[test]
+    fn test_diff_against_previous_run() {
+        let mut current = RunManifest::new("Deck");
+        current.record(&word("dan", 3), "día", WordStatus::Existing);
+        current.record(&word("kuća", 7), "hogar", WordStatus::Existing);
+        current.record(&word("biti", 1), "ser", WordStatus::Added);
+        current.record(&word("voda", 9), "agua", WordStatus::Added);
+        current.record(&word("grad", 12), "ciudad", WordStatus::Failed);
+
+        let diff = current.diff(Some(&previous()));
+
+        assert_eq!(diff.added, vec!["voda"]);
+        assert_eq!(
+            diff.updated,
+            vec![("kuća".to_string(), "casa".to_string(), "hogar".to_string())]
+        );
+        assert_eq!(diff.resolved, vec!["biti"]);
+        assert_eq!(diff.failed, vec!["grad"]);
+    }
+
+    #[test]
+    fn test_first_run_lists_everything_added() {
+        let diff = previous().diff(None);
+        assert_eq!(diff.added, vec!["dan", "kuća"]);
+        assert_eq!(diff.failed, vec!["biti"]);
+    }
+
+    #[test]
+    fn test_save_load_and_merge() {
+        let dir = tempdir().unwrap();
+        let path = RunManifest::path(dir.path(), "Croatian: Top 100");
+        assert!(path.ends_with("Croatian__Top_100.json"));
+
+        previous().save(&path).unwrap();
+        let loaded = RunManifest::load(&path).unwrap().unwrap();
+
+        let mut partial = RunManifest::new("Deck");
+        partial.record(&word("biti", 1), "ser", WordStatus::Added);
+        let merged = partial.merged_with(Some(loaded));
+
+        assert_eq!(merged.words.len(), 3);
+        assert_eq!(merged.words["biti"].status, WordStatus::Added);
+    }
+
+    #[test]
+    fn test_recipe_and_rebuild_words() {
+        let recipe = BuildRecipe {
+            tool_version: "0.1.0".to_string(),
+            target_language: "hr".to_string(),
+            base_languages: vec!["es".to_string()],
+            word_list_hash: String::new(),
+            frequency_source: "opensubtitles 50k".to_string(),
+            translators: vec!["MyMemory".to_string()],
+            options: serde_json::json!({ "bidirectional": false }),
+        };
+        let manifest = previous().with_recipe(Some(recipe)).sealed();
+        assert!(manifest.is_intact());
+
+        // A retry has no recipe of its own and keeps the previous one
+        let retried = RunManifest::new("Deck").merged_with(Some(manifest.clone()));
+        assert_eq!(retried.recipe, manifest.recipe);
+
+        let words: Vec<_> = manifest
+            .rebuild_words()
+            .unwrap()
+            .into_iter()
+            .map(|w| w.text)
+            .collect();
+        assert_eq!(words, vec!["biti", "dan", "kuća"]);
+        assert_eq!(manifest.translations()["kuća"], "casa");
+
+        let mut edited = manifest.clone();
+        edited.words.get_mut("dan").unwrap().translation = "noche".to_string();
+        assert!(!edited.is_intact());
+
+        // Manifests from before rebuilding existed have no part of speech
+        let old: RunManifest = serde_json::from_value(serde_json::json!({
+            "deck_name": "Deck",
+            "words": { "dan": { "translation": "día", "status": "added" } },
+        }))
+        .unwrap();
+        assert!(old.recipe.is_none());
+        assert!(old.rebuild_words().is_err());
+    }
+
+    #[test]
+    fn test_find_words_across_manifests() {
+        let dir = tempdir().unwrap();
+        previous()
+            .save(&RunManifest::path(dir.path(), "Deck"))
+            .unwrap();
+        let mut other = RunManifest::new("Other");
+        other.record(
+            &word("dan", 1),
+            "es: día, jornada<br>en: day",
+            WordStatus::Added,
+        );
+        other.save(&RunManifest::path(dir.path(), "Other")).unwrap();
+
+        let manifests = RunManifest::load_all(dir.path()).unwrap();
+        let found: Vec<WordMatch> = manifests.iter().flat_map(|m| m.find("Dan")).collect();
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].deck_name, "Deck");
+        assert!(!found[1].in_translation);
+
+        let found = manifests[1].find("jornada");
+        assert_eq!(found[0].word, "dan");
+        assert!(found[0].in_translation);
+        assert!(manifests[1].find("es").is_empty());
+        assert!(RunManifest::load_all(&dir.path().join("missing"))
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,179 @@
+//! Deck names made from templates such as "{target} for {base} speakers — {date}"
+
+use crate::error::{AnkiDeckBuilderError, Result};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Placeholders a deck name template can use
+pub const PLACEHOLDERS: &[&str] = &[
+    "target",
+    "target_code",
+    "base",
+    "base_code",
+    "words",
+    "source",
+    "size",
+    "date",
+];
+
+/// Name of a deck of frequent words when no template is given
+pub const DEFAULT_TEMPLATE: &str = "{target} → {base} (Top {words} Words)";
+
+/// Name of a starter deck when no template is given
+pub const STARTER_TEMPLATE: &str = "{target} → {base} (Starter)";
+
+/// What the placeholders of a template stand for
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeckNameValues {
+    /// Target language name, e.g. "Croatian"
+    pub target: String,
+    pub target_code: String,
+    /// Base language names, e.g. "Spanish, English"
+    pub base: String,
+    /// Base language codes, e.g. "es+en"
+    pub base_code: String,
+    /// Number of words the deck is built from
+    pub words: usize,
+    /// Where the words come from, e.g. "opensubtitles" or "starter"
+    pub source: String,
+    /// Frequency list size, e.g. "50k"
+    pub size: String,
+    /// Day the deck is built, as YYYY-MM-DD
+    pub date: String,
+}
+
+impl DeckNameValues {
+    fn get(&self, placeholder: &str) -> Option<String> {
+        Some(match placeholder {
+            "target" => self.target.clone(),
+            "target_code" => self.target_code.clone(),
+            "base" => self.base.clone(),
+            "base_code" => self.base_code.clone(),
+            "words" => self.words.to_string(),
+            "source" => self.source.clone(),
+            "size" => self.size.clone(),
+            "date" => self.date.clone(),
+            _ => return None,
+        })
+    }
+}
+
+/// Fill in a template's placeholders. Unknown placeholders are an error
+/// rather than ending up in the deck name
+pub fn render_deck_name(template: &str, values: &DeckNameValues) -> Result<String> {
+    fill_template(template, "deck name", PLACEHOLDERS, |placeholder| {
+        values.get(placeholder)
+    })
+    .map(|name| name.trim().to_string())
+}
+
+/// Replace each `{placeholder}` of a template with its value. One without a
+/// value is an error naming what the template is for and the `known` ones
+pub(crate) fn fill_template(
+    template: &str,
+    what: &str,
+    known: &[&str],
+    value: impl Fn(&str) -> Option<String>,
+) -> Result<String> {
+    let mut filled = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        filled.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('}') else {
+            break;
+        };
+        let placeholder = &rest[start + 1..start + end];
+        let value = value(placeholder).ok_or_else(|| {
+            AnkiDeckBuilderError::ConfigurationError(format!(
+                "unknown placeholder {{{}}} in {} template '{}' (known: {})",
+                placeholder,
+                what,
+                template,
+                known.join(", ")
+            ))
+        })?;
+        filled.push_str(&value);
+        rest = &rest[start + end + 1..];
+    }
+    filled.push_str(rest);
+    Ok(filled)
+}
+
+/// Today's date in UTC, as YYYY-MM-DD
+pub fn today() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let (year, month, day) = civil_date((secs / 86_400) as i64);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// A time in seconds since the Unix epoch, as YYYY-MM-DD HH:MM in UTC
+pub fn format_timestamp(secs: u64) -> String {
+    let (year, month, day) = civil_date((secs / 86_400) as i64);
+    let minutes = secs % 86_400 / 60;
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        year,
+        month,
+        day,
+        minutes / 60,
+        minutes % 60
+    )
+}
+
+/// Calendar date of a number of days since 1970-01-01 (Howard Hinnant's
+/// `civil_from_days`)
+fn civil_date(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_deck_name() {
+        let values = DeckNameValues {
+            target: "Croatian".to_string(),
+            target_code: "hr".to_string(),
+            base: "Spanish".to_string(),
+            base_code: "es".to_string(),
+            words: 800,
+            source: "opensubtitles".to_string(),
+            size: "50k".to_string(),
+            date: "2024-03-01".to_string(),
+        };
+        assert_eq!(
+            render_deck_name(DEFAULT_TEMPLATE, &values).unwrap(),
+            "Croatian → Spanish (Top 800 Words)"
+        );
+        assert_eq!(
+            render_deck_name("{target} for {base} speakers — {date}", &values).unwrap(),
+            "Croatian for Spanish speakers — 2024-03-01"
+        );
+        assert_eq!(
+            render_deck_name("{target_code}::{source} {size}", &values).unwrap(),
+            "hr::opensubtitles 50k"
+        );
+        assert!(render_deck_name("{target} {month}", &values).is_err());
+    }
+
+    #[test]
+    fn test_civil_date() {
+        assert_eq!(civil_date(0), (1970, 1, 1));
+        assert_eq!(civil_date(19_783), (2024, 3, 1));
+        assert_eq!(civil_date(11_016), (2000, 2, 29));
+        assert_eq!(format_timestamp(1_709_301_900), "2024-03-01 14:05");
+        assert_eq!(today().len(), 10);
+    }
+}
@@ -0,0 +1,335 @@
+use crate::error::Result;
+use crate::events::{BuildEvent, ReviewReply, ReviewRequest};
+use crate::i18n::Locale;
+use crate::language::PartOfSpeech;
+use dialoguer::theme::{ColorfulTheme, SimpleTheme, Theme};
+use indicatif::ProgressStyle;
+use serde::Serialize;
+use std::borrow::Cow;
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
+
+/// Symbols with a meaning kept in plain output; other emoji are dropped
+const PLAIN_SYMBOLS: &[(char, &str)] = &[
+    ('✅', "[ok]"),
+    ('✓', "[ok]"),
+    ('❌', "[error]"),
+    ('✗', "[x]"),
+    ('⚠', "[warn]"),
+    ('ℹ', "[info]"),
+    ('💡', "[tip]"),
+    ('⏹', "[stopped]"),
+    ('⏱', "[time]"),
+    ('⏭', "[skip]"),
+    ('⏸', "[paused]"),
+    ('➕', "+"),
+    ('➖', "-"),
+    ('→', "->"),
+    ('↻', "*"),
+    ('…', "..."),
+    ('≠', "!="),
+    ('“', "\""),
+    ('”', "\""),
+    ('—', "--"),
+    ('–', "-"),
+    ('└', "`-"),
+    ('─', "-"),
+    ('▏', "|"),
+    ('▶', ">"),
+];
+
+/// How the CLI writes to the terminal: human-readable text or JSON
+/// results, in which language, with emoji or their ASCII stand-ins, and
+/// whether a dashboard takes the output instead. Set up once from the
+/// command line and handed to each command
+#[derive(Debug, Clone)]
+pub struct Console {
+    json: bool,
+    emoji: bool,
+    locale: Locale,
+    /// Where build events go. While set, `say!` output is sent there too
+    /// instead of being printed, so it can't scribble over a full-screen view
+    events: Option<Sender<BuildEvent>>,
+    /// Put before each line, to tell apart runs printing at once
+    prefix: Option<String>,
+    /// Where JSON results are kept instead of printed, for a command that
+    /// combines those of several runs into one document
+    results: Option<Arc<Mutex<Vec<serde_json::Value>>>>,
+}
+
+impl Default for Console {
+    fn default() -> Self {
+        Self {
+            json: false,
+            emoji: true,
+            locale: Locale::default(),
+            events: None,
+            prefix: None,
+            results: None,
+        }
+    }
+}
+
+impl Console {
+    /// A console writing JSON results on stdout when `json` is set. Emoji
+    /// and colors are on unless turned off by `--no-emoji` and `--no-color`,
+    /// by NO_COLOR, or, for emoji, by a Windows console that can't show them
+    pub fn new(json: bool, no_emoji: bool, no_color: bool) -> Self {
+        if no_color || std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+            dialoguer::console::set_colors_enabled(false);
+            dialoguer::console::set_colors_enabled_stderr(false);
+        }
+        Self {
+            json,
+            emoji: !no_emoji && console_shows_emoji(),
+            ..Self::default()
+        }
+    }
+
+    /// Print messages in `locale`, as asked with `--locale`
+    pub fn with_locale(mut self, locale: &str) -> Result<Self> {
+        self.locale.choose(locale)?;
+        Ok(self)
+    }
+
+    /// Print messages in `base_language` unless `--locale` chose otherwise
+    pub fn with_implied_locale(mut self, base_language: &str) -> Self {
+        self.locale.imply(base_language);
+        self
+    }
+
+    /// Send output and build events to `sender` instead of the terminal
+    pub fn with_events(mut self, sender: Sender<BuildEvent>) -> Self {
+        self.events = Some(sender);
+        self
+    }
+
+    /// Start every line with "[`prefix`] "
+    pub fn with_prefix(mut self, prefix: &str) -> Self {
+        self.prefix = Some(prefix.to_string());
+        self
+    }
+
+    /// Whether lines start with a prefix
+    pub fn is_prefixed(&self) -> bool {
+        self.prefix.is_some()
+    }
+
+    /// What lines start with: "[prefix] ", or nothing
+    pub fn line_prefix(&self) -> String {
+        self.prefix
+            .as_ref()
+            .map(|prefix| format!("[{}] ", prefix))
+            .unwrap_or_default()
+    }
+
+    /// Keep JSON results in `results` instead of printing them
+    pub fn with_results(mut self, results: Arc<Mutex<Vec<serde_json::Value>>>) -> Self {
+        self.results = Some(results);
+        self
+    }
+
+    /// Whether commands should emit JSON results on stdout
+    pub fn is_json(&self) -> bool {
+        self.json
+    }
+
+    /// Whether a dashboard is listening to build events
+    pub fn has_dashboard(&self) -> bool {
+        self.events.is_some()
+    }
+
+    /// Send an event to the dashboard, if one is listening
+    pub fn emit(&self, event: BuildEvent) {
+        if let Some(sender) = &self.events {
+            let _ = sender.send(event);
+        }
+    }
+
+    /// Ask the dashboard to review a translation and wait for the answer.
+    /// None when nothing is listening or the request was dropped unanswered
+    pub fn request_review(
+        &self,
+        position: usize,
+        total: usize,
+        word: &str,
+        pos: &PartOfSpeech,
+        translation: &str,
+        issue: Option<&str>,
+    ) -> Option<ReviewReply> {
+        let (request, answer) = ReviewRequest::new(position, total, word, pos, translation, issue);
+        self.events
+            .as_ref()?
+            .send(BuildEvent::Review(request))
+            .ok()?;
+        answer.recv().ok()
+    }
+
+    /// `text` in the console's locale
+    pub fn localize<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        self.locale.localize(text)
+    }
+
+    /// A dialog prompt or progress message in the console's locale
+    pub fn tr(&self, text: &str) -> String {
+        self.localize(text).into_owned()
+    }
+
+    /// `text` as it should be printed: unchanged, or with emoji spelled out
+    /// in ASCII when they are off. Letters of other alphabets are kept
+    pub fn plain<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        match self.emoji {
+            true => Cow::Borrowed(text),
+            false => plain(text),
+        }
+    }
+
+    /// Prompt theme: symbols and colors, or plain ASCII
+    pub fn theme(&self) -> Box<dyn Theme> {
+        match self.emoji {
+            true => Box::new(ColorfulTheme::default()),
+            false => Box::new(SimpleTheme),
+        }
+    }
+
+    /// A progress style drawn in ASCII when emoji are off
+    pub fn console_safe(&self, style: ProgressStyle) -> ProgressStyle {
+        match self.emoji {
+            true => style,
+            false => style.tick_chars("|/-\\ ").progress_chars("=>-"),
+        }
+    }
+
+    /// Print a JSON result to stdout, or keep it when asked to with
+    /// `with_results`. Does nothing unless JSON mode is enabled
+    pub fn emit_json<T: Serialize>(&self, value: &T) -> anyhow::Result<()> {
+        if !self.json {
+            return Ok(());
+        }
+        match &self.results {
+            Some(results) => results
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(serde_json::to_value(value)?),
+            None => println!("{}", serde_json::to_string_pretty(value)?),
+        }
+        Ok(())
+    }
+
+    /// Print human-readable output, in the console's locale. Goes to stderr
+    /// in JSON mode so stdout stays parseable, and to the dashboard while
+    /// one is listening
+    pub fn say(&self, text: &str) {
+        let text = self.prefixed(self.localize(text));
+        if let Some(sender) = &self.events {
+            let _ = sender.send(BuildEvent::Message(text.into_owned()));
+        } else if self.json {
+            eprintln!("{}", self.plain(&text));
+        } else {
+            println!("{}", self.plain(&text));
+        }
+    }
+
+    /// Print a problem to stderr, in the console's locale
+    pub fn say_err(&self, text: &str) {
+        eprintln!("{}", self.plain(&self.prefixed(self.localize(text))));
+    }
+
+    /// `text` with the prefix before each of its lines but blank ones
+    fn prefixed<'a>(&self, text: Cow<'a, str>) -> Cow<'a, str> {
+        if self.prefix.is_none() {
+            return text;
+        }
+        let prefix = self.line_prefix();
+        let lines: Vec<String> = text
+            .split('\n')
+            .map(|line| match line.is_empty() {
+                true => String::new(),
+                false => format!("{}{}", prefix, line),
+            })
+            .collect();
+        Cow::Owned(lines.join("\n"))
+    }
+}
+
+/// The classic Windows console shows emoji as mojibake; Windows Terminal
+/// and the VS Code terminal, which set these variables, don't
+fn console_shows_emoji() -> bool {
+    !cfg!(windows)
+        || std::env::var_os("WT_SESSION").is_some()
+        || std::env::var_os("TERM_PROGRAM").is_some()
+}
+
+/// `text` with emoji spelled out in ASCII. Letters of other alphabets are
+/// kept
+fn plain(text: &str) -> Cow<'_, str> {
+    if text.is_ascii() {
+        return Cow::Borrowed(text);
+    }
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if let Some((_, symbol)) = PLAIN_SYMBOLS.iter().find(|(emoji, _)| *emoji == c) {
+            plain.push_str(symbol);
+        } else if is_pictograph(c) {
+            // With the spaces that set it off from the text
+            while chars.next_if(|c| *c == ' ' || *c == '\u{fe0f}').is_some() {}
+        } else if c != '\u{fe0f}' {
+            plain.push(c);
+        }
+    }
+    Cow::Owned(plain)
+}
+
+pub(crate) fn is_pictograph(c: char) -> bool {
+    matches!(c as u32, 0x2300..=0x23ff | 0x2600..=0x27bf | 0x2b00..=0x2bff | 0x1f000..=0x1faff)
+}
+
+/// Print human-readable output on `console`, in its locale. Goes to stderr
+/// in JSON mode so stdout stays parseable, and to the dashboard while one
+/// is listening
+#[macro_export]
+macro_rules! say {
+    ($console:expr) => {
+        $console.say("")
+    };
+    ($console:expr, $($arg:tt)*) => {
+        $console.say(&format!($($arg)*))
+    };
+}
+
+/// Print a problem to stderr on `console`, in plain ASCII when emoji are off
+#[macro_export]
+macro_rules! say_err {
+    ($console:expr, $($arg:tt)*) => {
+        $console.say_err(&format!($($arg)*))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plain() {
+        let console = Console::new(false, true, false);
+        assert_eq!(console.plain("✅ Cards added"), "[ok] Cards added");
+        assert_eq!(console.plain("⚠️  kuća → house"), "[warn]  kuća -> house");
+        assert_eq!(
+            console.plain("\n📚 Deck name: Croatian"),
+            "\nDeck name: Croatian"
+        );
+        assert_eq!(console.plain("  🗂️  Profile: Učenje"), "  Profile: Učenje");
+        assert_eq!(Console::default().plain("📚 Deck"), "📚 Deck");
+    }
+
+    #[test]
+    fn test_prefixed() {
+        let console = Console::default().with_prefix("Croatian");
+        assert_eq!(
+            console.prefixed(Cow::Borrowed("\n📋 Summary:\n  Nouns: 5")),
+            "\n[Croatian] 📋 Summary:\n[Croatian]   Nouns: 5"
+        );
+        assert_eq!(Console::default().prefixed(Cow::Borrowed("Deck")), "Deck");
+    }
+}
@@ -0,0 +1,186 @@
+use crate::ankiweb::combine_translations;
+use crate::cancel::CancellationToken;
+use crate::error::Result;
+use crate::language::{TranslationChain, Word};
+use futures::stream::{self, Stream, StreamExt};
+use std::collections::BTreeMap;
+
+/// A word with its translation into every base language
+#[derive(Debug, Clone)]
+pub struct TranslatedWord {
+    pub word: Word,
+    /// Card text, combining all base languages
+    pub translation: String,
+    /// Why the translation is not trusted, if no provider gave a usable answer
+    pub issue: Option<String>,
+}
+
+/// Translate words with up to `concurrency` requests in flight, yielding them
+/// in their original order as each becomes ready. Words are only translated
+/// as the consumer asks for them, so whatever it does with one word (review,
+/// adding notes) happens while the next few are being translated. Words in
+/// `pinned` keep the translation given there without asking the translator.
+/// The stream ends early once `cancel` is cancelled, dropping requests in flight
+pub fn translate_words<'a>(
+    translator: &'a TranslationChain,
+    words: Vec<Word>,
+    from: &'a str,
+    to: &'a [String],
+    pinned: &'a BTreeMap<String, String>,
+    concurrency: usize,
+    cancel: &'a CancellationToken,
+) -> impl Stream<Item = Result<TranslatedWord>> + 'a {
+    stream::iter(words)
+        .map(move |word| async move {
+            match pinned.get(&word.text) {
+                Some(translation) => Ok(TranslatedWord {
+                    translation: translation.clone(),
+                    issue: None,
+                    word,
+                }),
+                None => translate_word(translator, word, from, to).await,
+            }
+        })
+        .buffered(concurrency.max(1))
+        .take_until(cancel.cancelled())
+}
+
+/// Translate one word into each base language
+pub async fn translate_word(
+    translator: &TranslationChain,
+    word: Word,
+    from: &str,
+    to: &[String],
+) -> Result<TranslatedWord> {
+    let mut translated = Vec::with_capacity(to.len());
+    let mut issues = Vec::new();
+    for code in to {
+        let checked = translator
+            .translate_as(&word.text, Some(&word.pos), from, code)
+            .await?;
+        if let Some(issue) = checked.issue {
+            issues.push(if to.len() > 1 {
+                format!("{}: {}", code, issue)
+            } else {
+                issue.to_string()
+            });
+        }
+        translated.push((code.clone(), checked.text));
+    }
+
+    Ok(TranslatedWord {
+        word,
+        translation: combine_translations(&translated),
+        issue: (!issues.is_empty()).then(|| issues.join("; ")),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::language::{PartOfSpeech, Translator};
+    use async_trait::async_trait;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    /// Answers "<text>-<to>", slower for earlier words, and records how many
+    /// requests were in flight at once
+    struct SlowTranslator {
+        in_flight: Arc<AtomicUsize>,
+        peak: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl Translator for SlowTranslator {
+        fn name(&self) -> &'static str {
+            "slow"
+        }
+
+        async fn translate(&self, text: &str, _from: &str, to: &str) -> Result<String> {
+            let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(now, Ordering::SeqCst);
+
+            let delay = 40 - text.len().min(4) * 10;
+            tokio::time::sleep(Duration::from_millis(delay as u64)).await;
+
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            Ok(format!("{}-{}", text, to))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_translate_words_in_order_with_bounded_concurrency() {
+        let peak = Arc::new(AtomicUsize::new(0));
+        let translator = TranslationChain::new(vec![Box::new(SlowTranslator {
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            peak: peak.clone(),
+        })]);
+
+        let words: Vec<Word> = ["a", "bb", "ccc", "dddd", "e"]
+            .iter()
+            .enumerate()
+            .map(|(i, text)| Word::new(text.to_string(), PartOfSpeech::Noun, i + 1))
+            .collect();
+        let to = vec!["es".to_string(), "en".to_string()];
+
+        let cancel = CancellationToken::new();
+        let results: Vec<TranslatedWord> =
+            translate_words(&translator, words, "hr", &to, &BTreeMap::new(), 2, &cancel)
+                .map(|result| result.unwrap())
+                .collect()
+                .await;
+
+        let texts: Vec<_> = results.iter().map(|t| t.word.text.as_str()).collect();
+        assert_eq!(texts, vec!["a", "bb", "ccc", "dddd", "e"]);
+        assert_eq!(results[1].translation, "es: bb-es<br>en: bb-en");
+        assert!(results.iter().all(|t| t.issue.is_none()));
+        assert!(peak.load(Ordering::SeqCst) <= 2);
+    }
+
+    #[tokio::test]
+    async fn test_pinned_translations_skip_the_translator() {
+        let translator = TranslationChain::new(vec![Box::new(SlowTranslator {
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            peak: Arc::new(AtomicUsize::new(0)),
+        })]);
+        let words = vec![
+            Word::new("dan".to_string(), PartOfSpeech::Noun, 1),
+            Word::new("noć".to_string(), PartOfSpeech::Noun, 2),
+        ];
+        let to = vec!["es".to_string()];
+        let pinned = BTreeMap::from([("dan".to_string(), "día".to_string())]);
+        let cancel = CancellationToken::new();
+
+        let results: Vec<TranslatedWord> =
+            translate_words(&translator, words, "hr", &to, &pinned, 2, &cancel)
+                .map(|result| result.unwrap())
+                .collect()
+                .await;
+
+        assert_eq!(results[0].translation, "día");
+        assert_eq!(results[1].translation, "noć-es");
+    }
+
+    #[tokio::test]
+    async fn test_translate_words_stops_when_cancelled() {
+        let translator = TranslationChain::new(vec![Box::new(SlowTranslator {
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            peak: Arc::new(AtomicUsize::new(0)),
+        })]);
+        let words: Vec<Word> = ["a", "b", "c"]
+            .iter()
+            .enumerate()
+            .map(|(i, text)| Word::new(text.to_string(), PartOfSpeech::Noun, i + 1))
+            .collect();
+        let to = vec!["es".to_string()];
+        let cancel = CancellationToken::new();
+
+        let pinned = BTreeMap::new();
+        let stream = translate_words(&translator, words, "hr", &to, &pinned, 1, &cancel);
+        futures::pin_mut!(stream);
+        assert!(stream.next().await.is_some());
+        cancel.cancel();
+        assert!(stream.next().await.is_none());
+    }
+}
@@ -0,0 +1,169 @@
+use crate::cache_file;
+use crate::cancel::CancellationToken;
+use crate::language::{normalize_text, TranslationChain, Word};
+use crate::pipeline::translate_word;
+use futures::stream::{self, StreamExt};
+use std::collections::HashSet;
+use std::path::Path;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+/// Spaces out requests shared by concurrent tasks, so no more than one
+/// starts per interval whatever the concurrency
+pub struct Pacer {
+    interval: Duration,
+    next: Mutex<Option<Instant>>,
+}
+
+impl Pacer {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            next: Mutex::new(None),
+        }
+    }
+
+    /// Wait for this caller's turn
+    pub async fn wait(&self) {
+        let start = {
+            let mut next = self.next.lock().await;
+            let now = Instant::now();
+            let start = next.map_or(now, |next| next.max(now));
+            *next = Some(start + self.interval);
+            start
+        };
+        tokio::time::sleep_until(start).await;
+    }
+}
+
+/// Words that still need a translation into at least one of `to`, judged
+/// from the translation cache the providers share
+pub fn uncached_words(cache_dir: &Path, words: Vec<Word>, from: &str, to: &[String]) -> Vec<Word> {
+    let cached: Vec<HashSet<String>> = to
+        .iter()
+        .map(|code| {
+            let cache_file = cache_dir
+                .join("translations")
+                .join(format!("{}_{}.json", from, code));
+            cache_file::read_map::<String>(&cache_file)
+                .map(|cache| cache.keys().map(|text| normalize_text(text)).collect())
+                .unwrap_or_default()
+        })
+        .collect();
+
+    words
+        .into_iter()
+        .filter(|word| !cached.iter().all(|cache| cache.contains(&word.text)))
+        .collect()
+}
+
+/// What a prefetch run did with its words
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PrefetchStats {
+    pub translated: usize,
+    /// Translated, but flagged as untrustworthy
+    pub flagged: usize,
+    pub failed: usize,
+}
+
+/// Fills the translation cache, with up to `concurrency` requests in flight
+/// and each started no sooner than its pacer allows
+pub struct Prefetcher<'a> {
+    translator: &'a TranslationChain,
+    pacer: Pacer,
+    concurrency: usize,
+}
+
+impl<'a> Prefetcher<'a> {
+    pub fn new(translator: &'a TranslationChain, delay: Duration, concurrency: usize) -> Self {
+        Self {
+            translator,
+            pacer: Pacer::new(delay),
+            concurrency,
+        }
+    }
+
+    /// Translate words so their translations land in the cache. `on_word` is
+    /// called after every word, for progress
+    pub async fn run(
+        &self,
+        words: Vec<Word>,
+        from: &str,
+        to: &[String],
+        cancel: &CancellationToken,
+        mut on_word: impl FnMut(&str),
+    ) -> PrefetchStats {
+        let (translator, pacer) = (self.translator, &self.pacer);
+        let results = stream::iter(words)
+            .map(|word| async move {
+                pacer.wait().await;
+                let text = word.text.clone();
+                (text, translate_word(translator, word, from, to).await)
+            })
+            .buffer_unordered(self.concurrency.max(1))
+            .take_until(cancel.cancelled());
+        futures::pin_mut!(results);
+
+        let mut stats = PrefetchStats::default();
+        while let Some((text, result)) = results.next().await {
+            match result {
+                Ok(translated) if translated.issue.is_some() => stats.flagged += 1,
+                Ok(_) => stats.translated += 1,
+                Err(e) => {
+                    tracing::debug!("Prefetching '{}' failed: {}", text, e);
+                    stats.failed += 1;
+                }
+            }
+            on_word(&text);
+        }
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::language::PartOfSpeech;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_uncached_words() {
+        let temp_dir = tempdir().unwrap();
+        let translations = temp_dir.path().join("translations");
+        std::fs::create_dir_all(&translations).unwrap();
+        std::fs::write(
+            translations.join("hr_en.json"),
+            r#"{"dan": "day", "noć": "night"}"#,
+        )
+        .unwrap();
+        std::fs::write(translations.join("hr_es.json"), r#"{"dan": "día"}"#).unwrap();
+
+        let words: Vec<Word> = ["dan", "noć", "kuća"]
+            .iter()
+            .enumerate()
+            .map(|(i, text)| Word::new(text.to_string(), PartOfSpeech::Noun, i + 1))
+            .collect();
+        let texts =
+            |words: Vec<Word>| -> Vec<String> { words.into_iter().map(|w| w.text).collect() };
+
+        let to = vec!["en".to_string()];
+        assert_eq!(
+            texts(uncached_words(temp_dir.path(), words.clone(), "hr", &to)),
+            ["kuća"]
+        );
+        let to = vec!["en".to_string(), "es".to_string()];
+        assert_eq!(
+            texts(uncached_words(temp_dir.path(), words, "hr", &to)),
+            ["noć", "kuća"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pacer_spaces_out_requests() {
+        let pacer = Pacer::new(Duration::from_millis(20));
+        let started = Instant::now();
+        futures::future::join_all((0..4).map(|_| pacer.wait())).await;
+        assert!(started.elapsed() >= Duration::from_millis(60));
+    }
+}
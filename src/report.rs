@@ -0,0 +1,295 @@
+use crate::error::{AnkiDeckBuilderError, Result};
+use crate::language::{PartOfSpeech, Word};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Columns of a CSV report, one row per failure
+const CSV_HEADER: &str =
+    "deck_name,target_language,base_languages,word,pos,rank,translation,phase,error";
+
+/// Step of `create` a word failed in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailurePhase {
+    /// No translator answered
+    Translation,
+    /// Translated, but held back as untrustworthy
+    Validation,
+    /// Anki refused a note
+    AddNote,
+    /// The run stopped before getting to the word
+    NotReached,
+}
+
+impl FailurePhase {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Translation => "translation",
+            Self::Validation => "validation",
+            Self::AddNote => "add_note",
+            Self::NotReached => "not_reached",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        [
+            Self::Translation,
+            Self::Validation,
+            Self::AddNote,
+            Self::NotReached,
+        ]
+        .into_iter()
+        .find(|phase| phase.name() == name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FailedWord {
+    pub word: String,
+    pub pos: PartOfSpeech,
+    pub rank: usize,
+    pub translation: Option<String>,
+    pub phase: FailurePhase,
+    pub error: String,
+}
+
+/// Words a `create` run could not add, with why, so they can be retried
+/// with `create --from-report`. Saved as CSV when the path ends in `.csv`,
+/// as JSON otherwise
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FailureReport {
+    pub deck_name: String,
+    pub target_language: String,
+    pub base_languages: Vec<String>,
+    pub failures: Vec<FailedWord>,
+}
+
+impl FailureReport {
+    pub fn new(deck_name: &str, target_language: &str, base_languages: &[String]) -> Self {
+        Self {
+            deck_name: deck_name.to_string(),
+            target_language: target_language.to_string(),
+            base_languages: base_languages.to_vec(),
+            failures: Vec::new(),
+        }
+    }
+
+    /// Default report file for a deck
+    pub fn path(report_dir: &Path, deck_name: &str) -> PathBuf {
+        let slug: String = deck_name
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '_' })
+            .collect();
+        report_dir.join(format!("{}.json", slug))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// Record a failure. A word whose notes fail one after another is
+    /// recorded once per phase
+    pub fn record(
+        &mut self,
+        word: &Word,
+        translation: Option<&str>,
+        phase: FailurePhase,
+        error: impl std::fmt::Display,
+    ) {
+        if self
+            .failures
+            .last()
+            .is_some_and(|last| last.word == word.text && last.phase == phase)
+        {
+            return;
+        }
+
+        self.failures.push(FailedWord {
+            word: word.text.clone(),
+            pos: word.pos.clone(),
+            rank: word.rank,
+            translation: translation.map(str::to_string),
+            phase,
+            error: error.to_string(),
+        });
+    }
+
+    /// The failed words, once each, in the order they failed
+    pub fn words(&self) -> Vec<Word> {
+        let mut seen = HashSet::new();
+        self.failures
+            .iter()
+            .filter(|failure| seen.insert(failure.word.as_str()))
+            .map(|failure| Word::new(failure.word.clone(), failure.pos.clone(), failure.rank))
+            .collect()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let content = if is_csv(path) {
+            self.to_csv()
+        } else {
+            serde_json::to_string_pretty(self)?
+        };
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        if is_csv(path) {
+            Self::from_csv(&content)
+        } else {
+            Ok(serde_json::from_str(&content)?)
+        }
+    }
+
+    fn to_csv(&self) -> String {
+        let mut csv = format!("{}\n", CSV_HEADER);
+        for failure in &self.failures {
+            let row = [
+                self.deck_name.as_str(),
+                &self.target_language,
+                &self.base_languages.join("+"),
+                &failure.word,
+                failure.pos.name(),
+                &failure.rank.to_string(),
+                failure.translation.as_deref().unwrap_or(""),
+                failure.phase.name(),
+                &failure.error,
+            ];
+            let row: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+            csv.push_str(&row.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+
+    fn from_csv(content: &str) -> Result<Self> {
+        let invalid = |line: usize, reason: &str| {
+            AnkiDeckBuilderError::ConfigurationError(format!(
+                "Invalid report, line {}: {}",
+                line, reason
+            ))
+        };
+
+        let mut report = Self::default();
+        for (index, line) in content.lines().enumerate().skip(1) {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let fields = parse_csv_line(line);
+            let [deck_name, target, bases, word, pos, rank, translation, phase, error] =
+                <[String; 9]>::try_from(fields)
+                    .map_err(|_| invalid(index + 1, "expected 9 columns"))?;
+
+            report.deck_name = deck_name;
+            report.target_language = target;
+            report.base_languages = bases.split('+').map(str::to_string).collect();
+            report.failures.push(FailedWord {
+                word,
+                pos: PartOfSpeech::from_name(&pos)
+                    .ok_or_else(|| invalid(index + 1, "unknown part of speech"))?,
+                rank: rank
+                    .parse()
+                    .map_err(|_| invalid(index + 1, "rank is not a number"))?,
+                translation: (!translation.is_empty()).then_some(translation),
+                phase: FailurePhase::from_name(&phase)
+                    .ok_or_else(|| invalid(index + 1, "unknown phase"))?,
+                error,
+            });
+        }
+        Ok(report)
+    }
+}
+
+fn is_csv(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"))
+}
+
+/// Quote a field when it contains a separator, quote, or line break
+pub(crate) fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn parse_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn report() -> FailureReport {
+        let mut report = FailureReport::new("Deck", "hr", &["es".to_string(), "en".to_string()]);
+        let dan = Word::new("dan".to_string(), PartOfSpeech::Noun, 3);
+        let biti = Word::new("biti".to_string(), PartOfSpeech::Verb, 1);
+        report.record(
+            &dan,
+            Some("día, \"jour\""),
+            FailurePhase::AddNote,
+            "model not found",
+        );
+        report.record(
+            &dan,
+            Some("día, \"jour\""),
+            FailurePhase::AddNote,
+            "model not found",
+        );
+        report.record(&biti, None, FailurePhase::Translation, "HTTP 429");
+        report.record(&dan, None, FailurePhase::NotReached, "deadline");
+        report
+    }
+
+    #[test]
+    fn test_record_and_words() {
+        let report = report();
+
+        assert_eq!(report.failures.len(), 3);
+        let words: Vec<_> = report.words().into_iter().map(|w| w.text).collect();
+        assert_eq!(words, vec!["dan", "biti"]);
+    }
+
+    #[test]
+    fn test_json_and_csv_round_trip() {
+        let dir = tempdir().unwrap();
+        let expected = report();
+
+        for name in ["report.json", "report.csv"] {
+            let path = dir.path().join(name);
+            expected.save(&path).unwrap();
+            let loaded = FailureReport::load(&path).unwrap();
+
+            assert_eq!(loaded.deck_name, "Deck");
+            assert_eq!(loaded.base_languages, vec!["es", "en"]);
+            assert_eq!(loaded.failures, expected.failures);
+        }
+    }
+}
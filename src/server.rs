@@ -0,0 +1,709 @@
+use crate::ankiweb::{AnkiBackend, SchedulingPreset};
+use crate::audio::{AudioProviderKind, DEFAULT_AUDIO_PROVIDERS};
+use crate::builder::{DeckBuilder, DeckSpec, Enrichments, FrequencyList, WordSource};
+use crate::cancel::CancellationToken;
+use crate::config::ServiceSettings;
+use crate::error::{AnkiDeckBuilderError, Result};
+use crate::events::{BuildEvent, BuildOutcome, BuildSummary};
+use crate::language::{
+    language_support, FrequencySourceKind, Glossary, LanguagePacks, ListSize, PartOfSpeech,
+    PostProcessor, Section, Selection, TranslationChain, TranslatorKind, Word,
+};
+use axum::extract::{Path, State};
+use axum::http::{header, StatusCode};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use futures::stream::{self, Stream};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{broadcast, Semaphore};
+
+/// Events kept for a subscriber that falls behind before it starts missing some
+const EVENT_BUFFER: usize = 256;
+
+/// A deck to build, as posted to `/api/builds`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BuildRequest {
+    /// Defaults to "<target> → <base>"
+    pub deck_name: Option<String>,
+    pub target_language: String,
+    pub base_language: String,
+    pub extra_base_languages: Vec<String>,
+    pub words_per_pos: usize,
+    pub selection: Selection,
+    pub frequency_source: FrequencySourceKind,
+    pub list_size: ListSize,
+    /// Common phrases added to a `Phrases` subdeck
+    pub include_phrases: usize,
+    /// Build from the hand-picked starter list instead of a frequency list
+    pub starter: bool,
+    /// Generated numbers added to a `Numbers` subdeck
+    pub include_numbers: bool,
+    /// Generated weekdays, months and clock times added to a `Dates` subdeck
+    pub include_dates: bool,
+    /// Words to use as they are instead of picking them from a frequency list
+    pub words: Vec<RequestedWord>,
+    pub with_images: bool,
+    pub readings: bool,
+    pub with_conjugations: bool,
+    pub with_declensions: bool,
+    pub with_senses: bool,
+    pub with_audio: bool,
+    pub with_mnemonics: bool,
+    pub with_etymology: bool,
+    /// A sentence using each word, written by the LLM
+    pub with_examples: bool,
+    /// Where recordings are looked for, in order
+    pub audio_providers: Vec<AudioProviderKind>,
+    /// Enrichments looked up first, in this order
+    pub enrichment_order: Vec<String>,
+    pub bidirectional: bool,
+    pub preset: Option<SchedulingPreset>,
+    pub skip_cognates: Option<f64>,
+    pub back_translate: bool,
+    /// Translator asked first
+    pub translator: TranslatorKind,
+}
+
+impl Default for BuildRequest {
+    fn default() -> Self {
+        Self {
+            deck_name: None,
+            target_language: String::new(),
+            base_language: "en".to_string(),
+            extra_base_languages: Vec::new(),
+            words_per_pos: 100,
+            selection: Selection::Equal,
+            frequency_source: FrequencySourceKind::Opensubtitles,
+            list_size: ListSize::default(),
+            include_phrases: 0,
+            starter: false,
+            include_numbers: false,
+            include_dates: false,
+            words: Vec::new(),
+            with_images: false,
+            readings: true,
+            with_conjugations: false,
+            with_declensions: false,
+            with_senses: false,
+            with_audio: false,
+            with_mnemonics: false,
+            with_etymology: false,
+            with_examples: false,
+            audio_providers: DEFAULT_AUDIO_PROVIDERS.to_vec(),
+            enrichment_order: Vec::new(),
+            bidirectional: true,
+            preset: None,
+            skip_cognates: None,
+            back_translate: false,
+            translator: TranslatorKind::default(),
+        }
+    }
+}
+
+/// A word given in a build request
+#[derive(Debug, Clone, Deserialize)]
+pub struct RequestedWord {
+    pub text: String,
+    pub pos: PartOfSpeech,
+}
+
+impl BuildRequest {
+    /// The deck this request describes
+    pub fn spec(&self, rules: PostProcessor, packs: &LanguagePacks) -> Result<DeckSpec> {
+        let deck_name = self.deck_name.clone().unwrap_or_else(|| {
+            format!(
+                "{} → {}",
+                self.target_language.to_uppercase(),
+                self.base_language.to_uppercase()
+            )
+        });
+        let words = if !self.words.is_empty() {
+            WordSource::Words(
+                self.words
+                    .iter()
+                    .enumerate()
+                    .map(|(i, word)| Word::new(word.text.clone(), word.pos.clone(), i + 1))
+                    .collect(),
+            )
+        } else if self.starter {
+            WordSource::Starter
+        } else {
+            WordSource::Top {
+                list: FrequencyList {
+                    source: self.frequency_source,
+                    list_size: self.list_size,
+                    merge_weights: Vec::new(),
+                },
+                selection: self.selection,
+                words_per_pos: self.words_per_pos,
+            }
+        };
+
+        Ok(DeckSpec::new(
+            &deck_name,
+            &self.target_language,
+            &self.base_language,
+            packs,
+        )?
+        .with_extra_base_languages(&self.extra_base_languages, packs)?
+        .with_words(words)
+        .with_phrases(self.include_phrases)
+        .with_sections(
+            [
+                (self.include_numbers, Section::Numbers),
+                (self.include_dates, Section::Dates),
+            ]
+            .into_iter()
+            .filter_map(|(asked, section)| asked.then_some(section))
+            .collect(),
+        )
+        .with_enrichments(Enrichments {
+            images: self.with_images,
+            readings: self.readings,
+            conjugations: self.with_conjugations,
+            declensions: self.with_declensions,
+            senses: self.with_senses,
+            audio: self.with_audio,
+            mnemonics: self.with_mnemonics,
+            etymology: self.with_etymology,
+            examples: self.with_examples,
+        })
+        .with_audio_providers(self.audio_providers.clone())
+        .with_enrichment_order(&self.enrichment_order)?
+        .with_bidirectional(self.bidirectional)
+        .with_preset(self.preset)
+        .with_skip_cognates(self.skip_cognates)
+        .with_back_translation(self.back_translate)
+        .with_translator(self.translator)
+        .with_rules(rules))
+    }
+}
+
+/// Where a build job is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    /// Waiting for the build before it to finish
+    Queued,
+    Running,
+    Finished,
+    Failed,
+}
+
+/// What `/api/builds/{id}` reports about a job
+#[derive(Debug, Clone, Serialize)]
+pub struct JobStatus {
+    pub id: u64,
+    pub deck_name: String,
+    pub state: JobState,
+    pub done: usize,
+    pub total: usize,
+    pub summary: Option<BuildSummary>,
+    pub error: Option<String>,
+}
+
+struct Job {
+    status: JobStatus,
+    cancel: CancellationToken,
+    events: broadcast::Sender<(String, Value)>,
+}
+
+/// Makes the translators of each build; tests swap in their own
+type TranslatorFactory = Arc<dyn Fn() -> Option<TranslationChain> + Send + Sync>;
+
+/// Shared by every request: the backend builds go into and the jobs so far
+#[derive(Clone)]
+pub struct ServerState {
+    backend: Arc<dyn AnkiBackend>,
+    cache_dir: PathBuf,
+    services: ServiceSettings,
+    rules: PostProcessor,
+    glossary: Glossary,
+    jobs: Arc<Mutex<BTreeMap<u64, Job>>>,
+    next_id: Arc<AtomicU64>,
+    /// Builds write to the same collection, so they run one at a time
+    queue: Arc<Semaphore>,
+    translators: TranslatorFactory,
+}
+
+impl ServerState {
+    pub fn new(backend: Arc<dyn AnkiBackend>, cache_dir: PathBuf, rules: PostProcessor) -> Self {
+        Self {
+            backend,
+            cache_dir,
+            services: ServiceSettings::default(),
+            rules,
+            glossary: Glossary::default(),
+            jobs: Arc::new(Mutex::new(BTreeMap::new())),
+            next_id: Arc::new(AtomicU64::new(1)),
+            queue: Arc::new(Semaphore::new(1)),
+            translators: Arc::new(|| None),
+        }
+    }
+
+    /// Reach the services every build uses with these settings
+    pub fn with_services(mut self, services: ServiceSettings) -> Self {
+        self.services = services;
+        self
+    }
+
+    /// Take your own translations from `glossary` in every build
+    pub fn with_glossary(mut self, glossary: Glossary) -> Self {
+        self.glossary = glossary;
+        self
+    }
+
+    /// Translate every build with translators from `factory` instead of the
+    /// configured ones
+    pub fn with_translators(
+        mut self,
+        factory: impl Fn() -> TranslationChain + Send + Sync + 'static,
+    ) -> Self {
+        self.translators = Arc::new(move || Some(factory()));
+        self
+    }
+
+    fn jobs(&self) -> std::sync::MutexGuard<'_, BTreeMap<u64, Job>> {
+        self.jobs.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn status(&self, id: u64) -> Option<JobStatus> {
+        self.jobs().get(&id).map(|job| job.status.clone())
+    }
+
+    /// Record an event on the job's status and pass it to its subscribers
+    fn record(&self, id: u64, event: BuildEvent) {
+        let mut jobs = self.jobs();
+        let Some(job) = jobs.get_mut(&id) else {
+            return;
+        };
+        let Some((kind, data)) = event_json(&event) else {
+            return;
+        };
+        match event {
+            BuildEvent::Progress { done, total } => {
+                job.status.done = done;
+                job.status.total = total;
+            }
+            BuildEvent::Finished(summary) => job.status.summary = Some(summary),
+            _ => {}
+        }
+        // Nobody may be listening
+        let _ = job.events.send((kind.to_string(), data));
+    }
+
+    fn finish(&self, id: u64, result: Result<BuildSummary>) {
+        let mut jobs = self.jobs();
+        let Some(job) = jobs.get_mut(&id) else {
+            return;
+        };
+        let data = match result {
+            Ok(summary) => {
+                job.status.state = JobState::Finished;
+                job.status.summary = Some(summary);
+                json!(job.status)
+            }
+            Err(e) => {
+                job.status.state = JobState::Failed;
+                job.status.error = Some(e.to_string());
+                json!(job.status)
+            }
+        };
+        let _ = job.events.send(("done".to_string(), data));
+    }
+
+    /// Queue a build and return its job ID
+    fn start(&self, spec: DeckSpec) -> u64 {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let cancel = CancellationToken::new();
+        let (events, _) = broadcast::channel(EVENT_BUFFER);
+        self.jobs().insert(
+            id,
+            Job {
+                status: JobStatus {
+                    id,
+                    deck_name: spec.deck_name.clone(),
+                    state: JobState::Queued,
+                    done: 0,
+                    total: 0,
+                    summary: None,
+                    error: None,
+                },
+                cancel: cancel.clone(),
+                events,
+            },
+        );
+
+        let state = self.clone();
+        tokio::spawn(async move {
+            let Ok(_turn) = state.queue.clone().acquire_owned().await else {
+                return;
+            };
+            if let Some(job) = state.jobs().get_mut(&id) {
+                job.status.state = JobState::Running;
+            }
+            tracing::info!("Build {} of '{}' started", id, spec.deck_name);
+
+            let recorder = state.clone();
+            let mut builder =
+                DeckBuilder::new(spec, state.backend.as_ref(), state.cache_dir.clone())
+                    .with_services(state.services.clone())
+                    .with_cancel(cancel)
+                    .with_events(move |event| recorder.record(id, event));
+            if let Some(translator) = (state.translators)() {
+                builder = builder.with_translator(translator);
+            }
+            let result = builder.build().await;
+            if let Err(e) = &result {
+                tracing::warn!("Build {} failed: {}", id, e);
+            }
+            state.finish(id, result);
+        });
+        id
+    }
+}
+
+/// An event as an SSE event name and JSON payload; review requests have no
+/// place over HTTP
+fn event_json(event: &BuildEvent) -> Option<(&'static str, Value)> {
+    Some(match event {
+        BuildEvent::Phase(phase) => ("phase", json!({ "phase": phase.label() })),
+        BuildEvent::Message(message) => ("message", json!({ "message": message })),
+        BuildEvent::Progress { done, total } => {
+            ("progress", json!({ "done": done, "total": total }))
+        }
+        BuildEvent::Translated {
+            word,
+            pos,
+            translation,
+            issue,
+        } => (
+            "translated",
+            json!({ "word": word, "pos": pos, "translation": translation, "issue": issue }),
+        ),
+        BuildEvent::NoteAdded { word } => ("note_added", json!({ "word": word })),
+        BuildEvent::NoteFailed { word, error } => {
+            ("note_failed", json!({ "word": word, "error": error }))
+        }
+        BuildEvent::Finished(summary) => ("finished", json!(summary)),
+        BuildEvent::Review(_) => return None,
+    })
+}
+
+/// An error answer with a JSON body
+struct ApiError(StatusCode, String);
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(json!({ "error": self.1 }))).into_response()
+    }
+}
+
+impl From<AnkiDeckBuilderError> for ApiError {
+    fn from(e: AnkiDeckBuilderError) -> Self {
+        let status = match e {
+            AnkiDeckBuilderError::UnsupportedLanguage(_)
+            | AnkiDeckBuilderError::ConfigurationError(_) => StatusCode::BAD_REQUEST,
+            AnkiDeckBuilderError::AnkiConnectNotRunning { .. } => StatusCode::SERVICE_UNAVAILABLE,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        Self(status, e.to_string())
+    }
+}
+
+fn not_found(id: u64) -> ApiError {
+    ApiError(StatusCode::NOT_FOUND, format!("no build {}", id))
+}
+
+/// The HTTP API:
+///
+/// - `GET /api/languages`: supported languages and their data
+/// - `POST /api/builds`: start a build, answering its job ID
+/// - `GET /api/builds`: every job
+/// - `GET /api/builds/{id}`: a job's state and progress
+/// - `GET /api/builds/{id}/events`: the job's progress as server-sent events
+/// - `POST /api/builds/{id}/cancel`: stop a job at its next word
+/// - `GET /api/builds/{id}/apkg`: the finished deck as an .apkg file
+pub fn router(state: ServerState) -> Router {
+    Router::new()
+        .route("/api/languages", get(languages))
+        .route("/api/builds", post(start_build).get(list_builds))
+        .route("/api/builds/:id", get(build_status))
+        .route("/api/builds/:id/events", get(build_events))
+        .route("/api/builds/:id/cancel", post(cancel_build))
+        .route("/api/builds/:id/apkg", get(download_apkg))
+        .with_state(state)
+}
+
+/// Serve the API until `shutdown` is cancelled
+pub async fn serve(
+    state: ServerState,
+    address: SocketAddr,
+    shutdown: CancellationToken,
+) -> Result<()> {
+    let listener = tokio::net::TcpListener::bind(address).await?;
+    tracing::info!("Listening on http://{}", listener.local_addr()?);
+    axum::serve(listener, router(state))
+        .with_graceful_shutdown(shutdown.cancelled_owned())
+        .await?;
+    Ok(())
+}
+
+async fn languages(State(state): State<ServerState>) -> std::result::Result<Json<Value>, ApiError> {
+    Ok(Json(json!(language_support(&state.services)?)))
+}
+
+async fn start_build(
+    State(state): State<ServerState>,
+    Json(request): Json<BuildRequest>,
+) -> std::result::Result<(StatusCode, Json<JobStatus>), ApiError> {
+    let spec = request
+        .spec(state.rules.clone(), &state.services.language_packs)?
+        .with_glossary(state.glossary.clone());
+    let id = state.start(spec);
+    let status = state.status(id).ok_or_else(|| not_found(id))?;
+    Ok((StatusCode::ACCEPTED, Json(status)))
+}
+
+async fn list_builds(State(state): State<ServerState>) -> Json<Vec<JobStatus>> {
+    Json(
+        state
+            .jobs()
+            .values()
+            .map(|job| job.status.clone())
+            .collect(),
+    )
+}
+
+async fn build_status(
+    State(state): State<ServerState>,
+    Path(id): Path<u64>,
+) -> std::result::Result<Json<JobStatus>, ApiError> {
+    state.status(id).map(Json).ok_or_else(|| not_found(id))
+}
+
+async fn cancel_build(
+    State(state): State<ServerState>,
+    Path(id): Path<u64>,
+) -> std::result::Result<Json<JobStatus>, ApiError> {
+    let jobs = state.jobs();
+    let job = jobs.get(&id).ok_or_else(|| not_found(id))?;
+    job.cancel.cancel();
+    Ok(Json(job.status.clone()))
+}
+
+/// The job's current status, then its events until it is done
+async fn build_events(
+    State(state): State<ServerState>,
+    Path(id): Path<u64>,
+) -> std::result::Result<
+    Sse<impl Stream<Item = std::result::Result<Event, std::convert::Infallible>>>,
+    ApiError,
+> {
+    let (status, receiver) = {
+        let jobs = state.jobs();
+        let job = jobs.get(&id).ok_or_else(|| not_found(id))?;
+        (job.status.clone(), job.events.subscribe())
+    };
+    let finished = matches!(status.state, JobState::Finished | JobState::Failed);
+    let first = Event::default()
+        .event("status")
+        .data(json!(status).to_string());
+
+    let events = stream::unfold((receiver, finished), |(mut receiver, done)| async move {
+        if done {
+            return None;
+        }
+        loop {
+            match receiver.recv().await {
+                Ok((kind, data)) => {
+                    let done = kind == "done";
+                    let event = Event::default().event(kind).data(data.to_string());
+                    return Some((Ok(event), (receiver, done)));
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    });
+    let stream = futures::StreamExt::chain(stream::once(async move { Ok(first) }), events);
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+async fn download_apkg(
+    State(state): State<ServerState>,
+    Path(id): Path<u64>,
+) -> std::result::Result<Response, ApiError> {
+    let status = state.status(id).ok_or_else(|| not_found(id))?;
+    let completed = status
+        .summary
+        .as_ref()
+        .is_some_and(|summary| summary.outcome == BuildOutcome::Completed);
+    if status.state != JobState::Finished || !completed {
+        return Err(ApiError(
+            StatusCode::CONFLICT,
+            format!("build {} has not completed", id),
+        ));
+    }
+
+    // Anki writes the package, so the file goes somewhere on this computer
+    let export_dir = state.cache_dir.join("exports");
+    std::fs::create_dir_all(&export_dir).map_err(AnkiDeckBuilderError::from)?;
+    let path = export_dir.join(format!("build-{}.apkg", id));
+    state
+        .backend
+        .export_package(&status.deck_name, &path, false)
+        .await?;
+    let data = std::fs::read(&path).map_err(AnkiDeckBuilderError::from)?;
+    let _ = std::fs::remove_file(&path);
+
+    let filename: String = status
+        .deck_name
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect();
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/octet-stream".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}.apkg\"", filename),
+            ),
+        ],
+        data,
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ankiweb::MockAnki;
+    use crate::language::Translator;
+    use async_trait::async_trait;
+    use std::time::Duration;
+
+    struct EchoTranslator;
+
+    #[async_trait]
+    impl Translator for EchoTranslator {
+        fn name(&self) -> &'static str {
+            "echo"
+        }
+
+        async fn translate(&self, text: &str, _from: &str, to: &str) -> Result<String> {
+            Ok(format!("{}-{}", text, to))
+        }
+    }
+
+    async fn start_server(anki: &MockAnki, cache_dir: PathBuf) -> (String, CancellationToken) {
+        let state = ServerState::new(Arc::new(anki.clone()), cache_dir, PostProcessor::default())
+            .with_translators(|| TranslationChain::new(vec![Box::new(EchoTranslator)]));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let shutdown = CancellationToken::new();
+        let stop = shutdown.clone();
+        tokio::spawn(async move {
+            axum::serve(listener, router(state))
+                .with_graceful_shutdown(stop.cancelled_owned())
+                .await
+        });
+        (url, shutdown)
+    }
+
+    #[tokio::test]
+    async fn test_build_over_http() {
+        let anki = MockAnki::new();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let (url, shutdown) = start_server(&anki, temp_dir.path().to_path_buf()).await;
+        let client = reqwest::Client::new();
+
+        let response = client
+            .post(format!("{}/api/builds", url))
+            .json(&json!({
+                "deck_name": "Croatian",
+                "target_language": "hr",
+                "base_language": "es",
+                "bidirectional": false,
+                "words": [
+                    { "text": "dan", "pos": "Noun" },
+                    { "text": "biti", "pos": "Verb" },
+                ],
+            }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 202);
+        let id = response.json::<Value>().await.unwrap()["id"]
+            .as_u64()
+            .unwrap();
+
+        let mut status = Value::Null;
+        for _ in 0..50 {
+            status = client
+                .get(format!("{}/api/builds/{}", url, id))
+                .send()
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+            if status["state"] == "finished" {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(status["state"], "finished");
+        assert_eq!(status["summary"]["notes_added"], 2);
+        assert_eq!(anki.notes().len(), 2);
+
+        let apkg = client
+            .get(format!("{}/api/builds/{}/apkg", url, id))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(apkg.status(), 200);
+        let package: Value = serde_json::from_slice(&apkg.bytes().await.unwrap()).unwrap();
+        assert_eq!(package["notes"].as_array().unwrap().len(), 2);
+
+        shutdown.cancel();
+    }
+
+    #[tokio::test]
+    async fn test_bad_requests() {
+        let anki = MockAnki::new();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let (url, shutdown) = start_server(&anki, temp_dir.path().to_path_buf()).await;
+        let client = reqwest::Client::new();
+
+        let response = client
+            .post(format!("{}/api/builds", url))
+            .json(&json!({ "target_language": "Klingon" }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 400);
+        assert!(response.json::<Value>().await.unwrap()["error"]
+            .as_str()
+            .unwrap()
+            .contains("Klingon"));
+
+        let response = client
+            .get(format!("{}/api/builds/42", url))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 404);
+
+        shutdown.cancel();
+    }
+}
@@ -0,0 +1,166 @@
+use crate::cache_file;
+use crate::error::Result;
+use crate::language::PartOfSpeech;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Decision made for one word during interactive review
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ReviewDecision {
+    Keep,
+    Edit(String),
+    Skip,
+}
+
+/// A word as reviewed: the same spelling can be listed under several parts
+/// of speech, each translated and decided on its own
+type ReviewedWord = (String, PartOfSpeech);
+
+/// Partial interactive review state. Saved after every decision so an
+/// interrupted review can be resumed on the next launch
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReviewSession {
+    pub deck_name: String,
+    /// Parts of speech chosen before the words were picked, once chosen
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parts_of_speech: Option<Vec<PartOfSpeech>>,
+    #[serde(
+        serialize_with = "serialize_decisions",
+        deserialize_with = "deserialize_decisions"
+    )]
+    pub decisions: HashMap<ReviewedWord, ReviewDecision>,
+}
+
+impl ReviewSession {
+    pub fn new(deck_name: &str) -> Self {
+        Self {
+            deck_name: deck_name.to_string(),
+            parts_of_speech: None,
+            decisions: HashMap::new(),
+        }
+    }
+
+    /// Session file for a language pair
+    pub fn path(session_dir: &Path, target: &str, base: &str) -> PathBuf {
+        session_dir.join(format!("{}_{}_review.json", target, base))
+    }
+
+    /// Load a saved session, if any
+    pub fn load(path: &Path) -> Option<Self> {
+        cache_file::read_json(path)
+    }
+
+    /// Save the session to disk, replacing the old file only once the new
+    /// one is complete so an interrupt can't leave half a session
+    pub fn save(&self, path: &Path) -> Result<()> {
+        cache_file::write_json(path, self)
+    }
+
+    /// Remove a saved session once it has been used
+    pub fn clear(path: &Path) -> Result<()> {
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    /// Whether anything was chosen yet that is worth resuming
+    pub fn has_progress(&self) -> bool {
+        self.parts_of_speech.is_some() || !self.decisions.is_empty()
+    }
+
+    pub fn record(&mut self, word: &str, pos: &PartOfSpeech, decision: ReviewDecision) {
+        self.decisions
+            .insert((word.to_string(), pos.clone()), decision);
+    }
+
+    pub fn is_reviewed(&self, word: &str, pos: &PartOfSpeech) -> bool {
+        self.decisions
+            .contains_key(&(word.to_string(), pos.clone()))
+    }
+
+    /// Translation to use for a word, or None if it was skipped.
+    /// Unreviewed words keep their translation
+    pub fn apply(&self, word: &str, pos: &PartOfSpeech, translation: &str) -> Option<String> {
+        match self.decisions.get(&(word.to_string(), pos.clone())) {
+            Some(ReviewDecision::Skip) => None,
+            Some(ReviewDecision::Edit(edited)) => Some(edited.clone()),
+            Some(ReviewDecision::Keep) | None => Some(translation.to_string()),
+        }
+    }
+}
+
+/// JSON keys are strings, so decisions are saved as a list of
+/// `[word, pos, decision]`
+fn serialize_decisions<S: Serializer>(
+    decisions: &HashMap<ReviewedWord, ReviewDecision>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.collect_seq(
+        decisions
+            .iter()
+            .map(|((word, pos), decision)| (word, pos, decision)),
+    )
+}
+
+fn deserialize_decisions<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<HashMap<ReviewedWord, ReviewDecision>, D::Error> {
+    let entries: Vec<(String, PartOfSpeech, ReviewDecision)> = Vec::deserialize(deserializer)?;
+    Ok(entries
+        .into_iter()
+        .map(|(word, pos, decision)| ((word, pos), decision))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_save_and_load() {
+        let temp_dir = tempdir().unwrap();
+        let path = ReviewSession::path(temp_dir.path(), "hr", "es");
+
+        let mut session = ReviewSession::new("My Deck");
+        session.parts_of_speech = Some(vec![PartOfSpeech::Noun, PartOfSpeech::Verb]);
+        session.record("dan", &PartOfSpeech::Noun, ReviewDecision::Keep);
+        session.save(&path).unwrap();
+
+        let loaded = ReviewSession::load(&path).unwrap();
+        assert_eq!(loaded.deck_name, "My Deck");
+        assert_eq!(loaded.parts_of_speech, session.parts_of_speech);
+        assert!(loaded.is_reviewed("dan", &PartOfSpeech::Noun));
+        assert!(!loaded.is_reviewed("dan", &PartOfSpeech::Verb));
+
+        ReviewSession::clear(&path).unwrap();
+        assert!(ReviewSession::load(&path).is_none());
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut session = ReviewSession::new("My Deck");
+        session.record(
+            "dan",
+            &PartOfSpeech::Noun,
+            ReviewDecision::Edit("el día".to_string()),
+        );
+        session.record("biti", &PartOfSpeech::Verb, ReviewDecision::Skip);
+        // The same spelling as another part of speech is decided apart
+        session.record("biti", &PartOfSpeech::Noun, ReviewDecision::Keep);
+
+        let noun = PartOfSpeech::Noun;
+        assert_eq!(
+            session.apply("dan", &noun, "día"),
+            Some("el día".to_string())
+        );
+        assert_eq!(session.apply("biti", &PartOfSpeech::Verb, "ser"), None);
+        assert_eq!(session.apply("biti", &noun, "ser"), Some("ser".to_string()));
+        assert_eq!(
+            session.apply("kuća", &noun, "casa"),
+            Some("casa".to_string())
+        );
+    }
+}
@@ -0,0 +1,597 @@
+//! Where a build's notes end up. The build adds its notes to a
+//! [`DeckSink`], so the same run can fill a running Anki, export an .apkg
+//! file, write a CSV file for Anki's importer, or just print what it made
+
+use crate::ankiweb::backend::media_filename;
+use crate::ankiweb::models::strip_html;
+use crate::ankiweb::{AnkiBackend, Note, NoteModel, NoteRejection, SchedulingPreset};
+use crate::error::{AnkiDeckBuilderError, Result};
+use crate::output::Console;
+use crate::report::csv_field;
+use crate::say;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Which sink a build writes to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputBackend {
+    /// Add the notes to a running Anki through AnkiConnect
+    #[default]
+    Ankiconnect,
+    /// Build the deck in Anki, then have Anki export it as an .apkg file
+    Apkg,
+    /// Write a CSV file for Anki's File → Import, with media in a folder beside it
+    Csv,
+    /// Print each note instead of saving it
+    Stdout,
+}
+
+impl OutputBackend {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Ankiconnect => "ankiconnect",
+            Self::Apkg => "apkg",
+            Self::Csv => "csv",
+            Self::Stdout => "stdout",
+        }
+    }
+
+    /// Whether notes go through a running Anki
+    pub fn uses_anki(&self) -> bool {
+        matches!(self, Self::Ankiconnect | Self::Apkg)
+    }
+
+    /// Whether the sink writes a file, given with `--output`
+    pub fn writes_file(&self) -> bool {
+        matches!(self, Self::Apkg | Self::Csv)
+    }
+}
+
+/// The sink a backend writes to. `anki` is used by the backends that go
+/// through Anki, `path` is the file the others write, and `console` is where
+/// the notes are printed
+pub fn open_sink<'a>(
+    backend: OutputBackend,
+    anki: &'a dyn AnkiBackend,
+    deck: &str,
+    path: Option<&Path>,
+    console: &Console,
+) -> Result<Box<dyn DeckSink + 'a>> {
+    let path = || {
+        path.ok_or_else(|| {
+            AnkiDeckBuilderError::ConfigurationError(format!(
+                "the {} output backend needs a file to write (--output)",
+                backend.name()
+            ))
+        })
+    };
+    Ok(match backend {
+        OutputBackend::Ankiconnect => Box::new(AnkiConnectSink::new(anki)),
+        OutputBackend::Apkg => Box::new(ApkgSink::new(anki, deck, path()?)?),
+        OutputBackend::Csv => Box::new(CsvSink::new(path()?)),
+        OutputBackend::Stdout => Box::new(StdoutSink::new(console.clone())),
+    })
+}
+
+/// Receives the decks, media and notes of a build. Only adding notes and
+/// storing media are required; sinks without a collection accept every note
+#[async_trait]
+pub trait DeckSink: Send + Sync {
+    /// Identifies the sink in messages ("ankiconnect")
+    fn name(&self) -> &'static str;
+
+    /// Check the sink can be written to, before anything is translated
+    async fn verify(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Make sure a note type the notes use exists
+    async fn ensure_model(&self, _model: &NoteModel) -> Result<()> {
+        Ok(())
+    }
+
+    /// Create a deck notes go into. Returns its ID when the sink gives decks one
+    async fn create_deck(&self, name: &str) -> Result<Option<i64>>;
+
+    async fn apply_preset(&self, _deck: &str, _preset: SchedulingPreset) -> Result<()> {
+        Ok(())
+    }
+
+    async fn set_deck_description(&self, _deck: &str, _description: &str) -> Result<()> {
+        Ok(())
+    }
+
+    /// Which of these notes would be refused, and why
+    async fn check_notes(&self, notes: &[Note]) -> Result<Vec<Option<NoteRejection>>> {
+        Ok(vec![None; notes.len()])
+    }
+
+    /// Which of a word's notes duplicate ones the sink already has, or
+    /// would be refused for another reason
+    async fn check_duplicates(&self, notes: &[Note]) -> Result<Vec<Option<NoteRejection>>> {
+        Ok(vec![None; notes.len()])
+    }
+
+    /// Keep a media file for the notes, returning the name fields refer to it by
+    async fn store_media_file(&self, name: &str, data: &[u8]) -> Result<String>;
+
+    /// Add a note, returning its ID when it went into Anki's collection
+    async fn add_note(&self, note: &Note) -> Result<Option<i64>>;
+
+    /// Called once every note is added. Returns the file written, if any
+    async fn finish(&self) -> Result<Option<PathBuf>> {
+        Ok(None)
+    }
+}
+
+/// Adds notes to the collection of a running Anki
+pub struct AnkiConnectSink<'a> {
+    backend: &'a dyn AnkiBackend,
+}
+
+impl<'a> AnkiConnectSink<'a> {
+    pub fn new(backend: &'a dyn AnkiBackend) -> Self {
+        Self { backend }
+    }
+}
+
+#[async_trait]
+impl DeckSink for AnkiConnectSink<'_> {
+    fn name(&self) -> &'static str {
+        "ankiconnect"
+    }
+
+    async fn verify(&self) -> Result<()> {
+        self.backend.verify_connection().await
+    }
+
+    async fn ensure_model(&self, model: &NoteModel) -> Result<()> {
+        self.backend.ensure_model(model).await
+    }
+
+    async fn create_deck(&self, name: &str) -> Result<Option<i64>> {
+        self.backend.create_deck(name).await.map(Some)
+    }
+
+    async fn apply_preset(&self, deck: &str, preset: SchedulingPreset) -> Result<()> {
+        self.backend.apply_preset(deck, preset).await.map(|_| ())
+    }
+
+    async fn set_deck_description(&self, deck: &str, description: &str) -> Result<()> {
+        self.backend.set_deck_description(deck, description).await
+    }
+
+    async fn check_notes(&self, notes: &[Note]) -> Result<Vec<Option<NoteRejection>>> {
+        self.backend.check_notes(notes).await
+    }
+
+    async fn check_duplicates(&self, notes: &[Note]) -> Result<Vec<Option<NoteRejection>>> {
+        self.backend.check_duplicates(notes).await
+    }
+
+    async fn store_media_file(&self, name: &str, data: &[u8]) -> Result<String> {
+        self.backend.store_media_file(name, data).await
+    }
+
+    async fn add_note(&self, note: &Note) -> Result<Option<i64>> {
+        self.backend.add_note(note).await.map(Some)
+    }
+}
+
+/// Builds the deck in a running Anki like [`AnkiConnectSink`], then has
+/// Anki export it, subdecks included, as an .apkg file. Anki writes the
+/// file itself, so the path is made absolute first
+pub struct ApkgSink<'a> {
+    anki: AnkiConnectSink<'a>,
+    deck: String,
+    path: PathBuf,
+}
+
+impl<'a> ApkgSink<'a> {
+    pub fn new(backend: &'a dyn AnkiBackend, deck: &str, path: &Path) -> Result<Self> {
+        Ok(Self {
+            anki: AnkiConnectSink::new(backend),
+            deck: deck.to_string(),
+            path: std::path::absolute(path)?,
+        })
+    }
+}
+
+#[async_trait]
+impl DeckSink for ApkgSink<'_> {
+    fn name(&self) -> &'static str {
+        "apkg"
+    }
+
+    async fn verify(&self) -> Result<()> {
+        self.anki.verify().await
+    }
+
+    async fn ensure_model(&self, model: &NoteModel) -> Result<()> {
+        self.anki.ensure_model(model).await
+    }
+
+    async fn create_deck(&self, name: &str) -> Result<Option<i64>> {
+        self.anki.create_deck(name).await
+    }
+
+    async fn apply_preset(&self, deck: &str, preset: SchedulingPreset) -> Result<()> {
+        self.anki.apply_preset(deck, preset).await
+    }
+
+    async fn set_deck_description(&self, deck: &str, description: &str) -> Result<()> {
+        self.anki.set_deck_description(deck, description).await
+    }
+
+    async fn check_notes(&self, notes: &[Note]) -> Result<Vec<Option<NoteRejection>>> {
+        self.anki.check_notes(notes).await
+    }
+
+    async fn check_duplicates(&self, notes: &[Note]) -> Result<Vec<Option<NoteRejection>>> {
+        self.anki.check_duplicates(notes).await
+    }
+
+    async fn store_media_file(&self, name: &str, data: &[u8]) -> Result<String> {
+        self.anki.store_media_file(name, data).await
+    }
+
+    async fn add_note(&self, note: &Note) -> Result<Option<i64>> {
+        self.anki.add_note(note).await
+    }
+
+    async fn finish(&self) -> Result<Option<PathBuf>> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        self.anki
+            .backend
+            .export_package(&self.deck, &self.path, false)
+            .await?;
+        Ok(Some(self.path.clone()))
+    }
+}
+
+/// Writes the notes as a CSV file with the header lines Anki's importer
+/// reads: one column for the note type, one for the deck, one per field and
+/// one for the tags. Media files go into a `<name>.media` folder beside it,
+/// to be copied into Anki's media folder
+pub struct CsvSink {
+    path: PathBuf,
+    /// Field names in the order of the note types used, so Anki maps them
+    fields: Mutex<Vec<String>>,
+    notes: Mutex<Vec<Note>>,
+}
+
+impl CsvSink {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            fields: Mutex::new(vec!["Front".to_string(), "Back".to_string()]),
+            notes: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Folder media files are written to
+    pub fn media_dir(&self) -> PathBuf {
+        csv_media_dir(&self.path)
+    }
+
+    /// The file's contents: Anki's header lines, then one row per note
+    pub fn to_csv(&self) -> String {
+        let notes = self.notes.lock().unwrap();
+        let mut fields = self.fields.lock().unwrap().clone();
+        let extra: BTreeSet<&String> = notes
+            .iter()
+            .flat_map(|note| note.fields.keys())
+            .filter(|name| !fields.contains(name))
+            .collect();
+        fields.extend(extra.into_iter().cloned());
+
+        let mut columns = vec!["Note type".to_string(), "Deck".to_string()];
+        columns.extend(fields.iter().cloned());
+        columns.push("Tags".to_string());
+
+        let mut csv = format!(
+            "#separator:Comma\n#html:true\n#notetype column:1\n#deck column:2\n#tags column:{}\n#columns:{}\n",
+            columns.len(),
+            columns
+                .iter()
+                .map(|c| csv_field(c))
+                .collect::<Vec<_>>()
+                .join(",")
+        );
+        for note in notes.iter() {
+            let mut row = vec![csv_field(&note.model_name), csv_field(&note.deck_name)];
+            row.extend(
+                fields
+                    .iter()
+                    .map(|name| csv_field(note.fields.get(name).map_or("", String::as_str))),
+            );
+            row.push(csv_field(&note.tags.join(" ")));
+            csv.push_str(&row.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+}
+
+#[async_trait]
+impl DeckSink for CsvSink {
+    fn name(&self) -> &'static str {
+        "csv"
+    }
+
+    async fn verify(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        Ok(())
+    }
+
+    async fn ensure_model(&self, model: &NoteModel) -> Result<()> {
+        let mut fields = self.fields.lock().unwrap();
+        for (i, name) in model.fields.iter().enumerate() {
+            if !fields.contains(name) {
+                let at = i.min(fields.len());
+                fields.insert(at, name.clone());
+            }
+        }
+        Ok(())
+    }
+
+    async fn create_deck(&self, _name: &str) -> Result<Option<i64>> {
+        // Anki creates a row's deck when it is imported
+        Ok(None)
+    }
+
+    async fn check_duplicates(&self, notes: &[Note]) -> Result<Vec<Option<NoteRejection>>> {
+        let added = self.notes.lock().unwrap();
+        Ok(notes
+            .iter()
+            .map(|note| {
+                let value = note.dedup_value().filter(|_| !note.allow_duplicate)?;
+                added
+                    .iter()
+                    .any(|added| {
+                        added.model_name == note.model_name
+                            && added.deck_name == note.deck_name
+                            && added.dedup_value() == Some(value)
+                    })
+                    .then_some(NoteRejection::Duplicate)
+            })
+            .collect())
+    }
+
+    async fn store_media_file(&self, name: &str, data: &[u8]) -> Result<String> {
+        let filename = media_filename(name, data);
+        let dir = self.media_dir();
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(dir.join(&filename), data)?;
+        Ok(filename)
+    }
+
+    async fn add_note(&self, note: &Note) -> Result<Option<i64>> {
+        self.notes.lock().unwrap().push(note.clone());
+        Ok(None)
+    }
+
+    async fn finish(&self) -> Result<Option<PathBuf>> {
+        std::fs::write(&self.path, self.to_csv())?;
+        Ok(Some(self.path.clone()))
+    }
+}
+
+/// Folder the media of a CSV file go into: "deck.media" beside "deck.csv"
+pub fn csv_media_dir(path: &Path) -> PathBuf {
+    path.with_extension("media")
+}
+
+/// Prints each note as plain text instead of saving it, to see what a build
+/// would make. Media files are named but not kept
+#[derive(Debug, Default)]
+pub struct StdoutSink {
+    console: Console,
+}
+
+impl StdoutSink {
+    pub fn new(console: Console) -> Self {
+        Self { console }
+    }
+}
+
+#[async_trait]
+impl DeckSink for StdoutSink {
+    fn name(&self) -> &'static str {
+        "stdout"
+    }
+
+    async fn create_deck(&self, _name: &str) -> Result<Option<i64>> {
+        Ok(None)
+    }
+
+    async fn store_media_file(&self, name: &str, data: &[u8]) -> Result<String> {
+        Ok(media_filename(name, data))
+    }
+
+    async fn add_note(&self, note: &Note) -> Result<Option<i64>> {
+        let plain = |name: &str| {
+            note.fields
+                .get(name)
+                .map(|value| strip_html(&value.replace("<br>", " / ")))
+                .unwrap_or_default()
+        };
+        say!(
+            self.console,
+            "  [{}] {} → {}",
+            note.deck_name,
+            plain("Front"),
+            plain("Back")
+        );
+        let mut others: Vec<_> = note
+            .fields
+            .keys()
+            .filter(|name| !["Front", "Back"].contains(&name.as_str()))
+            .filter(|name| !note.fields[*name].is_empty())
+            .collect();
+        others.sort();
+        for name in others {
+            say!(self.console, "      {}: {}", name, plain(name));
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ankiweb::{DuplicateScope, MockAnki};
+    use tempfile::TempDir;
+
+    fn note(front: &str, back: &str) -> Note {
+        Note::new("Croatian".to_string(), front.to_string(), back.to_string())
+            .with_tags(vec!["auto-generated".to_string(), "pos:noun".to_string()])
+    }
+
+    #[tokio::test]
+    async fn test_anki_sink_adds_to_collection() {
+        let anki = MockAnki::new();
+        let sink = AnkiConnectSink::new(&anki);
+        sink.verify().await.unwrap();
+        assert!(sink.create_deck("Croatian").await.unwrap().is_some());
+
+        sink.add_note(&note("dan", "día")).await.unwrap();
+        // Checked in one request, an empty front apart from a duplicate
+        let checks = sink
+            .check_duplicates(&[note("dan", "día"), note("", "noche"), note("noć", "noche")])
+            .await
+            .unwrap();
+        assert_eq!(
+            checks,
+            [
+                Some(NoteRejection::Duplicate),
+                Some(NoteRejection::Empty),
+                None
+            ]
+        );
+        assert_eq!(anki.requests("canAddNotesWithErrorDetail"), 1);
+        assert_eq!(anki.notes().len(), 1);
+        assert_eq!(sink.finish().await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_anki_sink_searches_dedup_fields_together() {
+        let anki = MockAnki::new();
+        let sink = AnkiConnectSink::new(&anki);
+        sink.create_deck("Croatian").await.unwrap();
+        let ranked = |front: &str, rank| note(front, "día").with_rank(rank);
+        sink.ensure_model(&NoteModel::ranked()).await.unwrap();
+        sink.add_note(&ranked("dan", 1)).await.unwrap();
+
+        let checks = sink
+            .check_duplicates(&[
+                ranked("dan", 2),
+                ranked("noć", 3),
+                ranked("", 4),
+                ranked("dan", 5).with_allow_duplicate(true),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(
+            checks,
+            [
+                Some(NoteRejection::Duplicate),
+                None,
+                Some(NoteRejection::Empty),
+                None
+            ]
+        );
+        assert_eq!(anki.requests("findNotes"), 1);
+    }
+
+    #[tokio::test]
+    async fn test_anki_sink_honours_duplicate_scope() {
+        let anki = MockAnki::new();
+        let sink = AnkiConnectSink::new(&anki);
+        sink.ensure_model(&NoteModel::ranked()).await.unwrap();
+        let ranked = |deck: &str, front: &str| {
+            Note::new(deck.to_string(), front.to_string(), "día".to_string()).with_rank(1)
+        };
+        sink.create_deck("Other").await.unwrap();
+        sink.add_note(&ranked("Other", "dan")).await.unwrap();
+
+        // In the collection scope a note in another deck is a duplicate;
+        // limited to the deck, it is not
+        sink.create_deck("Croatian").await.unwrap();
+        let checks = sink
+            .check_duplicates(&[
+                ranked("Croatian", "dan"),
+                ranked("Croatian", "dan").with_duplicate_scope(DuplicateScope::Deck),
+                ranked("Other", "dan").with_duplicate_scope(DuplicateScope::Deck),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(
+            checks,
+            [
+                Some(NoteRejection::Duplicate),
+                None,
+                Some(NoteRejection::Duplicate)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_csv_sink_writes_anki_headers() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("deck.csv");
+        let sink = CsvSink::new(&path);
+        sink.verify().await.unwrap();
+
+        let mut with_image = note("kuća", "casa, hogar");
+        let filename = sink.store_media_file("kuća.jpg", b"jpeg").await.unwrap();
+        with_image
+            .fields
+            .insert("Image".to_string(), format!("<img src=\"{}\">", filename));
+        sink.add_note(&note("dan", "día")).await.unwrap();
+        sink.add_note(&with_image).await.unwrap();
+        let checks = sink
+            .check_duplicates(&[note("dan", "otro"), note("noć", "noche")])
+            .await
+            .unwrap();
+        assert_eq!(checks, [Some(NoteRejection::Duplicate), None]);
+
+        assert_eq!(sink.finish().await.unwrap(), Some(path.clone()));
+        let csv = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines[4], "#tags column:6");
+        assert_eq!(lines[5], "#columns:Note type,Deck,Front,Back,Image,Tags");
+        assert_eq!(lines[6], "Basic,Croatian,dan,día,,auto-generated pos:noun");
+        assert!(lines[7].starts_with("Basic,Croatian,kuća,\"casa, hogar\",\"<img src=\"\""));
+        assert!(sink.media_dir().join(filename).exists());
+    }
+
+    #[tokio::test]
+    async fn test_csv_sink_follows_model_field_order() {
+        let sink = CsvSink::new(Path::new("deck.csv"));
+        let mut model = NoteModel::basic();
+        model.fields = vec![
+            "Front".to_string(),
+            "Reading".to_string(),
+            "Back".to_string(),
+        ];
+        sink.ensure_model(&model).await.unwrap();
+
+        let mut with_reading = note("日", "día");
+        with_reading
+            .fields
+            .insert("Reading".to_string(), "nichi".to_string());
+        sink.add_note(&with_reading).await.unwrap();
+        let csv = sink.to_csv();
+        assert!(csv.contains("#columns:Note type,Deck,Front,Reading,Back,Tags\n"));
+        assert!(csv.contains("Croatian,日,nichi,día,"));
+    }
+}
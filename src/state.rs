@@ -0,0 +1,85 @@
+//! What the program remembers between runs to save asking again, like the
+//! language pairs decks were built for lately
+
+use crate::cache_file;
+use crate::error::Result;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Language pairs remembered; the least recently used go first
+pub const MAX_RECENT_PAIRS: usize = 5;
+
+/// A target language and the base languages it was translated into, as codes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LanguagePair {
+    pub target: String,
+    pub base: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct State {
+    /// Most recently used first
+    pub recent_pairs: Vec<LanguagePair>,
+}
+
+impl State {
+    /// The state, empty when nothing was remembered yet
+    pub fn load(path: &Path) -> Self {
+        cache_file::read_json(path).unwrap_or_default()
+    }
+
+    /// Put a pair first among the recent ones, keeping [`MAX_RECENT_PAIRS`]
+    pub fn remember_pair(path: &Path, pair: LanguagePair) -> Result<()> {
+        cache_file::update_json(path, |state: &mut State| {
+            state.recent_pairs.retain(|recent| *recent != pair);
+            state.recent_pairs.insert(0, pair);
+            state.recent_pairs.truncate(MAX_RECENT_PAIRS);
+        })
+    }
+
+    /// Base languages last used with `target`, or with any target
+    pub fn last_base_for(&self, target: &str) -> Option<&[String]> {
+        self.recent_pairs
+            .iter()
+            .find(|pair| pair.target == target)
+            .or_else(|| self.recent_pairs.first())
+            .map(|pair| pair.base.as_slice())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn pair(target: &str, base: &[&str]) -> LanguagePair {
+        LanguagePair {
+            target: target.to_string(),
+            base: base.iter().map(|code| code.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_recent_pairs() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("state.json");
+        assert!(State::load(&path).last_base_for("hr").is_none());
+
+        for target in ["hr", "sr", "de", "fr", "it", "pt"] {
+            State::remember_pair(&path, pair(target, &["es"])).unwrap();
+        }
+        State::remember_pair(&path, pair("de", &["en", "es"])).unwrap();
+        State::remember_pair(&path, pair("pt", &["es"])).unwrap();
+
+        let state = State::load(&path);
+        let targets: Vec<&str> = state
+            .recent_pairs
+            .iter()
+            .map(|pair| pair.target.as_str())
+            .collect();
+        assert_eq!(targets, vec!["pt", "de", "it", "fr", "de"]);
+        assert_eq!(state.last_base_for("de").unwrap(), ["en", "es"]);
+        assert_eq!(state.last_base_for("hr").unwrap(), ["es"]);
+    }
+}
@@ -0,0 +1,507 @@
+use crate::cancel::CancellationToken;
+use crate::error::Result;
+use crate::events::{
+    BuildEvent, BuildOutcome, BuildPhase, BuildSummary, ReviewReply, ReviewRequest,
+};
+use crate::session::ReviewDecision;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Gauge, List, ListItem, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use std::collections::VecDeque;
+use std::io;
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// How long to wait for a key press before redrawing
+const TICK: Duration = Duration::from_millis(50);
+
+/// Translations and log lines kept for scrolling back
+const HISTORY: usize = 200;
+
+/// Full-screen view of a deck build, drawn on its own thread from the build
+/// events it receives. Closes once the build is over and the user quits
+pub struct Dashboard {
+    thread: JoinHandle<io::Result<()>>,
+}
+
+impl Dashboard {
+    /// Take over the terminal and start showing `events`. Quitting before
+    /// the build is over cancels it through `cancel`
+    pub fn start(events: Receiver<BuildEvent>, cancel: CancellationToken) -> Result<Self> {
+        let terminal = ratatui::try_init()?;
+        let thread = std::thread::spawn(move || {
+            let result = DashboardState::new(cancel).run(terminal, events);
+            ratatui::restore();
+            result
+        });
+        Ok(Self { thread })
+    }
+
+    /// Wait for the user to close the dashboard, then give the terminal back
+    pub fn wait(self) -> Result<()> {
+        match self.thread.join() {
+            Ok(result) => Ok(result?),
+            Err(_) => {
+                ratatui::restore();
+                Err(io::Error::other("dashboard stopped unexpectedly").into())
+            }
+        }
+    }
+}
+
+struct Preview {
+    word: String,
+    translation: String,
+    issue: Option<String>,
+}
+
+struct DashboardState {
+    cancel: CancellationToken,
+    phase: Option<BuildPhase>,
+    done: usize,
+    total: usize,
+    notes_added: usize,
+    notes_failed: usize,
+    translations: VecDeque<Preview>,
+    log: VecDeque<String>,
+    review: Option<ReviewRequest>,
+    /// Text of the translation being edited inline
+    editing: Option<String>,
+    summary: Option<BuildSummary>,
+    /// Set once the build stops sending events
+    closed: bool,
+}
+
+impl DashboardState {
+    fn new(cancel: CancellationToken) -> Self {
+        Self {
+            cancel,
+            phase: None,
+            done: 0,
+            total: 0,
+            notes_added: 0,
+            notes_failed: 0,
+            translations: VecDeque::new(),
+            log: VecDeque::new(),
+            review: None,
+            editing: None,
+            summary: None,
+            closed: false,
+        }
+    }
+
+    fn run(
+        mut self,
+        mut terminal: DefaultTerminal,
+        events: Receiver<BuildEvent>,
+    ) -> io::Result<()> {
+        loop {
+            loop {
+                match events.try_recv() {
+                    Ok(event) => self.apply(event),
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        self.closed = true;
+                        break;
+                    }
+                }
+            }
+
+            terminal.draw(|frame| self.draw(frame))?;
+
+            if event::poll(TICK)? {
+                if let Event::Key(key) = event::read()? {
+                    if key.kind == KeyEventKind::Press && self.handle_key(key) {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+
+    fn apply(&mut self, event: BuildEvent) {
+        match event {
+            BuildEvent::Phase(phase) => self.phase = Some(phase),
+            BuildEvent::Message(text) => {
+                for line in text.lines().filter(|line| !line.trim().is_empty()) {
+                    push_limited(&mut self.log, line.to_string());
+                }
+            }
+            BuildEvent::Progress { done, total } => {
+                self.done = done;
+                self.total = total;
+            }
+            BuildEvent::Translated {
+                word,
+                translation,
+                issue,
+                ..
+            } => push_limited(
+                &mut self.translations,
+                Preview {
+                    word,
+                    translation,
+                    issue,
+                },
+            ),
+            BuildEvent::NoteAdded { .. } => self.notes_added += 1,
+            BuildEvent::NoteFailed { word, error } => {
+                self.notes_failed += 1;
+                push_limited(&mut self.log, format!("⚠️  {}: {}", word, error));
+            }
+            BuildEvent::Review(request) => {
+                self.editing = None;
+                self.review = Some(request);
+            }
+            BuildEvent::Finished(summary) => {
+                self.phase = Some(BuildPhase::Finished);
+                self.summary = Some(summary);
+            }
+        }
+    }
+
+    /// Handle a key press, returning true once the dashboard should close
+    fn handle_key(&mut self, key: KeyEvent) -> bool {
+        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            return self.quit_or_cancel();
+        }
+
+        if let Some(text) = self.editing.as_mut() {
+            match key.code {
+                KeyCode::Enter if !text.trim().is_empty() => {
+                    let text = text.trim().to_string();
+                    self.editing = None;
+                    self.answer(ReviewReply::Decide(ReviewDecision::Edit(text)));
+                }
+                KeyCode::Esc => self.editing = None,
+                KeyCode::Backspace => {
+                    text.pop();
+                }
+                KeyCode::Char(c) => text.push(c),
+                _ => {}
+            }
+            return false;
+        }
+
+        if let Some(review) = &self.review {
+            match key.code {
+                KeyCode::Enter | KeyCode::Char('k') => {
+                    self.answer(ReviewReply::Decide(ReviewDecision::Keep))
+                }
+                KeyCode::Char('e') => self.editing = Some(review.translation.clone()),
+                KeyCode::Char('s') => self.answer(ReviewReply::Decide(ReviewDecision::Skip)),
+                KeyCode::Char('a') => self.answer(ReviewReply::KeepRest),
+                KeyCode::Char('q') | KeyCode::Esc => return self.quit_or_cancel(),
+                _ => {}
+            }
+            return false;
+        }
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit_or_cancel(),
+            _ => false,
+        }
+    }
+
+    fn answer(&mut self, reply: ReviewReply) {
+        if let Some(review) = self.review.take() {
+            review.answer(reply);
+        }
+    }
+
+    /// Close once the build is over; before that, ask it to stop
+    fn quit_or_cancel(&mut self) -> bool {
+        if self.closed {
+            return true;
+        }
+        // Dropping an unanswered review lets the build see the cancellation
+        self.review = None;
+        self.editing = None;
+        self.cancel.cancel();
+        false
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [phases, progress, body, footer] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Min(5),
+            Constraint::Length(7),
+        ])
+        .areas(frame.area());
+        let [translations, log] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(body);
+
+        frame.render_widget(self.phases(), phases);
+        frame.render_widget(self.progress(), progress);
+        frame.render_widget(self.translations(translations), translations);
+        frame.render_widget(latest_lines("Log", &self.log, log), log);
+        frame.render_widget(self.footer(), footer);
+    }
+
+    fn phases(&self) -> Paragraph<'static> {
+        let finished = self.summary.is_some();
+        let spans: Vec<Span> = BuildPhase::ALL
+            .iter()
+            .flat_map(|phase| {
+                let (marker, style) = match self.phase {
+                    Some(current) if finished || current > *phase => {
+                        ("✔", Style::default().fg(Color::Green))
+                    }
+                    Some(current) if current == *phase => (
+                        "▶",
+                        Style::default()
+                            .fg(Color::Yellow)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                    _ => ("·", Style::default().fg(Color::DarkGray)),
+                };
+                [
+                    Span::styled(format!("{} {}", marker, phase.label()), style),
+                    Span::raw("   "),
+                ]
+            })
+            .collect();
+
+        Paragraph::new(Line::from(spans)).block(titled("Anki Deck Builder"))
+    }
+
+    fn progress(&self) -> Gauge<'static> {
+        let ratio = if self.total == 0 {
+            0.0
+        } else {
+            (self.done as f64 / self.total as f64).min(1.0)
+        };
+        let mut label = format!(
+            "{}/{} words · {} notes added",
+            self.done, self.total, self.notes_added
+        );
+        if self.notes_failed > 0 {
+            label.push_str(&format!(" · {} failed", self.notes_failed));
+        }
+
+        Gauge::default()
+            .block(titled("Progress"))
+            .gauge_style(Style::default().fg(Color::Cyan))
+            .ratio(ratio)
+            .label(label)
+    }
+
+    fn translations(&self, area: Rect) -> List<'static> {
+        let visible = area.height.saturating_sub(2) as usize;
+        let items: Vec<ListItem> = self
+            .translations
+            .iter()
+            .skip(self.translations.len().saturating_sub(visible))
+            .map(|preview| {
+                let mut spans = vec![
+                    Span::styled(
+                        preview.word.clone(),
+                        Style::default().add_modifier(Modifier::BOLD),
+                    ),
+                    Span::raw(" → "),
+                    Span::raw(preview.translation.replace("<br>", " · ")),
+                ];
+                if let Some(issue) = &preview.issue {
+                    spans.push(Span::styled(
+                        format!("  ⚠️  {}", issue),
+                        Style::default().fg(Color::Yellow),
+                    ));
+                }
+                ListItem::new(Line::from(spans))
+            })
+            .collect();
+
+        List::new(items).block(titled("Translations"))
+    }
+
+    fn footer(&self) -> Paragraph<'static> {
+        if let Some(review) = &self.review {
+            return self.review_panel(review);
+        }
+
+        let lines = match &self.summary {
+            Some(summary) => summary_lines(summary),
+            None if self.cancel.is_cancelled() => {
+                vec![Line::from("⏹️  Stopping after the current step...")]
+            }
+            None => vec![Line::from("q: cancel")],
+        };
+        let lines = if self.closed {
+            lines
+                .into_iter()
+                .chain([Line::from(""), Line::from("Press q to exit")])
+                .collect()
+        } else {
+            lines
+        };
+        Paragraph::new(lines).block(titled(if self.summary.is_some() {
+            "Summary"
+        } else {
+            "Status"
+        }))
+    }
+
+    fn review_panel(&self, review: &ReviewRequest) -> Paragraph<'static> {
+        let mut lines = vec![Line::from(vec![
+            Span::raw(format!("[{}/{}] ", review.position, review.total)),
+            Span::styled(
+                review.word.clone(),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(format!(" ({})", review.pos.name())),
+        ])];
+        lines.push(match &self.editing {
+            Some(text) => Line::from(vec![
+                Span::raw("Edit: "),
+                Span::styled(format!("{}▏", text), Style::default().fg(Color::Cyan)),
+            ]),
+            None => Line::from(format!("→ {}", review.translation.replace("<br>", " · "))),
+        });
+        if let Some(issue) = &review.issue {
+            lines.push(Line::styled(
+                format!("⚠️  {}", issue),
+                Style::default().fg(Color::Yellow),
+            ));
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from(if self.editing.is_some() {
+            "Enter: save · Esc: back"
+        } else {
+            "Enter: keep · e: edit · s: skip · a: keep all remaining · q: cancel"
+        }));
+
+        Paragraph::new(lines).block(titled("Review"))
+    }
+}
+
+fn summary_lines(summary: &BuildSummary) -> Vec<Line<'static>> {
+    let headline = match summary.outcome {
+        BuildOutcome::Completed => "🎉 Deck creation complete!".to_string(),
+        BuildOutcome::DeadlineReached => format!(
+            "⏱️  Deadline reached after {} of {} words. Rerun to add the rest.",
+            summary.words, summary.total_words
+        ),
+        BuildOutcome::Cancelled => format!(
+            "⏹️  Cancelled after {} of {} words; the notes added so far are kept.",
+            summary.words, summary.total_words
+        ),
+        BuildOutcome::Failed => format!(
+            "❌ Stopped after {} of {} words; the notes added so far are kept.",
+            summary.words, summary.total_words
+        ),
+    };
+
+    let mut details = format!(
+        "✅ {} notes added ({} cards) to '{}'",
+        summary.notes_added, summary.cards_added, summary.deck_name
+    );
+    for (count, what) in [
+        (summary.duplicates_skipped, "duplicates skipped"),
+        (summary.cognates_skipped, "cognates skipped"),
+        (summary.notes_failed, "notes failed"),
+        (summary.flagged, "words flagged"),
+    ] {
+        if count > 0 {
+            details.push_str(&format!(" · {} {}", count, what));
+        }
+    }
+
+    vec![
+        Line::styled(headline, Style::default().add_modifier(Modifier::BOLD)),
+        Line::from(details),
+    ]
+}
+
+fn latest_lines(title: &'static str, lines: &VecDeque<String>, area: Rect) -> List<'static> {
+    let visible = area.height.saturating_sub(2) as usize;
+    let items: Vec<ListItem> = lines
+        .iter()
+        .skip(lines.len().saturating_sub(visible))
+        .map(|line| ListItem::new(line.clone()))
+        .collect();
+    List::new(items).block(titled(title))
+}
+
+fn titled(title: &'static str) -> Block<'static> {
+    Block::default().borders(Borders::ALL).title(title)
+}
+
+fn push_limited<T>(items: &mut VecDeque<T>, item: T) {
+    if items.len() == HISTORY {
+        items.pop_front();
+    }
+    items.push_back(item);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::language::PartOfSpeech;
+
+    fn press(state: &mut DashboardState, code: KeyCode) -> bool {
+        state.handle_key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    fn review(state: &mut DashboardState) -> Receiver<ReviewReply> {
+        let (request, answer) =
+            ReviewRequest::new(1, 3, "dan", &PartOfSpeech::Noun, "día", Some("echoed"));
+        state.apply(BuildEvent::Review(request));
+        answer
+    }
+
+    #[test]
+    fn test_review_keys() {
+        let mut state = DashboardState::new(CancellationToken::new());
+
+        let answer = review(&mut state);
+        press(&mut state, KeyCode::Enter);
+        assert_eq!(
+            answer.recv().unwrap(),
+            ReviewReply::Decide(ReviewDecision::Keep)
+        );
+        assert!(state.review.is_none());
+
+        // Inline editing starts from the current translation
+        let answer = review(&mut state);
+        press(&mut state, KeyCode::Char('e'));
+        for _ in 0..3 {
+            press(&mut state, KeyCode::Backspace);
+        }
+        for c in "jour".chars() {
+            press(&mut state, KeyCode::Char(c));
+        }
+        press(&mut state, KeyCode::Enter);
+        assert_eq!(
+            answer.recv().unwrap(),
+            ReviewReply::Decide(ReviewDecision::Edit("jour".to_string()))
+        );
+
+        let answer = review(&mut state);
+        press(&mut state, KeyCode::Char('s'));
+        assert_eq!(
+            answer.recv().unwrap(),
+            ReviewReply::Decide(ReviewDecision::Skip)
+        );
+    }
+
+    #[test]
+    fn test_quit_cancels_until_the_build_is_over() {
+        let cancel = CancellationToken::new();
+        let mut state = DashboardState::new(cancel.clone());
+
+        // Quitting mid-review drops the request so the build can stop
+        let answer = review(&mut state);
+        assert!(!press(&mut state, KeyCode::Char('q')));
+        assert!(cancel.is_cancelled());
+        assert!(answer.recv().is_err());
+
+        state.closed = true;
+        assert!(press(&mut state, KeyCode::Char('q')));
+    }
+}
@@ -1,4 +1,4 @@
-use anki_deck_builder::ankiweb::{AnkiClient, Note};
+use anki_deck_builder::ankiweb::{AnkiBackend, AnkiClient, Note};
 
 /// Tests require AnkiConnect to be running on localhost:8765
 /// Skip these tests if AnkiConnect is not available
@@ -75,3 +75,28 @@ async fn test_create_and_add_note() {
         deck_name
     );
 }
+
+#[tokio::test]
+#[ignore] // Run with: cargo test -- --ignored
+async fn test_find_and_suspend_cards() {
+    use anki_deck_builder::ankiweb::deck_query;
+
+    let client =
+        AnkiClient::new("http://localhost:8765".to_string()).expect("Failed to create client");
+
+    client.verify_connection().await.expect("Connection failed");
+
+    let deck_name = "AnkiDeckBuilder_Test";
+    client
+        .create_deck(deck_name)
+        .await
+        .expect("Failed to create deck");
+
+    let cards = client
+        .find_cards(&deck_query(deck_name, &[]))
+        .await
+        .expect("Failed to find cards");
+
+    client.suspend(&cards).await.expect("Failed to suspend");
+    client.unsuspend(&cards).await.expect("Failed to unsuspend");
+}
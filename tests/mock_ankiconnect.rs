@@ -0,0 +1,460 @@
+use anki_deck_builder::ankiweb::{
+    added_today_query, deck_query, find_ankiconnect, wait_for_ankiconnect, AnkiBackend, AnkiClient,
+    AnkiConnectSettings, CardBuilder, DuplicateScope, MockAnki, MockAnkiServer, Note, NoteMapping,
+    NoteModel, NoteRejection, SchedulingPreset, SortField,
+};
+use anki_deck_builder::http::HttpSettings;
+use anki_deck_builder::language::PartOfSpeech;
+use anki_deck_builder::AnkiDeckBuilderError;
+use std::time::Duration;
+
+/// Offline counterparts of the AnkiConnect integration tests, run against
+/// the mock server so they need no running Anki
+async fn mock_client() -> (MockAnki, MockAnkiServer, AnkiClient) {
+    let anki = MockAnki::new();
+    let server = MockAnkiServer::start(anki.clone())
+        .await
+        .expect("Failed to start mock server");
+    let client = AnkiClient::new(server.url().to_string()).expect("Failed to create client");
+    (anki, server, client)
+}
+
+fn note(deck: &str, front: &str, back: &str) -> Note {
+    Note::new(deck.to_string(), front.to_string(), back.to_string())
+}
+
+#[tokio::test]
+async fn test_connection_and_decks() {
+    let (_anki, _server, client) = mock_client().await;
+
+    client.verify_connection().await.unwrap();
+    client.create_deck("Croatian").await.unwrap();
+
+    let decks = client.get_decks().await.unwrap();
+    assert!(decks.contains(&"Default".to_string()));
+    assert!(decks.contains(&"Croatian".to_string()));
+}
+
+#[tokio::test]
+async fn test_unreachable_server() {
+    let (_anki, server, _client) = mock_client().await;
+    let url = server.url().to_string();
+    drop(server);
+    tokio::task::yield_now().await;
+
+    let client = AnkiClient::new(url).unwrap();
+    let err = client.verify_connection().await.unwrap_err();
+    assert!(matches!(
+        err,
+        AnkiDeckBuilderError::AnkiConnectNotRunning { .. }
+    ));
+}
+
+#[tokio::test]
+async fn test_add_note_and_duplicates() {
+    let (anki, _server, client) = mock_client().await;
+    client.create_deck("Deck").await.unwrap();
+
+    let first = note("Deck", "dan", "día");
+    let check = |notes: Vec<Note>| {
+        let client = &client;
+        async move { client.check_duplicates(&notes).await.unwrap() }
+    };
+    assert_eq!(check(vec![first.clone()]).await, [None]);
+    client.add_note(&first).await.unwrap();
+    assert_eq!(
+        check(vec![first.clone()]).await,
+        [Some(NoteRejection::Duplicate)]
+    );
+
+    // Anki rejects a second note with the same first field
+    let err = client.add_note(&first).await.unwrap_err();
+    assert!(
+        matches!(err, AnkiDeckBuilderError::AnkiConnectError(ref m) if m.contains("duplicate"))
+    );
+
+    // Duplicates keyed on another field are found by searching that field
+    let by_back = note("Deck", "dan", "día").with_dedup_field("Back");
+    let other = note("Deck", "noć", "noche").with_dedup_field("Back");
+    assert_eq!(
+        check(vec![by_back, other]).await,
+        [Some(NoteRejection::Duplicate), None]
+    );
+
+    assert_eq!(anki.notes().len(), 1);
+}
+
+#[tokio::test]
+async fn test_check_notes_before_adding() {
+    let (anki, _server, client) = mock_client().await;
+    client.create_deck("Deck").await.unwrap();
+    client.add_note(&note("Deck", "dan", "día")).await.unwrap();
+
+    let checks = client
+        .check_notes(&[
+            note("Deck", "dan", "…"),
+            note("Deck", "noć", "…"),
+            note("Deck", "", "…"),
+            note("Missing", "voda", "…"),
+        ])
+        .await
+        .unwrap();
+    assert_eq!(checks[0], Some(NoteRejection::Duplicate));
+    assert_eq!(checks[1], None);
+    assert_eq!(checks[2], Some(NoteRejection::Empty));
+    assert!(matches!(&checks[3], Some(NoteRejection::Other(m)) if m.contains("deck")));
+
+    // Checking duplicates within another deck only
+    client.create_deck("Deck::Duplicates").await.unwrap();
+    let mut moved = note("Deck::Duplicates", "dan", "día");
+    moved.duplicate_scope = DuplicateScope::Deck;
+    assert_eq!(
+        client.check_notes(&[moved.clone()]).await.unwrap(),
+        vec![None]
+    );
+    client.add_note(&moved).await.unwrap();
+
+    // Older AnkiConnect versions only say whether a note can be added
+    anki.fail_action("canAddNotesWithErrorDetail", "unsupported action");
+    let checks = client
+        .check_notes(&[note("Deck", "dan", "…"), note("Deck", "noć", "…")])
+        .await
+        .unwrap();
+    assert_eq!(checks, vec![Some(NoteRejection::Duplicate), None]);
+}
+
+#[tokio::test]
+async fn test_ranked_notes() {
+    let (anki, _server, client) = mock_client().await;
+    client.create_deck("Deck").await.unwrap();
+
+    let builder = CardBuilder::new("Deck", "hr", "es").with_sort_field(SortField::Rank);
+    client
+        .ensure_model(&builder.required_model().unwrap())
+        .await
+        .unwrap();
+    // Creating it a second time is a no-op
+    client.ensure_model(&NoteModel::ranked()).await.unwrap();
+
+    let notes = builder.build("dan", "día", &PartOfSpeech::Noun, 1);
+    assert_eq!(client.check_duplicates(&notes).await.unwrap(), [None, None]);
+    for note in &notes {
+        client.add_note(note).await.unwrap();
+    }
+    assert_eq!(
+        client.check_duplicates(&notes).await.unwrap(),
+        [
+            Some(NoteRejection::Duplicate),
+            Some(NoteRejection::Duplicate)
+        ]
+    );
+    assert_eq!(anki.notes().len(), 2);
+}
+
+#[tokio::test]
+async fn test_error_response() {
+    let (anki, _server, client) = mock_client().await;
+    anki.fail_action("createDeck", "deck name is reserved");
+
+    let err = client.create_deck("Deck").await.unwrap_err();
+    assert!(matches!(
+        err,
+        AnkiDeckBuilderError::AnkiConnectError(ref m) if m == "deck name is reserved"
+    ));
+}
+
+fn retrying_client(server: &MockAnkiServer, retries: u32) -> AnkiClient {
+    let settings = AnkiConnectSettings {
+        retries,
+        retry_delay_ms: 10,
+        ..AnkiConnectSettings::default()
+    };
+    AnkiClient::with_settings(
+        server.url().to_string(),
+        &settings,
+        &HttpSettings::default(),
+    )
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_busy_collection_is_retried() {
+    let (anki, server, _client) = mock_client().await;
+    anki.fail_action_times("addNote", "collection is not available", 2);
+
+    let client = retrying_client(&server, 3);
+    client.create_deck("Deck").await.unwrap();
+    client.add_note(&note("Deck", "dan", "day")).await.unwrap();
+    assert_eq!(anki.notes().len(), 1);
+}
+
+#[tokio::test]
+async fn test_busy_collection_gives_up() {
+    let (anki, server, _client) = mock_client().await;
+    anki.fail_action("createDeck", "Collection is not available");
+
+    let err = retrying_client(&server, 2)
+        .create_deck("Deck")
+        .await
+        .unwrap_err();
+    assert!(matches!(err, AnkiDeckBuilderError::AnkiConnectError(_)));
+}
+
+#[tokio::test]
+async fn test_timeout() {
+    let (anki, server, _client) = mock_client().await;
+    anki.set_delay(Duration::from_millis(500));
+
+    let client =
+        AnkiClient::with_timeout(server.url().to_string(), Duration::from_millis(50)).unwrap();
+    let err = client.get_decks().await.unwrap_err();
+    assert!(matches!(err, AnkiDeckBuilderError::HttpError(ref e) if e.is_timeout()));
+}
+
+#[tokio::test]
+async fn test_suspend_by_tag() {
+    let (anki, _server, client) = mock_client().await;
+    client.create_deck("Deck").await.unwrap();
+
+    let builder = CardBuilder::new("Deck", "hr", "es").with_bidirectional(false);
+    for note in builder.build("biti", "ser", &PartOfSpeech::Verb, 1) {
+        client.add_note(&note).await.unwrap();
+    }
+    for note in builder.build("dan", "día", &PartOfSpeech::Noun, 2) {
+        client.add_note(&note).await.unwrap();
+    }
+
+    let verbs = client
+        .find_cards(&deck_query("Deck", &["pos:verb".to_string()]))
+        .await
+        .unwrap();
+    assert_eq!(verbs.len(), 1);
+
+    assert!(client.suspend(&verbs).await.unwrap());
+    assert!(anki.is_suspended(verbs[0]));
+    assert!(!client.suspend(&verbs).await.unwrap());
+    assert!(client.unsuspend(&verbs).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_media_round_trip() {
+    let (_anki, _server, client) = mock_client().await;
+
+    let name = client.store_media_file("dan.mp3", b"audio").await.unwrap();
+    assert_eq!(
+        client.store_media_file("dan.mp3", b"audio").await.unwrap(),
+        name
+    );
+    assert_eq!(
+        client.retrieve_media_file(&name).await.unwrap(),
+        Some(b"audio".to_vec())
+    );
+    assert_eq!(
+        client.retrieve_media_file("missing.mp3").await.unwrap(),
+        None
+    );
+}
+
+#[tokio::test]
+async fn test_delete_notes() {
+    let (_anki, _server, client) = mock_client().await;
+    client.create_deck("Deck").await.unwrap();
+    let dan = client.add_note(&note("Deck", "dan", "día")).await.unwrap();
+    let kuca = client
+        .add_note(&note("Deck", "kuća", "casa"))
+        .await
+        .unwrap();
+
+    // IDs of notes already gone are ignored
+    client.delete_notes(&[dan, 999]).await.unwrap();
+    let left = client.find_notes(&deck_query("Deck", &[])).await.unwrap();
+    assert_eq!(left, vec![kuca]);
+}
+
+#[tokio::test]
+async fn test_mock_as_backend() {
+    // The mock can stand in for AnkiClient wherever an AnkiBackend is accepted
+    let backend: Box<dyn AnkiBackend> = Box::new(MockAnki::new());
+
+    backend.verify_connection().await.unwrap();
+    backend.create_deck("Deck").await.unwrap();
+    backend.add_note(&note("Deck", "dan", "día")).await.unwrap();
+
+    let found = backend.find_notes(&deck_query("Deck", &[])).await.unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(
+        backend
+            .can_add_notes(&[note("Deck", "dan", "x")])
+            .await
+            .unwrap(),
+        vec![false]
+    );
+}
+
+#[tokio::test]
+async fn test_deck_fronts() {
+    let (_anki, _server, client) = mock_client().await;
+    client.create_deck("Old").await.unwrap();
+
+    let builder = CardBuilder::new("Old", "hr", "es").with_sort_field(SortField::Rank);
+    client
+        .ensure_model(&builder.required_model().unwrap())
+        .await
+        .unwrap();
+    for note in builder.build("dan", "día", &PartOfSpeech::Noun, 1) {
+        client.add_note(&note).await.unwrap();
+    }
+    client
+        .add_note(&note("Old", "<b>kuća</b>", "casa"))
+        .await
+        .unwrap();
+
+    let mut fronts = client.deck_fronts("Old").await.unwrap();
+    fronts.sort();
+    assert_eq!(fronts, vec!["dan", "día", "kuća"]);
+
+    let infos = client.notes_info(&[9999]).await.unwrap();
+    assert!(infos.is_empty());
+    assert!(client.deck_fronts("Default").await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_user_note_type() {
+    let (anki, _server, client) = mock_client().await;
+    client.create_deck("Deck").await.unwrap();
+
+    let mut vocab = NoteModel::basic();
+    vocab.name = "Vocab".to_string();
+    vocab.fields = vec!["Term".to_string(), "Meaning".to_string()];
+    client.create_model(&vocab).await.unwrap();
+    assert_eq!(
+        client.get_model_field_names("Vocab").await.unwrap(),
+        vec!["Term", "Meaning"]
+    );
+
+    let mapping = NoteMapping::new("Vocab")
+        .with_field_map(&["Front=Term".to_string(), "Back=Meaning".to_string()])
+        .unwrap();
+    let builder = CardBuilder::new("Deck", "hr", "es").with_mapping(Some(mapping));
+    for note in builder.build("dan", "día", &PartOfSpeech::Noun, 1) {
+        client.add_note(&note).await.unwrap();
+    }
+    let checks = client
+        .check_duplicates(&builder.build("dan", "día", &PartOfSpeech::Noun, 1))
+        .await
+        .unwrap();
+    assert!(checks
+        .iter()
+        .all(|check| *check == Some(NoteRejection::Duplicate)));
+
+    let notes = anki.notes();
+    assert_eq!(notes.len(), 2);
+    assert!(notes.iter().all(|n| n.model_name == "Vocab"));
+    assert_eq!(client.deck_fronts("Deck").await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_switch_profile() {
+    let (anki, _server, client) = mock_client().await;
+    anki.add_profile("Partner");
+
+    assert_eq!(
+        client.get_profiles().await.unwrap(),
+        vec!["User 1".to_string(), "Partner".to_string()]
+    );
+
+    let previous = client.switch_profile("Partner").await.unwrap();
+    assert_eq!(previous.as_deref(), Some("User 1"));
+    assert_eq!(anki.active_profile(), "Partner");
+
+    // Already open, nothing to switch back to
+    assert_eq!(client.switch_profile("Partner").await.unwrap(), None);
+
+    let err = client.switch_profile("Nobody").await.unwrap_err();
+    assert!(err.to_string().contains("User 1, Partner"));
+
+    client.load_profile("User 1").await.unwrap();
+    assert_eq!(client.get_active_profile().await.unwrap(), "User 1");
+}
+
+#[tokio::test]
+async fn test_apply_preset() {
+    let (anki, _server, client) = mock_client().await;
+    client.create_deck("Croatian").await.unwrap();
+    client.create_deck("Russian").await.unwrap();
+
+    let config = client
+        .apply_preset("Croatian", SchedulingPreset::Gentle)
+        .await
+        .unwrap();
+    assert_eq!(config.name, "Anki Deck Builder (gentle)");
+
+    // The deck moved to its own group, with untouched settings kept
+    let saved = anki.deck_config("Croatian");
+    assert_eq!(saved["new"]["perDay"], 10);
+    assert_eq!(saved["new"]["delays"], serde_json::json!([1.0, 10.0, 60.0]));
+    assert_eq!(saved["new"]["bury"], false);
+    assert_eq!(saved["maxTaken"], 60);
+    assert_eq!(anki.deck_config("Default")["new"]["perDay"], 20);
+
+    // Another deck with the same preset shares the group
+    let again = client
+        .apply_preset("Russian", SchedulingPreset::Gentle)
+        .await
+        .unwrap();
+    assert_eq!(again.id, config.id);
+
+    let err = client
+        .apply_preset("Missing", SchedulingPreset::Intense)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, AnkiDeckBuilderError::AnkiConnectError(_)));
+}
+
+#[tokio::test]
+async fn test_sync() {
+    let (anki, _server, client) = mock_client().await;
+    client.sync().await.unwrap();
+    assert_eq!(anki.syncs(), 1);
+
+    anki.fail_action("sync", "auth not set");
+    let err = client.sync().await.unwrap_err();
+    assert!(err.to_string().contains("auth not set"));
+    assert_eq!(anki.syncs(), 1);
+}
+
+#[tokio::test]
+async fn test_gui_browse() {
+    let (anki, _server, client) = mock_client().await;
+    client.create_deck("Deck").await.unwrap();
+    client.add_note(&note("Deck", "dan", "día")).await.unwrap();
+
+    let query = added_today_query("Deck");
+    assert_eq!(client.gui_browse(&query).await.unwrap().len(), 1);
+    assert_eq!(anki.browsed(), Some(query));
+    client.gui_deck_browser().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_find_ankiconnect_on_another_port() {
+    let (_anki, server, _client) = mock_client().await;
+    let (_stopped, stopped_server, _) = mock_client().await;
+    let unreachable = stopped_server.url().to_string();
+    drop(stopped_server);
+    tokio::task::yield_now().await;
+
+    let urls = vec![unreachable.clone(), server.url().to_string()];
+    assert_eq!(find_ankiconnect(&urls).await.as_deref(), Some(server.url()));
+    assert_eq!(
+        find_ankiconnect(std::slice::from_ref(&unreachable)).await,
+        None
+    );
+
+    let err = wait_for_ankiconnect(&[unreachable], Duration::from_millis(100))
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        AnkiDeckBuilderError::AnkiConnectNotRunning { .. }
+    ));
+}